@@ -0,0 +1,128 @@
+//! # obsidian-driver::ai::api::audio
+//!
+//! This module contains the Audio struct, the input of `AIDriver::transcribe`: a recording read from a file or held in memory, e.g. a lecture recording attached to a note.
+//!
+//! @public Audio
+//!
+//! @public Audio::from_path
+//!
+//! @public Audio::from_bytes
+//!
+//! @public Audio::with_language
+//!
+//! @public Audio::with_prompt
+//!
+//! @crate Audio::digest
+
+// std imports
+use std::path::Path;
+
+// third-party imports
+use sha2::{Digest, Sha256};
+
+// first-party imports
+use crate::prelude::*;
+
+/// Audio struct
+///
+/// A recording to transcribe. The file name is sent with the audio, and its extension tells the
+/// provider the format, e.g. `.mp3`, `.m4a`, `.wav` or `.webm`.
+///
+/// # Examples
+/// ```
+/// use obsidian_driver::ai::api::audio::Audio;
+///
+/// let audio = Audio::from_bytes("lecture.mp3", vec![0; 16])
+/// 	.with_language("en")
+/// 	.with_prompt("Karnaugh maps, Quine-McCluskey");
+/// assert_eq!(audio.file_name, "lecture.mp3");
+/// ```
+/// @public
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Audio {
+    pub file_name: String,
+    pub bytes: Vec<u8>,
+    // the ISO-639-1 code of the spoken language; detected by the provider if not set
+    pub language: Option<String>,
+    // text the recording continues or terms it uses, to guide the spelling of names and jargon
+    pub prompt: Option<String>,
+}
+
+impl Audio {
+    /// Read a recording from a file.
+    ///
+    /// # Arguments
+    /// @param `path`: `&Path`
+    /// @returns `Result<Audio>`
+    pub fn from_path(path: &Path) -> Result<Audio> {
+        let bytes = std::fs::read(path)?;
+        let file_name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .ok_or(Error::PathNotFound(path.to_path_buf()))?;
+        Ok(Audio::from_bytes(&file_name, bytes))
+    }
+
+    /// Create a recording from bytes in memory.
+    ///
+    /// # Arguments
+    /// @param `file_name`: `&str` - A name with the extension of the format, e.g. `recording.m4a`
+    /// @param `bytes`: `Vec<u8>`
+    /// @returns `Audio`
+    pub fn from_bytes(file_name: &str, bytes: Vec<u8>) -> Audio {
+        Audio {
+            file_name: file_name.to_string(),
+            bytes,
+            language: None,
+            prompt: None,
+        }
+    }
+
+    /// Set the spoken language, which improves accuracy and speed.
+    ///
+    /// # Arguments
+    /// @param `language`: `&str` - An ISO-639-1 code, e.g. `en`
+    /// @returns `Audio`
+    pub fn with_language(mut self, language: &str) -> Audio {
+        self.language = Some(language.to_string());
+        self
+    }
+
+    /// Set a prompt that guides the transcription, e.g. the course's terminology.
+    ///
+    /// # Arguments
+    /// @param `prompt`: `&str`
+    /// @returns `Audio`
+    pub fn with_prompt(mut self, prompt: &str) -> Audio {
+        self.prompt = Some(prompt.to_string());
+        self
+    }
+
+    /// A hash of the recording and its settings, to key recorded transcriptions.
+    ///
+    /// @crate
+    pub(crate) fn digest(&self) -> String {
+        let digest = Sha256::new()
+            .chain_update(&self.bytes)
+            .chain_update(self.language.as_deref().unwrap_or_default())
+            .chain_update([0])
+            .chain_update(self.prompt.as_deref().unwrap_or_default())
+            .finalize();
+        digest.iter().map(|byte| f!("{:02x}", byte)).collect()
+    }
+}
+
+#[cfg(test)]
+mod audio_tests {
+    use super::*;
+
+    #[test]
+    fn test_audio_from_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("lecture.m4a");
+        std::fs::write(&path, [1, 2, 3]).unwrap();
+        let audio = Audio::from_path(&path).unwrap();
+        assert_eq!(audio, Audio::from_bytes("lecture.m4a", vec![1, 2, 3]));
+        assert_ne!(audio.digest(), audio.clone().with_language("en").digest());
+    }
+}
@@ -0,0 +1,317 @@
+//! # obsidian-driver::ai::api::cache
+//!
+//! This module contains the opt-in response cache: chat responses and transcripts are stored under a hash of the model and the request or recording, so an identical request, e.g. when a pipeline is re-run after a crash, is answered from the cache instead of the API.
+//!
+//! @public ResponseCache
+//!
+//! @public ResponseCache::in_memory
+//!
+//! @public ResponseCache::open
+//!
+//! @public ResponseCache::key
+//!
+//! @public ResponseCache::conversation_key
+//!
+//! @public ResponseCache::get
+//!
+//! @public ResponseCache::insert
+//!
+//! @public ResponseCache::len
+//!
+//! @public ResponseCache::is_empty
+//!
+//! @crate CachedProvider
+
+// std imports
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+// third-party imports
+use sha2::{Digest, Sha256};
+use tracing::debug;
+
+// first-party imports
+use super::audio::Audio;
+use super::image::Image;
+use super::provider::{AiProvider, ProviderFuture};
+use super::AIDriver;
+use crate::ai::prompt::{Conversation, Prompt, SystemPromptStacking};
+use crate::file::write_atomic;
+use crate::prelude::*;
+
+/// ResponseCache struct
+///
+/// Chat responses keyed by a SHA-256 hash of the model, the system and user prompts, and the
+/// response budget. A cache opened from a file is written back after every new response, so
+/// responses survive a crash. Clones share their entries. Embeddings are not cached here, the
+/// vault already keeps them with its notes.
+///
+/// # Example
+/// ```
+/// use obsidian_driver::ai::api::AIDriver;
+/// use obsidian_driver::ai::api::cache::ResponseCache;
+/// use obsidian_driver::ai::api::mock::MockDriver;
+/// use obsidian_driver::ai::prompt::{Prompt, ResponseBudget};
+///
+/// #[tokio::main]
+/// async fn main() {
+/// 	let mock = MockDriver::new().with_responses(["First", "Second"]);
+/// 	let driver = AIDriver::new_mock(mock.clone()).with_cache(ResponseCache::in_memory());
+///
+/// 	let prompt = Prompt::new("You take notes.", "Summarize this", ResponseBudget::Unlimited);
+/// 	assert_eq!(driver.chat_smart(prompt.clone()).await.unwrap(), "First");
+/// 	assert_eq!(driver.chat_smart(prompt).await.unwrap(), "First");
+/// 	assert_eq!(mock.prompts().len(), 1);
+/// }
+/// ```
+///
+/// @public
+#[derive(Clone, Debug, Default)]
+pub struct ResponseCache {
+    entries: Arc<Mutex<BTreeMap<String, String>>>,
+    // None for an in-memory cache
+    path: Option<PathBuf>,
+}
+
+impl ResponseCache {
+    /// Create an empty cache that is not saved.
+    ///
+    /// # Arguments
+    /// @return ResponseCache
+    pub fn in_memory() -> ResponseCache {
+        ResponseCache::default()
+    }
+
+    /// Open a cache file, or start an empty cache that will be saved there.
+    ///
+    /// # Arguments
+    /// @param path: &Path - A JSON file of hashes and responses
+    /// @return Result<ResponseCache>
+    pub fn open(path: &Path) -> Result<ResponseCache> {
+        let entries = match std::fs::read_to_string(path) {
+            Ok(text) => serde_json::from_str(&text)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(ResponseCache {
+            entries: Arc::new(Mutex::new(entries)),
+            path: Some(path.to_path_buf()),
+        })
+    }
+
+    /// The cache key of a prompt sent to a model.
+    ///
+    /// # Arguments
+    /// @param model: &str - The name of the model the prompt is sent to
+    /// @param prompt: &Prompt
+    /// @return String - A hex SHA-256 hash
+    ///
+    /// # Example
+    /// ```
+    /// use obsidian_driver::ai::api::cache::ResponseCache;
+    /// use obsidian_driver::ai::prompt::{ImagePart, Prompt, ResponseBudget};
+    ///
+    /// let prompt = Prompt::new("You take notes.", "Summarize this", ResponseBudget::Unlimited);
+    /// let shorter = Prompt::new("You take notes.", "Summarize this", ResponseBudget::Tokens(100));
+    /// assert_ne!(ResponseCache::key("gpt-4o", &prompt), ResponseCache::key("gpt-4o-mini", &prompt));
+    /// assert_ne!(ResponseCache::key("gpt-4o", &prompt), ResponseCache::key("gpt-4o", &shorter));
+    ///
+    /// let with_image = prompt.clone().with_image(ImagePart::Url("https://example.com/cell.png".to_string()));
+    /// assert_ne!(ResponseCache::key("gpt-4o", &prompt), ResponseCache::key("gpt-4o", &with_image));
+    /// ```
+    pub fn key(model: &str, prompt: &Prompt) -> String {
+        let mut request = serde_json::json!({
+            "model": model,
+            "system": prompt.system_messages(SystemPromptStacking::Separate),
+            "user": prompt.user_prompt,
+            "budget": prompt.response_budget,
+        });
+        // only set for prompts with images, so the keys of text prompts stay the same
+        if !prompt.images.is_empty() {
+            request["images"] = serde_json::json!(prompt.images);
+        }
+        hash(&request)
+    }
+
+    /// The cache key of a conversation sent to a model.
+    ///
+    /// # Arguments
+    /// @param model: &str - The name of the model the conversation is sent to
+    /// @param conversation: &Conversation
+    /// @return String - A hex SHA-256 hash
+    pub fn conversation_key(model: &str, conversation: &Conversation) -> String {
+        let request = serde_json::json!({
+            "model": model,
+            "messages": conversation.messages(),
+            "budget": conversation.response_budget,
+        });
+        hash(&request)
+    }
+
+    /// Get the stored response for a key.
+    ///
+    /// # Arguments
+    /// @param key: &str
+    /// @return Option<String>
+    pub fn get(&self, key: &str) -> Option<String> {
+        self.entries.lock().expect("Response cache lock poisoned").get(key).cloned()
+    }
+
+    /// Store a response, and save the cache if it was opened from a file.
+    ///
+    /// # Arguments
+    /// @param key: &str
+    /// @param response: &str
+    /// @return Result<()>
+    pub fn insert(&self, key: &str, response: &str) -> Result<()> {
+        let mut entries = self.entries.lock().expect("Response cache lock poisoned");
+        entries.insert(key.to_string(), response.to_string());
+        if let Some(path) = &self.path {
+            write_atomic(path, serde_json::to_string_pretty(&*entries)?)?;
+        }
+        Ok(())
+    }
+
+    /// The number of stored responses.
+    ///
+    /// # Arguments
+    /// @return usize
+    pub fn len(&self) -> usize {
+        self.entries.lock().expect("Response cache lock poisoned").len()
+    }
+
+    /// Whether no response is stored.
+    ///
+    /// # Arguments
+    /// @return bool
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Hex SHA-256 of a JSON value.
+///
+/// @super
+pub(super) fn hash(value: &serde_json::Value) -> String {
+    let digest = Sha256::digest(value.to_string().as_bytes());
+    digest.iter().map(|byte| f!("{:02x}", byte)).collect()
+}
+
+/// A provider that answers chats from a ResponseCache, and asks the driver it wraps otherwise.
+///
+/// @crate
+#[derive(Debug)]
+pub(crate) struct CachedProvider {
+    pub(crate) inner: AIDriver,
+    pub(crate) cache: ResponseCache,
+}
+
+impl CachedProvider {
+    /// Answer from the cache, or ask the wrapped driver and store its response.
+    ///
+    /// @private
+    async fn cached<'a>(&'a self, key: String, request: impl FnOnce() -> ProviderFuture<'a, String>) -> Result<String> {
+        if let Some(response) = self.cache.get(&key) {
+            debug!(%key, "response cache hit");
+            return Ok(response);
+        }
+        debug!(%key, "response cache miss");
+        // the request is only made on a miss; providers may record or send it when it is made
+        let response = request().await?;
+        self.cache.insert(&key, &response)?;
+        Ok(response)
+    }
+}
+
+impl AiProvider for CachedProvider {
+    fn chat_smart(&self, prompt: Prompt) -> ProviderFuture<'_, String> {
+        let key = ResponseCache::key(self.smart_model(), &prompt);
+        Box::pin(self.cached(key, move || self.inner.provider().chat_smart(prompt)))
+    }
+
+    fn chat_cheap(&self, prompt: Prompt) -> ProviderFuture<'_, String> {
+        let key = ResponseCache::key(self.cheap_model(), &prompt);
+        Box::pin(self.cached(key, move || self.inner.provider().chat_cheap(prompt)))
+    }
+
+    fn chat_conversation(&self, conversation: &Conversation) -> ProviderFuture<'_, String> {
+        let key = ResponseCache::conversation_key(self.smart_model(), conversation);
+        let conversation = conversation.clone();
+        Box::pin(async move { self.cached(key, || self.inner.provider().chat_conversation(&conversation)).await })
+    }
+
+    fn embed<'a>(&'a self, text: &'a str) -> ProviderFuture<'a, Vec<f64>> {
+        self.inner.provider().embed(text)
+    }
+
+    fn describe_image(&self, prompt: Prompt, image: Image) -> ProviderFuture<'_, String> {
+        let key = hash(&serde_json::json!({ "prompt": ResponseCache::key(self.smart_model(), &prompt), "image": image.digest() }));
+        Box::pin(self.cached(key, move || self.inner.provider().describe_image(prompt, image)))
+    }
+
+    fn transcribe(&self, audio: Audio) -> ProviderFuture<'_, String> {
+        let key = hash(&serde_json::json!({ "model": self.transcription_model(), "transcription": audio.digest() }));
+        Box::pin(self.cached(key, move || self.inner.provider().transcribe(audio)))
+    }
+
+    fn embedding_model(&self) -> &str {
+        self.inner.provider().embedding_model()
+    }
+
+    fn embedding_dimensions(&self) -> Option<usize> {
+        self.inner.provider().embedding_dimensions()
+    }
+
+    fn max_input_tokens(&self) -> Option<u32> {
+        self.inner.provider().max_input_tokens()
+    }
+
+    // the profile shares the cache, its keys name the models
+    fn with_profile(&self, name: &str) -> Result<AIDriver> {
+        Ok(self.inner.with_profile(name)?.with_cache(self.cache.clone()))
+    }
+
+    // providers that do not name their models still get separate keys for the two models
+    fn smart_model(&self) -> &str {
+        Some(self.inner.provider().smart_model()).filter(|model| !model.is_empty()).unwrap_or("smart")
+    }
+
+    fn cheap_model(&self) -> &str {
+        Some(self.inner.provider().cheap_model()).filter(|model| !model.is_empty()).unwrap_or("cheap")
+    }
+
+    fn transcription_model(&self) -> &str {
+        self.inner.provider().transcription_model()
+    }
+}
+
+#[cfg(test)]
+mod cache_tests {
+    use super::*;
+    use crate::ai::api::mock::MockDriver;
+    use crate::ai::prompt::ResponseBudget;
+
+    #[tokio::test]
+    async fn test_cache_file_survives_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("responses.json");
+        let prompt = Prompt::new("You take notes.", "Summarize this", ResponseBudget::Unlimited);
+
+        let driver = AIDriver::new_mock(MockDriver::new().with_responses(["Notes", "Title"]))
+            .with_cache(ResponseCache::open(&path).unwrap());
+        assert_eq!(driver.chat_smart(prompt.clone()).await.unwrap(), "Notes");
+        // the cheap model is cached separately
+        assert_eq!(driver.chat_cheap(prompt.clone()).await.unwrap(), "Title");
+
+        let mock = MockDriver::new();
+        let driver = AIDriver::new_mock(mock.clone()).with_cache(ResponseCache::open(&path).unwrap());
+        assert_eq!(driver.chat_smart(prompt.clone()).await.unwrap(), "Notes");
+        assert_eq!(driver.chat_cheap(prompt).await.unwrap(), "Title");
+        assert!(mock.prompts().is_empty());
+        // failed requests are not stored
+        let other = Prompt::new("You take notes.", "Something else", ResponseBudget::Unlimited);
+        assert!(driver.chat_smart(other).await.is_err());
+        assert_eq!(ResponseCache::open(&path).unwrap().len(), 2);
+    }
+}
@@ -0,0 +1,120 @@
+//! # obsidian-driver::ai::api::image
+//!
+//! This module contains the Image struct, the input of `AIDriver::describe_image`: a picture read from a file or held in memory, e.g. a screenshot or whiteboard photo attached to a note.
+//!
+//! @public Image
+//!
+//! @public Image::from_path
+//!
+//! @public Image::from_bytes
+//!
+//! @public Image::to_data_url
+//!
+//! @public ImagePart::from<&Image>
+//!
+//! @crate Image::digest
+
+// std imports
+use std::path::Path;
+
+// third-party imports
+use base64::Engine;
+use sha2::{Digest, Sha256};
+
+// first-party imports
+use crate::ai::prompt::ImagePart;
+use crate::prelude::*;
+
+/// Image struct
+///
+/// A picture to send to a vision model. The media type is taken from the extension of the file
+/// name; PNG, JPEG, GIF and WebP images are supported.
+///
+/// # Examples
+/// ```
+/// use obsidian_driver::ai::api::image::Image;
+///
+/// let image = Image::from_bytes("whiteboard.JPG", vec![0xff, 0xd8]).unwrap();
+/// assert_eq!(image.media_type, "image/jpeg");
+/// assert_eq!(image.to_data_url(), "data:image/jpeg;base64,/9g=");
+/// assert!(Image::from_bytes("notes.pdf", vec![]).is_err());
+/// ```
+/// @public
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Image {
+    pub file_name: String,
+    pub media_type: String,
+    pub bytes: Vec<u8>,
+}
+
+impl Image {
+    /// Read an image from a file.
+    ///
+    /// # Arguments
+    /// @param `path`: `&Path`
+    /// @returns `Result<Image>` - Errors with `NotSupported` if the format is not supported
+    pub fn from_path(path: &Path) -> Result<Image> {
+        let file_name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .ok_or(Error::PathNotFound(path.to_path_buf()))?;
+        media_type(&file_name)?;
+        Image::from_bytes(&file_name, std::fs::read(path)?)
+    }
+
+    /// Create an image from bytes in memory.
+    ///
+    /// # Arguments
+    /// @param `file_name`: `&str` - A name with the extension of the format, e.g. `screenshot.png`
+    /// @param `bytes`: `Vec<u8>`
+    /// @returns `Result<Image>` - Errors with `NotSupported` if the format is not supported
+    pub fn from_bytes(file_name: &str, bytes: Vec<u8>) -> Result<Image> {
+        Ok(Image {
+            file_name: file_name.to_string(),
+            media_type: media_type(file_name)?.to_string(),
+            bytes,
+        })
+    }
+
+    /// Encode the image as a `data:` URL, the way chat APIs accept inline images.
+    ///
+    /// # Arguments
+    /// @returns `String`
+    pub fn to_data_url(&self) -> String {
+        ImagePart::from(self).url()
+    }
+
+    /// A hash of the image, to key cached and recorded responses.
+    ///
+    /// @crate
+    pub(crate) fn digest(&self) -> String {
+        let digest = Sha256::new().chain_update(self.media_type.as_bytes()).chain_update(&self.bytes).finalize();
+        digest.iter().map(|byte| f!("{:02x}", byte)).collect()
+    }
+}
+
+impl From<&Image> for ImagePart {
+    fn from(image: &Image) -> Self {
+        ImagePart::Base64 {
+            media_type: image.media_type.clone(),
+            data: base64::engine::general_purpose::STANDARD.encode(&image.bytes),
+        }
+    }
+}
+
+/// The media type of an image file name, from its extension.
+///
+/// @private
+fn media_type(file_name: &str) -> Result<&'static str> {
+    let extension = Path::new(file_name)
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    match extension.as_str() {
+        "png" => Ok("image/png"),
+        "jpg" | "jpeg" => Ok("image/jpeg"),
+        "gif" => Ok("image/gif"),
+        "webp" => Ok("image/webp"),
+        _ => Err(Error::NotSupported(f!("images of type .{}: {}", extension, file_name))),
+    }
+}
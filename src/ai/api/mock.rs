@@ -0,0 +1,310 @@
+//! # obsidian-driver::ai::api::mock
+//!
+//! This module provides an offline driver that returns canned chat responses and deterministic embeddings, for testing code that uses an AIDriver.
+//!
+//! @public MockDriver
+//!
+//! @public MockDriver::new
+//!
+//! @public MockDriver::with_responses
+//!
+//! @public MockDriver::with_default_response
+//!
+//! @public MockDriver::with_embedding_dimensions
+//!
+//! @public MockDriver::with_max_input_tokens
+//!
+//! @public MockDriver::with_transcript
+//!
+//! @public MockDriver::push_response
+//!
+//! @public MockDriver::prompts
+//!
+//! @private MockDriver::chat
+//!
+//! @private MockDriver::embedding
+
+// std imports
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+// third-party imports
+use sha2::{Digest, Sha256};
+
+// first-party imports
+use super::audio::Audio;
+use super::image::Image;
+use super::provider::{AiProvider, ProviderFuture};
+use crate::ai::prompt::{ImagePart, Prompt};
+use crate::prelude::*;
+
+/// The name reported as the embedding model of a MockDriver.
+pub const MOCK_EMBEDDING_MODEL: &str = "mock-embedding";
+
+/// The names reported as the chat models of a MockDriver.
+pub const MOCK_SMART_MODEL: &str = "mock-smart";
+pub const MOCK_CHEAP_MODEL: &str = "mock-cheap";
+
+/// The name reported as the transcription model of a MockDriver.
+pub const MOCK_TRANSCRIPTION_MODEL: &str = "mock-transcription";
+
+/// Offline driver for tests.
+///
+/// Chat requests (smart, cheap and with images alike) are answered with the queued responses in order, then
+/// with the default response; with neither, they fail with `InvalidChatResponse`. Every prompt
+/// is recorded. Embeddings are derived from a hash of the text, so the same text always gets
+/// the same unit-length vector. Recordings are transcribed to the transcript set with
+/// `with_transcript`. Clones share their queue and recorded prompts.
+///
+/// # Examples
+/// ```
+/// use obsidian_driver::ai::api::AIDriver;
+/// use obsidian_driver::ai::api::mock::MockDriver;
+/// use obsidian_driver::ai::prompt::{Prompt, ResponseBudget};
+///
+/// #[tokio::main]
+/// async fn main() {
+/// 	let mock = MockDriver::new().with_responses(["# Summary\n\nShort."]);
+/// 	let driver = AIDriver::new_mock(mock.clone());
+///
+/// 	let prompt = Prompt::new("You are a helpful assistant", "Summarize this", ResponseBudget::Unlimited);
+/// 	assert_eq!(driver.chat_smart(prompt).await.unwrap(), "# Summary\n\nShort.");
+/// 	assert_eq!(mock.prompts()[0].user_prompt, "Summarize this");
+///
+/// 	let embedding = driver.get_embedding("text").await.unwrap();
+/// 	assert_eq!(embedding, driver.get_embedding("text").await.unwrap());
+/// }
+/// ```
+/// @public
+#[derive(Clone, Debug)]
+pub struct MockDriver {
+    responses: Arc<Mutex<VecDeque<String>>>,
+    default_response: Option<String>,
+    embedding_dimensions: usize,
+    max_input_tokens: Option<u32>,
+    transcript: Option<String>,
+    prompts: Arc<Mutex<Vec<Prompt>>>,
+}
+
+impl Default for MockDriver {
+    fn default() -> Self {
+        MockDriver {
+            responses: Arc::default(),
+            default_response: None,
+            embedding_dimensions: 8,
+            max_input_tokens: None,
+            transcript: None,
+            prompts: Arc::default(),
+        }
+    }
+}
+
+impl MockDriver {
+    /// Create a MockDriver with no canned responses and 8-dimensional embeddings.
+    ///
+    /// # Arguments
+    /// @returns `MockDriver`
+    pub fn new() -> MockDriver {
+        MockDriver::default()
+    }
+
+    /// Queue chat responses, returned in order.
+    ///
+    /// # Arguments
+    /// @param `responses`: `impl IntoIterator<Item = impl Into<String>>`
+    /// @returns `MockDriver`
+    pub fn with_responses(self, responses: impl IntoIterator<Item = impl Into<String>>) -> MockDriver {
+        for response in responses {
+            self.push_response(response);
+        }
+        self
+    }
+
+    /// Set the response returned once the queue is empty.
+    ///
+    /// # Arguments
+    /// @param `response`: `&str`
+    /// @returns `MockDriver`
+    pub fn with_default_response(mut self, response: &str) -> MockDriver {
+        self.default_response = Some(response.to_string());
+        self
+    }
+
+    /// Set the length of the embedding vectors.
+    ///
+    /// # Arguments
+    /// @param `dimensions`: `usize`
+    /// @returns `MockDriver`
+    pub fn with_embedding_dimensions(mut self, dimensions: usize) -> MockDriver {
+        self.embedding_dimensions = dimensions;
+        self
+    }
+
+    /// Set the prompt size reported to callers that split long inputs; prompts are not checked against it.
+    ///
+    /// # Arguments
+    /// @param `tokens`: `u32`
+    /// @returns `MockDriver`
+    pub fn with_max_input_tokens(mut self, tokens: u32) -> MockDriver {
+        self.max_input_tokens = Some(tokens);
+        self
+    }
+
+    /// Set the transcript returned for every recording; without one, transcription fails with `NotSupported`.
+    ///
+    /// # Arguments
+    /// @param `transcript`: `&str`
+    /// @returns `MockDriver`
+    pub fn with_transcript(mut self, transcript: &str) -> MockDriver {
+        self.transcript = Some(transcript.to_string());
+        self
+    }
+
+    /// Queue one more chat response.
+    ///
+    /// # Arguments
+    /// @param `response`: `impl Into<String>`
+    pub fn push_response(&self, response: impl Into<String>) {
+        self.responses.lock().unwrap().push_back(response.into());
+    }
+
+    /// Get the prompts sent so far, in order.
+    ///
+    /// # Arguments
+    /// @returns `Vec<Prompt>`
+    pub fn prompts(&self) -> Vec<Prompt> {
+        self.prompts.lock().unwrap().clone()
+    }
+
+    /// Answer a chat request.
+    ///
+    /// # Arguments
+    /// @param `prompt`: `Prompt` - The prompt, recorded for `prompts`.
+    /// @returns `Result<String>` - The next canned response.
+    ///
+    /// @private
+    fn chat(&self, prompt: Prompt) -> Result<String> {
+        self.prompts.lock().unwrap().push(prompt);
+        self.responses
+            .lock()
+            .unwrap()
+            .pop_front()
+            .or_else(|| self.default_response.clone())
+            .ok_or(Error::InvalidChatResponse("MockDriver has no response left".to_string()))
+    }
+
+    /// Get the embedding of the text: SHA-256 based, normalized to unit length.
+    ///
+    /// # Arguments
+    /// @param `text`: `&str`
+    /// @returns `Vec<f64>`
+    ///
+    /// @private
+    fn embedding(&self, text: &str) -> Vec<f64> {
+        let mut embedding = Vec::with_capacity(self.embedding_dimensions);
+        let mut block = 0u64;
+        while embedding.len() < self.embedding_dimensions {
+            let digest = Sha256::new().chain_update(block.to_le_bytes()).chain_update(text.as_bytes()).finalize();
+            for chunk in digest.chunks(2) {
+                if embedding.len() == self.embedding_dimensions {
+                    break;
+                }
+                let value = u16::from_le_bytes([chunk[0], chunk[1]]) as f64 / u16::MAX as f64;
+                embedding.push(value * 2.0 - 1.0);
+            }
+            block += 1;
+        }
+        let norm = embedding.iter().map(|value| value * value).sum::<f64>().sqrt();
+        if norm > 0.0 {
+            embedding.iter_mut().for_each(|value| *value /= norm);
+        }
+        embedding
+    }
+}
+
+impl AiProvider for MockDriver {
+    fn chat_smart(&self, prompt: Prompt) -> ProviderFuture<'_, String> {
+        let response = self.chat(prompt);
+        Box::pin(async move { response })
+    }
+
+    fn chat_cheap(&self, prompt: Prompt) -> ProviderFuture<'_, String> {
+        let response = self.chat(prompt);
+        Box::pin(async move { response })
+    }
+
+    fn embed<'a>(&'a self, text: &'a str) -> ProviderFuture<'a, Vec<f64>> {
+        let embedding = self.embedding(text);
+        Box::pin(async move { Ok(embedding) })
+    }
+
+    fn describe_image(&self, prompt: Prompt, image: Image) -> ProviderFuture<'_, String> {
+        let response = self.chat(prompt.with_image(ImagePart::from(&image)));
+        Box::pin(async move { response })
+    }
+
+    fn transcribe(&self, audio: Audio) -> ProviderFuture<'_, String> {
+        let transcript = self
+            .transcript
+            .clone()
+            .ok_or(Error::NotSupported(f!("transcribing {} without a transcript set", audio.file_name)));
+        Box::pin(async move { transcript })
+    }
+
+    fn embedding_model(&self) -> &str {
+        MOCK_EMBEDDING_MODEL
+    }
+
+    fn smart_model(&self) -> &str {
+        MOCK_SMART_MODEL
+    }
+
+    fn cheap_model(&self) -> &str {
+        MOCK_CHEAP_MODEL
+    }
+
+    fn transcription_model(&self) -> &str {
+        MOCK_TRANSCRIPTION_MODEL
+    }
+
+    fn embedding_dimensions(&self) -> Option<usize> {
+        Some(self.embedding_dimensions)
+    }
+
+    fn max_input_tokens(&self) -> Option<u32> {
+        self.max_input_tokens
+    }
+}
+
+#[cfg(test)]
+mod mock_tests {
+    use super::*;
+    use crate::ai::api::AIDriver;
+    use crate::ai::prompt::ResponseBudget;
+    use crate::file::vault::Vault;
+
+    #[tokio::test]
+    async fn test_responses_in_order_then_error() {
+        let driver = AIDriver::new_mock(MockDriver::new().with_responses(["one", "two"]));
+        let prompt = Prompt::new("system", "user", ResponseBudget::Unlimited);
+        assert_eq!(driver.chat_smart(prompt.clone()).await.unwrap(), "one");
+        assert_eq!(driver.chat_cheap(prompt.clone()).await.unwrap(), "two");
+        assert!(matches!(driver.chat_smart(prompt).await, Err(Error::InvalidChatResponse(_))));
+    }
+
+    #[tokio::test]
+    async fn test_update_embeddings_offline() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.md"), "# A").unwrap();
+        std::fs::write(dir.path().join("b.md"), "# B").unwrap();
+        let mut vault = Vault::from_path(dir.path().to_path_buf()).unwrap();
+        vault.add_ai_driver(AIDriver::new_mock(MockDriver::new().with_embedding_dimensions(20)));
+        vault.update_embeddings().await.unwrap();
+
+        let a = vault.get_file(&"a.md".into()).unwrap().get_embedding().unwrap();
+        let b = vault.get_file(&"b.md".into()).unwrap().get_embedding().unwrap();
+        assert_eq!(a.len(), 20);
+        assert_ne!(a, b);
+        assert!((a.iter().map(|value| value * value).sum::<f64>() - 1.0).abs() < 1e-9);
+    }
+}
@@ -4,6 +4,20 @@
 //!
 //! @public openai
 //!
+//! @public mock
+//!
+//! @public provider
+//!
+//! @public cache
+//!
+//! @public replay
+//!
+//! @public postprocess
+//!
+//! @public audio
+//!
+//! @public image
+//!
 //! @public AIDriver
 //!
 //! @public AIDriver::new_openai
@@ -14,27 +28,72 @@
 //!
 //! @public AIDriver::new_openai_from_config_path_no_validation
 //!
+//! @public AIDriver::with_profile
+//!
+//! @public AIDriver::with_cache
+//!
+//! @public AIDriver::with_post_processor
+//!
+//! @public AIDriver::with_comments_stripped
+//!
+//! @public AIDriver::record
+//!
+//! @public AIDriver::replay
+//!
+//! @public AIDriver::new_mock
+//!
+//! @public AIDriver::from_provider
+//!
+//! @public AIDriver::provider
+//!
 //! @public AIDriver::chat_smart
 //!
 //! @public AIDriver::chat_cheap
 //!
+//! @public AIDriver::chat_conversation
+//!
 //! @public AIDriver::get_embedding
+//!
+//! @public AIDriver::describe_image
+//!
+//! @public AIDriver::transcribe
+//!
+//! @public AIDriver::embedding_model
+//!
+//! @public AIDriver::embedding_dimensions
+//!
+//! @public AIDriver::max_input_tokens
 
 // std imports
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 // third-party imports
+use cache::{CachedProvider, ResponseCache};
+use mock::MockDriver;
 use openai::{OpenAIConfig, OpenAIDriver};
+use postprocess::{PostProcessedProvider, PostProcessor};
+use redact::CommentStrippedProvider;
+use provider::AiProvider;
+use replay::ReplayProvider;
 
 // first-party imports
 use crate::prelude::*;
 
 // mod imports
+pub mod audio;
+pub mod cache;
+pub mod image;
+pub mod mock;
 pub mod openai;
+pub mod postprocess;
+pub mod provider;
+mod redact;
+pub mod replay;
 
 /// The AI Driver enum.
 ///
-/// This enum provides a high-level interface to the AI models.
+/// This enum provides a high-level interface to the AI models. Each variant wraps an `AiProvider`; `Custom` holds any provider implemented outside the crate.
 ///
 /// # Examples
 /// ```
@@ -61,6 +120,10 @@ pub mod openai;
 #[derive(Clone, Debug)]
 pub enum AIDriver {
     OpenAI(OpenAIDriver),
+    /// Offline driver for tests, see `mock::MockDriver`.
+    Mock(MockDriver),
+    /// A provider implemented outside the crate, see `AIDriver::from_provider`.
+    Custom(Arc<dyn AiProvider>),
 }
 
 impl AIDriver {
@@ -114,7 +177,7 @@ impl AIDriver {
 	///
 	/// # Arguments
 	/// @param `config`: `OpenAIConfig` - The configuration for the OpenAI API.
-	/// @returns `AIDriver` - The new AIDriver.
+	/// @returns `Result<AIDriver>` - The new AIDriver. Errors if the HTTP client settings are invalid.
 	///
 	/// # Examples
 	/// ```should_panic
@@ -123,11 +186,11 @@ impl AIDriver {
 	///
 	/// let openai_config_path = PathBuf::from(".openai_config.json");
 	/// let openai_config = OpenAIConfig::from_file(openai_config_path).unwrap();
-	/// let driver = AIDriver::new_openai_no_validation(openai_config);
+	/// let driver = AIDriver::new_openai_no_validation(openai_config).unwrap();
 	/// ```
 	/// @public
-	pub fn new_openai_no_validation(config: OpenAIConfig) -> AIDriver {
-		AIDriver::OpenAI(OpenAIDriver::new_no_validate(config))
+	pub fn new_openai_no_validation(config: OpenAIConfig) -> Result<AIDriver> {
+		Ok(AIDriver::OpenAI(OpenAIDriver::new_no_validate(config)?))
 	}
 
 	/// This function creates a new OpenAI AIDriver from a config file without validation.
@@ -147,7 +210,199 @@ impl AIDriver {
 	/// @public
 	pub fn new_openai_from_config_path_no_validation(config_path: PathBuf) -> Result<AIDriver> {
 		let config = OpenAIConfig::from_file(config_path)?;
-		Ok(AIDriver::OpenAI(OpenAIDriver::new_no_validate(config)))
+		Ok(AIDriver::OpenAI(OpenAIDriver::new_no_validate(config)?))
+	}
+
+	/// This function creates a driver that uses the settings of a named profile of the config, e.g. other models or URLs.
+	///
+	/// The new driver is not validated again. OpenAI drivers have profiles, and so do drivers wrapped
+	/// with `with_cache`, `with_post_processor` or `with_comments_stripped`, which keep their wrappers.
+	/// Recording and replaying drivers have none, as a cassette holds the responses of one set of models.
+	///
+	/// # Arguments
+	/// @param `name`: `&str` - The profile, see `OpenAIConfig::profile`.
+	/// @returns `Result<AIDriver>` - The new AIDriver. Errors with `UnknownProfile` if the config has no such profile.
+	///
+	/// # Examples
+	/// ```
+	/// use obsidian_driver::ai::api::AIDriver;
+	/// use std::path::PathBuf;
+	///
+	/// async fn with_profile_example() {
+	/// 	let driver = AIDriver::new_openai_from_config_path(PathBuf::from(".openai_config.json")).await.unwrap();
+	/// 	let fast = driver.with_profile("fast").unwrap();
+	/// }
+	/// ```
+	/// @public
+	pub fn with_profile(&self, name: &str) -> Result<AIDriver> {
+		match self {
+			AIDriver::OpenAI(driver) => Ok(AIDriver::OpenAI(OpenAIDriver::new_no_validate(driver.config().profile(name)?)?)),
+			AIDriver::Mock(_) => Err(Error::UnknownProfile(name.to_string())),
+			AIDriver::Custom(provider) => provider.with_profile(name),
+		}
+	}
+
+	/// This function wraps the driver in a response cache, so identical chat requests are only sent once.
+	///
+	/// Requests are keyed by the model, prompts and response budget, see `cache::ResponseCache`.
+	///
+	/// # Arguments
+	/// @param `cache`: `ResponseCache` - e.g. `ResponseCache::open` to keep responses between runs.
+	/// @returns `AIDriver` - The cached driver.
+	///
+	/// # Examples
+	/// ```
+	/// use obsidian_driver::ai::api::AIDriver;
+	/// use obsidian_driver::ai::api::cache::ResponseCache;
+	/// use std::path::{Path, PathBuf};
+	///
+	/// async fn with_cache_example() {
+	/// 	let driver = AIDriver::new_openai_from_config_path(PathBuf::from(".openai_config.json")).await.unwrap();
+	/// 	let driver = driver.with_cache(ResponseCache::open(Path::new("responses.json")).unwrap());
+	/// }
+	/// ```
+	/// @public
+	pub fn with_cache(self, cache: ResponseCache) -> AIDriver {
+		AIDriver::Custom(Arc::new(CachedProvider { inner: self, cache }))
+	}
+
+	/// This function wraps the driver so every chat response is cleaned up by a PostProcessor before it is returned, and so before it becomes a note.
+	///
+	/// Wrap a cached or recording driver, rather than the other way around, to keep the raw responses in the cache or cassette.
+	///
+	/// # Arguments
+	/// @param `processor`: `PostProcessor` - The cleanup steps to apply.
+	/// @returns `AIDriver` - The post-processing driver.
+	///
+	/// # Examples
+	/// ```
+	/// use obsidian_driver::ai::api::AIDriver;
+	/// use obsidian_driver::ai::api::postprocess::PostProcessor;
+	/// use std::path::PathBuf;
+	///
+	/// async fn with_post_processor_example() {
+	/// 	let driver = AIDriver::new_openai_from_config_path(PathBuf::from(".openai_config.json")).await.unwrap();
+	/// 	let driver = driver.with_post_processor(PostProcessor::default().with_unwrap_fence(false));
+	/// }
+	/// ```
+	/// @public
+	pub fn with_post_processor(self, processor: PostProcessor) -> AIDriver {
+		AIDriver::Custom(Arc::new(PostProcessedProvider { inner: self, processor }))
+	}
+
+	/// This function wraps the driver so Obsidian `%% comments %%` are removed from every prompt, conversation and embedding text before it is sent.
+	///
+	/// Comments often hold private annotations, e.g. grades or names, and Obsidian does not show them when reading, so they are usually not meant for the model either. Comments inside code are kept.
+	///
+	/// # Arguments
+	/// @returns `AIDriver` - The comment-stripping driver.
+	///
+	/// # Examples
+	/// ```
+	/// use obsidian_driver::ai::api::AIDriver;
+	/// use std::path::PathBuf;
+	///
+	/// async fn with_comments_stripped_example() {
+	/// 	let driver = AIDriver::new_openai_from_config_path(PathBuf::from(".openai_config.json")).await.unwrap();
+	/// 	let driver = driver.with_comments_stripped();
+	/// }
+	/// ```
+	/// @public
+	pub fn with_comments_stripped(self) -> AIDriver {
+		AIDriver::Custom(Arc::new(CommentStrippedProvider { inner: self }))
+	}
+
+	/// This function wraps the driver so every chat response and embedding it returns is saved to a cassette file, for `AIDriver::replay`.
+	///
+	/// # Arguments
+	/// @param `path`: `&Path` - The cassette file, added to if it exists.
+	/// @returns `Result<AIDriver>` - The recording driver.
+	///
+	/// # Examples
+	/// ```
+	/// use obsidian_driver::ai::api::AIDriver;
+	/// use std::path::{Path, PathBuf};
+	///
+	/// async fn record_example() {
+	/// 	let driver = AIDriver::new_openai_from_config_path(PathBuf::from(".openai_config.json")).await.unwrap();
+	/// 	let driver = driver.record(Path::new("tests/cassettes/notes.json")).unwrap();
+	/// }
+	/// ```
+	/// @public
+	pub fn record(self, path: &Path) -> Result<AIDriver> {
+		Ok(AIDriver::Custom(Arc::new(ReplayProvider::record(self, path)?)))
+	}
+
+	/// This function creates an offline driver that answers with the responses recorded in a cassette file by `AIDriver::record`.
+	///
+	/// Requests are matched by model, prompt and response budget; a request that was not recorded fails with `NotRecorded`.
+	///
+	/// # Arguments
+	/// @param `path`: `&Path` - The cassette file.
+	/// @returns `Result<AIDriver>` - The replaying driver.
+	///
+	/// # Examples
+	/// ```should_panic
+	/// use obsidian_driver::ai::api::AIDriver;
+	/// use std::path::Path;
+	///
+	/// let driver = AIDriver::replay(Path::new("tests/cassettes/notes.json")).unwrap();
+	/// ```
+	/// @public
+	pub fn replay(path: &Path) -> Result<AIDriver> {
+		Ok(AIDriver::Custom(Arc::new(ReplayProvider::replay(path)?)))
+	}
+
+	/// This function creates a new offline AIDriver for tests, see `mock::MockDriver`.
+	///
+	/// # Arguments
+	/// @param `mock`: `MockDriver` - The mock driver, with its canned responses.
+	/// @returns `AIDriver` - The new AIDriver.
+	///
+	/// # Examples
+	/// ```
+	/// use obsidian_driver::ai::api::AIDriver;
+	/// use obsidian_driver::ai::api::mock::MockDriver;
+	///
+	/// let driver = AIDriver::new_mock(MockDriver::new().with_default_response("# Note"));
+	/// assert_eq!(driver.embedding_model(), "mock-embedding");
+	/// ```
+	/// @public
+	pub fn new_mock(mock: MockDriver) -> AIDriver {
+		AIDriver::Mock(mock)
+	}
+
+	/// This function creates a new AIDriver from any AiProvider, for backends implemented outside the crate.
+	///
+	/// # Arguments
+	/// @param `provider`: `impl AiProvider + 'static` - The provider.
+	/// @returns `AIDriver` - The new AIDriver.
+	///
+	/// # Examples
+	/// ```
+	/// use obsidian_driver::ai::api::AIDriver;
+	/// use obsidian_driver::ai::api::mock::MockDriver;
+	///
+	/// let driver = AIDriver::from_provider(MockDriver::new());
+	/// assert_eq!(driver.provider().embedding_model(), "mock-embedding");
+	/// ```
+	/// @public
+	pub fn from_provider(provider: impl AiProvider + 'static) -> AIDriver {
+		AIDriver::Custom(Arc::new(provider))
+	}
+
+	/// This function returns the provider behind the driver.
+	///
+	/// # Arguments
+	/// @returns `&dyn AiProvider` - The provider.
+	///
+	/// @public
+	pub fn provider(&self) -> &dyn AiProvider {
+		match self {
+			AIDriver::OpenAI(driver) => driver,
+			AIDriver::Mock(driver) => driver,
+			AIDriver::Custom(provider) => provider.as_ref(),
+		}
 	}
 
 	/// This function sends a prompt to the smart AI model and returns the response.
@@ -159,22 +414,20 @@ impl AIDriver {
 	/// # Examples
 	/// ```
 	/// use obsidian_driver::ai::api::{AIDriver, openai::OpenAIConfig};
-	/// use obsidian_driver::ai::prompt::Prompt;
+	/// use obsidian_driver::ai::prompt::{Prompt, ResponseBudget};
 	/// use std::path::PathBuf;
 	///
 	/// async fn chat_smart_example() {
 	/// 	let openai_config_path = PathBuf::from(".openai_config.json");
 	/// 	let driver = AIDriver::new_openai_from_config_path(openai_config_path).await.unwrap();
 	///
-	/// 	let prompt = Prompt::new("You are a helpful assistant", "Provide a good morning message", 128);
+	/// 	let prompt = Prompt::new("You are a helpful assistant", "Provide a good morning message", ResponseBudget::Tokens(32));
 	/// 	let response = driver.chat_smart(prompt).await.unwrap();
 	/// }
 	/// ```
 	/// @public
     pub async fn chat_smart(&self, prompt: super::prompt::Prompt) -> Result<String> {
-        match self {
-            AIDriver::OpenAI(driver) => driver.chat_smart(prompt).await,
-        }
+        self.provider().chat_smart(prompt).await
     }
 
 	/// This function sends a prompt to the cheap AI model and returns the response.
@@ -186,24 +439,52 @@ impl AIDriver {
 	/// # Examples
 	/// ```
 	/// use obsidian_driver::ai::api::{AIDriver, openai::OpenAIConfig};
-	/// use obsidian_driver::ai::prompt::Prompt;
+	/// use obsidian_driver::ai::prompt::{Prompt, ResponseBudget};
 	/// use std::path::PathBuf;
 	///
 	/// async fn chat_cheap_example() {
 	/// 	let openai_config_path = PathBuf::from(".openai_config.json");
 	/// 	let driver = AIDriver::new_openai_from_config_path(openai_config_path).await.unwrap();
 	///
-	/// 	let prompt = Prompt::new("You are a helpful assistant", "Provide a good morning message", 128);
+	/// 	let prompt = Prompt::new("You are a helpful assistant", "Provide a good morning message", ResponseBudget::Tokens(32));
 	/// 	let response = driver.chat_cheap(prompt).await.unwrap();
 	/// }
 	/// ```
 	/// @public
     pub async fn chat_cheap(&self, prompt: super::prompt::Prompt) -> Result<String> {
-        match self {
-            AIDriver::OpenAI(driver) => driver.chat_cheap(prompt).await,
-        }
+        self.provider().chat_cheap(prompt).await
     }
 	
+	/// This function sends a conversation to the smart AI model and appends the response to it.
+	///
+	/// # Arguments
+	/// @param `conversation`: `&mut Conversation` - The message history, ending with the user's latest message.
+	/// @returns `Result<String>` - The response from the AI model.
+	///
+	/// # Examples
+	/// ```
+	/// use obsidian_driver::ai::api::AIDriver;
+	/// use obsidian_driver::ai::prompt::Conversation;
+	/// use std::path::PathBuf;
+	///
+	/// async fn chat_conversation_example() {
+	/// 	let openai_config_path = PathBuf::from(".openai_config.json");
+	/// 	let driver = AIDriver::new_openai_from_config_path(openai_config_path).await.unwrap();
+	///
+	/// 	let mut conversation = Conversation::new("You write lecture notes");
+	/// 	conversation.user("Make notes on Karnaugh maps");
+	/// 	let notes = driver.chat_conversation(&mut conversation).await.unwrap();
+	/// 	conversation.user("Now shorten the takeaways");
+	/// 	let shorter = driver.chat_conversation(&mut conversation).await.unwrap();
+	/// }
+	/// ```
+	/// @public
+	pub async fn chat_conversation(&self, conversation: &mut super::prompt::Conversation) -> Result<String> {
+		let response = self.provider().chat_conversation(conversation).await?;
+		conversation.assistant(&response);
+		Ok(response)
+	}
+
 	/// This function gets the embedding for a given text.
 	/// 
 	/// # Arguments
@@ -226,8 +507,115 @@ impl AIDriver {
 	/// ```
 	/// @public
     pub async fn get_embedding(&self, text: &str) -> Result<Vec<f64>> {
-        match self {
-            AIDriver::OpenAI(driver) => driver.get_embedding(text).await,
-        }
+        self.provider().embed(text).await
     }
+
+	/// This function sends a prompt with an image to the smart AI model, e.g. to describe a whiteboard photo or read the text in a screenshot.
+	///
+	/// # Arguments
+	/// @param `prompt`: `Prompt` - The instructions for the model.
+	/// @param `image`: `Image` - The image.
+	/// @returns `Result<String>` - The response from the AI model. Errors with `NotSupported` if the provider cannot see images.
+	///
+	/// # Examples
+	/// ```
+	/// use obsidian_driver::ai::api::AIDriver;
+	/// use obsidian_driver::ai::api::image::Image;
+	/// use obsidian_driver::ai::prompt::{Prompt, ResponseBudget};
+	/// use std::path::{Path, PathBuf};
+	///
+	/// async fn describe_image_example() {
+	/// 	let openai_config_path = PathBuf::from(".openai_config.json");
+	/// 	let driver = AIDriver::new_openai_from_config_path(openai_config_path).await.unwrap();
+	///
+	/// 	let prompt = Prompt::new("You transcribe images", "Transcribe the text in this image", ResponseBudget::Unlimited);
+	/// 	let image = Image::from_path(Path::new("whiteboard.jpg")).unwrap();
+	/// 	let text = driver.describe_image(prompt, image).await.unwrap();
+	/// }
+	/// ```
+	/// @public
+	pub async fn describe_image(&self, prompt: super::prompt::Prompt, image: image::Image) -> Result<String> {
+		self.provider().describe_image(prompt, image).await
+	}
+
+	/// This function transcribes a recording to text, e.g. a lecture recording to feed into `generate_file`.
+	///
+	/// # Arguments
+	/// @param `audio`: `Audio` - The recording, from a file or from memory.
+	/// @returns `Result<String>` - The transcript. Errors with `NotSupported` if the provider has no transcription model, and with `AudioTooLarge` if the recording is over its size limit, 25 MB for OpenAI.
+	///
+	/// # Examples
+	/// ```
+	/// use obsidian_driver::ai::api::AIDriver;
+	/// use obsidian_driver::ai::api::audio::Audio;
+	/// use std::path::{Path, PathBuf};
+	///
+	/// async fn transcribe_example() {
+	/// 	let openai_config_path = PathBuf::from(".openai_config.json");
+	/// 	let driver = AIDriver::new_openai_from_config_path(openai_config_path).await.unwrap();
+	///
+	/// 	let audio = Audio::from_path(Path::new("Lecture 1.m4a")).unwrap().with_language("en");
+	/// 	let transcript = driver.transcribe(audio).await.unwrap();
+	/// }
+	/// ```
+	/// @public
+	pub async fn transcribe(&self, audio: audio::Audio) -> Result<String> {
+		self.provider().transcribe(audio).await
+	}
+
+	/// This function returns the name of the model used for embeddings.
+	///
+	/// # Arguments
+	/// @returns `&str` - The embedding model name.
+	///
+	/// # Examples
+	/// ```should_panic
+	/// use obsidian_driver::ai::api::AIDriver;
+	/// use std::path::PathBuf;
+	///
+	/// let openai_config_path = PathBuf::from(".openai_config.json");
+	/// let driver = AIDriver::new_openai_from_config_path_no_validation(openai_config_path).unwrap();
+	/// let model = driver.embedding_model();
+	/// ```
+	/// @public
+	pub fn embedding_model(&self) -> &str {
+		self.provider().embedding_model()
+	}
+
+	/// This function returns the length of the vectors produced by the embedding model, if configured.
+	///
+	/// # Arguments
+	/// @returns `Option<usize>` - The embedding dimensions.
+	///
+	/// # Examples
+	/// ```should_panic
+	/// use obsidian_driver::ai::api::AIDriver;
+	/// use std::path::PathBuf;
+	///
+	/// let openai_config_path = PathBuf::from(".openai_config.json");
+	/// let driver = AIDriver::new_openai_from_config_path_no_validation(openai_config_path).unwrap();
+	/// let dimensions = driver.embedding_dimensions();
+	/// ```
+	/// @public
+	pub fn embedding_dimensions(&self) -> Option<usize> {
+		self.provider().embedding_dimensions()
+	}
+
+	/// This function returns the number of tokens the smart model accepts in a prompt, if known.
+	///
+	/// # Arguments
+	/// @returns `Option<u32>` - The prompt size limit.
+	///
+	/// # Examples
+	/// ```
+	/// use obsidian_driver::ai::api::AIDriver;
+	/// use obsidian_driver::ai::api::mock::MockDriver;
+	///
+	/// let driver = AIDriver::new_mock(MockDriver::new().with_max_input_tokens(8000));
+	/// assert_eq!(driver.max_input_tokens(), Some(8000));
+	/// ```
+	/// @public
+	pub fn max_input_tokens(&self) -> Option<u32> {
+		self.provider().max_input_tokens()
+	}
 }
@@ -6,36 +6,95 @@
 //!
 //! @public OpenAIConfig::from_file
 //!
+//! @public OpenAIConfig::from_json
+//!
+//! @public OpenAIConfig::apply_env
+//!
+//! @public OpenAIConfig::profile
+//!
+//! @public HttpClientConfig
+//!
+//! @public HttpClientConfig::build_client
+//!
+//! @public OpenAIConfig::smart_context_window
+//!
+//! @public OpenAIConfig::cheap_context_window
+//!
+//! @public OpenAIConfig::estimate_tokens
+//!
 //! @super OpenAIDriver
 //!
 //! @super OpenAIDriver::new
 //!
 //! @super OpenAIDriver::new_no_validate
 //!
+//! @super OpenAIDriver::config
+//!
+//! @super OpenAIDriver::embedding_model
+//!
+//! @super OpenAIDriver::embedding_dimensions
+//!
 //! @super OpenAIDriver::get_embedding
 //!
 //! @super OpenAIDriver::chat_smart
 //!
 //! @super OpenAIDriver::chat_cheap
 //!
+//! @super OpenAIDriver::chat_conversation
+//!
+//! @super OpenAIDriver::describe_image
+//!
+//! @super OpenAIDriver::transcribe
+//!
 //! @super OpenAIValidator
 //!
 //! @super OpenAIValidator::new
 //!
 //! @super OpenAIValidator::validate
 //!
+//! @private OpenAIValidator::check_models_response
+//!
+//! @private OpenAIDriver::messages
+//!
+//! @private OpenAIDriver::check_context_window
+//!
+//! @private OpenAIDriver::request_chat
+//!
 //! @private ChatMessage
+//!
+//! @private ChatContent
+//!
+//! @private ContentPart
+//!
+//! @private ImageUrl
+//!
+//! @private ModelList
+//!
+//! @private OpenAIConfig::profile_with_env
+//!
+//! @private interpolate_env
 
 // std imports
+use std::collections::BTreeMap;
 use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
 
 // third-party imports
-use reqwest::Client;
+use reqwest::{Client, StatusCode};
 use serde::{Deserialize, Serialize};
+use tracing::{debug, info};
 
 // first-party imports
+use super::audio::Audio;
+use super::image::Image;
+use super::provider::{AiProvider, ProviderFuture};
+use crate::ai::prompt::{Conversation, ImagePart, Prompt, Role, SystemPromptStacking};
 use crate::prelude::*;
 
+/// The largest recording the transcription API accepts, 25 MB.
+const MAX_TRANSCRIPTION_BYTES: usize = 25 * 1024 * 1024;
+
 /// Driver for the OpenAI API.
 ///
 /// This struct provides a pub(super) internal wrapper for the openai API.
@@ -55,8 +114,9 @@ use crate::prelude::*;
 /// ```
 /// @super
 #[derive(Clone, Debug)]
-pub(super) struct OpenAIDriver {
-    config: OpenAIConfig,
+pub struct OpenAIDriver {
+    // shared, so drivers and profiles stay cheap to clone
+    config: Arc<OpenAIConfig>,
     client: Client,
 }
 
@@ -70,23 +130,69 @@ impl OpenAIDriver {
     ///
     /// @super
     pub(super) async fn new(config: OpenAIConfig) -> Result<OpenAIDriver> {
-        config.validate().await?;
+        let client = config.http.build_client()?;
+        config.validate(&client).await?;
         Ok(OpenAIDriver {
-            config,
-            client: Client::new(),
+            config: Arc::new(config),
+            client,
         })
     }
     /// Internal constructor to create a new OpenAIDriver instance without validation.
     ///
     /// # Arguments
     /// @param `config`: `OpenAIConfig` - The configuration for the OpenAI API.
-    /// @returns `OpenAIDriver` - The new OpenAIDriver instance.
+    /// @returns `Result<OpenAIDriver>` - The new OpenAIDriver instance. Errors if the HTTP client settings are invalid.
+    ///
+    /// @super
+    pub(super) fn new_no_validate(config: OpenAIConfig) -> Result<OpenAIDriver> {
+        let client = config.http.build_client()?;
+        Ok(OpenAIDriver {
+            config: Arc::new(config),
+            client,
+        })
+    }
+
+    /// Get the config of the driver.
+    ///
+    /// # Arguments
+    /// @returns `&OpenAIConfig` - The config.
+    ///
+    /// @super
+    pub(super) fn config(&self) -> &OpenAIConfig {
+        &self.config
+    }
+
+    /// Get the configured embedding model.
+    ///
+    /// # Arguments
+    /// @returns `&str` - The name of the embedding model.
     ///
     /// @super
-    pub(super) fn new_no_validate(config: OpenAIConfig) -> OpenAIDriver {
-        OpenAIDriver{
-            config,
-            client: Client::new(),
+    pub(super) fn embedding_model(&self) -> &str {
+        &self.config.embedding_model
+    }
+
+    /// Get the configured embedding dimensions, if known.
+    ///
+    /// # Arguments
+    /// @returns `Option<usize>` - The length of the vectors produced by the embedding model.
+    ///
+    /// @super
+    pub(super) fn embedding_dimensions(&self) -> Option<usize> {
+        self.config.embedding_dimensions
+    }
+
+    /// Get the number of tokens the smart model accepts in a prompt, leaving room in its context window for a full response.
+    ///
+    /// # Arguments
+    /// @returns `u32` - The smaller of `smart_model_max_input_tokens` and the context window minus `smart_model_max_output_tokens`, or `smart_model_max_input_tokens` if no context window is set.
+    ///
+    /// @super
+    pub(super) fn max_input_tokens(&self) -> u32 {
+        let max_input_tokens = self.config.smart_model_max_input_tokens;
+        match self.config.smart_model_context_window {
+            Some(context_window) => max_input_tokens.min(context_window.saturating_sub(self.config.smart_model_max_output_tokens)),
+            None => max_input_tokens,
         }
     }
 
@@ -102,6 +208,7 @@ impl OpenAIDriver {
             "input": text,
             "model": &self.config.embedding_model,
         });
+        debug!(model = %self.config.embedding_model, characters = text.len(), "embedding request sent");
 
         let response = self
             .client
@@ -114,6 +221,11 @@ impl OpenAIDriver {
 
         let response_text = response.text().await?;
         let response_json: serde_json::Value = serde_json::from_str(&response_text)?;
+        info!(
+            model = %self.config.embedding_model,
+            prompt_tokens = response_json["usage"]["prompt_tokens"].as_u64(),
+            "embedding response received"
+        );
         let vec = response_json["data"][0]["embedding"]
             .as_array()
             .ok_or(Error::InvalidEmbeddingResponse(response_text))?
@@ -123,6 +235,82 @@ impl OpenAIDriver {
         Ok(vec)
     }
 
+    /// Build the chat messages for a prompt, sending stacked system prompts as configured.
+    ///
+    /// # Arguments
+    /// @param `prompt`: `crate::ai::prompt::Prompt` - The prompt to build messages for.
+    /// @returns `Vec<ChatMessage>` - The system messages followed by the user message, which holds the images of the prompt if it has any.
+    ///
+    /// @private
+    fn messages(&self, prompt: crate::ai::prompt::Prompt) -> Vec<ChatMessage> {
+        let mut messages: Vec<ChatMessage> = prompt
+            .system_messages(self.config.system_prompt_stacking)
+            .into_iter()
+            .map(|content| ChatMessage {
+                role: self.config.system_role.clone(),
+                content: ChatContent::Text(content),
+            })
+            .collect();
+        let content = if prompt.images.is_empty() {
+            ChatContent::Text(prompt.user_prompt)
+        } else {
+            let text = ContentPart::Text { text: prompt.user_prompt };
+            let images = prompt.images.iter().map(|image| ContentPart::ImageUrl {
+                image_url: ImageUrl { url: image.url() },
+            });
+            ChatContent::Parts(std::iter::once(text).chain(images).collect())
+        };
+        messages.push(ChatMessage {
+            role: "user".to_string(),
+            content,
+        });
+        messages
+    }
+
+    /// Check that a prompt and the response it asks for fit in a model's context window, before the request is sent.
+    ///
+    /// Without a configured context window, the max input tokens only bound the prompt, as they did before context windows could be set.
+    ///
+    /// # Arguments
+    /// @param `prompt`: `&Prompt` - The prompt, or the conversation as a prompt.
+    /// @param `smart`: `bool` - Whether the smart or the cheap model is used.
+    /// @returns `Result<u32>` - The number of completion tokens to request, from the prompt's response budget. Errors with `ContextWindowExceeded` and the measured sizes otherwise.
+    ///
+    /// @private
+    fn check_context_window(&self, prompt: &Prompt, smart: bool) -> Result<u32> {
+        let (model, max_input_tokens, max_output_tokens, context_window) = if smart {
+            (
+                &self.config.smart_text_model,
+                self.config.smart_model_max_input_tokens,
+                self.config.smart_model_max_output_tokens,
+                self.config.smart_model_context_window,
+            )
+        } else {
+            (
+                &self.config.cheap_text_model,
+                self.config.cheap_model_max_input_tokens,
+                self.config.cheap_model_max_output_tokens,
+                self.config.cheap_model_context_window,
+            )
+        };
+        let completion_tokens = prompt.response_budget.max_tokens(max_output_tokens);
+        let prompt_tokens = self.config.estimate_tokens(prompt);
+        let max_prompt_tokens = match context_window {
+            Some(context_window) => max_input_tokens.min(context_window.saturating_sub(completion_tokens)),
+            None => max_input_tokens,
+        };
+        if prompt_tokens > max_prompt_tokens {
+            return Err(Error::ContextWindowExceeded {
+                model: model.clone(),
+                prompt_tokens,
+                completion_tokens,
+                max_prompt_tokens,
+                context_window: context_window.unwrap_or(max_input_tokens),
+            });
+        }
+        Ok(completion_tokens)
+    }
+
     /// Chat with the smart model.
     ///
     /// # Arguments
@@ -131,31 +319,54 @@ impl OpenAIDriver {
     ///
     /// @super
     pub(super) async fn chat_smart(&self, prompt: crate::ai::prompt::Prompt) -> Result<String> {
-        let mut tokens = 0;
-        if let Some(max_chars) = prompt.max_characters {
-            tokens = max_chars / self.config.characters_per_token;
-        } else {
-            tokens = self.config.smart_model_max_output_tokens;
-        }
-
-        if tokens > self.config.smart_model_max_input_tokens {
-            return Err(Error::PromptExceedsModelTokenLimit(prompt));
-        }
+        let tokens = self.check_context_window(&prompt, true)?;
         let request_body = serde_json::json!({
             "model": &self.config.smart_text_model,
-            "messages": vec![
-                ChatMessage {
-                    role: "system".to_string(),
-                    content: prompt.system_prompt,
-                },
-                ChatMessage {
-                    role: "user".to_string(),
-                    content: prompt.user_prompt,
+            "messages": self.messages(prompt),
+            "max_tokens": tokens,
+        });
+        self.request_chat(request_body).await
+    }
+
+    /// Chat with the smart model, sending the whole message history.
+    ///
+    /// # Arguments
+    /// @param `conversation`: `Conversation` - The conversation to continue.
+    /// @returns `Result<String>` - The response from the chat.
+    ///
+    /// @super
+    pub(super) async fn chat_conversation(&self, conversation: Conversation) -> Result<String> {
+        let tokens = self.check_context_window(&conversation.to_prompt(), true)?;
+        let messages: Vec<ChatMessage> = conversation
+            .messages()
+            .iter()
+            .map(|message| ChatMessage {
+                role: match message.role {
+                    Role::System => self.config.system_role.clone(),
+                    Role::User => "user".to_string(),
+                    Role::Assistant => "assistant".to_string(),
                 },
-            ],
+                content: ChatContent::Text(message.content.clone()),
+            })
+            .collect();
+        let request_body = serde_json::json!({
+            "model": &self.config.smart_text_model,
+            "messages": messages,
             "max_tokens": tokens,
         });
+        self.request_chat(request_body).await
+    }
 
+    /// Send a chat completion request and get the message content of the first choice.
+    ///
+    /// # Arguments
+    /// @param `request_body`: `serde_json::Value` - The request body.
+    /// @returns `Result<String>` - The response message.
+    ///
+    /// @private
+    async fn request_chat(&self, request_body: serde_json::Value) -> Result<String> {
+        let model = request_body["model"].as_str().unwrap_or_default().to_string();
+        debug!(%model, max_tokens = request_body["max_tokens"].as_u64(), "chat request sent");
         let response = self
             .client
             .post(&self.config.chat_url)
@@ -166,6 +377,13 @@ impl OpenAIDriver {
             .await?;
         let response_text = response.text().await?;
         let response_json: serde_json::Value = serde_json::from_str(&response_text)?;
+        let usage = &response_json["usage"];
+        info!(
+            %model,
+            prompt_tokens = usage["prompt_tokens"].as_u64(),
+            completion_tokens = usage["completion_tokens"].as_u64(),
+            "chat response received"
+        );
         let response_message = response_json["choices"][0]["message"]["content"]
             .as_str()
             .ok_or(Error::InvalidChatResponse(response_text))?;
@@ -182,48 +400,143 @@ impl OpenAIDriver {
     ///
     /// @super
     pub(super) async fn chat_cheap(&self, prompt: crate::ai::prompt::Prompt) -> Result<String> {
-        let mut tokens = 0;
-        if let Some(max_chars) = prompt.max_characters {
-            tokens = max_chars / self.config.characters_per_token;
-        } else {
-            tokens = self.config.cheap_model_max_output_tokens;
-        }
-        if tokens > self.config.cheap_model_max_input_tokens {
-            return Err(Error::PromptExceedsModelTokenLimit(prompt));
-        }
+        let tokens = self.check_context_window(&prompt, false)?;
         let request_body = serde_json::json!({
             "model": &self.config.cheap_text_model,
-            "messages": vec![
-                ChatMessage {
-                    role: "system".to_string(),
-                    content: prompt.system_prompt,
-                },
-                ChatMessage {
-                    role: "user".to_string(),
-                    content: prompt.user_prompt,
-                },
-            ],
+            "messages": self.messages(prompt),
             "max_tokens": tokens,
         });
+        self.request_chat(request_body).await
+    }
+
+    /// Chat with the smart model about an image, which is sent inline with the user message.
+    ///
+    /// # Arguments
+    /// @param `prompt`: `crate::ai::prompt::Prompt` - The prompt to chat with.
+    /// @param `image`: `Image` - The image; the smart model must accept images.
+    /// @returns `Result<String>` - The response from the chat.
+    ///
+    /// @super
+    pub(super) async fn describe_image(&self, prompt: crate::ai::prompt::Prompt, image: Image) -> Result<String> {
+        self.chat_smart(prompt.with_image(ImagePart::from(&image))).await
+    }
 
+    /// Transcribe a recording with the transcription model, e.g. Whisper.
+    ///
+    /// # Arguments
+    /// @param `audio`: `Audio` - The recording; the API accepts files up to 25 MB.
+    /// @returns `Result<String>` - The transcript. Errors with `AudioTooLarge` before sending a larger recording.
+    ///
+    /// @super
+    pub(super) async fn transcribe(&self, audio: Audio) -> Result<String> {
+        if audio.bytes.len() > MAX_TRANSCRIPTION_BYTES {
+            return Err(Error::AudioTooLarge { file_name: audio.file_name, bytes: audio.bytes.len(), max_bytes: MAX_TRANSCRIPTION_BYTES });
+        }
+        let model = self.config.transcription_model.clone();
+        debug!(%model, file = %audio.file_name, bytes = audio.bytes.len(), "transcription request sent");
+        let file = reqwest::multipart::Part::bytes(audio.bytes).file_name(audio.file_name);
+        let mut form = reqwest::multipart::Form::new()
+            .text("model", model.clone())
+            .text("response_format", "json")
+            .part("file", file);
+        if let Some(language) = audio.language {
+            form = form.text("language", language);
+        }
+        if let Some(prompt) = audio.prompt {
+            form = form.text("prompt", prompt);
+        }
         let response = self
             .client
-            .post(&self.config.chat_url)
-            .header("Content-Type", "application/json")
+            .post(&self.config.transcription_url)
             .header("Authorization", format!("Bearer {}", self.config.api_key))
-            .json(&request_body)
+            .multipart(form)
             .send()
             .await?;
-
         let response_text = response.text().await?;
-        Ok(response_text)
+        let response_json: serde_json::Value =
+            serde_json::from_str(&response_text).map_err(|_| Error::InvalidTranscriptionResponse(response_text.clone()))?;
+        info!(%model, "transcription response received");
+        let transcript = response_json["text"]
+            .as_str()
+            .ok_or(Error::InvalidTranscriptionResponse(response_text.clone()))?;
+        Ok(transcript.trim().to_string())
     }
 }
 
+impl AiProvider for OpenAIDriver {
+    fn chat_smart(&self, prompt: Prompt) -> ProviderFuture<'_, String> {
+        Box::pin(OpenAIDriver::chat_smart(self, prompt))
+    }
+
+    fn chat_cheap(&self, prompt: Prompt) -> ProviderFuture<'_, String> {
+        Box::pin(OpenAIDriver::chat_cheap(self, prompt))
+    }
+
+    fn chat_conversation(&self, conversation: &Conversation) -> ProviderFuture<'_, String> {
+        Box::pin(OpenAIDriver::chat_conversation(self, conversation.clone()))
+    }
+
+    fn embed<'a>(&'a self, text: &'a str) -> ProviderFuture<'a, Vec<f64>> {
+        Box::pin(self.get_embedding(text))
+    }
+
+    fn describe_image(&self, prompt: Prompt, image: Image) -> ProviderFuture<'_, String> {
+        Box::pin(OpenAIDriver::describe_image(self, prompt, image))
+    }
+
+    fn transcribe(&self, audio: Audio) -> ProviderFuture<'_, String> {
+        Box::pin(OpenAIDriver::transcribe(self, audio))
+    }
+
+    fn embedding_model(&self) -> &str {
+        OpenAIDriver::embedding_model(self)
+    }
+
+    fn embedding_dimensions(&self) -> Option<usize> {
+        OpenAIDriver::embedding_dimensions(self)
+    }
+
+    fn max_input_tokens(&self) -> Option<u32> {
+        Some(OpenAIDriver::max_input_tokens(self))
+    }
+
+    fn smart_model(&self) -> &str {
+        &self.config.smart_text_model
+    }
+
+    fn cheap_model(&self) -> &str {
+        &self.config.cheap_text_model
+    }
+
+    fn transcription_model(&self) -> &str {
+        &self.config.transcription_model
+    }
+}
+
+/// Environment variables read by `OpenAIConfig::apply_env`, and the settings they override.
+const ENV_API_KEY: &str = "OPENAI_API_KEY";
+const ENV_BASE_URL: &str = "OPENAI_BASE_URL";
+const ENV_SMART_MODEL: &str = "OPENAI_SMART_MODEL";
+const ENV_CHEAP_MODEL: &str = "OPENAI_CHEAP_MODEL";
+const ENV_EMBEDDING_MODEL: &str = "OPENAI_EMBEDDING_MODEL";
+
 /// Configuration for the OpenAI API.
 ///
 /// This struct provides a configuration for the OpenAI API.
 ///
+/// A config file does not need to contain secrets. Settings are taken, from highest to lowest precedence, from:
+/// 1. the environment variables `OPENAI_API_KEY`, `OPENAI_BASE_URL` (sets the validation, chat,
+///    embedding and transcription urls to `<base>/models`, `<base>/chat/completions`,
+///    `<base>/embeddings` and `<base>/audio/transcriptions`),
+///    `OPENAI_SMART_MODEL`, `OPENAI_CHEAP_MODEL` and `OPENAI_EMBEDDING_MODEL`;
+/// 2. the config file, where `${NAME}` and `${NAME:-default}` in string values are replaced with
+///    environment variables, e.g. `"api_key": "${MY_OPENAI_KEY}"`;
+/// 3. the defaults of the optional fields. `api_key` may be left out of the file if it is set by the environment.
+///
+/// The file may also define named `profiles`, e.g. `"fast"`, `"quality"` or `"local"`, each holding
+/// the settings it changes, see `OpenAIConfig::profile` and `AIDriver::with_profile`. The
+/// environment variables still take precedence over a profile.
+///
 /// # Examples
 /// ```should_panic
 /// use obsidian_driver::ai::api::openai::OpenAIConfig;
@@ -235,18 +548,30 @@ impl OpenAIDriver {
 ///
 /// ```
 /// use obsidian_driver::ai::api::openai::OpenAIConfig;
+/// use obsidian_driver::ai::prompt::SystemPromptStacking;
 ///
 /// let openai_config = OpenAIConfig {
 ///     validation_url: "https://api.openai.com/v1/models".to_string(),
 ///     embedding_model: "text-embedding-3-small".to_string(),
+///     embedding_dimensions: Some(1536),
 ///     smart_text_model: "gpt-4o".to_string(),
 ///     cheap_text_model: "gpt-4o-mini".to_string(),
-///     smart_model_max_tokens: 128,
-///     cheap_model_max_tokens: 128,
+///     smart_model_max_input_tokens: 128000,
+///     smart_model_max_output_tokens: 4096,
+///     cheap_model_max_input_tokens: 128000,
+///     cheap_model_max_output_tokens: 4096,
+///     smart_model_context_window: Some(128000),
+///     cheap_model_context_window: Some(128000),
 ///     embedding_url: "https://api.openai.com/v1/embeddings".to_string(),
 ///     chat_url: "https://api.openai.com/v1/chat/completions".to_string(),
+///     transcription_model: "whisper-1".to_string(),
+///     transcription_url: "https://api.openai.com/v1/audio/transcriptions".to_string(),
 ///     api_key: "sk-...".to_string(),
+///     system_role: "system".to_string(),
+///     system_prompt_stacking: SystemPromptStacking::Separate,
 ///     characters_per_token: 4,
+///     http: Default::default(),
+///     profiles: Default::default(),
 /// };
 /// ```
 ///
@@ -258,6 +583,8 @@ pub struct OpenAIConfig {
 
     // Models
     pub embedding_model: String,
+    #[serde(default)]
+    pub embedding_dimensions: Option<usize>,
     pub smart_text_model: String,
     pub cheap_text_model: String,
 
@@ -265,31 +592,79 @@ pub struct OpenAIConfig {
     pub smart_model_max_output_tokens: u32,
     pub cheap_model_max_input_tokens: u32,
     pub cheap_model_max_output_tokens: u32,
+    // prompt and response together; if not set, only the max input tokens bound the prompt
+    #[serde(default)]
+    pub smart_model_context_window: Option<u32>,
+    #[serde(default)]
+    pub cheap_model_context_window: Option<u32>,
 
     // Urls
     pub embedding_url: String,
     pub chat_url: String,
 
+    // Audio
+    #[serde(default = "default_transcription_model")]
+    pub transcription_model: String,
+    #[serde(default = "default_transcription_url")]
+    pub transcription_url: String,
+
     // API key
+    #[serde(default)]
     pub api_key: String,
 
+    // Messages
+    #[serde(default = "default_system_role")]
+    pub system_role: String,
+    #[serde(default)]
+    pub system_prompt_stacking: SystemPromptStacking,
+
     // Other
     pub characters_per_token: u32,
+
+    // HTTP client: timeouts, proxy, user agent
+    #[serde(default)]
+    pub http: HttpClientConfig,
+
+    // Profiles: the settings each named profile changes
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub profiles: BTreeMap<String, serde_json::Value>,
+}
+
+/// Default role for system messages, used when the config file does not set one.
+///
+/// @private
+fn default_system_role() -> String {
+    "system".to_string()
+}
+
+/// Default transcription model, used when the config file does not set one.
+///
+/// @private
+fn default_transcription_model() -> String {
+    "whisper-1".to_string()
+}
+
+/// Default transcription url, used when the config file does not set one.
+///
+/// @private
+fn default_transcription_url() -> String {
+    "https://api.openai.com/v1/audio/transcriptions".to_string()
 }
 
 impl OpenAIConfig {
     /// Validate the OpenAIConfig.
     ///
     /// # Arguments
+    /// @param `client`: `&Client` - The client to send the validation request with.
     /// @returns `Result<()>` - The result of the validation.
     ///
     /// @private
-    async fn validate(&self) -> Result<()> {
-        let validator = OpenAIValidator::new(self.clone());
+    async fn validate(&self, client: &Client) -> Result<()> {
+        let validator = OpenAIValidator::new(self.clone(), client.clone());
         validator.validate().await
     }
 
-    ///  sCreate an OpenAIConfig from a file.
+    /// Create an OpenAIConfig from a file, with environment variables applied, see `OpenAIConfig::from_json`.
     ///
     /// # Arguments
     /// @param `config_path`: `PathBuf` - The path to the configuration file.
@@ -297,10 +672,305 @@ impl OpenAIConfig {
     ///
     /// @public
     pub fn from_file(config_path: PathBuf) -> Result<OpenAIConfig> {
-        let config_file = std::fs::File::open(config_path)?;
-        let config: OpenAIConfig = serde_json::from_reader(config_file)?;
+        let text = std::fs::read_to_string(config_path)?;
+        OpenAIConfig::from_json(&text)
+    }
+
+    /// Create an OpenAIConfig from JSON: `${NAME}` and `${NAME:-default}` in string values are
+    /// replaced with environment variables, then the `OPENAI_*` variables override the result.
+    ///
+    /// # Arguments
+    /// @param `text`: `&str` - The JSON config.
+    /// @returns `Result<OpenAIConfig>` - Errors with `MissingEnvVar` if a referenced variable without default, or the API key, is not set.
+    ///
+    /// # Examples
+    /// ```
+    /// use obsidian_driver::ai::api::openai::OpenAIConfig;
+    ///
+    /// std::env::set_var("OBSIDIAN_DRIVER_EXAMPLE_KEY", "sk-example");
+    /// let json = r#"{
+    /// 	"validation_url": "https://api.openai.com/v1/models",
+    /// 	"embedding_model": "text-embedding-3-small",
+    /// 	"smart_text_model": "${OBSIDIAN_DRIVER_EXAMPLE_MODEL:-gpt-4o}",
+    /// 	"cheap_text_model": "gpt-4o-mini",
+    /// 	"smart_model_max_input_tokens": 128000,
+    /// 	"smart_model_max_output_tokens": 4096,
+    /// 	"cheap_model_max_input_tokens": 128000,
+    /// 	"cheap_model_max_output_tokens": 4096,
+    /// 	"embedding_url": "https://api.openai.com/v1/embeddings",
+    /// 	"chat_url": "https://api.openai.com/v1/chat/completions",
+    /// 	"api_key": "${OBSIDIAN_DRIVER_EXAMPLE_KEY}",
+    /// 	"characters_per_token": 4
+    /// }"#;
+    /// let config = OpenAIConfig::from_json(json).unwrap();
+    /// assert_eq!(config.smart_text_model, "gpt-4o");
+    /// if std::env::var("OPENAI_API_KEY").is_err() {
+    /// 	assert_eq!(config.api_key, "sk-example");
+    /// }
+    /// ```
+    ///
+    /// @public
+    pub fn from_json(text: &str) -> Result<OpenAIConfig> {
+        let lookup = |name: &str| std::env::var(name).ok();
+        let mut value: serde_json::Value = serde_json::from_str(text)?;
+        interpolate_env(&mut value, &lookup)?;
+        let mut config: OpenAIConfig = serde_json::from_value(value)?;
+        config.apply_env(&lookup);
+        if config.api_key.is_empty() {
+            return Err(Error::MissingEnvVar(ENV_API_KEY.to_string()));
+        }
+        Ok(config)
+    }
+
+    /// Override settings with the `OPENAI_*` environment variables that are set.
+    ///
+    /// # Arguments
+    /// @param `lookup`: `&impl Fn(&str) -> Option<String>` - Gets a variable, e.g. `|name| std::env::var(name).ok()`.
+    ///
+    /// @public
+    pub fn apply_env(&mut self, lookup: &impl Fn(&str) -> Option<String>) {
+        let lookup = |name: &str| lookup(name).filter(|value| !value.is_empty());
+        if let Some(api_key) = lookup(ENV_API_KEY) {
+            self.api_key = api_key;
+        }
+        if let Some(base_url) = lookup(ENV_BASE_URL) {
+            let base_url = base_url.trim_end_matches('/');
+            self.validation_url = f!("{}/models", base_url);
+            self.chat_url = f!("{}/chat/completions", base_url);
+            self.embedding_url = f!("{}/embeddings", base_url);
+            self.transcription_url = f!("{}/audio/transcriptions", base_url);
+        }
+        if let Some(model) = lookup(ENV_SMART_MODEL) {
+            self.smart_text_model = model;
+        }
+        if let Some(model) = lookup(ENV_CHEAP_MODEL) {
+            self.cheap_text_model = model;
+        }
+        if let Some(model) = lookup(ENV_EMBEDDING_MODEL) {
+            self.embedding_model = model;
+        }
+    }
+
+    /// Get the config with the settings of a named profile applied, then the `OPENAI_*` environment variables.
+    ///
+    /// # Arguments
+    /// @param `name`: `&str` - The profile, a key of `profiles`.
+    /// @returns `Result<OpenAIConfig>` - Errors with `UnknownProfile` if there is no such profile.
+    ///
+    /// # Examples
+    /// ```
+    /// use obsidian_driver::ai::api::openai::OpenAIConfig;
+    ///
+    /// let json = r#"{
+    /// 	"validation_url": "https://api.openai.com/v1/models",
+    /// 	"embedding_model": "text-embedding-3-small",
+    /// 	"smart_text_model": "gpt-4o",
+    /// 	"cheap_text_model": "gpt-4o-mini",
+    /// 	"smart_model_max_input_tokens": 128000,
+    /// 	"smart_model_max_output_tokens": 4096,
+    /// 	"cheap_model_max_input_tokens": 128000,
+    /// 	"cheap_model_max_output_tokens": 4096,
+    /// 	"embedding_url": "https://api.openai.com/v1/embeddings",
+    /// 	"chat_url": "https://api.openai.com/v1/chat/completions",
+    /// 	"api_key": "sk-...",
+    /// 	"characters_per_token": 4,
+    /// 	"profiles": {
+    /// 		"fast": { "smart_text_model": "gpt-4o-mini" }
+    /// 	}
+    /// }"#;
+    /// let config = OpenAIConfig::from_json(json).unwrap();
+    /// let fast = config.profile("fast").unwrap();
+    /// assert_eq!(fast.cheap_text_model, config.cheap_text_model);
+    /// assert!(config.profile("slow").is_err());
+    /// ```
+    ///
+    /// @public
+    pub fn profile(&self, name: &str) -> Result<OpenAIConfig> {
+        self.profile_with_env(name, &|name: &str| std::env::var(name).ok())
+    }
+
+    /// Get the config with the settings of a named profile applied, then the `OPENAI_*` variables of `lookup`.
+    ///
+    /// @private
+    fn profile_with_env(&self, name: &str, lookup: &impl Fn(&str) -> Option<String>) -> Result<OpenAIConfig> {
+        let settings = self
+            .profiles
+            .get(name)
+            .and_then(|settings| settings.as_object())
+            .ok_or(Error::UnknownProfile(name.to_string()))?;
+        let mut value = serde_json::to_value(self)?;
+        if let Some(fields) = value.as_object_mut() {
+            for (key, setting) in settings.iter().filter(|(key, _)| key.as_str() != "profiles") {
+                fields.insert(key.clone(), setting.clone());
+            }
+        }
+        let mut config: OpenAIConfig = serde_json::from_value(value)?;
+        config.apply_env(lookup);
         Ok(config)
     }
+
+    /// Get the context window of the smart model: the tokens of the prompt and the response together.
+    ///
+    /// # Arguments
+    /// @returns `u32` - `smart_model_context_window`, or `smart_model_max_input_tokens` if it is not set.
+    ///
+    /// @public
+    pub fn smart_context_window(&self) -> u32 {
+        self.smart_model_context_window.unwrap_or(self.smart_model_max_input_tokens)
+    }
+
+    /// Get the context window of the cheap model: the tokens of the prompt and the response together.
+    ///
+    /// # Arguments
+    /// @returns `u32` - `cheap_model_context_window`, or `cheap_model_max_input_tokens` if it is not set.
+    ///
+    /// @public
+    pub fn cheap_context_window(&self) -> u32 {
+        self.cheap_model_context_window.unwrap_or(self.cheap_model_max_input_tokens)
+    }
+
+    /// Estimate the number of tokens a prompt takes up, from its length and `characters_per_token`.
+    ///
+    /// # Arguments
+    /// @param `prompt`: `&Prompt` - The prompt, counting the system and user prompts.
+    /// @returns `u32` - The estimated number of tokens, rounded up.
+    ///
+    /// @public
+    pub fn estimate_tokens(&self, prompt: &Prompt) -> u32 {
+        let characters: usize = std::iter::once(&prompt.system_prompt)
+            .chain(prompt.extra_system_prompts.iter())
+            .chain(std::iter::once(&prompt.user_prompt))
+            .map(|text| text.chars().count())
+            .sum();
+        (characters as u32).div_ceil(self.characters_per_token.max(1))
+    }
+}
+
+/// HttpClientConfig struct
+///
+/// Settings of the HTTP client used for every request to the API. Without a timeout a hung
+/// request would stall e.g. `Vault::update_embeddings` forever, so requests time out after
+/// 300 seconds and connections after 10 seconds unless configured otherwise.
+///
+/// # Examples
+/// ```
+/// use obsidian_driver::ai::api::openai::HttpClientConfig;
+///
+/// let http: HttpClientConfig = serde_json::from_str(r#"{
+/// 	"timeout_secs": 60,
+/// 	"proxy": "http://proxy.example.com:8080",
+/// 	"user_agent": "my-vault-tools/1.0"
+/// }"#).unwrap();
+/// assert_eq!(http.connect_timeout_secs, Some(10));
+/// let client = http.build_client().unwrap();
+/// ```
+///
+/// @public
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HttpClientConfig {
+    // for the whole request, until the response is read; None waits forever
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: Option<u64>,
+    #[serde(default = "default_connect_timeout_secs")]
+    pub connect_timeout_secs: Option<u64>,
+    // used for every request, e.g. "http://proxy:8080" or "socks5://proxy:1080"
+    #[serde(default)]
+    pub proxy: Option<String>,
+    #[serde(default)]
+    pub user_agent: Option<String>,
+}
+
+impl Default for HttpClientConfig {
+    fn default() -> Self {
+        HttpClientConfig {
+            timeout_secs: default_timeout_secs(),
+            connect_timeout_secs: default_connect_timeout_secs(),
+            proxy: None,
+            user_agent: None,
+        }
+    }
+}
+
+/// Default request timeout, in seconds.
+///
+/// @private
+fn default_timeout_secs() -> Option<u64> {
+    Some(300)
+}
+
+/// Default connect timeout, in seconds.
+///
+/// @private
+fn default_connect_timeout_secs() -> Option<u64> {
+    Some(10)
+}
+
+impl HttpClientConfig {
+    /// Build a `reqwest` client with these settings.
+    ///
+    /// # Arguments
+    /// @returns `Result<Client>` - Errors if the proxy url is invalid.
+    ///
+    /// @public
+    pub fn build_client(&self) -> Result<Client> {
+        let mut builder = Client::builder();
+        if let Some(timeout) = self.timeout_secs {
+            builder = builder.timeout(Duration::from_secs(timeout));
+        }
+        if let Some(timeout) = self.connect_timeout_secs {
+            builder = builder.connect_timeout(Duration::from_secs(timeout));
+        }
+        if let Some(proxy) = &self.proxy {
+            builder = builder.proxy(reqwest::Proxy::all(proxy)?);
+        }
+        if let Some(user_agent) = &self.user_agent {
+            builder = builder.user_agent(user_agent);
+        }
+        Ok(builder.build()?)
+    }
+}
+
+/// Replace `${NAME}` and `${NAME:-default}` in the strings of a JSON value with environment variables.
+///
+/// # Arguments
+/// @param `value`: `&mut serde_json::Value` - The value, changed in place.
+/// @param `lookup`: `&impl Fn(&str) -> Option<String>` - Gets a variable.
+/// @returns `Result<()>` - Errors with `MissingEnvVar` if a variable without default is not set.
+///
+/// @private
+fn interpolate_env(value: &mut serde_json::Value, lookup: &impl Fn(&str) -> Option<String>) -> Result<()> {
+    match value {
+        serde_json::Value::String(text) => {
+            let pattern = regex::Regex::new(r"\$\{([A-Za-z_][A-Za-z0-9_]*)(?::-([^}]*))?\}").unwrap();
+            let mut missing = None;
+            let replaced = pattern.replace_all(text, |captures: &regex::Captures| {
+                match (lookup(&captures[1]), captures.get(2)) {
+                    (Some(value), _) => value,
+                    (None, Some(default)) => default.as_str().to_string(),
+                    (None, None) => {
+                        missing.get_or_insert(captures[1].to_string());
+                        String::new()
+                    }
+                }
+            });
+            if let Some(name) = missing {
+                return Err(Error::MissingEnvVar(name));
+            }
+            *text = replaced.into_owned();
+        }
+        serde_json::Value::Array(values) => {
+            for value in values {
+                interpolate_env(value, lookup)?;
+            }
+        }
+        serde_json::Value::Object(map) => {
+            for value in map.values_mut() {
+                interpolate_env(value, lookup)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
 }
 
 /// Validator for the OpenAI API.
@@ -319,20 +989,18 @@ impl OpenAIValidator {
     ///
     /// # Arguments
     /// @param `config`: `OpenAIConfig` - The configuration for the OpenAI API.
+    /// @param `client`: `Client` - The client to send requests with, see `HttpClientConfig::build_client`.
     /// @returns `OpenAIValidator` - The new OpenAIValidator instance.
     ///
     /// @super
-    pub(super) fn new(config: OpenAIConfig) -> OpenAIValidator {
-        OpenAIValidator {
-            config,
-            client: Client::new(),
-        }
+    pub(super) fn new(config: OpenAIConfig, client: Client) -> OpenAIValidator {
+        OpenAIValidator { config, client }
     }
 
-    /// Validate the OpenAI API.
+    /// Validate the OpenAI API: the API key is accepted, and the configured models are listed by the validation url (`/v1/models`).
     ///
     /// # Arguments
-    /// @returns `Result<()>` - The result of the validation.
+    /// @returns `Result<()>` - The result of the validation. Errors with `InvalidApiKey` or `UnknownModel`.
     ///
     /// @super
     pub(super) async fn validate(&self) -> Result<()> {
@@ -343,16 +1011,62 @@ impl OpenAIValidator {
             .send()
             .await?;
 
+        let status = response.status();
         let response_text = response.text().await?;
-		let error = serde_json::from_str::<OpenAIValidationError>(&response_text);
-        match error {
-			Ok(e) => return Err(e.into()),
-			Err(_) => Ok(()),
-		}
+        self.check_models_response(status, &response_text)
+    }
+
+    /// Check the response of the models endpoint against the config.
+    ///
+    /// # Arguments
+    /// @param `status`: `StatusCode` - The HTTP status of the response.
+    /// @param `response_text`: `&str` - The body of the response.
+    /// @returns `Result<()>` - The result of the validation.
+    ///
+    /// @private
+    fn check_models_response(&self, status: StatusCode, response_text: &str) -> Result<()> {
+        let api_error = serde_json::from_str::<OpenAIValidationError>(response_text);
+        if status == StatusCode::UNAUTHORIZED || status == StatusCode::FORBIDDEN {
+            let message = match api_error {
+                Ok(e) => e.error.message,
+                Err(_) => status.to_string(),
+            };
+            return Err(Error::InvalidApiKey(message));
+        }
+        if !status.is_success() {
+            return match api_error {
+                Ok(e) => Err(e.into()),
+                Err(_) => Err(Error::Generic(f!("Validation request failed with status {}:\n{}", status, response_text))),
+            };
+        }
 
+        let models: ModelList = serde_json::from_str(response_text)?;
+        let available: Vec<&str> = models.data.iter().map(|model| model.id.as_str()).collect();
+        for model in [&self.config.smart_text_model, &self.config.cheap_text_model, &self.config.embedding_model] {
+            if !available.contains(&model.as_str()) {
+                return Err(Error::UnknownModel(model.clone()));
+            }
+        }
+        Ok(())
     }
 }
 
+/// The response of the models endpoint.
+///
+/// @private
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+struct ModelList {
+    data: Vec<ModelEntry>,
+}
+
+/// A model listed by the models endpoint.
+///
+/// @private
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+struct ModelEntry {
+    id: String,
+}
+
 /// OpenAI API Error.
 ///
 /// This struct provides an error for the OpenAI API.
@@ -388,5 +1102,210 @@ struct OpenAIValidationErrorInner {
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 struct ChatMessage {
     role: String,
-    content: String,
+    content: ChatContent,
+}
+
+/// The content of a chat message: plain text, or parts for messages with images.
+///
+/// @private
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+enum ChatContent {
+    Text(String),
+    Parts(Vec<ContentPart>),
+}
+
+/// A part of the content of a chat message.
+///
+/// @private
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ContentPart {
+    Text { text: String },
+    ImageUrl { image_url: ImageUrl },
+}
+
+/// An image in the content of a chat message: a URL, or a `data:` URL for an inline image.
+///
+/// @private
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+struct ImageUrl {
+    url: String,
+}
+
+#[cfg(test)]
+mod openai_tests {
+    use super::*;
+    use crate::ai::api::cache::ResponseCache;
+    use crate::ai::api::postprocess::PostProcessor;
+    use crate::ai::api::AIDriver;
+    use crate::ai::prompt::ResponseBudget;
+
+    fn config() -> OpenAIConfig {
+        OpenAIConfig {
+            validation_url: String::new(),
+            embedding_model: "text-embedding-3-small".to_string(),
+            embedding_dimensions: None,
+            smart_text_model: "smart".to_string(),
+            cheap_text_model: "cheap".to_string(),
+            smart_model_max_input_tokens: 100,
+            smart_model_max_output_tokens: 40,
+            cheap_model_max_input_tokens: 100,
+            cheap_model_max_output_tokens: 40,
+            smart_model_context_window: Some(120),
+            cheap_model_context_window: None,
+            embedding_url: String::new(),
+            chat_url: String::new(),
+            transcription_model: default_transcription_model(),
+            transcription_url: String::new(),
+            api_key: String::new(),
+            system_role: "system".to_string(),
+            system_prompt_stacking: SystemPromptStacking::Separate,
+            characters_per_token: 4,
+            http: HttpClientConfig::default(),
+            profiles: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_check_context_window_reports_sizes() {
+        let driver = OpenAIDriver::new_no_validate(config()).unwrap();
+        assert_eq!(driver.max_input_tokens(), 80);
+        let mut no_window = config();
+        no_window.smart_model_context_window = None;
+        assert_eq!(OpenAIDriver::new_no_validate(no_window).unwrap().max_input_tokens(), 100);
+        let prompt = Prompt::new("", &"x".repeat(320), ResponseBudget::Unlimited);
+        assert_eq!(driver.check_context_window(&prompt, true).unwrap(), 40);
+
+        let prompt = Prompt::new("", &"x".repeat(324), ResponseBudget::Unlimited);
+        match driver.check_context_window(&prompt, true) {
+            Err(Error::ContextWindowExceeded { model, prompt_tokens, completion_tokens, max_prompt_tokens, context_window }) => {
+                assert_eq!((model.as_str(), prompt_tokens, completion_tokens, max_prompt_tokens, context_window), ("smart", 81, 40, 80, 120));
+            }
+            other => panic!("expected ContextWindowExceeded, got {:?}", other),
+        }
+        // a smaller response leaves room for a longer prompt
+        let prompt = Prompt::new("", &"x".repeat(324), ResponseBudget::Tokens(10));
+        assert_eq!(driver.check_context_window(&prompt, true).unwrap(), 10);
+        // without a context window, the max input tokens only bound the prompt
+        let prompt = Prompt::new("", &"x".repeat(400), ResponseBudget::Unlimited);
+        assert_eq!(driver.check_context_window(&prompt, false).unwrap(), 40);
+        let prompt = Prompt::new("", &"x".repeat(404), ResponseBudget::Unlimited);
+        assert!(driver.check_context_window(&prompt, false).is_err());
+    }
+    #[test]
+    fn test_check_models_response() {
+        let validator = OpenAIValidator::new(config(), Client::new());
+        let models = r#"{"object": "list", "data": [{"id": "smart"}, {"id": "cheap"}, {"id": "text-embedding-3-small"}]}"#;
+        assert!(validator.check_models_response(StatusCode::OK, models).is_ok());
+
+        let missing = r#"{"object": "list", "data": [{"id": "smart"}, {"id": "text-embedding-3-small"}]}"#;
+        assert!(matches!(validator.check_models_response(StatusCode::OK, missing), Err(Error::UnknownModel(model)) if model == "cheap"));
+
+        let unauthorized = r#"{"error": {"message": "Incorrect API key provided", "type": "invalid_request_error", "param": null, "code": "invalid_api_key"}}"#;
+        assert!(matches!(
+            validator.check_models_response(StatusCode::UNAUTHORIZED, unauthorized),
+            Err(Error::InvalidApiKey(message)) if message == "Incorrect API key provided"
+        ));
+        assert!(validator.check_models_response(StatusCode::INTERNAL_SERVER_ERROR, "oops").is_err());
+    }
+    #[test]
+    fn test_env_interpolation_and_overrides() {
+        let env = |name: &str| match name {
+            "KEY" => Some("sk-file".to_string()),
+            "OPENAI_BASE_URL" => Some("http://localhost:8080/v1/".to_string()),
+            "OPENAI_SMART_MODEL" => Some("local-smart".to_string()),
+            _ => None,
+        };
+        let mut value = serde_json::to_value(config()).unwrap();
+        value["api_key"] = "${KEY}".into();
+        value["cheap_text_model"] = "${CHEAP:-mini}".into();
+        interpolate_env(&mut value, &env).unwrap();
+        let mut config: OpenAIConfig = serde_json::from_value(value).unwrap();
+        assert_eq!((config.api_key.as_str(), config.cheap_text_model.as_str()), ("sk-file", "mini"));
+
+        config.apply_env(&env);
+        assert_eq!(config.smart_text_model, "local-smart");
+        assert_eq!(config.chat_url, "http://localhost:8080/v1/chat/completions");
+        assert_eq!(config.validation_url, "http://localhost:8080/v1/models");
+        assert_eq!(config.transcription_url, "http://localhost:8080/v1/audio/transcriptions");
+
+        let mut missing = serde_json::json!({"api_key": "${UNSET}"});
+        assert!(matches!(interpolate_env(&mut missing, &env), Err(Error::MissingEnvVar(name)) if name == "UNSET"));
+    }
+    #[test]
+    fn test_profile_overrides_settings() {
+        let mut base = config();
+        base.profiles.insert("local".to_string(), serde_json::json!({"chat_url": "http://localhost:11434/v1/chat/completions", "smart_model_max_output_tokens": 1000}));
+        base.profiles.insert("broken".to_string(), serde_json::json!("gpt-4o"));
+        let no_env = |_: &str| None;
+        let local = base.profile_with_env("local", &no_env).unwrap();
+        assert_eq!(local.smart_model_max_output_tokens, 1000);
+        assert_eq!(local.cheap_model_max_output_tokens, 40);
+        assert_eq!(local.profiles, base.profiles);
+        assert_eq!(local.chat_url, "http://localhost:11434/v1/chat/completions");
+        assert!(matches!(base.profile_with_env("broken", &no_env), Err(Error::UnknownProfile(_))));
+        assert!(matches!(base.profile_with_env("missing", &no_env), Err(Error::UnknownProfile(_))));
+
+        // the environment still takes precedence over the profile
+        let env = |name: &str| (name == "OPENAI_BASE_URL").then(|| "http://localhost:8080/v1".to_string());
+        let local = base.profile_with_env("local", &env).unwrap();
+        assert_eq!(local.chat_url, "http://localhost:8080/v1/chat/completions");
+        assert_eq!(local.smart_model_max_output_tokens, 1000);
+    }
+    #[test]
+    fn test_profile_keeps_wrappers() {
+        let mut base = config();
+        base.profiles.insert("fast".to_string(), serde_json::json!({"smart_text_model": "cheap"}));
+        let driver = AIDriver::new_openai_no_validation(base)
+            .unwrap()
+            .with_cache(ResponseCache::in_memory())
+            .with_post_processor(PostProcessor::default())
+            .with_comments_stripped();
+        let fast = driver.with_profile("fast").unwrap();
+        assert!(matches!(fast, AIDriver::Custom(_)));
+        assert_eq!(fast.provider().smart_model(), "cheap");
+        assert!(matches!(driver.with_profile("missing"), Err(Error::UnknownProfile(_))));
+    }
+    #[tokio::test]
+    async fn test_transcribe_rejects_large_audio() {
+        let driver = OpenAIDriver::new_no_validate(config()).unwrap();
+        let audio = Audio::from_bytes("lecture.mp3", vec![0; MAX_TRANSCRIPTION_BYTES + 1]);
+        match driver.transcribe(audio).await {
+            Err(Error::AudioTooLarge { file_name, bytes, max_bytes }) => {
+                assert_eq!((file_name.as_str(), bytes, max_bytes), ("lecture.mp3", MAX_TRANSCRIPTION_BYTES + 1, MAX_TRANSCRIPTION_BYTES));
+            }
+            other => panic!("expected AudioTooLarge, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_http_client_config() {
+        let http: HttpClientConfig = serde_json::from_str(r#"{"timeout_secs": null}"#).unwrap();
+        assert_eq!(http.timeout_secs, None);
+        assert_eq!(http.connect_timeout_secs, Some(10));
+        let mut config = config();
+        config.http.proxy = Some("not a url".to_string());
+        assert!(OpenAIDriver::new_no_validate(config).is_err());
+    }
+    #[test]
+    fn test_messages_with_images() {
+        let driver = OpenAIDriver::new_no_validate(config()).unwrap();
+        let prompt = Prompt::new("System", "Question", ResponseBudget::Unlimited);
+        let text = serde_json::to_value(driver.messages(prompt.clone())).unwrap();
+        assert_eq!(text[1], serde_json::json!({"role": "user", "content": "Question"}));
+
+        let image = Image::from_bytes("cell.png", vec![1, 2, 3]).unwrap();
+        let prompt = prompt.with_image(ImagePart::from(&image)).with_image(ImagePart::Url("https://example.com/a.jpg".to_string()));
+        let parts = serde_json::to_value(driver.messages(prompt)).unwrap();
+        assert_eq!(parts[0], serde_json::json!({"role": "system", "content": "System"}));
+        assert_eq!(
+            parts[1]["content"],
+            serde_json::json!([
+                {"type": "text", "text": "Question"},
+                {"type": "image_url", "image_url": {"url": "data:image/png;base64,AQID"}},
+                {"type": "image_url", "image_url": {"url": "https://example.com/a.jpg"}},
+            ])
+        );
+    }
 }
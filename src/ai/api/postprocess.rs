@@ -0,0 +1,368 @@
+//! # obsidian-driver::ai::api::postprocess
+//!
+//! This module contains the post-processor for chat output: deterministic cleanup of the formatting rules the note prompts ask for, since models do not always follow them, applied to every chat response before it becomes a note.
+//!
+//! @public PostProcessor
+//!
+//! @public PostProcessor::process
+//!
+//! @crate PostProcessedProvider
+
+// third-party imports
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+// first-party imports
+use super::audio::Audio;
+use super::image::Image;
+use super::provider::{AiProvider, ProviderFuture};
+use super::AIDriver;
+use crate::ai::prompt::{Conversation, Prompt};
+use crate::file::mdfile::math::unicode_to_latex;
+use crate::prelude::*;
+
+/// ASCII replacements for the typographic characters models use most.
+const ASCII_REPLACEMENTS: [(char, &str); 12] = [
+    ('\u{2018}', "'"),
+    ('\u{2019}', "'"),
+    ('\u{201C}', "\""),
+    ('\u{201D}', "\""),
+    ('\u{2013}', "-"),
+    ('\u{2014}', "-"),
+    ('\u{2212}', "-"),
+    ('\u{2026}', "..."),
+    ('\u{00A0}', " "),
+    ('\u{2022}', "-"),
+    ('\u{00D7}', "x"),
+    ('\u{2192}', "->"),
+];
+
+/// PostProcessor struct
+///
+/// The cleanup steps applied to chat output, all on by default except `strip_non_ascii`, which
+/// would also remove accented and non-Latin text. They run in the order of the fields, and text
+/// inside code blocks is left alone.
+///
+/// # Example
+/// ```
+/// use obsidian_driver::ai::api::postprocess::PostProcessor;
+///
+/// let processor = PostProcessor::default();
+/// let response = "```markdown\n# Strings\nA substring \\( \\mu \\) of \u{201C}abc\u{201D}\n```";
+/// assert_eq!(processor.process(response), "# Strings\nA substring $\\mu$ of \u{201C}abc\u{201D}");
+/// assert_eq!(processor.process("```python\nprint(1)"), "```python\nprint(1)\n```");
+///
+/// let processor = PostProcessor::default().with_strip_non_ascii(true);
+/// assert_eq!(processor.process(response), "# Strings\nA substring $\\mu$ of \"abc\"");
+/// ```
+///
+/// @public
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PostProcessor {
+    // remove a ``` or ```markdown fence around the whole response
+    pub unwrap_fence: bool,
+    // close a code block left open at the end
+    pub balance_fences: bool,
+    // \( \) to $ $ and \[ \] to $$ $$
+    pub convert_math_delimiters: bool,
+    // Unicode math symbols to LaTeX, see `unicode_to_latex`
+    pub convert_unicode_math: bool,
+    // replace typographic characters with ASCII and remove other non-ASCII characters; off by default
+    pub strip_non_ascii: bool,
+}
+
+impl Default for PostProcessor {
+    fn default() -> Self {
+        PostProcessor {
+            unwrap_fence: true,
+            balance_fences: true,
+            convert_math_delimiters: true,
+            convert_unicode_math: true,
+            strip_non_ascii: false,
+        }
+    }
+}
+
+impl PostProcessor {
+    /// Remove a markdown fence around the whole response.
+    ///
+    /// # Arguments
+    /// @param unwrap_fence: bool
+    /// @return PostProcessor
+    pub fn with_unwrap_fence(mut self, unwrap_fence: bool) -> Self {
+        self.unwrap_fence = unwrap_fence;
+        self
+    }
+
+    /// Close a code block left open at the end of the response.
+    ///
+    /// # Arguments
+    /// @param balance_fences: bool
+    /// @return PostProcessor
+    pub fn with_balance_fences(mut self, balance_fences: bool) -> Self {
+        self.balance_fences = balance_fences;
+        self
+    }
+
+    /// Convert `\( \)` math to `$ $`, and `\[ \]` math to `$$ $$`.
+    ///
+    /// # Arguments
+    /// @param convert_math_delimiters: bool
+    /// @return PostProcessor
+    pub fn with_convert_math_delimiters(mut self, convert_math_delimiters: bool) -> Self {
+        self.convert_math_delimiters = convert_math_delimiters;
+        self
+    }
+
+    /// Replace Unicode math symbols, e.g. `α` or `≤`, with LaTeX, before `strip_non_ascii` would remove them.
+    ///
+    /// # Arguments
+    /// @param convert_unicode_math: bool
+    /// @return PostProcessor
+    pub fn with_convert_unicode_math(mut self, convert_unicode_math: bool) -> Self {
+        self.convert_unicode_math = convert_unicode_math;
+        self
+    }
+
+    /// Replace typographic quotes, dashes, and ellipses with ASCII, and remove other non-ASCII characters, outside code blocks.
+    ///
+    /// # Arguments
+    /// @param strip_non_ascii: bool
+    /// @return PostProcessor
+    pub fn with_strip_non_ascii(mut self, strip_non_ascii: bool) -> Self {
+        self.strip_non_ascii = strip_non_ascii;
+        self
+    }
+
+    /// Apply the enabled steps to a chat response.
+    ///
+    /// # Arguments
+    /// @param text: &str
+    /// @return String
+    pub fn process(&self, text: &str) -> String {
+        let mut text = text.to_string();
+        if self.unwrap_fence {
+            text = unwrap_fence(&text);
+        }
+        if self.balance_fences {
+            text = balance_fences(&text);
+        }
+        if self.convert_math_delimiters {
+            text = convert_math_delimiters(&text);
+        }
+        if self.convert_unicode_math {
+            text = unicode_to_latex(&text);
+        }
+        if self.strip_non_ascii {
+            text = strip_non_ascii(&text);
+        }
+        text
+    }
+}
+
+/// The fence marker opening or closing a code block on a line, e.g. "```" or "~~~~".
+///
+/// @private
+fn fence_marker(line: &str) -> Option<&str> {
+    let line = line.trim_start();
+    let length = line.chars().take_while(|c| *c == '`').count().max(line.chars().take_while(|c| *c == '~').count());
+    (length >= 3).then(|| &line[..length])
+}
+
+/// Remove a ```, ```markdown or ```md fence that wraps the whole text.
+///
+/// @private
+fn unwrap_fence(text: &str) -> String {
+    let trimmed = text.trim();
+    let lines: Vec<&str> = trimmed.lines().collect();
+    let Some(marker) = lines.first().and_then(|line| fence_marker(line)) else {
+        return text.to_string();
+    };
+    let info = lines[0].trim()[marker.len()..].trim().to_lowercase();
+    if lines.len() < 2 || !matches!(info.as_str(), "" | "markdown" | "md") {
+        return text.to_string();
+    }
+    // the fence opened on the first line must be closed on the last line, not before
+    let closing = lines[1..]
+        .iter()
+        .position(|line| fence_marker(line).is_some_and(|other| other.starts_with(marker) && line.trim() == other));
+    if closing != Some(lines.len() - 2) {
+        return text.to_string();
+    }
+    lines[1..lines.len() - 1].join("\n")
+}
+
+/// Close a code block left open at the end of the text.
+///
+/// @private
+fn balance_fences(text: &str) -> String {
+    let mut open: Option<&str> = None;
+    for line in text.lines() {
+        let Some(marker) = fence_marker(line) else {
+            continue;
+        };
+        match open {
+            None => open = Some(marker),
+            Some(opened) if marker.starts_with(opened) && line.trim() == marker => open = None,
+            Some(_) => {}
+        }
+    }
+    match open {
+        Some(marker) => {
+            let separator = if text.ends_with('\n') { "" } else { "\n" };
+            f!("{}{}{}", text, separator, marker)
+        }
+        None => text.to_string(),
+    }
+}
+
+/// Apply a transform to the text outside fenced code blocks.
+///
+/// @private
+fn outside_code(text: &str, transform: impl Fn(&str) -> String) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut prose = String::new();
+    let mut open: Option<String> = None;
+    for line in text.split_inclusive('\n') {
+        let marker = fence_marker(line);
+        match (&open, marker) {
+            (None, Some(marker)) => {
+                result.push_str(&transform(&prose));
+                prose.clear();
+                open = Some(marker.to_string());
+                result.push_str(line);
+            }
+            (None, None) => prose.push_str(line),
+            (Some(opened), Some(marker)) if marker.starts_with(opened.as_str()) && line.trim() == marker => {
+                open = None;
+                result.push_str(line);
+            }
+            (Some(_), _) => result.push_str(line),
+        }
+    }
+    result.push_str(&transform(&prose));
+    result
+}
+
+/// Convert `\( \)` and `\[ \]` math delimiters to dollar signs.
+///
+/// @private
+fn convert_math_delimiters(text: &str) -> String {
+    let inline = Regex::new(r"\\\((.+?)\\\)").unwrap();
+    let display = Regex::new(r"(?s)\\\[(.+?)\\\]").unwrap();
+    outside_code(text, |prose| {
+        let prose = display.replace_all(prose, |captures: &regex::Captures| f!("$${}$$", captures[1].trim()));
+        inline
+            .replace_all(&prose, |captures: &regex::Captures| f!("${}$", captures[1].trim()))
+            .into_owned()
+    })
+}
+
+/// Replace typographic characters with ASCII and remove other non-ASCII characters, outside code blocks.
+///
+/// @private
+fn strip_non_ascii(text: &str) -> String {
+    outside_code(text, |prose| {
+        let mut stripped = String::with_capacity(prose.len());
+        for c in prose.chars() {
+            if c.is_ascii() {
+                stripped.push(c);
+            } else if let Some((_, replacement)) = ASCII_REPLACEMENTS.iter().find(|(other, _)| *other == c) {
+                stripped.push_str(replacement);
+            }
+        }
+        stripped
+    })
+}
+
+/// A provider that runs a PostProcessor on the chat responses of the driver it wraps.
+///
+/// @crate
+#[derive(Debug)]
+pub(crate) struct PostProcessedProvider {
+    pub(crate) inner: AIDriver,
+    pub(crate) processor: PostProcessor,
+}
+
+impl AiProvider for PostProcessedProvider {
+    fn chat_smart(&self, prompt: Prompt) -> ProviderFuture<'_, String> {
+        Box::pin(async move { Ok(self.processor.process(&self.inner.provider().chat_smart(prompt).await?)) })
+    }
+
+    fn chat_cheap(&self, prompt: Prompt) -> ProviderFuture<'_, String> {
+        Box::pin(async move { Ok(self.processor.process(&self.inner.provider().chat_cheap(prompt).await?)) })
+    }
+
+    fn chat_conversation(&self, conversation: &Conversation) -> ProviderFuture<'_, String> {
+        let conversation = conversation.clone();
+        Box::pin(async move { Ok(self.processor.process(&self.inner.provider().chat_conversation(&conversation).await?)) })
+    }
+
+    fn embed<'a>(&'a self, text: &'a str) -> ProviderFuture<'a, Vec<f64>> {
+        self.inner.provider().embed(text)
+    }
+
+    fn describe_image(&self, prompt: Prompt, image: Image) -> ProviderFuture<'_, String> {
+        Box::pin(async move { Ok(self.processor.process(&self.inner.provider().describe_image(prompt, image).await?)) })
+    }
+
+    fn transcribe(&self, audio: Audio) -> ProviderFuture<'_, String> {
+        self.inner.provider().transcribe(audio)
+    }
+
+    fn embedding_model(&self) -> &str {
+        self.inner.provider().embedding_model()
+    }
+
+    fn embedding_dimensions(&self) -> Option<usize> {
+        self.inner.provider().embedding_dimensions()
+    }
+
+    fn max_input_tokens(&self) -> Option<u32> {
+        self.inner.provider().max_input_tokens()
+    }
+
+    fn with_profile(&self, name: &str) -> Result<AIDriver> {
+        Ok(self.inner.with_profile(name)?.with_post_processor(self.processor.clone()))
+    }
+
+    fn smart_model(&self) -> &str {
+        self.inner.provider().smart_model()
+    }
+
+    fn cheap_model(&self) -> &str {
+        self.inner.provider().cheap_model()
+    }
+
+    fn transcription_model(&self) -> &str {
+        self.inner.provider().transcription_model()
+    }
+}
+
+#[cfg(test)]
+mod postprocess_tests {
+    use super::*;
+    use crate::ai::api::mock::MockDriver;
+    use crate::ai::prompt::ResponseBudget;
+
+    #[test]
+    fn test_steps_leave_code_alone() {
+        let text = "Inline \\(x^2\\) and\n\\[\n\\sum_i i\n\\]\n```latex\n\\(kept\\)\n```\n";
+        assert_eq!(convert_math_delimiters(text), "Inline $x^2$ and\n$$\\sum_i i$$\n```latex\n\\(kept\\)\n```\n");
+        // a response that only starts and ends with code blocks is not wrapped
+        let text = "```\na\n```\ntext\n```\nb\n```";
+        assert_eq!(unwrap_fence(text), text);
+        assert_eq!(balance_fences("````\n```\nstill code"), "````\n```\nstill code\n````");
+        assert_eq!(strip_non_ascii("a \u{2014} b\u{2026} \u{03B1}"), "a - b... ");
+        assert_eq!(strip_non_ascii("caf\u{00E9}\n```\nprint(\"caf\u{00E9}\")\n```\n"), "caf\n```\nprint(\"caf\u{00E9}\")\n```\n");
+    }
+
+    #[tokio::test]
+    async fn test_driver_post_processes_chats() {
+        let driver = AIDriver::new_mock(MockDriver::new().with_responses(["```md\n# Note\n\\(a\\)\n```"]))
+            .with_post_processor(PostProcessor::default());
+        let prompt = Prompt::new("You take notes.", "Notes", ResponseBudget::Unlimited);
+        let file = crate::ai::generate_file(&driver, prompt, Default::default(), "Note.md".to_string(), std::path::PathBuf::from("out")).await.unwrap();
+        assert_eq!(file.get_mdfile().unwrap().get_body(), "# Note\n$a$");
+    }
+}
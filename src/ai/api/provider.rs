@@ -0,0 +1,173 @@
+//! # obsidian-driver::ai::api::provider
+//!
+//! This module contains the AiProvider trait, the interface every AI backend implements. Implement it to plug a backend of your own into `AIDriver::from_provider`.
+//!
+//! @public AiProvider
+//!
+//! @public ProviderFuture
+
+// std imports
+use std::fmt::Debug;
+use std::future::Future;
+use std::pin::Pin;
+
+// first-party imports
+use super::audio::Audio;
+use super::image::Image;
+use super::AIDriver;
+use crate::ai::prompt::{Conversation, Prompt};
+use crate::error::Error;
+
+/// The boxed future returned by AiProvider methods.
+///
+/// @public
+pub type ProviderFuture<'a, T> = Pin<Box<dyn Future<Output = Result<T, Error>> + Send + 'a>>;
+
+/// An AI backend: two chat models, an embedding model, and optionally vision and transcription models.
+///
+/// Methods return boxed futures so providers can be stored as `Arc<dyn AiProvider>`.
+///
+/// # Examples
+/// ```
+/// use obsidian_driver::ai::api::AIDriver;
+/// use obsidian_driver::ai::api::provider::{AiProvider, ProviderFuture};
+/// use obsidian_driver::ai::prompt::Prompt;
+///
+/// #[derive(Debug)]
+/// struct Echo;
+///
+/// impl AiProvider for Echo {
+/// 	fn chat_smart(&self, prompt: Prompt) -> ProviderFuture<'_, String> {
+/// 		Box::pin(async move { Ok(prompt.user_prompt) })
+/// 	}
+/// 	fn chat_cheap(&self, prompt: Prompt) -> ProviderFuture<'_, String> {
+/// 		self.chat_smart(prompt)
+/// 	}
+/// 	fn embed<'a>(&'a self, text: &'a str) -> ProviderFuture<'a, Vec<f64>> {
+/// 		Box::pin(async move { Ok(vec![text.len() as f64]) })
+/// 	}
+/// 	fn embedding_model(&self) -> &str {
+/// 		"length"
+/// 	}
+/// }
+///
+/// #[tokio::main]
+/// async fn main() {
+/// 	let driver = AIDriver::from_provider(Echo);
+/// 	assert_eq!(driver.get_embedding("four").await.unwrap(), vec![4.0]);
+/// }
+/// ```
+/// @public
+pub trait AiProvider: Debug + Send + Sync {
+    /// Send a prompt to the more capable chat model.
+    ///
+    /// # Arguments
+    /// @param `prompt`: `Prompt`
+    /// @returns `ProviderFuture<String>` - The response text.
+    fn chat_smart(&self, prompt: Prompt) -> ProviderFuture<'_, String>;
+
+    /// Send a prompt to the cheaper chat model.
+    ///
+    /// # Arguments
+    /// @param `prompt`: `Prompt`
+    /// @returns `ProviderFuture<String>` - The response text.
+    fn chat_cheap(&self, prompt: Prompt) -> ProviderFuture<'_, String>;
+
+    /// Send a message history to the more capable chat model.
+    ///
+    /// By default the conversation is flattened with `Conversation::to_prompt` and sent to `chat_smart`; override it for backends with native multi-turn support.
+    ///
+    /// # Arguments
+    /// @param `conversation`: `&Conversation`
+    /// @returns `ProviderFuture<String>` - The response text.
+    fn chat_conversation(&self, conversation: &Conversation) -> ProviderFuture<'_, String> {
+        self.chat_smart(conversation.to_prompt())
+    }
+
+    /// Get the embedding of a text.
+    ///
+    /// # Arguments
+    /// @param `text`: `&str`
+    /// @returns `ProviderFuture<Vec<f64>>`
+    fn embed<'a>(&'a self, text: &'a str) -> ProviderFuture<'a, Vec<f64>>;
+
+    /// Send a prompt with an image to the smart model, if it can see images.
+    ///
+    /// By default this fails with `NotSupported`; override it for backends with a vision model.
+    ///
+    /// # Arguments
+    /// @param `prompt`: `Prompt` - The instructions, e.g. to transcribe the text in the image.
+    /// @param `image`: `Image`
+    /// @returns `ProviderFuture<String>` - The response text.
+    fn describe_image(&self, _prompt: Prompt, image: Image) -> ProviderFuture<'_, String> {
+        Box::pin(async move { Err(Error::NotSupported(format!("images, such as {}", image.file_name))) })
+    }
+
+    /// Transcribe a recording to text.
+    ///
+    /// By default this fails with `NotSupported`; override it for backends with a speech-to-text model.
+    ///
+    /// # Arguments
+    /// @param `audio`: `Audio`
+    /// @returns `ProviderFuture<String>` - The transcript.
+    fn transcribe(&self, audio: Audio) -> ProviderFuture<'_, String> {
+        Box::pin(async move { Err(Error::NotSupported(format!("transcribing {}", audio.file_name))) })
+    }
+
+    /// Get the name of the embedding model, stored with cached embeddings.
+    ///
+    /// # Arguments
+    /// @returns `&str`
+    fn embedding_model(&self) -> &str;
+
+    /// Get the length of the embedding vectors, if known.
+    ///
+    /// # Arguments
+    /// @returns `Option<usize>`
+    fn embedding_dimensions(&self) -> Option<usize> {
+        None
+    }
+
+    /// Get the name of the smart chat model, used e.g. in cache keys. Empty if unknown.
+    ///
+    /// # Arguments
+    /// @returns `&str`
+    fn smart_model(&self) -> &str {
+        ""
+    }
+
+    /// Get the name of the cheap chat model, used e.g. in cache keys. Empty if unknown.
+    ///
+    /// # Arguments
+    /// @returns `&str`
+    fn cheap_model(&self) -> &str {
+        ""
+    }
+
+    /// Get the name of the transcription model, used e.g. in cache keys. Empty if unknown.
+    ///
+    /// # Arguments
+    /// @returns `&str`
+    fn transcription_model(&self) -> &str {
+        ""
+    }
+
+    /// Get the number of tokens the smart model accepts in a prompt, if known. `generate_file` splits longer inputs into chunks.
+    ///
+    /// # Arguments
+    /// @returns `Option<u32>`
+    fn max_input_tokens(&self) -> Option<u32> {
+        None
+    }
+
+    /// Get a driver with the settings of a named profile, see `AIDriver::with_profile`.
+    ///
+    /// By default this fails with `UnknownProfile`; override it for backends with profiles, and for wrappers, which wrap the profile of the driver they wrap.
+    ///
+    /// # Arguments
+    /// @param `name`: `&str`
+    /// @returns `Result<AIDriver, Error>`
+    fn with_profile(&self, name: &str) -> Result<AIDriver, Error> {
+        Err(Error::UnknownProfile(name.to_string()))
+    }
+}
@@ -0,0 +1,110 @@
+//! # obsidian-driver::ai::api::redact
+//!
+//! This module contains the comment-stripping provider, which removes Obsidian `%% comments %%` from everything sent to the model, since comments often hold private annotations that should not leave the vault.
+//!
+//! @crate CommentStrippedProvider
+
+// first-party imports
+use super::audio::Audio;
+use super::image::Image;
+use super::provider::{AiProvider, ProviderFuture};
+use super::AIDriver;
+use crate::ai::prompt::{Conversation, Prompt};
+use crate::error::Error;
+use crate::file::mdfile::obsidian::strip_comments;
+
+/// A provider that removes comments from the prompts, conversations and embedding texts of the driver it wraps.
+///
+/// @crate
+#[derive(Debug)]
+pub(crate) struct CommentStrippedProvider {
+	pub(crate) inner: AIDriver,
+}
+
+/// Remove comments from every part of a prompt.
+///
+/// @private
+fn strip_prompt(mut prompt: Prompt) -> Prompt {
+	prompt.system_prompt = strip_comments(&prompt.system_prompt);
+	prompt.user_prompt = strip_comments(&prompt.user_prompt);
+	for extra in prompt.extra_system_prompts.iter_mut() {
+		*extra = strip_comments(extra);
+	}
+	prompt
+}
+
+impl AiProvider for CommentStrippedProvider {
+	fn chat_smart(&self, prompt: Prompt) -> ProviderFuture<'_, String> {
+		self.inner.provider().chat_smart(strip_prompt(prompt))
+	}
+
+	fn chat_cheap(&self, prompt: Prompt) -> ProviderFuture<'_, String> {
+		self.inner.provider().chat_cheap(strip_prompt(prompt))
+	}
+
+	fn chat_conversation(&self, conversation: &Conversation) -> ProviderFuture<'_, String> {
+		let mut stripped = Conversation::default();
+		stripped.response_budget = conversation.response_budget;
+		for message in conversation.messages() {
+			stripped.push(message.role, &strip_comments(&message.content));
+		}
+		Box::pin(async move { self.inner.provider().chat_conversation(&stripped).await })
+	}
+
+	fn embed<'a>(&'a self, text: &'a str) -> ProviderFuture<'a, Vec<f64>> {
+		let text = strip_comments(text);
+		Box::pin(async move { self.inner.provider().embed(&text).await })
+	}
+
+	fn describe_image(&self, prompt: Prompt, image: Image) -> ProviderFuture<'_, String> {
+		self.inner.provider().describe_image(strip_prompt(prompt), image)
+	}
+
+	fn transcribe(&self, audio: Audio) -> ProviderFuture<'_, String> {
+		self.inner.provider().transcribe(audio)
+	}
+
+	fn embedding_model(&self) -> &str {
+		self.inner.provider().embedding_model()
+	}
+
+	fn embedding_dimensions(&self) -> Option<usize> {
+		self.inner.provider().embedding_dimensions()
+	}
+
+	fn max_input_tokens(&self) -> Option<u32> {
+		self.inner.provider().max_input_tokens()
+	}
+
+	fn with_profile(&self, name: &str) -> Result<AIDriver, Error> {
+		Ok(self.inner.with_profile(name)?.with_comments_stripped())
+	}
+
+	fn smart_model(&self) -> &str {
+		self.inner.provider().smart_model()
+	}
+
+	fn cheap_model(&self) -> &str {
+		self.inner.provider().cheap_model()
+	}
+
+	fn transcription_model(&self) -> &str {
+		self.inner.provider().transcription_model()
+	}
+}
+
+#[cfg(test)]
+mod redact_tests {
+	use super::*;
+	use crate::ai::api::mock::MockDriver;
+	use crate::ai::prompt::ResponseBudget;
+
+	#[tokio::test]
+	async fn test_comments_are_not_sent() {
+		let mock = MockDriver::new().with_responses(["ok"]);
+		let driver = AIDriver::new_mock(mock.clone()).with_comments_stripped();
+		let prompt = Prompt::new("You take notes.", "Cells divide.%% ask about exam %%\n`%%kept%%`", ResponseBudget::Unlimited);
+		driver.chat_smart(prompt).await.unwrap();
+		assert_eq!(mock.prompts()[0].user_prompt, "Cells divide.\n`%%kept%%`");
+	}
+}
@@ -0,0 +1,301 @@
+//! # obsidian-driver::ai::api::replay
+//!
+//! This module contains record and replay of provider responses: a recording driver saves every chat response, embedding and transcript of a real provider to a cassette file, and a replaying driver serves them back without network access, so tests of `generate_file`, `merge_files` or `Vault::update_embeddings` run deterministically offline.
+//!
+//! @public Cassette
+//!
+//! @public Cassette::open
+//!
+//! @public Cassette::save
+//!
+//! @crate ReplayProvider
+
+// std imports
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+// third-party imports
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+// first-party imports
+use super::audio::Audio;
+use super::image::Image;
+use super::cache::{hash, ResponseCache};
+use super::provider::{AiProvider, ProviderFuture};
+use super::AIDriver;
+use crate::ai::prompt::{Conversation, Prompt, Role};
+use crate::file::write_atomic;
+use crate::prelude::*;
+
+/// Cassette struct
+///
+/// The recorded responses of a provider, keyed like `ResponseCache`, with the model names and
+/// limits the provider reported, so a replaying driver behaves like the recorded one.
+///
+/// @public
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Cassette {
+    pub smart_model: String,
+    pub cheap_model: String,
+    pub embedding_model: String,
+    #[serde(default)]
+    pub transcription_model: String,
+    pub embedding_dimensions: Option<usize>,
+    pub max_input_tokens: Option<u32>,
+    pub chats: BTreeMap<String, String>,
+    pub embeddings: BTreeMap<String, Vec<f64>>,
+    #[serde(default)]
+    pub transcriptions: BTreeMap<String, String>,
+}
+
+impl Cassette {
+    /// Read a cassette file.
+    ///
+    /// # Arguments
+    /// @param path: &Path
+    /// @return Result<Cassette>
+    pub fn open(path: &Path) -> Result<Cassette> {
+        let text = std::fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&text)?)
+    }
+
+    /// Write the cassette to a file.
+    ///
+    /// # Arguments
+    /// @param path: &Path
+    /// @return Result<()>
+    pub fn save(&self, path: &Path) -> Result<()> {
+        write_atomic(path, serde_json::to_string_pretty(self)?)
+    }
+}
+
+/// A provider that records to or replays from a cassette file.
+///
+/// @crate
+#[derive(Debug)]
+pub(crate) struct ReplayProvider {
+    // the recorded provider; None when replaying
+    inner: Option<AIDriver>,
+    cassette: Arc<Mutex<Cassette>>,
+    path: PathBuf,
+    // copied from the cassette, which keeps them for replaying
+    smart_model: String,
+    cheap_model: String,
+    embedding_model: String,
+    transcription_model: String,
+}
+
+impl ReplayProvider {
+    /// Record the responses of a driver to a cassette file, adding to the file if it exists.
+    ///
+    /// @crate
+    pub(crate) fn record(inner: AIDriver, path: &Path) -> Result<ReplayProvider> {
+        let mut cassette = if path.exists() { Cassette::open(path)? } else { Cassette::default() };
+        let provider = inner.provider();
+        cassette.smart_model = provider.smart_model().to_string();
+        cassette.cheap_model = provider.cheap_model().to_string();
+        cassette.embedding_model = provider.embedding_model().to_string();
+        cassette.transcription_model = provider.transcription_model().to_string();
+        cassette.embedding_dimensions = provider.embedding_dimensions();
+        cassette.max_input_tokens = provider.max_input_tokens();
+        Ok(ReplayProvider::new(Some(inner), cassette, path))
+    }
+
+    /// Replay a cassette file.
+    ///
+    /// @crate
+    pub(crate) fn replay(path: &Path) -> Result<ReplayProvider> {
+        Ok(ReplayProvider::new(None, Cassette::open(path)?, path))
+    }
+
+    /// Create the provider around a cassette.
+    ///
+    /// @private
+    fn new(inner: Option<AIDriver>, cassette: Cassette, path: &Path) -> ReplayProvider {
+        ReplayProvider {
+            inner,
+            smart_model: cassette.smart_model.clone(),
+            cheap_model: cassette.cheap_model.clone(),
+            embedding_model: cassette.embedding_model.clone(),
+            transcription_model: cassette.transcription_model.clone(),
+            cassette: Arc::new(Mutex::new(cassette)),
+            path: path.to_path_buf(),
+        }
+    }
+
+    /// Get a chat response: from the provider when recording, from the cassette when replaying.
+    ///
+    /// @private
+    async fn chat<'a>(&'a self, key: String, request: &str, send: impl FnOnce(&'a AIDriver) -> ProviderFuture<'a, String>) -> Result<String> {
+        let Some(inner) = &self.inner else {
+            debug!(%key, "chat response replayed");
+            let cassette = self.cassette.lock().expect("Cassette lock poisoned");
+            return cassette.chats.get(&key).cloned().ok_or(Error::NotRecorded(excerpt(request)));
+        };
+        let response = send(inner).await?;
+        debug!(%key, "chat response recorded");
+        let mut cassette = self.cassette.lock().expect("Cassette lock poisoned");
+        cassette.chats.insert(key, response.clone());
+        cassette.save(&self.path)?;
+        Ok(response)
+    }
+}
+
+/// The start of a request, to name it in errors.
+///
+/// @private
+fn excerpt(request: &str) -> String {
+    let excerpt: String = request.chars().take(80).collect();
+    if excerpt.len() < request.len() {
+        f!("{}...", excerpt)
+    } else {
+        excerpt
+    }
+}
+
+impl AiProvider for ReplayProvider {
+    fn chat_smart(&self, prompt: Prompt) -> ProviderFuture<'_, String> {
+        let key = ResponseCache::key(&self.smart_model, &prompt);
+        Box::pin(async move {
+            let request = prompt.user_prompt.clone();
+            self.chat(key, &request, |inner| inner.provider().chat_smart(prompt)).await
+        })
+    }
+
+    fn chat_cheap(&self, prompt: Prompt) -> ProviderFuture<'_, String> {
+        let key = ResponseCache::key(&self.cheap_model, &prompt);
+        Box::pin(async move {
+            let request = prompt.user_prompt.clone();
+            self.chat(key, &request, |inner| inner.provider().chat_cheap(prompt)).await
+        })
+    }
+
+    fn chat_conversation(&self, conversation: &Conversation) -> ProviderFuture<'_, String> {
+        let key = ResponseCache::conversation_key(&self.smart_model, conversation);
+        let conversation = conversation.clone();
+        Box::pin(async move {
+            let request = conversation
+                .messages()
+                .iter()
+                .rev()
+                .find(|message| message.role == Role::User)
+                .map(|message| message.content.clone())
+                .unwrap_or_default();
+            self.chat(key, &request, |inner| inner.provider().chat_conversation(&conversation)).await
+        })
+    }
+
+    fn embed<'a>(&'a self, text: &'a str) -> ProviderFuture<'a, Vec<f64>> {
+        Box::pin(async move {
+            let key = hash(&serde_json::json!({ "model": self.embedding_model, "text": text }));
+            let Some(inner) = &self.inner else {
+                let cassette = self.cassette.lock().expect("Cassette lock poisoned");
+                return cassette.embeddings.get(&key).cloned().ok_or(Error::NotRecorded(excerpt(text)));
+            };
+            let embedding = inner.provider().embed(text).await?;
+            let mut cassette = self.cassette.lock().expect("Cassette lock poisoned");
+            cassette.embeddings.insert(key, embedding.clone());
+            cassette.save(&self.path)?;
+            Ok(embedding)
+        })
+    }
+
+    fn describe_image(&self, prompt: Prompt, image: Image) -> ProviderFuture<'_, String> {
+        let key = hash(&serde_json::json!({ "prompt": ResponseCache::key(&self.smart_model, &prompt), "image": image.digest() }));
+        Box::pin(async move {
+            let request = f!("{} {}", image.file_name, prompt.user_prompt);
+            self.chat(key, &request, |inner| inner.provider().describe_image(prompt, image)).await
+        })
+    }
+
+    fn transcribe(&self, audio: Audio) -> ProviderFuture<'_, String> {
+        Box::pin(async move {
+            let key = hash(&serde_json::json!({ "model": self.transcription_model, "transcription": audio.digest() }));
+            let Some(inner) = &self.inner else {
+                let cassette = self.cassette.lock().expect("Cassette lock poisoned");
+                return cassette.transcriptions.get(&key).cloned().ok_or(Error::NotRecorded(audio.file_name));
+            };
+            let transcript = inner.provider().transcribe(audio).await?;
+            let mut cassette = self.cassette.lock().expect("Cassette lock poisoned");
+            cassette.transcriptions.insert(key, transcript.clone());
+            cassette.save(&self.path)?;
+            Ok(transcript)
+        })
+    }
+
+    fn embedding_model(&self) -> &str {
+        &self.embedding_model
+    }
+
+    fn embedding_dimensions(&self) -> Option<usize> {
+        self.cassette.lock().expect("Cassette lock poisoned").embedding_dimensions
+    }
+
+    fn max_input_tokens(&self) -> Option<u32> {
+        self.cassette.lock().expect("Cassette lock poisoned").max_input_tokens
+    }
+
+    fn smart_model(&self) -> &str {
+        &self.smart_model
+    }
+
+    fn cheap_model(&self) -> &str {
+        &self.cheap_model
+    }
+
+    fn transcription_model(&self) -> &str {
+        &self.transcription_model
+    }
+}
+
+#[cfg(test)]
+mod replay_tests {
+    use super::*;
+    use crate::ai::api::mock::MockDriver;
+    use crate::ai::prompt::{Context, ResponseBudget};
+    use crate::file::vault::Vault;
+
+    #[tokio::test]
+    async fn test_replay_serves_recorded_responses() {
+        let dir = tempfile::tempdir().unwrap();
+        let cassette = dir.path().join("cassette.json");
+        let vault_dir = tempfile::tempdir().unwrap();
+        std::fs::write(vault_dir.path().join("Cells.md"), "# Cells\n\nCells divide.").unwrap();
+        let prompt = Prompt::new("You take notes.", "Notes on [text]", ResponseBudget::Unlimited);
+        let mut context = Context::default();
+        context.insert("text", "mitosis");
+
+        let audio = Audio::from_bytes("lecture.mp3", vec![1, 2, 3]);
+        let recording = AIDriver::new_mock(MockDriver::new().with_responses(["# Mitosis"]).with_transcript("Cells divide."))
+            .record(&cassette)
+            .unwrap();
+        recording.transcribe(audio.clone()).await.unwrap();
+        let recorded = crate::ai::generate_file(&recording, prompt.clone(), context.clone(), "Mitosis".to_string(), dir.path().to_path_buf()).await.unwrap();
+        let mut vault = Vault::from_path(vault_dir.path().to_path_buf()).unwrap();
+        vault.add_ai_driver(recording);
+        vault.update_embeddings().await.unwrap();
+        let embedding = vault.get_file(&PathBuf::from("Cells.md")).unwrap().get_embedding().cloned();
+
+        let replaying = AIDriver::replay(&cassette).unwrap();
+        assert_eq!(replaying.embedding_model(), crate::ai::api::mock::MOCK_EMBEDDING_MODEL);
+        let replayed = crate::ai::generate_file(&replaying, prompt.clone(), context, "Mitosis".to_string(), dir.path().to_path_buf()).await.unwrap();
+        assert_eq!(replayed.get_mdfile(), recorded.get_mdfile());
+        let mut vault = Vault::from_path(vault_dir.path().to_path_buf()).unwrap();
+        vault.add_ai_driver(replaying.clone());
+        vault.update_embeddings().await.unwrap();
+        assert_eq!(vault.get_file(&PathBuf::from("Cells.md")).unwrap().get_embedding().cloned(), embedding);
+
+        assert_eq!(replaying.transcribe(audio.clone()).await.unwrap(), "Cells divide.");
+        // a transcript is recorded for the model that made it
+        let mut other_model = Cassette::open(&cassette).unwrap();
+        other_model.transcription_model = "other-transcription".to_string();
+        other_model.save(&cassette).unwrap();
+        let replaying_other = AIDriver::replay(&cassette).unwrap();
+        assert!(matches!(replaying_other.transcribe(audio).await, Err(Error::NotRecorded(_))));
+
+        let unrecorded = Prompt::new("You take notes.", "Something else", ResponseBudget::Unlimited);
+        assert!(matches!(replaying.chat_smart(unrecorded).await, Err(Error::NotRecorded(request)) if request == "Something else"));
+    }
+}
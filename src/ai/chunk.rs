@@ -0,0 +1,254 @@
+//! # obsidian-driver::ai::chunk
+//!
+//! This module contains chunked generation, for inputs too long for the model: the longest context value (usually a transcript) is split by headings, paragraphs, lines, or sentences, a response is generated for each chunk, and a final pass merges the responses into one note.
+//!
+//! @public split_text
+//!
+//! @public chat_chunked
+
+// third-party imports
+use futures::future;
+
+// first-party imports
+use crate::ai::api::AIDriver;
+use crate::ai::prompt::{Context, Prompt, ResponseBudget, CHARACTERS_PER_TOKEN};
+use crate::prelude::*;
+
+const CHUNK_MERGE_USER_PROMPT: &str = r#"The notes below were written from consecutive parts of one source, in order. Combine them into a single note in the same format: merge sections that cover the same topic, remove repetition, and keep every piece of information, in the order of the source. If the notes have Takeaways sections, combine them into one Takeaways section at the end that keeps every takeaway.
+
+[parts]"#;
+
+/// Split a text into chunks of at most `max_tokens` estimated tokens.
+///
+/// The text is split before headings first, then between paragraphs, lines, sentences, and
+/// words, and the pieces are packed back together in order, so chunks break at the most natural
+/// boundary that makes them fit. Chunks are trimmed.
+///
+/// # Arguments
+/// @param text: &str
+/// @param max_tokens: u32 - Estimated at 4 characters per token
+/// @returns Vec<String> - The chunks, in order
+///
+/// # Examples
+/// ```
+/// use obsidian_driver::ai::chunk::split_text;
+///
+/// let text = "# One\n\nFirst part.\n\n# Two\n\nSecond part.";
+/// assert_eq!(split_text(text, 100), vec![text.to_string()]);
+/// assert_eq!(split_text(text, 6), vec!["# One\n\nFirst part.".to_string(), "# Two\n\nSecond part.".to_string()]);
+/// ```
+/// @public
+pub fn split_text(text: &str, max_tokens: u32) -> Vec<String> {
+    let max_characters = (max_tokens.max(1) * CHARACTERS_PER_TOKEN) as usize;
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+    for piece in pieces(text, max_characters, 0) {
+        if !current.trim().is_empty() && current.chars().count() + piece.chars().count() > max_characters {
+            chunks.push(current.trim().to_string());
+            current.clear();
+        }
+        current.push_str(piece);
+    }
+    if !current.trim().is_empty() {
+        chunks.push(current.trim().to_string());
+    }
+    chunks
+}
+
+/// Split a text into pieces of at most `max_characters`, at the boundaries of `level` and finer ones.
+///
+/// @private
+fn pieces(text: &str, max_characters: usize, level: usize) -> Vec<&str> {
+    if text.chars().count() <= max_characters {
+        return vec![text];
+    }
+    let split: Vec<&str> = match level {
+        0 => {
+            let mut sections = Vec::new();
+            let mut start = 0;
+            let mut offset = 0;
+            for line in text.split_inclusive('\n') {
+                let hashes = line.len() - line.trim_start_matches('#').len();
+                if offset > start && (1..=6).contains(&hashes) && line[hashes..].starts_with([' ', '\t']) {
+                    sections.push(&text[start..offset]);
+                    start = offset;
+                }
+                offset += line.len();
+            }
+            sections.push(&text[start..]);
+            sections
+        }
+        1 => text.split_inclusive("\n\n").collect(),
+        2 => text.split_inclusive('\n').collect(),
+        3 => text.split_inclusive(". ").collect(),
+        4 => text.split_inclusive(' ').collect(),
+        _ => {
+            // no boundary left, cut at character boundaries
+            let mut cuts: Vec<usize> = text.char_indices().map(|(index, _)| index).step_by(max_characters).collect();
+            cuts.push(text.len());
+            return cuts.windows(2).map(|cut| &text[cut[0]..cut[1]]).collect();
+        }
+    };
+    split
+        .into_iter()
+        .flat_map(|piece| pieces(piece, max_characters, level + 1))
+        .collect()
+}
+
+/// Send a prompt to the smart model, splitting it into several requests if it is longer than `max_input_tokens`.
+///
+/// A prompt that fits is sent as it is. Otherwise the longest context value is split with
+/// `split_text` so that each substituted prompt fits, every chunk gets its own response, and
+/// the responses are merged with further requests, in groups that fit, until one is left.
+///
+/// # Arguments
+/// @param driver: &AIDriver - The AI driver to use
+/// @param prompt: &Prompt - The prompt, with its placeholders
+/// @param context: &Context - The context to substitute into the prompt
+/// @param max_input_tokens: u32 - The largest prompt the model accepts
+/// @returns Result<String> - The response, or the merged responses. Errors with `PromptExceedsModelTokenLimit` if even an empty chunk does not fit.
+///
+/// # Examples
+/// ```
+/// use obsidian_driver::ai::api::AIDriver;
+/// use obsidian_driver::ai::api::mock::MockDriver;
+/// use obsidian_driver::ai::chunk::chat_chunked;
+/// use obsidian_driver::ai::prompt::{Context, Prompt, ResponseBudget};
+///
+/// #[tokio::main]
+/// async fn main() {
+/// 	let mock = MockDriver::new().with_responses(["Notes 1", "Notes 2", "Merged notes"]);
+/// 	let driver = AIDriver::new_mock(mock.clone());
+/// 	let prompt = Prompt::new("You take notes.", "Make notes:\n\n[text]", ResponseBudget::Unlimited);
+/// 	let mut context = Context::default();
+/// 	context.insert("text", &"A sentence of the lecture. ".repeat(40));
+///
+/// 	let notes = chat_chunked(&driver, &prompt, &context, 200).await.unwrap();
+/// 	assert_eq!(notes, "Merged notes");
+/// 	assert_eq!(mock.prompts().len(), 3);
+/// }
+/// ```
+/// @public
+pub async fn chat_chunked(driver: &AIDriver, prompt: &Prompt, context: &Context, max_input_tokens: u32) -> Result<String> {
+    let substituted = prompt.substitute(context)?;
+    if substituted.estimated_tokens() <= max_input_tokens {
+        return driver.chat_smart(substituted).await;
+    }
+    let longest = prompt
+        .placeholders()
+        .into_iter()
+        .filter_map(|key| context.get(&key).map(|value| (value.chars().count(), key)))
+        .max();
+    let Some((_, key)) = longest else {
+        return Err(Error::PromptExceedsModelTokenLimit(Box::new(substituted)));
+    };
+    let mut chunk_context = context.clone();
+    chunk_context.insert(&key, "");
+    let overhead = prompt.substitute(&chunk_context)?.estimated_tokens();
+    // the value is repeated wherever its placeholder appears
+    let placeholder = f!("[{}]", key);
+    let occurrences = std::iter::once(&prompt.system_prompt)
+        .chain(prompt.extra_system_prompts.iter())
+        .chain(std::iter::once(&prompt.user_prompt))
+        .map(|text| text.matches(&placeholder).count() as u32)
+        .sum::<u32>()
+        .max(1);
+    if overhead + occurrences >= max_input_tokens {
+        return Err(Error::PromptExceedsModelTokenLimit(Box::new(substituted)));
+    }
+
+    let value = context.get(&key).cloned().unwrap_or_default();
+    let mut prompts = Vec::new();
+    for chunk in split_text(&value, (max_input_tokens - overhead) / occurrences) {
+        chunk_context.insert(&key, &chunk);
+        prompts.push(prompt.substitute(&chunk_context)?);
+    }
+    let responses = future::try_join_all(prompts.into_iter().map(|prompt| driver.chat_smart(prompt))).await?;
+    merge_responses(driver, &substituted, responses, max_input_tokens).await
+}
+
+/// Merge the responses to the chunks of a prompt, in groups that fit in `max_input_tokens`, until one is left.
+///
+/// @private
+async fn merge_responses(driver: &AIDriver, prompt: &Prompt, mut responses: Vec<String>, max_input_tokens: u32) -> Result<String> {
+    // the merge requests keep the system prompts, so the merged note follows the same rules
+    let mut merge_prompt = prompt.clone();
+    merge_prompt.user_prompt = CHUNK_MERGE_USER_PROMPT.to_string();
+    let overhead = merge_prompt.estimated_tokens();
+    let budget = max_input_tokens.saturating_sub(overhead) as usize * CHARACTERS_PER_TOKEN as usize;
+
+    while responses.len() > 1 {
+        // consecutive responses, packed into groups that fit
+        let mut groups: Vec<Vec<String>> = Vec::new();
+        let mut size = 0;
+        for response in responses {
+            let length = response.chars().count() + 20;
+            match groups.last_mut() {
+                Some(group) if size + length <= budget => group.push(response),
+                _ => {
+                    groups.push(vec![response]);
+                    size = 0;
+                }
+            }
+            size += length;
+        }
+        if groups.iter().all(|group| group.len() == 1) {
+            // no two responses fit in one request
+            let parts: Vec<String> = groups.into_iter().flatten().collect();
+            return Ok(parts.join("\n\n"));
+        }
+        let merges = groups.into_iter().map(|group| {
+            let merge_prompt = &merge_prompt;
+            async move {
+                if group.len() == 1 {
+                    return Ok(group.into_iter().next().unwrap_or_default());
+                }
+                let parts: Vec<String> = group
+                    .iter()
+                    .enumerate()
+                    .map(|(index, response)| f!("**Part {}**\n\n{}", index + 1, response.trim()))
+                    .collect();
+                let mut context = Context::default();
+                context.insert("parts", &parts.join("\n\n"));
+                let mut merge_prompt = merge_prompt.clone();
+                merge_prompt.user_prompt = Prompt::new("", CHUNK_MERGE_USER_PROMPT, ResponseBudget::Unlimited).substitute(&context)?.user_prompt;
+                driver.chat_smart(merge_prompt).await
+            }
+        });
+        responses = future::try_join_all(merges).await?;
+    }
+    Ok(responses.pop().unwrap_or_default())
+}
+
+#[cfg(test)]
+mod chunk_tests {
+    use super::*;
+    use crate::ai::api::mock::MockDriver;
+
+    #[test]
+    fn test_split_text_prefers_natural_boundaries() {
+        let text = "First paragraph is here.\n\nSecond one. It has two sentences.\n\nThird.";
+        let chunks = split_text(text, 8);
+        assert_eq!(chunks, vec!["First paragraph is here.", "Second one.", "It has two sentences.\n\nThird."]);
+        let long_word = "x".repeat(10);
+        assert_eq!(split_text(&long_word, 1), vec!["xxxx", "xxxx", "xx"]);
+    }
+
+    #[tokio::test]
+    async fn test_chat_chunked_merges_in_groups() {
+        let mock = MockDriver::new().with_default_response("Notes on one part of the lecture.");
+        let driver = AIDriver::new_mock(mock.clone());
+        let prompt = Prompt::new("You take notes.", "Make notes:\n\n[text]", ResponseBudget::Unlimited);
+        let mut context = Context::default();
+        let transcript: Vec<String> = (0..40).map(|i| f!("Paragraph {} of the lecture transcript.", i)).collect();
+        context.insert("text", &transcript.join("\n\n"));
+
+        chat_chunked(&driver, &prompt, &context, 200).await.unwrap();
+        let prompts = mock.prompts();
+        assert!(prompts.iter().all(|prompt| prompt.estimated_tokens() <= 200));
+        let chunks = prompts.iter().filter(|prompt| prompt.user_prompt.starts_with("Make notes")).count();
+        assert!(chunks > 1);
+        assert!(prompts[chunks].user_prompt.contains("**Part 1**\n\nNotes on one part of the lecture."));
+        assert!(prompts.iter().skip(chunks).all(|prompt| prompt.system_prompt == "You take notes."));
+    }
+}
@@ -4,37 +4,88 @@
 //!
 //! @public api
 //!
+//! @public chunk
+//!
+//! @public outline
+//!
 //! @public prompt
 //!
+//! @public questions
+//!
+//! @public retrieval
+//!
+//! @public structured
+//!
 //! @public generate_file
 //!
+//! @public skeleton
+//!
+//! @public generate_file_with_skeleton
+//!
+//! @public generate_file_from_template
+//!
+//! @public generate_files
+//!
 //! @public generate_file_and_title
 //!
+//! @public generate_file_from_audio
+//!
 //! @public merge_files
+//!
+//! @public MergedFiles
+//!
+//! @public summarize_cluster
+//!
+//! @public summarize
+//!
+//! @public SummaryPreset
+//!
+//! @public SummaryTarget
+//!
+//! @public transform_section
+//!
+//! @public NOTE_STYLE_SYSTEM_PROMPT
 
 // std imports
 use std::path::PathBuf;
 
 // third-party imports
 use futures::future;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
 
 // first-party imports
+use crate::batch::{BatchReport, BatchStatus, CancellationToken, Checkpoint};
 use crate::file::mdfile::MDFile;
+use crate::file::naming::{resolve_collision, CollisionStrategy, NamingPolicy};
+use crate::file::template::Template;
+use crate::file::vault::cluster::NoteCluster;
+use crate::file::vault::Vault;
 use crate::prelude::*;
 
 // module imports
+use api::audio::Audio;
 use api::AIDriver;
-use prompt::{Context, Prompt};
+use prompt::{Context, Prompt, ResponseBudget};
+use skeleton::NoteSkeleton;
 
 // submodules
 pub mod api;
+pub mod chunk;
+pub mod outline;
 pub mod prompt;
+pub mod questions;
+pub mod retrieval;
+pub mod skeleton;
+pub mod structured;
 
 
 /// Generate a file from a prompt and context
 ///
 /// This function takes a prompt and a context and generates a file from the prompt. The prompt is substituted with the context and then passed to the AI model to generate the file. The file is then converted to a `crate::file::File` and returned.
 ///
+/// If the driver knows its prompt size limit and the substituted prompt exceeds it, the longest context value is split into chunks, a note is generated for each chunk, and the notes are merged (see `chunk::chat_chunked`).
+///
 /// # Arguments
 /// @param driver: &AIDriver - The AI driver to use for generating the file
 /// @param prompt: Prompt - The prompt to generate the file from
@@ -50,12 +101,12 @@ pub mod prompt;
 ///
 /// use obsidian_driver::ai::generate_file;
 /// use obsidian_driver::ai::api::AIDriver;
-/// use obsidian_driver::ai::prompt::{Prompt, Context};
+/// use obsidian_driver::ai::prompt::{Prompt, ResponseBudget, Context};
 ///
 /// async fn generate_file_example() {
 /// 	let openai_config_path = PathBuf::from(".openai_config.json");
 /// 	let driver = AIDriver::new_openai_from_config_path(openai_config_path).await.unwrap();
-/// 	let prompt: Prompt = Prompt::new("You are a helpful assistant", "Summarize the following text in your own words:\n\n$text$", 512);
+/// 	let prompt: Prompt = Prompt::new("You are a helpful assistant", "Summarize the following text in your own words:\n\n[text]", ResponseBudget::Tokens(128));
 /// 	let mut context = Context::default();
 /// 	context.insert("text", "This is a test text, it could be anything, even the entire works of Shakespeare");
 /// 	let prompt: Prompt = prompt.substitute(&context).unwrap();
@@ -65,25 +116,214 @@ pub mod prompt;
 /// ```
 /// @public
 pub async fn generate_file(driver: &AIDriver, prompt: Prompt, context: Context, title: String, output_folder: PathBuf) -> Result<crate::file::File> {
-    let prompt: Prompt = prompt.substitute(&context)?;
-    let file: String = driver.chat_smart(prompt).await?;
-    let mdfile: MDFile = MDFile::from_string(file);
+    let file: String = match driver.max_input_tokens() {
+        Some(max_input_tokens) => chunk::chat_chunked(driver, &prompt, &context, max_input_tokens).await?,
+        None => driver.chat_smart(prompt.substitute(&context)?).await?,
+    };
+    let mdfile: MDFile = MDFile::parse(&file)?;
     let path = output_folder.join(title);
     let file = crate::file::File::from_mdfile(path, mdfile);
     Ok(file)
 
 }
 
+/// Generate a file from a prompt and context that contains every heading of a skeleton
+///
+/// This function works like `generate_file`, but the skeleton's headings are stacked onto the system prompt, and if the generated file is still missing any of them, a follow-up call writes the missing sections (see `skeleton::enforce_skeleton`).
+///
+/// # Arguments
+/// @param driver: &AIDriver - The AI driver to use for generating the file
+/// @param prompt: Prompt - The prompt to generate the file from
+/// @param context: Context - The context to substitute into the prompt
+/// @param title: String - The title of the file
+/// @param output_folder: PathBuf - The output folder to save the file in
+/// @param skeleton: &NoteSkeleton - The headings the file must contain
+/// @returns Result<crate::file::File> - The generated file
+///
+/// # Example
+///
+/// ```
+/// use std::path::PathBuf;
+///
+/// use obsidian_driver::ai::generate_file_with_skeleton;
+/// use obsidian_driver::ai::api::AIDriver;
+/// use obsidian_driver::ai::prompt::{Prompt, ResponseBudget, Context};
+/// use obsidian_driver::ai::skeleton::NoteSkeleton;
+///
+/// async fn generate_file_with_skeleton_example() {
+/// 	let openai_config_path = PathBuf::from(".openai_config.json");
+/// 	let driver = AIDriver::new_openai_from_config_path(openai_config_path).await.unwrap();
+/// 	let prompt: Prompt = Prompt::new("You are a helpful assistant", "Make lecture notes from the following transcript:\n\n[text]", ResponseBudget::Unlimited);
+/// 	let mut context = Context::default();
+/// 	context.insert("text", "This is a test text, it could be anything, even the entire works of Shakespeare");
+/// 	let skeleton = NoteSkeleton::new(&["## Summary", "## Takeaways"]);
+///
+/// 	let file = generate_file_with_skeleton(&driver, prompt, context, "test.md".to_string(), PathBuf::from("output"), &skeleton).await.unwrap();
+/// }
+/// ```
+/// @public
+pub async fn generate_file_with_skeleton(driver: &AIDriver, prompt: Prompt, context: Context, title: String, output_folder: PathBuf, skeleton: &NoteSkeleton) -> Result<crate::file::File> {
+    let prompt: Prompt = prompt.stack_system_prompt(&skeleton.instructions());
+    let mut file = generate_file(driver, prompt, context, title, output_folder).await?;
+    if let Some(mdfile) = file.get_mdfile_mut() {
+        skeleton::enforce_skeleton(driver, mdfile, skeleton).await?;
+    }
+    Ok(file)
+}
+
+/// Generate a file from a prompt and context that follows a note template
+///
+/// The template's headings are enforced like `generate_file_with_skeleton`, and the template's front matter, with its placeholders filled from the context and title, is added to the generated file for every key the model did not write itself.
+///
+/// # Arguments
+/// @param driver: &AIDriver - The AI driver to use for generating the file
+/// @param prompt: Prompt - The prompt to generate the file from
+/// @param context: Context - The context to substitute into the prompt and the template
+/// @param title: String - The title of the file
+/// @param output_folder: PathBuf - The output folder to save the file in
+/// @param template: &Template - The template the file must follow
+/// @returns Result<crate::file::File> - The generated file
+///
+/// # Example
+///
+/// ```
+/// use std::path::PathBuf;
+///
+/// use obsidian_driver::ai::generate_file_from_template;
+/// use obsidian_driver::ai::api::AIDriver;
+/// use obsidian_driver::ai::prompt::{Prompt, ResponseBudget, Context};
+/// use obsidian_driver::file::vault::Vault;
+///
+/// async fn generate_file_from_template_example() {
+/// 	let openai_config_path = PathBuf::from(".openai_config.json");
+/// 	let driver = AIDriver::new_openai_from_config_path(openai_config_path).await.unwrap();
+/// 	let vault = Vault::from_path(PathBuf::from("vault")).unwrap();
+/// 	let template = vault.get_template("Lecture").unwrap();
+/// 	let prompt: Prompt = Prompt::new("You are a helpful assistant", "Make lecture notes from the following transcript:\n\n[text]", ResponseBudget::Unlimited);
+/// 	let mut context = Context::default();
+/// 	context.insert("text", "This is a test text, it could be anything, even the entire works of Shakespeare");
+///
+/// 	let file = generate_file_from_template(&driver, prompt, context, "Lecture 1.md".to_string(), PathBuf::from("output"), &template).await.unwrap();
+/// }
+/// ```
+/// @public
+pub async fn generate_file_from_template(driver: &AIDriver, prompt: Prompt, context: Context, title: String, output_folder: PathBuf, template: &Template) -> Result<crate::file::File> {
+    let stem = std::path::Path::new(&title)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| title.clone());
+    let note = template.instantiate(&stem, &context)?;
+    // the headings of the filled-in note, so placeholders in headings are enforced with their values
+    let skeleton = NoteSkeleton::from_mdfile(&note);
+    let mut file = generate_file_with_skeleton(driver, prompt, context, title, output_folder, &skeleton).await?;
+    if let (Some(mdfile), Some(serde_yaml::Value::Mapping(yaml))) = (file.get_mdfile_mut(), note.get_yaml()) {
+        for (key, value) in yaml {
+            let Some(key) = key.as_str() else {
+                continue;
+            };
+            if mdfile.get_yaml_key(key).is_none() {
+                mdfile.add_yaml_key(key.to_string(), value.clone());
+            }
+        }
+    }
+    Ok(file)
+}
+
+/// Generate and write a batch of files from one prompt, stopping early if `shutdown` is cancelled
+///
+/// Each job is a title and the context for that file. Files are generated `concurrency` at a time and written to `output_folder` as soon as their group finishes, and their paths are recorded in the checkpoint at `checkpoint_path`. Once `shutdown` is cancelled, requests already in flight are finished, written, and checkpointed, and no new ones are started. Running the batch again with the same checkpoint skips the files that were already written.
+///
+/// # Arguments
+/// @param driver: &AIDriver - The AI driver to use for generating the files
+/// @param prompt: Prompt - The prompt to generate each file from
+/// @param jobs: Vec<(String, Context)> - The title and context of each file
+/// @param output_folder: PathBuf - The output folder to save the files in
+/// @param shutdown: &CancellationToken - Cancel to stop after the in-flight requests
+/// @param checkpoint_path: &PathBuf - The checkpoint file
+/// @param concurrency: usize - The number of requests in flight at once
+/// @returns Result<BatchReport> - The output paths that were written, failed, or not started
+///
+/// # Example
+///
+/// ```
+/// use std::path::PathBuf;
+///
+/// use obsidian_driver::ai::generate_files;
+/// use obsidian_driver::ai::api::AIDriver;
+/// use obsidian_driver::ai::prompt::{Prompt, ResponseBudget, Context};
+/// use obsidian_driver::batch::CancellationToken;
+///
+/// async fn generate_files_example() {
+/// 	let openai_config_path = PathBuf::from(".openai_config.json");
+/// 	let driver = AIDriver::new_openai_from_config_path(openai_config_path).await.unwrap();
+/// 	let prompt: Prompt = Prompt::new("You are a helpful assistant", "Make lecture notes from the following transcript:\n\n[text]", ResponseBudget::Unlimited);
+/// 	let mut context = Context::default();
+/// 	context.insert("text", "This is a test text, it could be anything, even the entire works of Shakespeare");
+/// 	let jobs = vec![("Lecture 1.md".to_string(), context)];
+///
+/// 	let shutdown = CancellationToken::new();
+/// 	let report = generate_files(&driver, prompt, jobs, PathBuf::from("output"), &shutdown, &PathBuf::from("checkpoint.json"), 4).await.unwrap();
+/// }
+/// ```
+/// @public
+pub async fn generate_files(driver: &AIDriver, prompt: Prompt, jobs: Vec<(String, Context)>, output_folder: PathBuf, shutdown: &CancellationToken, checkpoint_path: &PathBuf, concurrency: usize) -> Result<BatchReport> {
+    let mut checkpoint = Checkpoint::load(checkpoint_path)?;
+    let jobs: Vec<(String, Context)> = jobs
+        .into_iter()
+        .filter(|(title, _)| !checkpoint.is_complete(&output_folder.join(title)))
+        .collect();
+
+    let mut report = BatchReport::default();
+    let mut started = 0;
+    for group in jobs.chunks(concurrency.max(1)) {
+        if shutdown.is_cancelled() {
+            info!(remaining = jobs.len() - started, "batch generation cancelled");
+            report.status = BatchStatus::Cancelled;
+            break;
+        }
+        started += group.len();
+        let futures = group.iter().map(|(title, context)| {
+            generate_file(driver, prompt.clone(), context.clone(), title.clone(), output_folder.clone())
+        });
+        let results = future::join_all(futures).await;
+        for ((title, _), result) in group.iter().zip(results) {
+            let path = output_folder.join(title);
+            let written = result.and_then(|file| {
+                std::fs::create_dir_all(&output_folder)?;
+                file.write()
+            });
+            match written {
+                Ok(()) => {
+                    info!(path = %path.display(), "file generated");
+                    checkpoint.complete(path.clone());
+                    report.completed.push(path);
+                }
+                Err(e) => {
+                    warn!(path = %path.display(), error = %e, "file generation failed");
+                    report.failed.push((path, e.to_string()))
+                }
+            }
+        }
+        checkpoint.save(checkpoint_path)?;
+    }
+    report.remaining = jobs[started..].iter().map(|(title, _)| output_folder.join(title)).collect();
+    Ok(report)
+}
+
 /// Generate a file and title from a file prompt and title prompt
 ///
 /// This function takes a file prompt and a title prompt and generates a file and title from the prompts. The prompts are substituted with the context and then passed to the AI model to generate the file and title. The file is then converted to a `crate::file::File` and returned.
 ///
+/// The generated title, without quotes or backticks around it, is turned into a file name with `naming`, which follows the vault's conventions and is valid on every file system, and the file is given the `.md` extension. If a file of that name already exists in the output folder, `collisions` decides whether the name is changed, the call fails, or the file is overwritten when written.
+///
 /// # Arguments
 /// @param driver: &AIDriver - The AI driver to use for generating the file
 /// @param file_prompt: Prompt - The prompt to generate the file from
 /// @param title_prompt: Prompt - The prompt to generate the title from
 /// @param context: Context - The context to substitute into the prompts
 /// @param output_folder: PathBuf - The output folder to save the file in
+/// @param naming: &NamingPolicy - How the title is turned into a file name, e.g. `Vault::get_naming_policy`
+/// @param collisions: CollisionStrategy - What to do if the output file already exists
 /// @returns Result<crate::file::File> - The generated file
 ///
 /// # Example
@@ -92,21 +332,22 @@ pub async fn generate_file(driver: &AIDriver, prompt: Prompt, context: Context,
 ///
 /// use obsidian_driver::ai::generate_file_and_title;
 /// use obsidian_driver::ai::api::AIDriver;
-/// use obsidian_driver::ai::prompt::{Prompt, Context};
+/// use obsidian_driver::ai::prompt::{Prompt, ResponseBudget, Context};
+/// use obsidian_driver::file::naming::{CollisionStrategy, NamingPolicy};
 ///
 /// async fn generate_file_and_title_example() {
 /// 	let openai_config_path = PathBuf::from(".openai_config.json");
 /// 	let driver = AIDriver::new_openai_from_config_path(openai_config_path).await.unwrap();
-/// 	let file_prompt: Prompt = Prompt::new("You are a helpful assistant", "Summarize the following text in your own words:\n\n$text$", 512);
-/// 	let title_prompt: Prompt = Prompt::new("You are a helpful assistant", "Provide a title for the summary", 64);
+/// 	let file_prompt: Prompt = Prompt::new("You are a helpful assistant", "Summarize the following text in your own words:\n\n[text]", ResponseBudget::Tokens(128));
+/// 	let title_prompt: Prompt = Prompt::new("You are a helpful assistant", "Provide a title for the summary", ResponseBudget::Tokens(16));
 /// 	let mut context = Context::default();
 /// 	context.insert("text", "This is a test text, it could be anything, even the entire works of Shakespeare");
 ///
-/// 	let file = generate_file_and_title(&driver, file_prompt, title_prompt, context, PathBuf::from("output")).await.unwrap();
+/// 	let file = generate_file_and_title(&driver, file_prompt, title_prompt, context, PathBuf::from("output"), &NamingPolicy::default(), CollisionStrategy::NumericSuffix).await.unwrap();
 /// }
 /// ```
 /// @public
-pub async fn generate_file_and_title(driver: &AIDriver, file_prompt: Prompt, title_prompt: Prompt, context: Context, output_folder: PathBuf) -> Result<crate::file::File> {
+pub async fn generate_file_and_title(driver: &AIDriver, file_prompt: Prompt, title_prompt: Prompt, context: Context, output_folder: PathBuf, naming: &NamingPolicy, collisions: CollisionStrategy) -> Result<crate::file::File> {
     let file_prompt: Prompt = file_prompt.substitute(&context)?;
     let title_prompt: Prompt = title_prompt.substitute(&context)?;
 
@@ -114,35 +355,78 @@ pub async fn generate_file_and_title(driver: &AIDriver, file_prompt: Prompt, tit
     let title = title?;
     let file = file?;
 
-    let mdfile: MDFile = MDFile::from_string(file);
-    let path = output_folder.join(title);
+    let mdfile: MDFile = MDFile::parse(&file)?;
+    // models often quote the title they were asked for
+    let title = naming.name(title.trim().trim_matches(['"', '\'', '`']).trim());
+    let path = resolve_collision(&output_folder.join(f!("{}.md", title)), collisions, |path| path.exists())?;
     let file = crate::file::File::from_mdfile(path, mdfile);
     Ok(file)
 }
 
-const MERGE_SYSTEM_PROMPT: &str = r#"```system
-You are a organized student making lecture notes.
-	I have the following rules:
-	 - Tabs must be used for indentation.
-	 - Text must formatted in markdown.
-	 - Math must be formatted in LaTeX. ($$ …$$ for multiline, $ … $ for inline)
-		 - Prefer to use LaTeX for special characters over raw utf8. THIS IS IMPORTANT, MAKE SURE TO USE $ SIGNS AROUND LATEX
-		 - Wrong Example -> A substring \( α \) of a string \( \omega \) is a sequence of symbols that appears consecutively within \( \omega \)
-		 - Correct Example -> A substring $\mu$ of a string $\omega$ is a sequence of symbols that appears consecutively within $\omega$
-	 - Algorithm Pseudocode must be formatted in the following manor:
-	```pseudo
-		\\begin{algorithm}
-			\\caption{A-Star Search Algorithm}
-			\\begin{algorithmic}
-				\\Procedure{AStar}{$Graph, start, goal$}
-					\\State …
-					…
-				\\EndProcedure
-			\\end{algorithmic}
-		\\end{algorithm}
-	```
-	 - I must avoid formatting with inline code blocks: `code block here`, and prefer to use multiline code blocks
-```"#;
+/// Generate a file from a recording
+///
+/// This function transcribes the recording with `AIDriver::transcribe`, puts the transcript in the context under `text`, and generates the file with `generate_file`, so a long lecture is split into chunks like any other long transcript.
+///
+/// # Arguments
+/// @param driver: &AIDriver - The AI driver to use for transcribing and generating the file
+/// @param prompt: Prompt - The prompt to generate the file from, with a `[text]` placeholder for the transcript
+/// @param context: Context - Values for the other placeholders of the prompt
+/// @param audio: Audio - The recording
+/// @param title: String - The title of the file
+/// @param output_folder: PathBuf - The output folder to save the file in
+/// @returns Result<crate::file::File> - The generated file
+///
+/// # Example
+/// ```
+/// use std::path::{Path, PathBuf};
+///
+/// use obsidian_driver::ai::generate_file_from_audio;
+/// use obsidian_driver::ai::api::AIDriver;
+/// use obsidian_driver::ai::api::audio::Audio;
+/// use obsidian_driver::ai::prompt::{Prompt, ResponseBudget, Context};
+///
+/// async fn generate_file_from_audio_example() {
+/// 	let openai_config_path = PathBuf::from(".openai_config.json");
+/// 	let driver = AIDriver::new_openai_from_config_path(openai_config_path).await.unwrap();
+/// 	let prompt: Prompt = Prompt::new("You are a helpful assistant", "Make lecture notes from the following transcript:\n\n[text]", ResponseBudget::Unlimited);
+/// 	let audio = Audio::from_path(Path::new("Lecture 1.m4a")).unwrap();
+///
+/// 	let file = generate_file_from_audio(&driver, prompt, Context::default(), audio, "Lecture 1.md".to_string(), PathBuf::from("output")).await.unwrap();
+/// }
+/// ```
+/// @public
+pub async fn generate_file_from_audio(driver: &AIDriver, prompt: Prompt, mut context: Context, audio: Audio, title: String, output_folder: PathBuf) -> Result<crate::file::File> {
+    let file_name = audio.file_name.clone();
+    let transcript = driver.transcribe(audio).await?;
+    info!(file = %file_name, characters = transcript.len(), "recording transcribed");
+    context.insert("text", &transcript);
+    generate_file(driver, prompt, context, title, output_folder).await
+}
+
+/// Style rules shared by the note-writing prompts. Stack it onto a task prompt with `Prompt::stack_system_prompt`.
+///
+/// @public
+pub const NOTE_STYLE_SYSTEM_PROMPT: &str = r#"I have the following rules:
+ - Tabs must be used for indentation.
+ - Text must formatted in markdown.
+ - Math must be formatted in LaTeX. ($$ …$$ for multiline, $ … $ for inline)
+	 - Prefer to use LaTeX for special characters over raw utf8. THIS IS IMPORTANT, MAKE SURE TO USE $ SIGNS AROUND LATEX
+	 - Wrong Example -> A substring \( α \) of a string \( \omega \) is a sequence of symbols that appears consecutively within \( \omega \)
+	 - Correct Example -> A substring $\mu$ of a string $\omega$ is a sequence of symbols that appears consecutively within $\omega$
+ - Algorithm Pseudocode must be formatted in the following manor:
+```pseudo
+	\\begin{algorithm}
+		\\caption{A-Star Search Algorithm}
+		\\begin{algorithmic}
+			\\Procedure{AStar}{$Graph, start, goal$}
+				\\State …
+				…
+			\\EndProcedure
+		\\end{algorithmic}
+	\\end{algorithm}
+```
+ - I must avoid formatting with inline code blocks: `code block here`, and prefer to use multiline code blocks"#;
+const MERGE_SYSTEM_PROMPT: &str = "You are a organized student making lecture notes.";
 const MERGE_USER_PROMPT: &str = r#"**User**
 Below are multiple notes from similar topics. Merge these together preserving as much information as you can. Your goal is to keep a logical ordering within each of the sections and between each of the sections.
 
@@ -171,22 +455,503 @@ Since this is transcript, please ignore any details that may be missed due to th
 Also do not ever directly use any non-ASCII characters in these notes.
 "#;
 
-pub async fn merge_files(driver: AIDriver, files: Vec<&crate::file::File>) -> crate::file::mdfile::MDFile {
-    let mut prompt = Prompt::new(MERGE_SYSTEM_PROMPT, MERGE_USER_PROMPT, None);
-	let mut context = Context::default();
+/// The result of `merge_files`.
+///
+/// @public
+#[derive(Clone, Debug, PartialEq)]
+pub struct MergedFiles {
+    pub mdfile: MDFile,
+    // the inputs that were merged, in order
+    pub merged: Vec<PathBuf>,
+    // the inputs that are not markdown notes and were left out
+    pub skipped: Vec<PathBuf>,
+}
+
+/// Merge notes on similar topics into one note
+///
+/// The notes are sent together to the smart model, which merges their sections and keeps every takeaway. Files that are not markdown notes are left out and listed in the result.
+///
+/// # Arguments
+/// @param driver: &AIDriver - The AI driver to use for merging the files
+/// @param files: Vec<&crate::file::File> - The files to merge
+/// @returns Result<MergedFiles> - The merged note. Errors with `Generic` if no file is a markdown note, and with `InvalidFrontmatter` if the reply has invalid front matter.
+///
+/// # Example
+/// ```
+/// use std::path::PathBuf;
+///
+/// use obsidian_driver::ai::merge_files;
+/// use obsidian_driver::ai::api::AIDriver;
+/// use obsidian_driver::file::vault::Vault;
+///
+/// async fn merge_files_example() {
+/// 	let openai_config_path = PathBuf::from(".openai_config.json");
+/// 	let driver = AIDriver::new_openai_from_config_path(openai_config_path).await.unwrap();
+/// 	let vault = Vault::from_path(PathBuf::from("vault")).unwrap();
+/// 	let files = vec![
+/// 		vault.get_file(&PathBuf::from("Lecture 1.md")).unwrap(),
+/// 		vault.get_file(&PathBuf::from("Lecture 1 (recording).md")).unwrap(),
+/// 	];
+///
+/// 	let merged = merge_files(&driver, files).await.unwrap();
+/// 	println!("{}", merged.mdfile);
+/// }
+/// ```
+/// @public
+pub async fn merge_files(driver: &AIDriver, files: Vec<&crate::file::File>) -> Result<MergedFiles> {
+    let prompt = Prompt::new(MERGE_SYSTEM_PROMPT, MERGE_USER_PROMPT, ResponseBudget::Unlimited)
+        .stack_system_prompt(NOTE_STYLE_SYSTEM_PROMPT);
+    let mut merged = Vec::new();
+    let mut skipped = Vec::new();
+    let mut notes = String::new();
+    for file in files {
+        let Some(mdfile) = file.get_mdfile() else {
+            skipped.push(file.get_path().clone());
+            continue;
+        };
+        notes.push_str(&mdfile.to_string());
+        notes.push_str("\n\n");
+        merged.push(file.get_path().clone());
+    }
+    if merged.is_empty() {
+        return Err(Error::Generic("No markdown notes to merge".to_string()));
+    }
+    let mut context = Context::default();
+    context.insert("notes", &notes);
+    let prompt = prompt.substitute(&context)?;
+    let response = driver.chat_smart(prompt).await?;
+    Ok(MergedFiles {
+        mdfile: MDFile::parse(&response)?,
+        merged,
+        skipped,
+    })
+}
+
+const CLUSTER_LABEL_SYSTEM_PROMPT: &str = "You are a librarian naming the topics of a personal knowledge base.";
+const CLUSTER_LABEL_USER_PROMPT: &str = r#"Below are notes on one topic. Reply with a short label for the topic, at most five words, and nothing else: no quotes, no punctuation at the end.
+
+[notes]"#;
+const CLUSTER_SUMMARY_SYSTEM_PROMPT: &str = "You are an organized student writing an overview page for a group of related notes.";
+const CLUSTER_SUMMARY_USER_PROMPT: &str = r#"Below are notes on one topic. Write an overview note for them: start with a short summary of the topic, then explain how the notes relate to each other and what each one covers. Refer to a note by its title as a wikilink, like [[Note Title]]. Do not repeat the notes in full.
+
+[notes]"#;
+
+/// Generate a label and an overview note for a cluster of notes
+///
+/// This function asks the cheap model for a short label and the smart model for an overview of the cluster's members, concurrently. The overview is returned as a file named after the label in `output_folder`, with a `## Notes` section linking every member, so it can be written as a topic hub page. The label is made a safe file name, and a number is added to it if `output_folder` already has a file of that name.
+///
+/// # Arguments
+/// @param driver: &AIDriver - The AI driver to use for generating the file
+/// @param vault: &Vault - The vault the cluster's notes are in
+/// @param cluster: &NoteCluster - The cluster to describe, e.g. from `Vault::cluster_notes`
+/// @param output_folder: PathBuf - The output folder to save the file in
+/// @returns Result<crate::file::File> - The overview note
+///
+/// # Example
+/// ```
+/// use std::path::PathBuf;
+///
+/// use obsidian_driver::ai::summarize_cluster;
+/// use obsidian_driver::ai::api::AIDriver;
+/// use obsidian_driver::file::vault::Vault;
+/// use obsidian_driver::file::vault::cluster::ClusterMethod;
+///
+/// async fn summarize_cluster_example() {
+/// 	let openai_config_path = PathBuf::from(".openai_config.json");
+/// 	let driver = AIDriver::new_openai_from_config_path(openai_config_path).await.unwrap();
+/// 	let vault = Vault::from_path(PathBuf::from("vault")).unwrap();
+///
+/// 	for cluster in vault.cluster_notes(ClusterMethod::KMeans(8)).unwrap() {
+/// 		let file = summarize_cluster(&driver, &vault, &cluster, PathBuf::from("vault/Topics")).await.unwrap();
+/// 		file.write().unwrap();
+/// 	}
+/// }
+/// ```
+/// @public
+pub async fn summarize_cluster(driver: &AIDriver, vault: &Vault, cluster: &NoteCluster, output_folder: PathBuf) -> Result<crate::file::File> {
 	let mut notes = String::new();
-	for (index, file) in files.into_iter().enumerate() {
-		let mdfile = file.get_mdfile();
-		if (mdfile.is_none()) {
+	let mut titles = Vec::new();
+	for path in &cluster.members {
+		let Some(mdfile) = vault.get_file(path).and_then(|file| file.get_mdfile()) else {
 			continue;
-		}
-		let mdfile = mdfile.unwrap();
-		context.insert(&format!("**note {}**", index), &mdfile.to_string());
-		notes.push_str(&mdfile.to_string());
-		notes.push_str("\n\n");
+		};
+		let title = path.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default();
+		notes.push_str(&f!("**{}**\n\n{}\n\n", title, mdfile.get_body()));
+		titles.push(title);
 	}
+	let mut context = Context::default();
 	context.insert("notes", &notes);
-	prompt = prompt.substitute(&context).unwrap();
-	let file = driver.chat_smart(prompt).await.unwrap();
-	MDFile::from_string(file)
-}
\ No newline at end of file
+	let label_prompt = Prompt::new(CLUSTER_LABEL_SYSTEM_PROMPT, CLUSTER_LABEL_USER_PROMPT, ResponseBudget::Tokens(16)).substitute(&context)?;
+	let summary_prompt = Prompt::new(CLUSTER_SUMMARY_SYSTEM_PROMPT, CLUSTER_SUMMARY_USER_PROMPT, ResponseBudget::Unlimited)
+		.stack_system_prompt(NOTE_STYLE_SYSTEM_PROMPT)
+		.substitute(&context)?;
+
+	let (label, summary) = future::join(driver.chat_cheap(label_prompt), driver.chat_smart(summary_prompt)).await;
+	let label = label?;
+	let label = label.trim().trim_matches(['"', '\'', '`', '*']).trim_end_matches('.').trim();
+	let label = if label.is_empty() { cluster.label.as_str() } else { label };
+
+	let mut mdfile = MDFile::parse(&summary?)?;
+	let mut body = mdfile.get_body().trim_end().to_string();
+	body.push_str("\n\n## Notes\n");
+	for title in titles {
+		body.push_str(&f!("- [[{}]]\n", title));
+	}
+	mdfile.set_body(body);
+	let name = crate::file::naming::sanitize_filename(label);
+	let path = resolve_collision(&output_folder.join(f!("{}.md", name)), CollisionStrategy::NumericSuffix, |path| path.exists())?;
+	Ok(crate::file::File::from_mdfile(path, mdfile))
+}
+
+const SUMMARY_SYSTEM_PROMPT: &str = "You are an organized student summarizing your own notes.";
+const SUMMARY_PARAGRAPH_PROMPT: &str = r#"Summarize the note below in one paragraph of at most five sentences. Reply with the paragraph only.
+
+[note]"#;
+const SUMMARY_BULLETS_PROMPT: &str = r#"List the key takeaways of the note below as three to seven markdown bullet points of one sentence each. Reply with the list only.
+
+[note]"#;
+const SUMMARY_ELI5_PROMPT: &str = r#"Explain the main idea of the note below in one short paragraph, in plain words a child could follow, without jargon or formulas. Reply with the paragraph only.
+
+[note]"#;
+
+/// The kind of summary `summarize` writes.
+///
+/// @public
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SummaryPreset {
+    /// One paragraph of at most five sentences.
+    Paragraph,
+    /// Three to seven bullet points.
+    Bullets,
+    /// One paragraph in plain words, explained like to a five-year-old.
+    Eli5,
+}
+
+impl SummaryPreset {
+    /// The prompt of the preset, with a `[note]` placeholder.
+    ///
+    /// @private
+    fn prompt(&self) -> Prompt {
+        let (user_prompt, budget) = match self {
+            SummaryPreset::Paragraph => (SUMMARY_PARAGRAPH_PROMPT, ResponseBudget::Tokens(300)),
+            SummaryPreset::Bullets => (SUMMARY_BULLETS_PROMPT, ResponseBudget::Tokens(400)),
+            SummaryPreset::Eli5 => (SUMMARY_ELI5_PROMPT, ResponseBudget::Tokens(300)),
+        };
+        Prompt::new(SUMMARY_SYSTEM_PROMPT, user_prompt, budget).stack_system_prompt(NOTE_STYLE_SYSTEM_PROMPT)
+    }
+}
+
+/// Where a summary is written in a note.
+///
+/// # Example
+/// ```
+/// use obsidian_driver::ai::SummaryTarget;
+/// use obsidian_driver::file::mdfile::MDFile;
+///
+/// let mut mdfile = MDFile::from_string("# Cells\n\nCells divide.".to_string());
+/// SummaryTarget::Frontmatter("summary".to_string()).write(&mut mdfile, "Cells divide.");
+/// SummaryTarget::Section("Summary".to_string()).write(&mut mdfile, "Cells divide.");
+/// assert_eq!(mdfile.to_string(), "---\nsummary: Cells divide.\n---\n# Cells\n\nCells divide.\n\n## Summary\n\nCells divide.\n");
+/// ```
+/// @public
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SummaryTarget {
+    /// A front matter key, e.g. `summary`.
+    Frontmatter(String),
+    /// A section with this heading, replaced if it exists and appended otherwise.
+    Section(String),
+}
+
+impl SummaryTarget {
+    /// Write a summary to a note, replacing an earlier summary at the same place.
+    ///
+    /// # Arguments
+    /// @param mdfile: &mut MDFile - The note
+    /// @param summary: &str - The summary, e.g. from `summarize`
+    pub fn write(&self, mdfile: &mut MDFile, summary: &str) {
+        match self {
+            SummaryTarget::Frontmatter(key) => mdfile.add_yaml_key(key.clone(), serde_yaml::Value::from(summary.trim())),
+            SummaryTarget::Section(heading) => mdfile.set_section(heading, summary),
+        }
+    }
+}
+
+/// Summarize a note with a preset
+///
+/// The body of the note, without its `%% comments %%` and front matter, is sent to the smart model. Notes longer than the driver's prompt size limit are summarized in chunks whose summaries are merged (see `chunk::chat_chunked`). Write the summary to the note with `SummaryTarget::write`.
+///
+/// # Arguments
+/// @param driver: &AIDriver - The AI driver to use for summarizing
+/// @param mdfile: &MDFile - The note to summarize
+/// @param preset: SummaryPreset - The kind of summary
+/// @returns Result<String> - The summary, trimmed
+///
+/// # Example
+/// ```
+/// use std::path::PathBuf;
+///
+/// use obsidian_driver::ai::{summarize, SummaryPreset, SummaryTarget};
+/// use obsidian_driver::ai::api::AIDriver;
+/// use obsidian_driver::file::vault::Vault;
+///
+/// async fn summarize_example() {
+/// 	let openai_config_path = PathBuf::from(".openai_config.json");
+/// 	let driver = AIDriver::new_openai_from_config_path(openai_config_path).await.unwrap();
+/// 	let mut vault = Vault::from_path(PathBuf::from("vault")).unwrap();
+/// 	let path = PathBuf::from("Lecture 1.md");
+///
+/// 	let mdfile = vault.get_file(&path).unwrap().get_mdfile().unwrap();
+/// 	let summary = summarize(&driver, mdfile, SummaryPreset::Bullets).await.unwrap();
+/// 	let mdfile = vault.get_file_mut(&path).unwrap().get_mdfile_mut().unwrap();
+/// 	SummaryTarget::Section("Summary".to_string()).write(mdfile, &summary);
+/// 	vault.write_file(&path).unwrap();
+/// }
+/// ```
+/// @public
+pub async fn summarize(driver: &AIDriver, mdfile: &MDFile, preset: SummaryPreset) -> Result<String> {
+    let prompt = preset.prompt();
+    let mut context = Context::default();
+    context.insert("note", mdfile.strip_comments().get_body().trim());
+    let summary = match driver.max_input_tokens() {
+        Some(max_input_tokens) => chunk::chat_chunked(driver, &prompt, &context, max_input_tokens).await?,
+        None => driver.chat_smart(prompt.substitute(&context)?).await?,
+    };
+    Ok(summary.trim().to_string())
+}
+
+const TRANSFORM_SYSTEM_PROMPT: &str = "You are an organized student editing one section of your notes.";
+const TRANSFORM_USER_PROMPT: &str = r#"Below is one section of a note and the outline of the whole note. Rewrite the section as the instruction says, and keep everything the instruction does not ask to change. Reply with the new content of the section only, without its heading.
+
+**Instruction**
+[instruction]
+
+**Outline**
+[outline]
+
+**Section**
+[section]"#;
+
+/// Rewrite one section of a note with an instruction
+///
+/// Only the section, the instruction and the outline of the note's headings are sent to the smart model, and the response replaces the content under the heading, so the rest of the note, including manual edits, is left as it is. A heading line repeated at the start of the response is dropped.
+///
+/// # Arguments
+/// @param driver: &AIDriver - The AI driver to use for rewriting
+/// @param mdfile: &mut MDFile - The note; the first section with the heading is rewritten
+/// @param heading: &str - The heading text, without the leading `#`s
+/// @param instruction: &str - What to change, e.g. "Add a worked example"
+/// @returns Result<String> - The new content of the section. Errors with `SectionNotFound` if the note has no such heading.
+///
+/// # Example
+/// ```
+/// use std::path::PathBuf;
+///
+/// use obsidian_driver::ai::transform_section;
+/// use obsidian_driver::ai::api::AIDriver;
+/// use obsidian_driver::file::vault::Vault;
+///
+/// async fn transform_section_example() {
+/// 	let openai_config_path = PathBuf::from(".openai_config.json");
+/// 	let driver = AIDriver::new_openai_from_config_path(openai_config_path).await.unwrap();
+/// 	let mut vault = Vault::from_path(PathBuf::from("vault")).unwrap();
+/// 	let path = PathBuf::from("Lecture 1.md");
+///
+/// 	let mdfile = vault.get_file_mut(&path).unwrap().get_mdfile_mut().unwrap();
+/// 	transform_section(&driver, mdfile, "Takeaways", "Turn the takeaways into a table").await.unwrap();
+/// 	vault.write_file(&path).unwrap();
+/// }
+/// ```
+/// @public
+pub async fn transform_section(driver: &AIDriver, mdfile: &mut MDFile, heading: &str, instruction: &str) -> Result<String> {
+    let section = mdfile.get_section(heading).ok_or(Error::SectionNotFound(heading.to_string()))?;
+    let heading_line = mdfile.get_body()[section.range.start..section.content_range.start].trim().to_string();
+    let outline: Vec<String> = mdfile
+        .get_sections()
+        .into_iter()
+        .map(|other| {
+            let line = f!("{} {}", "#".repeat(other.level), other.heading);
+            if other.range == section.range { f!("{} (this section)", line) } else { line }
+        })
+        .collect();
+    let mut context = Context::default();
+    context.insert("instruction", instruction.trim());
+    context.insert("outline", &outline.join("\n"));
+    context.insert("section", mdfile.get_body()[section.range.clone()].trim());
+    let prompt = Prompt::new(TRANSFORM_SYSTEM_PROMPT, TRANSFORM_USER_PROMPT, ResponseBudget::Unlimited)
+        .stack_system_prompt(NOTE_STYLE_SYSTEM_PROMPT)
+        .substitute(&context)?;
+    let response = driver.chat_smart(prompt).await?;
+    let response = response.trim();
+    let content = match response.split_once('\n') {
+        Some((first, rest)) if first.trim() == heading_line => rest.trim(),
+        _ if response == heading_line => "",
+        _ => response,
+    };
+    mdfile.set_section(heading, content);
+    Ok(content.to_string())
+}
+
+#[cfg(test)]
+mod ai_tests {
+    use super::*;
+    use crate::ai::api::mock::MockDriver;
+
+    #[tokio::test]
+    async fn test_generate_file_and_title_sanitizes_and_dedups_title() {
+        let driver = AIDriver::new_mock(MockDriver::new().with_responses(["# Notes", "\"Cells: Division?\""]));
+        let file_prompt = Prompt::new("system", "Notes on [text]", ResponseBudget::Unlimited);
+        let title_prompt = Prompt::new("system", "Title for [text]", ResponseBudget::Unlimited);
+        let mut context = Context::default();
+        context.insert("text", "cells");
+
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("Cells - Division.md"), "# Existing").unwrap();
+
+        let output_folder = dir.path().to_path_buf();
+        let file = generate_file_and_title(&driver, file_prompt, title_prompt, context, output_folder.clone(), &NamingPolicy::default(), CollisionStrategy::NumericSuffix).await.unwrap();
+        assert_eq!(file.get_path(), &dir.path().join("Cells - Division 1.md"));
+
+        let driver = AIDriver::new_mock(MockDriver::new().with_responses(["# Notes", "`Cells: Division?`"]));
+        let naming = NamingPolicy::default().with_case(crate::file::naming::NameCase::Kebab);
+        let file = generate_file_and_title(&driver, Prompt::new("", "Notes", ResponseBudget::Unlimited), Prompt::new("", "Title", ResponseBudget::Unlimited), Context::default(), output_folder, &naming, CollisionStrategy::Error).await.unwrap();
+        assert_eq!(file.get_path(), &dir.path().join("cells-division.md"));
+    }
+
+    #[tokio::test]
+    async fn test_generate_file_from_template_fills_heading_placeholders() {
+        let mock = MockDriver::new().with_responses(["# Lecture 1\n\n## BIOL 200 Summary\n\nCells divide."]);
+        let driver = AIDriver::new_mock(mock.clone());
+        let template = Template::new("Lecture", MDFile::from_string("---\ncourse: \"{{course}}\"\n---\n# {{title}}\n\n## {{course}} Summary\n".to_string()));
+        let prompt = Prompt::new("system", "Notes on [course]", ResponseBudget::Unlimited);
+        let mut context = Context::default();
+        context.insert("course", "BIOL 200");
+
+        let file = generate_file_from_template(&driver, prompt, context, "Lecture 1.md".to_string(), PathBuf::from("out"), &template).await.unwrap();
+        let mdfile = file.get_mdfile().unwrap();
+        assert_eq!(mdfile.get_body(), "# Lecture 1\n\n## BIOL 200 Summary\n\nCells divide.");
+        assert_eq!(mdfile.get_yaml_key("course"), Some(&serde_yaml::Value::from("BIOL 200")));
+        // the heading was already there, so no second request was needed to add it
+        assert_eq!(mock.prompts().len(), 1);
+        assert!(mock.prompts()[0].extra_system_prompts[0].contains("## BIOL 200 Summary"));
+    }
+
+    #[tokio::test]
+    async fn test_generate_file_from_audio() {
+        let mock = MockDriver::new().with_transcript("Today we cover mitosis.").with_responses(["# Mitosis"]);
+        let driver = AIDriver::new_mock(mock.clone());
+        let prompt = Prompt::new("system", "Notes on [text]", ResponseBudget::Unlimited);
+        let audio = Audio::from_bytes("lecture.mp3", vec![0; 4]);
+
+        let file = generate_file_from_audio(&driver, prompt.clone(), Context::default(), audio.clone(), "Mitosis.md".to_string(), PathBuf::from("out")).await.unwrap();
+        assert_eq!(file.get_path(), &PathBuf::from("out/Mitosis.md"));
+        assert_eq!(mock.prompts()[0].user_prompt, "Notes on Today we cover mitosis.");
+
+        let driver = AIDriver::new_mock(MockDriver::new().with_default_response("# Notes"));
+        let failed = generate_file_from_audio(&driver, prompt, Context::default(), audio, "Mitosis.md".to_string(), PathBuf::from("out")).await;
+        assert!(matches!(failed, Err(Error::NotSupported(_))));
+    }
+
+    #[tokio::test]
+    async fn test_summarize_presets() {
+        let mock = MockDriver::new().with_responses(["  - Cells divide.\n- DNA is copied.\n"]);
+        let driver = AIDriver::new_mock(mock.clone());
+        let mut mdfile = MDFile::from_string("---\ntags: [bio]\n---\n# Mitosis\n\nCells divide. %%todo%%".to_string());
+
+        let summary = summarize(&driver, &mdfile, SummaryPreset::Bullets).await.unwrap();
+        assert_eq!(summary, "- Cells divide.\n- DNA is copied.");
+        let prompt = &mock.prompts()[0];
+        assert!(prompt.user_prompt.starts_with("List the key takeaways"));
+        assert!(prompt.user_prompt.ends_with("\n\n# Mitosis\n\nCells divide."));
+        assert_eq!(prompt.response_budget, ResponseBudget::Tokens(400));
+
+        let target = SummaryTarget::Section("Summary".to_string());
+        target.write(&mut mdfile, &summary);
+        target.write(&mut mdfile, "- Cells divide.");
+        assert_eq!(mdfile.get_body(), "# Mitosis\n\nCells divide. %%todo%%\n\n## Summary\n\n- Cells divide.\n");
+    }
+
+    #[tokio::test]
+    async fn test_summarize_cluster_sanitizes_label() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("mitosis.md"), "# Mitosis").unwrap();
+        std::fs::create_dir(dir.path().join("Topics")).unwrap();
+        std::fs::write(dir.path().join("Topics/Cell-Division.md"), "# Existing").unwrap();
+        let vault = Vault::from_path(dir.path().to_path_buf()).unwrap();
+        let cluster = NoteCluster { label: "mitosis".to_string(), members: vec![PathBuf::from("mitosis.md")], centroid: vec![0.0] };
+        let driver = AIDriver::new_mock(MockDriver::new().with_responses(["\"Cell/Division\"", "Cells divide."]));
+
+        let file = summarize_cluster(&driver, &vault, &cluster, dir.path().join("Topics")).await.unwrap();
+        assert_eq!(file.get_path(), &dir.path().join("Topics/Cell-Division 1.md"));
+        assert_eq!(file.get_mdfile().unwrap().get_body(), "Cells divide.\n\n## Notes\n- [[mitosis]]\n");
+
+        let driver = AIDriver::new_mock(MockDriver::new().with_responses(["Cells", "---\ntitle: Cells: Division\n---\nCells divide."]));
+        let failed = summarize_cluster(&driver, &vault, &cluster, dir.path().join("Topics")).await;
+        assert!(matches!(failed, Err(Error::InvalidFrontmatter(_))));
+    }
+
+    #[tokio::test]
+    async fn test_transform_section_splices_response() {
+        let mock = MockDriver::new().with_responses(["## Takeaways\n\n| Term | Meaning |\n| --- | --- |"]);
+        let driver = AIDriver::new_mock(mock.clone());
+        let mut mdfile = MDFile::from_string("# Cells\n\nIntro\n\n## Takeaways\n\n- Cells divide.\n\n## Sources\n\n- Book".to_string());
+
+        let content = transform_section(&driver, &mut mdfile, "Takeaways", "Make a table").await.unwrap();
+        assert_eq!(content, "| Term | Meaning |\n| --- | --- |");
+        assert_eq!(
+            mdfile.get_body(),
+            "# Cells\n\nIntro\n\n## Takeaways\n\n| Term | Meaning |\n| --- | --- |\n\n## Sources\n\n- Book"
+        );
+        let prompt = &mock.prompts()[0].user_prompt;
+        assert!(prompt.contains("**Outline**\n# Cells\n## Takeaways (this section)\n## Sources\n"));
+        assert!(prompt.ends_with("**Section**\n## Takeaways\n\n- Cells divide."));
+
+        assert!(matches!(transform_section(&driver, &mut mdfile, "Missing", "Shorten").await, Err(Error::SectionNotFound(_))));
+        assert_eq!(mock.prompts().len(), 1);
+    }
+
+    struct JsonFile;
+
+    impl crate::file::registry::FileType for JsonFile {
+        fn extensions(&self) -> Vec<String> {
+            vec!["mergejson".to_string()]
+        }
+
+        fn parse(&self, contents: &str) -> Result<serde_json::Value> {
+            Ok(serde_json::from_str(contents)?)
+        }
+
+        fn serialize(&self, data: &serde_json::Value) -> Result<String> {
+            Ok(data.to_string())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_merge_files_skips_and_propagates_errors() {
+        crate::file::registry::register_file_type(std::sync::Arc::new(JsonFile));
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.md"), "# A").unwrap();
+        std::fs::write(dir.path().join("b.mergejson"), "{}").unwrap();
+        let vault = Vault::from_path(dir.path().to_path_buf()).unwrap();
+        let note = vault.get_file(&PathBuf::from("a.md")).unwrap();
+        let other = vault.get_file(&PathBuf::from("b.mergejson")).unwrap();
+        let driver = AIDriver::new_mock(MockDriver::new().with_responses(["# Merged"]));
+
+        let merged = merge_files(&driver, vec![note, other]).await.unwrap();
+        assert_eq!(merged.mdfile.get_body(), "# Merged");
+        assert_eq!(merged.merged, vec![note.get_path().clone()]);
+        assert_eq!(merged.skipped, vec![other.get_path().clone()]);
+
+        // the mock has no response left
+        let failed = merge_files(&driver, vec![note]).await;
+        assert!(matches!(failed, Err(Error::InvalidChatResponse(_))));
+        assert!(matches!(merge_files(&driver, vec![other]).await, Err(Error::Generic(_))));
+
+        let driver = AIDriver::new_mock(MockDriver::new().with_responses(["---
+title: Foo: bar
+---
+# Merged"]));
+        assert!(matches!(merge_files(&driver, vec![note]).await, Err(Error::InvalidFrontmatter(_))));
+    }
+}
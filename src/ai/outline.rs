@@ -0,0 +1,296 @@
+//! # obsidian-driver::ai::outline
+//!
+//! This module contains the reorganization of messy notes: the smart model proposes a new heading outline, which can be previewed as a diff and applied by reordering, renaming and re-levelling the existing sections, so the text of the note is moved but never rewritten.
+//!
+//! @public OutlineEntry
+//!
+//! @public OutlinePlan
+//!
+//! @public OutlinePlan::outline
+//!
+//! @public OutlinePlan::reorganize
+//!
+//! @public OutlinePlan::preview
+//!
+//! @public OutlinePlan::apply
+//!
+//! @public propose_outline
+
+// third-party imports
+use serde::{Deserialize, Serialize};
+
+// first-party imports
+use crate::ai::api::AIDriver;
+use crate::ai::prompt::{Context, Prompt, ResponseBudget};
+use crate::ai::structured::{parse_json_response, JSON_SYSTEM_PROMPT};
+use crate::file::mdfile::section::Section;
+use crate::file::mdfile::MDFile;
+use crate::prelude::*;
+
+const OUTLINE_SYSTEM_PROMPT: &str = "You are an organized student restructuring your lecture notes.";
+const OUTLINE_USER_PROMPT: &str = r#"Below is a note whose sections are in a confusing order, e.g. because it was merged from several sources. Propose a clearer outline: order the sections logically, give them clear headings and levels, and add grouping headings where sections belong together. Every current section appears exactly once, named by its current heading in "source". New grouping headings have "source": null. Do not drop, split or merge sections.
+
+Reply with a JSON object of the form {"outline": [{"heading": "...", "level": 2, "source": "..."}]}, in the new order.
+
+**Current outline**
+[outline]
+
+**Note**
+[note]"#;
+
+/// A heading of a proposed outline.
+///
+/// @public
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OutlineEntry {
+    // 1 to 6
+    pub level: usize,
+    pub heading: String,
+    // the current heading whose content goes under this one; None for a new grouping heading
+    #[serde(default)]
+    pub source: Option<String>,
+}
+
+/// A new heading outline for a note, e.g. from `propose_outline`.
+///
+/// A section is a heading and its text up to the next heading of any level. Applying the plan
+/// writes the text before the first heading, then every entry in order with the content of its
+/// source section. Sections the plan leaves out are kept at the end, so no text is lost.
+///
+/// # Example
+/// ```
+/// use obsidian_driver::ai::outline::{OutlineEntry, OutlinePlan};
+/// use obsidian_driver::file::mdfile::MDFile;
+///
+/// let mut mdfile = MDFile::from_string("# Cells\n\n## Meiosis\n\nFour cells.\n\n## Intro\n\nCells divide.\n".to_string());
+/// let entry = |level: usize, heading: &str, source: Option<&str>| OutlineEntry { level, heading: heading.to_string(), source: source.map(str::to_string) };
+/// let plan = OutlinePlan {
+/// 	entries: vec![
+/// 		entry(1, "Cells", Some("Cells")),
+/// 		entry(2, "Introduction", Some("Intro")),
+/// 		entry(2, "Meiosis", Some("Meiosis")),
+/// 	],
+/// };
+/// assert!(plan.preview(&mdfile).contains("+ ## Introduction\n"));
+/// plan.apply(&mut mdfile);
+/// assert_eq!(mdfile.get_body(), "# Cells\n\n## Introduction\n\nCells divide.\n\n## Meiosis\n\nFour cells.\n");
+/// ```
+/// @public
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OutlinePlan {
+    #[serde(rename = "outline")]
+    pub entries: Vec<OutlineEntry>,
+}
+
+impl OutlinePlan {
+    /// The headings of the plan, one per line, e.g. `## Introduction`.
+    ///
+    /// # Arguments
+    /// @returns String
+    pub fn outline(&self) -> String {
+        let lines: Vec<String> = self.entries.iter().map(|entry| heading_line(entry.level, &entry.heading)).collect();
+        lines.join("\n")
+    }
+
+    /// The body of a note reorganized by the plan.
+    ///
+    /// # Arguments
+    /// @param mdfile: &MDFile
+    /// @returns String
+    pub fn reorganize(&self, mdfile: &MDFile) -> String {
+        let body = mdfile.get_body();
+        let sections = Section::parse_all(body);
+        let first = sections.first().map(|section| section.range.start).unwrap_or(body.len());
+        // (level, heading, content, used)
+        let mut chunks: Vec<(usize, String, &str, bool)> = sections
+            .iter()
+            .enumerate()
+            .map(|(i, section)| {
+                let end = sections.get(i + 1).map(|next| next.range.start).unwrap_or(body.len());
+                (section.level, section.heading.clone(), body[section.content_range.start.min(end)..end].trim(), false)
+            })
+            .collect();
+
+        let mut parts: Vec<String> = Vec::new();
+        if !body[..first].trim().is_empty() {
+            parts.push(body[..first].trim().to_string());
+        }
+        let part = |level: usize, heading: &str, content: &str| {
+            if content.is_empty() {
+                heading_line(level, heading)
+            } else {
+                f!("{}\n\n{}", heading_line(level, heading), content)
+            }
+        };
+        for entry in &self.entries {
+            let source = entry.source.as_ref().and_then(|source| {
+                chunks.iter_mut().find(|(_, heading, _, used)| !used && heading == source.trim())
+            });
+            let content = match source {
+                Some(chunk) => {
+                    chunk.3 = true;
+                    chunk.2
+                }
+                None => "",
+            };
+            parts.push(part(entry.level, entry.heading.trim(), content));
+        }
+        for (level, heading, content, _) in chunks.iter().filter(|(_, _, _, used)| !used) {
+            parts.push(part(*level, heading, content));
+        }
+        if parts.is_empty() {
+            return String::new();
+        }
+        f!("{}\n", parts.join("\n\n"))
+    }
+
+    /// A line diff from the body of a note to the body reorganized by the plan.
+    ///
+    /// Lines are prefixed with `  ` if kept, `- ` if removed and `+ ` if added; moved sections
+    /// show as removed at their old place and added at their new one.
+    ///
+    /// # Arguments
+    /// @param mdfile: &MDFile
+    /// @returns String
+    pub fn preview(&self, mdfile: &MDFile) -> String {
+        line_diff(mdfile.get_body(), &self.reorganize(mdfile))
+    }
+
+    /// Reorganize a note by the plan.
+    ///
+    /// # Arguments
+    /// @param mdfile: &mut MDFile
+    pub fn apply(&self, mdfile: &mut MDFile) {
+        let body = self.reorganize(mdfile);
+        mdfile.set_body(body);
+    }
+}
+
+/// Ask the smart model for a clearer heading outline of a note
+///
+/// The current outline and the body of the note are sent, and the model replies with the new outline as JSON. Review the plan with `OutlinePlan::preview` before applying it.
+///
+/// # Arguments
+/// @param driver: &AIDriver - The AI driver to use
+/// @param mdfile: &MDFile - The note to reorganize
+/// @returns Result<OutlinePlan> - Errors with `InvalidChatResponse` if the reply is not an outline
+///
+/// # Example
+/// ```
+/// use std::path::PathBuf;
+///
+/// use obsidian_driver::ai::api::AIDriver;
+/// use obsidian_driver::ai::outline::propose_outline;
+/// use obsidian_driver::file::vault::Vault;
+///
+/// async fn propose_outline_example() {
+/// 	let openai_config_path = PathBuf::from(".openai_config.json");
+/// 	let driver = AIDriver::new_openai_from_config_path(openai_config_path).await.unwrap();
+/// 	let mut vault = Vault::from_path(PathBuf::from("vault")).unwrap();
+/// 	let path = PathBuf::from("Lecture 1.md");
+///
+/// 	let mdfile = vault.get_file_mut(&path).unwrap().get_mdfile_mut().unwrap();
+/// 	let plan = propose_outline(&driver, mdfile).await.unwrap();
+/// 	println!("{}", plan.preview(mdfile));
+/// 	plan.apply(mdfile);
+/// 	vault.write_file(&path).unwrap();
+/// }
+/// ```
+/// @public
+pub async fn propose_outline(driver: &AIDriver, mdfile: &MDFile) -> Result<OutlinePlan> {
+    let outline: Vec<String> = mdfile
+        .get_sections()
+        .into_iter()
+        .map(|section| heading_line(section.level, &section.heading))
+        .collect();
+    let mut context = Context::default();
+    context.insert("outline", &outline.join("\n"));
+    context.insert("note", mdfile.get_body().trim());
+    let prompt = Prompt::new(OUTLINE_SYSTEM_PROMPT, OUTLINE_USER_PROMPT, ResponseBudget::Tokens(2000))
+        .stack_system_prompt(JSON_SYSTEM_PROMPT)
+        .substitute(&context)?;
+    let response = driver.chat_smart(prompt).await?;
+    let mut plan: OutlinePlan = parse_json_response(&response)?;
+    plan.entries.retain(|entry| !entry.heading.trim().is_empty());
+    Ok(plan)
+}
+
+/// A markdown heading line, with the level clamped to 1 to 6.
+///
+/// @private
+fn heading_line(level: usize, heading: &str) -> String {
+    f!("{} {}", "#".repeat(level.clamp(1, 6)), heading)
+}
+
+/// A line diff of two texts, from their longest common subsequence of lines.
+///
+/// @private
+fn line_diff(before: &str, after: &str) -> String {
+    let before: Vec<&str> = before.lines().collect();
+    let after: Vec<&str> = after.lines().collect();
+    // lengths[i][j]: the longest common subsequence of before[i..] and after[j..]
+    let mut lengths = vec![vec![0usize; after.len() + 1]; before.len() + 1];
+    for i in (0..before.len()).rev() {
+        for j in (0..after.len()).rev() {
+            lengths[i][j] = if before[i] == after[j] {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+    let mut diff = String::new();
+    let (mut i, mut j) = (0, 0);
+    while i < before.len() || j < after.len() {
+        if i < before.len() && j < after.len() && before[i] == after[j] {
+            diff.push_str(&f!("  {}\n", before[i]));
+            i += 1;
+            j += 1;
+        } else if j < after.len() && (i == before.len() || lengths[i][j + 1] >= lengths[i + 1][j]) {
+            diff.push_str(&f!("+ {}\n", after[j]));
+            j += 1;
+        } else {
+            diff.push_str(&f!("- {}\n", before[i]));
+            i += 1;
+        }
+    }
+    diff
+}
+
+#[cfg(test)]
+mod outline_tests {
+    use super::*;
+    use crate::ai::api::mock::MockDriver;
+
+    #[tokio::test]
+    async fn test_propose_and_apply_outline() {
+        let mdfile = MDFile::from_string(
+            "Merged from two lectures.\n\n## Anaphase\n\nSplit.\n\n### Detail\n\nFast.\n\n## Prophase\n\nCondense.\n\n## Sources\n\n- Book\n".to_string(),
+        );
+        let response = r#"{"outline": [
+            {"heading": "Phases", "level": 2, "source": null},
+            {"heading": "Prophase", "level": 3, "source": "Prophase"},
+            {"heading": "Anaphase", "level": 3, "source": "Anaphase"},
+            {"heading": "Anaphase details", "level": 4, "source": "Detail"},
+            {"heading": "", "level": 2, "source": "Sources"}
+        ]}"#;
+        let mock = MockDriver::new().with_responses([response]);
+        let driver = AIDriver::new_mock(mock.clone());
+
+        let plan = propose_outline(&driver, &mdfile).await.unwrap();
+        assert_eq!(plan.entries.len(), 4);
+        assert!(mock.prompts()[0].user_prompt.contains("**Current outline**\n## Anaphase\n### Detail\n## Prophase\n## Sources\n"));
+        assert_eq!(plan.outline(), "## Phases\n### Prophase\n### Anaphase\n#### Anaphase details");
+
+        let mut reorganized = mdfile.clone();
+        plan.apply(&mut reorganized);
+        assert_eq!(
+            reorganized.get_body(),
+            "Merged from two lectures.\n\n## Phases\n\n### Prophase\n\nCondense.\n\n### Anaphase\n\nSplit.\n\n#### Anaphase details\n\nFast.\n\n## Sources\n\n- Book\n"
+        );
+        let preview = plan.preview(&mdfile);
+        assert!(preview.starts_with("  Merged from two lectures.\n  \n+ ## Phases\n"));
+        assert!(preview.contains("- ## Prophase\n"));
+        assert!(preview.ends_with("  ## Sources\n  \n  - Book\n"));
+    }
+}
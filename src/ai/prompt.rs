@@ -1,13 +1,35 @@
 //! # obsidian-driver::ai::prompt
 //!
-//! This module contains the Prompt struct and the Context struct.
+//! This module contains the Prompt struct, the Context struct, and the Conversation struct for multi-turn chats.
 //!
 //! @public Prompt
 //!
 //! @public Prompt::new
 //!
+//! @public Prompt::from_file
+//!
+//! @public Prompt::from_markdown
+//!
+//! @public Prompt::placeholders
+//!
+//! @public Prompt::estimated_tokens
+//!
 //! @public Prompt::substitute
 //!
+//! @public Prompt::stack_system_prompt
+//!
+//! @public Prompt::system_messages
+//!
+//! @public Prompt::with_image
+//!
+//! @public ImagePart
+//!
+//! @public ImagePart::url
+//!
+//! @public SystemPromptStacking
+//!
+//! @public ResponseBudget
+//!
 //! @public Context
 //!
 //! @public Context::default
@@ -17,10 +39,17 @@
 //! @public Context::insert
 //!
 //! @public Context::get
+//!
+//! @public Role
+//!
+//! @public Message
+//!
+//! @public Conversation
 
 // std imports
 use std::collections::HashMap;
 use std::fmt::Display;
+use std::path::{Path, PathBuf};
 
 // third-party imports
 use serde::{Serialize, Deserialize};
@@ -29,36 +58,149 @@ use serde::{Serialize, Deserialize};
 use crate::prelude::*;
 
 
-// Any substrings surrounded by $ will be replaced with the value of the key in the context when prompted
+// Any substrings surrounded by [] will be replaced with the value of the key in the context when prompted
 // or return an error if the key is not found
 
 /// The Prompt struct.
 ///
-/// This struct contains the system prompt, user prompt, and the maximum number of characters allowed in the response.
+/// This struct contains the system prompt, any stacked system prompts, the user prompt, and the maximum number of characters allowed in the response.
 ///
 /// # Examples
 /// ```
-/// use obsidian_driver::ai::prompt::Prompt;
+/// use obsidian_driver::ai::prompt::{Prompt, ResponseBudget};
 ///
-/// let prompt = Prompt::new("You are a helpful assistant", "This is a sample prompt", 100);
+/// let prompt = Prompt::new("You are a helpful assistant", "This is a sample prompt", ResponseBudget::Tokens(25));
 /// ```
 ///
 /// ```
-/// use obsidian_driver::ai::prompt::{Prompt, Context};
+/// use obsidian_driver::ai::prompt::{Prompt, ResponseBudget, Context};
 ///
-/// let prompt = Prompt::new("You are a helpful assistant named $name$", "This is a sample prompt", 100);
+/// let prompt = Prompt::new("You are a helpful assistant named [name]", "This is a sample prompt", ResponseBudget::Tokens(25));
 /// let mut context = Context::default();
 /// context.insert("name", "Bob");
 ///
 /// let actual = prompt.substitute(&context).unwrap();
-/// let expected = Prompt::new("You are a helpful assistant named Bob", "This is a sample prompt", 100);
+/// let expected = Prompt::new("You are a helpful assistant named Bob", "This is a sample prompt", ResponseBudget::Tokens(25));
 /// assert_eq!(actual, expected);
 /// ```
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Prompt {
 	pub system_prompt: String,
+	#[serde(default)]
+	pub extra_system_prompts: Vec<String>,
 	pub user_prompt: String,
-	pub max_characters: Option<u32>,
+	#[serde(default)]
+	pub response_budget: ResponseBudget,
+	// the placeholders declared by a prompt file; empty for prompts built in code
+	#[serde(default)]
+	pub variables: Vec<String>,
+	// sent after the user prompt, to models that accept images
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub images: Vec<ImagePart>,
+}
+
+/// An image sent with the user prompt of a Prompt.
+///
+/// # Examples
+/// ```
+/// use obsidian_driver::ai::prompt::ImagePart;
+///
+/// let inline = ImagePart::Base64 { media_type: "image/png".to_string(), data: "iVBORw0KGgo=".to_string() };
+/// assert_eq!(inline.url(), "data:image/png;base64,iVBORw0KGgo=");
+///
+/// let linked = ImagePart::Url("https://example.com/diagram.png".to_string());
+/// assert_eq!(linked.url(), "https://example.com/diagram.png");
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImagePart {
+	/// An image the provider downloads.
+	Url(String),
+	/// An image sent inline, base64 encoded.
+	Base64 { media_type: String, data: String },
+}
+
+impl ImagePart {
+	/// The URL of the image as chat APIs accept it: its URL, or a `data:` URL for an inline image.
+	///
+	/// # Arguments
+	/// @returns String
+	pub fn url(&self) -> String {
+		match self {
+			ImagePart::Url(url) => url.clone(),
+			ImagePart::Base64 { media_type, data } => format!("data:{};base64,{}", media_type, data),
+		}
+	}
+}
+
+/// The number of characters per token assumed when converting between characters and tokens, e.g. when a prompt file sets `max_characters`.
+pub(crate) const CHARACTERS_PER_TOKEN: u32 = 4;
+
+/// Matches the `[key]` placeholders of a prompt.
+const PROMPT_PLACEHOLDER_PATTERN: &str = r"\[(\w+)\]";
+
+/// How a provider sends stacked system prompts.
+///
+/// # Examples
+/// ```
+/// use obsidian_driver::ai::prompt::SystemPromptStacking;
+///
+/// let stacking = SystemPromptStacking::default();
+/// assert_eq!(stacking, SystemPromptStacking::Separate);
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SystemPromptStacking {
+	/// Join all system prompts into a single system message.
+	Concatenate,
+	/// Send each system prompt as its own system message.
+	#[default]
+	Separate,
+}
+
+/// How long a response to a prompt may be.
+///
+/// Providers turn the budget into the `max_tokens` of the request, so every chat method limits
+/// the response the same way.
+///
+/// # Examples
+/// ```
+/// use obsidian_driver::ai::prompt::ResponseBudget;
+///
+/// assert_eq!(ResponseBudget::default(), ResponseBudget::Unlimited);
+/// assert_eq!(ResponseBudget::Tokens(256).max_tokens(4096), 256);
+/// assert_eq!(ResponseBudget::Unlimited.max_tokens(4096), 4096);
+/// assert_eq!(ResponseBudget::from_characters(1000), ResponseBudget::Tokens(250));
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ResponseBudget {
+	/// At most this many tokens, capped at the most the model can write.
+	Tokens(u32),
+	/// As many tokens as the model can write.
+	#[default]
+	Unlimited,
+}
+
+impl ResponseBudget {
+	/// A budget for a response of about this many characters, at 4 characters per token.
+	///
+	/// # Arguments
+	/// @param characters: u32
+	/// @returns ResponseBudget - The tokens, rounded up.
+	pub fn from_characters(characters: u32) -> ResponseBudget {
+		ResponseBudget::Tokens(characters.div_ceil(CHARACTERS_PER_TOKEN))
+	}
+
+	/// The `max_tokens` to request from a model.
+	///
+	/// # Arguments
+	/// @param model_max_output_tokens: u32 - The most tokens the model can write.
+	/// @returns u32
+	pub fn max_tokens(&self, model_max_output_tokens: u32) -> u32 {
+		match self {
+			ResponseBudget::Tokens(tokens) => (*tokens).min(model_max_output_tokens),
+			ResponseBudget::Unlimited => model_max_output_tokens,
+		}
+	}
 }
 
 impl Prompt {
@@ -67,20 +209,232 @@ impl Prompt {
 	/// # Arguments
 	/// @param system_prompt: &str - The system prompt.
 	/// @param user_prompt: &str - The user prompt.
-	/// @param max_characters: u32 - The maximum number of characters allowed in the response.
+	/// @param response_budget: ResponseBudget - How long the response may be.
 	/// @returns Prompt - The new Prompt.
 	///
 	/// # Examples
 	/// ```
-	/// use obsidian_driver::ai::prompt::Prompt;
+	/// use obsidian_driver::ai::prompt::{Prompt, ResponseBudget};
 	///
-	/// let prompt = Prompt::new("You are a helpful assistant", "This is a sample prompt", 100);
+	/// let prompt = Prompt::new("You are a helpful assistant", "This is a sample prompt", ResponseBudget::Tokens(25));
 	/// ```
-	pub fn new(system_prompt: &str, user_prompt: &str, max_characters: Option<u32>) -> Prompt {
+	pub fn new(system_prompt: &str, user_prompt: &str, response_budget: ResponseBudget) -> Prompt {
 		Prompt {
 			system_prompt: system_prompt.to_string(),
+			extra_system_prompts: Vec::new(),
 			user_prompt: user_prompt.to_string(),
-			max_characters,
+			response_budget,
+			variables: Vec::new(),
+			images: Vec::new(),
+		}
+	}
+
+	/// Load a prompt from a markdown file, resolving `!include` directives.
+	///
+	/// A line `!include <path>` is replaced by the body of that file (its front matter is dropped); paths are relative to the including file, and includes may nest. See `Prompt::from_markdown` for the file format.
+	///
+	/// # Arguments
+	/// @param path: &Path - The prompt file.
+	/// @returns Result<Prompt> - Errors with `InvalidPrompt` for bad front matter, include cycles, or undeclared placeholders.
+	///
+	/// # Examples
+	/// ```should_panic
+	/// use std::path::Path;
+	///
+	/// use obsidian_driver::ai::prompt::Prompt;
+	///
+	/// let prompt = Prompt::from_file(Path::new("prompts/lecture-notes.md")).unwrap();
+	/// ```
+	pub fn from_file(path: &Path) -> Result<Prompt> {
+		let text = resolve_includes(path, &mut Vec::new())?;
+		Prompt::from_markdown(&text)
+	}
+
+	/// Parse a prompt from markdown text.
+	///
+	/// The front matter may set `system`, `user`, `max_tokens` (or `max_characters`, at 4 characters per token) for the response budget, and `variables`, the list of placeholders the prompt uses. The body holds a `# System` and a `# User` section; a body without those headings is the user prompt. When `variables` is set, every `[placeholder]` must be declared in it.
+	///
+	/// # Arguments
+	/// @param text: &str - The prompt file contents.
+	/// @returns Result<Prompt>
+	///
+	/// # Examples
+	/// ```
+	/// use obsidian_driver::ai::prompt::{Prompt, ResponseBudget};
+	///
+	/// let text = "---\nmax_tokens: 512\nvariables: [text]\n---\n# System\nYou are a student.\n\n# User\nSummarize:\n\n[text]\n";
+	/// let prompt = Prompt::from_markdown(text).unwrap();
+	/// assert_eq!(prompt.system_prompt, "You are a student.");
+	/// assert_eq!(prompt.user_prompt, "Summarize:\n\n[text]");
+	/// assert_eq!(prompt.response_budget, ResponseBudget::Tokens(512));
+	/// assert_eq!(prompt.variables, vec!["text".to_string()]);
+	/// ```
+	pub fn from_markdown(text: &str) -> Result<Prompt> {
+		let (yaml, body) = split_front_matter(text)?;
+		let string_key = |key: &str| yaml.get(key).and_then(|value| value.as_str()).map(str::to_string);
+		let number_key = |key: &str| -> Result<Option<u32>> {
+			match yaml.get(key) {
+				None => Ok(None),
+				Some(value) => value
+					.as_u64()
+					.and_then(|value| u32::try_from(value).ok())
+					.map(Some)
+					.ok_or(Error::InvalidPrompt(format!("`{}` must be a positive number", key))),
+			}
+		};
+
+		let heading_pattern = regex::Regex::new(r"(?mi)^#[ \t]+(system|user)[ \t]*$").unwrap();
+		let headings: Vec<(String, std::ops::Range<usize>)> = heading_pattern
+			.captures_iter(body)
+			.map(|captures| (captures[1].to_lowercase(), captures.get(0).unwrap().range()))
+			.collect();
+		let mut sections: HashMap<String, String> = HashMap::new();
+		for (i, (name, range)) in headings.iter().enumerate() {
+			let end = headings.get(i + 1).map(|(_, next)| next.start).unwrap_or(body.len());
+			sections.insert(name.clone(), body[range.end..end].trim().to_string());
+		}
+		if headings.is_empty() {
+			sections.insert("user".to_string(), body.trim().to_string());
+		}
+
+		let mut prompt = Prompt::new(
+			&string_key("system").or_else(|| sections.remove("system")).unwrap_or_default(),
+			&string_key("user").or_else(|| sections.remove("user")).unwrap_or_default(),
+			match (number_key("max_tokens")?, number_key("max_characters")?) {
+				(Some(tokens), _) => ResponseBudget::Tokens(tokens),
+				(None, Some(characters)) => ResponseBudget::from_characters(characters),
+				(None, None) => ResponseBudget::Unlimited,
+			},
+		);
+		if let Some(variables) = yaml.get("variables") {
+			prompt.variables = serde_yaml::from_value(variables.clone())
+				.map_err(|e| Error::InvalidPrompt(format!("`variables` must be a list of names: {}", e)))?;
+			let undeclared: Vec<String> = prompt
+				.placeholders()
+				.into_iter()
+				.filter(|key| !prompt.variables.contains(key))
+				.collect();
+			if !undeclared.is_empty() {
+				return Err(Error::InvalidPrompt(format!("Undeclared placeholders: {}", undeclared.join(", "))));
+			}
+		}
+		Ok(prompt)
+	}
+
+	/// Get the keys of the `[placeholders]` in the prompt, in order of first appearance.
+	///
+	/// # Arguments
+	/// @returns Vec<String>
+	///
+	/// # Examples
+	/// ```
+	/// use obsidian_driver::ai::prompt::{Prompt, ResponseBudget};
+	///
+	/// let prompt = Prompt::new("You are [name]", "Summarize [text] for [name]", ResponseBudget::Unlimited);
+	/// assert_eq!(prompt.placeholders(), vec!["name".to_string(), "text".to_string()]);
+	/// ```
+	pub fn placeholders(&self) -> Vec<String> {
+		let pattern = regex::Regex::new(PROMPT_PLACEHOLDER_PATTERN).unwrap();
+		let mut keys: Vec<String> = Vec::new();
+		let texts = std::iter::once(&self.system_prompt)
+			.chain(self.extra_system_prompts.iter())
+			.chain(std::iter::once(&self.user_prompt));
+		for text in texts {
+			for captures in pattern.captures_iter(text) {
+				if !keys.iter().any(|key| key == &captures[1]) {
+					keys.push(captures[1].to_string());
+				}
+			}
+		}
+		keys
+	}
+
+	/// Estimate the number of tokens the prompt takes up, from its length in characters.
+	///
+	/// # Arguments
+	/// @returns u32 - The characters of the system and user prompts, divided by 4.
+	///
+	/// # Examples
+	/// ```
+	/// use obsidian_driver::ai::prompt::{Prompt, ResponseBudget};
+	///
+	/// let prompt = Prompt::new("You are a note taker", "Summarize this", ResponseBudget::Unlimited);
+	/// assert_eq!(prompt.estimated_tokens(), 9);
+	/// ```
+	pub fn estimated_tokens(&self) -> u32 {
+		let characters: usize = std::iter::once(&self.system_prompt)
+			.chain(self.extra_system_prompts.iter())
+			.chain(std::iter::once(&self.user_prompt))
+			.map(|text| text.chars().count())
+			.sum();
+		(characters as u32).div_ceil(CHARACTERS_PER_TOKEN)
+	}
+
+	/// Stack an additional system prompt after the existing ones.
+	///
+	/// This allows shared style rules to be kept separately from task-specific instructions.
+	///
+	/// # Arguments
+	/// @param system_prompt: &str - The system prompt to add.
+	/// @returns Prompt - The Prompt with the system prompt stacked.
+	///
+	/// # Examples
+	/// ```
+	/// use obsidian_driver::ai::prompt::{Prompt, ResponseBudget};
+	///
+	/// let prompt = Prompt::new("You are a helpful assistant", "This is a sample prompt", ResponseBudget::Tokens(25))
+	/// 	.stack_system_prompt("Always answer in markdown");
+	/// assert_eq!(prompt.extra_system_prompts, vec!["Always answer in markdown".to_string()]);
+	/// ```
+	pub fn stack_system_prompt(mut self, system_prompt: &str) -> Prompt {
+		self.extra_system_prompts.push(system_prompt.to_string());
+		self
+	}
+
+	/// Attach an image to the user prompt, for models that accept images.
+	///
+	/// # Arguments
+	/// @param image: ImagePart - The image to send after the user prompt.
+	/// @returns Prompt - The Prompt with the image attached.
+	///
+	/// # Examples
+	/// ```
+	/// use obsidian_driver::ai::prompt::{ImagePart, Prompt, ResponseBudget};
+	///
+	/// let prompt = Prompt::new("You read diagrams", "What does this diagram show?", ResponseBudget::Unlimited)
+	/// 	.with_image(ImagePart::Url("https://example.com/diagram.png".to_string()));
+	/// assert_eq!(prompt.images.len(), 1);
+	/// ```
+	pub fn with_image(mut self, image: ImagePart) -> Prompt {
+		self.images.push(image);
+		self
+	}
+
+	/// Get the system messages to send for this prompt.
+	///
+	/// # Arguments
+	/// @param stacking: SystemPromptStacking - How the provider sends stacked system prompts.
+	/// @returns Vec<String> - The system messages, in order.
+	///
+	/// # Examples
+	/// ```
+	/// use obsidian_driver::ai::prompt::{Prompt, ResponseBudget, SystemPromptStacking};
+	///
+	/// let prompt = Prompt::new("You are a helpful assistant", "This is a sample prompt", ResponseBudget::Tokens(25))
+	/// 	.stack_system_prompt("Always answer in markdown");
+	///
+	/// let actual = prompt.system_messages(SystemPromptStacking::Concatenate);
+	/// let expected = vec!["You are a helpful assistant\n\nAlways answer in markdown".to_string()];
+	/// assert_eq!(actual, expected);
+	/// ```
+	pub fn system_messages(&self, stacking: SystemPromptStacking) -> Vec<String> {
+		let messages = std::iter::once(&self.system_prompt)
+			.chain(self.extra_system_prompts.iter())
+			.filter(|message| !message.is_empty())
+			.cloned();
+		match stacking {
+			SystemPromptStacking::Concatenate => vec![messages.collect::<Vec<String>>().join("\n\n")],
+			SystemPromptStacking::Separate => messages.collect(),
 		}
 	}
 
@@ -92,58 +446,117 @@ impl Prompt {
 	///
 	/// # Examples
 	/// ```
-	/// use obsidian_driver::ai::prompt::{Prompt, Context};
+	/// use obsidian_driver::ai::prompt::{Prompt, ResponseBudget, Context};
 	///
-	/// let prompt = Prompt::new("You are a helpful assistant named $name$", "This is a sample prompt", 100);
+	/// let prompt = Prompt::new("You are a helpful assistant named [name]", "This is a sample prompt", ResponseBudget::Tokens(25));
 	/// let mut context = Context::default();
 	/// context.insert("name", "Bob");
 	///
 	/// let actual = prompt.substitute(&context).unwrap();
-	/// let expected = Prompt::new("You are a helpful assistant named Bob", "This is a sample prompt", 100);
+	/// let expected = Prompt::new("You are a helpful assistant named Bob", "This is a sample prompt", ResponseBudget::Tokens(25));
 	/// assert_eq!(actual, expected);
 	/// ```
 	///
 	/// ```
-	/// use obsidian_driver::ai::prompt::{Prompt, Context};
+	/// use obsidian_driver::ai::prompt::{Prompt, ResponseBudget, Context};
 	///
-	/// let prompt = Prompt::new("You are a helpful $profession$ named $name$", "This is a sample prompt for someone who's job is a(n) $profession$", 100);
+	/// let prompt = Prompt::new("You are a helpful [profession] named [name]", "This is a sample prompt for someone who's job is a(n) [profession]", ResponseBudget::Tokens(25));
 	/// let mut context = Context::default();
 	/// context.insert("name", "Bob");
 	/// context.insert("profession", "assistant");
 	///
 	/// let actual = prompt.substitute(&context).unwrap();
-	/// let expected = Prompt::new("You are a helpful assistant named Bob", "This is a sample prompt for someone who's job is a(n) assistant", 100);
+	/// let expected = Prompt::new("You are a helpful assistant named Bob", "This is a sample prompt for someone who's job is a(n) assistant", ResponseBudget::Tokens(25));
 	/// assert_eq!(actual, expected);
 	/// ```
 	pub fn substitute(&self, context: &Context) -> Result<Self> {
-		let mut system_prompt = self.system_prompt.clone();
-		let mut user_prompt = self.user_prompt.clone();
-		let mut matches: Vec<String> = Vec::new();
-		let pattern = regex::Regex::new(r"\[\w+\]").unwrap();
-		for cap in pattern.captures_iter(&system_prompt) {
-			matches.push(cap[0].to_string());
-		}
-		for cap in pattern.captures_iter(&user_prompt) {
-			matches.push(cap[0].to_string());
-		}
-		for m in matches {
-			let key = m.trim_start_matches('[').trim_end_matches(']');
-			match context.get(key) {
-				Some(value) => {
-					system_prompt = system_prompt.replace(&m, value);
-					user_prompt = user_prompt.replace(&m, value);
-				},
-				None => return Err(Error::InvalidContextKey(format!("Key not found in context: {}", key)))
-			}
-		}
+		let pattern = regex::Regex::new(PROMPT_PLACEHOLDER_PATTERN).unwrap();
+		let value = |captures: &regex::Captures| match context.get(&captures[1]) {
+			Some(value) => Ok(value.clone()),
+			None => Err(Error::InvalidContextKey(format!("Key not found in context: {}", &captures[1])))
+		};
 		Ok(Prompt {
-			system_prompt,
-			user_prompt,
-			max_characters: self.max_characters,
+			system_prompt: substitute_placeholders(&self.system_prompt, &pattern, value)?,
+			extra_system_prompts: self.extra_system_prompts
+				.iter()
+				.map(|extra| substitute_placeholders(extra, &pattern, value))
+				.collect::<Result<Vec<String>>>()?,
+			user_prompt: substitute_placeholders(&self.user_prompt, &pattern, value)?,
+			response_budget: self.response_budget,
+			// every placeholder has been filled
+			variables: Vec::new(),
+			images: self.images.clone(),
 		})
 	}
 }
 
+/// Split prompt text into its front matter mapping and body.
+///
+/// @private
+fn split_front_matter(text: &str) -> Result<(serde_yaml::Mapping, &str)> {
+	let Some(rest) = text.strip_prefix("---\n") else {
+		return Ok((serde_yaml::Mapping::new(), text));
+	};
+	let Some(end) = rest.find("\n---") else {
+		return Ok((serde_yaml::Mapping::new(), text));
+	};
+	let body = rest[end + 4..].strip_prefix('\n').unwrap_or(&rest[end + 4..]);
+	let yaml: Option<serde_yaml::Mapping> = serde_yaml::from_str(&rest[..end])
+		.map_err(|e| Error::InvalidPrompt(format!("Invalid front matter: {}", e)))?;
+	Ok((yaml.unwrap_or_default(), body))
+}
+
+/// Read a prompt file and replace its `!include <path>` lines with the bodies of the included files.
+///
+/// @private
+fn resolve_includes(path: &Path, stack: &mut Vec<PathBuf>) -> Result<String> {
+	let canonical = path.canonicalize()?;
+	if stack.contains(&canonical) {
+		return Err(Error::InvalidPrompt(format!("Include cycle at {}", path.display())));
+	}
+	stack.push(canonical);
+	let text = std::fs::read_to_string(path)?;
+	let folder = path.parent().unwrap_or(Path::new(""));
+	let mut resolved = String::with_capacity(text.len());
+	for line in text.split_inclusive('\n') {
+		match line.trim().strip_prefix("!include ") {
+			Some(include) => {
+				let included = resolve_includes(&folder.join(include.trim()), stack)?;
+				let (_, body) = split_front_matter(&included)?;
+				resolved.push_str(body.trim_end());
+				resolved.push('\n');
+			}
+			None => resolved.push_str(line),
+		}
+	}
+	stack.pop();
+	Ok(resolved)
+}
+
+/// Replace every match of a placeholder pattern in a text with the value computed from its captures.
+///
+/// This is the substitution used by prompts (`[key]`) and note templates (`{{key}}`).
+///
+/// # Arguments
+/// @param text: &str - The text containing placeholders.
+/// @param pattern: &regex::Regex - The placeholder pattern.
+/// @param value: impl Fn(&regex::Captures) -> Result<String> - Computes the replacement, or fails for unknown keys.
+/// @returns Result<String> - The text with every placeholder replaced.
+///
+/// @crate
+pub(crate) fn substitute_placeholders(text: &str, pattern: &regex::Regex, value: impl Fn(&regex::Captures) -> Result<String>) -> Result<String> {
+	let mut substituted = String::with_capacity(text.len());
+	let mut last = 0;
+	for captures in pattern.captures_iter(text) {
+		let whole = captures.get(0).unwrap();
+		substituted.push_str(&text[last..whole.start()]);
+		substituted.push_str(&value(&captures)?);
+		last = whole.end();
+	}
+	substituted.push_str(&text[last..]);
+	Ok(substituted)
+}
+
 /// The Context struct.
 ///
 /// This struct contains a hashmap of keys and values.
@@ -225,9 +638,185 @@ impl From<HashMap<String, String>> for Context {
 	}
 }
 
+/// The author of a message in a Conversation.
+///
+/// @public
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Role {
+	System,
+	User,
+	Assistant,
+}
+
+/// A single message in a Conversation.
+///
+/// @public
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Message {
+	pub role: Role,
+	pub content: String,
+}
+
+/// The Conversation struct.
+///
+/// A message history for multi-turn chats, so a note can be refined over several requests. Send it with `AIDriver::chat_conversation`, which appends the reply.
+///
+/// # Examples
+/// ```
+/// use obsidian_driver::ai::api::AIDriver;
+/// use obsidian_driver::ai::api::mock::MockDriver;
+/// use obsidian_driver::ai::prompt::{Conversation, Prompt, ResponseBudget};
+///
+/// #[tokio::main]
+/// async fn main() {
+/// 	let driver = AIDriver::new_mock(MockDriver::new().with_responses(["# Notes\n\nLong version", "# Notes\n\nShort"]));
+///
+/// 	let mut conversation = Conversation::from_prompt(&Prompt::new("You write lecture notes", "Make notes from: [...]", ResponseBudget::Unlimited));
+/// 	driver.chat_conversation(&mut conversation).await.unwrap();
+/// 	conversation.user("Now shorten the takeaways");
+/// 	let shorter = driver.chat_conversation(&mut conversation).await.unwrap();
+///
+/// 	assert_eq!(shorter, "# Notes\n\nShort");
+/// 	assert_eq!(conversation.messages().len(), 5);
+/// }
+/// ```
+/// @public
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Conversation {
+	messages: Vec<Message>,
+	#[serde(default)]
+	pub response_budget: ResponseBudget,
+}
+
+impl Conversation {
+	/// Create a conversation with a system prompt.
+	///
+	/// # Arguments
+	/// @param system_prompt: &str - The system prompt, skipped if empty.
+	/// @returns Conversation
+	pub fn new(system_prompt: &str) -> Conversation {
+		let mut conversation = Conversation::default();
+		conversation.push(Role::System, system_prompt);
+		conversation
+	}
+
+	/// Start a conversation from a prompt: its system prompts, then its user prompt.
+	///
+	/// # Arguments
+	/// @param prompt: &Prompt
+	/// @returns Conversation
+	pub fn from_prompt(prompt: &Prompt) -> Conversation {
+		let mut conversation = Conversation {
+			messages: Vec::new(),
+			response_budget: prompt.response_budget,
+		};
+		for message in prompt.system_messages(SystemPromptStacking::Separate) {
+			conversation.push(Role::System, &message);
+		}
+		conversation.user(&prompt.user_prompt);
+		conversation
+	}
+
+	/// Add a message. Empty messages are skipped.
+	///
+	/// # Arguments
+	/// @param role: Role
+	/// @param content: &str
+	pub fn push(&mut self, role: Role, content: &str) {
+		if !content.is_empty() {
+			self.messages.push(Message {
+				role,
+				content: content.to_string(),
+			});
+		}
+	}
+
+	/// Add a user message.
+	///
+	/// # Arguments
+	/// @param content: &str
+	pub fn user(&mut self, content: &str) {
+		self.push(Role::User, content);
+	}
+
+	/// Add an assistant message, e.g. a reply or an edited version of one.
+	///
+	/// # Arguments
+	/// @param content: &str
+	pub fn assistant(&mut self, content: &str) {
+		self.push(Role::Assistant, content);
+	}
+
+	/// Get the messages, in order.
+	///
+	/// # Arguments
+	/// @returns &[Message]
+	pub fn messages(&self) -> &[Message] {
+		&self.messages
+	}
+
+	/// Get the latest assistant message.
+	///
+	/// # Arguments
+	/// @returns Option<&str>
+	pub fn last_response(&self) -> Option<&str> {
+		self.messages
+			.iter()
+			.rev()
+			.find(|message| message.role == Role::Assistant)
+			.map(|message| message.content.as_str())
+	}
+
+	/// Flatten the conversation into a single Prompt, for providers without multi-turn support.
+	///
+	/// System messages become the system prompts, and the other messages become a transcript in the user prompt.
+	///
+	/// # Arguments
+	/// @returns Prompt
+	///
+	/// # Examples
+	/// ```
+	/// use obsidian_driver::ai::prompt::Conversation;
+	///
+	/// let mut conversation = Conversation::new("Be brief");
+	/// conversation.user("Hi");
+	/// conversation.assistant("Hello");
+	/// conversation.user("Shorter");
+	///
+	/// let prompt = conversation.to_prompt();
+	/// assert_eq!(prompt.system_prompt, "Be brief");
+	/// assert_eq!(prompt.user_prompt, "**User**\nHi\n\n**Assistant**\nHello\n\n**User**\nShorter");
+	/// ```
+	pub fn to_prompt(&self) -> Prompt {
+		let mut system = self.messages.iter().filter(|message| message.role == Role::System);
+		let mut prompt = Prompt::new(
+			system.next().map(|message| message.content.as_str()).unwrap_or_default(),
+			"",
+			self.response_budget,
+		);
+		for message in system {
+			prompt = prompt.stack_system_prompt(&message.content);
+		}
+		let turns: Vec<&Message> = self.messages.iter().filter(|message| message.role != Role::System).collect();
+		prompt.user_prompt = match turns.as_slice() {
+			[only] if only.role == Role::User => only.content.clone(),
+			_ => turns
+				.iter()
+				.map(|message| match message.role {
+					Role::Assistant => format!("**Assistant**\n{}", message.content),
+					_ => format!("**User**\n{}", message.content),
+				})
+				.collect::<Vec<String>>()
+				.join("\n\n"),
+		};
+		prompt
+	}
+}
+
 impl Display for Prompt {
 	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-		write!(f, "#### System Prompt ####\n{}\n\n#### User Prompt####\n{}\n\n#### Max Characters ####\n{:?}", self.system_prompt, self.user_prompt, self.max_characters)
+		let system_prompt = self.system_messages(SystemPromptStacking::Concatenate).join("");
+		write!(f, "#### System Prompt ####\n{}\n\n#### User Prompt####\n{}\n\n#### Response Budget ####\n{:?}", system_prompt, self.user_prompt, self.response_budget)
 	}
 }
 
@@ -237,27 +826,75 @@ mod prompt_tests {
 
 	#[test]
 	fn test_prompt_new() {
-		let prompt = Prompt::new("You are a helpful assistant", "This is a sample prompt", Some(100));
+		let prompt = Prompt::new("You are a helpful assistant", "This is a sample prompt", ResponseBudget::Tokens(25));
 		assert_eq!(prompt.system_prompt, "You are a helpful assistant");
 		assert_eq!(prompt.user_prompt, "This is a sample prompt");
-		assert_eq!(prompt.max_characters, Some(100));
+		assert_eq!(prompt.response_budget, ResponseBudget::Tokens(25));
 	}
 
 	#[test]
 	fn test_prompt_substitube_valid() {
-		let prompt = Prompt::new("You are a helpful assistant named [name]", "This is a sample prompt", Some(100));
+		let prompt = Prompt::new("You are a helpful assistant named [name]", "This is a sample prompt", ResponseBudget::Tokens(25));
 		let mut context = Context::default();
 		context.insert("name", "Bob");
-		let expected = Prompt::new("You are a helpful assistant named Bob", "This is a sample prompt", Some(100));
+		let expected = Prompt::new("You are a helpful assistant named Bob", "This is a sample prompt", ResponseBudget::Tokens(25));
 		let actual = prompt.substitute(&context).unwrap();
 		assert_eq!(actual, expected);
 	}
 
 	#[test]
 	fn test_prompt_substitube_invalid() {
-		let prompt = Prompt::new("You are a helpful assistant named [name]", "This is a sample prompt", Some(100));
+		let prompt = Prompt::new("You are a helpful assistant named [name]", "This is a sample prompt", ResponseBudget::Tokens(25));
 		let context = Context::default();
 		let actual = prompt.substitute(&context);
 		assert!(actual.is_err());
 	}
-}
\ No newline at end of file
+
+	#[test]
+	fn test_prompt_stacked_system_prompts() {
+		let prompt = Prompt::new("You are a helpful assistant named [name]", "This is a sample prompt", ResponseBudget::Tokens(25))
+			.stack_system_prompt("Always greet [name]");
+		let mut context = Context::default();
+		context.insert("name", "Bob");
+		let actual = prompt.substitute(&context).unwrap().system_messages(SystemPromptStacking::Separate);
+		let expected = vec!["You are a helpful assistant named Bob".to_string(), "Always greet Bob".to_string()];
+		assert_eq!(actual, expected);
+	}
+
+	#[test]
+	fn test_prompt_from_file_with_includes() {
+		let dir = tempfile::tempdir().unwrap();
+		std::fs::create_dir(dir.path().join("shared")).unwrap();
+		std::fs::write(dir.path().join("shared/style.md"), "---\nnote: ignored\n---\nUse markdown.\n!include ../rules.md\n").unwrap();
+		std::fs::write(dir.path().join("rules.md"), "Use LaTeX for math.").unwrap();
+		std::fs::write(
+			dir.path().join("notes.md"),
+			"---\nvariables: [text]\n---\n# System\nYou take notes.\n!include shared/style.md\n# User\n[text]\n",
+		)
+		.unwrap();
+		let prompt = Prompt::from_file(&dir.path().join("notes.md")).unwrap();
+		assert_eq!(prompt.system_prompt, "You take notes.\nUse markdown.\nUse LaTeX for math.");
+		assert_eq!(prompt.user_prompt, "[text]");
+
+		std::fs::write(dir.path().join("rules.md"), "!include shared/style.md").unwrap();
+		assert!(matches!(Prompt::from_file(&dir.path().join("notes.md")), Err(Error::InvalidPrompt(_))));
+	}
+
+	#[test]
+	fn test_prompt_from_markdown_undeclared_variable() {
+		let result = Prompt::from_markdown("---\nvariables: [text]\n---\nSummarize [text] for [audience]");
+		assert!(matches!(result, Err(Error::InvalidPrompt(_))));
+	}
+
+	#[test]
+	fn test_conversation_from_prompt() {
+		let prompt = Prompt::new("System", "Question", ResponseBudget::Tokens(25)).stack_system_prompt("Style");
+		let mut conversation = Conversation::from_prompt(&prompt);
+		assert_eq!(conversation.to_prompt(), prompt);
+		conversation.assistant("Answer");
+		conversation.user("");
+		let roles: Vec<Role> = conversation.messages().iter().map(|message| message.role).collect();
+		assert_eq!(roles, vec![Role::System, Role::System, Role::User, Role::Assistant]);
+		assert_eq!(conversation.last_response(), Some("Answer"));
+	}
+}
@@ -0,0 +1,257 @@
+//! # obsidian-driver::ai::questions
+//!
+//! This module contains practice exam questions generated from notes, with their answers and difficulties, written to a companion `Questions - <Topic>` note where every question carries a `#difficulty/...` tag and a folded answer.
+//!
+//! @public Difficulty
+//!
+//! @public Difficulty::tag
+//!
+//! @public Question
+//!
+//! @public generate_questions
+//!
+//! @public generate_questions_file
+
+// std imports
+use std::fmt::Display;
+use std::path::PathBuf;
+
+// third-party imports
+use serde::{Deserialize, Serialize};
+
+// first-party imports
+use crate::ai::api::AIDriver;
+use crate::ai::prompt::{Context, Prompt, ResponseBudget};
+use crate::ai::structured::{parse_json_response, JSON_SYSTEM_PROMPT};
+use crate::ai::NOTE_STYLE_SYSTEM_PROMPT;
+use crate::file::mdfile::callout::{Callout, CalloutFold};
+use crate::file::mdfile::MDFile;
+use crate::file::naming::sanitize_filename;
+use crate::prelude::*;
+
+const QUESTIONS_SYSTEM_PROMPT: &str = "You are a teacher writing practice exam questions from a student's lecture notes.";
+const QUESTIONS_USER_PROMPT: &str = r#"Below are notes on [topic]. Write [count] practice exam questions that test understanding of the notes, each with its answer. Cover the takeaways of the notes first. Mix easy questions (recall a definition or fact), medium questions (explain or apply an idea) and hard questions (combine ideas, work through a problem, or compare approaches). Answers are complete but short, in markdown.
+
+Reply with a JSON object of the form {"questions": [{"question": "...", "answer": "...", "difficulty": "easy"}]}, where difficulty is "easy", "medium" or "hard".
+
+**Notes**
+
+[notes]"#;
+
+/// How hard a practice question is.
+///
+/// # Example
+/// ```
+/// use obsidian_driver::ai::questions::Difficulty;
+///
+/// assert_eq!(Difficulty::Medium.tag(), "difficulty/medium");
+/// ```
+/// @public
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Difficulty {
+    Easy,
+    Medium,
+    Hard,
+}
+
+impl Difficulty {
+    /// The tag of questions of this difficulty, without the `#`.
+    ///
+    /// # Arguments
+    /// @returns String
+    pub fn tag(&self) -> String {
+        f!("difficulty/{}", self)
+    }
+}
+
+impl Display for Difficulty {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Difficulty::Easy => write!(f, "easy"),
+            Difficulty::Medium => write!(f, "medium"),
+            Difficulty::Hard => write!(f, "hard"),
+        }
+    }
+}
+
+/// A practice question and its answer.
+///
+/// @public
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Question {
+    pub question: String,
+    // markdown
+    pub answer: String,
+    pub difficulty: Difficulty,
+}
+
+/// The JSON reply to the questions prompt.
+///
+/// @private
+#[derive(Deserialize)]
+struct QuestionList {
+    questions: Vec<Question>,
+}
+
+/// Generate practice questions and answers from notes on a topic
+///
+/// The bodies of the notes are sent together to the smart model, which replies with the questions as JSON. Files that are not markdown notes are left out.
+///
+/// # Arguments
+/// @param driver: &AIDriver - The AI driver to use for generating the questions
+/// @param files: Vec<&crate::file::File> - The notes to ask about
+/// @param topic: &str - The topic of the notes, e.g. "Cell Division"
+/// @param count: usize - How many questions to ask for
+/// @returns Result<Vec<Question>> - The questions, easiest first. Errors with `Generic` if no file is a markdown note, and with `InvalidChatResponse` if the reply is not the questions.
+///
+/// # Example
+/// ```
+/// use std::path::PathBuf;
+///
+/// use obsidian_driver::ai::api::AIDriver;
+/// use obsidian_driver::ai::questions::generate_questions;
+/// use obsidian_driver::file::vault::Vault;
+///
+/// async fn generate_questions_example() {
+/// 	let openai_config_path = PathBuf::from(".openai_config.json");
+/// 	let driver = AIDriver::new_openai_from_config_path(openai_config_path).await.unwrap();
+/// 	let vault = Vault::from_path(PathBuf::from("vault")).unwrap();
+/// 	let files = vec![vault.get_file(&PathBuf::from("Lecture 1.md")).unwrap()];
+///
+/// 	for question in generate_questions(&driver, files, "Cell Division", 10).await.unwrap() {
+/// 		println!("[{}] {}", question.difficulty, question.question);
+/// 	}
+/// }
+/// ```
+/// @public
+pub async fn generate_questions(driver: &AIDriver, files: Vec<&crate::file::File>, topic: &str, count: usize) -> Result<Vec<Question>> {
+    let mut notes = String::new();
+    for file in files {
+        let Some(mdfile) = file.get_mdfile() else {
+            continue;
+        };
+        notes.push_str(&f!("**{}**\n\n{}\n\n", title(file.get_path()), mdfile.strip_comments().get_body().trim()));
+    }
+    if notes.is_empty() {
+        return Err(Error::Generic("No markdown notes to ask questions about".to_string()));
+    }
+    let mut context = Context::default();
+    context.insert("topic", topic);
+    context.insert("count", &count.to_string());
+    context.insert("notes", notes.trim_end());
+    let prompt = Prompt::new(QUESTIONS_SYSTEM_PROMPT, QUESTIONS_USER_PROMPT, ResponseBudget::Unlimited)
+        .stack_system_prompt(NOTE_STYLE_SYSTEM_PROMPT)
+        .stack_system_prompt(JSON_SYSTEM_PROMPT)
+        .substitute(&context)?;
+    let response = driver.chat_smart(prompt).await?;
+    let mut questions = parse_json_response::<QuestionList>(&response)?.questions;
+    questions.sort_by_key(|question| question.difficulty);
+    Ok(questions)
+}
+
+/// Generate practice questions from notes on a topic as a `Questions - <Topic>` note
+///
+/// The note links the notes it was made from, and lists the questions easiest first, each under its own heading with a `#difficulty/...` tag and its answer in a folded callout, so the answers stay hidden while practicing.
+///
+/// # Arguments
+/// @param driver: &AIDriver - The AI driver to use for generating the questions
+/// @param files: Vec<&crate::file::File> - The notes to ask about
+/// @param topic: &str - The topic of the notes, e.g. "Cell Division"
+/// @param count: usize - How many questions to ask for
+/// @param output_folder: PathBuf - The output folder to save the file in
+/// @returns Result<crate::file::File> - The questions note, see `generate_questions` for the errors
+///
+/// # Example
+/// ```
+/// use std::path::PathBuf;
+///
+/// use obsidian_driver::ai::api::AIDriver;
+/// use obsidian_driver::ai::questions::generate_questions_file;
+/// use obsidian_driver::file::vault::Vault;
+///
+/// async fn generate_questions_file_example() {
+/// 	let openai_config_path = PathBuf::from(".openai_config.json");
+/// 	let driver = AIDriver::new_openai_from_config_path(openai_config_path).await.unwrap();
+/// 	let vault = Vault::from_path(PathBuf::from("vault")).unwrap();
+/// 	let files = vec![
+/// 		vault.get_file(&PathBuf::from("Lecture 1.md")).unwrap(),
+/// 		vault.get_file(&PathBuf::from("Lecture 2.md")).unwrap(),
+/// 	];
+///
+/// 	let file = generate_questions_file(&driver, files, "Cell Division", 10, PathBuf::from("vault/Practice")).await.unwrap();
+/// 	file.write().unwrap();
+/// }
+/// ```
+/// @public
+pub async fn generate_questions_file(driver: &AIDriver, files: Vec<&crate::file::File>, topic: &str, count: usize, output_folder: PathBuf) -> Result<crate::file::File> {
+    let sources: Vec<String> = files
+        .iter()
+        .filter(|file| file.get_mdfile().is_some())
+        .map(|file| f!("[[{}]]", title(file.get_path())))
+        .collect();
+    let questions = generate_questions(driver, files, topic, count).await?;
+
+    let name = f!("Questions - {}", topic);
+    let mut body = f!("# {}\n\nFrom {}\n", name, sources.join(", "));
+    for (number, question) in questions.iter().enumerate() {
+        let answer = Callout::new("answer", question.answer.trim())
+            .with_title("Answer")
+            .with_fold(CalloutFold::Closed);
+        body.push_str(&f!(
+            "\n## {}. {}\n\n#{}\n\n{}\n",
+            number + 1,
+            question.question.trim().replace('\n', " "),
+            question.difficulty.tag(),
+            answer
+        ));
+    }
+    let mut mdfile = MDFile::new(None, body);
+    mdfile.add_yaml_key("topic".to_string(), serde_yaml::Value::from(topic));
+    mdfile.add_yaml_key("tags".to_string(), serde_yaml::Value::Sequence(vec![serde_yaml::Value::from("questions")]));
+    let path = output_folder.join(f!("{}.md", sanitize_filename(&name)));
+    Ok(crate::file::File::from_mdfile(path, mdfile))
+}
+
+/// The title of a note, its file name without the extension.
+///
+/// @private
+fn title(path: &std::path::Path) -> String {
+    path.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod questions_tests {
+    use super::*;
+    use crate::ai::api::mock::MockDriver;
+    use crate::file::vault::Vault;
+
+    #[tokio::test]
+    async fn test_generate_questions_file() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("Mitosis.md"), "# Mitosis\n\nCells divide.").unwrap();
+        let vault = Vault::from_path(dir.path().to_path_buf()).unwrap();
+        let note = vault.get_file(&PathBuf::from("Mitosis.md")).unwrap();
+        let response = r#"```json
+{"questions": [
+    {"question": "Why do cells divide?", "answer": "To grow.\n\nAnd to repair.", "difficulty": "hard"},
+    {"question": "What is mitosis?", "answer": "Cell division.", "difficulty": "easy"}
+]}
+```"#;
+        let mock = MockDriver::new().with_responses([response, "Sorry, I can't."]);
+        let driver = AIDriver::new_mock(mock.clone());
+
+        let file = generate_questions_file(&driver, vec![note], "Cells: Division", 2, PathBuf::from("out")).await.unwrap();
+        assert_eq!(file.get_path(), &PathBuf::from("out/Questions - Cells - Division.md"));
+        assert_eq!(
+            file.get_mdfile().unwrap().to_string(),
+            "---\ntopic: 'Cells: Division'\ntags:\n- questions\n---\n# Questions - Cells: Division\n\nFrom [[Mitosis]]\n\n\
+             ## 1. What is mitosis?\n\n#difficulty/easy\n\n> [!answer]- Answer\n> Cell division.\n\n\
+             ## 2. Why do cells divide?\n\n#difficulty/hard\n\n> [!answer]- Answer\n> To grow.\n>\n> And to repair.\n"
+        );
+        assert!(mock.prompts()[0].user_prompt.contains("Below are notes on Cells: Division. Write 2 practice"));
+
+        let failed = generate_questions(&driver, vec![note], "Cells", 2).await;
+        assert!(matches!(failed, Err(Error::InvalidChatResponse(_))));
+    }
+}
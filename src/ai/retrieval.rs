@@ -0,0 +1,327 @@
+//! # obsidian-driver::ai::retrieval
+//!
+//! This module contains the ContextBuilder, which selects the notes most relevant to a query or seed note by embedding similarity and packs them into a prompt Context within a token budget.
+//!
+//! @public ContextBuilder
+//!
+//! @public ContextBuilder::new
+//!
+//! @public ContextBuilder::with_top_k
+//!
+//! @public ContextBuilder::with_max_tokens
+//!
+//! @public ContextBuilder::with_max_distance
+//!
+//! @public ContextBuilder::with_key
+//!
+//! @public ContextBuilder::exclude
+//!
+//! @public ContextBuilder::build_for_query
+//!
+//! @public ContextBuilder::build_for_note
+//!
+//! @public ContextBuilder::build_for_embedding
+//!
+//! @public RetrievedContext
+//!
+//! @public RetrievedNote
+
+// std imports
+use std::path::{Path, PathBuf};
+
+// first-party imports
+use crate::ai::api::AIDriver;
+use crate::ai::prompt::{Context, CHARACTERS_PER_TOKEN};
+use crate::file::vault::Vault;
+use crate::prelude::*;
+
+/// The context key the retrieved notes are inserted under by default.
+const DEFAULT_CONTEXT_KEY: &str = "context";
+
+/// A note selected by a ContextBuilder.
+///
+/// @public
+#[derive(Clone, Debug, PartialEq)]
+pub struct RetrievedNote {
+    pub path: PathBuf,
+    // embedding distance to the query
+    pub distance: f64,
+    // the body of the note, cut short if it did not fit the budget
+    pub text: String,
+    pub truncated: bool,
+}
+
+/// The notes selected by a ContextBuilder, nearest first.
+///
+/// # Examples
+/// ```
+/// use std::path::PathBuf;
+///
+/// use obsidian_driver::ai::prompt::{Context, Prompt, ResponseBudget};
+/// use obsidian_driver::ai::retrieval::{RetrievedContext, RetrievedNote};
+///
+/// let retrieved = RetrievedContext {
+/// 	key: "context".to_string(),
+/// 	notes: vec![RetrievedNote { path: PathBuf::from("Cells.md"), distance: 0.1, text: "Cells are small.".to_string(), truncated: false }],
+/// };
+/// assert_eq!(retrieved.text(), "### [[Cells]]\n\nCells are small.");
+///
+/// let prompt = Prompt::new("Answer from the notes.", "[context]\n\nHow big are cells?", ResponseBudget::Unlimited);
+/// let prompt = prompt.substitute(&retrieved.to_context()).unwrap();
+/// ```
+/// @public
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RetrievedContext {
+    // the placeholder the notes are inserted under
+    pub key: String,
+    pub notes: Vec<RetrievedNote>,
+}
+
+impl RetrievedContext {
+    /// Get the notes as one text, each under a `### [[name]]` heading.
+    ///
+    /// # Arguments
+    /// @returns String
+    pub fn text(&self) -> String {
+        self.notes
+            .iter()
+            .map(|note| note_block(&note.path, &note.text))
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+
+    /// Get the paths of the notes, nearest first.
+    ///
+    /// # Arguments
+    /// @returns Vec<PathBuf>
+    pub fn sources(&self) -> Vec<PathBuf> {
+        self.notes.iter().map(|note| note.path.clone()).collect()
+    }
+
+    /// Insert the text of the notes into a context under the key.
+    ///
+    /// # Arguments
+    /// @param context: &mut Context
+    pub fn insert_into(&self, context: &mut Context) {
+        context.insert(&self.key, &self.text());
+    }
+
+    /// Get a context holding only the text of the notes under the key.
+    ///
+    /// # Arguments
+    /// @returns Context
+    pub fn to_context(&self) -> Context {
+        let mut context = Context::default();
+        self.insert_into(&mut context);
+        context
+    }
+}
+
+/// The ContextBuilder struct.
+///
+/// Selects up to `top_k` notes (default 5) nearest to a query by embedding, and keeps their
+/// bodies, nearest first, until the `max_tokens` budget (default 2000, at 4 characters per
+/// token) is spent; the note that crosses the budget is cut short and the rest are dropped.
+/// Notes need embeddings, see `Vault::update_embeddings`.
+///
+/// # Examples
+/// ```
+/// use std::path::PathBuf;
+///
+/// use obsidian_driver::ai::api::AIDriver;
+/// use obsidian_driver::ai::prompt::{Prompt, ResponseBudget};
+/// use obsidian_driver::ai::retrieval::ContextBuilder;
+/// use obsidian_driver::file::vault::Vault;
+///
+/// async fn context_builder_example() {
+/// 	let driver = AIDriver::new_openai_from_config_path(PathBuf::from(".openai_config.json")).await.unwrap();
+/// 	let mut vault = Vault::from_path(PathBuf::from("vault")).unwrap();
+/// 	vault.add_ai_driver(driver.clone());
+/// 	vault.update_embeddings().await.unwrap();
+///
+/// 	let retrieved = ContextBuilder::new(&vault).with_top_k(3).with_max_tokens(1000).build_for_query(&driver, "mitochondria").await.unwrap();
+/// 	let mut context = retrieved.to_context();
+/// 	context.insert("question", "What do mitochondria do?");
+/// 	let prompt = Prompt::new("Answer from the notes.", "[context]\n\n[question]", ResponseBudget::Unlimited).substitute(&context).unwrap();
+/// 	let answer = driver.chat_smart(prompt).await.unwrap();
+/// }
+/// ```
+/// @public
+#[derive(Clone, Debug)]
+pub struct ContextBuilder<'a> {
+    vault: &'a Vault,
+    top_k: usize,
+    max_tokens: usize,
+    max_distance: Option<f64>,
+    key: String,
+    excluded: Vec<PathBuf>,
+}
+
+impl<'a> ContextBuilder<'a> {
+    /// Create a ContextBuilder over a vault.
+    ///
+    /// # Arguments
+    /// @param vault: &Vault
+    /// @returns ContextBuilder
+    pub fn new(vault: &'a Vault) -> ContextBuilder<'a> {
+        ContextBuilder {
+            vault,
+            top_k: 5,
+            max_tokens: 2000,
+            max_distance: None,
+            key: DEFAULT_CONTEXT_KEY.to_string(),
+            excluded: Vec::new(),
+        }
+    }
+
+    /// Set the maximum number of notes.
+    ///
+    /// # Arguments
+    /// @param top_k: usize
+    /// @returns ContextBuilder
+    pub fn with_top_k(mut self, top_k: usize) -> ContextBuilder<'a> {
+        self.top_k = top_k;
+        self
+    }
+
+    /// Set the token budget of the note text.
+    ///
+    /// # Arguments
+    /// @param max_tokens: usize
+    /// @returns ContextBuilder
+    pub fn with_max_tokens(mut self, max_tokens: usize) -> ContextBuilder<'a> {
+        self.max_tokens = max_tokens;
+        self
+    }
+
+    /// Drop notes further than a distance from the query.
+    ///
+    /// # Arguments
+    /// @param max_distance: f64
+    /// @returns ContextBuilder
+    pub fn with_max_distance(mut self, max_distance: f64) -> ContextBuilder<'a> {
+        self.max_distance = Some(max_distance);
+        self
+    }
+
+    /// Set the context key the notes are inserted under. Defaults to `context`.
+    ///
+    /// # Arguments
+    /// @param key: &str
+    /// @returns ContextBuilder
+    pub fn with_key(mut self, key: &str) -> ContextBuilder<'a> {
+        self.key = key.to_string();
+        self
+    }
+
+    /// Never select a note.
+    ///
+    /// # Arguments
+    /// @param path: PathBuf - Relative to the vault root
+    /// @returns ContextBuilder
+    pub fn exclude(mut self, path: PathBuf) -> ContextBuilder<'a> {
+        self.excluded.push(path);
+        self
+    }
+
+    /// Select the notes nearest to a query text, embedded with the driver.
+    ///
+    /// # Arguments
+    /// @param driver: &AIDriver
+    /// @param query: &str
+    /// @returns Result<RetrievedContext>
+    pub async fn build_for_query(&self, driver: &AIDriver, query: &str) -> Result<RetrievedContext> {
+        let embedding = driver.get_embedding(query).await?;
+        self.build_for_embedding(&embedding)
+    }
+
+    /// Select the notes nearest to a seed note, which is itself left out.
+    ///
+    /// # Arguments
+    /// @param seed: &Path - Relative to the vault root
+    /// @returns Result<RetrievedContext> - Errors with `PathNotFound` if the seed is not in the vault or has no embedding
+    pub fn build_for_note(&self, seed: &Path) -> Result<RetrievedContext> {
+        let embedding = self
+            .vault
+            .get_file(&seed.to_path_buf())
+            .and_then(|file| file.get_embedding())
+            .ok_or(Error::PathNotFound(seed.to_path_buf()))?;
+        self.clone().exclude(seed.to_path_buf()).build_for_embedding(embedding)
+    }
+
+    /// Select the notes nearest to an embedding.
+    ///
+    /// # Arguments
+    /// @param embedding: &[f64]
+    /// @returns Result<RetrievedContext>
+    pub fn build_for_embedding(&self, embedding: &[f64]) -> Result<RetrievedContext> {
+        let candidates = self
+            .vault
+            .get_closest_files_to_embedding(embedding, self.top_k + self.excluded.len())?;
+        let mut budget = self.max_tokens * CHARACTERS_PER_TOKEN as usize;
+        let mut notes = Vec::new();
+        for (path, distance) in candidates {
+            if notes.len() == self.top_k || budget == 0 {
+                break;
+            }
+            if self.excluded.contains(&path) || self.max_distance.is_some_and(|max| distance > max) {
+                continue;
+            }
+            let Some(mdfile) = self.vault.get_file(&path).and_then(|file| file.get_mdfile()) else {
+                continue;
+            };
+            let body = mdfile.get_body().trim();
+            if body.is_empty() {
+                continue;
+            }
+            // the heading counts against the budget too
+            let overhead = note_block(&path, "").chars().count() + 2;
+            let available = budget.saturating_sub(overhead);
+            let truncated = body.chars().count() > available;
+            let text: String = body.chars().take(available).collect();
+            budget = budget.saturating_sub(overhead + text.chars().count());
+            if text.trim().is_empty() {
+                break;
+            }
+            notes.push(RetrievedNote { path, distance, text, truncated });
+        }
+        Ok(RetrievedContext { key: self.key.clone(), notes })
+    }
+}
+
+/// Format a note body under a `### [[name]]` heading.
+///
+/// @private
+fn note_block(path: &Path, text: &str) -> String {
+    let name = path.with_extension("").to_string_lossy().replace('\\', "/");
+    f!("### [[{}]]\n\n{}", name, text)
+}
+
+#[cfg(test)]
+mod retrieval_tests {
+    use super::*;
+    use crate::ai::api::mock::MockDriver;
+
+    #[tokio::test]
+    async fn test_build_for_note_budget() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("seed.md"), "Seed").unwrap();
+        std::fs::write(dir.path().join("a.md"), "a".repeat(100)).unwrap();
+        std::fs::write(dir.path().join("b.md"), "b".repeat(100)).unwrap();
+        let mut vault = Vault::from_path(dir.path().to_path_buf()).unwrap();
+        vault.add_ai_driver(AIDriver::new_mock(MockDriver::new()));
+        vault.update_embeddings().await.unwrap();
+
+        let retrieved = ContextBuilder::new(&vault)
+            .with_max_tokens(40)
+            .build_for_note(Path::new("seed.md"))
+            .unwrap();
+        assert!(!retrieved.sources().contains(&PathBuf::from("seed.md")));
+        assert!(retrieved.text().chars().count() <= 160);
+        assert!(retrieved.notes.iter().any(|note| note.truncated));
+
+        let all = ContextBuilder::new(&vault).build_for_note(Path::new("seed.md")).unwrap();
+        assert_eq!(all.notes.len(), 2);
+        assert!(all.to_context().get("context").unwrap().contains("### [[a]]"));
+    }
+}
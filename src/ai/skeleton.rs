@@ -0,0 +1,321 @@
+//! # obsidian-driver::ai::skeleton
+//!
+//! This module contains the NoteSkeleton struct, which describes the headings a generated note must contain.
+//!
+//! @public NoteSkeleton
+//!
+//! @public NoteSkeleton::new
+//!
+//! @public NoteSkeleton::from_mdfile
+//!
+//! @public NoteSkeleton::missing
+//!
+//! @public NoteSkeleton::instructions
+//!
+//! @public NoteSkeleton::fill
+//!
+//! @public SkeletonHeading
+//!
+//! @public enforce_skeleton
+
+// std imports
+use std::fmt::Display;
+
+// third-party imports
+use serde::{Deserialize, Serialize};
+
+// first-party imports
+use crate::ai::api::AIDriver;
+use crate::ai::prompt::{Context, Prompt, ResponseBudget};
+use crate::file::mdfile::section::Section;
+use crate::file::mdfile::MDFile;
+use crate::prelude::*;
+
+const SKELETON_SYSTEM_PROMPT: &str = "You complete markdown notes that are missing required sections.";
+const SKELETON_USER_PROMPT: &str = r#"The note below is missing the following sections:
+
+[headings]
+
+Write only the missing sections, using exactly these headings, based on the content of the note. Do not repeat the rest of the note.
+
+**Note**
+
+[note]"#;
+
+/// A heading required by a NoteSkeleton.
+///
+/// # Examples
+/// ```
+/// use obsidian_driver::ai::skeleton::SkeletonHeading;
+///
+/// let heading = SkeletonHeading { level: 2, heading: "Takeaways".to_string() };
+/// assert_eq!(heading.to_string(), "## Takeaways");
+/// ```
+/// @public
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SkeletonHeading {
+    pub level: usize,
+    pub heading: String,
+}
+
+impl SkeletonHeading {
+    /// Check whether a parsed section has this heading.
+    ///
+    /// @private
+    fn matches(&self, section: &Section) -> bool {
+        section.level == self.level && section.heading == self.heading
+    }
+}
+
+impl Display for SkeletonHeading {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", "#".repeat(self.level), self.heading)
+    }
+}
+
+/// The NoteSkeleton struct.
+///
+/// This struct lists the headings, in order, that a generated note must contain.
+///
+/// # Examples
+/// ```
+/// use obsidian_driver::ai::skeleton::NoteSkeleton;
+/// use obsidian_driver::file::mdfile::MDFile;
+///
+/// let skeleton = NoteSkeleton::new(&["## Summary", "## Takeaways"]);
+/// let mdfile = MDFile::from_string("## Summary\n\nText".to_string());
+///
+/// let missing: Vec<String> = skeleton.missing(&mdfile).iter().map(|heading| heading.to_string()).collect();
+/// assert_eq!(missing, vec!["## Takeaways".to_string()]);
+/// ```
+/// @public
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NoteSkeleton {
+    headings: Vec<SkeletonHeading>,
+}
+
+impl NoteSkeleton {
+    /// Create a new NoteSkeleton from markdown heading lines. Lines without `#`s are taken as level 2 headings.
+    ///
+    /// # Arguments
+    /// @param headings: &[&str] - The required headings, e.g. `"## Takeaways"`.
+    /// @returns NoteSkeleton - The new NoteSkeleton.
+    pub fn new(headings: &[&str]) -> NoteSkeleton {
+        let headings = headings
+            .iter()
+            .map(|line| {
+                let line = line.trim();
+                let level = line.chars().take_while(|c| *c == '#').count();
+                SkeletonHeading {
+                    level: if level == 0 { 2 } else { level },
+                    heading: line[level..].trim().to_string(),
+                }
+            })
+            .collect();
+        NoteSkeleton { headings }
+    }
+
+    /// Create a new NoteSkeleton from the headings of an existing note, e.g. a template.
+    ///
+    /// # Arguments
+    /// @param mdfile: &MDFile - The note to take the headings from.
+    /// @returns NoteSkeleton - The new NoteSkeleton.
+    ///
+    /// # Examples
+    /// ```
+    /// use obsidian_driver::ai::skeleton::NoteSkeleton;
+    /// use obsidian_driver::file::mdfile::MDFile;
+    ///
+    /// let template = MDFile::from_string("## Summary\n\n## Takeaways\n".to_string());
+    /// assert_eq!(NoteSkeleton::from_mdfile(&template), NoteSkeleton::new(&["## Summary", "## Takeaways"]));
+    /// ```
+    pub fn from_mdfile(mdfile: &MDFile) -> NoteSkeleton {
+        let headings = mdfile
+            .get_sections()
+            .into_iter()
+            .map(|section| SkeletonHeading {
+                level: section.level,
+                heading: section.heading,
+            })
+            .collect();
+        NoteSkeleton { headings }
+    }
+
+    /// Get the required headings, in order.
+    ///
+    /// # Arguments
+    /// @returns &[SkeletonHeading] - The required headings.
+    pub fn headings(&self) -> &[SkeletonHeading] {
+        &self.headings
+    }
+
+    /// Get the required headings that the note does not contain.
+    ///
+    /// # Arguments
+    /// @param mdfile: &MDFile - The note to check.
+    /// @returns Vec<SkeletonHeading> - The missing headings, in skeleton order.
+    pub fn missing(&self, mdfile: &MDFile) -> Vec<SkeletonHeading> {
+        let sections = mdfile.get_sections();
+        self.headings
+            .iter()
+            .filter(|heading| !sections.iter().any(|section| heading.matches(section)))
+            .cloned()
+            .collect()
+    }
+
+    /// Get instructions describing the skeleton, suitable for stacking onto a system prompt.
+    ///
+    /// # Arguments
+    /// @returns String - The instructions.
+    pub fn instructions(&self) -> String {
+        let headings: Vec<String> = self.headings.iter().map(|heading| heading.to_string()).collect();
+        f!(
+            "The note must contain the following headings, exactly as written and in this order:\n{}",
+            headings.join("\n")
+        )
+    }
+
+    /// Insert the missing sections into a note.
+    ///
+    /// Sections are taken from `generated` when it has a matching heading, otherwise an empty
+    /// heading is inserted. Each section is placed after the previous skeleton heading present
+    /// in the note, so the result always contains every heading in skeleton order.
+    ///
+    /// # Arguments
+    /// @param mdfile: &mut MDFile - The note to fill.
+    /// @param generated: &MDFile - A note containing the missing sections.
+    ///
+    /// # Examples
+    /// ```
+    /// use obsidian_driver::ai::skeleton::NoteSkeleton;
+    /// use obsidian_driver::file::mdfile::MDFile;
+    ///
+    /// let skeleton = NoteSkeleton::new(&["## Summary", "## Takeaways"]);
+    /// let mut mdfile = MDFile::from_string("## Takeaways\n\n- One\n".to_string());
+    /// let generated = MDFile::from_string("## Summary\n\nShort.\n".to_string());
+    ///
+    /// skeleton.fill(&mut mdfile, &generated);
+    /// assert_eq!(mdfile.get_body(), "## Summary\n\nShort.\n\n## Takeaways\n\n- One\n");
+    /// ```
+    pub fn fill(&self, mdfile: &mut MDFile, generated: &MDFile) {
+        let generated_sections = generated.get_sections();
+        for (i, wanted) in self.headings.iter().enumerate() {
+            let sections = mdfile.get_sections();
+            let find = |heading: &SkeletonHeading| {
+                sections.iter().find(|section| heading.matches(section)).cloned()
+            };
+            if find(wanted).is_some() {
+                continue;
+            }
+
+            let text = match generated_sections.iter().find(|section| wanted.matches(section)) {
+                Some(section) => generated.get_body()[section.range.clone()].trim_end().to_string(),
+                None => wanted.to_string(),
+            };
+
+            let body = mdfile.get_body();
+            let previous_end = self.headings[..i]
+                .iter()
+                .rev()
+                .find_map(&find)
+                .map(|section| section.range.end);
+            let next_start = self.headings[i + 1..]
+                .iter()
+                .find_map(&find)
+                .map(|section| section.range.start);
+            let position = match (previous_end, next_start) {
+                (Some(end), Some(start)) => end.min(start),
+                (Some(end), None) => end,
+                (None, Some(start)) => start,
+                (None, None) => body.len(),
+            };
+
+            let before = &body[..position];
+            let separator = if before.is_empty() || before.ends_with("\n\n") {
+                ""
+            } else if before.ends_with('\n') {
+                "\n"
+            } else {
+                "\n\n"
+            };
+            let new_body = f!("{}{}{}\n\n{}", before, separator, text, &body[position..]);
+            let new_body = if position == body.len() {
+                f!("{}\n", new_body.trim_end())
+            } else {
+                new_body
+            };
+            mdfile.set_body(new_body);
+        }
+    }
+}
+
+/// Make sure a note contains every heading in the skeleton
+///
+/// If headings are missing, the AI model is asked to write only the missing sections, which are
+/// then inserted into the note in skeleton order. Headings the model still does not provide are
+/// inserted empty.
+///
+/// # Arguments
+/// @param driver: &AIDriver - The AI driver to use for writing the missing sections
+/// @param mdfile: &mut MDFile - The note to check and fill
+/// @param skeleton: &NoteSkeleton - The required headings
+/// @returns Result<()> - Errors with `InvalidFrontmatter` if the reply has invalid front matter; the note is then left as it was.
+///
+/// # Example
+/// ```
+/// use std::path::PathBuf;
+///
+/// use obsidian_driver::ai::api::AIDriver;
+/// use obsidian_driver::ai::skeleton::{enforce_skeleton, NoteSkeleton};
+/// use obsidian_driver::file::mdfile::MDFile;
+///
+/// async fn enforce_skeleton_example() {
+/// 	let openai_config_path = PathBuf::from(".openai_config.json");
+/// 	let driver = AIDriver::new_openai_from_config_path(openai_config_path).await.unwrap();
+/// 	let skeleton = NoteSkeleton::new(&["## Summary", "## Takeaways"]);
+/// 	let mut mdfile = MDFile::from_string("## Summary\n\nText".to_string());
+/// 	enforce_skeleton(&driver, &mut mdfile, &skeleton).await.unwrap();
+/// }
+/// ```
+/// @public
+pub async fn enforce_skeleton(driver: &AIDriver, mdfile: &mut MDFile, skeleton: &NoteSkeleton) -> Result<()> {
+    let missing = skeleton.missing(mdfile);
+    if missing.is_empty() {
+        return Ok(());
+    }
+    let headings: Vec<String> = missing.iter().map(|heading| heading.to_string()).collect();
+    let mut context = Context::default();
+    context.insert("headings", &headings.join("\n"));
+    context.insert("note", &mdfile.to_string());
+    let prompt = Prompt::new(SKELETON_SYSTEM_PROMPT, SKELETON_USER_PROMPT, ResponseBudget::Unlimited).substitute(&context)?;
+
+    let response = driver.chat_smart(prompt).await?;
+    let generated = MDFile::parse(&response)?;
+    skeleton.fill(mdfile, &generated);
+    Ok(())
+}
+
+#[cfg(test)]
+mod skeleton_tests {
+    use super::*;
+
+    #[test]
+    fn test_fill_keeps_skeleton_order() {
+        let skeleton = NoteSkeleton::new(&["# Title", "## Summary", "## Details", "## Takeaways"]);
+        let mut mdfile = MDFile::from_string("# Title\n\nIntro\n\n## Details\n\nMore".to_string());
+        let generated = MDFile::from_string("## Takeaways\n\n- One\n\n## Summary\n\nShort.".to_string());
+
+        skeleton.fill(&mut mdfile, &generated);
+        let expected = "# Title\n\nIntro\n\n## Summary\n\nShort.\n\n## Details\n\nMore\n\n## Takeaways\n\n- One\n";
+        assert_eq!(mdfile.get_body(), expected);
+        assert!(skeleton.missing(&mdfile).is_empty());
+    }
+
+    #[test]
+    fn test_fill_inserts_empty_heading_when_not_generated() {
+        let skeleton = NoteSkeleton::new(&["Takeaways"]);
+        let mut mdfile = MDFile::from_string("Body".to_string());
+        skeleton.fill(&mut mdfile, &MDFile::from_string(String::new()));
+        assert_eq!(mdfile.get_body(), "Body\n\n## Takeaways\n");
+    }
+}
@@ -0,0 +1,45 @@
+//! # obsidian-driver::ai::structured
+//!
+//! This module contains structured output: prompts that ask for a JSON reply, and the parsing of that reply into Rust types, for pipelines that write responses into front matter or notes of a fixed shape.
+//!
+//! @public JSON_SYSTEM_PROMPT
+//!
+//! @public parse_json_response
+
+// third-party imports
+use serde::de::DeserializeOwned;
+
+// first-party imports
+use crate::prelude::*;
+
+/// Output rules for prompts whose response is parsed with `parse_json_response`. Stack it onto a task prompt with `Prompt::stack_system_prompt`.
+///
+/// @public
+pub const JSON_SYSTEM_PROMPT: &str = "Reply with valid JSON only, in the shape the user asks for: no text before or after it, no comments, and no code fences.";
+
+/// Parse the JSON reply of a model.
+///
+/// A code fence around the JSON, which models add despite being told not to, is removed first.
+///
+/// # Arguments
+/// @param response: &str - The response of the model
+/// @returns Result<T> - Errors with `InvalidChatResponse` and the response if it is not JSON of the type
+///
+/// # Example
+/// ```
+/// use obsidian_driver::ai::structured::parse_json_response;
+///
+/// let terms: Vec<String> = parse_json_response("```json\n[\"mitosis\", \"meiosis\"]\n```").unwrap();
+/// assert_eq!(terms, vec!["mitosis".to_string(), "meiosis".to_string()]);
+/// assert!(parse_json_response::<Vec<String>>("Sure! Here are the terms").is_err());
+/// ```
+/// @public
+pub fn parse_json_response<T: DeserializeOwned>(response: &str) -> Result<T> {
+    let mut json = response.trim();
+    if let Some(fenced) = json.strip_prefix("```") {
+        // drop the language of the fence, e.g. ```json
+        json = fenced.split_once('\n').map(|(_, rest)| rest).unwrap_or_default();
+        json = json.trim_end().strip_suffix("```").unwrap_or(json);
+    }
+    serde_json::from_str(json).map_err(|e| Error::InvalidChatResponse(f!("{}: {}", e, response)))
+}
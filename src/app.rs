@@ -0,0 +1,501 @@
+//! obsidian-driver::app
+//!
+//! This module contains a client for a running Obsidian through the community Local REST API plugin, so changes made by this crate show up in the editor at once: notes are written through the app, opened in it, and its commands run.
+//!
+//! The plugin serves HTTPS on `127.0.0.1:27124` with a self-signed certificate, and plain HTTP on port 27123 if enabled; its API key is in the plugin settings. Writing through the app assumes it has the same vault open as the Vault.
+//!
+//! @public LocalRestClient
+//!
+//! @public AppStatus
+//!
+//! @public AppCommand
+//!
+//! @public Vault::write_file_via_app
+//!
+//! @public Vault::write_all_via_app
+
+// std imports
+use std::path::{Path, PathBuf};
+
+// third-party imports
+use reqwest::{Client, Method, RequestBuilder, Response, StatusCode};
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+// first-party imports
+use crate::batch::BatchReport;
+use crate::file::vault::Vault;
+use crate::prelude::*;
+use crate::sync::{remote_key, uri_encode};
+
+/// The HTTPS address of the plugin.
+const DEFAULT_BASE_URL: &str = "https://127.0.0.1:27124";
+
+/// The status of the app, from `GET /`.
+///
+/// @public
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AppStatus {
+    // whether the API key was accepted
+    pub authenticated: bool,
+    pub obsidian_version: Option<String>,
+    pub plugin_version: Option<String>,
+}
+
+/// A command of the app's command palette.
+///
+/// @public
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AppCommand {
+    // e.g. `app:reload` or `editor:toggle-bold`
+    pub id: String,
+    pub name: String,
+}
+
+/// LocalRestClient struct
+///
+/// A client for the Local REST API plugin. Paths are relative to the vault root.
+///
+/// # Example
+/// ```
+/// use std::path::Path;
+///
+/// use obsidian_driver::app::LocalRestClient;
+///
+/// async fn app_example() {
+/// 	let app = LocalRestClient::new("api-key-from-the-plugin-settings");
+/// 	app.put_note(Path::new("Inbox/Idea.md"), "# Idea").await.unwrap();
+/// 	app.open(Path::new("Inbox/Idea.md"), false).await.unwrap();
+/// }
+/// ```
+///
+/// @public
+#[derive(Clone, Debug)]
+pub struct LocalRestClient {
+    // without a trailing `/`
+    base_url: String,
+    api_key: String,
+    // accept invalid certificates from hosts other than this machine
+    accept_invalid_certs: bool,
+    client: Client,
+}
+
+/// The body of `GET /`.
+///
+/// @private
+#[derive(Deserialize)]
+struct StatusBody {
+    #[serde(default)]
+    authenticated: bool,
+    #[serde(default)]
+    versions: StatusVersions,
+}
+
+/// The versions in the body of `GET /`.
+///
+/// @private
+#[derive(Default, Deserialize)]
+struct StatusVersions {
+    obsidian: Option<String>,
+    #[serde(rename = "self")]
+    plugin: Option<String>,
+}
+
+/// The body of `GET /vault/<folder>/`.
+///
+/// @private
+#[derive(Deserialize)]
+struct FilesBody {
+    files: Vec<String>,
+}
+
+/// The body of `GET /commands/`.
+///
+/// @private
+#[derive(Deserialize)]
+struct CommandsBody {
+    commands: Vec<AppCommand>,
+}
+
+impl LocalRestClient {
+    /// Create a client for the plugin on this machine, over HTTPS. The plugin's self-signed certificate is accepted.
+    ///
+    /// # Arguments
+    /// @param api_key: &str
+    /// @return LocalRestClient
+    pub fn new(api_key: &str) -> Self {
+        LocalRestClient {
+            base_url: DEFAULT_BASE_URL.to_string(),
+            api_key: api_key.to_string(),
+            accept_invalid_certs: false,
+            client: build_client(DEFAULT_BASE_URL, false),
+        }
+    }
+
+    /// Talk to the plugin at another address, e.g. `http://127.0.0.1:27123` for the insecure server.
+    ///
+    /// Invalid certificates are only accepted from `localhost`, `127.0.0.1` and `::1`, unless
+    /// `with_accept_invalid_certs` opts in for other hosts.
+    ///
+    /// # Arguments
+    /// @param base_url: &str
+    /// @return LocalRestClient
+    pub fn with_base_url(mut self, base_url: &str) -> Self {
+        self.base_url = base_url.trim_end_matches('/').to_string();
+        self.client = build_client(&self.base_url, self.accept_invalid_certs);
+        self
+    }
+
+    /// Accept invalid certificates from any host, e.g. to reach the plugin on another machine through a tunnel.
+    ///
+    /// # Arguments
+    /// @param accept: bool
+    /// @return LocalRestClient
+    pub fn with_accept_invalid_certs(mut self, accept: bool) -> Self {
+        self.accept_invalid_certs = accept;
+        self.client = build_client(&self.base_url, accept);
+        self
+    }
+
+    /// Start a request to the plugin.
+    ///
+    /// @private
+    fn request(&self, method: Method, endpoint: &str) -> RequestBuilder {
+        debug!(%method, endpoint, "app request");
+        self.client.request(method, f!("{}{}", self.base_url, endpoint)).bearer_auth(&self.api_key)
+    }
+
+    /// Get the status of the app; works without a valid API key.
+    ///
+    /// # Arguments
+    /// @return Result<AppStatus> - Errors if Obsidian is not running or the plugin is disabled
+    pub async fn status(&self) -> Result<AppStatus> {
+        let body: StatusBody = check(self.request(Method::GET, "/").send().await?, "status").await?.json().await?;
+        Ok(AppStatus {
+            authenticated: body.authenticated,
+            obsidian_version: body.versions.obsidian,
+            plugin_version: body.versions.plugin,
+        })
+    }
+
+    /// List a folder of the vault, as the app sees it. Subfolders end with `/`.
+    ///
+    /// # Arguments
+    /// @param folder: &Path - Empty for the vault root
+    /// @return Result<Vec<PathBuf>> - Relative to the vault root
+    pub async fn list(&self, folder: &Path) -> Result<Vec<PathBuf>> {
+        let key = remote_key(folder);
+        let endpoint = match key.is_empty() {
+            true => "/vault/".to_string(),
+            false => f!("/vault/{}/", uri_encode(&key, true)),
+        };
+        let body: FilesBody = check(self.request(Method::GET, &endpoint).send().await?, "list").await?.json().await?;
+        Ok(body.files.into_iter().map(|name| folder.join(name)).collect())
+    }
+
+    /// Get the contents of a note from the app, including unsaved edits in its editor.
+    ///
+    /// # Arguments
+    /// @param path: &Path
+    /// @return Result<String> - Errors with `PathNotFound` if the app has no such file
+    pub async fn get_note(&self, path: &Path) -> Result<String> {
+        let response = self.request(Method::GET, &note_endpoint("vault", path)).header("Accept", "text/markdown").send().await?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Err(Error::PathNotFound(path.to_path_buf()));
+        }
+        Ok(check(response, "get").await?.text().await?)
+    }
+
+    /// Create or replace a note through the app, which updates open editors.
+    ///
+    /// # Arguments
+    /// @param path: &Path
+    /// @param contents: &str
+    /// @return Result<()>
+    pub async fn put_note(&self, path: &Path, contents: &str) -> Result<()> {
+        let request = self
+            .request(Method::PUT, &note_endpoint("vault", path))
+            .header("Content-Type", "text/markdown")
+            .body(contents.to_string());
+        check(request.send().await?, "put").await?;
+        Ok(())
+    }
+
+    /// Append text to a note through the app, creating it if needed.
+    ///
+    /// # Arguments
+    /// @param path: &Path
+    /// @param text: &str
+    /// @return Result<()>
+    pub async fn append(&self, path: &Path, text: &str) -> Result<()> {
+        let request = self
+            .request(Method::POST, &note_endpoint("vault", path))
+            .header("Content-Type", "text/markdown")
+            .body(text.to_string());
+        check(request.send().await?, "append").await?;
+        Ok(())
+    }
+
+    /// Delete a file through the app.
+    ///
+    /// # Arguments
+    /// @param path: &Path
+    /// @return Result<()> - A missing file is not an error
+    pub async fn delete(&self, path: &Path) -> Result<()> {
+        let response = self.request(Method::DELETE, &note_endpoint("vault", path)).send().await?;
+        if response.status() != StatusCode::NOT_FOUND {
+            check(response, "delete").await?;
+        }
+        Ok(())
+    }
+
+    /// Open a file in the app, the way clicking it in the file explorer does.
+    ///
+    /// # Arguments
+    /// @param path: &Path
+    /// @param new_leaf: bool - Open it in a new tab instead of the active one
+    /// @return Result<()>
+    pub async fn open(&self, path: &Path, new_leaf: bool) -> Result<()> {
+        let mut endpoint = note_endpoint("open", path);
+        if new_leaf {
+            endpoint.push_str("?newLeaf=true");
+        }
+        check(self.request(Method::POST, &endpoint).send().await?, "open").await?;
+        Ok(())
+    }
+
+    /// List the commands of the app.
+    ///
+    /// # Arguments
+    /// @return Result<Vec<AppCommand>>
+    pub async fn commands(&self) -> Result<Vec<AppCommand>> {
+        let body: CommandsBody = check(self.request(Method::GET, "/commands/").send().await?, "commands").await?.json().await?;
+        Ok(body.commands)
+    }
+
+    /// Run a command of the app, e.g. `app:reload` to reload the whole app.
+    ///
+    /// # Arguments
+    /// @param id: &str - See `LocalRestClient::commands`
+    /// @return Result<()>
+    pub async fn run_command(&self, id: &str) -> Result<()> {
+        let endpoint = f!("/commands/{}/", uri_encode(id, false));
+        check(self.request(Method::POST, &endpoint).send().await?, "command").await?;
+        Ok(())
+    }
+}
+
+impl Vault {
+    /// Write a file of the Vault through the running app instead of directly to disk, if its contents differ from the file on disk, so open editors show the change.
+    ///
+    /// Backups, timestamps and events are handled as by `Vault::write_file`.
+    ///
+    /// # Arguments
+    /// @param app: &LocalRestClient
+    /// @param path: &PathBuf
+    /// @return Result<bool> - Whether the file was written
+    ///
+    /// # Example
+    /// ```should_panic
+    /// use std::path::PathBuf;
+    ///
+    /// use obsidian_driver::app::LocalRestClient;
+    /// use obsidian_driver::file::vault::Vault;
+    ///
+    /// async fn write_file_via_app_example() {
+    /// 	let mut vault = Vault::from_path(PathBuf::from("vault")).unwrap();
+    /// 	let app = LocalRestClient::new("api-key");
+    /// 	let path = PathBuf::from("note.md");
+    /// 	vault.get_file_mut(&path).unwrap().get_mdfile_mut().unwrap().set_body("# Rewritten".to_string());
+    /// 	vault.write_file_via_app(&app, &path).await.unwrap();
+    /// }
+    /// # panic!("needs a running Obsidian");
+    /// ```
+    pub async fn write_file_via_app(&mut self, app: &LocalRestClient, path: &PathBuf) -> Result<bool> {
+        let Some(contents) = self.prepare_write(path)? else {
+            return Ok(false);
+        };
+        app.put_note(path, &contents).await?;
+        self.finish_write(path, contents)?;
+        Ok(true)
+    }
+
+    /// Write every dirty file of the Vault through the running app, see `Vault::write_file_via_app`.
+    ///
+    /// A failure to write one file does not stop the others.
+    ///
+    /// # Arguments
+    /// @param app: &LocalRestClient
+    /// @return BatchReport - `completed` lists the files written, `failed` the files that could not be, with the error
+    pub async fn write_all_via_app(&mut self, app: &LocalRestClient) -> BatchReport {
+        let mut report = BatchReport::default();
+        for path in self.dirty_files() {
+            match self.write_file_via_app(app, &path).await {
+                Ok(true) => report.completed.push(path),
+                Ok(false) => {}
+                Err(e) => report.failed.push((path, e.to_string())),
+            }
+        }
+        report
+    }
+}
+
+/// The endpoint of a file under `/vault/` or `/open/`.
+///
+/// @private
+fn note_endpoint(kind: &str, path: &Path) -> String {
+    f!("/{}/{}", kind, uri_encode(&remote_key(path), true))
+}
+
+/// Fail with `AppRequestFailed` if a response is not a success. The plugin sends `{"message": ..., "errorCode": ...}` on errors.
+///
+/// @private
+async fn check(response: Response, what: &str) -> Result<Response> {
+    if response.status().is_success() {
+        return Ok(response);
+    }
+    let status = response.status();
+    let body = response.text().await.unwrap_or_default();
+    let message = serde_json::from_str::<serde_json::Value>(&body)
+        .ok()
+        .and_then(|value| value.get("message").and_then(|message| message.as_str()).map(str::to_string))
+        .unwrap_or(body);
+    Err(Error::AppRequestFailed(f!("{} failed with {}: {}", what, status, message)))
+}
+
+/// Build the HTTP client for a base URL. Invalid certificates are accepted from this machine, or from any host if asked to.
+///
+/// # Arguments
+/// @param base_url: &str
+/// @param accept_invalid_certs: bool
+/// @return Client
+///
+/// @private
+fn build_client(base_url: &str, accept_invalid_certs: bool) -> Client {
+    Client::builder()
+        .danger_accept_invalid_certs(accept_invalid_certs || is_loopback(base_url))
+        .build()
+        .expect("the TLS backend can be initialised")
+}
+
+/// Whether a base URL points at this machine: `localhost` or a loopback address.
+///
+/// # Arguments
+/// @param base_url: &str
+/// @return bool
+///
+/// @private
+fn is_loopback(base_url: &str) -> bool {
+    let Some(host) = reqwest::Url::parse(base_url).ok().and_then(|url| url.host_str().map(str::to_string)) else {
+        return false;
+    };
+    host.eq_ignore_ascii_case("localhost")
+        || host
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .parse::<std::net::IpAddr>()
+            .is_ok_and(|ip| ip.is_loopback())
+}
+
+#[cfg(test)]
+mod app_tests {
+    use super::*;
+    use std::io::{Read, Write};
+    use std::sync::{Arc, Mutex};
+
+    /// Answer each request on a local port with the next canned response, recording the requests.
+    fn fake_app(responses: Vec<(u16, &'static str)>) -> (String, Arc<Mutex<Vec<String>>>) {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = f!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let recorded = requests.clone();
+        std::thread::spawn(move || {
+            for (status, body) in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut request = Vec::new();
+                let mut buffer = [0u8; 4096];
+                loop {
+                    let read = stream.read(&mut buffer).unwrap();
+                    request.extend_from_slice(&buffer[..read]);
+                    let text = String::from_utf8_lossy(&request).to_string();
+                    if let Some(end) = text.find("\r\n\r\n") {
+                        let length = text
+                            .lines()
+                            .find_map(|line| line.to_lowercase().strip_prefix("content-length:").map(|n| n.trim().parse::<usize>().unwrap()))
+                            .unwrap_or(0);
+                        if request.len() >= end + 4 + length || read == 0 {
+                            break;
+                        }
+                    }
+                }
+                recorded.lock().unwrap().push(String::from_utf8_lossy(&request).to_string());
+                let response = f!("HTTP/1.1 {} X\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}", status, body.len(), body);
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+        });
+        (base_url, requests)
+    }
+
+    #[tokio::test]
+    async fn test_client_requests() {
+        let (base_url, requests) = fake_app(vec![
+            (200, r#"{"status":"OK","authenticated":true,"versions":{"obsidian":"1.6.7","self":"3.0.1"}}"#),
+            (200, r#"{"files":["Lecture 1.md","Labs/"]}"#),
+            (404, r#"{"message":"File not found","errorCode":40400}"#),
+            (204, ""),
+            (400, r#"{"message":"Bad command","errorCode":40000}"#),
+        ]);
+        let app = LocalRestClient::new("key").with_base_url(&base_url);
+
+        let status = app.status().await.unwrap();
+        assert!(status.authenticated);
+        assert_eq!(status.plugin_version.as_deref(), Some("3.0.1"));
+        let files = app.list(Path::new("Courses")).await.unwrap();
+        assert_eq!(files, vec![PathBuf::from("Courses/Lecture 1.md"), PathBuf::from("Courses/Labs/")]);
+        assert!(matches!(app.get_note(Path::new("Missing.md")).await, Err(Error::PathNotFound(_))));
+        app.open(Path::new("Courses/Lecture 1.md"), true).await.unwrap();
+        let Err(Error::AppRequestFailed(message)) = app.run_command("nope").await else {
+            panic!("expected the command to fail");
+        };
+        assert!(message.ends_with("Bad command"));
+
+        let requests = requests.lock().unwrap();
+        assert!(requests[0].starts_with("GET / HTTP/1.1"));
+        assert!(requests[0].to_lowercase().contains("authorization: bearer key"));
+        assert!(requests[1].starts_with("GET /vault/Courses/ HTTP/1.1"));
+        assert!(requests[3].starts_with("POST /open/Courses/Lecture%201.md?newLeaf=true HTTP/1.1"));
+    }
+
+    #[tokio::test]
+    async fn test_write_file_via_app() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.md"), "# A").unwrap();
+        let mut vault = Vault::from_path(dir.path().to_path_buf()).unwrap();
+        let (base_url, requests) = fake_app(vec![(204, "")]);
+        let app = LocalRestClient::new("key").with_base_url(&base_url);
+
+        assert!(!vault.write_file_via_app(&app, &"a.md".into()).await.unwrap());
+        vault.get_file_mut(&"a.md".into()).unwrap().get_mdfile_mut().unwrap().set_body("# A\n\nEdited".to_string());
+        // the fake app does not write the file, as Obsidian would
+        let report = vault.write_all_via_app(&app).await;
+        assert_eq!(report.completed, vec![PathBuf::from("a.md")]);
+        assert!(vault.dirty_files().is_empty());
+        let requests = requests.lock().unwrap();
+        assert!(requests[0].starts_with("PUT /vault/a.md HTTP/1.1"));
+        assert!(requests[0].ends_with("# A\n\nEdited"));
+    }
+
+    #[test]
+    fn test_invalid_certs_only_for_loopback() {
+        assert!(is_loopback(DEFAULT_BASE_URL));
+        assert!(is_loopback("https://localhost:27124"));
+        assert!(is_loopback("https://[::1]:27124"));
+        assert!(!is_loopback("https://192.168.1.20:27124"));
+        assert!(!is_loopback("https://obsidian.example.com"));
+        assert!(!is_loopback("not a url"));
+
+        let app = LocalRestClient::new("key").with_base_url("https://192.168.1.20:27124");
+        assert!(!app.accept_invalid_certs);
+        assert!(app.with_accept_invalid_certs(true).accept_invalid_certs);
+    }
+}
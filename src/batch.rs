@@ -0,0 +1,130 @@
+//! obsidian-driver::batch
+//!
+//! This module contains the types shared by long-running batch jobs: the cancellation token they listen to, the report they return, and the checkpoint they persist so an interrupted job can be resumed.
+//!
+//! @public CancellationToken
+//!
+//! @public BatchStatus
+//!
+//! @public BatchReport
+//!
+//! @public Checkpoint
+
+// std imports
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+
+// third-party imports
+use serde::{Deserialize, Serialize};
+pub use tokio_util::sync::CancellationToken;
+
+// first-party imports
+use crate::prelude::*;
+
+/// How a batch job ended.
+///
+/// @public
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BatchStatus {
+    /// Every item was attempted.
+    #[default]
+    Completed,
+    /// The cancellation token fired; in-flight items were finished and the rest were not started.
+    Cancelled,
+}
+
+/// BatchReport struct
+///
+/// The outcome of a batch job. Items are identified by path: the note path relative to the vault
+/// root for embedding jobs, the output path for generation jobs.
+///
+/// @public
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BatchReport {
+    pub status: BatchStatus,
+    pub completed: Vec<PathBuf>,
+    pub failed: Vec<(PathBuf, String)>,
+    pub remaining: Vec<PathBuf>,
+}
+
+/// Checkpoint struct
+///
+/// The items a batch job has finished, persisted as JSON so a later run can skip them.
+///
+/// # Example
+/// ```
+/// use std::path::PathBuf;
+///
+/// use obsidian_driver::batch::Checkpoint;
+///
+/// let mut checkpoint = Checkpoint::default();
+/// checkpoint.complete(PathBuf::from("output/Lecture 1.md"));
+/// assert!(checkpoint.is_complete(&PathBuf::from("output/Lecture 1.md")));
+/// ```
+/// @public
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    completed: BTreeSet<PathBuf>,
+}
+
+impl Checkpoint {
+    /// Load a checkpoint, or start an empty one if the file does not exist.
+    ///
+    /// # Arguments
+    /// @param path: &PathBuf
+    /// @return Result<Checkpoint>
+    pub fn load(path: &PathBuf) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let checkpoint_str = std::fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&checkpoint_str)?)
+    }
+
+    /// Write the checkpoint to disk.
+    ///
+    /// # Arguments
+    /// @param path: &PathBuf
+    /// @return Result<()>
+    pub fn save(&self, path: &PathBuf) -> Result<()> {
+        let checkpoint_str = serde_json::to_string(self)?;
+        crate::file::write_atomic(path, checkpoint_str)?;
+        Ok(())
+    }
+
+    /// Mark an item as finished.
+    ///
+    /// # Arguments
+    /// @param item: PathBuf
+    pub fn complete(&mut self, item: PathBuf) {
+        self.completed.insert(item);
+    }
+
+    /// Check whether an item was finished by a previous run.
+    ///
+    /// # Arguments
+    /// @param item: &PathBuf
+    /// @return bool
+    pub fn is_complete(&self, item: &PathBuf) -> bool {
+        self.completed.contains(item)
+    }
+}
+
+#[cfg(test)]
+mod batch_tests {
+    use super::*;
+
+    #[test]
+    fn test_checkpoint_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("checkpoint.json");
+        assert_eq!(Checkpoint::load(&path).unwrap(), Checkpoint::default());
+
+        let mut checkpoint = Checkpoint::default();
+        checkpoint.complete(PathBuf::from("a.md"));
+        checkpoint.save(&path).unwrap();
+        let loaded = Checkpoint::load(&path).unwrap();
+        assert!(loaded.is_complete(&PathBuf::from("a.md")));
+        assert!(!loaded.is_complete(&PathBuf::from("b.md")));
+    }
+}
@@ -0,0 +1,577 @@
+//! obsidian-driver::daemon
+//!
+//! This module contains the VaultDaemon, which keeps a Vault up to date for as long as it runs: it polls the vault folder for changed and deleted files, links and tags the notes that changed, embeds new and changed notes, runs scheduled jobs, and saves its state so a restart only picks up what changed in between. It replaces re-scanning the whole vault from cron.
+//!
+//! @public TagRule
+//!
+//! @public DaemonOptions
+//!
+//! @public DaemonTick
+//!
+//! @public VaultDaemon
+//!
+//! @public schedule
+
+// std imports
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+// third-party imports
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use serde_yaml::{Mapping, Value};
+use tracing::{debug, info, warn};
+
+// first-party imports
+use crate::batch::CancellationToken;
+use crate::file::vault::query::Query;
+use crate::file::vault::Vault;
+use crate::prelude::*;
+
+// submodules
+pub mod schedule;
+
+use schedule::{Job, JobState, JobTask};
+
+/// Where the daemon keeps its state, relative to the vault root.
+const STATE_PATH: &str = ".obsidian-driver/daemon/vault_cache.json";
+
+/// Where the daemon keeps the last run of each job, relative to the vault root.
+const JOBS_STATE_PATH: &str = ".obsidian-driver/daemon/jobs.json";
+
+/// A rule adding a tag to the front matter of changed notes that match a query.
+///
+/// # Example
+/// ```
+/// use obsidian_driver::daemon::TagRule;
+///
+/// let rule = TagRule::parse("FROM \"Courses/Biology\"", "biology").unwrap();
+/// ```
+///
+/// @public
+#[derive(Clone, Debug, PartialEq)]
+pub struct TagRule {
+    pub query: Query,
+    // without the `#`
+    pub tag: String,
+}
+
+impl TagRule {
+    /// Create a rule from a query string, see `Query::parse`.
+    ///
+    /// # Arguments
+    /// @param query: &str
+    /// @param tag: &str - With or without the `#`
+    /// @return Result<TagRule>
+    pub fn parse(query: &str, tag: &str) -> Result<Self> {
+        Ok(TagRule {
+            query: Query::parse(query)?,
+            tag: tag.trim_start_matches('#').to_string(),
+        })
+    }
+}
+
+/// DaemonOptions struct
+///
+/// # Example
+/// ```
+/// use std::time::Duration;
+///
+/// use obsidian_driver::daemon::{DaemonOptions, TagRule};
+///
+/// let options = DaemonOptions::default()
+/// 	.with_poll_interval(Duration::from_secs(10))
+/// 	.with_embeddings(true)
+/// 	.with_autolink(true)
+/// 	.with_tag_rule(TagRule::parse("FROM \"Inbox\"", "inbox").unwrap());
+/// ```
+///
+/// @public
+#[derive(Clone, Debug, PartialEq)]
+pub struct DaemonOptions {
+    // the time between two scans of the vault folder
+    pub poll_interval: Duration,
+    // embed new and changed notes; the vault needs an AI driver
+    pub embed: bool,
+    // the number of embedding requests in flight at once
+    pub embed_concurrency: usize,
+    // turn unlinked mentions of other notes in changed notes into links, see `Vault::suggest_links`
+    pub autolink: bool,
+    pub tag_rules: Vec<TagRule>,
+    // recurring jobs, see `schedule::load_jobs`
+    pub jobs: Vec<Job>,
+    // the cache file holding the state, relative to the vault root unless absolute
+    pub state_path: PathBuf,
+    // the file holding the last run of each job, relative to the vault root unless absolute
+    pub jobs_state_path: PathBuf,
+}
+
+impl Default for DaemonOptions {
+    fn default() -> Self {
+        DaemonOptions {
+            poll_interval: Duration::from_secs(30),
+            embed: false,
+            embed_concurrency: 8,
+            autolink: false,
+            tag_rules: Vec::new(),
+            jobs: Vec::new(),
+            state_path: PathBuf::from(STATE_PATH),
+            jobs_state_path: PathBuf::from(JOBS_STATE_PATH),
+        }
+    }
+}
+
+impl DaemonOptions {
+    /// Set the time between two scans of the vault folder.
+    ///
+    /// # Arguments
+    /// @param poll_interval: Duration
+    /// @return DaemonOptions
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    /// Embed new and changed notes.
+    ///
+    /// # Arguments
+    /// @param embed: bool
+    /// @return DaemonOptions
+    pub fn with_embeddings(mut self, embed: bool) -> Self {
+        self.embed = embed;
+        self
+    }
+
+    /// Link unlinked mentions in changed notes.
+    ///
+    /// # Arguments
+    /// @param autolink: bool
+    /// @return DaemonOptions
+    pub fn with_autolink(mut self, autolink: bool) -> Self {
+        self.autolink = autolink;
+        self
+    }
+
+    /// Add a tag rule.
+    ///
+    /// # Arguments
+    /// @param rule: TagRule
+    /// @return DaemonOptions
+    pub fn with_tag_rule(mut self, rule: TagRule) -> Self {
+        self.tag_rules.push(rule);
+        self
+    }
+
+    /// Add recurring jobs.
+    ///
+    /// # Arguments
+    /// @param jobs: Vec<Job>
+    /// @return DaemonOptions
+    pub fn with_jobs(mut self, jobs: Vec<Job>) -> Self {
+        self.jobs.extend(jobs);
+        self
+    }
+
+    /// Keep the state in another file.
+    ///
+    /// # Arguments
+    /// @param state_path: PathBuf - Relative to the vault root unless absolute
+    /// @return DaemonOptions
+    pub fn with_state_path(mut self, state_path: PathBuf) -> Self {
+        self.state_path = state_path;
+        self
+    }
+
+    /// Keep the last run of each job in another file.
+    ///
+    /// # Arguments
+    /// @param jobs_state_path: PathBuf - Relative to the vault root unless absolute
+    /// @return DaemonOptions
+    pub fn with_jobs_state_path(mut self, jobs_state_path: PathBuf) -> Self {
+        self.jobs_state_path = jobs_state_path;
+        self
+    }
+}
+
+/// DaemonTick struct
+///
+/// What one pass of the daemon did. Paths are relative to the vault root.
+///
+/// @public
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DaemonTick {
+    // added or modified on disk since the last pass
+    pub changed: Vec<PathBuf>,
+    // deleted on disk since the last pass
+    pub removed: Vec<PathBuf>,
+    // notes that received links
+    pub linked: Vec<PathBuf>,
+    // notes that received tags
+    pub tagged: Vec<PathBuf>,
+    pub embedded: Vec<PathBuf>,
+    pub failed: Vec<(PathBuf, String)>,
+    // the names of the jobs that ran
+    pub jobs_run: Vec<String>,
+    // jobs that failed, with the error; they run again at their next occurrence
+    pub jobs_failed: Vec<(String, String)>,
+}
+
+impl DaemonTick {
+    /// Whether the pass changed anything.
+    ///
+    /// # Arguments
+    /// @return bool
+    pub fn is_empty(&self) -> bool {
+        *self == DaemonTick::default()
+    }
+}
+
+/// VaultDaemon struct
+///
+/// Keeps a Vault up to date until cancelled. Notes changed by the daemon itself are not picked
+/// up as changes on the next pass.
+///
+/// # Example
+/// ```should_panic
+/// use std::path::PathBuf;
+///
+/// use obsidian_driver::ai::api::AIDriver;
+/// use obsidian_driver::batch::CancellationToken;
+/// use obsidian_driver::daemon::{DaemonOptions, VaultDaemon};
+///
+/// async fn daemon_example() {
+/// 	let options = DaemonOptions::default().with_embeddings(true).with_autolink(true);
+/// 	let mut daemon = VaultDaemon::open(PathBuf::from("vault"), options).unwrap();
+/// 	daemon.vault_mut().add_ai_driver(AIDriver::new_openai_from_config_path(PathBuf::from(".openai_config.json")).await.unwrap());
+/// 	// cancel a clone of the token from a Ctrl-C handler to stop cleanly
+/// 	let shutdown = CancellationToken::new();
+/// 	daemon.run(&shutdown).await.unwrap();
+/// }
+/// # panic!("needs a vault");
+/// ```
+///
+/// @public
+pub struct VaultDaemon {
+    vault: Vault,
+    options: DaemonOptions,
+}
+
+impl VaultDaemon {
+    /// Create a daemon for a loaded Vault.
+    ///
+    /// # Arguments
+    /// @param vault: Vault
+    /// @param options: DaemonOptions
+    /// @return VaultDaemon
+    pub fn new(vault: Vault, options: DaemonOptions) -> Self {
+        VaultDaemon { vault, options }
+    }
+
+    /// Create a daemon for a vault folder, loading the state of the last run if there is one.
+    ///
+    /// # Arguments
+    /// @param vault_root: PathBuf
+    /// @param options: DaemonOptions
+    /// @return Result<VaultDaemon>
+    pub fn open(vault_root: PathBuf, options: DaemonOptions) -> Result<Self> {
+        let state_path = vault_root.join(&options.state_path);
+        let vault = match state_path.exists() {
+            true => Vault::from_cache(vault_root, &state_path)?,
+            false => Vault::from_path(vault_root)?,
+        };
+        Ok(VaultDaemon::new(vault, options))
+    }
+
+    /// Get the Vault.
+    ///
+    /// # Arguments
+    /// @return &Vault
+    pub fn vault(&self) -> &Vault {
+        &self.vault
+    }
+
+    /// Get the Vault mutably, e.g. to add an AI driver.
+    ///
+    /// # Arguments
+    /// @return &mut Vault
+    pub fn vault_mut(&mut self) -> &mut Vault {
+        &mut self.vault
+    }
+
+    /// Stop the daemon and get the Vault back.
+    ///
+    /// # Arguments
+    /// @return Vault
+    pub fn into_vault(self) -> Vault {
+        self.vault
+    }
+
+    /// The absolute path of the state file.
+    ///
+    /// @private
+    fn state_path(&self) -> PathBuf {
+        self.vault.get_vault_root().join(&self.options.state_path)
+    }
+
+    /// Run one pass: pick up changes on disk, link and tag the changed notes, embed, run the jobs that are due, and save the state if anything changed.
+    ///
+    /// # Arguments
+    /// @param shutdown: &CancellationToken - Stops the pass early; the next pass picks up the rest
+    /// @return Result<DaemonTick> - Errors with `NoAIDriver` if embedding is on without a driver
+    pub async fn tick(&mut self, shutdown: &CancellationToken) -> Result<DaemonTick> {
+        let mut tick = DaemonTick {
+            changed: self.vault.refresh_until(shutdown)?.completed,
+            removed: self.vault.forget_missing_files(),
+            ..DaemonTick::default()
+        };
+        tick.changed.sort();
+        let changed: Vec<PathBuf> = tick
+            .changed
+            .iter()
+            .filter(|path| self.vault.get_file(path).is_some_and(|file| file.get_mdfile().is_some()))
+            .cloned()
+            .collect();
+
+        if self.options.autolink {
+            for path in &changed {
+                let linked = self.vault.suggest_links(path).and_then(|suggestions| {
+                    self.vault.apply_link_suggestions(&suggestions)?;
+                    Ok(!suggestions.is_empty())
+                });
+                match linked {
+                    Ok(true) => tick.linked.push(path.clone()),
+                    Ok(false) => {}
+                    Err(e) => tick.failed.push((path.clone(), e.to_string())),
+                }
+            }
+        }
+
+        let targets: HashSet<&PathBuf> = changed.iter().collect();
+        for rule in &self.options.tag_rules {
+            let add_tag = |path: &Path, yaml: &mut Mapping| {
+                if targets.contains(&path.to_path_buf()) {
+                    add_tag(yaml, &rule.tag);
+                }
+            };
+            for update in self.vault.update_frontmatter(&rule.query, add_tag, false)? {
+                if !tick.tagged.contains(&update.path) {
+                    tick.tagged.push(update.path);
+                }
+            }
+        }
+        tick.tagged.sort();
+
+        let state_path = self.state_path();
+        if self.options.embed && !shutdown.is_cancelled() {
+            if let Some(parent) = state_path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            let report = self
+                .vault
+                .update_embeddings_until(shutdown, &state_path, self.options.embed_concurrency)
+                .await?;
+            tick.embedded = report.completed;
+            tick.failed.extend(report.failed);
+        }
+        if !shutdown.is_cancelled() {
+            self.run_jobs(&mut tick, chrono::Local::now().naive_local(), shutdown).await?;
+        }
+        if !tick.is_empty() {
+            if let Some(parent) = state_path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            self.vault.to_cache(&state_path)?;
+        }
+        Ok(tick)
+    }
+
+    /// Run the jobs that are due at `now`, in the order they were declared, and record their last run.
+    ///
+    /// A failed job counts as run, so it is retried at its next occurrence rather than on every pass.
+    ///
+    /// @private
+    async fn run_jobs(&mut self, tick: &mut DaemonTick, now: NaiveDateTime, shutdown: &CancellationToken) -> Result<()> {
+        let jobs_state_path = self.vault.get_vault_root().join(&self.options.jobs_state_path);
+        let mut state = JobState::load(&jobs_state_path)?;
+        let due: Vec<Job> = self
+            .options
+            .jobs
+            .iter()
+            .filter(|job| job.schedule.is_due(state.last_run.get(&job.name).copied(), now))
+            .cloned()
+            .collect();
+        if due.is_empty() {
+            return Ok(());
+        }
+        for job in due {
+            debug!(job = %job.name, "running job");
+            let result = match &job.task {
+                JobTask::Embed => {
+                    let state_path = self.state_path();
+                    if let Some(parent) = state_path.parent() {
+                        std::fs::create_dir_all(parent)?;
+                    }
+                    self.vault
+                        .update_embeddings_until(shutdown, &state_path, self.options.embed_concurrency)
+                        .await
+                        .map(|report| {
+                            tick.embedded.extend(report.completed);
+                            tick.failed.extend(report.failed);
+                        })
+                }
+                JobTask::OrphanReport { path } => self.vault.write_orphan_report(path).map(|_| ()),
+                JobTask::DailyNote => self.vault.daily_note(now.date()).map(|_| ()),
+            };
+            match result {
+                Ok(()) => tick.jobs_run.push(job.name.clone()),
+                Err(e) => {
+                    warn!(job = %job.name, error = %e, "job failed");
+                    tick.jobs_failed.push((job.name.clone(), e.to_string()));
+                }
+            }
+            state.last_run.insert(job.name, now);
+        }
+        state.save(&jobs_state_path)
+    }
+
+    /// Run passes until `shutdown` is cancelled, then save the state.
+    ///
+    /// A failed pass is logged and retried at the next interval.
+    ///
+    /// # Arguments
+    /// @param shutdown: &CancellationToken
+    /// @return Result<()> - Errors with `NoAIDriver` if embedding is on without a driver
+    pub async fn run(&mut self, shutdown: &CancellationToken) -> Result<()> {
+        info!(vault = %self.vault.get_vault_root().display(), "daemon started");
+        while !shutdown.is_cancelled() {
+            match self.tick(shutdown).await {
+                Ok(tick) if !tick.is_empty() => {
+                    info!(
+                        changed = tick.changed.len(),
+                        removed = tick.removed.len(),
+                        linked = tick.linked.len(),
+                        tagged = tick.tagged.len(),
+                        embedded = tick.embedded.len(),
+                        failed = tick.failed.len(),
+                        jobs = tick.jobs_run.len(),
+                        "daemon pass"
+                    );
+                }
+                Ok(_) => debug!("daemon pass found no changes"),
+                Err(Error::NoAIDriver) => return Err(Error::NoAIDriver),
+                Err(e) => warn!(error = %e, "daemon pass failed"),
+            }
+            tokio::select! {
+                _ = shutdown.cancelled() => {}
+                _ = tokio::time::sleep(self.options.poll_interval) => {}
+            }
+        }
+        let state_path = self.state_path();
+        if let Some(parent) = state_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        self.vault.to_cache(&state_path)?;
+        info!("daemon stopped");
+        Ok(())
+    }
+}
+
+/// Add a tag to the `tags` of a front matter mapping, unless it is there already.
+///
+/// @private
+fn add_tag(yaml: &mut Mapping, tag: &str) {
+    let tag = Value::String(tag.to_string());
+    match yaml.get_mut("tags") {
+        Some(Value::Sequence(tags)) => {
+            if !tags.contains(&tag) {
+                tags.push(tag);
+            }
+        }
+        Some(Value::String(existing)) if Value::String(existing.clone()) == tag => {}
+        Some(existing @ Value::String(_)) => *existing = Value::Sequence(vec![existing.clone(), tag]),
+        _ => {
+            yaml.insert("tags".into(), Value::Sequence(vec![tag]));
+        }
+    }
+}
+
+#[cfg(test)]
+mod daemon_tests {
+    use super::schedule::Schedule;
+    use super::*;
+
+    #[tokio::test]
+    async fn test_daemon_links_tags_and_saves_state() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("Inbox")).unwrap();
+        std::fs::write(dir.path().join("Mitosis.md"), "# Mitosis").unwrap();
+        std::fs::write(dir.path().join("old.md"), "# Old").unwrap();
+        let options = DaemonOptions::default()
+            .with_autolink(true)
+            .with_tag_rule(TagRule::parse("FROM \"Inbox\"", "#inbox").unwrap());
+        let mut daemon = VaultDaemon::open(dir.path().to_path_buf(), options.clone()).unwrap();
+        let shutdown = CancellationToken::new();
+        assert!(daemon.tick(&shutdown).await.unwrap().is_empty());
+
+        std::fs::write(dir.path().join("Inbox/cells.md"), "---\ntags: cells\n---\nCells divide by mitosis.").unwrap();
+        std::fs::remove_file(dir.path().join("old.md")).unwrap();
+        let tick = daemon.tick(&shutdown).await.unwrap();
+        assert_eq!(tick.changed, vec![PathBuf::from("Inbox/cells.md")]);
+        assert_eq!(tick.removed, vec![PathBuf::from("old.md")]);
+        assert_eq!(tick.linked, vec![PathBuf::from("Inbox/cells.md")]);
+        assert_eq!(tick.tagged, vec![PathBuf::from("Inbox/cells.md")]);
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("Inbox/cells.md")).unwrap(),
+            "---\ntags:\n- cells\n- inbox\n---\nCells divide by [[Mitosis|mitosis]]."
+        );
+        // the daemon's own edits are not changes
+        assert!(daemon.tick(&shutdown).await.unwrap().is_empty());
+
+        shutdown.cancel();
+        daemon.run(&shutdown).await.unwrap();
+        let restarted = VaultDaemon::open(dir.path().to_path_buf(), options).unwrap();
+        assert!(restarted.vault().get_file(&PathBuf::from("old.md")).is_none());
+        assert!(restarted.vault().get_file(&PathBuf::from("Inbox/cells.md")).is_some());
+    }
+
+    #[tokio::test]
+    async fn test_daemon_runs_due_jobs_once() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.md"), "# A").unwrap();
+        let jobs = vec![
+            Job::new(
+                "orphans",
+                Schedule::parse("weekly mon 08:00").unwrap(),
+                JobTask::OrphanReport { path: PathBuf::from("Orphans.md") },
+            ),
+            Job::new("daily note", Schedule::parse("daily 00:00").unwrap(), JobTask::DailyNote),
+        ];
+        let options = DaemonOptions::default().with_jobs(jobs).with_jobs_state_path(PathBuf::from("jobs.json"));
+        let mut daemon = VaultDaemon::open(dir.path().to_path_buf(), options.clone()).unwrap();
+        let shutdown = CancellationToken::new();
+
+        let tick = daemon.tick(&shutdown).await.unwrap();
+        assert_eq!(tick.jobs_run, vec!["orphans".to_string(), "daily note".to_string()]);
+        assert!(dir.path().join("Orphans.md").exists());
+        let today = daemon.vault().settings().daily_notes.path_for(chrono::Local::now().date_naive());
+        assert!(dir.path().join(today).exists());
+        assert!(daemon.tick(&shutdown).await.unwrap().is_empty());
+        assert!(dir.path().join("jobs.json").exists());
+
+        // the last runs survive a restart
+        let mut restarted = VaultDaemon::open(dir.path().to_path_buf(), options).unwrap();
+        assert!(restarted.tick(&shutdown).await.unwrap().jobs_run.is_empty());
+    }
+
+    #[test]
+    fn test_add_tag() {
+        let mut yaml = Mapping::new();
+        add_tag(&mut yaml, "a");
+        add_tag(&mut yaml, "a");
+        assert_eq!(yaml.get("tags"), Some(&Value::Sequence(vec!["a".into()])));
+        yaml.insert("tags".into(), "b".into());
+        add_tag(&mut yaml, "a");
+        assert_eq!(yaml.get("tags"), Some(&Value::Sequence(vec!["b".into(), "a".into()])));
+    }
+}
@@ -0,0 +1,320 @@
+//! obsidian-driver::daemon::schedule
+//!
+//! This module contains the recurring jobs of the VaultDaemon: what to run (re-embed changed notes, write an orphan report, create the daily note) and when, declared in a JSON file, and the time each job last ran, kept so a restarted daemon does not repeat or skip jobs.
+//!
+//! ```json
+//! {
+//!   "jobs": [
+//!     { "name": "nightly embeddings", "schedule": "daily 02:00", "task": "embed" },
+//!     { "name": "orphans", "schedule": "weekly mon 08:00", "task": "orphan_report", "path": "Reports/Orphans.md" },
+//!     { "name": "daily note", "schedule": "daily 00:00", "task": "daily_note" }
+//!   ]
+//! }
+//! ```
+//!
+//! @public Schedule
+//!
+//! @public JobTask
+//!
+//! @public Job
+//!
+//! @public load_jobs
+//!
+//! @crate JobState
+
+// std imports
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+// third-party imports
+use chrono::{Datelike, NaiveDateTime, NaiveTime, TimeDelta, Weekday};
+use serde::{Deserialize, Serialize};
+
+// first-party imports
+use crate::file::write_atomic;
+use crate::prelude::*;
+
+/// When a job runs, written `every 30m` (or `h`, `d`), `daily 02:00`, or `weekly mon 08:00`. Times are local.
+///
+/// A job that never ran is due at once; after that, a daily or weekly job runs once per
+/// occurrence, and a missed occurrence runs at the next pass.
+///
+/// # Example
+/// ```
+/// use obsidian_driver::daemon::schedule::Schedule;
+///
+/// let schedule = Schedule::parse("weekly mon 08:00").unwrap();
+/// assert_eq!(schedule.to_string(), "weekly Mon 08:00");
+/// assert!(Schedule::parse("hourly").is_err());
+/// ```
+///
+/// @public
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum Schedule {
+    Every(Duration),
+    Daily(NaiveTime),
+    Weekly(Weekday, NaiveTime),
+}
+
+impl Schedule {
+    /// Parse a schedule.
+    ///
+    /// # Arguments
+    /// @param text: &str
+    /// @return Result<Schedule> - Errors with `InvalidSchedule`
+    pub fn parse(text: &str) -> Result<Self> {
+        let invalid = || Error::InvalidSchedule(f!("{}: expected `every 30m`, `daily 02:00` or `weekly mon 08:00`", text));
+        let time = |word: &str| NaiveTime::parse_from_str(word, "%H:%M").map_err(|_| invalid());
+        let words: Vec<&str> = text.split_whitespace().collect();
+        match words.as_slice() {
+            ["every", amount] => {
+                let split = amount.find(|c: char| !c.is_ascii_digit()).ok_or_else(invalid)?;
+                let count: u64 = amount[..split].parse().map_err(|_| invalid())?;
+                let seconds = match &amount[split..] {
+                    "m" => 60,
+                    "h" => 60 * 60,
+                    "d" => 24 * 60 * 60,
+                    _ => return Err(invalid()),
+                };
+                match count.checked_mul(seconds) {
+                    None | Some(0) => Err(invalid()),
+                    Some(interval) => Ok(Schedule::Every(Duration::from_secs(interval))),
+                }
+            }
+            ["daily", at] => Ok(Schedule::Daily(time(at)?)),
+            ["weekly", day, at] => Ok(Schedule::Weekly(day.parse().map_err(|_| invalid())?, time(at)?)),
+            _ => Err(invalid()),
+        }
+    }
+
+    /// Whether a job is due.
+    ///
+    /// # Arguments
+    /// @param last_run: Option<NaiveDateTime> - None if the job never ran
+    /// @param now: NaiveDateTime
+    /// @return bool
+    pub fn is_due(&self, last_run: Option<NaiveDateTime>, now: NaiveDateTime) -> bool {
+        let Some(last_run) = last_run else {
+            return true;
+        };
+        let latest = match *self {
+            Schedule::Every(interval) => return now - last_run >= TimeDelta::from_std(interval).unwrap_or(TimeDelta::MAX),
+            Schedule::Daily(at) => now.date().and_time(at),
+            Schedule::Weekly(day, at) => {
+                let days_back = (7 + now.weekday().num_days_from_monday() - day.num_days_from_monday()) % 7;
+                (now.date() - TimeDelta::days(days_back as i64)).and_time(at)
+            }
+        };
+        let period = match self {
+            Schedule::Weekly(..) => TimeDelta::days(7),
+            _ => TimeDelta::days(1),
+        };
+        // the most recent occurrence that is not in the future
+        let latest = if latest > now { latest - period } else { latest };
+        last_run < latest
+    }
+}
+
+impl fmt::Display for Schedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Schedule::Every(interval) => {
+                let minutes = interval.as_secs() / 60;
+                match (minutes % (24 * 60), minutes % 60) {
+                    (0, _) => write!(f, "every {}d", minutes / (24 * 60)),
+                    (_, 0) => write!(f, "every {}h", minutes / 60),
+                    _ => write!(f, "every {}m", minutes),
+                }
+            }
+            Schedule::Daily(at) => write!(f, "daily {}", at.format("%H:%M")),
+            Schedule::Weekly(day, at) => write!(f, "weekly {} {}", day, at.format("%H:%M")),
+        }
+    }
+}
+
+impl TryFrom<String> for Schedule {
+    type Error = Error;
+
+    fn try_from(text: String) -> Result<Self> {
+        Schedule::parse(&text)
+    }
+}
+
+impl From<Schedule> for String {
+    fn from(schedule: Schedule) -> Self {
+        schedule.to_string()
+    }
+}
+
+/// What a job does.
+///
+/// @public
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "task", rename_all = "snake_case")]
+pub enum JobTask {
+    /// Embed the notes without an embedding, i.e. new and changed notes; the vault needs an AI driver.
+    Embed,
+    /// Write the orphan notes to a note, see `Vault::write_orphan_report`.
+    OrphanReport { path: PathBuf },
+    /// Create today's daily note, see `Vault::daily_note`.
+    DailyNote,
+}
+
+/// Job struct
+///
+/// A named task on a schedule. The name identifies the job in the state, so renaming a job runs it again.
+///
+/// # Example
+/// ```
+/// use obsidian_driver::daemon::schedule::{Job, JobTask, Schedule};
+///
+/// let job = Job::new("daily note", Schedule::parse("daily 00:00").unwrap(), JobTask::DailyNote);
+/// ```
+///
+/// @public
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Job {
+    pub name: String,
+    pub schedule: Schedule,
+    #[serde(flatten)]
+    pub task: JobTask,
+}
+
+impl Job {
+    /// Create a job.
+    ///
+    /// # Arguments
+    /// @param name: &str
+    /// @param schedule: Schedule
+    /// @param task: JobTask
+    /// @return Job
+    pub fn new(name: &str, schedule: Schedule, task: JobTask) -> Self {
+        Job {
+            name: name.to_string(),
+            schedule,
+            task,
+        }
+    }
+}
+
+/// The layout of a jobs file.
+///
+/// @private
+#[derive(Deserialize)]
+struct JobsFile {
+    jobs: Vec<Job>,
+}
+
+/// Load the jobs declared in a JSON file, `{"jobs": [...]}`.
+///
+/// # Arguments
+/// @param path: &Path
+/// @return Result<Vec<Job>> - Errors if a schedule is invalid or two jobs have the same name
+///
+/// # Example
+/// ```should_panic
+/// use std::path::Path;
+///
+/// use obsidian_driver::daemon::schedule::load_jobs;
+/// use obsidian_driver::daemon::DaemonOptions;
+///
+/// let jobs = load_jobs(Path::new("jobs.json")).unwrap();
+/// let options = DaemonOptions::default().with_jobs(jobs);
+/// ```
+///
+/// @public
+pub fn load_jobs(path: &Path) -> Result<Vec<Job>> {
+    let file: JobsFile = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+    for (i, job) in file.jobs.iter().enumerate() {
+        if file.jobs[..i].iter().any(|other| other.name == job.name) {
+            return Err(Error::InvalidSchedule(f!("two jobs are named {}", job.name)));
+        }
+    }
+    Ok(file.jobs)
+}
+
+/// The last time each job ran, by job name.
+///
+/// @crate
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct JobState {
+    pub(crate) last_run: BTreeMap<String, NaiveDateTime>,
+}
+
+impl JobState {
+    /// Load the state, or start an empty one if the file does not exist.
+    ///
+    /// @crate
+    pub(crate) fn load(path: &Path) -> Result<Self> {
+        match path.exists() {
+            true => Ok(serde_json::from_str(&std::fs::read_to_string(path)?)?),
+            false => Ok(JobState::default()),
+        }
+    }
+
+    /// Save the state.
+    ///
+    /// @crate
+    pub(crate) fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        write_atomic(path, serde_json::to_string_pretty(self)?)
+    }
+}
+
+#[cfg(test)]
+mod schedule_tests {
+    use super::*;
+
+    fn at(text: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    #[test]
+    fn test_schedules_are_due() {
+        // 2024-09-03 is a Tuesday
+        let daily = Schedule::parse("daily 02:00").unwrap();
+        assert!(daily.is_due(None, at("2024-09-03 01:00")));
+        assert!(daily.is_due(Some(at("2024-09-02 02:00")), at("2024-09-03 02:00")));
+        assert!(!daily.is_due(Some(at("2024-09-02 02:00")), at("2024-09-03 01:59")));
+        assert!(!daily.is_due(Some(at("2024-09-03 02:01")), at("2024-09-03 23:00")));
+        // a missed occurrence runs once
+        assert!(daily.is_due(Some(at("2024-08-30 02:00")), at("2024-09-03 01:00")));
+
+        let weekly = Schedule::parse("weekly Monday 08:00").unwrap();
+        assert!(!weekly.is_due(Some(at("2024-09-02 08:00")), at("2024-09-08 23:00")));
+        assert!(weekly.is_due(Some(at("2024-09-02 08:00")), at("2024-09-09 08:00")));
+        assert!(weekly.is_due(Some(at("2024-09-02 07:00")), at("2024-09-03 00:00")));
+
+        let every = Schedule::parse("every 90m").unwrap();
+        assert!(!every.is_due(Some(at("2024-09-03 01:00")), at("2024-09-03 02:29")));
+        assert!(every.is_due(Some(at("2024-09-03 01:00")), at("2024-09-03 02:30")));
+        assert_eq!(Schedule::parse("every 6h").unwrap().to_string(), "every 6h");
+        assert!(Schedule::parse("every 0m").is_err());
+        assert!(matches!(Schedule::parse("every 18446744073709551615d"), Err(Error::InvalidSchedule(_))));
+    }
+
+    #[test]
+    fn test_load_jobs() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("jobs.json");
+        std::fs::write(
+            &path,
+            r#"{"jobs": [
+                {"name": "orphans", "schedule": "weekly mon 08:00", "task": "orphan_report", "path": "Reports/Orphans.md"},
+                {"name": "daily note", "schedule": "daily 00:00", "task": "daily_note"}
+            ]}"#,
+        )
+        .unwrap();
+        let jobs = load_jobs(&path).unwrap();
+        assert_eq!(jobs[0].task, JobTask::OrphanReport { path: PathBuf::from("Reports/Orphans.md") });
+        assert_eq!(jobs[1].schedule, Schedule::Daily(NaiveTime::from_hms_opt(0, 0, 0).unwrap()));
+
+        std::fs::write(&path, r#"{"jobs": [{"name": "x", "schedule": "sometimes", "task": "embed"}]}"#).unwrap();
+        assert!(load_jobs(&path).is_err());
+    }
+}
@@ -19,7 +19,16 @@ pub enum Error {
     InvalidEmbeddingResponse(String),
 
     #[error("Prompt Exceeds Model Token Limit:\n{0}")]
-    PromptExceedsModelTokenLimit(crate::ai::prompt::Prompt),
+    PromptExceedsModelTokenLimit(Box<crate::ai::prompt::Prompt>),
+
+    #[error("Context Window Exceeded:\n{model}: the prompt has about {prompt_tokens} tokens, but only {max_prompt_tokens} fit in its context window of {context_window} with {completion_tokens} kept for the response")]
+    ContextWindowExceeded {
+        model: String,
+        prompt_tokens: u32,
+        completion_tokens: u32,
+        max_prompt_tokens: u32,
+        context_window: u32,
+    },
 
     #[error("Vault Already Contains Path:\n{0}")]
     VaultAlreadyContainsPath(PathBuf),
@@ -27,12 +36,88 @@ pub enum Error {
     #[error("Path: {0}\nNot In Vault Root:\n{0}")]
     PathNotInVaultRoot(PathBuf, PathBuf),
 
+    #[error("Path Not Found In Vault:\n{0}")]
+    PathNotFound(PathBuf),
+
+    #[error("Path Already Exists:\n{0}")]
+    PathAlreadyExists(PathBuf),
+
+    #[error("Folder Cannot Be Moved Into Itself:\n{0}")]
+    FolderMovedIntoItself(PathBuf),
+
+    #[error("Invalid API Key:\n{0}")]
+    InvalidApiKey(String),
+
+    #[error("Environment Variable Not Set:\n{0}")]
+    MissingEnvVar(String),
+
+    #[error("Unknown Profile:\n{0}")]
+    UnknownProfile(String),
+
+    #[error("Unknown Model:\n{0}")]
+    UnknownModel(String),
+
+    #[error("No Recorded Response For Request:\n{0}")]
+    NotRecorded(String),
+
+    #[error("Operation Cancelled")]
+    Cancelled,
+
     #[error("No AI Driver Provided")]
     NoAIDriver,
 
     #[error("Invalid Chat Response:\n{0}")]
     InvalidChatResponse(String),
 
+    #[error("Embedding Model Mismatch:\nexpected {0}, found {1}")]
+    EmbeddingModelMismatch(String, String),
+
+    #[error("Embedding Dimension Mismatch:\nexpected {0}, found {1}")]
+    EmbeddingDimensionMismatch(usize, usize),
+
+    #[error("Invalid Query:\n{0}")]
+    InvalidQuery(String),
+
+    #[error("Unknown Saved Query:\n{0}")]
+    UnknownQuery(String),
+
+    #[error("Invalid Prompt:\n{0}")]
+    InvalidPrompt(String),
+
+    #[error("Invalid Date In Front Matter Key: {0}\n{1}")]
+    InvalidDate(String, String),
+
+    #[error("Export Failed:\n{0}")]
+    ExportFailed(String),
+
+    #[error("Sync Failed:\n{0}")]
+    SyncFailed(String),
+
+    #[error("Invalid Front Matter:\n{0}")]
+    InvalidFrontmatter(String),
+
+    #[error("Obsidian App Request Failed:\n{0}")]
+    AppRequestFailed(String),
+
+    #[error("Invalid Schedule:\n{0}")]
+    InvalidSchedule(String),
+
+    #[error("Not Supported By The AI Provider:\n{0}")]
+    NotSupported(String),
+
+    #[error("Invalid Transcription Response:\n{0}")]
+    InvalidTranscriptionResponse(String),
+
+    #[error("Audio Too Large:\n{file_name} has {bytes} bytes, but at most {max_bytes} can be transcribed at once; split or compress the recording")]
+    AudioTooLarge {
+        file_name: String,
+        bytes: usize,
+        max_bytes: usize,
+    },
+
+    #[error("Section Not Found:\n{0}")]
+    SectionNotFound(String),
+
     // Transparent Errors
     #[error(transparent)]
     IO(#[from] std::io::Error),
@@ -54,4 +139,10 @@ pub enum Error {
 
     #[error(transparent)]
     WalkDirError(#[from] walkdir::Error),
+
+    #[error(transparent)]
+    Bincode(#[from] bincode::Error),
+
+    #[error(transparent)]
+    Glob(#[from] globset::Error),
 }
@@ -0,0 +1,460 @@
+//! obsidian-driver::export::anki
+//!
+//! This module contains the Anki exporter: question and answer cards are extracted from a note (its `cards` front matter, Takeaways and `question::answer` lines) or generated by the AI model, and written as a tab separated file Anki can import.
+//!
+//! @public Card
+//!
+//! @public extract_cards
+//!
+//! @public generate_cards
+//!
+//! @public generate_term_cards
+//!
+//! @public read_frontmatter_cards
+//!
+//! @public write_frontmatter_cards
+//!
+//! @public parse_cards
+//!
+//! @public to_tsv
+//!
+//! @public ANKI_CARDS_KEY
+//!
+//! @public CARDS_KEY
+
+// std imports
+use std::path::{Path, PathBuf};
+
+// third-party imports
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+// first-party imports
+use crate::ai::api::AIDriver;
+use crate::ai::prompt::{Context, Prompt, ResponseBudget};
+use crate::ai::structured::{parse_json_response, JSON_SYSTEM_PROMPT};
+use crate::file::mdfile::obsidian;
+use crate::file::mdfile::MDFile;
+use crate::prelude::*;
+
+/// The front matter key listing the ids of the cards already exported from a note.
+///
+/// @public
+pub const ANKI_CARDS_KEY: &str = "anki-cards";
+
+/// The front matter key holding the cards of a note, as a list of `front` and `back` pairs.
+///
+/// @public
+pub const CARDS_KEY: &str = "cards";
+
+const CARDS_SYSTEM_PROMPT: &str = "You are a student writing flashcards to review lecture notes.";
+const CARDS_USER_PROMPT: &str = r#"Write flashcards for the most important ideas of the note below: definitions, facts, and the reasons behind them. Each card tests one idea, and its answer is short.
+
+Reply with the cards only, each formatted like this, with a blank line between cards:
+Q: What is an alphabet?
+A: A finite non-empty set of symbols.
+
+**Note**
+
+[note]"#;
+const TERM_CARDS_USER_PROMPT: &str = r#"List the terms the note below defines or relies on, each with a definition of one or two sentences that follows the note. Leave out terms the note only mentions in passing.
+
+Reply with a JSON object of the form {"cards": [{"term": "...", "definition": "..."}]}.
+
+**Note**
+
+[note]"#;
+
+/// A card as the JSON reply to the term cards prompt.
+///
+/// @private
+#[derive(Deserialize)]
+struct TermCard {
+    term: String,
+    definition: String,
+}
+
+/// The JSON reply to the term cards prompt.
+///
+/// @private
+#[derive(Deserialize)]
+struct TermCardList {
+    cards: Vec<TermCard>,
+}
+
+/// A card as stored in the `cards` front matter.
+///
+/// @private
+#[derive(Serialize, Deserialize)]
+struct FrontmatterCard {
+    front: String,
+    back: String,
+}
+
+/// Card struct
+///
+/// A question and answer card, with the note it came from.
+///
+/// # Example
+/// ```
+/// use std::path::PathBuf;
+///
+/// use obsidian_driver::export::anki::Card;
+///
+/// let card = Card::new("Alphabet", "Finite non-empty set of symbols", PathBuf::from("Lectures/Strings.md"));
+/// assert_eq!(card.id().len(), 8);
+/// assert_eq!(card.tag(), "obsidian::Strings");
+/// ```
+///
+/// @public
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Card {
+    pub front: String,
+    pub back: String,
+    // relative to the vault root
+    pub source: PathBuf,
+}
+
+impl Card {
+    /// Create a card; the front and back are trimmed.
+    ///
+    /// # Arguments
+    /// @param front: &str - The question
+    /// @param back: &str - The answer
+    /// @param source: PathBuf - The note the card came from
+    /// @return Card
+    pub fn new(front: &str, back: &str, source: PathBuf) -> Card {
+        Card {
+            front: front.trim().to_string(),
+            back: back.trim().to_string(),
+            source,
+        }
+    }
+
+    /// A stable id for the card: the first 8 hex digits of the SHA-256 of its front and back.
+    ///
+    /// @return String
+    pub fn id(&self) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(self.front.as_bytes());
+        hasher.update(b"\t");
+        hasher.update(self.back.as_bytes());
+        let digest = hasher.finalize();
+        digest.iter().take(4).map(|byte| f!("{:02x}", byte)).collect()
+    }
+
+    /// The Anki tag naming the source note: `obsidian::` and the note name, with spaces replaced by `_`.
+    ///
+    /// @return String
+    pub fn tag(&self) -> String {
+        let stem = self.source.file_stem().map(|stem| stem.to_string_lossy()).unwrap_or_default();
+        f!("obsidian::{}", stem.split_whitespace().collect::<Vec<&str>>().join("_"))
+    }
+}
+
+/// Extract the cards a note already contains.
+///
+/// Three conventions are recognized: the `cards` front matter (see `write_frontmatter_cards`),
+/// the items of a `Takeaways` section written as `- **Term**: definition`, and lines written as
+/// `question::answer`, as used by spaced repetition plugins. Lines in code blocks and comments
+/// are ignored.
+///
+/// # Arguments
+/// @param path: &Path - The note, relative to the vault root
+/// @param mdfile: &MDFile
+/// @return Vec<Card> - The cards, front matter first, then Takeaways
+///
+/// # Example
+/// ```
+/// use std::path::Path;
+///
+/// use obsidian_driver::export::anki::extract_cards;
+/// use obsidian_driver::file::mdfile::MDFile;
+///
+/// let mdfile = MDFile::from_string("Mitosis::Division of a cell nucleus\n\n## Takeaways\n- **Alphabet**: Finite set of symbols.\n".to_string());
+/// let cards = extract_cards(Path::new("Strings.md"), &mdfile);
+/// assert_eq!(cards[0].front, "Alphabet");
+/// assert_eq!(cards[0].back, "Finite set of symbols.");
+/// assert_eq!(cards[1].front, "Mitosis");
+/// ```
+///
+/// @public
+pub fn extract_cards(path: &Path, mdfile: &MDFile) -> Vec<Card> {
+    let body = mdfile.get_body();
+    let hidden = obsidian::hidden_ranges(body);
+    let mut cards = read_frontmatter_cards(path, mdfile);
+
+    if let Some(section) = mdfile.get_section("Takeaways") {
+        let takeaway_pattern = Regex::new(r"^\s*[-*+]\s+(?:\*\*(.+?):?\*\*|__(.+?):?__)\s*:?\s+(.+)$").unwrap();
+        for line in body[section.content_range].lines() {
+            let Some(captures) = takeaway_pattern.captures(line) else {
+                continue;
+            };
+            let term = captures.get(1).or(captures.get(2)).map(|term| term.as_str()).unwrap_or_default();
+            cards.push(Card::new(term, &captures[3], path.to_path_buf()));
+        }
+    }
+
+    let mut offset = 0;
+    for line in body.split_inclusive('\n') {
+        let start = offset;
+        offset += line.len();
+        let Some(separator) = line.find("::") else {
+            continue;
+        };
+        if hidden.iter().any(|range| range.contains(&(start + separator))) {
+            continue;
+        }
+        let (front, back) = (&line[..separator], &line[separator + 2..]);
+        let front = front.trim().trim_start_matches(['-', '*', '+']).trim();
+        if !front.is_empty() && !back.trim().is_empty() && !back.contains("::") {
+            cards.push(Card::new(front, back, path.to_path_buf()));
+        }
+    }
+    cards
+}
+
+/// Ask the AI model to write cards for a note.
+///
+/// # Arguments
+/// @param driver: &AIDriver - The AI driver to use for generating the cards
+/// @param path: &Path - The note, relative to the vault root
+/// @param mdfile: &MDFile
+/// @return Result<Vec<Card>>
+///
+/// # Example
+/// ```
+/// use std::path::{Path, PathBuf};
+///
+/// use obsidian_driver::ai::api::AIDriver;
+/// use obsidian_driver::export::anki::generate_cards;
+/// use obsidian_driver::file::mdfile::MDFile;
+///
+/// async fn generate_cards_example() {
+/// 	let driver = AIDriver::new_openai_from_config_path(PathBuf::from(".openai_config.json")).await.unwrap();
+/// 	let mdfile = MDFile::from_string("# Cells\n\nMitochondria make ATP.".to_string());
+/// 	let cards = generate_cards(&driver, Path::new("Cells.md"), &mdfile).await.unwrap();
+/// }
+/// ```
+///
+/// @public
+pub async fn generate_cards(driver: &AIDriver, path: &Path, mdfile: &MDFile) -> Result<Vec<Card>> {
+    let mut context = Context::default();
+    context.insert("note", mdfile.get_body());
+    let prompt = Prompt::new(CARDS_SYSTEM_PROMPT, CARDS_USER_PROMPT, ResponseBudget::Unlimited).substitute(&context)?;
+    let response = driver.chat_smart(prompt).await?;
+    Ok(parse_cards(&response, path))
+}
+
+/// Ask the AI model for the terms a note defines, as cards with the term on the front and its definition on the back.
+///
+/// The model replies with JSON, so the cards need no parsing of prose. Store them with
+/// `write_frontmatter_cards`.
+///
+/// # Arguments
+/// @param driver: &AIDriver - The AI driver to use for generating the cards
+/// @param path: &Path - The note, relative to the vault root
+/// @param mdfile: &MDFile
+/// @return Result<Vec<Card>> - Errors with `InvalidChatResponse` if the reply is not the cards
+///
+/// # Example
+/// ```
+/// use std::path::{Path, PathBuf};
+///
+/// use obsidian_driver::ai::api::AIDriver;
+/// use obsidian_driver::export::anki::{generate_term_cards, write_frontmatter_cards};
+/// use obsidian_driver::file::mdfile::MDFile;
+///
+/// async fn generate_term_cards_example() {
+/// 	let driver = AIDriver::new_openai_from_config_path(PathBuf::from(".openai_config.json")).await.unwrap();
+/// 	let mut mdfile = MDFile::from_string("# Cells\n\nMitochondria make ATP.".to_string());
+/// 	let cards = generate_term_cards(&driver, Path::new("Cells.md"), &mdfile).await.unwrap();
+/// 	write_frontmatter_cards(&mut mdfile, &cards);
+/// }
+/// ```
+///
+/// @public
+pub async fn generate_term_cards(driver: &AIDriver, path: &Path, mdfile: &MDFile) -> Result<Vec<Card>> {
+    let mut context = Context::default();
+    context.insert("note", mdfile.strip_comments().get_body().trim());
+    let prompt = Prompt::new(CARDS_SYSTEM_PROMPT, TERM_CARDS_USER_PROMPT, ResponseBudget::Unlimited)
+        .stack_system_prompt(JSON_SYSTEM_PROMPT)
+        .substitute(&context)?;
+    let response = driver.chat_cheap(prompt).await?;
+    let cards = parse_json_response::<TermCardList>(&response)?
+        .cards
+        .into_iter()
+        .filter(|card| !card.term.trim().is_empty() && !card.definition.trim().is_empty())
+        .map(|card| Card::new(&card.term, &card.definition, path.to_path_buf()))
+        .collect();
+    Ok(cards)
+}
+
+/// Read the cards in the `cards` front matter of a note. Entries without a front or back are skipped.
+///
+/// # Arguments
+/// @param path: &Path - The note, relative to the vault root
+/// @param mdfile: &MDFile
+/// @return Vec<Card>
+///
+/// # Example
+/// ```
+/// use std::path::Path;
+///
+/// use obsidian_driver::export::anki::read_frontmatter_cards;
+/// use obsidian_driver::file::mdfile::MDFile;
+///
+/// let mdfile = MDFile::from_string("---\ncards:\n- front: ATP\n  back: The energy carrier of the cell.\n- front: Missing back\n---\n# Cells".to_string());
+/// let cards = read_frontmatter_cards(Path::new("Cells.md"), &mdfile);
+/// assert_eq!(cards.len(), 1);
+/// assert_eq!(cards[0].front, "ATP");
+/// ```
+///
+/// @public
+pub fn read_frontmatter_cards(path: &Path, mdfile: &MDFile) -> Vec<Card> {
+    let Some(serde_yaml::Value::Sequence(entries)) = mdfile.get_yaml_key(CARDS_KEY) else {
+        return Vec::new();
+    };
+    entries
+        .iter()
+        .filter_map(|entry| serde_yaml::from_value::<FrontmatterCard>(entry.clone()).ok())
+        .filter(|card| !card.front.trim().is_empty() && !card.back.trim().is_empty())
+        .map(|card| Card::new(&card.front, &card.back, path.to_path_buf()))
+        .collect()
+}
+
+/// Write cards to the `cards` front matter of a note, replacing the cards there.
+///
+/// # Arguments
+/// @param mdfile: &mut MDFile
+/// @param cards: &[Card]
+///
+/// # Example
+/// ```
+/// use std::path::PathBuf;
+///
+/// use obsidian_driver::export::anki::{write_frontmatter_cards, Card};
+/// use obsidian_driver::file::mdfile::MDFile;
+///
+/// let mut mdfile = MDFile::from_string("# Cells".to_string());
+/// write_frontmatter_cards(&mut mdfile, &[Card::new("ATP", "The energy carrier of the cell.", PathBuf::from("Cells.md"))]);
+/// assert_eq!(mdfile.to_string(), "---\ncards:\n- front: ATP\n  back: The energy carrier of the cell.\n---\n# Cells");
+/// ```
+///
+/// @public
+pub fn write_frontmatter_cards(mdfile: &mut MDFile, cards: &[Card]) {
+    let entries = cards
+        .iter()
+        .map(|card| FrontmatterCard { front: card.front.clone(), back: card.back.clone() })
+        .map(|card| serde_yaml::to_value(card).expect("Cards serialize to YAML"))
+        .collect();
+    mdfile.add_yaml_key(CARDS_KEY.to_string(), serde_yaml::Value::Sequence(entries));
+}
+
+/// Parse cards written as `Q:` and `A:` lines. An answer continues until the next question or blank line.
+///
+/// # Arguments
+/// @param text: &str - e.g. the response of the AI model
+/// @param source: &Path - The note the cards are for
+/// @return Vec<Card>
+///
+/// # Example
+/// ```
+/// use std::path::Path;
+///
+/// use obsidian_driver::export::anki::parse_cards;
+///
+/// let cards = parse_cards("Q: What is a string?\nA: A finite sequence\nof symbols.\n\nQ: Unanswered?", Path::new("Strings.md"));
+/// assert_eq!(cards.len(), 1);
+/// assert_eq!(cards[0].back, "A finite sequence\nof symbols.");
+/// ```
+///
+/// @public
+pub fn parse_cards(text: &str, source: &Path) -> Vec<Card> {
+    let mut cards = Vec::new();
+    let mut question: Option<String> = None;
+    let mut answer: Option<String> = None;
+    let mut finish = |question: &mut Option<String>, answer: &mut Option<String>| {
+        if let (Some(front), Some(back)) = (question.take(), answer.take()) {
+            if !front.trim().is_empty() && !back.trim().is_empty() {
+                cards.push(Card::new(&front, &back, source.to_path_buf()));
+            }
+        }
+    };
+    for line in text.lines() {
+        let trimmed = line.trim().trim_start_matches(['*', '-']).trim_start();
+        if let Some(front) = trimmed.strip_prefix("Q:") {
+            finish(&mut question, &mut answer);
+            question = Some(front.trim_start_matches('*').to_string());
+        } else if let Some(back) = trimmed.strip_prefix("A:").filter(|_| question.is_some()) {
+            answer = Some(back.trim_start_matches('*').to_string());
+        } else if trimmed.is_empty() {
+            finish(&mut question, &mut answer);
+        } else if let Some(answer) = answer.as_mut() {
+            answer.push('\n');
+            answer.push_str(line.trim());
+        } else if let Some(question) = question.as_mut() {
+            question.push('\n');
+            question.push_str(line.trim());
+        }
+    }
+    finish(&mut question, &mut answer);
+    cards
+}
+
+/// Write cards as a tab separated file for Anki's File > Import, with the front, back and source note tag of each card.
+///
+/// Fields are imported as HTML, so markup characters are escaped and line breaks become `<br>`.
+///
+/// # Arguments
+/// @param cards: &[Card]
+/// @return String
+///
+/// # Example
+/// ```
+/// use std::path::PathBuf;
+///
+/// use obsidian_driver::export::anki::{to_tsv, Card};
+///
+/// let cards = vec![Card::new("a < b?", "Yes", PathBuf::from("Order.md"))];
+/// assert_eq!(to_tsv(&cards), "#separator:tab\n#html:true\n#tags column:3\na &lt; b?\tYes\tobsidian::Order\n");
+/// ```
+///
+/// @public
+pub fn to_tsv(cards: &[Card]) -> String {
+    let field = |text: &str| {
+        text.replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;")
+            .replace('\t', " ")
+            .replace("\r\n", "<br>")
+            .replace('\n', "<br>")
+    };
+    let mut tsv = String::from("#separator:tab\n#html:true\n#tags column:3\n");
+    for card in cards {
+        tsv.push_str(&f!("{}\t{}\t{}\n", field(&card.front), field(&card.back), card.tag()));
+    }
+    tsv
+}
+
+#[cfg(test)]
+mod anki_tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cards() {
+        let text = "Here are your cards:\n\n**Q:** What is ATP?\n**A:** The energy carrier\nof the cell.\n\n- Q: What divides?\n  A: Cells.\nQ: Which organelle makes ATP?\nA: The mitochondrion.\n\nThis line is not part of a card.\nQ: Unanswered?\n\nA: Not an answer.";
+        let cards = parse_cards(text, Path::new("Cells.md"));
+        let actual: Vec<(&str, &str)> = cards.iter().map(|card| (card.front.as_str(), card.back.as_str())).collect();
+        assert_eq!(
+            actual,
+            vec![
+                ("What is ATP?", "The energy carrier\nof the cell."),
+                ("What divides?", "Cells."),
+                ("Which organelle makes ATP?", "The mitochondrion."),
+            ]
+        );
+        assert!(cards.iter().all(|card| card.source == Path::new("Cells.md")));
+    }
+}
@@ -0,0 +1,451 @@
+//! obsidian-driver::export::html
+//!
+//! This module contains the HTML exporter: it renders the Obsidian flavor of markdown (wikilinks, embeds, callouts, highlights, comments) to plain HTML with pulldown-cmark.
+//!
+//! @public HtmlRenderer
+//!
+//! @public HtmlRenderer::new
+//!
+//! @public HtmlRenderer::with_link_resolver
+//!
+//! @public HtmlRenderer::render
+//!
+//! @public HtmlRenderer::render_document
+//!
+//! @public render
+//!
+//! @public heading_id
+
+// std imports
+use std::ops::Range;
+
+// third-party imports
+use pulldown_cmark::{Event, Options, Parser, Tag, TagEnd};
+
+// first-party imports
+use crate::file::mdfile::block::Block;
+use crate::file::mdfile::callout::{Callout, CalloutFold};
+use crate::file::mdfile::link::Link;
+use crate::file::mdfile::{obsidian, MDFile};
+use crate::prelude::*;
+
+/// The extensions of embeds rendered as images.
+const IMAGE_EXTENSIONS: [&str; 7] = ["png", "jpg", "jpeg", "gif", "svg", "webp", "bmp"];
+
+/// Marks the paragraph a callout is rendered into; callouts are rendered separately and spliced back in.
+const CALLOUT_PLACEHOLDER: &str = "OBSIDIANDRIVERCALLOUT";
+
+/// Turns a link into an href, None for links that do not resolve.
+type LinkResolver<'a> = Box<dyn Fn(&Link) -> Option<String> + Send + Sync + 'a>;
+
+/// HtmlRenderer struct
+///
+/// Renders notes to HTML. Links are turned into hrefs by a link resolver; the default one
+/// points `[[Note#Heading]]` at `Note.html#heading` next to the current page.
+///
+/// # Example
+/// ```
+/// use obsidian_driver::export::html::HtmlRenderer;
+/// use obsidian_driver::file::mdfile::MDFile;
+///
+/// let mdfile = MDFile::from_string("See [[Other Note#Key Idea]] and ==this==.".to_string());
+/// let html = HtmlRenderer::new().render(&mdfile);
+/// assert_eq!(html, "<p>See <a class=\"internal-link\" href=\"Other%20Note.html#key-idea\">Other Note &gt; Key Idea</a> and <mark>this</mark>.</p>\n");
+/// ```
+///
+/// @public
+pub struct HtmlRenderer<'a> {
+    link_resolver: LinkResolver<'a>,
+}
+
+impl Default for HtmlRenderer<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a> HtmlRenderer<'a> {
+    /// Create a renderer with the default link resolver.
+    ///
+    /// # Arguments
+    /// @return HtmlRenderer
+    pub fn new() -> Self {
+        HtmlRenderer {
+            link_resolver: Box::new(|link| Some(default_href(link))),
+        }
+    }
+
+    /// Set how links are turned into hrefs. Links the resolver returns None for are rendered as unresolved.
+    ///
+    /// # Arguments
+    /// @param link_resolver: impl Fn(&Link) -> Option<String> - e.g. a lookup into the pages of an exported site
+    /// @return HtmlRenderer
+    ///
+    /// # Example
+    /// ```
+    /// use obsidian_driver::export::html::HtmlRenderer;
+    /// use obsidian_driver::file::mdfile::MDFile;
+    ///
+    /// let renderer = HtmlRenderer::new().with_link_resolver(|link| (link.target == "Known").then(|| "/known/".to_string()));
+    /// let html = renderer.render(&MDFile::from_string("[[Known]] [[Unknown]]".to_string()));
+    /// assert!(html.contains("href=\"/known/\""));
+    /// assert!(html.contains("<a class=\"internal-link is-unresolved\">Unknown</a>"));
+    /// ```
+    pub fn with_link_resolver(mut self, link_resolver: impl Fn(&Link) -> Option<String> + Send + Sync + 'a) -> Self {
+        self.link_resolver = Box::new(link_resolver);
+        self
+    }
+
+    /// Render the body of a note to an HTML fragment.
+    ///
+    /// # Arguments
+    /// @param mdfile: &MDFile
+    /// @return String
+    pub fn render(&self, mdfile: &MDFile) -> String {
+        self.render_markdown(mdfile.get_body())
+    }
+
+    /// Render a note to a complete HTML page, with its front matter as `<meta>` elements.
+    ///
+    /// # Arguments
+    /// @param mdfile: &MDFile
+    /// @param title: &str - The page title, usually the note name
+    /// @return String
+    ///
+    /// # Example
+    /// ```
+    /// use obsidian_driver::export::html::HtmlRenderer;
+    /// use obsidian_driver::file::mdfile::MDFile;
+    ///
+    /// let mdfile = MDFile::from_string("---\ntags: [bio, cells]\n---\n# Cells".to_string());
+    /// let html = HtmlRenderer::new().render_document(&mdfile, "Cells");
+    /// assert!(html.contains("<title>Cells</title>"));
+    /// assert!(html.contains("<meta name=\"tags\" content=\"bio, cells\">"));
+    /// ```
+    pub fn render_document(&self, mdfile: &MDFile, title: &str) -> String {
+        page(title, mdfile.get_yaml(), &self.render(mdfile))
+    }
+
+    /// Render Obsidian markdown to HTML: Obsidian syntax is replaced by inline HTML, then the result is rendered as CommonMark.
+    ///
+    /// @private
+    fn render_markdown(&self, text: &str) -> String {
+        let callouts = Callout::parse_all(text);
+        let comments = obsidian::comment_ranges(text);
+        let skipped = |start: usize| {
+            callouts.iter().any(|callout| callout.range.contains(&start))
+                || comments.iter().any(|range| range.contains(&start))
+        };
+
+        let mut edits: Vec<(Range<usize>, String)> = Vec::new();
+        for range in comments.iter() {
+            edits.push((range.clone(), String::new()));
+        }
+        for (i, callout) in callouts.iter().enumerate() {
+            edits.push((callout.range.clone(), f!("{}{}", CALLOUT_PLACEHOLDER, i)));
+        }
+        for range in obsidian::highlight_ranges(text).into_iter().filter(|range| !skipped(range.start)) {
+            edits.push((range.start..range.start + 2, "<mark>".to_string()));
+            edits.push((range.end - 2..range.end, "</mark>".to_string()));
+        }
+        for link in Link::parse_all(text).into_iter().filter(|link| !skipped(link.range.start)) {
+            edits.push((link.range.clone(), self.link_html(&link)));
+        }
+        for anchor in Block::parse_all(text).into_iter().filter_map(|block| block.anchor_range) {
+            if !skipped(anchor.start) {
+                edits.push((anchor, String::new()));
+            }
+        }
+        edits.sort_by_key(|(range, _)| range.start);
+
+        let mut markdown = text.to_string();
+        let mut end = usize::MAX;
+        for (range, replacement) in edits.into_iter().rev() {
+            // a highlight or link may lie inside a range that was already replaced
+            if range.end > end {
+                continue;
+            }
+            end = range.start;
+            markdown.replace_range(range, &replacement);
+        }
+
+        let mut html = render_commonmark(&markdown);
+        for (i, callout) in callouts.iter().enumerate() {
+            let placeholder = f!("<p>{}{}</p>\n", CALLOUT_PLACEHOLDER, i);
+            html = html.replacen(&placeholder, &self.callout_html(callout), 1);
+        }
+        html
+    }
+
+    /// Render a link or embed as inline HTML.
+    ///
+    /// @private
+    fn link_html(&self, link: &Link) -> String {
+        let display = escape_html(&link.display_text());
+        let Some(href) = (self.link_resolver)(link) else {
+            return f!("<a class=\"internal-link is-unresolved\">{}</a>", display);
+        };
+        let is_image = link
+            .target
+            .rsplit_once('.')
+            .is_some_and(|(_, extension)| IMAGE_EXTENSIONS.contains(&extension.to_lowercase().as_str()));
+        match (link.embed, is_image) {
+            // markdown image syntax, as a lone `<img>` line would start a raw HTML block
+            (true, true) => f!(
+                "![{}](<{}>)",
+                link.alias.as_deref().unwrap_or(&link.target).replace(['[', ']'], ""),
+                href.replace(['<', '>'], "")
+            ),
+            (true, false) => f!("<a class=\"internal-embed\" href=\"{}\">{}</a>", escape_html(&href), display),
+            (false, _) => f!("<a class=\"internal-link\" href=\"{}\">{}</a>", escape_html(&href), display),
+        }
+    }
+
+    /// Render a callout, foldable ones as `<details>`.
+    ///
+    /// @private
+    fn callout_html(&self, callout: &Callout) -> String {
+        let title = match &callout.title {
+            Some(title) => title.clone(),
+            None => {
+                let mut chars = callout.kind.chars();
+                chars
+                    .next()
+                    .map(|first| first.to_uppercase().chain(chars).collect())
+                    .unwrap_or_default()
+            }
+        };
+        let content = self.render_markdown(&callout.content);
+        let kind = escape_html(&callout.kind);
+        match callout.fold {
+            None => f!(
+                "<div class=\"callout\" data-callout=\"{}\">\n<div class=\"callout-title\">{}</div>\n<div class=\"callout-content\">\n{}</div>\n</div>\n",
+                kind,
+                escape_html(&title),
+                content
+            ),
+            Some(fold) => f!(
+                "<details class=\"callout\" data-callout=\"{}\"{}>\n<summary class=\"callout-title\">{}</summary>\n<div class=\"callout-content\">\n{}</div>\n</details>\n",
+                kind,
+                if fold == CalloutFold::Open { " open" } else { "" },
+                escape_html(&title),
+                content
+            ),
+        }
+    }
+}
+
+/// Render the body of a note to an HTML fragment with the default link resolver, see `HtmlRenderer`.
+///
+/// # Arguments
+/// @param mdfile: &MDFile
+/// @return String
+///
+/// # Example
+/// ```
+/// use obsidian_driver::export::html;
+/// use obsidian_driver::file::mdfile::MDFile;
+///
+/// let mdfile = MDFile::from_string("## Key Idea\n\n> [!note]\n> Cells divide.".to_string());
+/// let html = html::render(&mdfile);
+/// assert!(html.starts_with("<h2 id=\"key-idea\">Key Idea</h2>"));
+/// assert!(html.contains("<div class=\"callout\" data-callout=\"note\">"));
+/// ```
+///
+/// @public
+pub fn render(mdfile: &MDFile) -> String {
+    HtmlRenderer::new().render(mdfile)
+}
+
+/// A complete HTML page with the given title and body, and the front matter as `<meta>` elements.
+///
+/// @crate
+pub(crate) fn page(title: &str, yaml: Option<&serde_yaml::Value>, body: &str) -> String {
+    page_with_head(title, yaml, "", body)
+}
+
+/// A complete HTML page, as `page`, with extra elements such as scripts appended to its `<head>`.
+///
+/// @crate
+pub(crate) fn page_with_head(title: &str, yaml: Option<&serde_yaml::Value>, extra_head: &str, body: &str) -> String {
+    let mut head = f!("<meta charset=\"utf-8\">\n<title>{}</title>\n", escape_html(title));
+    if let Some(serde_yaml::Value::Mapping(mapping)) = yaml {
+        for (key, value) in mapping.iter() {
+            if let (Some(key), Some(content)) = (scalar_text(key), meta_content(value)) {
+                head.push_str(&f!(
+                    "<meta name=\"{}\" content=\"{}\">\n",
+                    escape_html(&key),
+                    escape_html(&content)
+                ));
+            }
+        }
+    }
+    head.push_str(extra_head);
+    f!(
+        "<!DOCTYPE html>\n<html>\n<head>\n{}</head>\n<body>\n<article>\n{}</article>\n</body>\n</html>\n",
+        head,
+        body
+    )
+}
+
+/// The id given to a heading in rendered HTML: lowercase, with runs of other characters than letters and digits replaced by `-`.
+///
+/// # Arguments
+/// @param heading: &str
+/// @return String
+///
+/// # Example
+/// ```
+/// use obsidian_driver::export::html::heading_id;
+///
+/// assert_eq!(heading_id("Part 2: Cell Division!"), "part-2-cell-division");
+/// ```
+///
+/// @public
+pub fn heading_id(heading: &str) -> String {
+    heading
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// The href of a link for pages exported next to each other: `[[Folder/Note#Heading]]` becomes `Folder/Note.html#heading`, attachments keep their path.
+///
+/// @crate
+pub(crate) fn default_href(link: &Link) -> String {
+    let target = match link.target.rsplit_once('.') {
+        _ if link.target.is_empty() => String::new(),
+        Some((stem, "md")) => f!("{}.html", stem),
+        Some((_, extension)) if !extension.contains(['/', ' ']) => link.target.clone(),
+        _ => f!("{}.html", link.target),
+    };
+    f!("{}{}", encode_href(&target), fragment(link))
+}
+
+/// The `#fragment` of a link: the id of its last heading, or its block id.
+///
+/// @crate
+pub(crate) fn fragment(link: &Link) -> String {
+    if let Some(id) = link.block_id() {
+        return f!("#^{}", id);
+    }
+    link.heading_path()
+        .last()
+        .map(|heading| f!("#{}", heading_id(heading)))
+        .unwrap_or_default()
+}
+
+/// Encode the characters of a path that cannot appear in an href.
+///
+/// @crate
+pub(crate) fn encode_href(path: &str) -> String {
+    let mut encoded = String::with_capacity(path.len());
+    for c in path.chars() {
+        match c {
+            ' ' => encoded.push_str("%20"),
+            '#' => encoded.push_str("%23"),
+            '?' => encoded.push_str("%3F"),
+            '%' => encoded.push_str("%25"),
+            _ => encoded.push(c),
+        }
+    }
+    encoded
+}
+
+/// Escape text for use in HTML content and attribute values.
+///
+/// @crate
+pub(crate) fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Render CommonMark, with the GitHub extensions Obsidian supports, math, and ids on headings.
+///
+/// @private
+fn render_commonmark(markdown: &str) -> String {
+    let options = Options::ENABLE_TABLES
+        | Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_TASKLISTS
+        | Options::ENABLE_FOOTNOTES
+        | Options::ENABLE_MATH;
+    let mut events: Vec<Event> = Parser::new_ext(markdown, options).collect();
+
+    let mut heading: Option<(usize, String)> = None;
+    for i in 0..events.len() {
+        match &events[i] {
+            Event::Start(Tag::Heading { id: None, .. }) => heading = Some((i, String::new())),
+            Event::Text(text) | Event::Code(text) => {
+                if let Some((_, heading_text)) = heading.as_mut() {
+                    heading_text.push_str(text);
+                }
+            }
+            Event::End(TagEnd::Heading(_)) => {
+                if let Some((start, heading_text)) = heading.take() {
+                    if let Event::Start(Tag::Heading { id, .. }) = &mut events[start] {
+                        *id = Some(heading_id(&heading_text).into());
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut html = String::new();
+    pulldown_cmark::html::push_html(&mut html, events.into_iter());
+    html
+}
+
+/// The text of a scalar YAML value.
+///
+/// @private
+fn scalar_text(value: &serde_yaml::Value) -> Option<String> {
+    match value {
+        serde_yaml::Value::String(text) => Some(text.clone()),
+        serde_yaml::Value::Number(number) => Some(number.to_string()),
+        serde_yaml::Value::Bool(boolean) => Some(boolean.to_string()),
+        _ => None,
+    }
+}
+
+/// The content of the `<meta>` element for a front matter value; lists are joined with commas.
+///
+/// @private
+fn meta_content(value: &serde_yaml::Value) -> Option<String> {
+    match value {
+        serde_yaml::Value::Sequence(items) => {
+            Some(items.iter().filter_map(scalar_text).collect::<Vec<_>>().join(", "))
+        }
+        other => scalar_text(other),
+    }
+}
+
+#[cfg(test)]
+mod html_tests {
+    use super::*;
+
+    #[test]
+    fn test_render_obsidian_syntax() {
+        let text = "# Cells %%draft%%\n\nSee [[Mitochondria#^energy|energy]] ^intro\n\n![[diagram.png]]\n\n> [!tip]- Remember\n> Use [[Notes]].\n\n`[[not a link]]`";
+        let html = render(&MDFile::from_string(text.to_string()));
+        let expected = "<h1 id=\"cells\">Cells</h1>\n\
+            <p>See <a class=\"internal-link\" href=\"Mitochondria.html#^energy\">energy</a></p>\n\
+            <p><img src=\"diagram.png\" alt=\"diagram.png\" /></p>\n\
+            <details class=\"callout\" data-callout=\"tip\">\n<summary class=\"callout-title\">Remember</summary>\n\
+            <div class=\"callout-content\">\n<p>Use <a class=\"internal-link\" href=\"Notes.html\">Notes</a>.</p>\n</div>\n</details>\n\
+            <p><code>[[not a link]]</code></p>\n";
+        assert_eq!(html, expected);
+    }
+}
@@ -0,0 +1,17 @@
+//! obsidian-driver::export
+//!
+//! This module contains the exporters that turn notes into publishable formats.
+//!
+//! @public anki
+//!
+//! @public html
+//!
+//! @public pdf
+//!
+//! @public typst
+
+// submodules
+pub mod anki;
+pub mod html;
+pub mod pdf;
+pub mod typst;
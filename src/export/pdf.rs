@@ -0,0 +1,198 @@
+//! obsidian-driver::export::pdf
+//!
+//! This module contains the PDF export options: the note is rendered to Typst or HTML, then handed to an external program (`typst`, a headless browser, `weasyprint`, ...) that writes the PDF.
+//!
+//! @public PdfBackend
+//!
+//! @public PdfOptions
+
+// std imports
+use std::path::Path;
+use std::process::Command;
+
+// third-party imports
+use serde::{Deserialize, Serialize};
+
+// first-party imports
+use crate::prelude::*;
+
+/// Scripts rendering the math of an HTML page with KaTeX before it is printed.
+const KATEX_HEAD: &str = r#"<link rel="stylesheet" href="https://cdn.jsdelivr.net/npm/katex@0.16.11/dist/katex.min.css">
+<script defer src="https://cdn.jsdelivr.net/npm/katex@0.16.11/dist/katex.min.js"></script>
+<script>
+document.addEventListener("DOMContentLoaded", () => document.querySelectorAll(".math").forEach((element) =>
+	katex.render(element.textContent, element, { displayMode: element.classList.contains("math-display"), throwOnError: false })));
+</script>
+"#;
+
+/// PdfBackend enum
+///
+/// The program turning a rendered note into a PDF. In the arguments of an HTML backend,
+/// `{input}` is replaced by the HTML file and `{output}` by the PDF file.
+///
+/// # Example
+/// ```
+/// use obsidian_driver::export::pdf::PdfBackend;
+///
+/// let backend = PdfBackend::Html {
+/// 	program: "wkhtmltopdf".to_string(),
+/// 	args: vec!["{input}".to_string(), "{output}".to_string()],
+/// };
+/// assert_eq!(backend.source_extension(), "html");
+/// assert_eq!(PdfBackend::default(), PdfBackend::typst());
+/// ```
+///
+/// @public
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum PdfBackend {
+    Typst { program: String },
+    Html { program: String, args: Vec<String> },
+}
+
+impl Default for PdfBackend {
+    fn default() -> Self {
+        PdfBackend::typst()
+    }
+}
+
+impl PdfBackend {
+    /// Compile a Typst document with the `typst` command line.
+    ///
+    /// @return PdfBackend
+    pub fn typst() -> Self {
+        PdfBackend::Typst {
+            program: "typst".to_string(),
+        }
+    }
+
+    /// Print an HTML page with headless Chromium. Math is rendered with KaTeX, which is loaded from a CDN.
+    ///
+    /// @return PdfBackend
+    pub fn chromium() -> Self {
+        let args = [
+            "--headless",
+            "--disable-gpu",
+            "--no-pdf-header-footer",
+            "--virtual-time-budget=10000",
+            "--print-to-pdf={output}",
+            "{input}",
+        ];
+        PdfBackend::Html {
+            program: "chromium".to_string(),
+            args: args.iter().map(|arg| arg.to_string()).collect(),
+        }
+    }
+
+    /// Convert an HTML page with WeasyPrint. WeasyPrint does not run scripts, so math is left as TeX.
+    ///
+    /// @return PdfBackend
+    pub fn weasyprint() -> Self {
+        PdfBackend::Html {
+            program: "weasyprint".to_string(),
+            args: vec!["{input}".to_string(), "{output}".to_string()],
+        }
+    }
+
+    /// The extension of the intermediate file the backend reads: `typ` or `html`.
+    ///
+    /// @return &'static str
+    pub fn source_extension(&self) -> &'static str {
+        match self {
+            PdfBackend::Typst { .. } => "typ",
+            PdfBackend::Html { .. } => "html",
+        }
+    }
+
+    /// Run the backend on a rendered source file.
+    ///
+    /// # Arguments
+    /// @param input: &Path - The Typst or HTML file
+    /// @param output: &Path - The PDF to write
+    /// @return Result<()> - `Error::ExportFailed` with the program output if it exits with an error
+    ///
+    /// @crate
+    pub(crate) fn run(&self, input: &Path, output: &Path) -> Result<()> {
+        let input = input.to_string_lossy();
+        let output = output.to_string_lossy();
+        let (program, args) = match self {
+            PdfBackend::Typst { program } => {
+                // the root is the file system root, so absolute image paths resolve
+                let args = vec!["compile".to_string(), "--root".to_string(), "/".to_string(), input.into_owned(), output.into_owned()];
+                (program, args)
+            }
+            PdfBackend::Html { program, args } => {
+                let args = args
+                    .iter()
+                    .map(|arg| arg.replace("{input}", &input).replace("{output}", &output))
+                    .collect();
+                (program, args)
+            }
+        };
+        let result = Command::new(program)
+            .args(&args)
+            .output()
+            .map_err(|err| Error::ExportFailed(f!("could not run {}: {}", program, err)))?;
+        if !result.status.success() {
+            return Err(Error::ExportFailed(f!(
+                "{} exited with {}\n{}",
+                program,
+                result.status,
+                String::from_utf8_lossy(&result.stderr)
+            )));
+        }
+        Ok(())
+    }
+
+    /// Elements added to the `<head>` of the HTML page: the math rendering scripts, for backends that run them.
+    ///
+    /// @crate
+    pub(crate) fn extra_head(&self) -> &'static str {
+        match self {
+            PdfBackend::Typst { .. } => "",
+            PdfBackend::Html { .. } => KATEX_HEAD,
+        }
+    }
+}
+
+/// PdfOptions struct
+///
+/// Options for `Vault::export_pdf`. By default the note is compiled with Typst and the intermediate file is removed.
+///
+/// # Example
+/// ```
+/// use obsidian_driver::export::pdf::{PdfBackend, PdfOptions};
+///
+/// let options = PdfOptions::default()
+/// 	.with_backend(PdfBackend::chromium())
+/// 	.with_keep_source(true);
+/// ```
+///
+/// @public
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct PdfOptions {
+    pub backend: PdfBackend,
+    // keep the Typst or HTML file next to the PDF
+    pub keep_source: bool,
+}
+
+impl PdfOptions {
+    /// Set the program that writes the PDF.
+    ///
+    /// # Arguments
+    /// @param backend: PdfBackend
+    /// @return PdfOptions
+    pub fn with_backend(mut self, backend: PdfBackend) -> Self {
+        self.backend = backend;
+        self
+    }
+
+    /// Keep the intermediate Typst or HTML file next to the PDF, e.g. to debug the layout.
+    ///
+    /// # Arguments
+    /// @param keep_source: bool
+    /// @return PdfOptions
+    pub fn with_keep_source(mut self, keep_source: bool) -> Self {
+        self.keep_source = keep_source;
+        self
+    }
+}
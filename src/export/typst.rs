@@ -0,0 +1,336 @@
+//! obsidian-driver::export::typst
+//!
+//! This module contains the Typst exporter: it converts the Obsidian flavor of markdown to Typst markup, which the `typst` compiler turns into a PDF. TeX math is rendered with the `mitex` Typst package.
+//!
+//! @public TypstRenderer
+//!
+//! @public TypstRenderer::new
+//!
+//! @public TypstRenderer::with_link_resolver
+//!
+//! @public TypstRenderer::render
+//!
+//! @public TypstRenderer::render_document
+
+// std imports
+use std::ops::Range;
+
+// third-party imports
+use pulldown_cmark::{Event, HeadingLevel, Options, Parser, Tag, TagEnd};
+
+// first-party imports
+use crate::file::mdfile::block::Block;
+use crate::file::mdfile::callout::Callout;
+use crate::file::mdfile::link::Link;
+use crate::file::mdfile::{obsidian, MDFile};
+use crate::prelude::*;
+
+/// The Typst package rendering TeX math, imported when a note contains math.
+const MITEX_IMPORT: &str = "#import \"@preview/mitex:0.2.4\": *";
+
+/// The characters with a meaning in Typst markup, escaped in text.
+const TYPST_SPECIAL_CHARACTERS: &str = "\\#*_`$<>@[]~=-+/";
+
+/// Marks the paragraph a callout is rendered into; callouts are rendered separately and spliced back in.
+const CALLOUT_PLACEHOLDER: &str = "OBSIDIANDRIVERCALLOUT";
+
+/// Turns a link into the path of an image, or the URL of a link; None renders the link as its text.
+type LinkResolver<'a> = Box<dyn Fn(&Link) -> Option<String> + Send + Sync + 'a>;
+
+/// TypstRenderer struct
+///
+/// Renders notes to Typst markup. Internal links are rendered as their text, since a PDF
+/// stands on its own; image embeds are rendered with `#image`, at the path returned by the link
+/// resolver (by default the link target).
+///
+/// # Example
+/// ```
+/// use obsidian_driver::export::typst::TypstRenderer;
+/// use obsidian_driver::file::mdfile::MDFile;
+///
+/// let mdfile = MDFile::from_string("## Energy\n\nSee [[Mitochondria]]: $E = mc^2$".to_string());
+/// let markup = TypstRenderer::new().render(&mdfile);
+/// assert_eq!(markup, "== Energy\n\nSee Mitochondria: #mi(\"E = mc^2\")\n\n");
+/// ```
+///
+/// @public
+pub struct TypstRenderer<'a> {
+    link_resolver: LinkResolver<'a>,
+}
+
+impl Default for TypstRenderer<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a> TypstRenderer<'a> {
+    /// Create a renderer that uses link targets as image paths.
+    ///
+    /// # Arguments
+    /// @return TypstRenderer
+    pub fn new() -> Self {
+        TypstRenderer {
+            link_resolver: Box::new(|link| Some(link.target.clone())),
+        }
+    }
+
+    /// Set how image embeds are turned into paths. Paths are read by `typst` relative to the `.typ` file, or to `--root` if they are absolute.
+    ///
+    /// # Arguments
+    /// @param link_resolver: impl Fn(&Link) -> Option<String>
+    /// @return TypstRenderer
+    pub fn with_link_resolver(mut self, link_resolver: impl Fn(&Link) -> Option<String> + Send + Sync + 'a) -> Self {
+        self.link_resolver = Box::new(link_resolver);
+        self
+    }
+
+    /// Render the body of a note to Typst markup.
+    ///
+    /// # Arguments
+    /// @param mdfile: &MDFile
+    /// @return String
+    pub fn render(&self, mdfile: &MDFile) -> String {
+        self.render_markdown(mdfile.get_body())
+    }
+
+    /// Render a note to a complete Typst document, with page setup and the imports it needs.
+    ///
+    /// # Arguments
+    /// @param mdfile: &MDFile
+    /// @param title: &str - The document title, usually the note name
+    /// @return String
+    ///
+    /// # Example
+    /// ```
+    /// use obsidian_driver::export::typst::TypstRenderer;
+    /// use obsidian_driver::file::mdfile::MDFile;
+    ///
+    /// let mdfile = MDFile::from_string("$$\n\\int_0^1 x\\,dx\n$$".to_string());
+    /// let document = TypstRenderer::new().render_document(&mdfile, "Integrals");
+    /// assert!(document.starts_with("#import \"@preview/mitex:0.2.4\": *\n"));
+    /// assert!(document.contains("#set document(title: \"Integrals\")"));
+    /// ```
+    pub fn render_document(&self, mdfile: &MDFile, title: &str) -> String {
+        let body = self.render(mdfile);
+        let mut document = String::new();
+        if body.contains("#mi(") || body.contains("#mitex(") {
+            document.push_str(MITEX_IMPORT);
+            document.push('\n');
+        }
+        document.push_str(&f!("#set document(title: {})\n", typst_string(title)));
+        document.push_str("#set page(paper: \"a4\", margin: 2cm)\n");
+        document.push_str("#set par(justify: true)\n\n");
+        document.push_str(&body);
+        document
+    }
+
+    /// Render Obsidian markdown to Typst: Obsidian syntax is replaced by CommonMark, which is then converted event by event.
+    ///
+    /// @private
+    fn render_markdown(&self, text: &str) -> String {
+        let callouts = Callout::parse_all(text);
+        let comments = obsidian::comment_ranges(text);
+        let skipped = |start: usize| {
+            callouts.iter().any(|callout| callout.range.contains(&start))
+                || comments.iter().any(|range| range.contains(&start))
+        };
+
+        let mut edits: Vec<(Range<usize>, String)> = Vec::new();
+        for range in comments.iter() {
+            edits.push((range.clone(), String::new()));
+        }
+        for (i, callout) in callouts.iter().enumerate() {
+            edits.push((callout.range.clone(), f!("{}{}", CALLOUT_PLACEHOLDER, i)));
+        }
+        for range in obsidian::highlight_ranges(text).into_iter().filter(|range| !skipped(range.start)) {
+            edits.push((range.start..range.start + 2, String::new()));
+            edits.push((range.end - 2..range.end, String::new()));
+        }
+        for link in Link::parse_all(text).into_iter().filter(|link| !skipped(link.range.start)) {
+            let replacement = match (link.embed, (self.link_resolver)(&link)) {
+                (true, Some(path)) => f!("![{}](<{}>)", link.target.replace(['[', ']'], ""), path.replace(['<', '>'], "")),
+                _ => link.display_text().replace(['[', ']'], ""),
+            };
+            edits.push((link.range.clone(), replacement));
+        }
+        for anchor in Block::parse_all(text).into_iter().filter_map(|block| block.anchor_range) {
+            if !skipped(anchor.start) {
+                edits.push((anchor, String::new()));
+            }
+        }
+        edits.sort_by_key(|(range, _)| range.start);
+
+        let mut markdown = text.to_string();
+        let mut end = usize::MAX;
+        for (range, replacement) in edits.into_iter().rev() {
+            // a highlight or link may lie inside a range that was already replaced
+            if range.end > end {
+                continue;
+            }
+            end = range.start;
+            markdown.replace_range(range, &replacement);
+        }
+
+        let mut markup = render_commonmark(&markdown);
+        for (i, callout) in callouts.iter().enumerate() {
+            let title = callout.title.clone().unwrap_or_else(|| callout.kind.clone());
+            let block = f!(
+                "#block(fill: luma(240), inset: 8pt, radius: 4pt, width: 100%)[\n*{}*\n\n{}]\n\n",
+                escape_typst(&title),
+                self.render_markdown(&callout.content)
+            );
+            markup = markup.replacen(&f!("{}{}\n\n", CALLOUT_PLACEHOLDER, i), &block, 1);
+        }
+        markup
+    }
+}
+
+/// Convert CommonMark, with the GitHub extensions Obsidian supports and math, to Typst markup.
+///
+/// @private
+fn render_commonmark(markdown: &str) -> String {
+    let options = Options::ENABLE_TABLES
+        | Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_TASKLISTS
+        | Options::ENABLE_MATH;
+    let mut markup = String::new();
+    // the numbering of the open lists, innermost last
+    let mut lists: Vec<bool> = Vec::new();
+    let mut in_image = false;
+    let mut in_code_block = false;
+    // the cells of the table row so far
+    let mut cells = 0;
+    for event in Parser::new_ext(markdown, options) {
+        if in_image {
+            in_image = !matches!(event, Event::End(TagEnd::Image));
+            continue;
+        }
+        match event {
+            Event::Start(Tag::Heading { level, .. }) => {
+                markup.push_str(&"=".repeat(heading_depth(level)));
+                markup.push(' ');
+            }
+            Event::End(TagEnd::Heading(_)) => markup.push_str("\n\n"),
+            Event::End(TagEnd::Paragraph) => markup.push_str(if lists.is_empty() { "\n\n" } else { "\n" }),
+            Event::Start(Tag::Emphasis) | Event::End(TagEnd::Emphasis) => markup.push('_'),
+            Event::Start(Tag::Strong) | Event::End(TagEnd::Strong) => markup.push('*'),
+            Event::Start(Tag::Strikethrough) => markup.push_str("#strike["),
+            Event::Start(Tag::BlockQuote(_)) => markup.push_str("#quote(block: true)[\n"),
+            Event::End(TagEnd::BlockQuote(_)) => markup.push_str("]\n\n"),
+            Event::End(TagEnd::Strikethrough) | Event::End(TagEnd::Link) | Event::End(TagEnd::TableCell) => {
+                markup.push(']')
+            }
+            Event::Start(Tag::CodeBlock(kind)) => {
+                let language = match kind {
+                    pulldown_cmark::CodeBlockKind::Fenced(language) => language.to_string(),
+                    pulldown_cmark::CodeBlockKind::Indented => String::new(),
+                };
+                markup.push_str(&f!("````{}\n", language));
+                in_code_block = true;
+            }
+            Event::End(TagEnd::CodeBlock) => {
+                markup.push_str("````\n\n");
+                in_code_block = false;
+            }
+            Event::Start(Tag::List(start)) => {
+                if !markup.is_empty() && !markup.ends_with('\n') {
+                    markup.push('\n');
+                }
+                lists.push(start.is_some());
+            }
+            Event::End(TagEnd::List(_)) => {
+                lists.pop();
+                if lists.is_empty() {
+                    markup.push('\n');
+                }
+            }
+            Event::Start(Tag::Item) => {
+                markup.push_str(&"  ".repeat(lists.len().saturating_sub(1)));
+                markup.push_str(if lists.last() == Some(&true) { "+ " } else { "- " });
+            }
+            Event::End(TagEnd::Item) if !markup.ends_with('\n') => markup.push('\n'),
+            Event::TaskListMarker(checked) => markup.push_str(if checked { "☑ " } else { "☐ " }),
+            Event::Start(Tag::Link { dest_url, .. }) => markup.push_str(&f!("#link({})[", typst_string(&dest_url))),
+            Event::Start(Tag::Image { dest_url, .. }) => {
+                markup.push_str(&f!("#image({}, width: 80%)", typst_string(&dest_url)));
+                in_image = true;
+            }
+            Event::Start(Tag::Table(alignments)) => {
+                markup.push_str(&f!("#table(columns: {},\n", alignments.len()));
+            }
+            Event::End(TagEnd::Table) => markup.push_str(")\n\n"),
+            Event::Start(Tag::TableHead) | Event::Start(Tag::TableRow) => cells = 0,
+            Event::Start(Tag::TableCell) => {
+                if cells > 0 {
+                    markup.push_str(", ");
+                }
+                markup.push('[');
+                cells += 1;
+            }
+            Event::End(TagEnd::TableRow) | Event::End(TagEnd::TableHead) => markup.push_str(",\n"),
+            Event::Text(text) if in_code_block => markup.push_str(&text),
+            Event::Text(text) => markup.push_str(&escape_typst(&text)),
+            Event::Code(code) => markup.push_str(&f!("#raw({})", typst_string(&code))),
+            Event::InlineMath(tex) => markup.push_str(&f!("#mi({})", typst_string(&tex))),
+            Event::DisplayMath(tex) => markup.push_str(&f!("#mitex({})", typst_string(tex.trim()))),
+            Event::SoftBreak => markup.push(' '),
+            Event::HardBreak => markup.push_str(" \\\n"),
+            Event::Rule => markup.push_str("#line(length: 100%)\n\n"),
+            _ => {}
+        }
+    }
+    markup
+}
+
+/// The number of `=` of a Typst heading.
+///
+/// @private
+fn heading_depth(level: HeadingLevel) -> usize {
+    match level {
+        HeadingLevel::H1 => 1,
+        HeadingLevel::H2 => 2,
+        HeadingLevel::H3 => 3,
+        HeadingLevel::H4 => 4,
+        HeadingLevel::H5 => 5,
+        HeadingLevel::H6 => 6,
+    }
+}
+
+/// Escape text so Typst shows it as it is.
+///
+/// @private
+fn escape_typst(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if TYPST_SPECIAL_CHARACTERS.contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// A Typst string literal.
+///
+/// @private
+fn typst_string(text: &str) -> String {
+    f!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod typst_tests {
+    use super::*;
+
+    #[test]
+    fn test_render_lists_tables_and_callouts() {
+        let text = "# Cells\n\n- *One* ^a\n  1. [x] Two\n\n| a | b |\n| - | - |\n| 1 | #2 |\n\n> [!note] Remember\n> `code` and [[Link|text]]\n\n![[cell.png]]";
+        let markup = TypstRenderer::new().render(&MDFile::from_string(text.to_string()));
+        let expected = "= Cells\n\n\
+            - _One_\n  + ☑ Two\n\n\
+            #table(columns: 2,\n[a], [b],\n[1], [\\#2],\n)\n\n\
+            #block(fill: luma(240), inset: 8pt, radius: 4pt, width: 100%)[\n*Remember*\n\n#raw(\"code\") and text\n\n]\n\n\
+            #image(\"cell.png\", width: 80%)\n\n";
+        assert_eq!(markup, expected);
+    }
+}
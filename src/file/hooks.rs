@@ -0,0 +1,279 @@
+//! obsidian-driver::file::hooks
+//!
+//! This module contains file hooks: transforms registered once that run on the raw text of every file when it is read and before it is written, so applications can normalize or stamp files without wrapping `File::write`.
+//!
+//! @public FileHook
+//!
+//! @public HookId
+//!
+//! @public register_hook
+//!
+//! @public unregister_hook
+//!
+//! @public NormalizeLineEndings
+//!
+//! @public StripTrailingWhitespace
+//!
+//! @public UpdateModified
+
+// std imports
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock, RwLock};
+
+// first-party imports
+use crate::file::mdfile::MDFile;
+use crate::prelude::*;
+
+/// A transform of the raw text of files, run when they are read and before they are written.
+///
+/// Both methods default to returning the text unchanged. Hooks run in the order they were
+/// registered, each on the output of the one before. Hooks are global to the process; when a
+/// Vault writes a file, the output of `before_write` becomes the contents of the file in memory.
+///
+/// # Example
+/// ```
+/// use std::path::Path;
+/// use std::sync::Arc;
+///
+/// use obsidian_driver::error::Error;
+/// use obsidian_driver::file::hooks::{register_hook, unregister_hook, FileHook};
+///
+/// struct Signature;
+///
+/// impl FileHook for Signature {
+/// 	fn before_write(&self, path: &Path, contents: String) -> Result<String, Error> {
+/// 		if path.extension().is_some_and(|extension| extension == "md") && !contents.ends_with("-- me\n") {
+/// 			return Ok(contents + "\n-- me\n");
+/// 		}
+/// 		Ok(contents)
+/// 	}
+/// }
+///
+/// let id = register_hook(Arc::new(Signature));
+/// unregister_hook(id);
+/// ```
+/// @public
+pub trait FileHook: Send + Sync {
+    /// Transform the text of a file read from `path`, before it is parsed.
+    fn on_read(&self, _path: &Path, contents: String) -> Result<String> {
+        Ok(contents)
+    }
+
+    /// Transform the text of a file about to be written to `path`.
+    fn before_write(&self, _path: &Path, contents: String) -> Result<String> {
+        Ok(contents)
+    }
+}
+
+/// The handle of a registered hook, to unregister it.
+///
+/// @public
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct HookId(u64);
+
+/// The registered hooks, in order.
+type Hooks = Vec<(HookId, Arc<dyn FileHook>)>;
+
+/// The registered hooks.
+///
+/// @private
+fn hooks() -> &'static RwLock<Hooks> {
+    static HOOKS: OnceLock<RwLock<Hooks>> = OnceLock::new();
+    HOOKS.get_or_init(Default::default)
+}
+
+/// Register a hook for every file read or written from now on, after the hooks registered before it.
+///
+/// # Arguments
+/// @param hook: Arc<dyn FileHook>
+/// @return HookId
+/// @public
+pub fn register_hook(hook: Arc<dyn FileHook>) -> HookId {
+    static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+    let id = HookId(NEXT_ID.fetch_add(1, Ordering::Relaxed));
+    hooks().write().expect("File hooks poisoned").push((id, hook));
+    id
+}
+
+/// Remove a registered hook. Removing a hook twice does nothing.
+///
+/// # Arguments
+/// @param id: HookId
+/// @public
+pub fn unregister_hook(id: HookId) {
+    hooks().write().expect("File hooks poisoned").retain(|(hook_id, _)| *hook_id != id);
+}
+
+/// Run the read hooks on the text of a file.
+///
+/// @crate
+pub(crate) fn run_on_read(path: &Path, contents: String) -> Result<String> {
+    let hooks = hooks().read().expect("File hooks poisoned").clone();
+    hooks.iter().try_fold(contents, |contents, (_, hook)| hook.on_read(path, contents))
+}
+
+/// Run the write hooks on the text of a file.
+///
+/// @crate
+pub(crate) fn run_before_write(path: &Path, contents: String) -> Result<String> {
+    let hooks = hooks().read().expect("File hooks poisoned").clone();
+    hooks.iter().try_fold(contents, |contents, (_, hook)| hook.before_write(path, contents))
+}
+
+/// Convert Windows (`\r\n`) and old Mac (`\r`) line endings to `\n`, on read and before write.
+///
+/// @public
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NormalizeLineEndings;
+
+impl NormalizeLineEndings {
+    /// Convert the line endings of a text.
+    ///
+    /// @private
+    fn normalize(contents: String) -> String {
+        if contents.contains('\r') {
+            contents.replace("\r\n", "\n").replace('\r', "\n")
+        } else {
+            contents
+        }
+    }
+}
+
+impl FileHook for NormalizeLineEndings {
+    fn on_read(&self, _path: &Path, contents: String) -> Result<String> {
+        Ok(Self::normalize(contents))
+    }
+
+    fn before_write(&self, _path: &Path, contents: String) -> Result<String> {
+        Ok(Self::normalize(contents))
+    }
+}
+
+/// Remove spaces and tabs at the end of lines before write.
+///
+/// Markdown line breaks written as two trailing spaces are removed too; use `<br>` or a
+/// backslash instead.
+///
+/// @public
+#[derive(Clone, Copy, Debug, Default)]
+pub struct StripTrailingWhitespace;
+
+impl FileHook for StripTrailingWhitespace {
+    fn before_write(&self, _path: &Path, contents: String) -> Result<String> {
+        let stripped: Vec<&str> = contents.split('\n').map(|line| line.trim_end_matches([' ', '\t'])).collect();
+        Ok(stripped.join("\n"))
+    }
+}
+
+/// Set a front matter key of markdown files to the current local date and time before write.
+///
+/// # Example
+/// ```
+/// use std::sync::Arc;
+///
+/// use obsidian_driver::file::hooks::{register_hook, UpdateModified};
+///
+/// register_hook(Arc::new(UpdateModified::new("modified")));
+/// ```
+/// @public
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UpdateModified {
+    pub key: String,
+}
+
+impl UpdateModified {
+    /// Create the hook for a front matter key.
+    ///
+    /// # Arguments
+    /// @param key: &str
+    /// @return UpdateModified
+    pub fn new(key: &str) -> Self {
+        UpdateModified { key: key.to_string() }
+    }
+}
+
+impl FileHook for UpdateModified {
+    fn before_write(&self, path: &Path, contents: String) -> Result<String> {
+        if path.extension().is_none_or(|extension| extension != "md") {
+            return Ok(contents);
+        }
+        let mut mdfile = MDFile::from_string(contents);
+        // whole minutes, like Obsidian's date and time properties
+        let now = chrono::Local::now().naive_local();
+        let now = now.date().and_hms_opt(chrono::Timelike::hour(&now), chrono::Timelike::minute(&now), 0).unwrap_or(now);
+        mdfile.set_yaml_datetime(&self.key, now);
+        Ok(mdfile.to_string())
+    }
+}
+
+#[cfg(test)]
+mod hooks_tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    // other tests read and write files concurrently, so the hooks only touch files in one folder
+    struct InFolder(PathBuf, Arc<dyn FileHook>);
+
+    impl FileHook for InFolder {
+        fn on_read(&self, path: &Path, contents: String) -> Result<String> {
+            match path.starts_with(&self.0) {
+                true => self.1.on_read(path, contents),
+                false => Ok(contents),
+            }
+        }
+
+        fn before_write(&self, path: &Path, contents: String) -> Result<String> {
+            match path.starts_with(&self.0) {
+                true => self.1.before_write(path, contents),
+                false => Ok(contents),
+            }
+        }
+    }
+
+    #[test]
+    fn test_hooks_run_on_read_and_write() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("note.md");
+        std::fs::write(&path, "# Note  \r\n\r\nText\t\r\n").unwrap();
+        let hooks: [Arc<dyn FileHook>; 3] = [Arc::new(NormalizeLineEndings), Arc::new(StripTrailingWhitespace), Arc::new(UpdateModified::new("modified"))];
+        let ids: Vec<HookId> = hooks.into_iter().map(|hook| register_hook(Arc::new(InFolder(dir.path().to_path_buf(), hook)))).collect();
+
+        let file = crate::file::File::read(path.clone()).unwrap();
+        assert_eq!(file.get_mdfile().unwrap().get_body(), "# Note  \n\nText\t\n");
+        file.write().unwrap();
+        let written = MDFile::from_string(std::fs::read_to_string(&path).unwrap());
+        assert_eq!(written.get_body(), "# Note\n\nText\n");
+        assert!(written.get_yaml_datetime("modified").unwrap().is_some());
+
+        for id in ids {
+            unregister_hook(id);
+        }
+        std::fs::write(&path, "a\r\n").unwrap();
+        let file = crate::file::File::read(path).unwrap();
+        assert_eq!(file.get_mdfile().unwrap().get_body(), "a\r\n");
+    }
+
+    #[test]
+    fn test_vault_keeps_hook_output() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        std::fs::write(root.join("note.md"), "# Note\n").unwrap();
+        let id = register_hook(Arc::new(InFolder(root.clone(), Arc::new(StripTrailingWhitespace))));
+        let mut vault = crate::file::vault::Vault::from_path(root.clone()).unwrap();
+
+        let path = PathBuf::from("note.md");
+        let mdfile = vault.get_file_mut(&path).unwrap().get_mdfile_mut().unwrap();
+        mdfile.set_body("# Edited  \n\nText\t\n".to_string());
+        mdfile.set_embedding(vec![1.0]);
+        assert!(vault.write_file(&path).unwrap());
+        unregister_hook(id);
+
+        // the note in memory is what the hooks wrote, so writing again changes nothing
+        let file = vault.get_file(&path).unwrap();
+        assert_eq!(file.get_mdfile().unwrap().get_body(), "# Edited\n\nText\n");
+        assert_eq!(std::fs::read_to_string(root.join("note.md")).unwrap(), "# Edited\n\nText\n");
+        assert_eq!(file.get_embedding(), Some(&vec![1.0]));
+        assert!(vault.dirty_files().is_empty());
+    }
+}
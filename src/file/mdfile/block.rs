@@ -0,0 +1,148 @@
+//! obsidian-driver::file::mdfile::block
+//!
+//! This module contains the Block struct, which represents a paragraph, list item or other markdown block that `[[Note#^id]]` links can point at once it carries a `^id` anchor.
+//!
+//! @public Block
+//!
+//! @public Block::parse_all
+
+// std imports
+use std::ops::Range;
+
+// third-party imports
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+/// The `Block` struct represents a block of a markdown file: a paragraph, a list item, a heading, or a run of lines such as a table or quote.
+///
+/// # Example
+/// ```
+/// use obsidian_driver::file::mdfile::block::Block;
+///
+/// let text = "Cells divide. ^division\n\n- One\n- Two ^two\n";
+/// let blocks = Block::parse_all(text);
+/// assert_eq!(blocks.len(), 3);
+/// assert_eq!(blocks[0].id, Some("division".to_string()));
+/// assert_eq!(&text[blocks[0].range.clone()], "Cells divide.");
+/// assert_eq!(&text[blocks[2].range.clone()], "- Two");
+/// ```
+/// @public
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Block {
+    pub id: Option<String>,
+    // byte range of the block text without its anchor, in the text it was parsed from
+    pub range: Range<usize>,
+    // byte range of the `^id` anchor, including the whitespace or blank lines before it
+    pub anchor_range: Option<Range<usize>>,
+}
+
+impl Block {
+    /// Parses all blocks in the given text, in order of appearance.
+    ///
+    /// Blocks are separated by blank lines; each list item and heading is a block of its own. A
+    /// `^id` at the end of a line is the id of the block it ends. An `^id` on a line of its own
+    /// is the id of the block just before it, as Obsidian does for tables, quotes and callouts.
+    /// Anchors in code or `%% comments %%` are ignored.
+    ///
+    /// # Arguments
+    /// @param text: &str - The text to parse.
+    /// @returns Vec<Block> - The blocks found in the text.
+    pub fn parse_all(text: &str) -> Vec<Block> {
+        let anchor_pattern = Regex::new(r"(?:^|[ \t]+)\^([A-Za-z0-9-]+)[ \t]*$").unwrap();
+        let item_pattern = Regex::new(r"^[ \t]*(?:[-*+]|\d+[.)])[ \t]|^#{1,6}[ \t]").unwrap();
+        let hidden = super::obsidian::hidden_ranges(text);
+
+        let mut blocks: Vec<Block> = Vec::new();
+        // start of the block being read
+        let mut open: Option<usize> = None;
+        let mut offset = 0;
+        for line in text.split_inclusive('\n') {
+            let start = offset;
+            offset += line.len();
+            let content = line.trim_end_matches(['\n', '\r']);
+            let blank = content.trim().is_empty();
+            // a hidden range may start with the blank lines before a code block
+            if hidden.iter().any(|range| range.contains(&start) && (range.start < start || !blank)) {
+                open.get_or_insert(start);
+                continue;
+            }
+            if blank {
+                close_block(text, &mut blocks, open.take(), start);
+                continue;
+            }
+            if item_pattern.is_match(content) {
+                close_block(text, &mut blocks, open.take(), start);
+            }
+            let block_start = *open.get_or_insert(start);
+
+            let Some(captures) = anchor_pattern.captures(content) else {
+                continue;
+            };
+            let anchor = captures.get(0).unwrap();
+            let id = Some(captures[1].to_string());
+            let anchor_end = start + content.len();
+            if anchor.start() > 0 {
+                blocks.push(Block {
+                    id,
+                    range: block_start..start + anchor.start(),
+                    anchor_range: Some(start + anchor.start()..anchor_end),
+                });
+            } else if block_start < start {
+                let range = trimmed_range(text, block_start, start);
+                blocks.push(Block {
+                    id,
+                    anchor_range: Some(range.end..anchor_end),
+                    range,
+                });
+            } else if let Some(last) = blocks.last_mut().filter(|last| last.id.is_none()) {
+                last.id = id;
+                last.anchor_range = Some(last.range.end..anchor_end);
+            }
+            open = None;
+        }
+        close_block(text, &mut blocks, open, text.len());
+        blocks
+    }
+}
+
+/// Add the block starting at `start` and ending before `end`, if one is open.
+///
+/// @private
+fn close_block(text: &str, blocks: &mut Vec<Block>, start: Option<usize>, end: usize) {
+    if let Some(start) = start {
+        blocks.push(Block {
+            id: None,
+            range: trimmed_range(text, start, end),
+            anchor_range: None,
+        });
+    }
+}
+
+/// The range from `start` to `end` without trailing whitespace.
+///
+/// @private
+fn trimmed_range(text: &str, start: usize, end: usize) -> Range<usize> {
+    start..start + text[start..end].trim_end().len()
+}
+
+#[cfg(test)]
+mod block_tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_inline_and_standalone_anchors() {
+        let text = "Intro\nline two ^intro\n\n| a | b |\n| - | - |\n\n^table\n\n```\ncode ^not-an-id\n```\n\n> quote\n^quote";
+        let blocks = Block::parse_all(text);
+        let actual: Vec<(Option<&str>, &str)> = blocks
+            .iter()
+            .map(|block| (block.id.as_deref(), &text[block.range.clone()]))
+            .collect();
+        let expected = vec![
+            (Some("intro"), "Intro\nline two"),
+            (Some("table"), "| a | b |\n| - | - |"),
+            (None, "```\ncode ^not-an-id\n```"),
+            (Some("quote"), "> quote"),
+        ];
+        assert_eq!(actual, expected);
+    }
+}
@@ -0,0 +1,205 @@
+//! obsidian-driver::file::mdfile::callout
+//!
+//! This module contains the Callout struct, which represents an Obsidian callout block (`> [!note] Title`) in a markdown file.
+//!
+//! @public Callout
+//!
+//! @public Callout::new
+//!
+//! @public Callout::with_title
+//!
+//! @public Callout::with_fold
+//!
+//! @public Callout::parse_all
+//!
+//! @public CalloutFold
+
+// std imports
+use std::fmt::Display;
+use std::ops::Range;
+
+// third-party imports
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+/// Whether a callout is foldable, and its initial state.
+///
+/// @public
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CalloutFold {
+    /// `[!note]+`, expanded by default
+    Open,
+    /// `[!note]-`, collapsed by default
+    Closed,
+}
+
+/// The `Callout` struct represents a callout block.
+///
+/// # Example
+/// ```
+/// use obsidian_driver::file::mdfile::callout::{Callout, CalloutFold};
+///
+/// let callout = Callout::new("summary", "First line\n\nSecond paragraph")
+/// 	.with_title("Summary")
+/// 	.with_fold(CalloutFold::Closed);
+/// assert_eq!(callout.to_string(), "> [!summary]- Summary\n> First line\n>\n> Second paragraph");
+///
+/// let parsed = Callout::parse_all(&callout.to_string());
+/// assert_eq!(parsed[0].kind, "summary");
+/// assert_eq!(parsed[0].content, "First line\n\nSecond paragraph");
+/// ```
+/// @public
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Callout {
+    // the callout type, lowercase, e.g. "note", "warning", "summary"
+    pub kind: String,
+    pub title: Option<String>,
+    pub fold: Option<CalloutFold>,
+    // the content with the leading `>` markers removed
+    pub content: String,
+    // byte range of the whole block in the text it was parsed from, empty for new callouts
+    pub range: Range<usize>,
+}
+
+impl Callout {
+    /// Creates a new callout without a title.
+    ///
+    /// # Arguments
+    /// @param kind: &str - The callout type, e.g. `note`.
+    /// @param content: &str - The content, as plain markdown.
+    /// @returns Callout
+    pub fn new(kind: &str, content: &str) -> Callout {
+        Callout {
+            kind: kind.to_lowercase(),
+            title: None,
+            fold: None,
+            content: content.to_string(),
+            range: 0..0,
+        }
+    }
+
+    /// Sets the title shown after the callout type.
+    ///
+    /// # Arguments
+    /// @param title: &str
+    /// @returns Callout
+    pub fn with_title(mut self, title: &str) -> Callout {
+        self.title = Some(title.to_string());
+        self
+    }
+
+    /// Makes the callout foldable.
+    ///
+    /// # Arguments
+    /// @param fold: CalloutFold
+    /// @returns Callout
+    pub fn with_fold(mut self, fold: CalloutFold) -> Callout {
+        self.fold = Some(fold);
+        self
+    }
+
+    /// Parses all top-level callouts in the given text, in order of appearance. Callouts inside code blocks or comments are ignored.
+    ///
+    /// # Arguments
+    /// @param text: &str - The text to parse.
+    /// @returns Vec<Callout> - The callouts found in the text.
+    pub fn parse_all(text: &str) -> Vec<Callout> {
+        let header_pattern = Regex::new(r"^[ \t]*>[ \t]*\[!([^\]\s]+)\]([+-]?)[ \t]*(.*?)[ \t]*$").unwrap();
+        let hidden = super::obsidian::hidden_ranges(text);
+
+        let mut callouts: Vec<Callout> = Vec::new();
+        // the callout whose lines are being read
+        let mut current: Option<Callout> = None;
+        let mut offset = 0;
+        for line in text.split_inclusive('\n') {
+            let start = offset;
+            offset += line.len();
+            let trimmed = line.trim_end_matches(['\n', '\r']);
+            let quoted = trimmed.trim_start().strip_prefix('>');
+
+            if let (Some(callout), Some(quoted)) = (current.as_mut(), quoted) {
+                let quoted = quoted.strip_prefix(' ').unwrap_or(quoted);
+                callout.content.push('\n');
+                callout.content.push_str(quoted);
+                callout.range.end = start + trimmed.len();
+                continue;
+            }
+            callouts.extend(current.take());
+
+            if hidden.iter().any(|range| range.contains(&start)) {
+                continue;
+            }
+            if let Some(captures) = header_pattern.captures(trimmed) {
+                let title = captures[3].to_string();
+                current = Some(Callout {
+                    kind: captures[1].to_lowercase(),
+                    title: (!title.is_empty()).then_some(title),
+                    fold: match &captures[2] {
+                        "+" => Some(CalloutFold::Open),
+                        "-" => Some(CalloutFold::Closed),
+                        _ => None,
+                    },
+                    content: String::new(),
+                    range: start..start + trimmed.len(),
+                });
+            }
+        }
+        callouts.extend(current);
+
+        for callout in callouts.iter_mut() {
+            // the first pushed line starts with the separator newline
+            if let Some(content) = callout.content.strip_prefix('\n') {
+                callout.content = content.to_string();
+            }
+        }
+        callouts
+    }
+}
+
+impl Display for Callout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let fold = match self.fold {
+            Some(CalloutFold::Open) => "+",
+            Some(CalloutFold::Closed) => "-",
+            None => "",
+        };
+        write!(f, "> [!{}]{}", self.kind, fold)?;
+        if let Some(title) = &self.title {
+            write!(f, " {}", title)?;
+        }
+        if self.content.is_empty() {
+            return Ok(());
+        }
+        for line in self.content.lines() {
+            if line.is_empty() {
+                write!(f, "\n>")?;
+            } else {
+                write!(f, "\n> {}", line)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod callout_tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_callouts() {
+        let text = "> [!NOTE]+ Title\n> line one\n>line two\n\n> plain quote\n```\n> [!tip]\n```\n> [!warning]\n";
+        let callouts = Callout::parse_all(text);
+        let actual: Vec<(&str, &str, &str)> = callouts
+            .iter()
+            .map(|callout| (callout.kind.as_str(), callout.content.as_str(), &text[callout.range.clone()]))
+            .collect();
+        let expected = vec![
+            ("note", "line one\nline two", "> [!NOTE]+ Title\n> line one\n>line two"),
+            ("warning", "", "> [!warning]"),
+        ];
+        assert_eq!(actual, expected);
+        assert_eq!(callouts[0].title, Some("Title".to_string()));
+        assert_eq!(callouts[0].fold, Some(CalloutFold::Open));
+        assert_eq!(callouts[1].title, None);
+    }
+}
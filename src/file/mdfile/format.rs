@@ -0,0 +1,170 @@
+//! obsidian-driver::file::mdfile::format
+//!
+//! This module contains the markdown normalizer, which rewrites the body of a file in one consistent style, so AI-generated and hand-written notes look alike and rewriting a whole vault only changes what is out of style.
+//!
+//! @public NormalizeStyle
+//!
+//! @public MDFile::normalize
+
+// third-party imports
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+// first-party imports
+use super::MDFile;
+
+/// NormalizeStyle struct
+///
+/// The style `MDFile::normalize` enforces. Line endings always become `\n`. Fenced code blocks
+/// are left as they are. By default every rule is applied and lists use `-`.
+///
+/// @public
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NormalizeStyle {
+    // one space after the `#`s of a heading, and a blank line before and after it
+    pub heading_spacing: bool,
+    // the marker of unordered list items, one of `-`, `*`, `+`; None keeps each item's marker
+    pub list_marker: Option<char>,
+    // replace runs of blank lines with one
+    pub collapse_blank_lines: bool,
+    // end with exactly one newline
+    pub final_newline: bool,
+}
+
+impl Default for NormalizeStyle {
+    fn default() -> Self {
+        NormalizeStyle {
+            heading_spacing: true,
+            list_marker: Some('-'),
+            collapse_blank_lines: true,
+            final_newline: true,
+        }
+    }
+}
+
+impl MDFile {
+    /// Rewrites the body in a consistent style. Normalizing a normalized body changes nothing.
+    ///
+    /// # Arguments
+    /// @param style: &NormalizeStyle - The rules to enforce.
+    /// @returns bool - Whether the body changed.
+    ///
+    /// # Example
+    /// ```
+    /// use obsidian_driver::file::mdfile::MDFile;
+    /// use obsidian_driver::file::mdfile::format::NormalizeStyle;
+    ///
+    /// let mut file = MDFile::from_string("#  Cells\r\nText\r\n\r\n\r\n* One\r\n+ Two\r\n## Parts".to_string());
+    /// assert!(file.normalize(&NormalizeStyle::default()));
+    /// assert_eq!(file.get_body(), "# Cells\n\nText\n\n- One\n- Two\n\n## Parts\n");
+    /// assert!(!file.normalize(&NormalizeStyle::default()));
+    /// ```
+    pub fn normalize(&mut self, style: &NormalizeStyle) -> bool {
+        let normalized = normalize_text(self.get_body(), style);
+        if &normalized == self.get_body() {
+            return false;
+        }
+        self.set_body(normalized);
+        true
+    }
+}
+
+/// Normalize markdown text.
+///
+/// @private
+fn normalize_text(text: &str, style: &NormalizeStyle) -> String {
+    let heading_pattern = Regex::new(r"^(#{1,6})[ \t]+(.*?)[ \t]*$").unwrap();
+    let list_pattern = Regex::new(r"^([ \t]*)[-*+]([ \t]+)").unwrap();
+    let text = text.replace("\r\n", "\n").replace('\r', "\n");
+
+    let mut lines: Vec<String> = Vec::new();
+    // the marker of the open code block
+    let mut fence: Option<String> = None;
+    let mut after_heading = false;
+    for line in text.split('\n') {
+        if let Some(marker) = &fence {
+            if line.trim() == marker.as_str() {
+                fence = None;
+            }
+            lines.push(line.to_string());
+            continue;
+        }
+        let blank = line.trim().is_empty();
+        if blank {
+            if !(style.collapse_blank_lines && lines.last().is_some_and(|last| last.trim().is_empty())) {
+                lines.push(String::new());
+            }
+            after_heading = false;
+            continue;
+        }
+        let needs_blank = |lines: &Vec<String>| lines.last().is_some_and(|last| !last.trim().is_empty());
+        if after_heading && needs_blank(&lines) {
+            lines.push(String::new());
+        }
+        after_heading = false;
+
+        let trimmed = line.trim_start();
+        let marker: String = trimmed.chars().take_while(|c| *c == '`' || *c == '~').collect();
+        if marker.len() >= 3 && marker.chars().all(|c| c == marker.chars().next().unwrap_or('`')) {
+            fence = Some(marker);
+            lines.push(line.to_string());
+            continue;
+        }
+
+        if let Some(captures) = heading_pattern.captures(line).filter(|_| style.heading_spacing) {
+            if needs_blank(&lines) {
+                lines.push(String::new());
+            }
+            lines.push(format!("{} {}", &captures[1], &captures[2]));
+            after_heading = true;
+            continue;
+        }
+
+        match (style.list_marker, list_pattern.captures(line)) {
+            // `* * *` is a thematic break, not a list
+            (Some(list_marker), Some(captures)) if !is_thematic_break(line) => {
+                let prefix = captures.get(0).map_or(0, |prefix| prefix.end());
+                lines.push(format!("{}{}{}{}", &captures[1], list_marker, &captures[2], &line[prefix..]));
+            }
+            _ => lines.push(line.to_string()),
+        }
+    }
+
+    let mut normalized = lines.join("\n");
+    if style.final_newline {
+        let trimmed_length = normalized.trim_end_matches(['\n', ' ', '\t']).len();
+        normalized.truncate(trimmed_length);
+        if !normalized.is_empty() {
+            normalized.push('\n');
+        }
+    }
+    normalized
+}
+
+/// Check whether a line is a thematic break, e.g. `---` or `* * *`.
+///
+/// @private
+fn is_thematic_break(line: &str) -> bool {
+    let characters: Vec<char> = line.chars().filter(|c| !c.is_whitespace()).collect();
+    characters.len() >= 3 && characters.iter().all(|c| *c == characters[0]) && matches!(characters[0], '-' | '*' | '_')
+}
+
+#[cfg(test)]
+mod format_tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_leaves_code_and_front_matter() {
+        let text = "---\ntags: [a]\n---\n# Title\n```md\n* kept\n\n\n#  kept\n```\n* * *\n\t* nested\n";
+        let mut file = MDFile::from_string(text.to_string());
+        file.normalize(&NormalizeStyle::default());
+        assert_eq!(file.to_string(), "---\ntags: [a]\n---\n# Title\n\n```md\n* kept\n\n\n#  kept\n```\n* * *\n\t- nested\n");
+
+        let style = NormalizeStyle {
+            list_marker: None,
+            final_newline: false,
+            ..NormalizeStyle::default()
+        };
+        assert_eq!(normalize_text("* a\n\n\n\nb", &style), "* a\n\nb");
+    }
+}
@@ -0,0 +1,344 @@
+//! obsidian-driver::file::mdfile::link
+//!
+//! This module contains the Link struct, which represents a link from a markdown file to another file in the vault.
+//!
+//! @public Link
+//!
+//! @public Link::parse_all
+//!
+//! @public Link::display_text
+//!
+//! @public Link::with_target
+//!
+//! @public Link::heading_path
+//!
+//! @public Link::block_id
+//!
+//! @public Link::parse_reference
+//!
+//! @public LinkKind
+
+// std imports
+use std::ops::Range;
+
+// third-party imports
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+/// The kind of syntax a link was written with.
+///
+/// @public
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LinkKind {
+    /// `[[target#heading|alias]]`
+    Wiki,
+    /// `[alias](target#heading)`
+    Markdown,
+}
+
+/// The `Link` struct represents a single internal link found in the body of a markdown file.
+///
+/// External links (urls) are not parsed.
+///
+/// # Example
+/// ```
+/// use obsidian_driver::file::mdfile::link::{Link, LinkKind};
+///
+/// let links = Link::parse_all("See [[Other Note#Summary|the summary]].");
+/// assert_eq!(links.len(), 1);
+/// assert_eq!(links[0].kind, LinkKind::Wiki);
+/// assert_eq!(links[0].target, "Other Note");
+/// assert_eq!(links[0].heading, Some("Summary".to_string()));
+/// assert_eq!(links[0].alias, Some("the summary".to_string()));
+/// ```
+/// @public
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Link {
+    pub kind: LinkKind,
+    pub embed: bool,
+    pub target: String,
+    pub heading: Option<String>,
+    pub alias: Option<String>,
+    // byte range of the whole link in the text it was parsed from
+    pub range: Range<usize>,
+}
+
+impl Link {
+    /// Parses all internal links in the given text, in order of appearance. Links in code or `%% comments %%` are ignored.
+    ///
+    /// # Arguments
+    /// @param text: &str - The text to parse.
+    /// @returns Vec<Link> - The links found in the text.
+    ///
+    /// # Example
+    /// ```
+    /// use obsidian_driver::file::mdfile::link::{Link, LinkKind};
+    ///
+    /// let links = Link::parse_all("![[image.png]] and [a note](folder/A%20Note.md) but not [a site](https://example.com)");
+    /// assert_eq!(links.len(), 2);
+    /// assert!(links[0].embed);
+    /// assert_eq!(links[1].kind, LinkKind::Markdown);
+    /// assert_eq!(links[1].target, "folder/A Note.md");
+    /// ```
+    pub fn parse_all(text: &str) -> Vec<Link> {
+        let wiki_pattern =
+            Regex::new(r"(!?)\[\[([^\[\]|#]*)(?:#([^\[\]|]*))?(?:\|([^\[\]]*))?\]\]").unwrap();
+        let markdown_pattern = Regex::new(r"(!?)\[([^\[\]]*)\]\(([^()\s]+)\)").unwrap();
+
+        let mut links = Vec::new();
+        for captures in wiki_pattern.captures_iter(text) {
+            let whole = captures.get(0).unwrap();
+            links.push(Link {
+                kind: LinkKind::Wiki,
+                embed: !captures[1].is_empty(),
+                target: captures[2].trim().to_string(),
+                heading: captures.get(3).map(|m| m.as_str().trim().to_string()),
+                alias: captures.get(4).map(|m| m.as_str().to_string()),
+                range: whole.range(),
+            });
+        }
+        for captures in markdown_pattern.captures_iter(text) {
+            let whole = captures.get(0).unwrap();
+            let destination = &captures[3];
+            if destination.contains("://") || destination.starts_with("mailto:") {
+                continue;
+            }
+            let (target, heading) = match destination.split_once('#') {
+                Some((target, heading)) => (target, Some(percent_decode(heading))),
+                None => (destination, None),
+            };
+            links.push(Link {
+                kind: LinkKind::Markdown,
+                embed: !captures[1].is_empty(),
+                target: percent_decode(target),
+                heading,
+                alias: Some(captures[2].to_string()),
+                range: whole.range(),
+            });
+        }
+        let hidden = super::obsidian::hidden_ranges(text);
+        links.retain(|link| !hidden.iter().any(|range| range.contains(&link.range.start)));
+        links.sort_by_key(|link| link.range.start);
+        links
+    }
+
+    /// Gets the text a reader sees for the link, used when a link is replaced by plain text.
+    ///
+    /// # Arguments
+    /// @returns String - The alias if present, otherwise the target and heading.
+    ///
+    /// # Example
+    /// ```
+    /// use obsidian_driver::file::mdfile::link::Link;
+    ///
+    /// let links = Link::parse_all("[[Note#Heading]] [[Note|Alias]]");
+    /// assert_eq!(links[0].display_text(), "Note > Heading");
+    /// assert_eq!(links[1].display_text(), "Alias");
+    /// ```
+    pub fn display_text(&self) -> String {
+        if let Some(alias) = &self.alias {
+            return alias.clone();
+        }
+        match &self.heading {
+            Some(heading) if self.target.is_empty() => heading.clone(),
+            Some(heading) => format!("{} > {}", self.target, heading),
+            None => self.target.clone(),
+        }
+    }
+
+    /// Gets the headings the link points at, outermost first: `[[Note#Part#Summary]]` points at the `Summary` heading under `Part`.
+    ///
+    /// # Arguments
+    /// @returns Vec<String> - The headings, empty if the link points at the whole note or at a block (`#^id`).
+    ///
+    /// # Example
+    /// ```
+    /// use obsidian_driver::file::mdfile::link::Link;
+    ///
+    /// let links = Link::parse_all("[[Note#Part#Summary]] [[Note#^block]]");
+    /// assert_eq!(links[0].heading_path(), vec!["Part".to_string(), "Summary".to_string()]);
+    /// assert!(links[1].heading_path().is_empty());
+    /// ```
+    pub fn heading_path(&self) -> Vec<String> {
+        match &self.heading {
+            Some(heading) if !heading.starts_with('^') => heading
+                .split('#')
+                .map(str::trim)
+                .filter(|heading| !heading.is_empty())
+                .map(String::from)
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Gets the block the link points at, as in `[[Note#^block-id]]`.
+    ///
+    /// # Arguments
+    /// @returns Option<&str> - The block id, without the `^`.
+    ///
+    /// # Example
+    /// ```
+    /// use obsidian_driver::file::mdfile::link::Link;
+    ///
+    /// let links = Link::parse_all("[[Note#^division]] [[Note#Heading]]");
+    /// assert_eq!(links[0].block_id(), Some("division"));
+    /// assert_eq!(links[1].block_id(), None);
+    /// ```
+    pub fn block_id(&self) -> Option<&str> {
+        self.heading.as_deref()?.strip_prefix('^').map(str::trim)
+    }
+
+    /// Parses a reference to a note or one of its headings written outside of a link: `[[Note#Heading]]`, `Note#Heading`, or `Note > Heading > Subheading` as Obsidian displays heading links.
+    ///
+    /// # Arguments
+    /// @param reference: &str - The reference.
+    /// @returns Link - A wikilink with the target and headings of the reference, spanning the whole (trimmed) reference.
+    ///
+    /// # Example
+    /// ```
+    /// use obsidian_driver::file::mdfile::link::Link;
+    ///
+    /// let link = Link::parse_reference("Cells > Structure > Membrane");
+    /// assert_eq!(link.target, "Cells");
+    /// assert_eq!(link.heading_path(), vec!["Structure".to_string(), "Membrane".to_string()]);
+    /// assert_eq!(Link::parse_reference("[[Cells#Structure]]").heading, Some("Structure".to_string()));
+    /// ```
+    pub fn parse_reference(reference: &str) -> Link {
+        let reference = reference.trim();
+        let whole_link = Link::parse_all(reference)
+            .into_iter()
+            .find(|link| link.range == (0..reference.len()));
+        if let Some(link) = whole_link {
+            return link;
+        }
+        let mut parts: Vec<&str> = match reference.contains(" > ") {
+            true => reference.split(" > ").collect(),
+            false => reference.split('#').collect(),
+        };
+        let target = parts.remove(0).trim().to_string();
+        let heading = parts
+            .iter()
+            .map(|part| part.trim())
+            .filter(|part| !part.is_empty())
+            .collect::<Vec<_>>()
+            .join("#");
+        Link {
+            kind: LinkKind::Wiki,
+            embed: false,
+            target,
+            heading: (!heading.is_empty()).then_some(heading),
+            alias: None,
+            range: 0..reference.len(),
+        }
+    }
+
+    /// Renders the link with a new target, keeping its kind, embed marker, heading, and alias.
+    ///
+    /// # Arguments
+    /// @param target: &str - The new target, e.g. `Folder/Note` for wikilinks or `../Note.md` for markdown links.
+    /// @returns String - The link text.
+    ///
+    /// # Example
+    /// ```
+    /// use obsidian_driver::file::mdfile::link::Link;
+    ///
+    /// let links = Link::parse_all("[[Old#Heading|Alias]] [text](Old.md)");
+    /// assert_eq!(links[0].with_target("Folder/New"), "[[Folder/New#Heading|Alias]]");
+    /// assert_eq!(links[1].with_target("Folder/New Note.md"), "[text](Folder/New%20Note.md)");
+    /// ```
+    pub fn with_target(&self, target: &str) -> String {
+        let embed = if self.embed { "!" } else { "" };
+        match self.kind {
+            LinkKind::Wiki => {
+                let heading = self
+                    .heading
+                    .as_ref()
+                    .map(|heading| format!("#{}", heading))
+                    .unwrap_or_default();
+                let alias = self
+                    .alias
+                    .as_ref()
+                    .map(|alias| format!("|{}", alias))
+                    .unwrap_or_default();
+                format!("{}[[{}{}{}]]", embed, target, heading, alias)
+            }
+            LinkKind::Markdown => {
+                let heading = self
+                    .heading
+                    .as_ref()
+                    .map(|heading| format!("#{}", percent_encode(heading)))
+                    .unwrap_or_default();
+                format!(
+                    "{}[{}]({}{})",
+                    embed,
+                    self.alias.as_deref().unwrap_or_default(),
+                    percent_encode(target),
+                    heading
+                )
+            }
+        }
+    }
+}
+
+/// Encodes the characters that cannot appear in a markdown link destination.
+///
+/// @private
+fn percent_encode(text: &str) -> String {
+    let mut encoded = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            ' ' => encoded.push_str("%20"),
+            '(' => encoded.push_str("%28"),
+            ')' => encoded.push_str("%29"),
+            '#' => encoded.push_str("%23"),
+            _ => encoded.push(c),
+        }
+    }
+    encoded
+}
+
+/// Decodes `%XX` escapes in a markdown link destination.
+///
+/// @private
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            if let Some(Ok(byte)) = text.get(i + 1..i + 3).map(|hex| u8::from_str_radix(hex, 16)) {
+                decoded.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[cfg(test)]
+mod link_tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_wiki_and_markdown_links() {
+        let text = "[[A]] ![[B#^block]] [c](C.md#Top) [d](https://d.com) `[[E]]` %%[[F]]%%";
+        let actual: Vec<(LinkKind, &str)> = Link::parse_all(text)
+            .iter()
+            .map(|link| (link.kind, &text[link.range.clone()]))
+            .collect();
+        let expected = vec![
+            (LinkKind::Wiki, "[[A]]"),
+            (LinkKind::Wiki, "![[B#^block]]"),
+            (LinkKind::Markdown, "[c](C.md#Top)"),
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_percent_decode() {
+        assert_eq!(percent_decode("A%20Note%2"), "A Note%2");
+    }
+}
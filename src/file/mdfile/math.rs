@@ -0,0 +1,277 @@
+//! obsidian-driver::file::mdfile::math
+//!
+//! This module contains the Unicode math converter, which replaces Unicode math symbols (α, Σ, →, ≤, ...) with the LaTeX the note prompts ask for, so notes render math the same way whether it was typed, pasted, or generated.
+//!
+//! @public unicode_to_latex
+//!
+//! @public MDFile::convert_unicode_math
+
+// std imports
+use std::ops::Range;
+
+// third-party imports
+use regex::Regex;
+
+// first-party imports
+use super::MDFile;
+
+/// LaTeX commands for the Unicode math symbols that are converted.
+const UNICODE_LATEX: [(char, &str); 99] = [
+    // greek
+    ('α', r"\alpha"),
+    ('β', r"\beta"),
+    ('γ', r"\gamma"),
+    ('δ', r"\delta"),
+    ('ε', r"\varepsilon"),
+    ('ϵ', r"\epsilon"),
+    ('ζ', r"\zeta"),
+    ('η', r"\eta"),
+    ('θ', r"\theta"),
+    ('ϑ', r"\vartheta"),
+    ('ι', r"\iota"),
+    ('κ', r"\kappa"),
+    ('λ', r"\lambda"),
+    ('μ', r"\mu"),
+    ('ν', r"\nu"),
+    ('ξ', r"\xi"),
+    ('π', r"\pi"),
+    ('ρ', r"\rho"),
+    ('σ', r"\sigma"),
+    ('ς', r"\varsigma"),
+    ('τ', r"\tau"),
+    ('υ', r"\upsilon"),
+    ('φ', r"\varphi"),
+    ('ϕ', r"\phi"),
+    ('χ', r"\chi"),
+    ('ψ', r"\psi"),
+    ('ω', r"\omega"),
+    ('Γ', r"\Gamma"),
+    ('Δ', r"\Delta"),
+    ('Θ', r"\Theta"),
+    ('Λ', r"\Lambda"),
+    ('Ξ', r"\Xi"),
+    ('Π', r"\Pi"),
+    ('Σ', r"\Sigma"),
+    ('Υ', r"\Upsilon"),
+    ('Φ', r"\Phi"),
+    ('Ψ', r"\Psi"),
+    ('Ω', r"\Omega"),
+    // operators
+    ('×', r"\times"),
+    ('÷', r"\div"),
+    ('±', r"\pm"),
+    ('∓', r"\mp"),
+    ('·', r"\cdot"),
+    ('∘', r"\circ"),
+    ('∑', r"\sum"),
+    ('∏', r"\prod"),
+    ('∫', r"\int"),
+    ('∮', r"\oint"),
+    ('∂', r"\partial"),
+    ('∇', r"\nabla"),
+    ('∞', r"\infty"),
+    ('°', r"^\circ"),
+    ('⊕', r"\oplus"),
+    ('⊗', r"\otimes"),
+    // relations
+    ('≤', r"\leq"),
+    ('≥', r"\geq"),
+    ('≠', r"\neq"),
+    ('≈', r"\approx"),
+    ('≡', r"\equiv"),
+    ('∼', r"\sim"),
+    ('≅', r"\cong"),
+    ('∝', r"\propto"),
+    ('≪', r"\ll"),
+    ('≫', r"\gg"),
+    // arrows
+    ('→', r"\to"),
+    ('←', r"\leftarrow"),
+    ('↔', r"\leftrightarrow"),
+    ('⇒', r"\Rightarrow"),
+    ('⇐', r"\Leftarrow"),
+    ('⇔', r"\Leftrightarrow"),
+    ('↦', r"\mapsto"),
+    ('↑', r"\uparrow"),
+    ('↓', r"\downarrow"),
+    // sets and logic
+    ('∈', r"\in"),
+    ('∉', r"\notin"),
+    ('∋', r"\ni"),
+    ('⊂', r"\subset"),
+    ('⊆', r"\subseteq"),
+    ('⊃', r"\supset"),
+    ('⊇', r"\supseteq"),
+    ('∪', r"\cup"),
+    ('∩', r"\cap"),
+    ('∅', r"\emptyset"),
+    ('∀', r"\forall"),
+    ('∃', r"\exists"),
+    ('∄', r"\nexists"),
+    ('¬', r"\neg"),
+    ('∧', r"\land"),
+    ('∨', r"\lor"),
+    ('⊤', r"\top"),
+    ('⊥', r"\bot"),
+    ('⊢', r"\vdash"),
+    ('⊨', r"\models"),
+    ('ℕ', r"\mathbb{N}"),
+    ('ℤ', r"\mathbb{Z}"),
+    ('ℚ', r"\mathbb{Q}"),
+    ('ℝ', r"\mathbb{R}"),
+    ('ℂ', r"\mathbb{C}"),
+    ('ℓ', r"\ell"),
+];
+
+/// Replace Unicode math symbols with LaTeX.
+///
+/// Outside math, each run of adjacent symbols becomes one inline math span, e.g. `α→β` becomes
+/// `$\alpha \to \beta$`. Inside `$...$` and `$$...$$` symbols become bare commands. Code and
+/// the targets of links are left as they are. Converting converted text changes nothing.
+///
+/// # Arguments
+/// @param text: &str - The markdown to convert.
+/// @returns String - The converted markdown.
+///
+/// # Example
+/// ```
+/// use obsidian_driver::file::mdfile::math::unicode_to_latex;
+///
+/// assert_eq!(unicode_to_latex("If x ≤ ε then f(x)→0"), r"If x $\leq$ $\varepsilon$ then f(x)$\to$0");
+/// assert_eq!(unicode_to_latex("$Σ_i αx_i$ in [[α decay]]"), r"$\Sigma_i \alpha x_i$ in [[α decay]]");
+/// ```
+/// @public
+pub fn unicode_to_latex(text: &str) -> String {
+    let mut converted = String::with_capacity(text.len());
+    let mut position = 0;
+    for (range, is_math) in protected_ranges(text) {
+        converted.push_str(&convert_prose(&text[position..range.start]));
+        match is_math {
+            true => converted.push_str(&convert_math(&text[range.clone()])),
+            false => converted.push_str(&text[range.clone()]),
+        }
+        position = range.end;
+    }
+    converted.push_str(&convert_prose(&text[position..]));
+    converted
+}
+
+impl MDFile {
+    /// Replace Unicode math symbols in the body with LaTeX, see `unicode_to_latex`.
+    ///
+    /// # Arguments
+    /// @returns bool - Whether the body changed.
+    ///
+    /// # Example
+    /// ```
+    /// use obsidian_driver::file::mdfile::MDFile;
+    ///
+    /// let mut file = MDFile::from_string("---\nunit: Ω\n---\nV = I·R, in Ω".to_string());
+    /// assert!(file.convert_unicode_math());
+    /// assert_eq!(file.to_string(), "---\nunit: Ω\n---\nV = I$\\cdot$R, in $\\Omega$");
+    /// ```
+    pub fn convert_unicode_math(&mut self) -> bool {
+        let converted = unicode_to_latex(self.get_body());
+        if &converted == self.get_body() {
+            return false;
+        }
+        self.set_body(converted);
+        true
+    }
+}
+
+/// The ranges that are not prose, sorted and without overlaps: code and link targets, and math (marked true).
+///
+/// @private
+fn protected_ranges(text: &str) -> Vec<(Range<usize>, bool)> {
+    let link_pattern = Regex::new(r"\[\[[^\]\n]*\]\]|\]\([^)\n]*\)").unwrap();
+    let math_pattern = Regex::new(r"(?s)\$\$.+?\$\$|\$[^$\n]+\$").unwrap();
+    let mut ranges: Vec<(Range<usize>, bool)> = super::code_ranges(text).into_iter().map(|range| (range, false)).collect();
+    ranges.extend(link_pattern.find_iter(text).map(|m| (m.range(), false)));
+    // math is found in the text around code and links, so a `$` in code does not start math
+    let mut position = 0;
+    let mut skipped = ranges.clone();
+    skipped.sort_by_key(|(range, _)| range.start);
+    for (range, _) in skipped.iter().chain(std::iter::once(&(text.len()..text.len(), false))) {
+        if range.start > position {
+            let gap = &text[position..range.start];
+            ranges.extend(math_pattern.find_iter(gap).map(|m| (position + m.start()..position + m.end(), true)));
+        }
+        position = position.max(range.end);
+    }
+    ranges.sort_by_key(|(range, _)| (range.start, std::cmp::Reverse(range.end)));
+    let mut disjoint: Vec<(Range<usize>, bool)> = Vec::new();
+    for (range, is_math) in ranges {
+        if disjoint.last().is_none_or(|(last, _)| range.start >= last.end) {
+            disjoint.push((range, is_math));
+        }
+    }
+    disjoint
+}
+
+/// The LaTeX command for a symbol.
+///
+/// @private
+fn latex(c: char) -> Option<&'static str> {
+    UNICODE_LATEX.iter().find(|(symbol, _)| *symbol == c).map(|(_, command)| *command)
+}
+
+/// Wrap each run of symbols in prose in inline math.
+///
+/// @private
+fn convert_prose(text: &str) -> String {
+    let mut converted = String::with_capacity(text.len());
+    let mut run: Vec<&str> = Vec::new();
+    for c in text.chars() {
+        match latex(c) {
+            Some(command) => run.push(command),
+            None => {
+                if !run.is_empty() {
+                    converted.push_str(&format!("${}$", run.join(" ")));
+                    run.clear();
+                }
+                converted.push(c);
+            }
+        }
+    }
+    if !run.is_empty() {
+        converted.push_str(&format!("${}$", run.join(" ")));
+    }
+    converted
+}
+
+/// Replace symbols in math with commands, separated from a following letter.
+///
+/// @private
+fn convert_math(text: &str) -> String {
+    let mut converted = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match latex(c) {
+            Some(command) => {
+                converted.push_str(command);
+                if chars.peek().is_some_and(|next| next.is_ascii_alphabetic()) {
+                    converted.push(' ');
+                }
+            }
+            None => converted.push(c),
+        }
+    }
+    converted
+}
+
+#[cfg(test)]
+mod math_tests {
+    use super::*;
+
+    #[test]
+    fn test_unicode_to_latex_skips_code() {
+        let text = "∀x∈ℝ: `a → b`\n```\nλ\n```\n$$\n∫ f\n$$ [link](α.md) `$` ω";
+        let converted = unicode_to_latex(text);
+        assert_eq!(
+            converted,
+            "$\\forall$x$\\in \\mathbb{R}$: `a → b`\n```\nλ\n```\n$$\n\\int f\n$$ [link](α.md) `$` $\\omega$"
+        );
+        assert_eq!(unicode_to_latex(&converted), converted);
+    }
+}
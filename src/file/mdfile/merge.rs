@@ -0,0 +1,415 @@
+//! obsidian-driver::file::mdfile::merge
+//!
+//! This module contains the structural diff and three-way merge of markdown files. Front matter is compared key by key and the body section by section, where a section is a heading line and the text up to the next heading of any level (the text before the first heading is its own section).
+//!
+//! @public MDFileDiff
+//!
+//! @public FrontmatterChange
+//!
+//! @public SectionChange
+//!
+//! @public MergeResult
+//!
+//! @public MergeConflict
+//!
+//! @public MergeLocation
+//!
+//! @public MDFile::diff
+//!
+//! @public MDFile::merge
+
+// std imports
+use std::collections::HashMap;
+
+// third-party imports
+use serde::{Deserialize, Serialize};
+use serde_yaml::{Mapping, Value};
+
+// first-party imports
+use super::section::Section;
+use super::MDFile;
+use crate::prelude::*;
+
+/// A front matter key that differs between two files.
+///
+/// @public
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FrontmatterChange {
+    pub key: String,
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+}
+
+/// A section that differs between two files, identified by its heading line (e.g. `## Summary`).
+///
+/// The text before the first heading has an empty heading. Repeated headings are numbered, e.g. `## Notes (2)`.
+///
+/// @public
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SectionChange {
+    pub heading: String,
+    pub before: Option<String>,
+    pub after: Option<String>,
+}
+
+/// The structural difference between two markdown files.
+///
+/// @public
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct MDFileDiff {
+    pub frontmatter: Vec<FrontmatterChange>,
+    pub sections: Vec<SectionChange>,
+}
+
+impl MDFileDiff {
+    /// Check whether the files are structurally the same.
+    ///
+    /// # Arguments
+    /// @returns bool
+    pub fn is_empty(&self) -> bool {
+        self.frontmatter.is_empty() && self.sections.is_empty()
+    }
+}
+
+/// Where a merge conflict is.
+///
+/// @public
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MergeLocation {
+    Frontmatter(String),
+    Section(String),
+}
+
+/// A key or section both sides changed differently. The merged file keeps our side.
+///
+/// @public
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MergeConflict {
+    pub location: MergeLocation,
+    // front matter values are rendered as YAML
+    pub base: Option<String>,
+    pub ours: Option<String>,
+    pub theirs: Option<String>,
+}
+
+/// The outcome of a three-way merge.
+///
+/// @public
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MergeResult {
+    pub merged: MDFile,
+    pub conflicts: Vec<MergeConflict>,
+}
+
+impl MergeResult {
+    /// Check whether the merge finished without conflicts.
+    ///
+    /// # Arguments
+    /// @returns bool
+    pub fn is_clean(&self) -> bool {
+        self.conflicts.is_empty()
+    }
+}
+
+impl MDFile {
+    /// Compares the file to another, key by key in the front matter and section by section in the body.
+    ///
+    /// # Arguments
+    /// @param other: &MDFile - The newer file.
+    /// @returns MDFileDiff - The changes from this file to `other`, in the order of `other`.
+    ///
+    /// # Example
+    /// ```
+    /// use obsidian_driver::file::mdfile::MDFile;
+    ///
+    /// let before = MDFile::from_string("---\nstatus: todo\n---\n# A\nOne\n# B\nTwo\n".to_string());
+    /// let after = MDFile::from_string("---\nstatus: done\n---\n# A\nOne\n# B\nThree\n".to_string());
+    ///
+    /// let diff = before.diff(&after);
+    /// assert_eq!(diff.frontmatter[0].key, "status");
+    /// assert_eq!(diff.sections.len(), 1);
+    /// assert_eq!(diff.sections[0].heading, "# B");
+    /// assert_eq!(diff.sections[0].after, Some("# B\nThree\n".to_string()));
+    /// ```
+    pub fn diff(&self, other: &MDFile) -> MDFileDiff {
+        let before = frontmatter_entries(self);
+        let after = frontmatter_entries(other);
+        let frontmatter = merged_keys(&after, &before)
+            .into_iter()
+            .filter_map(|key| {
+                let old = lookup(&before, &key).cloned();
+                let new = lookup(&after, &key).cloned();
+                (old != new).then_some(FrontmatterChange {
+                    key,
+                    before: old,
+                    after: new,
+                })
+            })
+            .collect();
+
+        let before = body_sections(&self.body);
+        let after = body_sections(&other.body);
+        let sections = merged_keys(&after, &before)
+            .into_iter()
+            .filter_map(|heading| {
+                let old = lookup(&before, &heading).cloned();
+                let new = lookup(&after, &heading).cloned();
+                (old != new).then_some(SectionChange {
+                    heading,
+                    before: old,
+                    after: new,
+                })
+            })
+            .collect();
+
+        MDFileDiff { frontmatter, sections }
+    }
+
+    /// Merges two edited versions of a file that share a common base.
+    ///
+    /// Each front matter key and each section is taken from whichever side changed it. When both
+    /// sides changed it to the same thing the change is kept once; when they changed it
+    /// differently, our version is kept and a conflict is reported. Sections added only by
+    /// `theirs` are placed after the section they follow in `theirs`.
+    ///
+    /// # Arguments
+    /// @param base: &MDFile - The common ancestor, e.g. the cached version.
+    /// @param ours: &MDFile - Our edit, e.g. the version on disk.
+    /// @param theirs: &MDFile - Their edit, e.g. AI-generated content.
+    /// @returns MergeResult
+    ///
+    /// # Example
+    /// ```
+    /// use obsidian_driver::file::mdfile::MDFile;
+    ///
+    /// let base = MDFile::from_string("# Notes\nDraft\n# Summary\nTBD\n".to_string());
+    /// let ours = MDFile::from_string("# Notes\nEdited by hand\n# Summary\nTBD\n".to_string());
+    /// let theirs = MDFile::from_string("# Notes\nDraft\n# Summary\nGenerated summary\n".to_string());
+    ///
+    /// let result = MDFile::merge(&base, &ours, &theirs);
+    /// assert!(result.is_clean());
+    /// assert_eq!(result.merged.get_body(), "# Notes\nEdited by hand\n# Summary\nGenerated summary\n");
+    /// ```
+    pub fn merge(base: &MDFile, ours: &MDFile, theirs: &MDFile) -> MergeResult {
+        let mut conflicts = Vec::new();
+
+        let base_yaml = frontmatter_entries(base);
+        let our_yaml = frontmatter_entries(ours);
+        let their_yaml = frontmatter_entries(theirs);
+        let mut mapping = Mapping::new();
+        for key in merged_keys(&our_yaml, &their_yaml) {
+            let (b, o, t) = (lookup(&base_yaml, &key), lookup(&our_yaml, &key), lookup(&their_yaml, &key));
+            let (value, conflict) = merge_value(b, o, t);
+            if conflict {
+                let render = |value: Option<&Value>| value.and_then(|value| serde_yaml::to_string(value).ok());
+                conflicts.push(MergeConflict {
+                    location: MergeLocation::Frontmatter(key.clone()),
+                    base: render(b),
+                    ours: render(o),
+                    theirs: render(t),
+                });
+            }
+            if let Some(value) = value {
+                mapping.insert(Value::String(key), value.clone());
+            }
+        }
+        let yaml = match (&ours.yaml, &theirs.yaml, mapping.is_empty()) {
+            (None, None, true) => None,
+            _ => Some(Value::Mapping(mapping)),
+        };
+
+        let base_sections = body_sections(&base.body);
+        let our_sections = body_sections(&ours.body);
+        let their_sections = body_sections(&theirs.body);
+        let mut body = String::new();
+        for heading in merged_keys(&our_sections, &their_sections) {
+            let (b, o, t) = (
+                lookup(&base_sections, &heading),
+                lookup(&our_sections, &heading),
+                lookup(&their_sections, &heading),
+            );
+            let (text, conflict) = merge_value(b, o, t);
+            if conflict {
+                conflicts.push(MergeConflict {
+                    location: MergeLocation::Section(heading.clone()),
+                    base: b.cloned(),
+                    ours: o.cloned(),
+                    theirs: t.cloned(),
+                });
+            }
+            if let Some(text) = text {
+                if !body.is_empty() && !body.ends_with('\n') {
+                    body.push('\n');
+                }
+                body.push_str(text);
+            }
+        }
+
+        MergeResult {
+            merged: MDFile::new(yaml, body),
+            conflicts,
+        }
+    }
+}
+
+/// Pick the merged value of one key. Returns the value and whether it was a conflict.
+///
+/// @private
+fn merge_value<'a, T: PartialEq>(base: Option<&'a T>, ours: Option<&'a T>, theirs: Option<&'a T>) -> (Option<&'a T>, bool) {
+    if ours == theirs || theirs == base {
+        (ours, false)
+    } else if ours == base {
+        (theirs, false)
+    } else {
+        (ours, true)
+    }
+}
+
+/// The front matter of a file as ordered (key, value) pairs. Non-string keys are rendered as YAML.
+///
+/// @private
+fn frontmatter_entries(mdfile: &MDFile) -> Vec<(String, Value)> {
+    match &mdfile.yaml {
+        Some(Value::Mapping(mapping)) => mapping
+            .iter()
+            .map(|(key, value)| {
+                let key = match key {
+                    Value::String(key) => key.clone(),
+                    key => serde_yaml::to_string(key).unwrap_or_default().trim().to_string(),
+                };
+                (key, value.clone())
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// The body split at every heading into (heading line, text) pairs. The text before the first heading has an empty key and is left out when empty.
+///
+/// Repeated headings are numbered by how often the same heading came before, so a heading that only looks numbered, e.g. `## Notes (draft)`, does not shift the numbers.
+///
+/// @private
+fn body_sections(body: &str) -> Vec<(String, String)> {
+    let headings = Section::parse_all(body);
+    let mut sections = Vec::new();
+    let mut occurrences: HashMap<String, usize> = HashMap::new();
+    let first = headings.first().map(|section| section.range.start).unwrap_or(body.len());
+    if first > 0 {
+        sections.push((String::new(), body[..first].to_string()));
+    }
+    for (i, section) in headings.iter().enumerate() {
+        let end = headings
+            .get(i + 1)
+            .map(|next| next.range.start)
+            .unwrap_or(body.len());
+        let heading = f!("{} {}", "#".repeat(section.level), section.heading);
+        let occurrence = occurrences.entry(heading.clone()).or_insert(0);
+        *occurrence += 1;
+        let mut number = *occurrence;
+        let mut key = if number == 1 { heading.clone() } else { f!("{} ({})", heading, number) };
+        // a written `## Notes (2)` may already hold the key
+        while lookup(&sections, &key).is_some() {
+            number += 1;
+            key = f!("{} ({})", heading, number);
+        }
+        sections.push((key, body[section.range.start..end].to_string()));
+    }
+    sections
+}
+
+/// Find the value of a key in ordered pairs.
+///
+/// @private
+fn lookup<'a, T>(entries: &'a [(String, T)], key: &str) -> Option<&'a T> {
+    entries.iter().find(|(other, _)| other == key).map(|(_, value)| value)
+}
+
+/// The keys of `primary` in order, with the keys only in `secondary` inserted after the key they follow in `secondary`.
+///
+/// @private
+fn merged_keys<T>(primary: &[(String, T)], secondary: &[(String, T)]) -> Vec<String> {
+    let mut keys: Vec<String> = primary.iter().map(|(key, _)| key.clone()).collect();
+    let mut previous: Option<usize> = None;
+    for (key, _) in secondary {
+        match keys.iter().position(|other| other == key) {
+            Some(index) => previous = Some(index),
+            None => {
+                let index = previous.map(|index| index + 1).unwrap_or(0);
+                keys.insert(index, key.clone());
+                previous = Some(index);
+            }
+        }
+    }
+    keys
+}
+
+#[cfg(test)]
+mod merge_tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_conflicts_keep_ours() {
+        let base = MDFile::from_string("---\na: 1\nb: 1\n---\n# X\nbase\n".to_string());
+        let ours = MDFile::from_string("---\na: 2\nb: 1\n---\n# X\nours\n".to_string());
+        let theirs = MDFile::from_string("---\na: 3\nb: 1\nc: 1\n---\n# X\ntheirs\n# Y\nnew\n".to_string());
+
+        let result = MDFile::merge(&base, &ours, &theirs);
+        assert_eq!(result.merged.to_string(), "---\na: 2\nb: 1\nc: 1\n---\n# X\nours\n# Y\nnew\n");
+        let locations: Vec<MergeLocation> = result.conflicts.into_iter().map(|conflict| conflict.location).collect();
+        assert_eq!(
+            locations,
+            vec![MergeLocation::Frontmatter("a".to_string()), MergeLocation::Section("# X".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_merge_deletions_and_repeated_headings() {
+        let base = MDFile::from_string("intro\n## Notes\none\n## Notes\ntwo\n## Old\nx".to_string());
+        let ours = MDFile::from_string("intro\n## Notes\none\n## Notes\nTWO\n## Old\nx".to_string());
+        let theirs = MDFile::from_string("intro\n## Notes\none\n## Notes\ntwo\n".to_string());
+
+        let result = MDFile::merge(&base, &ours, &theirs);
+        assert!(result.is_clean());
+        assert_eq!(result.merged.get_body(), "intro\n## Notes\none\n## Notes\nTWO\n");
+    }
+
+    #[test]
+    fn test_merge_headings_that_look_numbered() {
+        let base = MDFile::from_string("## Notes
+one
+## Notes
+two
+".to_string());
+        let ours = MDFile::from_string("## Notes (draft)
+idea
+## Notes
+one
+## Notes
+two
+".to_string());
+        let theirs = MDFile::from_string("## Notes
+one
+## Notes
+TWO
+".to_string());
+
+        let result = MDFile::merge(&base, &ours, &theirs);
+        assert!(result.is_clean());
+        assert_eq!(result.merged.get_body(), "## Notes (draft)
+idea
+## Notes
+one
+## Notes
+TWO
+");
+
+        let keys: Vec<String> = body_sections("## A (2)
+x
+## A
+y
+## A
+z
+").into_iter().map(|(key, _)| key).collect();
+        assert_eq!(keys, vec!["## A (2)", "## A", "## A (3)"]);
+    }
+}
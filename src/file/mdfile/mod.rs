@@ -14,6 +14,14 @@
 //!
 //! @public MDFile::get_yaml_key
 //!
+//! @public MDFile::get_yaml_date
+//!
+//! @public MDFile::get_yaml_datetime
+//!
+//! @public MDFile::set_yaml_date
+//!
+//! @public MDFile::set_yaml_datetime
+//!
 //! @public MDFile::set_body
 //!
 //! @public MDFile::get_body
@@ -29,6 +37,62 @@
 //! @public MDFile::update_embedding
 //!
 //! @public MDFile::get_embedding
+//!
+//! @public MDFile::set_embedding
+//!
+//! @public MDFile::content_hash
+//!
+//! @public MDFile::get_aliases
+//!
+//! @public MDFile::get_tags
+//!
+//! @public MDFile::get_links
+//!
+//! @public MDFile::get_sections
+//!
+//! @public MDFile::get_section
+//!
+//! @public MDFile::set_section
+//!
+//! @public MDFile::get_blocks
+//!
+//! @public MDFile::get_block
+//!
+//! @public MDFile::ensure_block_id
+//!
+//! @public MDFile::get_tasks
+//!
+//! @public MDFile::get_callouts
+//!
+//! @public MDFile::get_comments
+//!
+//! @public MDFile::strip_comments
+//!
+//! @public MDFile::insert_callout
+//!
+//! @public MDFile::word_count
+//!
+//! @public MDFile::reading_time
+//!
+//! @public MDFile::normalize
+//!
+//! @public MDFile::convert_unicode_math
+//!
+//! @public block
+//!
+//! @public callout
+//!
+//! @public format
+//!
+//! @public math
+//!
+//! @public link
+//!
+//! @public obsidian
+//!
+//! @public section
+//!
+//! @public task
 
 // std imports
 use std::path::PathBuf;
@@ -36,10 +100,33 @@ use std::path::PathBuf;
 // third-party imports
 use regex::Regex;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tracing::debug;
 
 // first-party imports
 use crate::prelude::*;
 
+// submodules
+pub mod block;
+pub mod callout;
+pub mod format;
+pub mod link;
+pub mod math;
+pub mod merge;
+pub mod obsidian;
+pub mod section;
+pub mod task;
+mod yaml;
+
+/// The reading speed assumed by `MDFile::reading_time`.
+const READING_WORDS_PER_MINUTE: f64 = 200.0;
+
+/// The date formats read from front matter, Obsidian's own `YYYY-MM-DD` first.
+const YAML_DATE_FORMATS: [&str; 3] = ["%Y-%m-%d", "%Y/%m/%d", "%Y.%m.%d"];
+
+/// The date and time formats read from front matter, Obsidian's own `YYYY-MM-DDTHH:mm` first. Timestamps with an offset are read as RFC 3339.
+const YAML_DATETIME_FORMATS: [&str; 4] = ["%Y-%m-%dT%H:%M", "%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%d %H:%M", "%Y-%m-%d %H:%M:%S%.f"];
+
 /// The `MDFile` struct represents a markdown file with optional YAML front matter.
 ///
 /// # Example
@@ -51,14 +138,27 @@ use crate::prelude::*;
 /// let file_string = "---\nkey: value\n---\n# Test\n\nThis is a test file.".to_string();
 /// let file = MDFile::from_string(file_string);
 /// ```
-#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct MDFile {
     yaml: Option<serde_yaml::Value>,
+    // front matter text as read, reused when writing the keys that were not edited
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    yaml_source: Option<String>,
     body: String,
     embedding: Option<Vec<f64>>,
+    // set when the contents change, cleared when the file is written
+    #[serde(skip)]
+    dirty: bool,
     // path: Option<PathBuf>
 }
 
+// equality is about the contents, not whether they have been saved
+impl PartialEq for MDFile {
+    fn eq(&self, other: &Self) -> bool {
+        self.yaml == other.yaml && self.body == other.body && self.embedding == other.embedding
+    }
+}
+
 impl MDFile {
     /// Creates a new `MDFile` struct with the given YAML front matter, body, and path.
     ///
@@ -88,7 +188,9 @@ impl MDFile {
     /// ```
     pub fn new(yaml: Option<serde_yaml::Value>, body: String) -> Self {
         Self {
+            dirty: false,
             yaml,
+            yaml_source: None,
             body,
             embedding: None,
         }
@@ -113,6 +215,7 @@ impl MDFile {
     pub fn set_yaml(&mut self, yaml: serde_yaml::Value) {
         if self.yaml.as_ref() != Some(&yaml) {
             self.embedding = None;
+            self.dirty = true;
         }
         self.yaml = Some(yaml);
     }
@@ -135,6 +238,7 @@ impl MDFile {
     /// ```
     pub fn add_yaml_key(&mut self, key: String, value: serde_yaml::Value) {
         self.embedding = None;
+        self.dirty = true;
         if let Some(yaml) = &mut self.yaml {
             if let serde_yaml::Value::Mapping(mapping) = yaml {
                 mapping.insert(serde_yaml::Value::String(key), value);
@@ -218,17 +322,130 @@ impl MDFile {
     /// assert_eq!(actual, expected);
     /// ```
     pub fn get_yaml_key(&self, key: &str) -> Option<&serde_yaml::Value> {
-        if let Some(yaml) = &self.yaml {
-            if let serde_yaml::Value::Mapping(mapping) = yaml {
-                mapping.get(&serde_yaml::Value::String(key.to_string()))
-            } else {
-                None
-            }
+        if let Some(serde_yaml::Value::Mapping(mapping)) = &self.yaml {
+            mapping.get(serde_yaml::Value::String(key.to_string()))
         } else {
             None
         }
     }
 
+    /// Gets a front matter key as a date.
+    ///
+    /// Reads `YYYY-MM-DD` as written by Obsidian, as well as `YYYY/MM/DD` and `YYYY.MM.DD`. A
+    /// date and time is read as its date.
+    ///
+    /// # Arguments
+    /// @param key: &str - The key of the key-value pair.
+    /// @returns Result<Option<chrono::NaiveDate>> - None if the key is missing or empty, an `InvalidDate` error if it is not a date.
+    ///
+    /// # Example
+    /// ```
+    /// use chrono::NaiveDate;
+    ///
+    /// use obsidian_driver::file::mdfile::MDFile;
+    ///
+    /// let file = MDFile::from_string("---\ndue: 2024-01-02\ntitle: Cells\n---\n# Test".to_string());
+    /// assert_eq!(file.get_yaml_date("due").unwrap(), NaiveDate::from_ymd_opt(2024, 1, 2));
+    /// assert_eq!(file.get_yaml_date("missing").unwrap(), None);
+    /// assert!(file.get_yaml_date("title").is_err());
+    /// ```
+    pub fn get_yaml_date(&self, key: &str) -> Result<Option<chrono::NaiveDate>> {
+        // an empty property, e.g. `due:`, is null
+        let Some(value) = self.get_yaml_key(key).filter(|value| !value.is_null()) else {
+            return Ok(None);
+        };
+        let text = yaml_date_text(key, value)?;
+        let date = YAML_DATE_FORMATS
+            .iter()
+            .find_map(|format| chrono::NaiveDate::parse_from_str(&text, format).ok());
+        match date {
+            Some(date) => Ok(Some(date)),
+            None => Ok(Some(parse_yaml_datetime(key, &text)?.date())),
+        }
+    }
+
+    /// Gets a front matter key as a date and time.
+    ///
+    /// Reads `YYYY-MM-DDTHH:mm` as written by Obsidian, with optional seconds, a space instead of
+    /// the `T`, or an RFC 3339 offset (the local time of the offset is kept). A date is read as
+    /// midnight of that day.
+    ///
+    /// # Arguments
+    /// @param key: &str - The key of the key-value pair.
+    /// @returns Result<Option<chrono::NaiveDateTime>> - None if the key is missing or empty, an `InvalidDate` error if it is not a date and time.
+    ///
+    /// # Example
+    /// ```
+    /// use chrono::NaiveDate;
+    ///
+    /// use obsidian_driver::file::mdfile::MDFile;
+    ///
+    /// let file = MDFile::from_string("---\ncreated: 2024-01-02T15:04\n---\n# Test".to_string());
+    /// let expected = NaiveDate::from_ymd_opt(2024, 1, 2).unwrap().and_hms_opt(15, 4, 0);
+    /// assert_eq!(file.get_yaml_datetime("created").unwrap(), expected);
+    /// ```
+    pub fn get_yaml_datetime(&self, key: &str) -> Result<Option<chrono::NaiveDateTime>> {
+        // an empty property, e.g. `due:`, is null
+        let Some(value) = self.get_yaml_key(key).filter(|value| !value.is_null()) else {
+            return Ok(None);
+        };
+        let text = yaml_date_text(key, value)?;
+        let date = YAML_DATE_FORMATS
+            .iter()
+            .find_map(|format| chrono::NaiveDate::parse_from_str(&text, format).ok());
+        match date {
+            Some(date) => Ok(Some(date.and_time(chrono::NaiveTime::MIN))),
+            None => Ok(Some(parse_yaml_datetime(key, &text)?)),
+        }
+    }
+
+    /// Sets a front matter key to a date, written as `YYYY-MM-DD` like Obsidian's date properties.
+    ///
+    /// # Arguments
+    /// @param key: &str - The key of the key-value pair.
+    /// @param date: chrono::NaiveDate - The date.
+    ///
+    /// # Example
+    /// ```
+    /// use chrono::NaiveDate;
+    ///
+    /// use obsidian_driver::file::mdfile::MDFile;
+    ///
+    /// let mut file = MDFile::new(None, "# Test".to_string());
+    /// file.set_yaml_date("due", NaiveDate::from_ymd_opt(2024, 1, 2).unwrap());
+    /// assert_eq!(file.to_string(), "---\ndue: 2024-01-02\n---\n# Test");
+    /// ```
+    pub fn set_yaml_date(&mut self, key: &str, date: chrono::NaiveDate) {
+        let value = date.format("%Y-%m-%d").to_string();
+        self.add_yaml_key(key.to_string(), serde_yaml::Value::String(value));
+    }
+
+    /// Sets a front matter key to a date and time, written as `YYYY-MM-DDTHH:mm` like Obsidian's date and time properties, with seconds only if they are not zero.
+    ///
+    /// # Arguments
+    /// @param key: &str - The key of the key-value pair.
+    /// @param datetime: chrono::NaiveDateTime - The date and time.
+    ///
+    /// # Example
+    /// ```
+    /// use chrono::NaiveDate;
+    ///
+    /// use obsidian_driver::file::mdfile::MDFile;
+    ///
+    /// let mut file = MDFile::new(None, "# Test".to_string());
+    /// let created = NaiveDate::from_ymd_opt(2024, 1, 2).unwrap().and_hms_opt(15, 4, 0).unwrap();
+    /// file.set_yaml_datetime("created", created);
+    /// assert_eq!(file.get_yaml_datetime("created").unwrap(), Some(created));
+    /// ```
+    pub fn set_yaml_datetime(&mut self, key: &str, datetime: chrono::NaiveDateTime) {
+        let format = match chrono::Timelike::second(&datetime) {
+            0 => "%Y-%m-%dT%H:%M",
+            _ => "%Y-%m-%dT%H:%M:%S",
+        };
+        let value = datetime.format(format).to_string();
+        self.add_yaml_key(key.to_string(), serde_yaml::Value::String(value));
+    }
+
     /// Sets the body of the markdown file.
     ///
     /// # Arguments
@@ -244,6 +461,9 @@ impl MDFile {
     /// assert_eq!(actual, expected);
     /// ```
     pub fn set_body(&mut self, body: String) {
+        if self.body != body {
+            self.dirty = true;
+        }
         self.embedding = None;
         self.body = body;
     }
@@ -282,40 +502,34 @@ impl MDFile {
     /// assert_eq!(actual, expected);
     /// ```
     pub fn from_string(contents: String) -> Self {
-        let yaml_pattern =
-            Regex::new(r"^(\-\-\-\n(?P<yaml>[\s\S]*?)\n?\-\-\-\n?)?(?P<body>[\s\S]*)").unwrap();
-        let captures = yaml_pattern.captures(&contents).unwrap();
-        let yaml = captures
-            .name("yaml")
-            .map(|m| serde_yaml::from_str(m.as_str()).unwrap());
-        let body = captures.name("body").unwrap().as_str().to_string();
-        Self::new(yaml, body)
+        Self::parse(&contents).unwrap()
     }
 
-    /// Converts the markdown file to a string.
+    /// Parses a markdown file from a string, failing instead of panicking on invalid front matter.
     ///
     /// # Arguments
-    /// @returns String - The markdown file as a string.
+    /// @param contents: &str - The contents of the markdown file.
+    /// @returns Result<MDFile> - Errors with `InvalidFrontmatter` if the YAML cannot be parsed.
     ///
     /// # Example
     /// ```
     /// use obsidian_driver::file::mdfile::MDFile;
     ///
-    /// let mut yaml = serde_yaml::from_str("key: value").unwrap();
-    /// let file = MDFile::new(Some(yaml), "# Test\n\nThis is a test file.".to_string());
-    ///
-    /// let actual = file.to_string();
-    /// let expected = "---\nkey: value\n---\n# Test\n\nThis is a test file.".to_string();
-    ///
-    /// assert_eq!(actual, expected);
+    /// assert!(MDFile::parse("---\nkey: [unclosed\n---\n# Test").is_err());
+    /// assert_eq!(MDFile::parse("# Test").unwrap().get_body(), "# Test");
     /// ```
-    pub fn to_string(&self) -> String {
-        if let Some(yaml) = &self.yaml {
-            let yaml_str = serde_yaml::to_string(yaml).unwrap();
-            format!("---\n{}---\n{}", yaml_str, self.body)
-        } else {
-            self.body.clone()
-        }
+    pub fn parse(contents: &str) -> Result<Self> {
+        let yaml_pattern =
+            Regex::new(r"^(\-\-\-\n(?P<yaml>[\s\S]*?)\n?\-\-\-\n?)?(?P<body>[\s\S]*)").unwrap();
+        let captures = yaml_pattern.captures(contents).unwrap();
+        let yaml = match captures.name("yaml") {
+            Some(m) => Some(serde_yaml::from_str(m.as_str()).map_err(|e| Error::InvalidFrontmatter(e.to_string()))?),
+            None => None,
+        };
+        let body = captures.name("body").unwrap().as_str().to_string();
+        let mut mdfile = Self::new(yaml, body);
+        mdfile.yaml_source = captures.name("yaml").map(|m| m.as_str().to_string());
+        Ok(mdfile)
     }
 
     /// Updates the embedding of the markdown file.
@@ -333,24 +547,18 @@ impl MDFile {
             return Ok(());
         }
         let embedding = driver.get_embedding(&self.to_string()).await;
-        match &embedding {
-            Err(e) => match e {
-                Error::InvalidEmbeddingResponse(string) => {
-                    let new_error = Error::InvalidEmbeddingResponse(format!(
-                        "{} for file: {:?}",
-                        string,
-                        path.to_string_lossy()
-                    ));
-                    return Err(new_error);
-                }
-                _ => {}
-            },
-            _ => {}
+        if let Err(Error::InvalidEmbeddingResponse(string)) = &embedding {
+            let new_error = Error::InvalidEmbeddingResponse(format!(
+                "{} for file: {:?}",
+                string,
+                path.to_string_lossy()
+            ));
+            return Err(new_error);
         }
 
-        self.embedding = Some(embedding.unwrap());
+        self.embedding = Some(embedding?);
 
-        println!("Updating embedding for file: {:?}", path.to_string_lossy());
+        debug!(path = %path.display(), "embedding updated");
         Ok(())
     }
 
@@ -362,6 +570,554 @@ impl MDFile {
     pub fn get_embedding(&self) -> Option<&Vec<f64>> {
         self.embedding.as_ref()
     }
+
+    /// Gets the aliases of the markdown file from the `aliases` (or `alias`) front matter key.
+    ///
+    /// # Arguments
+    /// @returns Vec<String> - The aliases, as a list even if the key holds a single string.
+    ///
+    /// # Example
+    /// ```
+    /// use obsidian_driver::file::mdfile::MDFile;
+    ///
+    /// let file = MDFile::from_string("---\naliases:\n  - K-map\n  - KV map\n---\nBody".to_string());
+    /// assert_eq!(file.get_aliases(), vec!["K-map".to_string(), "KV map".to_string()]);
+    /// ```
+    pub fn get_aliases(&self) -> Vec<String> {
+        let value = self
+            .get_yaml_key("aliases")
+            .or_else(|| self.get_yaml_key("alias"));
+        match value {
+            Some(serde_yaml::Value::String(alias)) => vec![alias.clone()],
+            Some(serde_yaml::Value::Sequence(aliases)) => aliases
+                .iter()
+                .filter_map(|alias| alias.as_str().map(str::to_string))
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Gets the tags of the markdown file, from the `tags` (or `tag`) front matter key and from `#tags` in the body.
+    ///
+    /// Tags are returned without the leading `#`, without duplicates, front matter tags first.
+    /// Tags inside code are ignored.
+    ///
+    /// # Arguments
+    /// @returns Vec<String> - The tags.
+    ///
+    /// # Example
+    /// ```
+    /// use obsidian_driver::file::mdfile::MDFile;
+    ///
+    /// let file = MDFile::from_string("---\ntags: [lecture]\n---\n# Title\n\nSome #cpsc/351 notes `#not-a-tag` %%#hidden%% (#exam). #lecture".to_string());
+    /// assert_eq!(file.get_tags(), vec!["lecture".to_string(), "cpsc/351".to_string(), "exam".to_string()]);
+    /// ```
+    pub fn get_tags(&self) -> Vec<String> {
+        let value = self.get_yaml_key("tags").or_else(|| self.get_yaml_key("tag"));
+        let mut tags: Vec<String> = match value {
+            Some(serde_yaml::Value::String(tags)) => tags
+                .split([',', ' '])
+                .map(|tag| tag.trim().trim_start_matches('#').to_string())
+                .filter(|tag| !tag.is_empty())
+                .collect(),
+            Some(serde_yaml::Value::Sequence(tags)) => tags
+                .iter()
+                .filter_map(|tag| tag.as_str())
+                .map(|tag| tag.trim().trim_start_matches('#').to_string())
+                .collect(),
+            _ => Vec::new(),
+        };
+
+        // a tag may follow whitespace or opening punctuation and ends at the first character
+        // that cannot be part of a tag, so "(#a, #b)." yields "a" and "b"
+        let tag_pattern = Regex::new(r#"(?:^|[\s(\[{"',;])#([\w/\-]*[^\W\d][\w/\-]*)"#).unwrap();
+        let mut skipped = obsidian::hidden_ranges(&self.body);
+        skipped.extend(self.get_links().into_iter().map(|link| link.range));
+        for captures in tag_pattern.captures_iter(&self.body) {
+            let tag = captures.get(1).unwrap();
+            if skipped.iter().any(|range| range.contains(&tag.start())) {
+                continue;
+            }
+            tags.push(tag.as_str().to_string());
+        }
+
+        let mut seen = std::collections::HashSet::new();
+        tags.retain(|tag| seen.insert(tag.clone()));
+        tags
+    }
+
+    /// Gets the internal links in the body of the markdown file.
+    ///
+    /// # Arguments
+    /// @returns Vec<link::Link> - The links, in order of appearance. Ranges are relative to the body.
+    ///
+    /// # Example
+    /// ```
+    /// use obsidian_driver::file::mdfile::MDFile;
+    ///
+    /// let file = MDFile::from_string("---\nkey: value\n---\nSee [[Other Note]].".to_string());
+    /// let links = file.get_links();
+    /// assert_eq!(links[0].target, "Other Note");
+    /// assert_eq!(links[0].range, 4..18);
+    /// ```
+    pub fn get_links(&self) -> Vec<link::Link> {
+        link::Link::parse_all(&self.body)
+    }
+
+    /// Gets the sections (headings and their content) of the markdown file.
+    ///
+    /// # Arguments
+    /// @returns Vec<section::Section> - The sections, in order of appearance. Ranges are relative to the body.
+    ///
+    /// # Example
+    /// ```
+    /// use obsidian_driver::file::mdfile::MDFile;
+    ///
+    /// let file = MDFile::from_string("# Title\n\n## Takeaways\n\n- One".to_string());
+    /// let headings: Vec<String> = file.get_sections().into_iter().map(|section| section.heading).collect();
+    /// assert_eq!(headings, vec!["Title".to_string(), "Takeaways".to_string()]);
+    /// ```
+    pub fn get_sections(&self) -> Vec<section::Section> {
+        section::Section::parse_all(&self.body)
+    }
+
+    /// Gets the first section with the given heading text.
+    ///
+    /// # Arguments
+    /// @param heading: &str - The heading text, without the leading `#`s.
+    /// @returns Option<section::Section> - The section, if found.
+    ///
+    /// # Example
+    /// ```
+    /// use obsidian_driver::file::mdfile::MDFile;
+    ///
+    /// let file = MDFile::from_string("# Title\n\n## Takeaways\n\n- One".to_string());
+    /// let section = file.get_section("Takeaways").unwrap();
+    /// assert_eq!(&file.get_body()[section.content_range], "\n- One");
+    /// ```
+    pub fn get_section(&self, heading: &str) -> Option<section::Section> {
+        self.get_sections()
+            .into_iter()
+            .find(|section| section.heading == heading)
+    }
+
+    /// Sets the content of the first section with the given heading text, or appends the section as `## heading` if there is none.
+    ///
+    /// The content replaces everything under the heading, including its subsections.
+    ///
+    /// # Arguments
+    /// @param heading: &str - The heading text, without the leading `#`s.
+    /// @param content: &str - The new content of the section.
+    ///
+    /// # Example
+    /// ```
+    /// use obsidian_driver::file::mdfile::MDFile;
+    ///
+    /// let mut file = MDFile::from_string("# Title\n\n## Summary\n\nOld\n\n## Notes\n\nText".to_string());
+    /// file.set_section("Summary", "New");
+    /// file.set_section("Takeaways", "- One");
+    /// assert_eq!(file.get_body(), "# Title\n\n## Summary\n\nNew\n\n## Notes\n\nText\n\n## Takeaways\n\n- One\n");
+    /// ```
+    pub fn set_section(&mut self, heading: &str, content: &str) {
+        let content = content.trim();
+        let body = match self.get_section(heading) {
+            Some(section) => {
+                let head = &self.body[..section.content_range.start];
+                let tail = &self.body[section.content_range.end..];
+                let newline = if head.ends_with('\n') { "" } else { "\n" };
+                let separator = if tail.is_empty() { "" } else { "\n" };
+                f!("{}{}\n{}\n{}{}", head, newline, content, separator, tail)
+            }
+            None => {
+                let head = self.body.trim_end();
+                let separator = if head.is_empty() { "" } else { "\n\n" };
+                f!("{}{}## {}\n\n{}\n", head, separator, heading, content)
+            }
+        };
+        self.set_body(body);
+    }
+
+    /// Gets the blocks (paragraphs, list items, headings, ...) of the markdown file, with their `^id` anchors.
+    ///
+    /// # Arguments
+    /// @returns Vec<block::Block> - The blocks, in order of appearance. Ranges are relative to the body.
+    ///
+    /// # Example
+    /// ```
+    /// use obsidian_driver::file::mdfile::MDFile;
+    ///
+    /// let file = MDFile::from_string("# Title\n\nCells divide. ^division".to_string());
+    /// let ids: Vec<Option<String>> = file.get_blocks().into_iter().map(|block| block.id).collect();
+    /// assert_eq!(ids, vec![None, Some("division".to_string())]);
+    /// ```
+    pub fn get_blocks(&self) -> Vec<block::Block> {
+        block::Block::parse_all(&self.body)
+    }
+
+    /// Gets the block with the given `^id`, as referenced by `[[Note#^id]]`.
+    ///
+    /// # Arguments
+    /// @param id: &str - The block id, without the `^`.
+    /// @returns Option<block::Block> - The block, if found.
+    ///
+    /// # Example
+    /// ```
+    /// use obsidian_driver::file::mdfile::MDFile;
+    ///
+    /// let file = MDFile::from_string("# Title\n\nCells divide. ^division".to_string());
+    /// let block = file.get_block("division").unwrap();
+    /// assert_eq!(&file.get_body()[block.range], "Cells divide.");
+    /// ```
+    pub fn get_block(&self, id: &str) -> Option<block::Block> {
+        self.get_blocks()
+            .into_iter()
+            .find(|block| block.id.as_deref() == Some(id))
+    }
+
+    /// Gets the id of the block containing a position of the body, adding an anchor to the block if it has none.
+    ///
+    /// New ids are derived from the text of the block, so the same paragraph gets the same id
+    /// every time it is anchored. Tables, quotes and code blocks get the anchor on a line of their
+    /// own, other blocks at the end of their last line.
+    ///
+    /// # Arguments
+    /// @param offset: usize - A byte offset into the body.
+    /// @returns Option<String> - The block id, None if the offset is not inside a block.
+    ///
+    /// # Example
+    /// ```
+    /// use obsidian_driver::file::mdfile::MDFile;
+    ///
+    /// let mut file = MDFile::from_string("# Title\n\nCells divide.".to_string());
+    /// let id = file.ensure_block_id(12).unwrap();
+    /// assert_eq!(file.get_body(), &format!("# Title\n\nCells divide. ^{}", id));
+    /// assert_eq!(file.ensure_block_id(12), Some(id));
+    /// ```
+    pub fn ensure_block_id(&mut self, offset: usize) -> Option<String> {
+        let blocks = self.get_blocks();
+        let block = blocks.iter().find(|block| {
+            block.range.contains(&offset) || block.anchor_range.as_ref().is_some_and(|range| range.contains(&offset))
+        })?;
+        if let Some(id) = &block.id {
+            return Some(id.clone());
+        }
+
+        let text = &self.body[block.range.clone()];
+        let digest: String = Sha256::digest(text.as_bytes()).iter().map(|byte| f!("{:02x}", byte)).collect();
+        let mut length = 6;
+        while blocks.iter().any(|other| other.id.as_deref() == Some(&digest[..length])) {
+            length += 1;
+        }
+        let id = digest[..length].to_string();
+        let last_line = text.lines().last().unwrap_or_default().trim_start();
+        let anchor = match ["|", ">", "```", "~~~"].iter().any(|marker| last_line.starts_with(marker)) {
+            true => f!("\n\n^{}", id),
+            false => f!(" ^{}", id),
+        };
+        let mut body = self.body.clone();
+        body.insert_str(block.range.end, &anchor);
+        self.set_body(body);
+        Some(id)
+    }
+
+    /// Gets the checklist items of the markdown file, with the headings they are under.
+    ///
+    /// # Arguments
+    /// @returns Vec<task::Task> - The tasks, in order of appearance. Ranges and lines are relative to the body.
+    ///
+    /// # Example
+    /// ```
+    /// use obsidian_driver::file::mdfile::MDFile;
+    ///
+    /// let mdfile = MDFile::from_string("---\ndue: friday\n---\n- [ ] Review notes".to_string());
+    /// assert_eq!(mdfile.get_tasks()[0].text, "Review notes");
+    /// ```
+    pub fn get_tasks(&self) -> Vec<task::Task> {
+        task::Task::parse_all(&self.body)
+    }
+
+    /// Gets the callout blocks of the markdown file.
+    ///
+    /// # Arguments
+    /// @returns Vec<callout::Callout> - The callouts, in order of appearance. Ranges are relative to the body.
+    ///
+    /// # Example
+    /// ```
+    /// use obsidian_driver::file::mdfile::MDFile;
+    ///
+    /// let mdfile = MDFile::from_string("> [!warning] Exam\n> Covers chapters 1-4".to_string());
+    /// assert_eq!(mdfile.get_callouts()[0].title, Some("Exam".to_string()));
+    /// ```
+    pub fn get_callouts(&self) -> Vec<callout::Callout> {
+        callout::Callout::parse_all(&self.body)
+    }
+
+    /// Gets the `%% comments %%` of the markdown file.
+    ///
+    /// # Arguments
+    /// @returns Vec<obsidian::Comment> - The comments, in order of appearance. Ranges are relative to the body.
+    ///
+    /// # Example
+    /// ```
+    /// use obsidian_driver::file::mdfile::MDFile;
+    ///
+    /// let mdfile = MDFile::from_string("# Cells\n%%\ntodo: add diagram\n%%".to_string());
+    /// assert_eq!(mdfile.get_comments()[0].text, "todo: add diagram");
+    /// ```
+    pub fn get_comments(&self) -> Vec<obsidian::Comment> {
+        obsidian::Comment::parse_all(&self.body)
+    }
+
+    /// Gets a copy of the markdown file with the comments removed from the body, e.g. to send it to an AI model.
+    ///
+    /// # Arguments
+    /// @returns MDFile
+    pub fn strip_comments(&self) -> MDFile {
+        let mut stripped = self.clone();
+        stripped.body = obsidian::strip_comments(&self.body);
+        stripped
+    }
+
+    /// Inserts a callout into the body as its own block, at the start of the line containing `at`.
+    ///
+    /// # Arguments
+    /// @param at: usize - A byte offset in the body; offsets past the end append the callout.
+    /// @param callout: &callout::Callout - The callout to insert.
+    ///
+    /// # Example
+    /// ```
+    /// use obsidian_driver::file::mdfile::MDFile;
+    /// use obsidian_driver::file::mdfile::callout::Callout;
+    ///
+    /// let mut mdfile = MDFile::from_string("# Lecture\nBody".to_string());
+    /// let at = mdfile.get_body().find("Body").unwrap();
+    /// mdfile.insert_callout(at, &Callout::new("summary", "Short version"));
+    /// assert_eq!(mdfile.get_body(), "# Lecture\n\n> [!summary]\n> Short version\n\nBody");
+    /// ```
+    pub fn insert_callout(&mut self, at: usize, callout: &callout::Callout) {
+        let body = &self.body;
+        let at = at.min(body.len());
+        let at = body.as_bytes()[..at]
+            .iter()
+            .rposition(|byte| *byte == b'\n')
+            .map(|i| i + 1)
+            .unwrap_or(0);
+        let (before, after) = body.split_at(at);
+        let lead = if before.is_empty() || before.ends_with("\n\n") {
+            ""
+        } else if before.ends_with('\n') {
+            "\n"
+        } else {
+            "\n\n"
+        };
+        let trail = if after.is_empty() || after.starts_with('\n') {
+            "\n"
+        } else {
+            "\n\n"
+        };
+        let body = f!("{}{}{}{}{}", before, lead, callout, trail, after);
+        self.set_body(body);
+    }
+
+    /// Counts the words a reader sees in the body of the markdown file.
+    ///
+    /// Front matter, code, `%% comments %%` and `^block` anchors are not counted, links count as their display
+    /// text, embeds are skipped, and tokens without a letter or digit (`-`, `#`, `|`) are not words.
+    ///
+    /// # Arguments
+    /// @returns usize - The number of words.
+    ///
+    /// # Example
+    /// ```
+    /// use obsidian_driver::file::mdfile::MDFile;
+    ///
+    /// let file = MDFile::from_string("---\ntags: [a, b]\n---\n# Cells\n\nSee [[Mitochondria|the powerhouse]] - `code here`.\n![[diagram.png]]".to_string());
+    /// assert_eq!(file.word_count(), 4);
+    /// ```
+    pub fn word_count(&self) -> usize {
+        let mut replacements: Vec<(std::ops::Range<usize>, String)> = obsidian::hidden_ranges(&self.body)
+            .into_iter()
+            .map(|range| (range, " ".to_string()))
+            .collect();
+        for link in self.get_links() {
+            let text = if link.embed { " ".to_string() } else { link.display_text() };
+            replacements.push((link.range, text));
+        }
+        for anchor in self.get_blocks().into_iter().filter_map(|block| block.anchor_range) {
+            replacements.push((anchor, " ".to_string()));
+        }
+        replacements.sort_by_key(|(range, _)| range.start);
+        let mut text = self.body.clone();
+        let mut end = usize::MAX;
+        for (range, replacement) in replacements.into_iter().rev() {
+            // a comment may lie inside a hidden range that was already replaced
+            if range.end > end {
+                continue;
+            }
+            end = range.start;
+            text.replace_range(range, &replacement);
+        }
+        text.split_whitespace()
+            .filter(|word| word.chars().any(char::is_alphanumeric))
+            .count()
+    }
+
+    /// Estimates how long the body of the markdown file takes to read, at 200 words per minute.
+    ///
+    /// # Arguments
+    /// @returns std::time::Duration - The reading time, see `MDFile::word_count`.
+    ///
+    /// # Example
+    /// ```
+    /// use std::time::Duration;
+    ///
+    /// use obsidian_driver::file::mdfile::MDFile;
+    ///
+    /// let file = MDFile::from_string("word ".repeat(300));
+    /// assert_eq!(file.reading_time(), Duration::from_secs(90));
+    /// ```
+    pub fn reading_time(&self) -> std::time::Duration {
+        std::time::Duration::from_secs_f64(self.word_count() as f64 * 60.0 / READING_WORDS_PER_MINUTE)
+    }
+
+    /// Sets the embedding of the markdown file, e.g. from an externally computed vector.
+    ///
+    /// # Arguments
+    /// @param embedding: Vec<f64> - The embedding of the markdown file.
+    ///
+    /// # Example
+    /// ```
+    /// use obsidian_driver::file::mdfile::MDFile;
+    ///
+    /// let mut file = MDFile::new(None, "# Test\n\nThis is a test file.".to_string());
+    /// file.set_embedding(vec![0.0, 1.0]);
+    /// assert_eq!(file.get_embedding(), Some(&vec![0.0, 1.0]));
+    /// ```
+    pub fn set_embedding(&mut self, embedding: Vec<f64>) {
+        self.embedding = Some(embedding);
+    }
+
+    /// Removes the embedding from the markdown file and returns it.
+    ///
+    /// # Arguments
+    /// @returns Option<Vec<f64>> - The embedding of the markdown file.
+    pub(crate) fn take_embedding(&mut self) -> Option<Vec<f64>> {
+        self.embedding.take()
+    }
+
+    /// Checks whether the contents have changed since the file was loaded or last written.
+    ///
+    /// # Arguments
+    /// @returns bool
+    ///
+    /// # Example
+    /// ```
+    /// use obsidian_driver::file::mdfile::MDFile;
+    ///
+    /// let mut file = MDFile::from_string("# Test".to_string());
+    /// assert!(!file.is_dirty());
+    /// file.set_body("# Edited".to_string());
+    /// assert!(file.is_dirty());
+    /// ```
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// Marks the contents as saved.
+    ///
+    /// @crate
+    pub(crate) fn mark_clean(&mut self) {
+        self.dirty = false;
+    }
+
+    /// Gets the SHA-256 hash of the serialized markdown file as a lowercase hex string.
+    ///
+    /// This is the same text that is sent to the embedding model, so it can be used to match
+    /// embeddings computed elsewhere against the notes they were computed from.
+    ///
+    /// # Arguments
+    /// @returns String - The content hash of the markdown file.
+    ///
+    /// # Example
+    /// ```
+    /// use obsidian_driver::file::mdfile::MDFile;
+    ///
+    /// let file = MDFile::new(None, "abc".to_string());
+    /// assert_eq!(file.content_hash(), "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+    /// ```
+    pub fn content_hash(&self) -> String {
+        let digest = Sha256::digest(self.to_string().as_bytes());
+        digest.iter().map(|byte| f!("{:02x}", byte)).collect()
+    }
+}
+
+/// The text of a front matter date value. Unquoted dates are strings to serde_yaml, anything else is not a date.
+///
+/// @private
+fn yaml_date_text(key: &str, value: &serde_yaml::Value) -> Result<String> {
+    match value {
+        serde_yaml::Value::String(text) => Ok(text.trim().to_string()),
+        other => Err(Error::InvalidDate(
+            key.to_string(),
+            f!("expected a date, found {}", serde_yaml::to_string(other).unwrap_or_default().trim()),
+        )),
+    }
+}
+
+/// Parse a front matter date and time in one of `YAML_DATETIME_FORMATS`, or RFC 3339.
+///
+/// @private
+fn parse_yaml_datetime(key: &str, text: &str) -> Result<chrono::NaiveDateTime> {
+    YAML_DATETIME_FORMATS
+        .iter()
+        .find_map(|format| chrono::NaiveDateTime::parse_from_str(text, format).ok())
+        .or_else(|| chrono::DateTime::parse_from_rfc3339(text).ok().map(|datetime| datetime.naive_local()))
+        .ok_or_else(|| {
+            Error::InvalidDate(
+                key.to_string(),
+                f!("{:?} is not in a known date format, e.g. 2024-01-02 or 2024-01-02T15:04", text),
+            )
+        })
+}
+
+/// Byte ranges of fenced code blocks and inline code in markdown text.
+///
+/// @crate
+pub(crate) fn code_ranges(text: &str) -> Vec<std::ops::Range<usize>> {
+    let code_pattern = Regex::new(r"(?ms)^\s*```.*?^\s*```|`[^`\n]*`").unwrap();
+    code_pattern.find_iter(text).map(|m| m.range()).collect()
+}
+
+/// Converts the markdown file to a string.
+///
+/// # Example
+/// ```
+/// use obsidian_driver::file::mdfile::MDFile;
+///
+/// let mut yaml = serde_yaml::from_str("key: value").unwrap();
+/// let file = MDFile::new(Some(yaml), "# Test\n\nThis is a test file.".to_string());
+///
+/// let actual = file.to_string();
+/// let expected = "---\nkey: value\n---\n# Test\n\nThis is a test file.".to_string();
+///
+/// assert_eq!(actual, expected);
+/// ```
+impl std::fmt::Display for MDFile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(yaml) = &self.yaml {
+            let preserved = self
+                .yaml_source
+                .as_deref()
+                .and_then(|source| yaml::to_string_preserving(source, yaml));
+            let yaml_str = match preserved {
+                Some(yaml_str) => yaml_str,
+                None => serde_yaml::to_string(yaml).map_err(|_| std::fmt::Error)?,
+            };
+            write!(f, "---\n{}---\n{}", yaml_str, self.body)
+        } else {
+            write!(f, "{}", self.body)
+        }
+    }
 }
 
 #[cfg(test)]
@@ -436,7 +1192,9 @@ mod mdfile_tests {
         let expected = MDFile {
             yaml: Some(serde_yaml::Value::Mapping(yaml_expected)),
             body: "# Test\n\nThis is a test file.".to_string(),
+            yaml_source: None,
             embedding: None,
+            dirty: false,
         };
         assert_eq!(actual, expected);
     }
@@ -451,7 +1209,9 @@ mod mdfile_tests {
         let mdfile = MDFile {
             yaml: Some(serde_yaml::Value::Mapping(yaml_expected)),
             body: "# Test\n\nThis is a test file.".to_string(),
+            yaml_source: None,
             embedding: None,
+            dirty: false,
         };
         let actual = mdfile.to_string();
         let expected = r#"---
@@ -472,7 +1232,9 @@ This is a test file."#
         let expected = MDFile {
             yaml: None,
             body: "# Test\n\nThis is a test file.".to_string(),
+            yaml_source: None,
             embedding: None,
+            dirty: false,
         };
         assert_eq!(actual, expected);
     }
@@ -482,7 +1244,9 @@ This is a test file."#
         let mdfile = MDFile {
             yaml: None,
             body: "# Test\n\nThis is a test file.".to_string(),
+            yaml_source: None,
             embedding: None,
+            dirty: false,
         };
         let actual = mdfile.to_string();
         let expected = r#"# Test
@@ -491,4 +1255,49 @@ This is a test file."#
             .to_string();
         assert_eq!(actual, expected);
     }
+
+    #[test]
+    fn test_to_string_preserves_untouched_yaml() {
+        let contents = "---
+# reviewed weekly
+title: 'Cell Biology'
+tags:
+  - bio
+  - \"lecture\"
+date: 2024-01-02
+---
+# Test".to_string();
+        let mut mdfile = MDFile::from_string(contents.clone());
+        assert_eq!(mdfile.to_string(), contents);
+
+        mdfile.add_yaml_key("date".to_string(), "2024-02-03".into());
+        mdfile.add_yaml_key("status".to_string(), "todo".into());
+        let expected = "---
+# reviewed weekly
+title: 'Cell Biology'
+tags:
+  - bio
+  - \"lecture\"
+date: 2024-02-03
+status: todo
+---
+# Test";
+        assert_eq!(mdfile.to_string(), expected);
+        assert_eq!(MDFile::from_string(mdfile.to_string()).get_yaml(), mdfile.get_yaml());
+    }
+
+    #[test]
+    fn test_get_yaml_date_formats() {
+        let contents = "---\na: 2024/01/02\nb: 2024-01-02 15:04:05\nc: 2024-01-02T15:04:00+02:00\nd: 3\ne: next week\nf:\ng: null\n---\n".to_string();
+        let mdfile = MDFile::from_string(contents);
+        let day = chrono::NaiveDate::from_ymd_opt(2024, 1, 2).unwrap();
+        assert_eq!(mdfile.get_yaml_date("a").unwrap(), Some(day));
+        assert_eq!(mdfile.get_yaml_date("b").unwrap(), Some(day));
+        assert_eq!(mdfile.get_yaml_datetime("b").unwrap(), day.and_hms_opt(15, 4, 5));
+        assert_eq!(mdfile.get_yaml_datetime("c").unwrap(), day.and_hms_opt(15, 4, 0));
+        assert!(matches!(mdfile.get_yaml_date("d"), Err(Error::InvalidDate(key, _)) if key == "d"));
+        assert!(matches!(mdfile.get_yaml_datetime("e"), Err(Error::InvalidDate(key, _)) if key == "e"));
+        assert_eq!(mdfile.get_yaml_date("f").unwrap(), None);
+        assert_eq!(mdfile.get_yaml_datetime("g").unwrap(), None);
+    }
 }
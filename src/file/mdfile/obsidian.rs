@@ -0,0 +1,257 @@
+//! obsidian-driver::file::mdfile::obsidian
+//!
+//! This module contains the Obsidian-flavored markdown layer: the syntax Obsidian adds on top of markdown (comments, highlights, embedded queries) and the rules the parsers share so their results match what the Obsidian app shows.
+//!
+//! @public comment_ranges
+//!
+//! @public highlight_ranges
+//!
+//! @public hidden_ranges
+//!
+//! @public visible_text
+//!
+//! @public strip_comments
+//!
+//! @public Comment
+//!
+//! @public Comment::parse_all
+//!
+//! @public EmbeddedQuery
+//!
+//! @public EmbeddedQuery::parse_all
+
+// std imports
+use std::ops::Range;
+
+// third-party imports
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+/// The code block languages Obsidian and its query plugins render as query results.
+const QUERY_LANGUAGES: [&str; 4] = ["query", "dataview", "dataviewjs", "tasks"];
+
+/// Byte ranges of `%% comments %%`, including the markers. Comments inside code are ignored and an unterminated comment runs to the end of the text, as in Obsidian.
+///
+/// # Arguments
+/// @param text: &str - The text to parse.
+/// @returns Vec<Range<usize>> - The comment ranges, in order.
+///
+/// # Example
+/// ```
+/// use obsidian_driver::file::mdfile::obsidian::comment_ranges;
+///
+/// let text = "Shown %%hidden%% shown `%%code%%`";
+/// let ranges = comment_ranges(text);
+/// assert_eq!(ranges.len(), 1);
+/// assert_eq!(&text[ranges[0].clone()], "%%hidden%%");
+/// ```
+/// @public
+pub fn comment_ranges(text: &str) -> Vec<Range<usize>> {
+    let code = super::code_ranges(text);
+    let mut ranges = Vec::new();
+    let mut search_from = 0;
+    while let Some(found) = text[search_from..].find("%%") {
+        let start = search_from + found;
+        if let Some(code_range) = code.iter().find(|range| range.contains(&start)) {
+            search_from = code_range.end;
+            continue;
+        }
+        let end = text[start + 2..]
+            .find("%%")
+            .map(|found| start + 2 + found + 2)
+            .unwrap_or(text.len());
+        ranges.push(start..end);
+        search_from = end;
+    }
+    ranges
+}
+
+/// Byte ranges of `==highlighted==` text, including the markers. Highlights in code or comments are ignored.
+///
+/// # Arguments
+/// @param text: &str - The text to parse.
+/// @returns Vec<Range<usize>> - The highlight ranges, in order.
+///
+/// # Example
+/// ```
+/// use obsidian_driver::file::mdfile::obsidian::highlight_ranges;
+///
+/// let text = "a ==key idea== b";
+/// assert_eq!(&text[highlight_ranges(text)[0].clone()], "==key idea==");
+/// ```
+/// @public
+pub fn highlight_ranges(text: &str) -> Vec<Range<usize>> {
+    let highlight_pattern = Regex::new(r"==[^=\s](?:[^=\n]*[^=\s])?==").unwrap();
+    let hidden = hidden_ranges(text);
+    highlight_pattern
+        .find_iter(text)
+        .map(|m| m.range())
+        .filter(|range| !hidden.iter().any(|other| other.contains(&range.start)))
+        .collect()
+}
+
+/// Byte ranges that Obsidian does not treat as note content: fenced code blocks (including embedded queries), inline code, and comments.
+///
+/// Links, tags, headings, tasks, and callouts starting inside these ranges are not parsed.
+///
+/// # Arguments
+/// @param text: &str - The text to parse.
+/// @returns Vec<Range<usize>> - The ranges, sorted by start.
+///
+/// @public
+pub fn hidden_ranges(text: &str) -> Vec<Range<usize>> {
+    let mut ranges = super::code_ranges(text);
+    ranges.extend(comment_ranges(text));
+    ranges.sort_by_key(|range| range.start);
+    ranges
+}
+
+/// The text a reader sees in Obsidian's reading view: comments are removed and highlight markers are dropped.
+///
+/// # Arguments
+/// @param text: &str - The text to convert.
+/// @returns String - The visible text.
+///
+/// # Example
+/// ```
+/// use obsidian_driver::file::mdfile::obsidian::visible_text;
+///
+/// assert_eq!(visible_text("A ==big== idea%% todo: expand %%."), "A big idea.");
+/// ```
+/// @public
+pub fn visible_text(text: &str) -> String {
+    let mut visible = text.to_string();
+    let mut removals: Vec<Range<usize>> = comment_ranges(text);
+    for range in highlight_ranges(text) {
+        removals.push(range.start..range.start + 2);
+        removals.push(range.end - 2..range.end);
+    }
+    removals.sort_by_key(|range| range.start);
+    for range in removals.into_iter().rev() {
+        visible.replace_range(range, "");
+    }
+    visible
+}
+
+/// Remove `%% comments %%`, e.g. before note text is exported or sent to an AI model. Comments inside code are kept.
+///
+/// # Arguments
+/// @param text: &str - The text to strip.
+/// @returns String - The text without comments.
+///
+/// # Example
+/// ```
+/// use obsidian_driver::file::mdfile::obsidian::strip_comments;
+///
+/// assert_eq!(strip_comments("Mitosis%% ask Dr. Lee %% has ==four== phases"), "Mitosis has ==four== phases");
+/// ```
+/// @public
+pub fn strip_comments(text: &str) -> String {
+    let mut stripped = text.to_string();
+    for range in comment_ranges(text).into_iter().rev() {
+        stripped.replace_range(range, "");
+    }
+    stripped
+}
+
+/// A `%% comment %%`, text Obsidian only shows in the editor.
+///
+/// # Example
+/// ```
+/// use obsidian_driver::file::mdfile::obsidian::Comment;
+///
+/// let comments = Comment::parse_all("Cells %% private: check with TA %%");
+/// assert_eq!(comments[0].text, "private: check with TA");
+/// ```
+/// @public
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Comment {
+    // the text between the markers, trimmed
+    pub text: String,
+    // byte range of the comment, including the markers, in the text it was parsed from
+    pub range: Range<usize>,
+}
+
+impl Comment {
+    /// Parses all comments in the given text, in order of appearance.
+    ///
+    /// # Arguments
+    /// @param text: &str - The text to parse.
+    /// @returns Vec<Comment> - The comments found in the text.
+    pub fn parse_all(text: &str) -> Vec<Comment> {
+        comment_ranges(text)
+            .into_iter()
+            .map(|range| {
+                let inner = &text[range.clone()][2..];
+                let inner = inner.strip_suffix("%%").unwrap_or(inner);
+                Comment {
+                    text: inner.trim().to_string(),
+                    range,
+                }
+            })
+            .collect()
+    }
+}
+
+/// An embedded query, a code block Obsidian (or a query plugin such as Dataview) renders as search results.
+///
+/// # Example
+/// ```
+/// use obsidian_driver::file::mdfile::obsidian::EmbeddedQuery;
+///
+/// let queries = EmbeddedQuery::parse_all("# Todo\n```dataview\nTASK FROM #lecture\n```\n");
+/// assert_eq!(queries[0].language, "dataview");
+/// assert_eq!(queries[0].source, "TASK FROM #lecture");
+/// ```
+/// @public
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EmbeddedQuery {
+    pub language: String,
+    pub source: String,
+    // byte range of the whole code block in the text it was parsed from
+    pub range: Range<usize>,
+}
+
+impl EmbeddedQuery {
+    /// Parses all embedded queries in the given text, in order of appearance.
+    ///
+    /// # Arguments
+    /// @param text: &str - The text to parse.
+    /// @returns Vec<EmbeddedQuery> - The queries found in the text.
+    pub fn parse_all(text: &str) -> Vec<EmbeddedQuery> {
+        let query_pattern = Regex::new(r"(?ms)^[ \t]*```[ \t]*(\w+)[ \t]*\n(.*?)\n?^[ \t]*```").unwrap();
+        let comments = comment_ranges(text);
+        query_pattern
+            .captures_iter(text)
+            .filter(|captures| QUERY_LANGUAGES.contains(&captures[1].to_lowercase().as_str()))
+            .map(|captures| {
+                let whole = captures.get(0).unwrap();
+                EmbeddedQuery {
+                    language: captures[1].to_lowercase(),
+                    source: captures[2].to_string(),
+                    range: whole.range(),
+                }
+            })
+            .filter(|query| !comments.iter().any(|range| range.contains(&query.range.start)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod obsidian_tests {
+    use super::*;
+
+    #[test]
+    fn test_comment_ranges_multiline_and_unterminated() {
+        let text = "a %%one\ntwo%% b %%open";
+        let actual: Vec<&str> = comment_ranges(text).into_iter().map(|range| &text[range]).collect();
+        assert_eq!(actual, vec!["%%one\ntwo%%", "%%open"]);
+    }
+
+    #[test]
+    fn test_highlights_skip_comments_and_code() {
+        let text = "==yes== `==no==` %%==no==%% a == b == c";
+        let actual: Vec<&str> = highlight_ranges(text).into_iter().map(|range| &text[range]).collect();
+        assert_eq!(actual, vec!["==yes=="]);
+    }
+}
@@ -0,0 +1,168 @@
+//! obsidian-driver::file::mdfile::section
+//!
+//! This module contains the Section struct, which represents a heading and the content under it in a markdown file.
+//!
+//! @public Section
+//!
+//! @public Section::parse_all
+//!
+//! @public Section::find_path
+
+// std imports
+use std::ops::Range;
+
+// third-party imports
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+/// The `Section` struct represents a heading and everything under it up to the next heading of the same or a higher level.
+///
+/// # Example
+/// ```
+/// use obsidian_driver::file::mdfile::section::Section;
+///
+/// let text = "# Title\n\nIntro\n\n## Takeaways\n\n- One\n";
+/// let sections = Section::parse_all(text);
+/// assert_eq!(sections.len(), 2);
+/// assert_eq!(sections[1].level, 2);
+/// assert_eq!(sections[1].heading, "Takeaways");
+/// assert_eq!(&text[sections[1].content_range.clone()], "\n- One\n");
+/// ```
+/// @public
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Section {
+    pub level: usize,
+    pub heading: String,
+    // byte range of the heading line and its content in the text it was parsed from
+    pub range: Range<usize>,
+    // byte range of the content after the heading line
+    pub content_range: Range<usize>,
+}
+
+impl Section {
+    /// Parses all sections in the given text, in order of appearance. Headings inside fenced code blocks or `%% comments %%` are ignored.
+    ///
+    /// # Arguments
+    /// @param text: &str - The text to parse.
+    /// @returns Vec<Section> - The sections found in the text. Nested sections overlap their parents.
+    pub fn parse_all(text: &str) -> Vec<Section> {
+        let heading_pattern = Regex::new(r"^(#{1,6})[ \t]+(.*?)[ \t]*$").unwrap();
+
+        // (level, heading, line start, line end)
+        let comments = super::obsidian::comment_ranges(text);
+        let mut headings: Vec<(usize, String, usize, usize)> = Vec::new();
+        let mut fence: Option<&str> = None;
+        let mut offset = 0;
+        for line in text.split_inclusive('\n') {
+            let start = offset;
+            offset += line.len();
+            let trimmed = line.trim_end_matches(['\n', '\r']);
+            let marker = trimmed.trim_start();
+            match fence {
+                Some(open) => {
+                    if marker.starts_with(open) {
+                        fence = None;
+                    }
+                    continue;
+                }
+                None if marker.starts_with("```") => {
+                    fence = Some("```");
+                    continue;
+                }
+                None if marker.starts_with("~~~") => {
+                    fence = Some("~~~");
+                    continue;
+                }
+                None => {}
+            }
+            if comments.iter().any(|range| range.contains(&start)) {
+                continue;
+            }
+            if let Some(captures) = heading_pattern.captures(trimmed) {
+                headings.push((captures[1].len(), captures[2].to_string(), start, offset));
+            }
+        }
+
+        let mut sections = Vec::new();
+        for (i, (level, heading, start, content_start)) in headings.iter().enumerate() {
+            let end = headings[i + 1..]
+                .iter()
+                .find(|(other_level, ..)| other_level <= level)
+                .map(|(.., other_start, _)| *other_start)
+                .unwrap_or(text.len());
+            sections.push(Section {
+                level: *level,
+                heading: heading.clone(),
+                range: *start..end,
+                content_range: *content_start..end,
+            });
+        }
+        sections
+    }
+
+    /// Finds the section a heading path points at, as in `[[Note#Part#Summary]]`: each heading is looked up inside the section of the heading before it.
+    ///
+    /// Headings are compared as Obsidian compares link headings, ignoring case, runs of
+    /// whitespace, and the characters links cannot contain (`#^[]|`).
+    ///
+    /// # Arguments
+    /// @param sections: &[Section] - The sections of a note, as returned by `parse_all`.
+    /// @param path: &[String] - The headings, outermost first.
+    /// @returns Option<&Section> - The section of the last heading, None if a heading is missing or the path is empty.
+    ///
+    /// # Example
+    /// ```
+    /// use obsidian_driver::file::mdfile::section::Section;
+    ///
+    /// let sections = Section::parse_all("# Intro\n## Summary\n# Part Two\n## Summary\n");
+    /// let path = vec!["part  two".to_string(), "Summary".to_string()];
+    /// let section = Section::find_path(&sections, &path).unwrap();
+    /// assert_eq!(section.range.start, 30);
+    /// ```
+    pub fn find_path<'a>(sections: &'a [Section], path: &[String]) -> Option<&'a Section> {
+        let mut found: Option<&Section> = None;
+        for heading in path {
+            let heading = normalize_heading(heading);
+            found = Some(sections.iter().find(|section| {
+                let inside = found.is_none_or(|parent| {
+                    section.range.start > parent.range.start && section.range.end <= parent.range.end
+                });
+                inside && normalize_heading(&section.heading) == heading
+            })?);
+        }
+        found
+    }
+}
+
+/// Normalize a heading for comparison with the heading of a link.
+///
+/// @private
+fn normalize_heading(heading: &str) -> String {
+    heading
+        .replace(['#', '^', '[', ']', '|'], " ")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+#[cfg(test)]
+mod section_tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_nested_sections_and_code_blocks() {
+        let text = "# A\n```\n# not a heading\n```\n## B\nb\n%%\n# hidden\n%%\n# C\nc";
+        let sections = Section::parse_all(text);
+        let actual: Vec<(usize, &str, &str)> = sections
+            .iter()
+            .map(|section| (section.level, section.heading.as_str(), &text[section.range.clone()]))
+            .collect();
+        let expected = vec![
+            (1, "A", "# A\n```\n# not a heading\n```\n## B\nb\n%%\n# hidden\n%%\n"),
+            (2, "B", "## B\nb\n%%\n# hidden\n%%\n"),
+            (1, "C", "# C\nc"),
+        ];
+        assert_eq!(actual, expected);
+    }
+}
@@ -0,0 +1,147 @@
+//! obsidian-driver::file::mdfile::task
+//!
+//! This module contains the Task struct, which represents a checklist item (`- [ ]` / `- [x]`) in a markdown file.
+//!
+//! @public Task
+//!
+//! @public Task::parse_all
+//!
+//! @public Task::is_completed
+//!
+//! @public TaskFilter
+
+// std imports
+use std::ops::Range;
+
+// third-party imports
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+// first-party imports
+use super::section::Section;
+
+/// Which tasks to return from a task query.
+///
+/// @public
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TaskFilter {
+    #[default]
+    All,
+    /// Tasks that are not checked off with `x`.
+    Open,
+    /// Tasks checked off with `x` or `X`.
+    Completed,
+}
+
+impl TaskFilter {
+    /// Check whether a task passes the filter.
+    ///
+    /// # Arguments
+    /// @param task: &Task
+    /// @returns bool
+    pub fn matches(&self, task: &Task) -> bool {
+        match self {
+            TaskFilter::All => true,
+            TaskFilter::Open => !task.is_completed(),
+            TaskFilter::Completed => task.is_completed(),
+        }
+    }
+}
+
+/// The `Task` struct represents a single checklist item.
+///
+/// # Example
+/// ```
+/// use obsidian_driver::file::mdfile::task::Task;
+///
+/// let text = "# Week 1\n\n## Homework\n\n- [ ] Read chapter 2\n- [x] Problem set 1\n";
+/// let tasks = Task::parse_all(text);
+/// assert_eq!(tasks.len(), 2);
+/// assert_eq!(tasks[0].text, "Read chapter 2");
+/// assert_eq!(tasks[0].headings, vec!["Week 1".to_string(), "Homework".to_string()]);
+/// assert!(tasks[1].is_completed());
+/// ```
+/// @public
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Task {
+    pub text: String,
+    // the character between the brackets, e.g. ' ', 'x', or a custom status like '/'
+    pub status: char,
+    // the enclosing headings, outermost first
+    pub headings: Vec<String>,
+    // zero-based line number in the text it was parsed from
+    pub line: usize,
+    // indentation of the list marker, in characters, for nested tasks
+    pub indent: usize,
+    // byte range of the whole line, without the line ending
+    pub range: Range<usize>,
+}
+
+impl Task {
+    /// Parses all checklist items in the given text, in order of appearance. Items inside code blocks or comments are ignored.
+    ///
+    /// # Arguments
+    /// @param text: &str - The text to parse.
+    /// @returns Vec<Task> - The tasks found in the text.
+    pub fn parse_all(text: &str) -> Vec<Task> {
+        let task_pattern = Regex::new(r"^([ \t]*)(?:[-*+]|\d+[.)])[ \t]+\[(.)\][ \t]+(.*?)[ \t]*$").unwrap();
+        let hidden = super::obsidian::hidden_ranges(text);
+        let sections = Section::parse_all(text);
+
+        let mut tasks = Vec::new();
+        let mut offset = 0;
+        for (line_number, line) in text.split_inclusive('\n').enumerate() {
+            let start = offset;
+            offset += line.len();
+            if hidden.iter().any(|range| range.contains(&start)) {
+                continue;
+            }
+            let trimmed = line.trim_end_matches(['\n', '\r']);
+            let Some(captures) = task_pattern.captures(trimmed) else {
+                continue;
+            };
+            let headings = sections
+                .iter()
+                .filter(|section| section.content_range.contains(&start))
+                .map(|section| section.heading.clone())
+                .collect();
+            tasks.push(Task {
+                text: captures[3].to_string(),
+                status: captures[2].chars().next().unwrap_or(' '),
+                headings,
+                line: line_number,
+                indent: captures[1].len(),
+                range: start..start + trimmed.len(),
+            });
+        }
+        tasks
+    }
+
+    /// Checks whether the task is checked off with `x` or `X`.
+    ///
+    /// # Arguments
+    /// @returns bool
+    pub fn is_completed(&self) -> bool {
+        matches!(self.status, 'x' | 'X')
+    }
+}
+
+#[cfg(test)]
+mod task_tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_tasks_skips_code_and_keeps_context() {
+        let text = "- [ ] top\n# A\n```\n- [ ] code\n```\n  1. [X] nested\n## B\n* [/] partial\n- [] not a task\n";
+        let actual: Vec<(&str, bool, Vec<String>, usize)> = Task::parse_all(text)
+            .iter()
+            .map(|task| (&text[task.range.clone()], task.is_completed(), task.headings.clone(), task.indent))
+            .collect();
+        let expected = vec![
+            ("- [ ] top", false, vec![], 0),
+            ("  1. [X] nested", true, vec!["A".to_string()], 2),
+            ("* [/] partial", false, vec!["A".to_string(), "B".to_string()], 0),
+        ];
+        assert_eq!(actual, expected);
+    }
+}
@@ -0,0 +1,112 @@
+//! obsidian-driver::file::mdfile::yaml
+//!
+//! This module contains the front matter serializer that keeps the original text of a note's YAML: untouched keys are written back exactly as they were read, in their original order, so saving a note only changes the keys that were edited.
+//!
+//! @super to_string_preserving
+
+// third-party imports
+use serde_yaml::{Mapping, Value};
+
+/// FrontmatterEntry struct
+///
+/// A top-level key of a front matter block and its original text, including indented continuation lines and comments up to the next key.
+///
+/// @private
+struct FrontmatterEntry<'a> {
+    key: Value,
+    value: Value,
+    text: &'a str,
+}
+
+/// Serialize front matter, reusing the original text for every key whose value did not change.
+///
+/// Changed keys are written in place, removed keys are dropped and new keys are appended in
+/// the order of the mapping. Returns None when the original text cannot be split into
+/// top-level keys, in which case the caller should serialize the whole value.
+///
+/// # Arguments
+/// @param source: &str - The original front matter, without the `---` markers
+/// @param yaml: &Value - The current front matter
+/// @return Option<String> - The front matter, ending with a newline
+///
+/// @super
+pub(super) fn to_string_preserving(source: &str, yaml: &Value) -> Option<String> {
+    let Value::Mapping(mapping) = yaml else {
+        return None;
+    };
+    let (preamble, entries) = split_entries(source)?;
+
+    let mut out = String::from(preamble);
+    let mut seen = Mapping::new();
+    for entry in entries.iter() {
+        seen.insert(entry.key.clone(), Value::Null);
+        match mapping.get(&entry.key) {
+            Some(value) if value == &entry.value => {
+                out.push_str(entry.text);
+                if !entry.text.ends_with('\n') {
+                    out.push('\n');
+                }
+            }
+            Some(value) => out.push_str(&single_entry(&entry.key, value)?),
+            None => {}
+        }
+    }
+    for (key, value) in mapping.iter() {
+        if !seen.contains_key(key) {
+            out.push_str(&single_entry(key, value)?);
+        }
+    }
+    // comments or odd layouts could change the meaning of the reused text
+    match serde_yaml::from_str::<Value>(&out) {
+        Ok(Value::Mapping(written)) if &written == mapping => Some(out),
+        Ok(Value::Null) if mapping.is_empty() => Some(out),
+        _ => None,
+    }
+}
+
+/// Split front matter into the text before the first key and its top-level entries.
+///
+/// A top-level key starts on an unindented line; indented lines, unindented list items and
+/// comments belong to the key above them.
+///
+/// @private
+fn split_entries(source: &str) -> Option<(&str, Vec<FrontmatterEntry<'_>>)> {
+    let mut starts = Vec::new();
+    let mut offset = 0;
+    for line in source.split_inclusive('\n') {
+        let starts_key = !line.starts_with(char::is_whitespace)
+            && !line.starts_with('#')
+            && !line.starts_with('-')
+            && !line.trim().is_empty();
+        if starts_key {
+            starts.push(offset);
+        }
+        offset += line.len();
+    }
+
+    let preamble_end = starts.first().copied().unwrap_or(source.len());
+    let mut entries = Vec::new();
+    for (i, start) in starts.iter().enumerate() {
+        let end = starts.get(i + 1).copied().unwrap_or(source.len());
+        let text = &source[*start..end];
+        let parsed: Mapping = serde_yaml::from_str(text).ok()?;
+        if parsed.len() != 1 {
+            return None;
+        }
+        let (key, value) = parsed.into_iter().next()?;
+        if entries.iter().any(|entry: &FrontmatterEntry| entry.key == key) {
+            return None;
+        }
+        entries.push(FrontmatterEntry { key, value, text });
+    }
+    Some((&source[..preamble_end], entries))
+}
+
+/// Serialize a single key-value pair as a line of front matter.
+///
+/// @private
+fn single_entry(key: &Value, value: &Value) -> Option<String> {
+    let mut mapping = Mapping::new();
+    mapping.insert(key.clone(), value.clone());
+    serde_yaml::to_string(&mapping).ok()
+}
@@ -13,20 +13,86 @@
 //! @public File::get_mdfile_mut
 //!
 //! @public File::from_mdfile
+//!
+//! @public File::get_other
+//!
+//! @public File::get_other_mut
+//!
+//! @public File::get_text
+//!
+//! @public File::get_embedding
+//!
+//! @public File::set_embedding
+//!
+//! @public File::content_hash
+//!
+//! @public File::is_dirty
+//!
+//! @public File::timestamps
+//!
+//! @public write_atomic
 
 // std imports
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
 
 // third-party imports
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 
 // first-party imports
 use crate::prelude::*;
 
 // submodules
+pub mod hooks;
 pub mod mdfile;
+pub mod naming;
+pub mod registry;
+pub mod template;
+pub mod timestamps;
 pub mod vault;
 
+/// Numbers the temporary files of `write_atomic`, so writes to the same path from several threads do not share one.
+static TEMP_FILE_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Write contents to a path atomically.
+///
+/// The contents are written and synced to a temporary file next to the target, which is then
+/// renamed over it, so a crash leaves either the old or the new contents, never a partial file.
+/// Concurrent writes to the same path each use their own temporary file; the last rename wins.
+///
+/// # Arguments
+/// @param path: impl AsRef<Path>
+/// @param contents: impl AsRef<[u8]>
+/// @returns Result<()>
+///
+/// # Example
+/// ```
+/// use obsidian_driver::file::write_atomic;
+///
+/// let dir = std::env::temp_dir();
+/// write_atomic(dir.join("obsidian_driver_write_atomic.md"), "# Note").unwrap();
+/// # std::fs::remove_file(dir.join("obsidian_driver_write_atomic.md")).unwrap();
+/// ```
+/// @public
+pub fn write_atomic(path: impl AsRef<Path>, contents: impl AsRef<[u8]>) -> Result<()> {
+    let path = path.as_ref();
+    let file_name = path
+        .file_name()
+        .ok_or(Error::Generic(f!("No file name for path: {}", path.display())))?;
+    let counter = TEMP_FILE_COUNTER.fetch_add(1, Ordering::Relaxed);
+    let temp_path = path.with_file_name(f!(".{}.{}.{}.tmp", file_name.to_string_lossy(), std::process::id(), counter));
+    let written = std::fs::File::create_new(&temp_path).and_then(|mut temp| {
+        std::io::Write::write_all(&mut temp, contents.as_ref())?;
+        temp.sync_all()
+    });
+    if let Err(e) = written.and_then(|_| std::fs::rename(&temp_path, path)) {
+        let _ = std::fs::remove_file(&temp_path);
+        return Err(e.into());
+    }
+    Ok(())
+}
+
 /// File struct
 ///
 /// This struct represents a file in the vault.
@@ -58,7 +124,7 @@ pub struct File {
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 enum FileContents {
     MDFile(mdfile::MDFile),
-    // 	Other(OtherFile)
+    Other(registry::OtherFile),
 }
 
 impl File {
@@ -88,10 +154,17 @@ impl File {
                     last_modified,
                 })
             }
-            _ => Err(Error::Generic(f!(
-                "Unsupported extension found for file: {}",
-                path.display()
-            ))),
+            _ => match registry::OtherFile::parse(ext, &contents)? {
+                Some(other) => Ok(Self {
+                    path,
+                    contents: FileContents::Other(other),
+                    last_modified,
+                }),
+                None => Err(Error::Generic(f!(
+                    "Unsupported extension found for file: {}",
+                    path.display()
+                ))),
+            },
         }
     }
 
@@ -102,6 +175,34 @@ impl File {
     /// @returns Result<Self>
     ///
     fn read_file(path: PathBuf) -> Result<Self> {
+        let (contents, last_modified) = Self::read_raw(&path)?;
+        Self::parse_raw(path, contents, Some(last_modified))
+    }
+
+    /// Read the contents and modification time of a file without parsing it. The read hooks are run on the contents, see `hooks`.
+    ///
+    /// # Arguments
+    /// @param path: &Path
+    /// @returns Result<(String, u128)> - The contents and the modification time in milliseconds since the epoch
+    ///
+    fn read_raw(path: &Path) -> Result<(String, u128)> {
+        let last_modified = std::fs::metadata(path)?
+            .modified()?
+            .duration_since(std::time::SystemTime::UNIX_EPOCH)?
+            .as_millis();
+        let contents = hooks::run_on_read(path, std::fs::read_to_string(path)?)?;
+        Ok((contents, last_modified))
+    }
+
+    /// Parse the contents of a file into a File struct, using the extension of its path
+    ///
+    /// # Arguments
+    /// @param path: PathBuf
+    /// @param contents: String
+    /// @param last_modified: Option<u128>
+    /// @returns Result<Self>
+    ///
+    fn parse_raw(path: PathBuf, contents: String, last_modified: Option<u128>) -> Result<Self> {
         let path_clone = path.clone();
 
         let ext = path_clone
@@ -115,12 +216,22 @@ impl File {
                 "Invalid extension for file: {}",
                 path.display()
             )))?;
-        let last_modified = std::fs::metadata(&path)?
-            .modified()?
-            .duration_since(std::time::SystemTime::UNIX_EPOCH)?
-            .as_millis();
-        let contents = std::fs::read_to_string(&path)?;
-        Self::new_raw(path, ext, contents, Some(last_modified))
+        Self::new_raw(path, ext, contents, last_modified)
+    }
+
+    /// Check whether a file can be loaded: a markdown note, or a file of a registered type, see `registry`. Other files, such as images and PDFs, are attachments and stay on disk only.
+    ///
+    /// # Arguments
+    /// @param path: &Path
+    /// @returns bool
+    ///
+    /// @crate
+    pub(crate) fn is_loadable(path: &Path) -> bool {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("md") => true,
+            Some(ext) => registry::get_file_type(ext).is_some(),
+            None => false,
+        }
     }
 
     /// Read a cached file
@@ -167,20 +278,88 @@ impl File {
 
     /// Write a file
     ///
-    /// This function writes a file to the filesystem.
+    /// This function writes a file to the filesystem, after running the write hooks on its contents (see `hooks`). The write is atomic, see `write_atomic`. Whether the file is dirty is not changed; a Vault marks its files clean when it writes them.
     ///
     /// # Arguments
     /// @returns Result<()> - Ok if successful, Err otherwise
     pub fn write(&self) -> Result<()> {
+        write_atomic(&self.path, self.contents_to_write()?)
+    }
+
+    /// Write a file, keep the output of the write hooks as its contents, and mark it clean
+    ///
+    /// # Arguments
+    /// @returns Result<()>
+    ///
+    /// @crate
+    pub(crate) fn save(&mut self) -> Result<()> {
+        let contents = self.contents_to_write()?;
+        write_atomic(&self.path, &contents)?;
+        self.keep_written(contents)?;
+        self.mark_clean();
+        Ok(())
+    }
+
+    /// Replace the contents with the text written to disk if the write hooks changed it, so the file in memory matches the file on disk. The embedding is kept.
+    ///
+    /// # Arguments
+    /// @param written: String - The contents after the write hooks
+    /// @returns Result<()>
+    ///
+    /// @crate
+    pub(crate) fn keep_written(&mut self, written: String) -> Result<()> {
+        if written == self.serialize_contents()? {
+            return Ok(());
+        }
+        let embedding = self.take_embedding();
+        let mut parsed = Self::parse_raw(self.path.clone(), written, self.last_modified)?;
+        if let Some(embedding) = embedding {
+            parsed.set_embedding(embedding);
+        }
+        self.contents = parsed.contents;
+        Ok(())
+    }
+
+    /// Check whether the contents have changed since the file was loaded or last written
+    ///
+    /// # Arguments
+    /// @returns bool
+    pub fn is_dirty(&self) -> bool {
         match &self.contents {
-            FileContents::MDFile(mdfile) => {
-                let contents = mdfile.to_string();
-                std::fs::write(&self.path, contents)?;
-                Ok(())
-            }
+            FileContents::MDFile(mdfile) => mdfile.is_dirty(),
+            FileContents::Other(other) => other.is_dirty(),
         }
     }
 
+    /// Mark the contents as saved
+    ///
+    /// @crate
+    pub(crate) fn mark_clean(&mut self) {
+        match &mut self.contents {
+            FileContents::MDFile(mdfile) => mdfile.mark_clean(),
+            FileContents::Other(other) => other.mark_clean(),
+        }
+    }
+
+    /// Get the contents of the file as text, before the write hooks
+    ///
+    /// # Arguments
+    /// @returns Result<String>
+    pub(crate) fn serialize_contents(&self) -> Result<String> {
+        match &self.contents {
+            FileContents::MDFile(mdfile) => Ok(mdfile.to_string()),
+            FileContents::Other(other) => other.serialize(),
+        }
+    }
+
+    /// Get the contents of the file as they are written to disk, after the write hooks. The hooks do not change the file in memory; see `keep_written`.
+    ///
+    /// # Arguments
+    /// @returns Result<String>
+    pub(crate) fn contents_to_write(&self) -> Result<String> {
+        hooks::run_before_write(&self.path, self.serialize_contents()?)
+    }
+
     /// Get the path of the file
     /// 
     /// # Arguments
@@ -196,6 +375,7 @@ impl File {
     pub fn get_mdfile(&self) -> Option<&mdfile::MDFile> {
         match &self.contents {
             FileContents::MDFile(mdfile) => Some(mdfile),
+            FileContents::Other(_) => None,
         }
     }
 
@@ -206,6 +386,103 @@ impl File {
     pub fn get_mdfile_mut(&mut self) -> Option<&mut mdfile::MDFile> {
         match &mut self.contents {
             FileContents::MDFile(mdfile) => Some(mdfile),
+            FileContents::Other(_) => None,
+        }
+    }
+
+    /// Get the OtherFile struct if the file is handled by a registered file type
+    ///
+    /// # Arguments
+    /// @returns Option<&registry::OtherFile> - Some if the file is not a markdown file, None otherwise
+    pub fn get_other(&self) -> Option<&registry::OtherFile> {
+        match &self.contents {
+            FileContents::MDFile(_) => None,
+            FileContents::Other(other) => Some(other),
+        }
+    }
+
+    /// Get the mutable OtherFile struct if the file is handled by a registered file type
+    ///
+    /// # Arguments
+    /// @returns Option<&mut registry::OtherFile> - Some if the file is not a markdown file, None otherwise
+    pub fn get_other_mut(&mut self) -> Option<&mut registry::OtherFile> {
+        match &mut self.contents {
+            FileContents::MDFile(_) => None,
+            FileContents::Other(other) => Some(other),
+        }
+    }
+
+    /// Get the text used to embed and search the file, whatever its type
+    ///
+    /// # Arguments
+    /// @returns String - The markdown for markdown files, the file type's text otherwise
+    pub fn get_text(&self) -> String {
+        match &self.contents {
+            FileContents::MDFile(mdfile) => mdfile.to_string(),
+            FileContents::Other(other) => other.text(),
+        }
+    }
+
+    /// Get the embedding of the file, whatever its type
+    ///
+    /// # Arguments
+    /// @returns Option<&Vec<f64>>
+    pub fn get_embedding(&self) -> Option<&Vec<f64>> {
+        match &self.contents {
+            FileContents::MDFile(mdfile) => mdfile.get_embedding(),
+            FileContents::Other(other) => other.get_embedding(),
+        }
+    }
+
+    /// Set the embedding of the file, whatever its type
+    ///
+    /// # Arguments
+    /// @param embedding: Vec<f64>
+    pub fn set_embedding(&mut self, embedding: Vec<f64>) {
+        match &mut self.contents {
+            FileContents::MDFile(mdfile) => mdfile.set_embedding(embedding),
+            FileContents::Other(other) => other.set_embedding(embedding),
+        }
+    }
+
+    /// Take the embedding out of the file, leaving None
+    ///
+    /// @crate
+    pub(crate) fn take_embedding(&mut self) -> Option<Vec<f64>> {
+        match &mut self.contents {
+            FileContents::MDFile(mdfile) => mdfile.take_embedding(),
+            FileContents::Other(other) => other.take_embedding(),
+        }
+    }
+
+    /// Get the SHA-256 hash of the file's text, used to match embeddings to unchanged files
+    ///
+    /// # Arguments
+    /// @returns String - The hex encoded hash, equal to `MDFile::content_hash` for markdown files
+    pub fn content_hash(&self) -> String {
+        match &self.contents {
+            FileContents::MDFile(mdfile) => mdfile.content_hash(),
+            FileContents::Other(other) => {
+                let digest = Sha256::digest(other.text().as_bytes());
+                digest.iter().map(|byte| f!("{:02x}", byte)).collect()
+            }
+        }
+    }
+
+    /// Update the embedding of the file, whatever its type, if it does not have one
+    ///
+    /// # Arguments
+    /// @param driver: &crate::ai::api::AIDriver
+    /// @returns Result<()>
+    pub async fn update_embedding(&mut self, driver: &crate::ai::api::AIDriver) -> Result<()> {
+        match &mut self.contents {
+            FileContents::MDFile(mdfile) => mdfile.update_embedding(driver, self.path.clone()).await,
+            FileContents::Other(other) => {
+                if other.get_embedding().is_none() {
+                    other.set_embedding(driver.get_embedding(&other.text()).await?);
+                }
+                Ok(())
+            }
         }
     }
 
@@ -256,4 +533,29 @@ mod file_tests {
         };
         assert_eq!(actual, expected);
     }
+
+    #[test]
+    fn test_write_atomic_replaces_contents() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("note.md");
+        std::fs::write(&path, "old").unwrap();
+        write_atomic(&path, "new").unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "new");
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_write_atomic_concurrent_writes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("note.md");
+        let contents: Vec<String> = (0..8).map(|i| f!("{}", i).repeat(10_000)).collect();
+        std::thread::scope(|scope| {
+            for text in contents.iter() {
+                let path = &path;
+                scope.spawn(move || write_atomic(path, text).unwrap());
+            }
+        });
+        assert!(contents.contains(&std::fs::read_to_string(&path).unwrap()));
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
 }
@@ -0,0 +1,461 @@
+//! obsidian-driver::file::naming
+//!
+//! This module contains file naming utilities: turning arbitrary titles, e.g. generated by the AI model, into file names that are valid on every file system and keep Obsidian links working.
+//!
+//! @public ReplacementPolicy
+//!
+//! @public FilenameSanitizer
+//!
+//! @public sanitize_filename
+//!
+//! @public NameCase
+//!
+//! @public NamingPolicy
+//!
+//! @public NamingPolicy::name
+//!
+//! @public NamingPolicy::name_at
+//!
+//! @public CollisionStrategy
+//!
+//! @public resolve_collision
+
+// std imports
+use std::path::{Path, PathBuf};
+
+// third-party imports
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+
+// first-party imports
+use crate::file::template::moment_to_strftime;
+use crate::prelude::*;
+
+/// Characters that are invalid in file names on Windows, macOS or Linux, or that break Obsidian links.
+const INVALID_CHARACTERS: &[char] = &['/', '\\', ':', '*', '?', '"', '<', '>', '|', '#', '^', '[', ']'];
+
+/// File names Windows reserves for devices, with or without an extension.
+const RESERVED_NAMES: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8", "COM9", "LPT1", "LPT2",
+    "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// The name used when nothing is left of a title.
+const UNTITLED: &str = "Untitled";
+
+/// Words Title Case leaves in lower case, unless they come first.
+const MINOR_WORDS: &[&str] = &[
+    "a", "an", "and", "as", "at", "but", "by", "for", "in", "nor", "of", "on", "or", "per", "the", "to", "vs", "via",
+];
+
+/// How a FilenameSanitizer replaces invalid characters.
+///
+/// @public
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ReplacementPolicy {
+    /// Drop invalid characters.
+    Remove,
+    /// Replace every invalid character with this text.
+    Replace(String),
+    /// Replace separators with a look-alike (`:` with ` -`, `/`, `\` and `|` with `-`, `"` with `'`) and drop the other invalid characters.
+    #[default]
+    Readable,
+}
+
+/// FilenameSanitizer struct
+///
+/// Turns a title into a file name, without extension. Invalid characters are replaced according
+/// to the policy, control characters become spaces, whitespace is collapsed, leading and trailing
+/// dots and spaces are trimmed, Windows device names get a trailing `_`, and the name is cut to
+/// `max_length` bytes. An empty result becomes `Untitled`.
+///
+/// # Example
+/// ```
+/// use obsidian_driver::file::naming::{FilenameSanitizer, ReplacementPolicy};
+///
+/// let sanitizer = FilenameSanitizer::default()
+/// 	.with_policy(ReplacementPolicy::Replace("_".to_string()))
+/// 	.with_max_length(12);
+/// assert_eq!(sanitizer.sanitize("Cells: Division / Mitosis"), "Cells_ Divis");
+/// ```
+///
+/// @public
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FilenameSanitizer {
+    pub policy: ReplacementPolicy,
+    // in bytes, without the extension
+    pub max_length: usize,
+}
+
+impl Default for FilenameSanitizer {
+    fn default() -> Self {
+        FilenameSanitizer {
+            policy: ReplacementPolicy::default(),
+            max_length: 200,
+        }
+    }
+}
+
+impl FilenameSanitizer {
+    /// Set how invalid characters are replaced.
+    ///
+    /// # Arguments
+    /// @param policy: ReplacementPolicy
+    /// @return FilenameSanitizer
+    pub fn with_policy(mut self, policy: ReplacementPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Set the maximum length of a name in bytes. Names are cut at a character boundary.
+    ///
+    /// # Arguments
+    /// @param max_length: usize
+    /// @return FilenameSanitizer
+    pub fn with_max_length(mut self, max_length: usize) -> Self {
+        self.max_length = max_length;
+        self
+    }
+
+    /// Turn a title into a valid file name.
+    ///
+    /// # Arguments
+    /// @param title: &str
+    /// @return String - The name, without extension
+    pub fn sanitize(&self, title: &str) -> String {
+        let mut replaced = String::with_capacity(title.len());
+        for character in title.chars() {
+            if INVALID_CHARACTERS.contains(&character) {
+                match &self.policy {
+                    ReplacementPolicy::Remove => {}
+                    ReplacementPolicy::Replace(replacement) => {
+                        replaced.extend(replacement.chars().filter(|c| !INVALID_CHARACTERS.contains(c) && !c.is_control()))
+                    }
+                    ReplacementPolicy::Readable => replaced.push_str(match character {
+                        ':' => " -",
+                        '/' | '\\' | '|' => "-",
+                        '"' => "'",
+                        _ => "",
+                    }),
+                }
+            } else if character.is_control() {
+                replaced.push(' ');
+            } else {
+                replaced.push(character);
+            }
+        }
+
+        let collapsed = replaced.split_whitespace().collect::<Vec<&str>>().join(" ");
+        let mut name = trim_name(&collapsed).to_string();
+        if name.len() > self.max_length {
+            let mut end = self.max_length;
+            while !name.is_char_boundary(end) {
+                end -= 1;
+            }
+            name = trim_name(&name[..end]).to_string();
+        }
+        let device = name.split('.').next().unwrap_or_default().to_uppercase();
+        if RESERVED_NAMES.contains(&device.as_str()) {
+            name.push('_');
+        }
+        if name.is_empty() {
+            name = UNTITLED.to_string();
+        }
+        name
+    }
+}
+
+/// Turn a title into a valid file name with the default sanitizer.
+///
+/// # Arguments
+/// @param title: &str
+/// @return String - The name, without extension
+///
+/// # Example
+/// ```
+/// use obsidian_driver::file::naming::sanitize_filename;
+///
+/// assert_eq!(sanitize_filename("\"Cells: Division/Mitosis\"..."), "'Cells - Division-Mitosis'");
+/// assert_eq!(sanitize_filename("# What is a [[Cell]]?"), "What is a Cell");
+/// assert_eq!(sanitize_filename("con"), "con_");
+/// ```
+///
+/// @public
+pub fn sanitize_filename(title: &str) -> String {
+    FilenameSanitizer::default().sanitize(title)
+}
+
+/// The case of the words of a file name.
+///
+/// @public
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NameCase {
+    /// Keep the title as it is.
+    #[default]
+    Keep,
+    /// `Mitosis and the Cell Cycle`: words start with a capital, except minor words after the first.
+    Title,
+    /// `mitosis-and-the-cell-cycle`: lower case letters and digits, joined by `-`.
+    Kebab,
+    /// `mitosis_and_the_cell_cycle`: lower case letters and digits, joined by `_`.
+    Snake,
+    /// `mitosis and the cell cycle`
+    Lower,
+}
+
+/// NamingPolicy struct
+///
+/// The file name conventions of a vault, applied to the names of notes the library creates or
+/// renames. The title is put in the case of the policy, prefixed with the date if a format is
+/// set, and sanitized, so `max_length` of the sanitizer includes the date.
+///
+/// # Example
+/// ```
+/// use chrono::NaiveDate;
+/// use obsidian_driver::file::naming::{NameCase, NamingPolicy};
+///
+/// let policy = NamingPolicy::default()
+/// 	.with_case(NameCase::Kebab)
+/// 	.with_date_prefix("YYYY-MM-DD")
+/// 	.with_max_length(22);
+/// let now = NaiveDate::from_ymd_opt(2024, 9, 3).unwrap().and_hms_opt(10, 0, 0).unwrap();
+/// assert_eq!(policy.name_at("Mitosis: The Cell Cycle", now), "2024-09-03-mitosis-the");
+/// ```
+///
+/// @public
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NamingPolicy {
+    pub case: NameCase,
+    // moment.js format of the date put before the title, e.g. `YYYY-MM-DD`
+    pub date_prefix: Option<String>,
+    pub sanitizer: FilenameSanitizer,
+}
+
+impl NamingPolicy {
+    /// Set the case of the words of a name.
+    ///
+    /// # Arguments
+    /// @param case: NameCase
+    /// @return NamingPolicy
+    pub fn with_case(mut self, case: NameCase) -> Self {
+        self.case = case;
+        self
+    }
+
+    /// Put the date before each name, separated by a space, or by `-` or `_` in kebab or snake case.
+    ///
+    /// # Arguments
+    /// @param format: &str - A moment.js format, e.g. `YYYY-MM-DD`
+    /// @return NamingPolicy
+    pub fn with_date_prefix(mut self, format: &str) -> Self {
+        self.date_prefix = Some(format.to_string());
+        self
+    }
+
+    /// Set the maximum length of a name in bytes, including the date prefix.
+    ///
+    /// # Arguments
+    /// @param max_length: usize
+    /// @return NamingPolicy
+    pub fn with_max_length(mut self, max_length: usize) -> Self {
+        self.sanitizer.max_length = max_length;
+        self
+    }
+
+    /// Set how invalid characters are replaced and names are cut.
+    ///
+    /// # Arguments
+    /// @param sanitizer: FilenameSanitizer
+    /// @return NamingPolicy
+    pub fn with_sanitizer(mut self, sanitizer: FilenameSanitizer) -> Self {
+        self.sanitizer = sanitizer;
+        self
+    }
+
+    /// Turn a title into a file name following the policy, dated with the current local time.
+    ///
+    /// # Arguments
+    /// @param title: &str
+    /// @return String - The name, without extension
+    pub fn name(&self, title: &str) -> String {
+        self.name_at(title, chrono::Local::now().naive_local())
+    }
+
+    /// Turn a title into a file name following the policy, dated with a given time.
+    ///
+    /// # Arguments
+    /// @param title: &str
+    /// @param now: NaiveDateTime - The time used for the date prefix
+    /// @return String - The name, without extension
+    pub fn name_at(&self, title: &str, now: NaiveDateTime) -> String {
+        let title = title.trim().trim_end_matches(".md");
+        let (words, separator) = match self.case {
+            NameCase::Keep => (title.to_string(), " "),
+            NameCase::Title => (title_case(title), " "),
+            NameCase::Lower => (title.to_lowercase(), " "),
+            NameCase::Kebab => (slug(title, "-"), "-"),
+            NameCase::Snake => (slug(title, "_"), "_"),
+        };
+        let name = match &self.date_prefix {
+            Some(format) => f!("{}{}{}", now.format(&moment_to_strftime(format)), separator, words),
+            None => words,
+        };
+        let name = self.sanitizer.sanitize(&name);
+        match self.case {
+            // cutting the name can leave a separator at its end
+            NameCase::Kebab | NameCase::Snake => name.trim_end_matches(separator).to_string(),
+            _ => name,
+        }
+    }
+}
+
+/// Capitalize the first letter of each word, except minor words after the first.
+///
+/// @private
+fn title_case(title: &str) -> String {
+    title
+        .split_whitespace()
+        .enumerate()
+        .map(|(i, word)| {
+            if i > 0 && MINOR_WORDS.contains(&word.to_lowercase().as_str()) {
+                return word.to_lowercase();
+            }
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+/// Lower case the letters and digits of a title, joining the runs between them with a separator.
+///
+/// @private
+fn slug(title: &str, separator: &str) -> String {
+    title
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect::<Vec<String>>()
+        .join(separator)
+}
+
+/// What to do when a new file would be written over an existing one.
+///
+/// @public
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CollisionStrategy {
+    /// Add ` 1`, ` 2`, ... to the name, as Obsidian does.
+    #[default]
+    NumericSuffix,
+    /// Add the current local date and time to the name, then a number if that is taken too.
+    Timestamp,
+    /// Fail with `Error::PathAlreadyExists`.
+    Error,
+    /// Keep the path and replace the existing file.
+    Overwrite,
+}
+
+/// Get a path for a new file that does not replace an existing one, according to a strategy.
+///
+/// # Arguments
+/// @param path: &Path - The path the file would be written to
+/// @param strategy: CollisionStrategy
+/// @param taken: impl Fn(&Path) -> bool - Whether a path is in use, e.g. `|path| path.exists()`
+/// @return Result<PathBuf> - The path itself if it is free
+///
+/// # Example
+/// ```
+/// use std::path::{Path, PathBuf};
+///
+/// use obsidian_driver::file::naming::{resolve_collision, CollisionStrategy};
+///
+/// let taken = |path: &Path| path == Path::new("Notes/Cells.md") || path == Path::new("Notes/Cells 1.md");
+/// let path = resolve_collision(Path::new("Notes/Cells.md"), CollisionStrategy::NumericSuffix, taken).unwrap();
+/// assert_eq!(path, PathBuf::from("Notes/Cells 2.md"));
+/// assert!(resolve_collision(Path::new("Notes/Cells.md"), CollisionStrategy::Error, taken).is_err());
+/// ```
+///
+/// @public
+pub fn resolve_collision(path: &Path, strategy: CollisionStrategy, taken: impl Fn(&Path) -> bool) -> Result<PathBuf> {
+    if !taken(path) {
+        return Ok(path.to_path_buf());
+    }
+    let stem = path.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default();
+    let extension = path.extension().map(|extension| f!(".{}", extension.to_string_lossy())).unwrap_or_default();
+    let stem = match strategy {
+        CollisionStrategy::Error => return Err(Error::PathAlreadyExists(path.to_path_buf())),
+        CollisionStrategy::Overwrite => return Ok(path.to_path_buf()),
+        CollisionStrategy::NumericSuffix => stem,
+        CollisionStrategy::Timestamp => {
+            let stamped = f!("{} {}", stem, chrono::Local::now().format("%Y-%m-%d %H%M%S"));
+            let candidate = path.with_file_name(f!("{}{}", stamped, extension));
+            if !taken(&candidate) {
+                return Ok(candidate);
+            }
+            stamped
+        }
+    };
+    let candidate = (1..)
+        .map(|i| path.with_file_name(f!("{} {}{}", stem, i, extension)))
+        .find(|candidate| !taken(candidate))
+        .expect("Ran out of file names");
+    Ok(candidate)
+}
+
+/// Trim the spaces and dots that file systems drop or hide at the ends of names.
+///
+/// @private
+fn trim_name(name: &str) -> &str {
+    name.trim_matches(|c: char| c == '.' || c.is_whitespace())
+}
+
+#[cfg(test)]
+mod naming_tests {
+    use super::*;
+
+    #[test]
+    fn test_sanitize_policies() {
+        let title = " Part 1: A/B | \"Quotes\"?\t";
+        assert_eq!(sanitize_filename(title), "Part 1 - A-B - 'Quotes'");
+        let remove = FilenameSanitizer::default().with_policy(ReplacementPolicy::Remove);
+        assert_eq!(remove.sanitize(title), "Part 1 AB Quotes");
+        // an invalid replacement is not let through
+        let replace = FilenameSanitizer::default().with_policy(ReplacementPolicy::Replace("/_".to_string()));
+        assert_eq!(replace.sanitize("a:b"), "a_b");
+        assert_eq!(sanitize_filename("..."), UNTITLED);
+        assert_eq!(FilenameSanitizer::default().with_max_length(5).sanitize("Zellé Teilung"), "Zell");
+    }
+
+    #[test]
+    fn test_naming_policy_cases() {
+        let title = "the structure OF DNA: a review";
+        assert_eq!(NamingPolicy::default().name(title), "the structure OF DNA - a review");
+        assert_eq!(NamingPolicy::default().with_case(NameCase::Title).name(title), "The Structure of DNA - a Review");
+        assert_eq!(NamingPolicy::default().with_case(NameCase::Snake).name(title), "the_structure_of_dna_a_review");
+        assert_eq!(NamingPolicy::default().with_case(NameCase::Lower).with_max_length(7).name(title), "the str");
+        assert_eq!(NamingPolicy::default().with_case(NameCase::Kebab).name("Élan: ?!"), "élan");
+    }
+
+    #[test]
+    fn test_resolve_collision_on_disk() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("Cells.md");
+        let exists = |path: &Path| path.exists();
+        assert_eq!(resolve_collision(&path, CollisionStrategy::Error, exists).unwrap(), path);
+
+        std::fs::write(&path, "# Cells").unwrap();
+        let numbered = resolve_collision(&path, CollisionStrategy::NumericSuffix, exists).unwrap();
+        assert_eq!(numbered, dir.path().join("Cells 1.md"));
+        let stamped = resolve_collision(&path, CollisionStrategy::Timestamp, exists).unwrap();
+        let name = stamped.file_name().unwrap().to_string_lossy().into_owned();
+        assert!(name.starts_with("Cells 20") && name.ends_with(".md"));
+        assert_eq!(resolve_collision(&path, CollisionStrategy::Overwrite, exists).unwrap(), path);
+        assert!(matches!(
+            resolve_collision(&path, CollisionStrategy::Error, exists),
+            Err(Error::PathAlreadyExists(_))
+        ));
+    }
+}
@@ -0,0 +1,276 @@
+//! obsidian-driver::file::registry
+//!
+//! This module contains the file-type registry, which lets downstream crates teach the library to read and write files other than markdown (e.g. `.canvas`, `.csv`, `.bib`).
+//!
+//! A registered type parses the raw text of a file into a `serde_json::Value`, so files of every type can be cached, searched, and written the same way.
+//!
+//! @public FileType
+//!
+//! @public register_file_type
+//!
+//! @public OtherFile
+
+// std imports
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock, RwLock};
+
+// third-party imports
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+// first-party imports
+use crate::prelude::*;
+
+/// A parser and serializer for files with the given extensions.
+///
+/// # Example
+/// ```
+/// use std::sync::Arc;
+///
+/// use obsidian_driver::error::Error;
+/// use obsidian_driver::file::registry::{register_file_type, FileType};
+/// use serde_json::Value;
+///
+/// struct Canvas;
+///
+/// impl FileType for Canvas {
+/// 	fn extensions(&self) -> Vec<String> {
+/// 		vec!["canvas".to_string()]
+/// 	}
+///
+/// 	fn parse(&self, contents: &str) -> Result<Value, Error> {
+/// 		Ok(serde_json::from_str(contents)?)
+/// 	}
+///
+/// 	fn serialize(&self, data: &Value) -> Result<String, Error> {
+/// 		Ok(serde_json::to_string_pretty(data)?)
+/// 	}
+/// }
+///
+/// register_file_type(Arc::new(Canvas));
+/// ```
+/// @public
+pub trait FileType: Send + Sync {
+    /// The extensions handled by this type, without the leading dot.
+    fn extensions(&self) -> Vec<String>;
+
+    /// Parse the raw text of a file.
+    fn parse(&self, contents: &str) -> Result<Value>;
+
+    /// Turn parsed data back into the raw text of a file.
+    fn serialize(&self, data: &Value) -> Result<String>;
+
+    /// The text used to embed and search the file. Defaults to the serialized file.
+    fn text(&self, data: &Value) -> String {
+        self.serialize(data).unwrap_or_else(|_| data.to_string())
+    }
+}
+
+/// The registered file types by extension.
+///
+/// @private
+fn registry() -> &'static RwLock<HashMap<String, Arc<dyn FileType>>> {
+    static REGISTRY: OnceLock<RwLock<HashMap<String, Arc<dyn FileType>>>> = OnceLock::new();
+    REGISTRY.get_or_init(Default::default)
+}
+
+/// Register a file type for all of its extensions, replacing any type registered for them before.
+///
+/// Markdown (`md`) is always handled by `MDFile` and cannot be replaced.
+///
+/// # Arguments
+/// @param file_type: Arc<dyn FileType>
+/// @public
+pub fn register_file_type(file_type: Arc<dyn FileType>) {
+    let mut registry = registry().write().expect("File type registry poisoned");
+    for extension in file_type.extensions() {
+        registry.insert(extension.to_lowercase(), file_type.clone());
+    }
+}
+
+/// Get the file type registered for an extension.
+///
+/// @crate
+pub(crate) fn get_file_type(extension: &str) -> Option<Arc<dyn FileType>> {
+    let registry = registry().read().expect("File type registry poisoned");
+    registry.get(&extension.to_lowercase()).cloned()
+}
+
+/// OtherFile struct
+///
+/// The parsed contents of a file handled by a registered FileType.
+///
+/// @public
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OtherFile {
+    extension: String,
+    data: Value,
+    embedding: Option<Vec<f64>>,
+    // set when the data changes, cleared when the file is written
+    #[serde(skip)]
+    dirty: bool,
+}
+
+// equality is about the contents, not whether they have been saved
+impl PartialEq for OtherFile {
+    fn eq(&self, other: &Self) -> bool {
+        self.extension == other.extension && self.data == other.data && self.embedding == other.embedding
+    }
+}
+
+impl OtherFile {
+    /// Parse a file with the type registered for its extension.
+    ///
+    /// # Arguments
+    /// @param extension: &str
+    /// @param contents: &str
+    /// @returns Result<Option<OtherFile>> - None if no type is registered for the extension
+    pub(crate) fn parse(extension: &str, contents: &str) -> Result<Option<Self>> {
+        let Some(file_type) = get_file_type(extension) else {
+            return Ok(None);
+        };
+        Ok(Some(Self {
+            extension: extension.to_lowercase(),
+            data: file_type.parse(contents)?,
+            embedding: None,
+            dirty: false,
+        }))
+    }
+
+    /// Get the extension of the file.
+    ///
+    /// # Arguments
+    /// @returns &str
+    pub fn get_extension(&self) -> &str {
+        &self.extension
+    }
+
+    /// Get the parsed data of the file.
+    ///
+    /// # Arguments
+    /// @returns &Value
+    pub fn get_data(&self) -> &Value {
+        &self.data
+    }
+
+    /// Replace the parsed data of the file. Clears the embedding.
+    ///
+    /// # Arguments
+    /// @param data: Value
+    pub fn set_data(&mut self, data: Value) {
+        if self.data != data {
+            self.dirty = true;
+        }
+        self.data = data;
+        self.embedding = None;
+    }
+
+    /// Turn the data back into the raw text of the file.
+    ///
+    /// # Arguments
+    /// @returns Result<String> - Errors if the file type is no longer registered
+    pub fn serialize(&self) -> Result<String> {
+        let file_type = get_file_type(&self.extension).ok_or(Error::Generic(f!(
+            "No file type registered for extension: {}",
+            self.extension
+        )))?;
+        file_type.serialize(&self.data)
+    }
+
+    /// Get the text used to embed and search the file.
+    ///
+    /// # Arguments
+    /// @returns String
+    pub fn text(&self) -> String {
+        match get_file_type(&self.extension) {
+            Some(file_type) => file_type.text(&self.data),
+            None => self.data.to_string(),
+        }
+    }
+
+    /// Get the embedding of the file.
+    ///
+    /// # Arguments
+    /// @returns Option<&Vec<f64>>
+    pub fn get_embedding(&self) -> Option<&Vec<f64>> {
+        self.embedding.as_ref()
+    }
+
+    /// Set the embedding of the file.
+    ///
+    /// # Arguments
+    /// @param embedding: Vec<f64>
+    pub fn set_embedding(&mut self, embedding: Vec<f64>) {
+        self.embedding = Some(embedding);
+    }
+
+    /// Take the embedding out of the file, leaving None.
+    ///
+    /// @crate
+    pub(crate) fn take_embedding(&mut self) -> Option<Vec<f64>> {
+        self.embedding.take()
+    }
+
+    /// Check whether the data has changed since the file was loaded or last written.
+    ///
+    /// # Arguments
+    /// @returns bool
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// Mark the data as saved.
+    ///
+    /// @crate
+    pub(crate) fn mark_clean(&mut self) {
+        self.dirty = false;
+    }
+}
+
+#[cfg(test)]
+mod registry_tests {
+    use super::*;
+    use crate::file::vault::Vault;
+
+    struct Lines;
+
+    impl FileType for Lines {
+        fn extensions(&self) -> Vec<String> {
+            vec!["lines".to_string()]
+        }
+
+        fn parse(&self, contents: &str) -> Result<Value> {
+            Ok(Value::from(contents.lines().collect::<Vec<&str>>()))
+        }
+
+        fn serialize(&self, data: &Value) -> Result<String> {
+            let lines: Vec<String> = serde_json::from_value(data.clone())?;
+            Ok(lines.join("\n"))
+        }
+    }
+
+    #[test]
+    fn test_registered_type_loads_caches_and_writes() {
+        register_file_type(Arc::new(Lines));
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.md"), "# A").unwrap();
+        std::fs::write(dir.path().join("list.lines"), "one\ntwo").unwrap();
+        let vault = Vault::from_path(dir.path().to_path_buf()).unwrap();
+
+        let path = std::path::PathBuf::from("list.lines");
+        let file = vault.get_file(&path).unwrap();
+        assert!(file.get_mdfile().is_none());
+        assert_eq!(file.get_other().unwrap().get_data(), &Value::from(vec!["one", "two"]));
+        assert_eq!(file.get_text(), "one\ntwo");
+
+        let cache_dir = tempfile::tempdir().unwrap();
+        let cache_path = cache_dir.path().join("cache.json");
+        vault.to_cache(&cache_path).unwrap();
+        let mut vault = Vault::from_cache(dir.path().to_path_buf(), &cache_path).unwrap();
+
+        let file = vault.get_file_mut(&path).unwrap();
+        file.get_other_mut().unwrap().set_data(Value::from(vec!["three"]));
+        file.write().unwrap();
+        assert_eq!(std::fs::read_to_string(dir.path().join("list.lines")).unwrap(), "three");
+    }
+}
@@ -0,0 +1,267 @@
+//! obsidian-driver::file::template
+//!
+//! This module contains the Template struct, a note whose `{{placeholders}}` are filled from a Context when a new note is created from it.
+//!
+//! @public Template
+//!
+//! @public Template::new
+//!
+//! @public Template::with_formats
+//!
+//! @public Template::placeholders
+//!
+//! @public Template::skeleton
+//!
+//! @public Template::instantiate
+//!
+//! @public Template::instantiate_at
+
+// third-party imports
+use chrono::NaiveDateTime;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+// first-party imports
+use crate::ai::prompt::{substitute_placeholders, Context};
+use crate::ai::skeleton::NoteSkeleton;
+use crate::file::mdfile::MDFile;
+use crate::prelude::*;
+
+/// Matches `{{key}}` and `{{key:format}}`.
+const PLACEHOLDER_PATTERN: &str = r"\{\{\s*(\w+)(?::([^}]*?))?\s*\}\}";
+
+/// Template struct
+///
+/// A note used as the starting point for new notes. Placeholders use the Obsidian template syntax:
+/// `{{title}}`, `{{date}}`, `{{time}}`, and `{{date:YYYY-MM-DD}}` / `{{time:HH:mm}}` with a
+/// moment.js style format are built in; any other `{{key}}` is taken from the Context, which can
+/// also override `title`, `date`, and `time`.
+///
+/// # Example
+/// ```
+/// use chrono::NaiveDate;
+/// use obsidian_driver::ai::prompt::Context;
+/// use obsidian_driver::file::mdfile::MDFile;
+/// use obsidian_driver::file::template::Template;
+///
+/// let mdfile = MDFile::from_string("---\ncreated: '{{date}}'\n---\n# {{title}}\n\nCourse: {{course}}\n".to_string());
+/// let template = Template::new("Lecture", mdfile);
+///
+/// let mut context = Context::default();
+/// context.insert("course", "CPSC 351");
+/// let now = NaiveDate::from_ymd_opt(2024, 9, 3).unwrap().and_hms_opt(10, 0, 0).unwrap();
+///
+/// let note = template.instantiate_at("Lecture 1", &context, now).unwrap();
+/// assert_eq!(note.get_body(), "# Lecture 1\n\nCourse: CPSC 351\n");
+/// assert_eq!(note.get_yaml_key("created").unwrap().as_str(), Some("2024-09-03"));
+/// ```
+/// @public
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Template {
+    name: String,
+    mdfile: MDFile,
+    // moment.js formats of `{{date}}` and `{{time}}` without a format
+    #[serde(default)]
+    date_format: Option<String>,
+    #[serde(default)]
+    time_format: Option<String>,
+}
+
+impl Template {
+    /// Create a template from a note.
+    ///
+    /// # Arguments
+    /// @param name: &str - The template name, usually the note's file name without extension
+    /// @param mdfile: MDFile - The template note
+    /// @returns Template
+    pub fn new(name: &str, mdfile: MDFile) -> Template {
+        Template {
+            name: name.to_string(),
+            mdfile,
+            date_format: None,
+            time_format: None,
+        }
+    }
+
+    /// Set the formats of `{{date}}` and `{{time}}` placeholders that do not give one. Defaults to `YYYY-MM-DD` and `HH:mm`.
+    ///
+    /// # Arguments
+    /// @param date_format: Option<&str> - A moment.js format, or None for the default
+    /// @param time_format: Option<&str> - A moment.js format, or None for the default
+    /// @returns Template
+    pub fn with_formats(mut self, date_format: Option<&str>, time_format: Option<&str>) -> Template {
+        self.date_format = date_format.map(str::to_string);
+        self.time_format = time_format.map(str::to_string);
+        self
+    }
+
+    /// Get the name of the template.
+    ///
+    /// # Arguments
+    /// @returns &str
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    /// Get the template note, with its placeholders.
+    ///
+    /// # Arguments
+    /// @returns &MDFile
+    pub fn get_mdfile(&self) -> &MDFile {
+        &self.mdfile
+    }
+
+    /// Get the keys of the placeholders in the template, in order of first appearance.
+    ///
+    /// # Arguments
+    /// @returns Vec<String>
+    ///
+    /// # Example
+    /// ```
+    /// use obsidian_driver::file::mdfile::MDFile;
+    /// use obsidian_driver::file::template::Template;
+    ///
+    /// let template = Template::new("Lecture", MDFile::from_string("# {{title}}\n{{summary}} {{date:YYYY}} {{title}}".to_string()));
+    /// assert_eq!(template.placeholders(), vec!["title", "summary", "date"]);
+    /// ```
+    pub fn placeholders(&self) -> Vec<String> {
+        let pattern = Regex::new(PLACEHOLDER_PATTERN).unwrap();
+        let text = self.mdfile.to_string();
+        let mut keys: Vec<String> = Vec::new();
+        for captures in pattern.captures_iter(&text) {
+            if !keys.iter().any(|key| key == &captures[1]) {
+                keys.push(captures[1].to_string());
+            }
+        }
+        keys
+    }
+
+    /// Get the headings of the template as a NoteSkeleton, so generated notes can be made to follow it.
+    ///
+    /// # Arguments
+    /// @returns NoteSkeleton
+    pub fn skeleton(&self) -> NoteSkeleton {
+        NoteSkeleton::from_mdfile(&self.mdfile)
+    }
+
+    /// Create a new note from the template, using the current local time for dates.
+    ///
+    /// # Arguments
+    /// @param title: &str - The title of the new note
+    /// @param context: &Context - Values for the placeholders
+    /// @returns Result<MDFile> - Errors with `InvalidContextKey` if a placeholder has no value, and with `InvalidFrontmatter` if the filled front matter is not valid YAML
+    pub fn instantiate(&self, title: &str, context: &Context) -> Result<MDFile> {
+        self.instantiate_at(title, context, chrono::Local::now().naive_local())
+    }
+
+    /// Create a new note from the template at a given time.
+    ///
+    /// # Arguments
+    /// @param title: &str - The title of the new note
+    /// @param context: &Context - Values for the placeholders
+    /// @param now: NaiveDateTime - The time used for `{{date}}` and `{{time}}`
+    /// @returns Result<MDFile> - Errors with `InvalidContextKey` if a placeholder has no value, and with `InvalidFrontmatter` if the filled front matter is not valid YAML
+    pub fn instantiate_at(&self, title: &str, context: &Context, now: NaiveDateTime) -> Result<MDFile> {
+        let pattern = Regex::new(PLACEHOLDER_PATTERN).unwrap();
+        let text = substitute_placeholders(&self.mdfile.to_string(), &pattern, |captures| {
+            let key = &captures[1];
+            let format = captures.get(2).map(|format| format.as_str());
+            if format.is_none() {
+                if let Some(value) = context.get(key) {
+                    return Ok(value.clone());
+                }
+            }
+            match key {
+                "title" => Ok(title.to_string()),
+                "date" => {
+                    let format = format.or(self.date_format.as_deref()).unwrap_or("YYYY-MM-DD");
+                    Ok(now.format(&moment_to_strftime(format)).to_string())
+                }
+                "time" => {
+                    let format = format.or(self.time_format.as_deref()).unwrap_or("HH:mm");
+                    Ok(now.format(&moment_to_strftime(format)).to_string())
+                }
+                _ => Err(Error::InvalidContextKey(f!("Key not found in context: {}", key))),
+            }
+        })?;
+        MDFile::parse(&text)
+    }
+}
+
+/// Convert a moment.js date format, as used by Obsidian, to a chrono format string.
+///
+/// @crate
+pub(crate) fn moment_to_strftime(format: &str) -> String {
+    const TOKENS: [(&str, &str); 18] = [
+        ("YYYY", "%Y"),
+        ("YY", "%y"),
+        ("MMMM", "%B"),
+        ("MMM", "%b"),
+        ("MM", "%m"),
+        ("M", "%-m"),
+        ("dddd", "%A"),
+        ("ddd", "%a"),
+        ("DD", "%d"),
+        ("D", "%-d"),
+        ("HH", "%H"),
+        ("H", "%-H"),
+        ("hh", "%I"),
+        ("h", "%-I"),
+        ("mm", "%M"),
+        ("ss", "%S"),
+        ("A", "%p"),
+        ("a", "%P"),
+    ];
+    let mut converted = String::new();
+    let mut rest = format;
+    'outer: while let Some(c) = rest.chars().next() {
+        // [text] is escaped literal text
+        if c == '[' {
+            if let Some(end) = rest.find(']') {
+                converted.push_str(&rest[1..end].replace('%', "%%"));
+                rest = &rest[end + 1..];
+                continue;
+            }
+        }
+        for (token, replacement) in TOKENS {
+            if let Some(after) = rest.strip_prefix(token) {
+                converted.push_str(replacement);
+                rest = after;
+                continue 'outer;
+            }
+        }
+        if c == '%' {
+            converted.push_str("%%");
+        } else {
+            converted.push(c);
+        }
+        rest = &rest[c.len_utf8()..];
+    }
+    converted
+}
+
+#[cfg(test)]
+mod template_tests {
+    use super::*;
+
+    #[test]
+    fn test_moment_to_strftime() {
+        assert_eq!(moment_to_strftime("YYYY-MM-DD [week] dddd HH:mm"), "%Y-%m-%d week %A %H:%M");
+    }
+
+    #[test]
+    fn test_instantiate_missing_key() {
+        let template = Template::new("T", MDFile::from_string("{{missing}}".to_string()));
+        let result = template.instantiate("Title", &Context::default());
+        assert!(matches!(result, Err(Error::InvalidContextKey(_))));
+    }
+
+    #[test]
+    fn test_instantiate_invalid_frontmatter() {
+        // the front matter of the template is only parsed once filled in
+        let template = Template::new("T", MDFile::new(None, "---\ntitle: {{title}}\n---\n# {{title}}".to_string()));
+        let result = template.instantiate("Cells: Structure", &Context::default());
+        assert!(matches!(result, Err(Error::InvalidFrontmatter(_))));
+        assert!(template.instantiate("Cells", &Context::default()).is_ok());
+    }
+}
@@ -0,0 +1,189 @@
+//! obsidian-driver::file::timestamps
+//!
+//! This module contains the created and modified times of files: those of the file system, and those written in the `created` and `modified` front matter keys, which survive syncing and copying. A TimestampPolicy decides the keys, and which of the two is kept in sync from the other.
+//!
+//! @public Timestamps
+//!
+//! @public TimestampSource
+//!
+//! @public TimestampPolicy
+//!
+//! @public File::timestamps
+
+// std imports
+use std::time::SystemTime;
+
+// third-party imports
+use chrono::{DateTime, Local, NaiveDateTime, SubsecRound, TimeZone};
+use serde::{Deserialize, Serialize};
+
+// first-party imports
+use super::File;
+use crate::prelude::*;
+
+/// Timestamps struct
+///
+/// The created and modified times of a file, in local time. The file system times are read
+/// when asked for, so they include changes made outside the library.
+///
+/// @public
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Timestamps {
+    // None if the file system does not record it
+    pub fs_created: Option<NaiveDateTime>,
+    pub fs_modified: Option<NaiveDateTime>,
+    // from the front matter keys of the policy; always None for other files than notes
+    pub created: Option<NaiveDateTime>,
+    pub modified: Option<NaiveDateTime>,
+}
+
+/// Which times are kept in sync from the other, see `Vault::reconcile_timestamps`.
+///
+/// @public
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TimestampSource {
+    /// The front matter keys are set to the file system times.
+    Filesystem,
+    /// The file system modified time is set to the front matter. The created time of a file
+    /// cannot be set on every platform, so it is left as it is.
+    Frontmatter,
+}
+
+/// TimestampPolicy struct
+///
+/// How the Vault reads and keeps the created and modified times of notes.
+///
+/// # Example
+/// ```
+/// use obsidian_driver::file::timestamps::{TimestampPolicy, TimestampSource};
+///
+/// let policy = TimestampPolicy::default()
+/// 	.with_keys("date created", "date modified")
+/// 	.with_sync_from(Some(TimestampSource::Filesystem))
+/// 	.with_stamp_on_write(true);
+/// ```
+///
+/// @public
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimestampPolicy {
+    pub created_key: String,
+    pub modified_key: String,
+    // which times `Vault::reconcile_timestamps` copies to the other; None leaves both
+    pub sync_from: Option<TimestampSource>,
+    // set the modified key to the current time whenever the Vault writes a note
+    pub stamp_on_write: bool,
+}
+
+impl Default for TimestampPolicy {
+    fn default() -> Self {
+        TimestampPolicy {
+            created_key: "created".to_string(),
+            modified_key: "modified".to_string(),
+            sync_from: None,
+            stamp_on_write: false,
+        }
+    }
+}
+
+impl TimestampPolicy {
+    /// Set the front matter keys of the created and modified times.
+    ///
+    /// # Arguments
+    /// @param created_key: &str
+    /// @param modified_key: &str
+    /// @return TimestampPolicy
+    pub fn with_keys(mut self, created_key: &str, modified_key: &str) -> Self {
+        self.created_key = created_key.to_string();
+        self.modified_key = modified_key.to_string();
+        self
+    }
+
+    /// Set which times are kept in sync from the other.
+    ///
+    /// # Arguments
+    /// @param sync_from: Option<TimestampSource>
+    /// @return TimestampPolicy
+    pub fn with_sync_from(mut self, sync_from: Option<TimestampSource>) -> Self {
+        self.sync_from = sync_from;
+        self
+    }
+
+    /// Set whether the modified key is set to the current time whenever a note is written.
+    ///
+    /// # Arguments
+    /// @param stamp_on_write: bool
+    /// @return TimestampPolicy
+    pub fn with_stamp_on_write(mut self, stamp_on_write: bool) -> Self {
+        self.stamp_on_write = stamp_on_write;
+        self
+    }
+}
+
+impl File {
+    /// Get the created and modified times of the file, from the file system and the front matter keys of a policy.
+    ///
+    /// # Arguments
+    /// @param policy: &TimestampPolicy
+    /// @return Result<Timestamps> - Errors if the file cannot be read, or with `InvalidDate` if a key is not a date
+    ///
+    /// # Example
+    /// ```should_panic
+    /// use std::path::PathBuf;
+    ///
+    /// use obsidian_driver::file::File;
+    /// use obsidian_driver::file::timestamps::TimestampPolicy;
+    ///
+    /// let file = File::read(PathBuf::from("note.md")).unwrap();
+    /// let timestamps = file.timestamps(&TimestampPolicy::default()).unwrap();
+    /// let modified = timestamps.modified.or(timestamps.fs_modified);
+    /// ```
+    pub fn timestamps(&self, policy: &TimestampPolicy) -> Result<Timestamps> {
+        let metadata = std::fs::metadata(&self.path)?;
+        let (created, modified) = match self.get_mdfile() {
+            Some(mdfile) => (mdfile.get_yaml_datetime(&policy.created_key)?, mdfile.get_yaml_datetime(&policy.modified_key)?),
+            None => (None, None),
+        };
+        Ok(Timestamps {
+            fs_created: metadata.created().ok().map(to_local),
+            fs_modified: Some(to_local(metadata.modified()?)),
+            created,
+            modified,
+        })
+    }
+}
+
+/// Convert a file system time to local time, to the second like front matter times.
+///
+/// @crate
+pub(crate) fn to_local(time: SystemTime) -> NaiveDateTime {
+    DateTime::<Local>::from(time).naive_local().trunc_subsecs(0)
+}
+
+/// Convert a local time to a file system time. Times skipped by a daylight saving change are None.
+///
+/// @crate
+pub(crate) fn to_system_time(datetime: NaiveDateTime) -> Option<SystemTime> {
+    Local.from_local_datetime(&datetime).earliest().map(SystemTime::from)
+}
+
+#[cfg(test)]
+mod timestamps_tests {
+    use super::*;
+
+    #[test]
+    fn test_timestamps_read_filesystem_and_frontmatter() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("note.md");
+        std::fs::write(&path, "---\ncreated: 2024-01-02T15:04\nedited: 2024-01-03\n---\n# Note").unwrap();
+        let file = File::read(path.clone()).unwrap();
+
+        let policy = TimestampPolicy::default().with_keys("created", "edited");
+        let timestamps = file.timestamps(&policy).unwrap();
+        let created = chrono::NaiveDate::from_ymd_opt(2024, 1, 2).unwrap().and_hms_opt(15, 4, 0);
+        assert_eq!(timestamps.created, created);
+        assert_eq!(timestamps.modified, chrono::NaiveDate::from_ymd_opt(2024, 1, 3).unwrap().and_hms_opt(0, 0, 0));
+        let fs_modified = to_local(std::fs::metadata(&path).unwrap().modified().unwrap());
+        assert_eq!(timestamps.fs_modified, Some(fs_modified));
+        assert_eq!(to_system_time(fs_modified).map(to_local), Some(fs_modified));
+    }
+}
@@ -0,0 +1,230 @@
+//! obsidian-driver::file::vault::anki
+//!
+//! This module contains the Anki export of a vault's cards, which records the exported cards in the front matter of their notes so exporting again only writes new cards, and the saving of cards to the `cards` front matter of a note or a linked cards note.
+//!
+//! @public CardsTarget
+//!
+//! @public Vault::export_anki
+//!
+//! @public Vault::save_cards
+
+// std imports
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+// third-party imports
+use serde::{Deserialize, Serialize};
+use serde_yaml::Value;
+
+// first-party imports
+use super::Vault;
+use crate::export::anki::{self, Card, ANKI_CARDS_KEY};
+use crate::file::mdfile::MDFile;
+use crate::prelude::*;
+
+/// Where `Vault::save_cards` writes the cards of a note.
+///
+/// @public
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CardsTarget {
+    /// The `cards` front matter of the note.
+    Frontmatter,
+    /// The `cards` front matter of a `<note> - Cards` note next to it, which links the note.
+    Note,
+}
+
+impl Vault {
+    /// Write the cards that were not exported before to an Anki import file.
+    ///
+    /// A card is skipped if its id is listed under `anki-cards` in the front matter of its source
+    /// note, or if it repeats an earlier card of the same note. The ids of the written cards are
+    /// added to that list and the notes are saved, so the next export only contains new cards.
+    ///
+    /// # Arguments
+    /// @param cards: &[Card] - e.g. from `anki::extract_cards` or `anki::generate_cards`
+    /// @param out: &Path - The TSV file to write, see `anki::to_tsv`
+    /// @return Result<Vec<Card>> - The cards written
+    ///
+    /// # Example
+    /// ```should_panic
+    /// use std::path::{Path, PathBuf};
+    ///
+    /// use obsidian_driver::export::anki::extract_cards;
+    /// use obsidian_driver::file::vault::Vault;
+    ///
+    /// let mut vault = Vault::from_path(PathBuf::from("vault")).unwrap();
+    /// let path = PathBuf::from("Lectures/Cells.md");
+    /// let mdfile = vault.get_file(&path).and_then(|file| file.get_mdfile()).unwrap();
+    /// let cards = extract_cards(&path, mdfile);
+    /// let exported = vault.export_anki(&cards, Path::new("cards.tsv")).unwrap();
+    /// ```
+    pub fn export_anki(&mut self, cards: &[Card], out: &Path) -> Result<Vec<Card>> {
+        let mut exported_ids: HashMap<PathBuf, HashSet<String>> = HashMap::new();
+        let mut new_cards: Vec<Card> = Vec::new();
+        for card in cards {
+            let mdfile = self
+                .files
+                .get(&card.source)
+                .and_then(|file| file.get_mdfile())
+                .ok_or(Error::PathNotFound(card.source.clone()))?;
+            let ids = exported_ids
+                .entry(card.source.clone())
+                .or_insert_with(|| exported_card_ids(mdfile.get_yaml_key(ANKI_CARDS_KEY)));
+            if ids.insert(card.id()) {
+                new_cards.push(card.clone());
+            }
+        }
+
+        if let Some(parent) = out.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(out, anki::to_tsv(&new_cards))?;
+
+        let mut sources: Vec<&PathBuf> = new_cards.iter().map(|card| &card.source).collect();
+        sources.sort();
+        sources.dedup();
+        for source in sources {
+            let mdfile = self
+                .files
+                .get_mut(source)
+                .and_then(|file| file.get_mdfile_mut())
+                .expect("File not found in vault");
+            let mut ids = match mdfile.get_yaml_key(ANKI_CARDS_KEY) {
+                Some(Value::Sequence(ids)) => ids.clone(),
+                _ => Vec::new(),
+            };
+            for card in new_cards.iter().filter(|card| &card.source == source) {
+                ids.push(Value::String(card.id()));
+            }
+            mdfile.add_yaml_key(ANKI_CARDS_KEY.to_string(), Value::Sequence(ids));
+            self.write_file(source)?;
+        }
+        Ok(new_cards)
+    }
+
+    /// Save the cards of a note to its `cards` front matter or to a linked cards note, replacing the cards saved there before.
+    ///
+    /// A cards note is created with a link to the note; if it exists, only its cards are replaced.
+    /// `anki::extract_cards` reads the saved cards back.
+    ///
+    /// # Arguments
+    /// @param path: &PathBuf - The note the cards are for
+    /// @param cards: &[Card] - e.g. from `anki::generate_term_cards`
+    /// @param target: CardsTarget
+    /// @return Result<PathBuf> - The note the cards were saved to; errors with `PathNotFound` if the path is not a note
+    ///
+    /// # Example
+    /// ```should_panic
+    /// use std::path::PathBuf;
+    ///
+    /// use obsidian_driver::export::anki::Card;
+    /// use obsidian_driver::file::vault::Vault;
+    /// use obsidian_driver::file::vault::anki::CardsTarget;
+    ///
+    /// let mut vault = Vault::from_path(PathBuf::from("vault")).unwrap();
+    /// let path = PathBuf::from("Cells.md");
+    /// let cards = vec![Card::new("ATP", "The energy carrier of the cell.", path.clone())];
+    /// let saved = vault.save_cards(&path, &cards, CardsTarget::Note).unwrap();
+    /// assert_eq!(saved, PathBuf::from("Cells - Cards.md"));
+    /// ```
+    pub fn save_cards(&mut self, path: &PathBuf, cards: &[Card], target: CardsTarget) -> Result<PathBuf> {
+        if self.files.get(path).and_then(|file| file.get_mdfile()).is_none() {
+            return Err(Error::PathNotFound(path.clone()));
+        }
+        let saved = match target {
+            CardsTarget::Frontmatter => path.clone(),
+            CardsTarget::Note => {
+                let stem = path.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default();
+                path.with_file_name(f!("{} - Cards.md", stem))
+            }
+        };
+        match self.files.get_mut(&saved).and_then(|file| file.get_mdfile_mut()) {
+            Some(mdfile) => {
+                anki::write_frontmatter_cards(mdfile, cards);
+                self.write_file(&saved)?;
+            }
+            None => {
+                let mut mdfile = MDFile::new(None, f!("Cards for {}\n", self.new_link(&saved, path, None)));
+                anki::write_frontmatter_cards(&mut mdfile, cards);
+                self.put_note(&saved, &mdfile.to_string())?;
+            }
+        }
+        Ok(saved)
+    }
+}
+
+/// The card ids listed in a note's `anki-cards` front matter.
+///
+/// @private
+fn exported_card_ids(value: Option<&Value>) -> HashSet<String> {
+    match value {
+        Some(Value::Sequence(ids)) => ids.iter().filter_map(|id| id.as_str().map(str::to_string)).collect(),
+        _ => HashSet::new(),
+    }
+}
+
+#[cfg(test)]
+mod anki_tests {
+    use super::*;
+    use crate::export::anki::extract_cards;
+
+    #[test]
+    fn test_export_anki_skips_exported_cards() {
+        let dir = tempfile::tempdir().unwrap();
+        let out = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("Strings.md"),
+            "## Takeaways\n- **Alphabet**: Finite set of symbols.\n- **String**: Finite sequence of symbols.\n",
+        )
+        .unwrap();
+        let mut vault = Vault::from_path(dir.path().to_path_buf()).unwrap();
+        let path = PathBuf::from("Strings.md");
+        let cards = extract_cards(&path, vault.get_file(&path).unwrap().get_mdfile().unwrap());
+        assert_eq!(cards.len(), 2);
+
+        let tsv_path = out.path().join("cards.tsv");
+        let exported = vault.export_anki(&cards, &tsv_path).unwrap();
+        assert_eq!(exported, cards);
+        let tsv = std::fs::read_to_string(&tsv_path).unwrap();
+        assert!(tsv.ends_with("String\tFinite sequence of symbols.\tobsidian::Strings\n"));
+        let on_disk = std::fs::read_to_string(dir.path().join("Strings.md")).unwrap();
+        assert!(on_disk.starts_with(&f!("---\nanki-cards:\n- {}\n- {}\n---\n", cards[0].id(), cards[1].id())));
+
+        let mut more = cards.clone();
+        more.push(Card::new("Language", "A set of strings.", path.clone()));
+        let exported = vault.export_anki(&more, &tsv_path).unwrap();
+        assert_eq!(exported, vec![more[2].clone()]);
+    }
+
+    #[tokio::test]
+    async fn test_save_term_cards() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("Cells.md"), "# Cells\n\nMitochondria make ATP.").unwrap();
+        let mut vault = Vault::from_path(dir.path().to_path_buf()).unwrap();
+        let path = PathBuf::from("Cells.md");
+        let response = r#"{"cards": [{"term": "ATP", "definition": "The energy carrier of the cell."}, {"term": "", "definition": "Empty"}]}"#;
+        let driver = crate::ai::api::AIDriver::new_mock(crate::ai::api::mock::MockDriver::new().with_default_response(response));
+        let mdfile = vault.get_file(&path).unwrap().get_mdfile().unwrap();
+        let cards = anki::generate_term_cards(&driver, &path, mdfile).await.unwrap();
+        assert_eq!(cards, vec![Card::new("ATP", "The energy carrier of the cell.", path.clone())]);
+
+        assert_eq!(vault.save_cards(&path, &cards, CardsTarget::Frontmatter).unwrap(), path);
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("Cells.md")).unwrap(),
+            "---\ncards:\n- front: ATP\n  back: The energy carrier of the cell.\n---\n# Cells\n\nMitochondria make ATP."
+        );
+        let mdfile = vault.get_file(&path).unwrap().get_mdfile().unwrap();
+        assert_eq!(anki::extract_cards(&path, mdfile), cards);
+
+        let note = vault.save_cards(&path, &cards, CardsTarget::Note).unwrap();
+        assert_eq!(note, PathBuf::from("Cells - Cards.md"));
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join(&note)).unwrap(),
+            "---\ncards:\n- front: ATP\n  back: The energy carrier of the cell.\n---\nCards for [[Cells]]\n"
+        );
+        vault.save_cards(&path, &[], CardsTarget::Note).unwrap();
+        assert!(std::fs::read_to_string(dir.path().join(&note)).unwrap().starts_with("---\ncards: []\n---\nCards for [[Cells]]"));
+        assert!(matches!(vault.save_cards(&PathBuf::from("Missing.md"), &cards, CardsTarget::Note), Err(Error::PathNotFound(_))));
+    }
+}
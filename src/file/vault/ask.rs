@@ -0,0 +1,132 @@
+//! obsidian-driver::file::vault::ask
+//!
+//! This module contains question answering over the vault: the notes nearest to a question are retrieved by embedding and the AI model answers from them, citing the notes it used.
+//!
+//! @public Answer
+//!
+//! @public Vault::ask
+//!
+//! @public Vault::ask_with
+
+// std imports
+use std::path::PathBuf;
+
+// third-party imports
+use serde::{Deserialize, Serialize};
+
+// first-party imports
+use super::Vault;
+use crate::ai::prompt::{Context, Prompt, ResponseBudget};
+use crate::ai::retrieval::ContextBuilder;
+use crate::file::mdfile::link::Link;
+use crate::prelude::*;
+
+const ASK_SYSTEM_PROMPT: &str = "You answer questions using only the notes provided. Cite every note you use with its wikilink, exactly as it appears in the note heading, e.g. [[Note]]. If the notes do not contain the answer, say so instead of guessing.";
+const ASK_USER_PROMPT: &str = r#"**Notes**
+
+[context]
+
+**Question**
+
+[question]"#;
+
+/// Answer struct
+///
+/// The answer to a question about the vault, with the notes it was grounded in.
+///
+/// @public
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Answer {
+    pub answer: String,
+    // the notes given to the model, nearest first
+    pub sources: Vec<PathBuf>,
+    // the sources the answer links to
+    pub cited: Vec<PathBuf>,
+}
+
+impl Vault {
+    /// Answer a question from the 5 notes nearest to it.
+    ///
+    /// Notes need embeddings, see `Vault::update_embeddings`.
+    ///
+    /// # Arguments
+    /// @param question: &str
+    /// @return Result<Answer> - Errors with `NoAIDriver` if no driver was added
+    ///
+    /// # Example
+    /// ```
+    /// use std::path::PathBuf;
+    ///
+    /// use obsidian_driver::ai::api::AIDriver;
+    /// use obsidian_driver::file::vault::Vault;
+    ///
+    /// async fn ask_example() {
+    /// 	let mut vault = Vault::from_path(PathBuf::from("vault")).unwrap();
+    /// 	vault.add_ai_driver(AIDriver::new_openai_from_config_path(PathBuf::from(".openai_config.json")).await.unwrap());
+    /// 	vault.update_embeddings().await.unwrap();
+    /// 	let answer = vault.ask("What do mitochondria do?").await.unwrap();
+    /// 	println!("{}\n\nSources: {:?}", answer.answer, answer.cited);
+    /// }
+    /// ```
+    pub async fn ask(&self, question: &str) -> Result<Answer> {
+        self.ask_with(question, ContextBuilder::new(self)).await
+    }
+
+    /// Answer a question from the notes a ContextBuilder selects.
+    ///
+    /// # Arguments
+    /// @param question: &str
+    /// @param builder: ContextBuilder - Sets the number of notes and the token budget
+    /// @return Result<Answer> - Errors with `NoAIDriver` if no driver was added
+    pub async fn ask_with(&self, question: &str, builder: ContextBuilder<'_>) -> Result<Answer> {
+        let driver = self.aidriver.as_ref().ok_or(Error::NoAIDriver)?;
+        let retrieved = builder.build_for_query(driver, question).await?;
+        let mut context = Context::default();
+        context.insert("context", &retrieved.text());
+        context.insert("question", question);
+        // the system prompt is left unsubstituted, its `[[Note]]` is not a placeholder
+        let user_prompt = Prompt::new("", ASK_USER_PROMPT, ResponseBudget::Unlimited).substitute(&context)?.user_prompt;
+        let prompt = Prompt::new(ASK_SYSTEM_PROMPT, &user_prompt, ResponseBudget::Unlimited);
+        let answer = driver.chat_smart(prompt).await?;
+
+        let sources = retrieved.sources();
+        let mut cited = Vec::new();
+        for link in Link::parse_all(&answer) {
+            let target = sources
+                .iter()
+                .find(|source| source.with_extension("").to_string_lossy().replace('\\', "/") == link.target);
+            let Some(target) = target else {
+                continue;
+            };
+            if !cited.contains(target) {
+                cited.push(target.clone());
+            }
+        }
+        Ok(Answer { answer, sources, cited })
+    }
+}
+
+#[cfg(test)]
+mod ask_tests {
+    use super::*;
+    use crate::ai::api::mock::MockDriver;
+    use crate::ai::api::AIDriver;
+
+    #[tokio::test]
+    async fn test_ask_cites_sources() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("Mitochondria.md"), "Mitochondria make ATP.").unwrap();
+        std::fs::write(dir.path().join("Ribosomes.md"), "Ribosomes make proteins.").unwrap();
+        let mock = MockDriver::new().with_responses(["They make ATP, see [[Mitochondria]] and [[Elsewhere]]."]);
+        let mut vault = Vault::from_path(dir.path().to_path_buf()).unwrap();
+        vault.add_ai_driver(AIDriver::new_mock(mock.clone()));
+        vault.update_embeddings().await.unwrap();
+
+        let answer = vault.ask("What do mitochondria do?").await.unwrap();
+        assert_eq!(answer.sources.len(), 2);
+        assert_eq!(answer.cited, vec![PathBuf::from("Mitochondria.md")]);
+        let prompt = &mock.prompts()[0];
+        assert!(prompt.user_prompt.contains("### [[Ribosomes]]\n\nRibosomes make proteins."));
+        assert!(prompt.user_prompt.ends_with("What do mitochondria do?"));
+    }
+}
@@ -0,0 +1,224 @@
+//! obsidian-driver::file::vault::autolink
+//!
+//! This module contains automatic wikilink suggestion: finding unlinked mentions of other notes' titles and aliases in a note and turning them into links.
+//!
+//! @public LinkSuggestion
+//!
+//! @public Vault::suggest_links
+//!
+//! @public Vault::apply_link_suggestions
+
+// std imports
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+// third-party imports
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+// first-party imports
+use super::Vault;
+use crate::file::mdfile::link::Link;
+use crate::file::mdfile::obsidian::hidden_ranges;
+use crate::prelude::*;
+
+/// LinkSuggestion struct
+///
+/// An unlinked mention of another note in the body of a note.
+///
+/// @public
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LinkSuggestion {
+    pub source: PathBuf,
+    // byte range of the mention in the body of the source note
+    pub range: Range<usize>,
+    pub found: String,
+    pub target: PathBuf,
+    // embedding distance between the source and target, when both have embeddings
+    pub distance: Option<f64>,
+}
+
+impl Vault {
+    /// Find mentions of other notes' titles and aliases in a note that are not linked yet.
+    ///
+    /// Mentions are matched case-insensitively on word boundaries, longest title first. Only the
+    /// first mention of each target is suggested, and code, comments, and existing links are
+    /// skipped. When a title belongs to several notes, the note closest to the source by
+    /// embedding wins, then the note with the shortest path. Suggestions with embeddings come
+    /// first, closest first; the rest follow in order of position.
+    ///
+    /// # Arguments
+    /// @param path: &Path - The note to suggest links for, relative to the vault root
+    /// @return Result<Vec<LinkSuggestion>>
+    ///
+    /// # Example
+    /// ```should_panic
+    /// use std::path::PathBuf;
+    ///
+    /// use obsidian_driver::file::vault::Vault;
+    ///
+    /// let mut vault = Vault::from_path(PathBuf::from("vault")).unwrap();
+    /// let suggestions = vault.suggest_links(&PathBuf::from("note.md")).unwrap();
+    /// let close: Vec<_> = suggestions.into_iter().filter(|suggestion| suggestion.distance.is_some_and(|distance| distance < 0.5)).collect();
+    /// vault.apply_link_suggestions(&close).unwrap();
+    /// ```
+    pub fn suggest_links(&self, path: &Path) -> Result<Vec<LinkSuggestion>> {
+        let mdfile = self
+            .files
+            .get(path)
+            .and_then(|file| file.get_mdfile())
+            .ok_or(Error::PathNotFound(path.to_path_buf()))?;
+        let source_embedding = self.files[path].get_embedding();
+
+        // lowercased title or alias -> notes it names
+        let mut names: HashMap<String, Vec<&PathBuf>> = HashMap::new();
+        for (other, file) in self.files.iter() {
+            let Some(other_mdfile) = file.get_mdfile() else {
+                continue;
+            };
+            if other == path {
+                continue;
+            }
+            let title = other.file_stem().and_then(|stem| stem.to_str()).unwrap_or_default();
+            for name in std::iter::once(title.to_string()).chain(other_mdfile.get_aliases()) {
+                let name = name.trim().to_lowercase();
+                if !name.is_empty() {
+                    names.entry(name).or_default().push(other);
+                }
+            }
+        }
+        let Some(pattern) = names_pattern(names.keys()) else {
+            return Ok(Vec::new());
+        };
+
+        let distance = |target: &Path| -> Option<f64> {
+            let (source, target) = (source_embedding?, self.files.get(target)?.get_embedding()?);
+            Some(source.iter().zip(target).map(|(a, b)| (a - b).powi(2)).sum::<f64>().sqrt())
+        };
+
+        let body = mdfile.get_body();
+        // mentions in code, comments and existing links are not linked
+        let mut skipped = hidden_ranges(body);
+        skipped.extend(Link::parse_all(body).into_iter().map(|link| link.range));
+        let mut linked: HashSet<PathBuf> = mdfile
+            .get_links()
+            .iter()
+            .filter_map(|link| self.resolve_link(path, link))
+            .collect();
+        let mut suggestions = Vec::new();
+        for found in pattern.find_iter(body) {
+            if skipped.iter().any(|range| range.start < found.end() && found.start() < range.end) {
+                continue;
+            }
+            let Some(targets) = names.get(&found.as_str().to_lowercase()) else {
+                continue;
+            };
+            let target = targets
+                .iter()
+                .min_by(|a, b| {
+                    let by_distance = match (distance(a), distance(b)) {
+                        (Some(a), Some(b)) => a.total_cmp(&b),
+                        (a, b) => b.is_some().cmp(&a.is_some()),
+                    };
+                    by_distance.then_with(|| (a.components().count(), a).cmp(&(b.components().count(), b)))
+                })
+                .map(|target| (*target).clone())
+                .unwrap();
+            if !linked.insert(target.clone()) {
+                continue;
+            }
+            suggestions.push(LinkSuggestion {
+                source: path.to_path_buf(),
+                range: found.range(),
+                found: found.as_str().to_string(),
+                distance: distance(&target),
+                target,
+            });
+        }
+        suggestions.sort_by(|a, b| match (a.distance, b.distance) {
+            (Some(x), Some(y)) => x.total_cmp(&y).then(a.range.start.cmp(&b.range.start)),
+            (x, y) => y.is_some().cmp(&x.is_some()).then(a.range.start.cmp(&b.range.start)),
+        });
+        Ok(suggestions)
+    }
+
+    /// Replace each suggested mention with a link to its target and write the changed notes.
+    ///
+    /// Links are written in the link style of the Vault, see `Vault::get_link_style`. The mention
+    /// is kept as the link text when it differs from the note name.
+    ///
+    /// # Arguments
+    /// @param suggestions: &[LinkSuggestion] - Suggestions from `suggest_links`, filtered to the ones to apply
+    /// @return Result<()>
+    pub fn apply_link_suggestions(&mut self, suggestions: &[LinkSuggestion]) -> Result<()> {
+        let edits = suggestions
+            .iter()
+            .map(|suggestion| {
+                let text = self.new_link(&suggestion.source, &suggestion.target, Some(&suggestion.found));
+                (suggestion.source.clone(), suggestion.range.clone(), text)
+            })
+            .collect();
+        self.edit_bodies(edits)
+    }
+}
+
+/// Build a single case-insensitive regex matching every name, longest first.
+///
+/// @private
+fn names_pattern<'a>(names: impl Iterator<Item = &'a String>) -> Option<Regex> {
+    let mut names: Vec<&String> = names.collect();
+    if names.is_empty() {
+        return None;
+    }
+    names.sort_by(|a, b| b.len().cmp(&a.len()).then(a.cmp(b)));
+    let alternatives: Vec<String> = names
+        .iter()
+        .map(|name| {
+            let start = if name.starts_with(|c: char| c.is_alphanumeric()) { r"\b" } else { "" };
+            let end = if name.ends_with(|c: char| c.is_alphanumeric()) { r"\b" } else { "" };
+            f!("{}{}{}", start, regex::escape(name), end)
+        })
+        .collect();
+    Regex::new(&f!("(?i){}", alternatives.join("|"))).ok()
+}
+
+#[cfg(test)]
+mod autolink_tests {
+    use super::*;
+
+    #[test]
+    fn test_suggest_and_apply_links() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("a")).unwrap();
+        std::fs::create_dir(dir.path().join("b")).unwrap();
+        std::fs::write(dir.path().join("Karnaugh Map.md"), "---\naliases: [K-map]\n---\n").unwrap();
+        std::fs::write(dir.path().join("a/Intro.md"), "").unwrap();
+        std::fs::write(dir.path().join("b/Intro.md"), "").unwrap();
+        std::fs::write(dir.path().join("Logic.md"), "").unwrap();
+        std::fs::write(
+            dir.path().join("note.md"),
+            "A k-map, a Karnaugh map, the intro. `Logic` [[Logic]] logic",
+        )
+        .unwrap();
+        let mut vault = Vault::from_path(dir.path().to_path_buf()).unwrap();
+        vault.files.get_mut(Path::new("note.md")).unwrap().set_embedding(vec![0.0, 0.0]);
+        vault.files.get_mut(Path::new("a/Intro.md")).unwrap().set_embedding(vec![3.0, 0.0]);
+        vault.files.get_mut(Path::new("b/Intro.md")).unwrap().set_embedding(vec![0.0, 1.0]);
+
+        let suggestions = vault.suggest_links(Path::new("note.md")).unwrap();
+        let actual: Vec<(&str, &str, Option<f64>)> = suggestions
+            .iter()
+            .map(|suggestion| (suggestion.found.as_str(), suggestion.target.to_str().unwrap(), suggestion.distance))
+            .collect();
+        let expected = vec![("intro", "b/Intro.md", Some(1.0)), ("k-map", "Karnaugh Map.md", None)];
+        assert_eq!(actual, expected);
+
+        vault.apply_link_suggestions(&suggestions).unwrap();
+        let note = std::fs::read_to_string(dir.path().join("note.md")).unwrap();
+        assert_eq!(
+            note,
+            "A [[Karnaugh Map|k-map]], a Karnaugh map, the [[b/Intro|intro]]. `Logic` [[Logic]] logic"
+        );
+    }
+}
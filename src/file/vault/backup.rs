@@ -0,0 +1,121 @@
+//! obsidian-driver::file::vault::backup
+//!
+//! This module contains the opt-in backup mode: once a backup folder is set, every vault operation that overwrites, moves or deletes a file first copies the original there with a timestamp.
+//!
+//! @public Vault::set_backup_folder
+//!
+//! @public Vault::get_backup_folder
+//!
+//! @super Vault::backup_file
+
+// std imports
+use std::path::{Path, PathBuf};
+
+// first-party imports
+use super::Vault;
+use crate::prelude::*;
+
+impl Vault {
+    /// Back up files before they are modified.
+    ///
+    /// Before `write_file`, `write_all`, link rewrites, `move_file` and `remove_file` change a
+    /// file on disk, its current contents are copied to `<folder>/<path>` with a timestamp added
+    /// to the file name, e.g. `Backups/Lectures/Cells.20240102T150405123.md`. A relative folder
+    /// is resolved against the vault root. Files already loaded from the folder are dropped from
+    /// the vault, and `refresh_until` and exports skip it.
+    ///
+    /// # Arguments
+    /// @param folder: PathBuf
+    ///
+    /// # Example
+    /// ```should_panic
+    /// use std::path::PathBuf;
+    ///
+    /// use obsidian_driver::file::vault::Vault;
+    ///
+    /// let mut vault = Vault::from_path(PathBuf::from("vault")).unwrap();
+    /// vault.set_backup_folder(PathBuf::from("Backups"));
+    /// vault.write_all();
+    /// ```
+    pub fn set_backup_folder(&mut self, folder: PathBuf) {
+        let relative = folder.strip_prefix(&self.vault_root).unwrap_or(&folder).to_path_buf();
+        let backups: Vec<PathBuf> = self.files.keys().filter(|path| path.starts_with(&relative)).cloned().collect();
+        for path in backups {
+            self.files.remove(&path);
+            self.properties.remove(&path);
+        }
+        self.backup_folder = Some(folder);
+    }
+
+    /// Get the backup folder, if backups are enabled.
+    ///
+    /// # Arguments
+    /// @return Option<&PathBuf>
+    pub fn get_backup_folder(&self) -> Option<&PathBuf> {
+        self.backup_folder.as_ref()
+    }
+
+    /// Copy a file to the backup folder, if one is set and the file exists on disk. Uses the path relative to the vault root.
+    ///
+    /// # Arguments
+    /// @param path: &Path
+    /// @return Result<Option<PathBuf>> - The absolute path of the backup
+    ///
+    /// @super
+    pub(super) fn backup_file(&self, path: &Path) -> Result<Option<PathBuf>> {
+        let Some(folder) = &self.backup_folder else {
+            return Ok(None);
+        };
+        let source = self.vault_root.join(path);
+        if !source.is_file() {
+            return Ok(None);
+        }
+        let timestamp = chrono::Local::now().format("%Y%m%dT%H%M%S%3f");
+        let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+        let name = match path.extension() {
+            Some(extension) => f!("{}.{}.{}", stem, timestamp, extension.to_string_lossy()),
+            None => f!("{}.{}", stem, timestamp),
+        };
+        let backup = self.vault_root.join(folder).join(path).with_file_name(name);
+        if let Some(parent) = backup.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::copy(&source, &backup)?;
+        Ok(Some(backup))
+    }
+}
+
+#[cfg(test)]
+mod backup_tests {
+    use super::*;
+
+    #[test]
+    fn test_write_file_backs_up_original() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("sub")).unwrap();
+        std::fs::write(dir.path().join("sub/a.md"), "# A").unwrap();
+        let mut vault = Vault::from_path(dir.path().to_path_buf()).unwrap();
+        vault.set_backup_folder(PathBuf::from("Backups"));
+
+        let path = PathBuf::from("sub/a.md");
+        let mdfile = vault.get_file_mut(&path).unwrap().get_mdfile_mut().unwrap();
+        mdfile.set_body("# Edited".to_string());
+        vault.write_file(&path).unwrap();
+
+        let backups: Vec<PathBuf> = std::fs::read_dir(dir.path().join("Backups/sub"))
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect();
+        assert_eq!(backups.len(), 1);
+        assert_eq!(std::fs::read_to_string(&backups[0]).unwrap(), "# A");
+        // the backup folder is not part of the vault
+        vault.refresh_until(&crate::batch::CancellationToken::new()).unwrap();
+        assert_eq!(vault.get_files().len(), 1);
+        let mut reloaded = Vault::from_path(dir.path().to_path_buf()).unwrap();
+        assert_eq!(reloaded.get_files().len(), 2);
+        reloaded.set_backup_folder(PathBuf::from("Backups"));
+        assert_eq!(reloaded.get_files().len(), 1);
+        reloaded.refresh_until(&crate::batch::CancellationToken::new()).unwrap();
+        assert_eq!(reloaded.get_files().len(), 1);
+    }
+}
@@ -0,0 +1,218 @@
+//! obsidian-driver::file::vault::cache
+//!
+//! This module contains the incremental vault cache: a folder holding one entry per file and an index of their hashes, so saving only rewrites the entries of files that changed.
+//!
+//! @public Vault::from_cache_dir
+//!
+//! @public Vault::to_cache_dir
+
+// std imports
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+// third-party imports
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+// first-party imports
+use super::profile::{Operation, Phase};
+use super::{profile, Vault};
+use crate::file::write_atomic;
+use crate::prelude::*;
+
+/// The index file of a cache folder.
+const CACHE_INDEX_FILE: &str = "index.json";
+
+/// The folder of a cache folder holding the file entries.
+const CACHE_ENTRY_FOLDER: &str = "entries";
+
+/// CacheIndex struct
+///
+/// The index of a cache folder: for each file path, the hash of its entry and the entry file name.
+///
+/// @private
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+struct CacheIndex {
+    entries: HashMap<PathBuf, CacheIndexEntry>,
+}
+
+/// CacheIndexEntry struct
+///
+/// @private
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+struct CacheIndexEntry {
+    // content hash of the file, combined with its embedding
+    hash: String,
+    // name of the entry file in the entry folder
+    entry: String,
+}
+
+impl Vault {
+    /// Create a Vault from a cache folder written by `to_cache_dir`.
+    ///
+    /// Files missing from the cache, or modified since it was written, are read from disk as in
+    /// `from_cache`. If the folder has no index yet, the vault is read from scratch.
+    ///
+    /// # Arguments
+    /// @param vault_root: PathBuf
+    /// @param cache_dir: &Path
+    /// @return Result<Self>
+    ///
+    /// # Example
+    /// ```should_panic
+    /// use std::path::{Path, PathBuf};
+    ///
+    /// use obsidian_driver::file::vault::Vault;
+    ///
+    /// let cache_dir = Path::new(".vault_cache");
+    /// let vault = Vault::from_cache_dir(PathBuf::from("vault"), cache_dir).unwrap();
+    /// vault.to_cache_dir(cache_dir).unwrap();
+    /// ```
+    pub fn from_cache_dir(vault_root: PathBuf, cache_dir: &Path) -> Result<Self> {
+        let index_path = cache_dir.join(CACHE_INDEX_FILE);
+        if !index_path.exists() {
+            return Self::from_path(vault_root);
+        }
+        let profiler = profile::Profiler::default();
+        let index = read_index(&index_path)?;
+        let mut files = HashMap::new();
+        for (path, entry) in index.entries {
+            // a missing or unreadable entry is re-read from the note itself
+            let entry_path = cache_dir.join(CACHE_ENTRY_FOLDER).join(&entry.entry);
+            let Ok(entry_str) = profiler.time(Operation::Load, Phase::Io, || std::fs::read_to_string(&entry_path)) else {
+                continue;
+            };
+            let file = profiler.time(Operation::Load, Phase::Parsing, || {
+                serde_json::from_str::<crate::file::File>(&entry_str)
+            });
+            if let Ok(file) = file {
+                files.insert(path, file);
+            }
+        }
+
+        let mut vault = Vault {
+            files,
+            profiler,
+            ..Default::default()
+        };
+        vault.refresh_from_disk(vault_root)?;
+        Ok(vault)
+    }
+
+    /// Write the Vault to a cache folder, rewriting only the entries of files whose contents or embedding changed since the last write.
+    ///
+    /// Entries of files no longer in the vault are removed. If an embedding store is attached,
+    /// embeddings are written to the store instead of the entries, as in `to_cache`.
+    ///
+    /// # Arguments
+    /// @param cache_dir: &Path - Created if it does not exist
+    /// @return Result<usize> - The number of entries written
+    pub fn to_cache_dir(&self, cache_dir: &Path) -> Result<usize> {
+        let entry_folder = cache_dir.join(CACHE_ENTRY_FOLDER);
+        std::fs::create_dir_all(&entry_folder)?;
+        let index_path = cache_dir.join(CACHE_INDEX_FILE);
+        let mut index = match index_path.exists() {
+            true => read_index(&index_path)?,
+            false => CacheIndex::default(),
+        };
+
+        let mut embeddings = Vec::new();
+        let mut written = 0;
+        for (path, file) in self.files.iter() {
+            let mut file = file.clone();
+            if self.embedding_store.is_some() {
+                if let Some(embedding) = file.take_embedding() {
+                    embeddings.push((path.clone(), file.content_hash(), embedding));
+                }
+            }
+            let entry = CacheIndexEntry {
+                hash: entry_hash(&file),
+                entry: f!("{}.json", hex_digest(path.to_string_lossy().as_bytes())),
+            };
+            if index.entries.get(path) == Some(&entry) && entry_folder.join(&entry.entry).exists() {
+                continue;
+            }
+            write_atomic(entry_folder.join(&entry.entry), serde_json::to_string(&file)?)?;
+            index.entries.insert(path.clone(), entry);
+            written += 1;
+        }
+
+        let removed: Vec<PathBuf> = index
+            .entries
+            .keys()
+            .filter(|path| !self.files.contains_key(*path))
+            .cloned()
+            .collect();
+        for path in removed {
+            if let Some(entry) = index.entries.remove(&path) {
+                let entry_path = entry_folder.join(entry.entry);
+                if entry_path.exists() {
+                    std::fs::remove_file(entry_path)?;
+                }
+            }
+        }
+
+        if let Some(store) = &self.embedding_store {
+            store.save(embeddings.into_iter(), &self.files.keys().collect())?;
+        }
+        write_atomic(&index_path, serde_json::to_string(&index)?)?;
+        Ok(written)
+    }
+}
+
+/// Read the index of a cache folder.
+///
+/// @private
+fn read_index(index_path: &Path) -> Result<CacheIndex> {
+    Ok(serde_json::from_str(&std::fs::read_to_string(index_path)?)?)
+}
+
+/// The hash identifying the cached state of a file: its content hash combined with its embedding.
+///
+/// @private
+fn entry_hash(file: &crate::file::File) -> String {
+    let mut hasher = Sha256::new().chain_update(file.content_hash().as_bytes());
+    for value in file.get_embedding().into_iter().flatten() {
+        hasher.update(value.to_le_bytes());
+    }
+    hex_digest_of(hasher)
+}
+
+/// The hex SHA-256 digest of some bytes.
+///
+/// @private
+fn hex_digest(bytes: &[u8]) -> String {
+    hex_digest_of(Sha256::new().chain_update(bytes))
+}
+
+/// Finish a hasher into a hex digest.
+///
+/// @private
+fn hex_digest_of(hasher: Sha256) -> String {
+    hasher.finalize().iter().map(|byte| f!("{:02x}", byte)).collect()
+}
+
+#[cfg(test)]
+mod cache_tests {
+    use super::*;
+    use crate::file::vault::links::BrokenLinkPolicy;
+
+    #[test]
+    fn test_cache_dir_rewrites_changed_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.md"), "# A").unwrap();
+        std::fs::write(dir.path().join("b.md"), "# B").unwrap();
+        let mut vault = Vault::from_path(dir.path().to_path_buf()).unwrap();
+        assert_eq!(vault.to_cache_dir(cache.path()).unwrap(), 2);
+        assert_eq!(vault.to_cache_dir(cache.path()).unwrap(), 0);
+
+        vault.get_file_mut(&"a.md".into()).unwrap().set_embedding(vec![1.0, 0.0]);
+        vault.remove_file(&"b.md".into(), BrokenLinkPolicy::Ignore).unwrap();
+        assert_eq!(vault.to_cache_dir(cache.path()).unwrap(), 1);
+        assert_eq!(std::fs::read_dir(cache.path().join(CACHE_ENTRY_FOLDER)).unwrap().count(), 1);
+
+        let cached = Vault::from_cache_dir(dir.path().to_path_buf(), cache.path()).unwrap();
+        assert_eq!(cached.get_file(&"a.md".into()).unwrap().get_embedding(), Some(&vec![1.0, 0.0]));
+    }
+}
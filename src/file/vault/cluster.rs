@@ -0,0 +1,250 @@
+//! obsidian-driver::file::vault::cluster
+//!
+//! This module contains embedding-based clustering of the notes of a Vault.
+//!
+//! @public ClusterMethod
+//!
+//! @public NoteCluster
+//!
+//! @public Vault::cluster_notes
+
+// std imports
+use std::path::PathBuf;
+
+// third-party imports
+use serde::{Deserialize, Serialize};
+
+// first-party imports
+use super::Vault;
+use crate::prelude::*;
+
+/// The maximum number of k-means iterations.
+const MAX_ITERATIONS: usize = 100;
+
+/// How to group notes into clusters.
+///
+/// @public
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ClusterMethod {
+    /// k-means with a fixed number of clusters. Initial centroids are picked farthest-first, so results are deterministic.
+    KMeans(usize),
+    /// Agglomerative clustering: clusters are merged while their centroids are at most this embedding distance apart.
+    Threshold(f64),
+}
+
+/// NoteCluster struct
+///
+/// A group of notes with similar embeddings.
+///
+/// @public
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct NoteCluster {
+    // the title of the member closest to the centroid
+    pub label: String,
+    // sorted by path
+    pub members: Vec<PathBuf>,
+    pub centroid: Vec<f64>,
+}
+
+impl Vault {
+    /// Group the notes that have embeddings into clusters by embedding distance.
+    ///
+    /// # Arguments
+    /// @param method: ClusterMethod
+    /// @return Result<Vec<NoteCluster>> - The clusters, largest first. Errors if embeddings have different dimensions.
+    ///
+    /// # Example
+    /// ```should_panic
+    /// use std::path::PathBuf;
+    ///
+    /// use obsidian_driver::file::vault::Vault;
+    /// use obsidian_driver::file::vault::cluster::ClusterMethod;
+    ///
+    /// let vault = Vault::from_path(PathBuf::from("vault")).unwrap();
+    /// for cluster in vault.cluster_notes(ClusterMethod::KMeans(8)).unwrap() {
+    /// 	println!("{}: {:?}", cluster.label, cluster.members);
+    /// }
+    /// ```
+    pub fn cluster_notes(&self, method: ClusterMethod) -> Result<Vec<NoteCluster>> {
+        let mut points: Vec<(&PathBuf, &Vec<f64>)> = self
+            .files
+            .iter()
+            .filter_map(|(path, file)| Some((path, file.get_embedding()?)))
+            .collect();
+        points.sort_by(|a, b| a.0.cmp(b.0));
+        if let Some((_, first)) = points.first() {
+            for (_, embedding) in &points {
+                if embedding.len() != first.len() {
+                    return Err(Error::EmbeddingDimensionMismatch(first.len(), embedding.len()));
+                }
+            }
+        }
+        let embeddings: Vec<&Vec<f64>> = points.iter().map(|(_, embedding)| *embedding).collect();
+
+        let groups = match method {
+            ClusterMethod::KMeans(k) => k_means(&embeddings, k),
+            ClusterMethod::Threshold(threshold) => agglomerate(&embeddings, threshold),
+        };
+
+        let mut clusters: Vec<NoteCluster> = groups
+            .into_iter()
+            .filter(|group| !group.is_empty())
+            .map(|group| {
+                let centroid = centroid(group.iter().map(|&i| embeddings[i]));
+                let central = group
+                    .iter()
+                    .min_by(|&&a, &&b| distance(embeddings[a], &centroid).total_cmp(&distance(embeddings[b], &centroid)))
+                    .unwrap();
+                let label = points[*central]
+                    .0
+                    .file_stem()
+                    .map(|stem| stem.to_string_lossy().into_owned())
+                    .unwrap_or_default();
+                let mut members: Vec<PathBuf> = group.iter().map(|&i| points[i].0.clone()).collect();
+                members.sort();
+                NoteCluster { label, members, centroid }
+            })
+            .collect();
+        clusters.sort_by(|a, b| b.members.len().cmp(&a.members.len()).then_with(|| a.members.cmp(&b.members)));
+        Ok(clusters)
+    }
+}
+
+/// Euclidean distance between two embeddings.
+///
+/// @private
+fn distance(a: &[f64], b: &[f64]) -> f64 {
+    a.iter().zip(b).map(|(a, b)| (a - b).powi(2)).sum::<f64>().sqrt()
+}
+
+/// The mean of a non-empty set of embeddings.
+///
+/// @private
+fn centroid<'a>(embeddings: impl Iterator<Item = &'a Vec<f64>>) -> Vec<f64> {
+    let mut sum: Vec<f64> = Vec::new();
+    let mut count = 0.0;
+    for embedding in embeddings {
+        if sum.is_empty() {
+            sum = vec![0.0; embedding.len()];
+        }
+        for (total, value) in sum.iter_mut().zip(embedding) {
+            *total += value;
+        }
+        count += 1.0;
+    }
+    sum.into_iter().map(|total| total / count).collect()
+}
+
+/// k-means clustering, returning the indices of the members of each cluster.
+///
+/// @private
+fn k_means(embeddings: &[&Vec<f64>], k: usize) -> Vec<Vec<usize>> {
+    let k = k.min(embeddings.len());
+    if k == 0 {
+        return Vec::new();
+    }
+    // farthest-first initialization, starting from the first point
+    let mut centroids: Vec<Vec<f64>> = vec![embeddings[0].clone()];
+    while centroids.len() < k {
+        let farthest = (0..embeddings.len())
+            .max_by(|&a, &b| {
+                let nearest = |i: usize| centroids.iter().map(|c| distance(embeddings[i], c)).fold(f64::INFINITY, f64::min);
+                nearest(a).total_cmp(&nearest(b)).then(b.cmp(&a))
+            })
+            .unwrap();
+        centroids.push(embeddings[farthest].clone());
+    }
+
+    let mut assignments = vec![usize::MAX; embeddings.len()];
+    for _ in 0..MAX_ITERATIONS {
+        let next: Vec<usize> = embeddings
+            .iter()
+            .map(|embedding| {
+                (0..k)
+                    .min_by(|&a, &b| distance(embedding, &centroids[a]).total_cmp(&distance(embedding, &centroids[b])))
+                    .unwrap()
+            })
+            .collect();
+        if next == assignments {
+            break;
+        }
+        assignments = next;
+        for (cluster, centroid_slot) in centroids.iter_mut().enumerate() {
+            let members = embeddings
+                .iter()
+                .zip(&assignments)
+                .filter(|(_, &assigned)| assigned == cluster)
+                .map(|(embedding, _)| *embedding);
+            let mean = centroid(members);
+            // an empty cluster keeps its previous centroid
+            if !mean.is_empty() {
+                *centroid_slot = mean;
+            }
+        }
+    }
+
+    let mut groups = vec![Vec::new(); k];
+    for (i, cluster) in assignments.into_iter().enumerate() {
+        groups[cluster].push(i);
+    }
+    groups
+}
+
+/// Agglomerative clustering by centroid distance, returning the indices of the members of each cluster.
+///
+/// @private
+fn agglomerate(embeddings: &[&Vec<f64>], threshold: f64) -> Vec<Vec<usize>> {
+    let mut clusters: Vec<(Vec<usize>, Vec<f64>)> = embeddings
+        .iter()
+        .enumerate()
+        .map(|(i, embedding)| (vec![i], (*embedding).clone()))
+        .collect();
+    loop {
+        let mut closest: Option<(usize, usize, f64)> = None;
+        for a in 0..clusters.len() {
+            for b in a + 1..clusters.len() {
+                let d = distance(&clusters[a].1, &clusters[b].1);
+                if d <= threshold && closest.is_none_or(|(_, _, best)| d < best) {
+                    closest = Some((a, b, d));
+                }
+            }
+        }
+        let Some((a, b, _)) = closest else {
+            break;
+        };
+        let (members, _) = clusters.remove(b);
+        clusters[a].0.extend(members);
+        clusters[a].1 = centroid(clusters[a].0.iter().map(|&i| embeddings[i]));
+    }
+    clusters.into_iter().map(|(members, _)| members).collect()
+}
+
+#[cfg(test)]
+mod cluster_tests {
+    use super::*;
+
+    #[test]
+    fn test_cluster_notes_k_means_and_threshold() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["a1", "a2", "a3", "b1", "b2", "none"] {
+            std::fs::write(dir.path().join(f!("{}.md", name)), name).unwrap();
+        }
+        let mut vault = Vault::from_path(dir.path().to_path_buf()).unwrap();
+        let embeddings = [("a1", [0.0, 0.0]), ("a2", [1.0, 0.0]), ("a3", [0.5, 0.1]), ("b1", [10.0, 10.0]), ("b2", [10.0, 11.0])];
+        for (name, embedding) in embeddings {
+            let path = PathBuf::from(f!("{}.md", name));
+            vault.files.get_mut(&path).unwrap().set_embedding(embedding.to_vec());
+        }
+
+        for method in [ClusterMethod::KMeans(2), ClusterMethod::Threshold(2.0)] {
+            let clusters = vault.cluster_notes(method).unwrap();
+            let actual: Vec<(&str, usize)> = clusters
+                .iter()
+                .map(|cluster| (cluster.label.as_str(), cluster.members.len()))
+                .collect();
+            assert_eq!(actual, vec![("a3", 3), ("b1", 2)]);
+            assert_eq!(clusters[1].centroid, vec![10.0, 10.5]);
+        }
+        assert_eq!(vault.cluster_notes(ClusterMethod::Threshold(0.1)).unwrap().len(), 5);
+    }
+}
@@ -0,0 +1,167 @@
+//! obsidian-driver::file::vault::daily
+//!
+//! This module contains daily notes: finding the note of a date where the Daily notes core plugin puts it, creating it from the plugin's template when it does not exist yet, and appending journal entries to it.
+//!
+//! @public Vault::daily_note
+//!
+//! @public Vault::journal
+
+// std imports
+use std::path::PathBuf;
+
+// third-party imports
+use chrono::{NaiveDate, NaiveDateTime};
+
+// first-party imports
+use super::Vault;
+use crate::ai::prompt::Context;
+use crate::prelude::*;
+
+impl Vault {
+    /// Get the daily note of a date, creating and writing it if it does not exist.
+    ///
+    /// The path and template come from the Daily notes settings, see `Vault::settings`. In the
+    /// template, `{{date}}` is the date of the note and `{{time}}` the current time. Without a
+    /// template the new note is empty.
+    ///
+    /// # Arguments
+    /// @param date: NaiveDate
+    /// @return Result<PathBuf> - The note, relative to the vault root; errors with `PathNotFound` if the configured template is missing
+    ///
+    /// # Example
+    /// ```should_panic
+    /// use std::path::PathBuf;
+    ///
+    /// use chrono::Local;
+    /// use obsidian_driver::file::vault::Vault;
+    ///
+    /// let mut vault = Vault::from_path(PathBuf::from("vault")).unwrap();
+    /// let today = vault.daily_note(Local::now().date_naive()).unwrap();
+    /// ```
+    pub fn daily_note(&mut self, date: NaiveDate) -> Result<PathBuf> {
+        let settings = self.settings.daily_notes.clone();
+        let path = settings.path_for(date);
+        if self.files.contains_key(&path) {
+            return Ok(path);
+        }
+        let contents = match &settings.template {
+            Some(template) => {
+                let mdfile = self
+                    .files
+                    .get(template)
+                    .and_then(|file| file.get_mdfile())
+                    .ok_or(Error::PathNotFound(template.clone()))?;
+                let name = template.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default();
+                let title = path.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default();
+                let now = date.and_time(chrono::Local::now().time());
+                self.template(&name, mdfile).instantiate_at(&title, &Context::default(), now)?.to_string()
+            }
+            None => String::new(),
+        };
+        self.put_note(&path, &contents)?;
+        Ok(path)
+    }
+
+    /// Append a timestamped entry to a section of today's daily note, creating the note if it does not exist.
+    ///
+    /// The entry is a list item, `- HH:MM text`, added after the last line of the first section
+    /// with the heading. Without such a section, a `##` heading is added at the end of the note.
+    ///
+    /// # Arguments
+    /// @param text: &str - Further lines are indented under the entry
+    /// @param section: &str - The heading text, without the leading `#`s
+    /// @return Result<PathBuf> - The daily note, relative to the vault root
+    ///
+    /// # Example
+    /// ```should_panic
+    /// use std::path::PathBuf;
+    ///
+    /// use obsidian_driver::file::vault::Vault;
+    ///
+    /// let mut vault = Vault::from_path(PathBuf::from("vault")).unwrap();
+    /// vault.journal("Finished the cell biology reading", "Log").unwrap();
+    /// ```
+    pub fn journal(&mut self, text: &str, section: &str) -> Result<PathBuf> {
+        self.journal_at(text, section, chrono::Local::now().naive_local())
+    }
+
+    /// Append a journal entry as if it were `now`.
+    ///
+    /// @private
+    fn journal_at(&mut self, text: &str, section: &str, now: NaiveDateTime) -> Result<PathBuf> {
+        let path = self.daily_note(now.date())?;
+        let mdfile = self
+            .files
+            .get_mut(&path)
+            .and_then(|file| file.get_mdfile_mut())
+            .ok_or(Error::PathNotFound(path.clone()))?;
+        let entry = f!("- {} {}", now.format("%H:%M"), text.trim().replace('\n', "\n  "));
+        let body = mdfile.get_body();
+        let body = match mdfile.get_section(section) {
+            Some(found) => {
+                let content = body[found.content_range.clone()].trim_end();
+                let (head, tail) = body.split_at(found.content_range.start + content.len());
+                match (content.is_empty(), tail.is_empty()) {
+                    // keep a blank line between the heading and the first entry
+                    (true, _) => f!("{}\n\n{}\n{}", head.trim_end_matches('\n'), entry, tail),
+                    (false, true) => f!("{}\n{}\n", head, entry),
+                    (false, false) => f!("{}\n{}{}", head, entry, tail),
+                }
+            }
+            None => {
+                let head = body.trim_end();
+                let separator = if head.is_empty() { "" } else { "\n\n" };
+                f!("{}{}## {}\n\n{}\n", head, separator, section, entry)
+            }
+        };
+        mdfile.set_body(body);
+        self.write_file(&path)?;
+        Ok(path)
+    }
+}
+
+#[cfg(test)]
+mod daily_tests {
+    use super::*;
+
+    #[test]
+    fn test_daily_note_from_template() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join(".obsidian")).unwrap();
+        std::fs::create_dir_all(dir.path().join("Templates")).unwrap();
+        std::fs::write(
+            dir.path().join(".obsidian/daily-notes.json"),
+            r#"{"folder": "Journal", "format": "YYYY/YYYY-MM-DD", "template": "Templates/Daily"}"#,
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("Templates/Daily.md"), "# {{title}}\n\n{{date:dddd}}\n").unwrap();
+        let mut vault = Vault::from_path(dir.path().to_path_buf()).unwrap();
+
+        let date = NaiveDate::from_ymd_opt(2024, 9, 3).unwrap();
+        let path = vault.daily_note(date).unwrap();
+        assert_eq!(path, PathBuf::from("Journal/2024/2024-09-03.md"));
+        assert_eq!(std::fs::read_to_string(dir.path().join(&path)).unwrap(), "# 2024-09-03\n\nTuesday\n");
+        std::fs::write(dir.path().join(&path), "# Edited").unwrap();
+        assert_eq!(vault.daily_note(date).unwrap(), path);
+        assert_eq!(std::fs::read_to_string(dir.path().join(&path)).unwrap(), "# Edited");
+    }
+
+    #[test]
+    fn test_journal_appends_under_heading() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join(".obsidian")).unwrap();
+        std::fs::create_dir_all(dir.path().join("Templates")).unwrap();
+        std::fs::write(dir.path().join(".obsidian/daily-notes.json"), r#"{"template": "Templates/Daily"}"#).unwrap();
+        std::fs::write(dir.path().join("Templates/Daily.md"), "# {{title}}\n\n## Log\n\n## Tasks\n").unwrap();
+        let mut vault = Vault::from_path(dir.path().to_path_buf()).unwrap();
+        let at = |time: &str| NaiveDateTime::parse_from_str(&f!("2024-09-03 {}", time), "%Y-%m-%d %H:%M").unwrap();
+
+        let path = vault.journal_at("Started reading", "Log", at("09:00")).unwrap();
+        vault.journal_at("Finished\nchapter 2", "Log", at("10:30")).unwrap();
+        vault.journal_at("Tired", "Mood", at("11:00")).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join(path)).unwrap(),
+            "# 2024-09-03\n\n## Log\n\n- 09:00 Started reading\n- 10:30 Finished\n  chapter 2\n\n## Tasks\n\n## Mood\n\n- 11:00 Tired\n"
+        );
+    }
+}
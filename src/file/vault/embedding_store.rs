@@ -0,0 +1,119 @@
+//! obsidian-driver::file::vault::embedding_store
+//!
+//! This module contains the EmbeddingStore struct, a binary store for note embeddings kept separately from the JSON vault cache.
+//!
+//! @crate EmbeddingStore
+//!
+//! @crate EmbeddingStore::new
+//!
+//! @crate EmbeddingStore::get
+//!
+//! @crate EmbeddingStore::save
+
+// std imports
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+// third-party imports
+use serde::{Deserialize, Serialize};
+
+// first-party imports
+use crate::prelude::*;
+
+/// EmbeddingStore struct
+///
+/// Embeddings are keyed by the note path relative to the vault root and tagged with the content
+/// hash of the note they were computed from, so stale vectors are never handed back. The store
+/// file is only read the first time an embedding is requested or saved, and saving keeps the
+/// loaded entries up to date.
+///
+/// @crate
+#[derive(Clone, Debug)]
+pub(crate) struct EmbeddingStore {
+    path: PathBuf,
+    // shared with clones, e.g. the copy `Vault::to_cache` saves from; None until loaded
+    entries: Arc<Mutex<Option<HashMap<PathBuf, StoredEmbedding>>>>,
+}
+
+/// StoredEmbedding struct
+///
+/// @private
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+struct StoredEmbedding {
+    hash: String,
+    embedding: Vec<f64>,
+}
+
+impl EmbeddingStore {
+    /// Create a new EmbeddingStore backed by the given file. The file does not need to exist yet.
+    ///
+    /// # Arguments
+    /// @param path: PathBuf - The path of the store file
+    /// @return Self
+    pub(crate) fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            entries: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Read the store file from disk.
+    ///
+    /// # Arguments
+    /// @return Result<HashMap<PathBuf, StoredEmbedding>>
+    fn read(&self) -> Result<HashMap<PathBuf, StoredEmbedding>> {
+        if !self.path.exists() {
+            return Ok(HashMap::new());
+        }
+        let bytes = std::fs::read(&self.path)?;
+        Ok(bincode::deserialize(&bytes)?)
+    }
+
+    /// Get the stored embedding for a note, if one exists for the note's current contents.
+    ///
+    /// # Arguments
+    /// @param path: &Path - The note path relative to the vault root
+    /// @param hash: &str - The content hash of the note
+    /// @return Result<Option<Vec<f64>>>
+    pub(crate) fn get(&self, path: &Path, hash: &str) -> Result<Option<Vec<f64>>> {
+        let mut entries = self.entries.lock().expect("Embedding store lock poisoned");
+        if entries.is_none() {
+            *entries = Some(self.read()?);
+        }
+        let entries = entries.as_ref().expect("Embedding store not loaded");
+        Ok(entries
+            .get(path)
+            .filter(|stored| stored.hash == hash)
+            .map(|stored| stored.embedding.clone()))
+    }
+
+    /// Write the given embeddings to the store file, and keep them for `get`.
+    ///
+    /// Entries of notes not in `embeddings` are kept as long as the note is still in the vault, so
+    /// embeddings of deleted or renamed notes are pruned.
+    ///
+    /// # Arguments
+    /// @param embeddings: impl Iterator<Item = (PathBuf, String, Vec<f64>)> - (path, content hash, embedding) triples
+    /// @param vault_paths: &HashSet<&PathBuf> - The paths of all notes currently in the vault
+    /// @return Result<()>
+    pub(crate) fn save(
+        &self,
+        embeddings: impl Iterator<Item = (PathBuf, String, Vec<f64>)>,
+        vault_paths: &HashSet<&PathBuf>,
+    ) -> Result<()> {
+        let mut loaded = self.entries.lock().expect("Embedding store lock poisoned");
+        let mut entries = match loaded.as_ref() {
+            Some(entries) => entries.clone(),
+            None => self.read()?,
+        };
+        entries.retain(|path, _| vault_paths.contains(&path));
+        for (path, hash, embedding) in embeddings {
+            entries.insert(path, StoredEmbedding { hash, embedding });
+        }
+        let bytes = bincode::serialize(&entries)?;
+        crate::file::write_atomic(&self.path, bytes)?;
+        *loaded = Some(entries);
+        Ok(())
+    }
+}
@@ -0,0 +1,98 @@
+//! obsidian-driver::file::vault::embeds
+//!
+//! This module contains embed expansion: replacing `![[Note]]`, `![[Note#Heading]]` and `![[Note#^block]]` with the text they show, as Obsidian does in reading view.
+//!
+//! @public Vault::expand_embeds
+
+// std imports
+use std::path::{Path, PathBuf};
+
+// first-party imports
+use super::Vault;
+use crate::file::mdfile::link::Link;
+use crate::prelude::*;
+
+impl Vault {
+    /// Get the body of a note with its embedded notes, sections and blocks replaced by their text.
+    ///
+    /// Embeds are expanded recursively. Embeds of other files (images, PDFs, ...), embeds that
+    /// do not resolve, and embeds that would include a note inside itself are left as they are.
+    ///
+    /// # Arguments
+    /// @param path: &Path - The note, relative to the vault root
+    /// @return Result<String> - The expanded body
+    ///
+    /// # Example
+    /// ```should_panic
+    /// use std::path::{Path, PathBuf};
+    ///
+    /// use obsidian_driver::file::vault::Vault;
+    ///
+    /// let vault = Vault::from_path(PathBuf::from("vault")).unwrap();
+    /// let body = vault.expand_embeds(Path::new("Lectures/Cells.md")).unwrap();
+    /// ```
+    pub fn expand_embeds(&self, path: &Path) -> Result<String> {
+        let body = self
+            .files
+            .get(path)
+            .and_then(|file| file.get_mdfile())
+            .ok_or(Error::PathNotFound(path.to_path_buf()))?
+            .get_body();
+        Ok(self.expand_text(path, body, &mut vec![path.to_path_buf()]))
+    }
+
+    /// Expand the embeds in text from the note at `source`; `stack` holds the notes being expanded.
+    ///
+    /// @private
+    fn expand_text(&self, source: &Path, text: &str, stack: &mut Vec<PathBuf>) -> String {
+        let mut expanded = text.to_string();
+        for link in Link::parse_all(text).into_iter().rev() {
+            if !link.embed {
+                continue;
+            }
+            let Some(target) = self.resolve_link(source, &link) else {
+                continue;
+            };
+            let Some(mdfile) = self.files.get(&target).and_then(|file| file.get_mdfile()) else {
+                continue;
+            };
+            if stack.contains(&target) {
+                continue;
+            }
+            let body = mdfile.get_body();
+            let embedded = if let Some(id) = link.block_id() {
+                mdfile.get_block(id).map(|block| &body[block.range])
+            } else if !link.heading_path().is_empty() {
+                self.resolve_section(source, &link).map(|(_, section)| &body[section.range])
+            } else {
+                Some(body.as_str())
+            };
+            let Some(embedded) = embedded else {
+                continue;
+            };
+            stack.push(target.clone());
+            let replacement = self.expand_text(&target, embedded.trim(), stack);
+            stack.pop();
+            expanded.replace_range(link.range, &replacement);
+        }
+        expanded
+    }
+}
+
+#[cfg(test)]
+mod embeds_tests {
+    use super::*;
+
+    #[test]
+    fn test_expand_embeds_recursively() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.md"), "# A\n\n![[b#Part]]\n\n![[c#^quote]] ![[a]] ![[image.png]]").unwrap();
+        std::fs::write(dir.path().join("b.md"), "# B\n## Part\nFrom b. ![[c]]\n## Other\nNot embedded.").unwrap();
+        std::fs::write(dir.path().join("c.md"), "Quoted. ^quote\n\nRest of c.").unwrap();
+        let vault = Vault::from_path(dir.path().to_path_buf()).unwrap();
+
+        let expanded = vault.expand_embeds(Path::new("a.md")).unwrap();
+        let expected = "# A\n\n## Part\nFrom b. Quoted. ^quote\n\nRest of c.\n\nQuoted. ![[a]] ![[image.png]]";
+        assert_eq!(expanded, expected);
+    }
+}
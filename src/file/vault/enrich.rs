@@ -0,0 +1,319 @@
+//! obsidian-driver::file::vault::enrich
+//!
+//! This module contains front matter enrichment: the cheap model fills the missing front matter fields of notes, such as a summary, keywords, course, and source, preferring values already used in the vault.
+//!
+//! @public EnrichField
+//!
+//! @public EnrichOptions
+//!
+//! @public Vault::enrich_frontmatter
+
+// std imports
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+// third-party imports
+use serde::{Deserialize, Serialize};
+use serde_yaml::{Mapping, Value};
+
+// first-party imports
+use super::frontmatter::FrontmatterUpdate;
+use super::query::Query;
+use super::Vault;
+use crate::ai::chunk::split_text;
+use crate::ai::prompt::{Context, Prompt, ResponseBudget};
+use crate::ai::structured::{parse_json_response, JSON_SYSTEM_PROMPT};
+use crate::prelude::*;
+
+const ENRICH_SYSTEM_PROMPT: &str = "You keep the front matter of the notes in a student's knowledge base complete and consistent.";
+const ENRICH_USER_PROMPT: &str = r#"Fill in the missing front matter fields of the note below:
+[fields]
+
+When a value already used in the vault fits, use it exactly, so notes stay consistent. Use null for a field the note gives no evidence for; do not guess.
+
+Reply with a JSON object with exactly the keys [keys].
+
+**Values used in the vault**
+[known]
+
+**Front matter**
+[yaml]
+
+**Note: [title]**
+[note]"#;
+// the note is cut to this many estimated tokens to keep the cheap model call small
+const NOTE_TOKENS: u32 = 3000;
+// how many of the most used values of a field are shown to the model
+const KNOWN_VALUES: usize = 20;
+// longer values, such as summaries, are not worth reusing and are not shown
+const KNOWN_VALUE_CHARS: usize = 60;
+
+/// A front matter field to fill, and what the model is told it holds.
+///
+/// @public
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EnrichField {
+    pub name: String,
+    // e.g. "a list of 3 to 7 key terms"
+    pub description: String,
+}
+
+impl EnrichField {
+    /// Create a new EnrichField.
+    ///
+    /// # Arguments
+    /// @param name: &str - The front matter key
+    /// @param description: &str - What the value is, including its shape if it is not a string
+    /// @return EnrichField
+    pub fn new(name: &str, description: &str) -> EnrichField {
+        EnrichField {
+            name: name.to_string(),
+            description: description.to_string(),
+        }
+    }
+}
+
+/// EnrichOptions struct
+///
+/// Options for `Vault::enrich_frontmatter`. By default `summary`, `keywords`, `course`, and
+/// `source` are filled.
+///
+/// # Example
+/// ```
+/// use obsidian_driver::file::vault::enrich::EnrichOptions;
+///
+/// let options = EnrichOptions::default()
+/// 	.without_field("source")
+/// 	.with_field("difficulty", "how hard the material is: easy, medium or hard")
+/// 	.with_dry_run(true);
+/// assert_eq!(options.fields.len(), 4);
+/// ```
+///
+/// @public
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct EnrichOptions {
+    pub fields: Vec<EnrichField>,
+    // only report the changes, without writing them
+    pub dry_run: bool,
+}
+
+impl Default for EnrichOptions {
+    fn default() -> Self {
+        EnrichOptions {
+            fields: vec![
+                EnrichField::new("summary", "one or two sentences on what the note covers"),
+                EnrichField::new("keywords", "a list of 3 to 7 key terms of the note"),
+                EnrichField::new("course", "the course the note belongs to, e.g. CPSC 351"),
+                EnrichField::new("source", "where the content comes from, e.g. a lecture, book, paper or website"),
+            ],
+            dry_run: false,
+        }
+    }
+}
+
+impl EnrichOptions {
+    /// Fill a field, replacing the description of a field of the same name.
+    ///
+    /// # Arguments
+    /// @param name: &str - The front matter key
+    /// @param description: &str
+    /// @return EnrichOptions
+    pub fn with_field(mut self, name: &str, description: &str) -> Self {
+        let field = EnrichField::new(name, description);
+        match self.fields.iter_mut().find(|other| other.name == name) {
+            Some(other) => *other = field,
+            None => self.fields.push(field),
+        }
+        self
+    }
+
+    /// Do not fill a field.
+    ///
+    /// # Arguments
+    /// @param name: &str - The front matter key
+    /// @return EnrichOptions
+    pub fn without_field(mut self, name: &str) -> Self {
+        self.fields.retain(|field| field.name != name);
+        self
+    }
+
+    /// Only propose the values; nothing is written.
+    ///
+    /// # Arguments
+    /// @param dry_run: bool
+    /// @return EnrichOptions
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+}
+
+impl Vault {
+    /// Ask the cheap model to fill the missing front matter fields of every note matching a query, and write the notes that changed.
+    ///
+    /// A field is missing if the key is absent, null, an empty string, or an empty list; fields
+    /// that are set are never changed. Notes missing none of the fields are not sent. The model
+    /// sees the note, its front matter, and the most used values of each field in the vault, and
+    /// fields it answers with null stay missing. The sources and conditions of the query select
+    /// the notes, its sort and limit are ignored, and notes whose front matter is not a mapping
+    /// are skipped. With `dry_run`, nothing is written and the report shows what would change.
+    ///
+    /// # Arguments
+    /// @param filter: &Query - e.g. `Query::new().from_folder("Lectures")`
+    /// @param options: &EnrichOptions
+    /// @return Result<Vec<FrontmatterUpdate>> - The notes that changed, sorted by path. Errors with `NoAIDriver` if no driver was added, and with `InvalidChatResponse` if a reply is not a JSON object.
+    ///
+    /// # Example
+    /// ```
+    /// use std::path::PathBuf;
+    ///
+    /// use obsidian_driver::ai::api::AIDriver;
+    /// use obsidian_driver::file::vault::Vault;
+    /// use obsidian_driver::file::vault::enrich::EnrichOptions;
+    /// use obsidian_driver::file::vault::query::Query;
+    ///
+    /// async fn enrich_frontmatter_example() {
+    /// 	let mut vault = Vault::from_path(PathBuf::from("vault")).unwrap();
+    /// 	vault.add_ai_driver(AIDriver::new_openai_from_config_path(PathBuf::from(".openai_config.json")).await.unwrap());
+    ///
+    /// 	let filter = Query::parse("FROM #lecture").unwrap();
+    /// 	for update in vault.enrich_frontmatter(&filter, &EnrichOptions::default().with_dry_run(true)).await.unwrap() {
+    /// 		println!("{}: {:?}", update.path.display(), update.changes);
+    /// 	}
+    /// }
+    /// ```
+    pub async fn enrich_frontmatter(&mut self, filter: &Query, options: &EnrichOptions) -> Result<Vec<FrontmatterUpdate>> {
+        let driver = self.aidriver.clone().ok_or(Error::NoAIDriver)?;
+        let mut paths: Vec<PathBuf> = self
+            .files
+            .iter()
+            .filter(|(path, file)| file.get_mdfile().is_some() && filter.matches(path, file))
+            .map(|(path, _)| path.clone())
+            .collect();
+        paths.sort();
+
+        let mut known: HashMap<&str, String> = HashMap::new();
+        for field in options.fields.iter() {
+            let mut values = self.properties.values(&field.name);
+            values.retain(|(value, _)| !value.trim().is_empty() && value.chars().count() <= KNOWN_VALUE_CHARS);
+            values.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+            values.truncate(KNOWN_VALUES);
+            if !values.is_empty() {
+                let values: Vec<String> = values.iter().map(|(value, count)| f!("{} ({})", value, count)).collect();
+                known.insert(&field.name, f!("- {}: {}", field.name, values.join(", ")));
+            }
+        }
+
+        let mut filled: HashMap<PathBuf, Mapping> = HashMap::new();
+        for path in paths {
+            let mdfile = self.files[&path].get_mdfile().expect("Filtered to markdown notes");
+            let yaml = match mdfile.get_yaml() {
+                Some(Value::Mapping(mapping)) => mapping.clone(),
+                Some(_) => continue,
+                None => Mapping::new(),
+            };
+            let missing: Vec<&EnrichField> = options.fields.iter().filter(|field| is_missing(yaml.get(field.name.as_str()))).collect();
+            if missing.is_empty() {
+                continue;
+            }
+
+            let fields: Vec<String> = missing.iter().map(|field| f!("- {}: {}", field.name, field.description)).collect();
+            let keys: Vec<String> = missing.iter().map(|field| f!("\"{}\"", field.name)).collect();
+            let known: Vec<&str> = missing.iter().filter_map(|field| known.get(field.name.as_str()).map(String::as_str)).collect();
+            let known = if known.is_empty() { "None".to_string() } else { known.join("\n") };
+            let yaml_text = if yaml.is_empty() { "None".to_string() } else { serde_yaml::to_string(&yaml).unwrap_or_default().trim().to_string() };
+            let body = mdfile.strip_comments().get_body().trim().to_string();
+            let title = path.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default();
+            let mut context = Context::default();
+            context.insert("fields", &fields.join("\n"));
+            context.insert("keys", &keys.join(", "));
+            context.insert("known", &known);
+            context.insert("yaml", &yaml_text);
+            context.insert("title", &title);
+            context.insert("note", split_text(&body, NOTE_TOKENS).first().map(String::as_str).unwrap_or_default());
+            let prompt = Prompt::new(ENRICH_SYSTEM_PROMPT, ENRICH_USER_PROMPT, ResponseBudget::Tokens(500))
+                .stack_system_prompt(JSON_SYSTEM_PROMPT)
+                .substitute(&context)?;
+            let response = driver.chat_cheap(prompt).await?;
+            let reply: serde_json::Map<String, serde_json::Value> = parse_json_response(&response)?;
+
+            let mut values = Mapping::new();
+            for field in missing {
+                let Some(value) = reply.get(&field.name) else {
+                    continue;
+                };
+                let Ok(value) = serde_yaml::to_value(value) else {
+                    continue;
+                };
+                if !is_missing(Some(&value)) {
+                    values.insert(Value::from(field.name.as_str()), value);
+                }
+            }
+            if !values.is_empty() {
+                filled.insert(path, values);
+            }
+        }
+
+        self.update_frontmatter(
+            filter,
+            |path, yaml| {
+                for (key, value) in filled.get(path).into_iter().flatten() {
+                    yaml.insert(key.clone(), value.clone());
+                }
+            },
+            options.dry_run,
+        )
+    }
+}
+
+/// Check whether a front matter value counts as missing: absent, null, an empty string, or an empty list.
+///
+/// @private
+fn is_missing(value: Option<&Value>) -> bool {
+    match value {
+        None | Some(Value::Null) => true,
+        Some(Value::String(string)) => string.trim().is_empty(),
+        Some(Value::Sequence(items)) => items.is_empty(),
+        Some(_) => false,
+    }
+}
+
+#[cfg(test)]
+mod enrich_tests {
+    use super::*;
+    use crate::ai::api::mock::MockDriver;
+    use crate::ai::api::AIDriver;
+
+    #[tokio::test]
+    async fn test_enrich_frontmatter_fills_missing_fields() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("Lectures")).unwrap();
+        std::fs::write(dir.path().join("Lectures/mitosis.md"), "---\ncourse: ''\nsummary: Cells divide.\n---\n# Mitosis").unwrap();
+        std::fs::write(dir.path().join("Lectures/done.md"), "---\ncourse: BIOL 241\nsummary: Done.\n---\n# Done").unwrap();
+        std::fs::write(dir.path().join("other.md"), "# Other").unwrap();
+        let mut vault = Vault::from_path(dir.path().to_path_buf()).unwrap();
+        let filter = Query::new().from_folder("Lectures");
+        let options = EnrichOptions::default().without_field("keywords").without_field("source");
+        assert!(matches!(vault.enrich_frontmatter(&filter, &options).await, Err(Error::NoAIDriver)));
+
+        let response = r#"{"course": "BIOL 241", "summary": "Ignored, it is set.", "source": "Lecture 3"}"#;
+        let mock = MockDriver::new().with_default_response(response);
+        vault.add_ai_driver(AIDriver::new_mock(mock.clone()));
+
+        let preview = vault.enrich_frontmatter(&filter, &options.clone().with_dry_run(true)).await.unwrap();
+        assert_eq!(mock.prompts().len(), 1);
+        let prompt = &mock.prompts()[0].user_prompt;
+        assert!(prompt.starts_with("Fill in the missing front matter fields of the note below:\n- course: the course"));
+        assert!(prompt.contains("exactly the keys \"course\".\n"));
+        assert!(prompt.contains("**Values used in the vault**\n- course: BIOL 241 (1)\n\n"));
+        assert_eq!(preview.len(), 1);
+        assert_eq!(preview[0].path, PathBuf::from("Lectures/mitosis.md"));
+        assert_eq!(preview[0].changes[0].after, Some("BIOL 241".into()));
+        assert!(vault.dirty_files().is_empty());
+
+        let updates = vault.enrich_frontmatter(&filter, &options).await.unwrap();
+        assert_eq!(updates, preview);
+        let written = std::fs::read_to_string(dir.path().join("Lectures/mitosis.md")).unwrap();
+        assert!(written.starts_with("---\ncourse: BIOL 241\nsummary: Cells divide.\n---\n"));
+    }
+}
@@ -0,0 +1,125 @@
+//! obsidian-driver::file::vault::events
+//!
+//! This module contains the event bus of the Vault: subscribers receive an event for every change made through the Vault's methods, so applications can react to changes without polling the file map.
+//!
+//! @public VaultEvent
+//!
+//! @public Vault::subscribe
+//!
+//! @crate EventBus
+
+// std imports
+use std::path::PathBuf;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+
+// first-party imports
+use super::links::BrokenLink;
+use super::Vault;
+
+/// A change to the Vault. Paths are relative to the vault root.
+///
+/// @public
+#[derive(Clone, Debug, PartialEq)]
+pub enum VaultEvent {
+    /// A file was added to the Vault, by `add_file`, a refresh from disk, or a merge.
+    FileAdded(PathBuf),
+    /// The contents of a file changed, by a link or text edit or a refresh from disk.
+    FileModified(PathBuf),
+    /// A file was removed from the Vault: moved to the trash by `remove_file`, or forgotten by `forget_missing_files` after it was deleted on disk.
+    FileRemoved(PathBuf),
+    /// A file was moved.
+    FileMoved { from: PathBuf, to: PathBuf },
+    /// A file received a new embedding.
+    EmbeddingUpdated(PathBuf),
+    /// A file was written to disk.
+    FileWritten(PathBuf),
+    /// A link no longer resolves, because the file it pointed at was removed.
+    LinkBroken(BrokenLink),
+}
+
+/// The subscribers of a Vault. Clones of a Vault share their subscribers.
+///
+/// @crate
+#[derive(Clone, Debug, Default)]
+pub(crate) struct EventBus {
+    subscribers: Arc<Mutex<Vec<Sender<VaultEvent>>>>,
+}
+
+impl EventBus {
+    /// Add a subscriber.
+    ///
+    /// @crate
+    pub(crate) fn subscribe(&self) -> Receiver<VaultEvent> {
+        let (sender, receiver) = channel();
+        self.subscribers.lock().expect("Event bus lock poisoned").push(sender);
+        receiver
+    }
+
+    /// Send an event to every subscriber, dropping the subscribers whose receiver was dropped.
+    ///
+    /// @crate
+    pub(crate) fn emit(&self, event: VaultEvent) {
+        let mut subscribers = self.subscribers.lock().expect("Event bus lock poisoned");
+        subscribers.retain(|subscriber| subscriber.send(event.clone()).is_ok());
+    }
+}
+
+impl Vault {
+    /// Subscribe to the changes made to the Vault from now on.
+    ///
+    /// Events are queued until they are received, and stop being sent once the receiver is
+    /// dropped. Edits made directly through `get_file_mut` are not reported until the file is
+    /// written.
+    ///
+    /// # Arguments
+    /// @return Receiver<VaultEvent>
+    ///
+    /// # Example
+    /// ```should_panic
+    /// use std::path::PathBuf;
+    ///
+    /// use obsidian_driver::file::vault::Vault;
+    /// use obsidian_driver::file::vault::events::VaultEvent;
+    ///
+    /// let mut vault = Vault::from_path(PathBuf::from("vault")).unwrap();
+    /// let events = vault.subscribe();
+    /// vault.move_file(&PathBuf::from("note.md"), &PathBuf::from("archive/note.md")).unwrap();
+    /// for event in events.try_iter() {
+    /// 	if let VaultEvent::FileModified(path) = event {
+    /// 		println!("links rewritten in {}", path.display());
+    /// 	}
+    /// }
+    /// ```
+    pub fn subscribe(&self) -> Receiver<VaultEvent> {
+        self.events.subscribe()
+    }
+}
+
+#[cfg(test)]
+mod events_tests {
+    use super::*;
+    use crate::file::vault::links::BrokenLinkPolicy;
+    use std::path::Path;
+
+    #[test]
+    fn test_events_of_move_and_remove() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.md"), "# A").unwrap();
+        std::fs::write(dir.path().join("b.md"), "See [[a]].").unwrap();
+        let mut vault = Vault::from_path(dir.path().to_path_buf()).unwrap();
+        let events = vault.subscribe();
+        let dropped = vault.subscribe();
+        drop(dropped);
+
+        vault.move_file(&PathBuf::from("a.md"), &PathBuf::from("c.md")).unwrap();
+        vault.remove_file(&PathBuf::from("c.md"), BrokenLinkPolicy::Report).unwrap();
+        let received: Vec<VaultEvent> = events.try_iter().collect();
+        assert_eq!(received[0], VaultEvent::FileMoved { from: PathBuf::from("a.md"), to: PathBuf::from("c.md") });
+        assert_eq!(received[1], VaultEvent::FileModified(PathBuf::from("b.md")));
+        assert_eq!(received[2], VaultEvent::FileWritten(PathBuf::from("b.md")));
+        assert_eq!(received[3], VaultEvent::FileRemoved(PathBuf::from("c.md")));
+        assert!(matches!(&received[4], VaultEvent::LinkBroken(broken) if broken.source == Path::new("b.md")));
+        assert_eq!(received.len(), 5);
+    }
+}
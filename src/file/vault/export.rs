@@ -0,0 +1,390 @@
+//! obsidian-driver::file::vault::export
+//!
+//! This module contains the vault exporters: the static site export, where every note of the vault is rendered to an HTML page with attachments, backlinks and an index, and the PDF export of a single note.
+//!
+//! @public SiteOptions
+//!
+//! @public SiteReport
+//!
+//! @public Vault::export_site
+//!
+//! @public Vault::export_pdf
+
+// std imports
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+// third-party imports
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use serde::{Deserialize, Serialize};
+
+// first-party imports
+use super::links::{relative_path, resolve_link_in, to_link_path};
+use super::Vault;
+use crate::export::html::{self, HtmlRenderer};
+use crate::export::pdf::{PdfBackend, PdfOptions};
+use crate::export::typst::TypstRenderer;
+use crate::file::mdfile::link::Link;
+use crate::file::mdfile::MDFile;
+use crate::prelude::*;
+
+/// The page listing every exported note.
+const INDEX_PAGE: &str = "index.html";
+
+/// SiteOptions struct
+///
+/// Options for `Vault::export_site`. By default every file is exported, with backlinks and an index page.
+///
+/// # Example
+/// ```
+/// use obsidian_driver::file::vault::export::SiteOptions;
+///
+/// let options = SiteOptions::default()
+/// 	.ignore("Templates/**")
+/// 	.ignore("**/*.canvas")
+/// 	.with_backlinks(false);
+/// ```
+///
+/// @public
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SiteOptions {
+    // glob patterns, matched against paths relative to the vault root
+    pub ignore: Vec<String>,
+    pub backlinks: bool,
+    pub index: bool,
+}
+
+impl Default for SiteOptions {
+    fn default() -> Self {
+        SiteOptions {
+            ignore: Vec::new(),
+            backlinks: true,
+            index: true,
+        }
+    }
+}
+
+impl SiteOptions {
+    /// Leave out the files matching a glob pattern, e.g. `Templates/**` or `**/private/*`. Links to them are rendered as unresolved.
+    ///
+    /// # Arguments
+    /// @param pattern: &str - Matched against paths relative to the vault root
+    /// @return SiteOptions
+    pub fn ignore(mut self, pattern: &str) -> Self {
+        self.ignore.push(pattern.to_string());
+        self
+    }
+
+    /// Add a list of the notes linking to each page at the bottom of the page.
+    ///
+    /// # Arguments
+    /// @param backlinks: bool
+    /// @return SiteOptions
+    pub fn with_backlinks(mut self, backlinks: bool) -> Self {
+        self.backlinks = backlinks;
+        self
+    }
+
+    /// Generate an `index.html` listing every page. A note at `index.md` takes its place.
+    ///
+    /// # Arguments
+    /// @param index: bool
+    /// @return SiteOptions
+    pub fn with_index(mut self, index: bool) -> Self {
+        self.index = index;
+        self
+    }
+
+    /// Compile the ignore patterns.
+    ///
+    /// @private
+    fn ignore_set(&self) -> Result<GlobSet> {
+        let mut builder = GlobSetBuilder::new();
+        for pattern in &self.ignore {
+            builder.add(Glob::new(pattern)?);
+        }
+        Ok(builder.build()?)
+    }
+}
+
+/// SiteReport struct
+///
+/// The files written by `Vault::export_site`, relative to the output folder and sorted.
+///
+/// @public
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct SiteReport {
+    pub pages: Vec<PathBuf>,
+    pub attachments: Vec<PathBuf>,
+}
+
+impl Vault {
+    /// Export the vault as a static website.
+    ///
+    /// Each note is rendered to a page at the same path with an `.html` extension, and other
+    /// files are copied as they are. Links between exported files become relative hrefs, so the
+    /// site works from any folder or server path; links to missing or ignored files are rendered
+    /// as unresolved.
+    ///
+    /// # Arguments
+    /// @param out_dir: &Path - Created if it does not exist; files already in it are overwritten
+    /// @param options: &SiteOptions
+    /// @return Result<SiteReport>
+    ///
+    /// # Example
+    /// ```should_panic
+    /// use std::path::{Path, PathBuf};
+    ///
+    /// use obsidian_driver::file::vault::Vault;
+    /// use obsidian_driver::file::vault::export::SiteOptions;
+    ///
+    /// let vault = Vault::from_path(PathBuf::from("vault")).unwrap();
+    /// let options = SiteOptions::default().ignore("Templates/**");
+    /// let report = vault.export_site(Path::new("site"), &options).unwrap();
+    /// println!("{} pages", report.pages.len());
+    /// ```
+    pub fn export_site(&self, out_dir: &Path, options: &SiteOptions) -> Result<SiteReport> {
+        let ignore = options.ignore_set()?;
+        let mut included: Vec<&PathBuf> = self.files.keys().filter(|path| !ignore.is_match(path)).collect();
+        included.sort();
+        let exported: HashSet<&PathBuf> = included.iter().copied().collect();
+        let attachments: Vec<PathBuf> = self.attachments()?.into_iter().filter(|path| !ignore.is_match(path)).collect();
+        let attachment_set: HashSet<&PathBuf> = attachments.iter().collect();
+
+        // the exported notes linking to each exported file
+        let mut backlinks: HashMap<PathBuf, BTreeSet<&PathBuf>> = HashMap::new();
+        for source in included.iter().copied() {
+            let Some(mdfile) = self.files[source].get_mdfile() else {
+                continue;
+            };
+            for link in mdfile.get_links() {
+                if let Some(target) = self.resolve_link(source, &link).filter(|target| target != source) {
+                    backlinks.entry(target).or_default().insert(source);
+                }
+            }
+        }
+
+        let mut report = SiteReport::default();
+        let copied = included
+            .iter()
+            .copied()
+            .filter(|path| self.files[*path].get_mdfile().is_none())
+            .chain(attachments.iter());
+        for path in copied {
+            let out_path = out_dir.join(path);
+            if let Some(parent) = out_path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::copy(self.vault_root.join(path), &out_path)?;
+            report.attachments.push(path.clone());
+        }
+        report.attachments.sort();
+
+        for path in included.iter().copied() {
+            let Some(mdfile) = self.files[path].get_mdfile() else {
+                continue;
+            };
+            let renderer = HtmlRenderer::new().with_link_resolver(|link| {
+                let target = self
+                    .resolve_link(path, link)
+                    .filter(|target| exported.contains(target))
+                    .or_else(|| {
+                        resolve_link_in(path, link, |other| attachment_set.contains(&other.to_path_buf()), attachments.iter())
+                    })?;
+                Some(f!("{}{}", page_href(path, &target), html::fragment(link)))
+            });
+            let mut body = renderer.render(mdfile);
+            let sources = backlinks.get(path).filter(|sources| options.backlinks && !sources.is_empty());
+            if let Some(sources) = sources {
+                body.push_str("<section class=\"backlinks\">\n<h2>Backlinks</h2>\n<ul>\n");
+                for source in sources {
+                    body.push_str(&f!(
+                        "<li><a class=\"internal-link\" href=\"{}\">{}</a></li>\n",
+                        html::escape_html(&page_href(path, source)),
+                        html::escape_html(&note_title(source))
+                    ));
+                }
+                body.push_str("</ul>\n</section>\n");
+            }
+
+            let page_path = path.with_extension("html");
+            if let Some(parent) = out_dir.join(&page_path).parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(out_dir.join(&page_path), html::page(&note_title(path), mdfile.get_yaml(), &body))?;
+            report.pages.push(page_path);
+        }
+
+        let index_path = PathBuf::from(INDEX_PAGE);
+        if options.index && !report.pages.contains(&index_path) {
+            let mut body = String::from("<h1>Index</h1>\n<ul>\n");
+            for page in report.pages.iter() {
+                body.push_str(&f!(
+                    "<li><a class=\"internal-link\" href=\"{}\">{}</a></li>\n",
+                    html::escape_html(&html::encode_href(&to_link_path(page))),
+                    html::escape_html(&note_title(page))
+                ));
+            }
+            body.push_str("</ul>\n");
+            std::fs::create_dir_all(out_dir)?;
+            std::fs::write(out_dir.join(&index_path), html::page("Index", None, &body))?;
+            report.pages.push(index_path);
+        }
+        Ok(report)
+    }
+
+    /// Export a note to PDF, with its embeds expanded.
+    ///
+    /// The note is rendered to a Typst document or an HTML page, written next to `out` with a
+    /// `.typ` or `.html` extension, and converted by the backend program. Embedded images point at
+    /// their files in the vault; links to other notes are rendered as text.
+    ///
+    /// # Arguments
+    /// @param path: &Path - The note, relative to the vault root
+    /// @param out: &Path - The PDF to write
+    /// @param options: &PdfOptions - The backend, and whether to keep the intermediate file
+    /// @return Result<()> - `Error::ExportFailed` if the backend cannot be run or fails
+    ///
+    /// # Example
+    /// ```should_panic
+    /// use std::path::{Path, PathBuf};
+    ///
+    /// use obsidian_driver::export::pdf::{PdfBackend, PdfOptions};
+    /// use obsidian_driver::file::vault::Vault;
+    ///
+    /// let vault = Vault::from_path(PathBuf::from("vault")).unwrap();
+    /// let options = PdfOptions::default().with_backend(PdfBackend::chromium());
+    /// vault.export_pdf(Path::new("Lectures/Cells.md"), Path::new("Cells.pdf"), &options).unwrap();
+    /// ```
+    pub fn export_pdf(&self, path: &Path, out: &Path, options: &PdfOptions) -> Result<()> {
+        let mdfile = self
+            .files
+            .get(path)
+            .and_then(|file| file.get_mdfile())
+            .ok_or(Error::PathNotFound(path.to_path_buf()))?;
+        let expanded = MDFile::new(mdfile.get_yaml().cloned(), self.expand_embeds(path)?);
+        let attachments = self.attachments()?;
+        let attachment_path = |link: &Link| {
+            resolve_link_in(path, link, |other| attachments.contains(other), attachments.iter())
+                .map(|target| self.vault_root.join(target))
+        };
+
+        let title = note_title(path);
+        let source = match options.backend {
+            PdfBackend::Typst { .. } => TypstRenderer::new()
+                .with_link_resolver(|link| attachment_path(link).map(|target| target.to_string_lossy().into_owned()))
+                .render_document(&expanded, &title),
+            PdfBackend::Html { .. } => {
+                let renderer = HtmlRenderer::new().with_link_resolver(|link| {
+                    attachment_path(link).map(|target| html::encode_href(&target.to_string_lossy()))
+                });
+                let body = renderer.render(&expanded);
+                html::page_with_head(&title, expanded.get_yaml(), options.backend.extra_head(), &body)
+            }
+        };
+
+        if let Some(parent) = out.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+        let source_path = out.with_extension(options.backend.source_extension());
+        std::fs::write(&source_path, source)?;
+        let result = options.backend.run(&source_path, out);
+        if !options.keep_source {
+            std::fs::remove_file(&source_path)?;
+        }
+        result
+    }
+}
+
+/// The href from the page of `source` to the page (or copy) of `target`, both relative to the vault root.
+///
+/// @private
+fn page_href(source: &Path, target: &Path) -> String {
+    let target = match target.extension() {
+        Some(extension) if extension == "md" => target.with_extension("html"),
+        _ => target.to_path_buf(),
+    };
+    let folder = source.parent().unwrap_or(Path::new(""));
+    html::encode_href(&to_link_path(&relative_path(folder, &target)))
+}
+
+/// The title of a note: its file name without the extension.
+///
+/// @private
+fn note_title(path: &Path) -> String {
+    path.file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod export_tests {
+    use super::*;
+
+    #[test]
+    fn test_export_site_links_backlinks_and_ignores() {
+        let dir = tempfile::tempdir().unwrap();
+        let out = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("Lectures")).unwrap();
+        std::fs::create_dir_all(dir.path().join("Templates")).unwrap();
+        std::fs::write(dir.path().join("Home.md"), "See [[Cells#Division]] and [[Lecture]].").unwrap();
+        std::fs::write(dir.path().join("Lectures/Cells.md"), "## Division\n\n![[cell.png]]").unwrap();
+        std::fs::write(dir.path().join("Templates/Lecture.md"), "# {{title}}").unwrap();
+        std::fs::write(dir.path().join("Lectures/cell.png"), [0u8, 1, 2]).unwrap();
+        // images are not loaded into the vault, only copied
+        let vault = Vault::from_path(dir.path().to_path_buf()).unwrap();
+        assert!(vault.get_file(&PathBuf::from("Lectures/cell.png")).is_none());
+
+        let options = SiteOptions::default().ignore("Templates/**");
+        let report = vault.export_site(out.path(), &options).unwrap();
+        let pages = vec![
+            PathBuf::from("Home.html"),
+            PathBuf::from("Lectures/Cells.html"),
+            PathBuf::from("index.html"),
+        ];
+        assert_eq!(report.pages, pages);
+        assert_eq!(report.attachments, vec![PathBuf::from("Lectures/cell.png")]);
+
+        let home = std::fs::read_to_string(out.path().join("Home.html")).unwrap();
+        assert!(home.contains("href=\"Lectures/Cells.html#division\""));
+        assert!(home.contains("<a class=\"internal-link is-unresolved\">Lecture</a>"));
+        let cells = std::fs::read_to_string(out.path().join("Lectures/Cells.html")).unwrap();
+        assert!(cells.contains("<img src=\"cell.png\""));
+        assert!(cells.contains("<li><a class=\"internal-link\" href=\"../Home.html\">Home</a></li>"));
+        assert!(out.path().join("Lectures/cell.png").exists());
+        assert!(!out.path().join("Templates").exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_export_pdf_runs_backend_on_expanded_note() {
+        let dir = tempfile::tempdir().unwrap();
+        let out = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("Cells.md"), "# Cells\n\n![[Division]]\n\n![[cell.png]]").unwrap();
+        std::fs::write(dir.path().join("Division.md"), "Mitosis has $4$ phases.").unwrap();
+        std::fs::write(dir.path().join("cell.png"), [0u8, 1, 2]).unwrap();
+        let vault = Vault::from_path(dir.path().to_path_buf()).unwrap();
+
+        // copying the page stands in for an HTML-to-PDF program
+        let backend = PdfBackend::Html {
+            program: "cp".to_string(),
+            args: vec!["{input}".to_string(), "{output}".to_string()],
+        };
+        let pdf = out.path().join("Cells.pdf");
+        vault.export_pdf(Path::new("Cells.md"), &pdf, &PdfOptions::default().with_backend(backend)).unwrap();
+        let page = std::fs::read_to_string(&pdf).unwrap();
+        assert!(page.contains("<p>Mitosis has <span class=\"math math-inline\">4</span> phases.</p>"));
+        assert!(page.contains(&f!("<img src=\"{}\"", dir.path().join("cell.png").display())));
+        assert!(page.contains("katex.min.js"));
+        assert!(!out.path().join("Cells.html").exists());
+
+        let failing = PdfBackend::Html {
+            program: "false".to_string(),
+            args: Vec::new(),
+        };
+        let options = PdfOptions::default().with_backend(failing).with_keep_source(true);
+        let result = vault.export_pdf(Path::new("Cells.md"), &pdf, &options);
+        assert!(matches!(result, Err(Error::ExportFailed(_))));
+        assert!(out.path().join("Cells.html").exists());
+    }
+}
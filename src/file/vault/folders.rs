@@ -0,0 +1,540 @@
+//! obsidian-driver::file::vault::folders
+//!
+//! This module contains the folder APIs of the Vault: listing the notes and subfolders of a folder, and folder notes, a note that stands for its folder, named like the folder (`Biology/Biology.md`) or `index.md`. The front matter of a folder note holds metadata for the whole folder, and its body can list the contents of the folder in a generated section.
+//!
+//! @public FolderListing
+//!
+//! @public Vault::list_folder
+//!
+//! @public Vault::subtree
+//!
+//! @public Vault::create_folder
+//!
+//! @public Vault::rename_folder
+//!
+//! @public Vault::move_folder
+//!
+//! @public FOLDER_CONTENTS_START
+//!
+//! @public FOLDER_CONTENTS_END
+//!
+//! @public Vault::folder_note
+//!
+//! @public Vault::is_folder_note
+//!
+//! @public Vault::folder_metadata
+//!
+//! @public Vault::folder_property
+//!
+//! @public Vault::create_folder_note
+//!
+//! @public Vault::update_folder_contents
+
+// std imports
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+// third-party imports
+use serde_yaml::Value;
+
+// first-party imports
+use super::events::VaultEvent;
+use super::Vault;
+use crate::prelude::*;
+
+/// The comment that starts the generated list of the contents of a folder.
+///
+/// @public
+pub const FOLDER_CONTENTS_START: &str = "%% begin folder contents %%";
+
+/// The comment that ends the generated list of the contents of a folder.
+///
+/// @public
+pub const FOLDER_CONTENTS_END: &str = "%% end folder contents %%";
+
+/// The name of the folder note that does not share the name of its folder.
+const INDEX_NOTE: &str = "index.md";
+
+/// FolderListing struct
+///
+/// The notes and subfolders in a folder of the vault, sorted, with paths relative to the vault root.
+///
+/// @public
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FolderListing {
+    pub notes: Vec<PathBuf>,
+    pub folders: Vec<PathBuf>,
+}
+
+impl Vault {
+    /// List the notes and subfolders directly in a folder.
+    ///
+    /// Folders are found from the files of the Vault, so a folder without files is not listed.
+    ///
+    /// # Arguments
+    /// @param folder: &Path - Relative to the vault root; empty for the vault root
+    /// @return FolderListing - Empty if the folder is not in the vault
+    ///
+    /// # Example
+    /// ```should_panic
+    /// use std::path::{Path, PathBuf};
+    ///
+    /// use obsidian_driver::file::vault::Vault;
+    ///
+    /// let vault = Vault::from_path(PathBuf::from("vault")).unwrap();
+    /// let listing = vault.list_folder(Path::new("Courses"));
+    /// assert!(listing.folders.contains(&PathBuf::from("Courses/Biology")));
+    /// ```
+    pub fn list_folder(&self, folder: &Path) -> FolderListing {
+        self.list(folder, false)
+    }
+
+    /// List the notes and folders anywhere below a folder, e.g. to embed only the notes of one folder.
+    ///
+    /// # Arguments
+    /// @param folder: &Path - Relative to the vault root; empty for the vault root
+    /// @return FolderListing - Empty if the folder is not in the vault
+    ///
+    /// # Example
+    /// ```should_panic
+    /// use std::path::{Path, PathBuf};
+    ///
+    /// use obsidian_driver::file::vault::Vault;
+    ///
+    /// let vault = Vault::from_path(PathBuf::from("vault")).unwrap();
+    /// let notes = vault.subtree(Path::new("Courses")).notes;
+    /// assert!(notes.contains(&PathBuf::from("Courses/Biology/Lecture 1.md")));
+    /// ```
+    pub fn subtree(&self, folder: &Path) -> FolderListing {
+        self.list(folder, true)
+    }
+
+    /// Create a folder in the vault, and its parents as needed.
+    ///
+    /// # Arguments
+    /// @param folder: &Path - Relative to the vault root
+    /// @return Result<()> - Errors with `PathAlreadyExists` if the path exists
+    pub fn create_folder(&self, folder: &Path) -> Result<()> {
+        let abs_folder = self.vault_root.join(folder);
+        if abs_folder.exists() {
+            return Err(Error::PathAlreadyExists(folder.to_path_buf()));
+        }
+        std::fs::create_dir_all(abs_folder)?;
+        Ok(())
+    }
+
+    /// Rename a folder, keeping it in the same parent folder. See `Vault::move_folder`.
+    ///
+    /// # Arguments
+    /// @param folder: &Path - Relative to the vault root
+    /// @param name: &str - The new name of the folder
+    /// @return Result<Vec<PathBuf>> - The notes whose links were rewritten
+    ///
+    /// # Example
+    /// ```should_panic
+    /// use std::path::{Path, PathBuf};
+    ///
+    /// use obsidian_driver::file::vault::Vault;
+    ///
+    /// let mut vault = Vault::from_path(PathBuf::from("vault")).unwrap();
+    /// vault.rename_folder(Path::new("Courses/Bio"), "Biology").unwrap();
+    /// assert!(vault.get_file(&PathBuf::from("Courses/Biology/Lecture 1.md")).is_some());
+    /// ```
+    pub fn rename_folder(&mut self, folder: &Path, name: &str) -> Result<Vec<PathBuf>> {
+        self.move_folder(folder, &folder.with_file_name(name))
+    }
+
+    /// Move a folder and everything in it to a new path, on disk and in memory.
+    ///
+    /// Every file of the folder is moved as by `Vault::move_file`, so it keeps its embedding and links to and
+    /// from it keep resolving. A folder note named like the folder is renamed with it. Files the Vault
+    /// does not load, like attachments and hidden files, are moved as well, links to attachments are
+    /// rewritten like links to notes, and the old folder is removed.
+    ///
+    /// # Arguments
+    /// @param from: &Path - Relative to the vault root
+    /// @param to: &Path - Relative to the vault root; its parents are created as needed
+    /// @return Result<Vec<PathBuf>> - The notes whose links were rewritten, at their new paths. Errors with `PathNotFound`
+    /// if `from` is not a folder, `PathAlreadyExists` if `to` exists, `FolderMovedIntoItself` if `to` is inside `from`, and
+    /// `PathNotInVaultRoot` if `to` is absolute or has `..` components
+    ///
+    /// # Example
+    /// ```should_panic
+    /// use std::path::{Path, PathBuf};
+    ///
+    /// use obsidian_driver::file::vault::Vault;
+    ///
+    /// let mut vault = Vault::from_path(PathBuf::from("vault")).unwrap();
+    /// let rewritten = vault.move_folder(Path::new("Biology"), Path::new("Archive/2024/Biology")).unwrap();
+    /// ```
+    pub fn move_folder(&mut self, from: &Path, to: &Path) -> Result<Vec<PathBuf>> {
+        let abs_from = self.vault_root.join(from);
+        if from.as_os_str().is_empty() || !abs_from.is_dir() {
+            return Err(Error::PathNotFound(from.to_path_buf()));
+        }
+        if !super::is_inside_vault(to) {
+            return Err(Error::PathNotInVaultRoot(to.to_path_buf(), self.vault_root.clone()));
+        }
+        if to.starts_with(from) {
+            return Err(Error::FolderMovedIntoItself(from.to_path_buf()));
+        }
+        let abs_to = self.vault_root.join(to);
+        if abs_to.exists() {
+            return Err(Error::PathAlreadyExists(to.to_path_buf()));
+        }
+
+        let renamed_note = match (from.file_name(), to.file_name()) {
+            (Some(old), Some(new)) if old != new => self
+                .folder_note(from)
+                .filter(|note| note.file_stem() == Some(old))
+                .map(|note| (note, to.join(f!("{}.md", new.to_string_lossy())))),
+            _ => None,
+        };
+        let destination = |path: &Path| match &renamed_note {
+            Some((note, renamed)) if note == path => renamed.clone(),
+            _ => to.join(path.strip_prefix(from).expect("path is in the folder")),
+        };
+        let moved = |path: &Path| match path.starts_with(from) {
+            true => destination(path),
+            false => path.to_path_buf(),
+        };
+        let mut files: Vec<PathBuf> = self.files.keys().filter(|path| path.starts_with(from)).cloned().collect();
+        files.sort();
+
+        // links are resolved before anything moves, and rewritten once everything has moved
+        let attachments = self.attachments()?;
+        let mut links = Vec::new();
+        for (source, file) in self.files.iter() {
+            let Some(mdfile) = file.get_mdfile() else {
+                continue;
+            };
+            for link in mdfile.get_links() {
+                if let Some(target) = self.resolve_link_among(source, &link, &attachments) {
+                    links.push((source.clone(), link, target));
+                }
+            }
+        }
+
+        std::fs::create_dir_all(&abs_to)?;
+        for path in &files {
+            self.relocate_file(path, &destination(path))?;
+        }
+        for entry in walkdir::WalkDir::new(&abs_from).min_depth(1).contents_first(true) {
+            let entry = entry?;
+            let target = abs_to.join(entry.path().strip_prefix(&abs_from).expect("entry is in the folder"));
+            match entry.file_type().is_dir() {
+                true => {
+                    std::fs::create_dir_all(&target)?;
+                    std::fs::remove_dir(entry.path())?;
+                }
+                false => {
+                    std::fs::create_dir_all(target.parent().expect("entry is in the folder"))?;
+                    std::fs::rename(entry.path(), &target)?;
+                }
+            }
+        }
+        std::fs::remove_dir(&abs_from)?;
+
+        let attachments = self.attachments()?;
+        let mut edits = Vec::new();
+        for (source, link, target) in links {
+            let (source, target) = (moved(&source), moved(&target));
+            if self.resolve_link_among(&source, &link, &attachments).as_ref() != Some(&target) {
+                let text = self.link_to(&source, &target, &link);
+                edits.push((source, link.range, text));
+            }
+        }
+        let rewritten: BTreeSet<PathBuf> = edits.iter().map(|(source, ..)| source.clone()).collect();
+        self.edit_bodies(edits)?;
+        Ok(rewritten.into_iter().collect())
+    }
+
+    /// List a folder, directly or recursively.
+    ///
+    /// @private
+    fn list(&self, folder: &Path, recursive: bool) -> FolderListing {
+        let mut notes = BTreeSet::new();
+        let mut folders = BTreeSet::new();
+        for (path, file) in self.files.iter() {
+            let Ok(rest) = path.strip_prefix(folder) else {
+                continue;
+            };
+            let components: Vec<_> = rest.components().collect();
+            let folder_depth = match recursive {
+                true => components.len().saturating_sub(1),
+                false => components.len().saturating_sub(1).min(1),
+            };
+            let mut current = folder.to_path_buf();
+            for component in &components[..folder_depth] {
+                current.push(component);
+                folders.insert(current.clone());
+            }
+            if (recursive || components.len() == 1) && file.get_mdfile().is_some() {
+                notes.insert(path.clone());
+            }
+        }
+        FolderListing {
+            notes: notes.into_iter().collect(),
+            folders: folders.into_iter().collect(),
+        }
+    }
+
+    /// Get the folder note of a folder: the note named like the folder, otherwise its `index.md`.
+    ///
+    /// # Arguments
+    /// @param folder: &Path - Relative to the vault root; empty for the vault root, whose folder note is `index.md`
+    /// @return Option<PathBuf> - The folder note, relative to the vault root
+    ///
+    /// # Example
+    /// ```should_panic
+    /// use std::path::{Path, PathBuf};
+    ///
+    /// use obsidian_driver::file::vault::Vault;
+    ///
+    /// let vault = Vault::from_path(PathBuf::from("vault")).unwrap();
+    /// assert_eq!(vault.folder_note(Path::new("Biology")), Some(PathBuf::from("Biology/Biology.md")));
+    /// ```
+    pub fn folder_note(&self, folder: &Path) -> Option<PathBuf> {
+        let named = folder.file_name().map(|name| folder.join(f!("{}.md", name.to_string_lossy())));
+        named
+            .into_iter()
+            .chain(std::iter::once(folder.join(INDEX_NOTE)))
+            .find(|path| self.files.get(path).is_some_and(|file| file.get_mdfile().is_some()))
+    }
+
+    /// Check whether a note is the folder note of the folder it is in.
+    ///
+    /// # Arguments
+    /// @param path: &Path - Relative to the vault root
+    /// @return bool
+    pub fn is_folder_note(&self, path: &Path) -> bool {
+        let folder = path.parent().unwrap_or(Path::new(""));
+        self.folder_note(folder).as_deref() == Some(path)
+    }
+
+    /// Get the front matter of the folder note of a folder, which holds the metadata of the folder.
+    ///
+    /// # Arguments
+    /// @param folder: &Path - Relative to the vault root
+    /// @return Option<&Value> - None if the folder has no folder note, or it has no front matter
+    pub fn folder_metadata(&self, folder: &Path) -> Option<&Value> {
+        let note = self.folder_note(folder)?;
+        self.files.get(&note)?.get_mdfile()?.get_yaml()
+    }
+
+    /// Get a property a note inherits from the folder notes of the folders it is in.
+    ///
+    /// The folder note of the folder of the note is checked first, then those of its parents up
+    /// to the vault root. A folder note does not inherit from its own folder.
+    ///
+    /// # Arguments
+    /// @param path: &Path - The note, relative to the vault root
+    /// @param key: &str - The property
+    /// @return Option<&Value> - The value from the nearest folder note that sets the property
+    ///
+    /// # Example
+    /// ```should_panic
+    /// use std::path::{Path, PathBuf};
+    ///
+    /// use obsidian_driver::file::vault::Vault;
+    ///
+    /// let vault = Vault::from_path(PathBuf::from("vault")).unwrap();
+    /// // `course: BIOL 241` in the front matter of `Biology/Biology.md`
+    /// let course = vault.folder_property(Path::new("Biology/Lectures/Lecture 1.md"), "course").unwrap();
+    /// assert_eq!(course.as_str(), Some("BIOL 241"));
+    /// ```
+    pub fn folder_property(&self, path: &Path, key: &str) -> Option<&Value> {
+        let mut folder = path.parent();
+        if self.is_folder_note(path) {
+            folder = folder.and_then(Path::parent);
+        }
+        while let Some(current) = folder {
+            if let Some(value) = self.folder_metadata(current).and_then(|yaml| yaml.get(key)) {
+                return Some(value);
+            }
+            folder = current.parent();
+        }
+        None
+    }
+
+    /// Create the folder note of a folder, named like the folder, with a heading and the list of the contents of the folder.
+    ///
+    /// # Arguments
+    /// @param folder: &Path - Relative to the vault root; empty for the vault root, whose folder note is `index.md`
+    /// @return Result<PathBuf> - The new folder note; errors with `PathAlreadyExists` if the folder already has one
+    ///
+    /// # Example
+    /// ```should_panic
+    /// use std::path::{Path, PathBuf};
+    ///
+    /// use obsidian_driver::file::vault::Vault;
+    ///
+    /// let mut vault = Vault::from_path(PathBuf::from("vault")).unwrap();
+    /// let note = vault.create_folder_note(Path::new("Biology")).unwrap();
+    /// assert_eq!(note, PathBuf::from("Biology/Biology.md"));
+    /// ```
+    pub fn create_folder_note(&mut self, folder: &Path) -> Result<PathBuf> {
+        if let Some(existing) = self.folder_note(folder) {
+            return Err(Error::PathAlreadyExists(existing));
+        }
+        let (path, title) = match folder.file_name() {
+            Some(name) => (folder.join(f!("{}.md", name.to_string_lossy())), name.to_string_lossy().into_owned()),
+            None => (PathBuf::from(INDEX_NOTE), self.vault_root.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default()),
+        };
+        if self.vault_root.join(&path).exists() {
+            return Err(Error::PathAlreadyExists(path));
+        }
+        let body = f!("# {}\n\n{}\n", title, self.folder_contents(folder, &path));
+        let file = crate::file::File::from_mdfile(self.vault_root.join(&path), crate::file::mdfile::MDFile::new(None, body));
+        self.files.insert(path.clone(), file);
+        self.notify(VaultEvent::FileAdded(path.clone()));
+        self.write_file(&path)?;
+        Ok(path)
+    }
+
+    /// Regenerate the list of the contents of a folder in its folder note, and write the note if the list changed.
+    ///
+    /// The list is the part of the body between `FOLDER_CONTENTS_START` and `FOLDER_CONTENTS_END`; it is
+    /// added to the end of the body if the note has none. Subfolders are listed first, as links to their folder notes
+    /// when they have one, then the other notes of the folder, sorted by name.
+    ///
+    /// # Arguments
+    /// @param folder: &Path - Relative to the vault root
+    /// @return Result<bool> - Whether the folder note changed; errors with `PathNotFound` if the folder has no folder note
+    pub fn update_folder_contents(&mut self, folder: &Path) -> Result<bool> {
+        let path = self.folder_note(folder).ok_or(Error::PathNotFound(folder.to_path_buf()))?;
+        let contents = self.folder_contents(folder, &path);
+        let body = self.files[&path].get_mdfile().expect("folder notes are notes").get_body();
+        let existing = body.find(FOLDER_CONTENTS_START).and_then(|start| {
+            let end = body[start..].find(FOLDER_CONTENTS_END)? + start + FOLDER_CONTENTS_END.len();
+            Some(start..end)
+        });
+        let edit = match existing {
+            Some(range) if body[range.clone()] == contents => return Ok(false),
+            Some(range) => (range, contents),
+            None => {
+                let separator = match body.trim_end().is_empty() {
+                    true => "",
+                    false if body.ends_with("\n\n") => "",
+                    false if body.ends_with('\n') => "\n",
+                    false => "\n\n",
+                };
+                (body.len()..body.len(), f!("{}{}\n", separator, contents))
+            }
+        };
+        self.edit_bodies(vec![(path, edit.0, edit.1)])?;
+        Ok(true)
+    }
+
+    /// The generated list of the contents of a folder, with its start and end comments.
+    ///
+    /// @private
+    fn folder_contents(&self, folder: &Path, note: &Path) -> String {
+        let listing = self.list_folder(folder);
+        let mut lines = vec![FOLDER_CONTENTS_START.to_string()];
+        for subfolder in &listing.folders {
+            let name = subfolder.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+            match self.folder_note(subfolder) {
+                Some(target) => lines.push(f!("- {}", self.new_link(note, &target, Some(&name)))),
+                None => lines.push(f!("- {}/", name)),
+            }
+        }
+        for target in listing.notes.iter().filter(|target| target.as_path() != note) {
+            lines.push(f!("- {}", self.new_link(note, target, None)));
+        }
+        lines.push(FOLDER_CONTENTS_END.to_string());
+        lines.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod folders_tests {
+    use super::*;
+
+    #[test]
+    fn test_folder_notes() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("Biology/Lectures")).unwrap();
+        std::fs::create_dir_all(dir.path().join("Biology/Labs")).unwrap();
+        std::fs::write(dir.path().join("Biology/Biology.md"), "---\ncourse: BIOL 241\n---\n# Biology").unwrap();
+        std::fs::write(dir.path().join("Biology/Syllabus.md"), "").unwrap();
+        std::fs::write(dir.path().join("Biology/Lectures/index.md"), "---\ntype: lecture\n---\n").unwrap();
+        std::fs::write(dir.path().join("Biology/Lectures/Lecture 1.md"), "").unwrap();
+        std::fs::write(dir.path().join("Biology/Labs/Lab 1.md"), "").unwrap();
+        let mut vault = Vault::from_path(dir.path().to_path_buf()).unwrap();
+
+        assert_eq!(vault.folder_note(Path::new("Biology/Lectures")), Some(PathBuf::from("Biology/Lectures/index.md")));
+        assert!(vault.is_folder_note(Path::new("Biology/Biology.md")));
+        assert!(!vault.is_folder_note(Path::new("Biology/Syllabus.md")));
+        let lecture = Path::new("Biology/Lectures/Lecture 1.md");
+        assert_eq!(vault.folder_property(lecture, "type").and_then(Value::as_str), Some("lecture"));
+        assert_eq!(vault.folder_property(lecture, "course").and_then(Value::as_str), Some("BIOL 241"));
+        assert_eq!(vault.folder_property(Path::new("Biology/Lectures/index.md"), "type"), None);
+
+        assert!(vault.update_folder_contents(Path::new("Biology")).unwrap());
+        let expected = "# Biology\n\n%% begin folder contents %%\n- Labs/\n- [[index|Lectures]]\n- [[Syllabus]]\n%% end folder contents %%\n";
+        assert_eq!(std::fs::read_to_string(dir.path().join("Biology/Biology.md")).unwrap(), f!("---\ncourse: BIOL 241\n---\n{}", expected));
+        assert!(!vault.update_folder_contents(Path::new("Biology")).unwrap());
+
+        let note = vault.create_folder_note(Path::new("Biology/Labs")).unwrap();
+        assert_eq!(note, PathBuf::from("Biology/Labs/Labs.md"));
+        assert_eq!(vault.get_file(&note).unwrap().get_mdfile().unwrap().get_body(), "# Labs\n\n%% begin folder contents %%\n- [[Lab 1]]\n%% end folder contents %%\n");
+        assert!(matches!(vault.create_folder_note(Path::new("Biology/Labs")), Err(Error::PathAlreadyExists(_))));
+        assert!(vault.update_folder_contents(Path::new("Biology")).unwrap());
+    }
+
+    #[test]
+    fn test_list_folder_and_subtree() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("Courses/Biology/Labs")).unwrap();
+        std::fs::write(dir.path().join("Courses/Overview.md"), "").unwrap();
+        std::fs::write(dir.path().join("Courses/Biology/Lecture 1.md"), "").unwrap();
+        std::fs::write(dir.path().join("Courses/Biology/Labs/Lab 1.md"), "").unwrap();
+        std::fs::write(dir.path().join("Coursework.md"), "").unwrap();
+        let vault = Vault::from_path(dir.path().to_path_buf()).unwrap();
+
+        let listing = vault.list_folder(Path::new("Courses"));
+        assert_eq!(listing.notes, vec![PathBuf::from("Courses/Overview.md")]);
+        assert_eq!(listing.folders, vec![PathBuf::from("Courses/Biology")]);
+
+        let subtree = vault.subtree(Path::new("Courses"));
+        let notes = ["Courses/Biology/Labs/Lab 1.md", "Courses/Biology/Lecture 1.md", "Courses/Overview.md"];
+        assert_eq!(subtree.notes, notes.map(PathBuf::from).to_vec());
+        assert_eq!(subtree.folders, vec![PathBuf::from("Courses/Biology"), PathBuf::from("Courses/Biology/Labs")]);
+        assert_eq!(vault.list_folder(Path::new("")).notes, vec![PathBuf::from("Coursework.md")]);
+        assert_eq!(vault.subtree(Path::new("Missing")), FolderListing::default());
+    }
+
+    #[test]
+    fn test_move_and_rename_folder() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("Bio/Labs")).unwrap();
+        std::fs::write(dir.path().join("Bio/Bio.md"), "[[Lecture 1]]").unwrap();
+        std::fs::write(dir.path().join("Bio/Lecture 1.md"), "[Lab](Labs/Lab%201.md)").unwrap();
+        std::fs::write(dir.path().join("Bio/Labs/Lab 1.md"), "").unwrap();
+        std::fs::write(dir.path().join("Bio/Labs/.draft"), "").unwrap();
+        std::fs::write(dir.path().join("Bio/diagram.png"), [0u8]).unwrap();
+        std::fs::write(dir.path().join("Home.md"), "[[Bio/Bio|Biology]] [[Bio/Labs/Lab 1]] ![[Bio/diagram.png]]").unwrap();
+        let mut vault = Vault::from_path(dir.path().to_path_buf()).unwrap();
+        vault.set_link_style(super::super::links::LinkStyle::default().with_path(super::super::links::LinkPath::Absolute));
+        vault.create_folder(Path::new("Archive")).unwrap();
+        assert!(matches!(vault.create_folder(Path::new("Archive")), Err(Error::PathAlreadyExists(_))));
+        assert!(matches!(vault.move_folder(Path::new("Bio"), Path::new("Bio/Old")), Err(Error::FolderMovedIntoItself(_))));
+        assert!(matches!(vault.move_folder(Path::new("Bio"), Path::new("../Bio")), Err(Error::PathNotInVaultRoot(..))));
+
+        let rewritten = vault.rename_folder(Path::new("Bio"), "Biology").unwrap();
+        assert_eq!(rewritten, vec![PathBuf::from("Home.md")]);
+        assert!(!dir.path().join("Bio").exists());
+        assert!(dir.path().join("Biology/Labs/.draft").exists());
+        assert!(vault.is_folder_note(Path::new("Biology/Biology.md")));
+        let home = std::fs::read_to_string(dir.path().join("Home.md")).unwrap();
+        assert_eq!(home, "[[Biology/Biology|Biology]] [[Biology/Labs/Lab 1]] ![[Biology/diagram.png]]");
+        assert!(dir.path().join("Biology/diagram.png").exists());
+
+        vault.move_folder(Path::new("Biology"), Path::new("Archive/Biology")).unwrap();
+        assert!(vault.get_file(&PathBuf::from("Archive/Biology/Labs/Lab 1.md")).is_some());
+        let lecture = std::fs::read_to_string(dir.path().join("Archive/Biology/Lecture 1.md")).unwrap();
+        assert_eq!(lecture, "[Lab](Labs/Lab%201.md)");
+        assert_eq!(vault.files.keys().filter(|path| path.starts_with("Biology")).count(), 0);
+    }
+}
@@ -0,0 +1,72 @@
+//! obsidian-driver::file::vault::format
+//!
+//! This module contains the vault-wide formatting rewrites: transforms of the markdown of a single note, applied to every note of the Vault.
+//!
+//! @public Vault::convert_unicode_math
+
+// std imports
+use std::path::PathBuf;
+
+// first-party imports
+use super::Vault;
+use crate::file::mdfile::math::unicode_to_latex;
+use crate::prelude::*;
+
+impl Vault {
+    /// Replace Unicode math symbols with LaTeX in every note, and write the notes that changed.
+    ///
+    /// See `unicode_to_latex` for what is converted. Front matter is not changed.
+    ///
+    /// # Arguments
+    /// @return Result<Vec<PathBuf>> - The notes that changed, sorted
+    ///
+    /// # Example
+    /// ```should_panic
+    /// use std::path::PathBuf;
+    ///
+    /// use obsidian_driver::file::vault::Vault;
+    ///
+    /// let mut vault = Vault::from_path(PathBuf::from("vault")).unwrap();
+    /// let changed = vault.convert_unicode_math().unwrap();
+    /// ```
+    pub fn convert_unicode_math(&mut self) -> Result<Vec<PathBuf>> {
+        self.rewrite_bodies(unicode_to_latex)
+    }
+
+    /// Rewrite the body of every note, and write the notes whose body changed.
+    ///
+    /// @private
+    fn rewrite_bodies(&mut self, rewrite: impl Fn(&str) -> String) -> Result<Vec<PathBuf>> {
+        let mut edits: Vec<(PathBuf, std::ops::Range<usize>, String)> = self
+            .files
+            .iter()
+            .filter_map(|(path, file)| {
+                let body = file.get_mdfile()?.get_body();
+                let rewritten = rewrite(body);
+                (&rewritten != body).then(|| (path.clone(), 0..body.len(), rewritten))
+            })
+            .collect();
+        edits.sort_by(|a, b| a.0.cmp(&b.0));
+        let changed = edits.iter().map(|(path, ..)| path.clone()).collect();
+        self.edit_bodies(edits)?;
+        Ok(changed)
+    }
+}
+
+#[cfg(test)]
+mod format_tests {
+    use super::*;
+
+    #[test]
+    fn test_convert_unicode_math() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.md"), "---\nsymbol: λ\n---\nλ-calculus").unwrap();
+        std::fs::write(dir.path().join("b.md"), "$\\lambda$").unwrap();
+        let mut vault = Vault::from_path(dir.path().to_path_buf()).unwrap();
+
+        assert_eq!(vault.convert_unicode_math().unwrap(), vec![PathBuf::from("a.md")]);
+        let text = std::fs::read_to_string(dir.path().join("a.md")).unwrap();
+        assert_eq!(text, "---\nsymbol: λ\n---\n$\\lambda$-calculus");
+        assert!(vault.convert_unicode_math().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,137 @@
+//! obsidian-driver::file::vault::frontmatter
+//!
+//! This module contains bulk front matter editing: applying one change to the front matter of every note a query matches.
+//!
+//! @public FrontmatterUpdate
+//!
+//! @public Vault::update_frontmatter
+
+// std imports
+use std::path::{Path, PathBuf};
+
+// third-party imports
+use serde::{Deserialize, Serialize};
+use serde_yaml::{Mapping, Value};
+
+// first-party imports
+use super::query::Query;
+use super::Vault;
+use crate::file::mdfile::merge::FrontmatterChange;
+use crate::prelude::*;
+
+/// FrontmatterUpdate struct
+///
+/// The front matter keys a bulk edit changed in one note.
+///
+/// @public
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FrontmatterUpdate {
+    pub path: PathBuf,
+    pub changes: Vec<FrontmatterChange>,
+}
+
+impl Vault {
+    /// Apply a change to the front matter of every note matching a query, and write the notes that changed.
+    ///
+    /// The mutation gets the note path and its front matter mapping (empty for notes without
+    /// front matter); notes whose front matter is not a mapping are skipped. The sources and
+    /// conditions of the query select the notes, its sort and limit are ignored. With `dry_run`,
+    /// nothing is modified and the report shows what would change.
+    ///
+    /// # Arguments
+    /// @param filter: &Query - e.g. `Query::new().from_folder("Lectures")`
+    /// @param mutation: impl FnMut(&Path, &mut Mapping)
+    /// @param dry_run: bool
+    /// @return Result<Vec<FrontmatterUpdate>> - The notes that changed, sorted by path
+    ///
+    /// # Example
+    /// ```should_panic
+    /// use std::path::PathBuf;
+    ///
+    /// use obsidian_driver::file::vault::Vault;
+    /// use obsidian_driver::file::vault::query::Query;
+    ///
+    /// let mut vault = Vault::from_path(PathBuf::from("vault")).unwrap();
+    /// let filter = Query::parse("FROM #lecture WHERE !status").unwrap();
+    /// let updates = vault.update_frontmatter(&filter, |_, yaml| {
+    /// 	yaml.entry("status".into()).or_insert("todo".into());
+    /// }, true).unwrap();
+    /// ```
+    pub fn update_frontmatter(
+        &mut self,
+        filter: &Query,
+        mut mutation: impl FnMut(&Path, &mut Mapping),
+        dry_run: bool,
+    ) -> Result<Vec<FrontmatterUpdate>> {
+        let mut paths: Vec<PathBuf> = self
+            .files
+            .iter()
+            .filter(|(path, file)| filter.matches(path, file))
+            .map(|(path, _)| path.clone())
+            .collect();
+        paths.sort();
+
+        let mut updates = Vec::new();
+        for path in paths {
+            let Some(mdfile) = self.files.get(&path).and_then(|file| file.get_mdfile()) else {
+                continue;
+            };
+            let mut mapping = match mdfile.get_yaml() {
+                Some(Value::Mapping(mapping)) => mapping.clone(),
+                Some(_) => continue,
+                None => Mapping::new(),
+            };
+            mutation(&path, &mut mapping);
+            if mapping.is_empty() && mdfile.get_yaml().is_none() {
+                continue;
+            }
+            let mut updated = mdfile.clone();
+            updated.set_yaml(Value::Mapping(mapping));
+            let changes = mdfile.diff(&updated).frontmatter;
+            if changes.is_empty() {
+                continue;
+            }
+            if !dry_run {
+                let mdfile = self
+                    .files
+                    .get_mut(&path)
+                    .and_then(|file| file.get_mdfile_mut())
+                    .expect("File not found in vault");
+                *mdfile = updated;
+                self.write_file(&path)?;
+            }
+            updates.push(FrontmatterUpdate { path, changes });
+        }
+        Ok(updates)
+    }
+}
+
+#[cfg(test)]
+mod frontmatter_tests {
+    use super::*;
+
+    #[test]
+    fn test_update_frontmatter_dry_run_and_write() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("Lectures")).unwrap();
+        std::fs::write(dir.path().join("Lectures/one.md"), "---\nstatus: done\n---\n# One").unwrap();
+        std::fs::write(dir.path().join("Lectures/two.md"), "# Two").unwrap();
+        std::fs::write(dir.path().join("other.md"), "# Other").unwrap();
+        let mut vault = Vault::from_path(dir.path().to_path_buf()).unwrap();
+        let filter = Query::new().from_folder("Lectures");
+        let add_status = |_: &Path, yaml: &mut Mapping| {
+            yaml.entry("status".into()).or_insert("todo".into());
+        };
+
+        let preview = vault.update_frontmatter(&filter, add_status, true).unwrap();
+        assert_eq!(preview.len(), 1);
+        assert_eq!(preview[0].path, PathBuf::from("Lectures/two.md"));
+        assert_eq!(preview[0].changes[0].after, Some("todo".into()));
+        assert!(vault.dirty_files().is_empty());
+
+        assert_eq!(vault.update_frontmatter(&filter, add_status, false).unwrap(), preview);
+        let written = std::fs::read_to_string(dir.path().join("Lectures/two.md")).unwrap();
+        assert!(written.starts_with("---\nstatus: todo\n---\n"));
+        assert!(vault.update_frontmatter(&filter, add_status, false).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,288 @@
+//! obsidian-driver::file::vault::graph
+//!
+//! This module contains the link graph of a Vault, with exporters to DOT (Graphviz) and JSON for visualization and analysis.
+//!
+//! @public LinkGraph
+//!
+//! @public GraphNode
+//!
+//! @public GraphEdge
+//!
+//! @public Vault::link_graph
+//!
+//! @public Vault::orphan_notes
+//!
+//! @public Vault::write_orphan_report
+
+// std imports
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+// third-party imports
+use serde::{Deserialize, Serialize};
+
+// first-party imports
+use super::Vault;
+use crate::prelude::*;
+
+/// A file in the link graph, or the target of an unresolved link.
+///
+/// @public
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GraphNode {
+    // the path relative to the vault root, or the link target as written for dangling nodes
+    pub path: PathBuf,
+    // false for the targets of links that do not resolve to a file in the vault
+    pub resolved: bool,
+}
+
+/// The links from one note to one target.
+///
+/// @public
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GraphEdge {
+    pub source: PathBuf,
+    pub target: PathBuf,
+    // the number of links from source to target
+    pub count: usize,
+}
+
+/// LinkGraph struct
+///
+/// The notes of a vault and the links between them. Nodes are sorted by path with dangling
+/// nodes last, and edges are sorted by source then target.
+///
+/// # Example
+/// ```should_panic
+/// use std::path::PathBuf;
+///
+/// use obsidian_driver::file::vault::Vault;
+///
+/// let vault = Vault::from_path(PathBuf::from("vault")).unwrap();
+/// let graph = vault.link_graph();
+/// std::fs::write("vault.dot", graph.to_dot()).unwrap();
+/// std::fs::write("vault.json", graph.to_json().unwrap()).unwrap();
+/// ```
+/// @public
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LinkGraph {
+    pub nodes: Vec<GraphNode>,
+    pub edges: Vec<GraphEdge>,
+}
+
+impl LinkGraph {
+    /// Get the edges leaving a node.
+    ///
+    /// # Arguments
+    /// @param path: &Path
+    /// @return Vec<&GraphEdge>
+    pub fn outgoing(&self, path: &Path) -> Vec<&GraphEdge> {
+        self.edges.iter().filter(|edge| edge.source == path).collect()
+    }
+
+    /// Get the edges entering a node.
+    ///
+    /// # Arguments
+    /// @param path: &Path
+    /// @return Vec<&GraphEdge>
+    pub fn incoming(&self, path: &Path) -> Vec<&GraphEdge> {
+        self.edges.iter().filter(|edge| edge.target == path).collect()
+    }
+
+    /// Get the nodes for links that do not resolve.
+    ///
+    /// # Arguments
+    /// @return Vec<&GraphNode>
+    pub fn dangling(&self) -> Vec<&GraphNode> {
+        self.nodes.iter().filter(|node| !node.resolved).collect()
+    }
+
+    /// Render the graph in the Graphviz DOT language. Dangling nodes are drawn dashed and edges for repeated links are labeled with their count.
+    ///
+    /// # Arguments
+    /// @return String
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph vault {\n");
+        for node in &self.nodes {
+            let style = if node.resolved { "" } else { " [style=dashed]" };
+            dot.push_str(&f!("    {}{};\n", dot_id(&node.path), style));
+        }
+        for edge in &self.edges {
+            let label = if edge.count > 1 {
+                f!(" [label=\"{}\"]", edge.count)
+            } else {
+                String::new()
+            };
+            dot.push_str(&f!("    {} -> {}{};\n", dot_id(&edge.source), dot_id(&edge.target), label));
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Serialize the graph to JSON, as `{"nodes": [...], "edges": [...]}`.
+    ///
+    /// # Arguments
+    /// @return Result<String>
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+}
+
+/// Quote a path as a DOT identifier.
+///
+/// @private
+fn dot_id(path: &Path) -> String {
+    let name = path.to_string_lossy().replace('\\', "\\\\").replace('"', "\\\"");
+    f!("\"{}\"", name)
+}
+
+impl Vault {
+    /// Build the link graph of the vault. Every file is a node; links that do not resolve add a dangling node named after their target.
+    ///
+    /// # Arguments
+    /// @return LinkGraph
+    ///
+    /// # Example
+    /// ```should_panic
+    /// use std::path::PathBuf;
+    ///
+    /// use obsidian_driver::file::vault::Vault;
+    ///
+    /// let vault = Vault::from_path(PathBuf::from("vault")).unwrap();
+    /// let graph = vault.link_graph();
+    /// for node in graph.dangling() {
+    /// 	println!("missing: {}", node.path.display());
+    /// }
+    /// ```
+    pub fn link_graph(&self) -> LinkGraph {
+        let mut nodes: BTreeMap<PathBuf, bool> = self.files.keys().map(|path| (path.clone(), true)).collect();
+        let mut edges: BTreeMap<(PathBuf, PathBuf), usize> = BTreeMap::new();
+        for (source, file) in self.files.iter() {
+            let Some(mdfile) = file.get_mdfile() else {
+                continue;
+            };
+            for link in mdfile.get_links() {
+                let target = match self.resolve_link(source, &link) {
+                    Some(target) => target,
+                    None => {
+                        let target = PathBuf::from(&link.target);
+                        nodes.entry(target.clone()).or_insert(false);
+                        target
+                    }
+                };
+                *edges.entry((source.clone(), target)).or_insert(0) += 1;
+            }
+        }
+
+        let mut nodes: Vec<GraphNode> = nodes
+            .into_iter()
+            .map(|(path, resolved)| GraphNode { path, resolved })
+            .collect();
+        nodes.sort_by_key(|node| !node.resolved);
+        let edges = edges
+            .into_iter()
+            .map(|((source, target), count)| GraphEdge { source, target, count })
+            .collect();
+        LinkGraph { nodes, edges }
+    }
+
+    /// Get the notes no other note links to, sorted by path.
+    ///
+    /// # Arguments
+    /// @return Vec<PathBuf>
+    pub fn orphan_notes(&self) -> Vec<PathBuf> {
+        self.orphans_ignoring(None)
+    }
+
+    /// Get the notes no other note links to, not counting the links from `ignored`.
+    ///
+    /// @private
+    fn orphans_ignoring(&self, ignored: Option<&Path>) -> Vec<PathBuf> {
+        let graph = self.link_graph();
+        let mut orphans: Vec<PathBuf> = self
+            .files
+            .iter()
+            .filter(|(path, file)| file.get_mdfile().is_some() && Some(path.as_path()) != ignored)
+            .filter(|(path, _)| {
+                graph
+                    .incoming(path)
+                    .iter()
+                    .all(|edge| &edge.source == *path || Some(edge.source.as_path()) == ignored)
+            })
+            .map(|(path, _)| path.clone())
+            .collect();
+        orphans.sort();
+        orphans
+    }
+
+    /// Write a note listing the orphan notes, see `Vault::orphan_notes`, replacing the note if it exists. The report itself and its links are not counted.
+    ///
+    /// # Arguments
+    /// @param path: &PathBuf - The report note, relative to the vault root
+    /// @return Result<Vec<PathBuf>> - The orphans listed
+    ///
+    /// # Example
+    /// ```should_panic
+    /// use std::path::PathBuf;
+    ///
+    /// use obsidian_driver::file::vault::Vault;
+    ///
+    /// let mut vault = Vault::from_path(PathBuf::from("vault")).unwrap();
+    /// let orphans = vault.write_orphan_report(&PathBuf::from("Reports/Orphans.md")).unwrap();
+    /// ```
+    pub fn write_orphan_report(&mut self, path: &PathBuf) -> Result<Vec<PathBuf>> {
+        let orphans = self.orphans_ignoring(Some(path));
+        let mut report = f!("# Orphan notes\n\n{} notes have no links to them.\n\n", orphans.len());
+        for orphan in &orphans {
+            report.push_str(&f!("- {}\n", self.new_link(path, orphan, None)));
+        }
+        self.put_note(path, &report)?;
+        Ok(orphans)
+    }
+}
+
+#[cfg(test)]
+mod graph_tests {
+    use super::*;
+
+    #[test]
+    fn test_link_graph_with_dangling_nodes() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("sub")).unwrap();
+        std::fs::write(dir.path().join("a.md"), "[[b]] [[b#Heading]] [[Missing \"Note\"]]").unwrap();
+        std::fs::write(dir.path().join("sub/b.md"), "[back](../a.md)").unwrap();
+        let vault = Vault::from_path(dir.path().to_path_buf()).unwrap();
+
+        let graph = vault.link_graph();
+        let nodes: Vec<(&str, bool)> = graph
+            .nodes
+            .iter()
+            .map(|node| (node.path.to_str().unwrap(), node.resolved))
+            .collect();
+        assert_eq!(nodes, vec![("a.md", true), ("sub/b.md", true), ("Missing \"Note\"", false)]);
+        assert_eq!(graph.outgoing(Path::new("a.md"))[1].count, 2);
+        assert_eq!(graph.incoming(Path::new("a.md")).len(), 1);
+
+        let dot = graph.to_dot();
+        assert!(dot.contains("    \"Missing \\\"Note\\\"\" [style=dashed];\n"));
+        assert!(dot.contains("    \"a.md\" -> \"sub/b.md\" [label=\"2\"];\n"));
+        let json: LinkGraph = serde_json::from_str(&graph.to_json().unwrap()).unwrap();
+        assert_eq!(json, graph);
+    }
+
+    #[test]
+    fn test_orphan_report() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.md"), "[[b]] [[a]]").unwrap();
+        std::fs::write(dir.path().join("b.md"), "# B").unwrap();
+        std::fs::write(dir.path().join("c.md"), "[[c]]").unwrap();
+        let mut vault = Vault::from_path(dir.path().to_path_buf()).unwrap();
+
+        assert_eq!(vault.orphan_notes(), vec![PathBuf::from("a.md"), PathBuf::from("c.md")]);
+        let orphans = vault.write_orphan_report(&PathBuf::from("Orphans.md")).unwrap();
+        assert_eq!(orphans.len(), 2);
+        let report = std::fs::read_to_string(dir.path().join("Orphans.md")).unwrap();
+        assert_eq!(report, "# Orphan notes\n\n2 notes have no links to them.\n\n- [[a]]\n- [[c]]\n");
+        assert_eq!(vault.write_orphan_report(&PathBuf::from("Orphans.md")).unwrap(), orphans);
+    }
+}
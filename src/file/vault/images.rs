@@ -0,0 +1,157 @@
+//! obsidian-driver::file::vault::images
+//!
+//! This module contains image attachments read by a vision model: alt text, the text in an image, or a description, returned or written to a note that embeds the image, so screenshots and whiteboard photos can be found by search and embeddings like any note.
+//!
+//! @public ImageTask
+//!
+//! @public Vault::read_image
+//!
+//! @public Vault::write_image_note
+
+// std imports
+use std::path::PathBuf;
+
+// third-party imports
+use serde::{Deserialize, Serialize};
+
+// first-party imports
+use super::Vault;
+use crate::ai::api::image::Image;
+use crate::ai::prompt::{Prompt, ResponseBudget};
+use crate::prelude::*;
+
+const IMAGE_SYSTEM_PROMPT: &str = "You read images attached to study notes, such as screenshots, slides, diagrams and whiteboard photos.";
+const ALT_TEXT_PROMPT: &str = "Write alt text for this image: one sentence on what it shows. Reply with the sentence only.";
+const TEXT_PROMPT: &str = "Transcribe all the text in this image, including handwriting. Keep its structure with Markdown headings, lists and tables, and write math as LaTeX between $ signs. Reply with the transcription only.";
+const DESCRIPTION_PROMPT: &str = "Describe this image in Markdown for someone searching their notes: what it shows, the text and labels in it, and the ideas it explains. Reply with the description only.";
+
+/// What to get from an image.
+///
+/// @public
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImageTask {
+    /// One sentence on what the image shows.
+    AltText,
+    /// The text in the image, as Markdown.
+    Text,
+    /// What the image shows and explains, as Markdown.
+    Description,
+}
+
+impl ImageTask {
+    /// The prompt sent with the image.
+    ///
+    /// @private
+    fn prompt(&self) -> Prompt {
+        match self {
+            ImageTask::AltText => Prompt::new(IMAGE_SYSTEM_PROMPT, ALT_TEXT_PROMPT, ResponseBudget::Tokens(100)),
+            ImageTask::Text => Prompt::new(IMAGE_SYSTEM_PROMPT, TEXT_PROMPT, ResponseBudget::Unlimited),
+            ImageTask::Description => Prompt::new(IMAGE_SYSTEM_PROMPT, DESCRIPTION_PROMPT, ResponseBudget::Unlimited),
+        }
+    }
+}
+
+impl Vault {
+    /// Send an image of the vault to the vision model of the AI driver.
+    ///
+    /// # Arguments
+    /// @param image: &PathBuf - The image, relative to the vault root
+    /// @param task: ImageTask
+    /// @return Result<String> - Errors with `NoAIDriver` if no driver was added, and with `NotSupported` if the driver or the image format does not support it
+    ///
+    /// # Example
+    /// ```
+    /// use std::path::PathBuf;
+    ///
+    /// use obsidian_driver::ai::api::AIDriver;
+    /// use obsidian_driver::file::vault::Vault;
+    /// use obsidian_driver::file::vault::images::ImageTask;
+    ///
+    /// async fn read_image_example() {
+    /// 	let mut vault = Vault::from_path(PathBuf::from("vault")).unwrap();
+    /// 	vault.add_ai_driver(AIDriver::new_openai_from_config_path(PathBuf::from(".openai_config.json")).await.unwrap());
+    /// 	let alt = vault.read_image(&PathBuf::from("Attachments/cell.png"), ImageTask::AltText).await.unwrap();
+    /// }
+    /// ```
+    pub async fn read_image(&self, image: &PathBuf, task: ImageTask) -> Result<String> {
+        let driver = self.aidriver.as_ref().ok_or(Error::NoAIDriver)?;
+        let path = self.vault_root.join(image);
+        if !path.is_file() {
+            return Err(Error::PathNotFound(image.clone()));
+        }
+        let response = driver.describe_image(task.prompt(), Image::from_path(&path)?).await?;
+        Ok(response.trim().to_string())
+    }
+
+    /// Write a note next to an image that embeds it, followed by what the vision model read from it.
+    ///
+    /// The note is named after the image, e.g. `whiteboard.png.md` for `whiteboard.png`, and is
+    /// replaced if it exists.
+    ///
+    /// # Arguments
+    /// @param image: &PathBuf - The image, relative to the vault root
+    /// @param task: ImageTask - Usually `Text` or `Description`
+    /// @return Result<PathBuf> - The note, relative to the vault root
+    ///
+    /// # Example
+    /// ```
+    /// use std::path::PathBuf;
+    ///
+    /// use obsidian_driver::ai::api::AIDriver;
+    /// use obsidian_driver::file::vault::Vault;
+    /// use obsidian_driver::file::vault::images::ImageTask;
+    ///
+    /// async fn write_image_note_example() {
+    /// 	let mut vault = Vault::from_path(PathBuf::from("vault")).unwrap();
+    /// 	vault.add_ai_driver(AIDriver::new_openai_from_config_path(PathBuf::from(".openai_config.json")).await.unwrap());
+    /// 	let note = vault.write_image_note(&PathBuf::from("Attachments/whiteboard.jpg"), ImageTask::Text).await.unwrap();
+    /// }
+    /// ```
+    pub async fn write_image_note(&mut self, image: &PathBuf, task: ImageTask) -> Result<PathBuf> {
+        let text = self.read_image(image, task).await?;
+        let file_name = image
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .ok_or(Error::PathNotFound(image.clone()))?;
+        let note = image.with_file_name(f!("{}.md", file_name));
+        let contents = f!("!{}\n\n{}\n", self.new_link(&note, image, None), text);
+        self.put_note(&note, &contents)?;
+        Ok(note)
+    }
+}
+
+#[cfg(test)]
+mod images_tests {
+    use super::*;
+    use crate::ai::api::mock::MockDriver;
+    use crate::ai::api::AIDriver;
+
+    #[tokio::test]
+    async fn test_write_image_note() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.md"), "# A").unwrap();
+        std::fs::write(dir.path().join("board.png"), [0u8, 1, 2]).unwrap();
+        std::fs::write(dir.path().join("board.bmp"), [0u8, 1, 2]).unwrap();
+        // images are not loaded into the vault
+        let mut vault = Vault::from_path(dir.path().to_path_buf()).unwrap();
+        let image = PathBuf::from("board.png");
+        assert!(vault.get_file(&image).is_none());
+        assert!(matches!(vault.read_image(&image, ImageTask::Text).await, Err(Error::NoAIDriver)));
+
+        let mock = MockDriver::new().with_responses(["  # Mitosis\n\n- Prophase  "]);
+        vault.add_ai_driver(AIDriver::new_mock(mock.clone()));
+        let note = vault.write_image_note(&image, ImageTask::Text).await.unwrap();
+        assert_eq!(note, PathBuf::from("board.png.md"));
+        assert_eq!(mock.prompts()[0].user_prompt, TEXT_PROMPT);
+        assert_eq!(mock.prompts()[0].images[0].url(), "data:image/png;base64,AAEC");
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join(&note)).unwrap(),
+            "![[board.png]]\n\n# Mitosis\n\n- Prophase\n"
+        );
+        assert!(vault.get_file(&note).is_some());
+
+        assert!(matches!(vault.read_image(&PathBuf::from("board.bmp"), ImageTask::Text).await, Err(Error::NotSupported(_))));
+        assert!(matches!(vault.read_image(&PathBuf::from("missing.png"), ImageTask::Text).await, Err(Error::PathNotFound(_))));
+    }
+}
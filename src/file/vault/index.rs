@@ -0,0 +1,421 @@
+//! obsidian-driver::file::vault::index
+//!
+//! This module contains the generated index note of a Vault, a home page listing its top-level folders, its hub notes by number of notes linking to them, its recently modified notes, and its topics from embedding clusters, optionally with an AI summary of each. The list is kept between two comments and regenerated in place, so text around it is kept.
+//!
+//! @public VAULT_INDEX_START
+//!
+//! @public VAULT_INDEX_END
+//!
+//! @public IndexOptions
+//!
+//! @public Vault::update_index_note
+
+// std imports
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+// third-party imports
+use serde::{Deserialize, Serialize};
+
+// first-party imports
+use super::cluster::ClusterMethod;
+use super::Vault;
+use crate::ai::chunk::split_text;
+use crate::ai::prompt::{Context, Prompt, ResponseBudget};
+use crate::prelude::*;
+
+/// The comment that starts the generated part of the index note.
+///
+/// @public
+pub const VAULT_INDEX_START: &str = "%% begin vault index %%";
+
+/// The comment that ends the generated part of the index note.
+///
+/// @public
+pub const VAULT_INDEX_END: &str = "%% end vault index %%";
+
+// how many members of a topic are linked, nearest to its centroid first
+const TOPIC_MEMBERS: usize = 5;
+// how much of the topic's notes is sent to summarize it
+const TOPIC_TOKENS: u32 = 2000;
+const TOPIC_SYSTEM_PROMPT: &str = "You write the home page of a student's knowledge base.";
+const TOPIC_USER_PROMPT: &str = r#"Below are notes on one topic. Summarize what the topic covers in one sentence of at most 25 words. Reply with the sentence only.
+
+[notes]"#;
+
+/// IndexOptions struct
+///
+/// Options for `Vault::update_index_note`. By default the 10 notes with the most notes linking to
+/// them and the 10 most recently modified notes are listed, and topics are found with
+/// `ClusterMethod::KMeans(8)`, without summaries.
+///
+/// # Example
+/// ```
+/// use obsidian_driver::file::vault::cluster::ClusterMethod;
+/// use obsidian_driver::file::vault::index::IndexOptions;
+///
+/// let options = IndexOptions::default()
+/// 	.with_hubs(5)
+/// 	.with_topics(Some(ClusterMethod::Threshold(0.4)))
+/// 	.with_summaries(true);
+/// ```
+///
+/// @public
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct IndexOptions {
+    pub hubs: usize,
+    pub recent: usize,
+    // None to leave the topics out
+    pub topics: Option<ClusterMethod>,
+    // summarize each topic with the AI driver of the vault
+    #[serde(default)]
+    pub summaries: bool,
+}
+
+impl Default for IndexOptions {
+    fn default() -> Self {
+        IndexOptions {
+            hubs: 10,
+            recent: 10,
+            topics: Some(ClusterMethod::KMeans(8)),
+            summaries: false,
+        }
+    }
+}
+
+impl IndexOptions {
+    /// List this many hub notes; 0 to leave them out.
+    ///
+    /// # Arguments
+    /// @param hubs: usize
+    /// @return IndexOptions
+    pub fn with_hubs(mut self, hubs: usize) -> Self {
+        self.hubs = hubs;
+        self
+    }
+
+    /// List this many recently modified notes; 0 to leave them out.
+    ///
+    /// # Arguments
+    /// @param recent: usize
+    /// @return IndexOptions
+    pub fn with_recent(mut self, recent: usize) -> Self {
+        self.recent = recent;
+        self
+    }
+
+    /// Find topics with a clustering method, or leave them out.
+    ///
+    /// # Arguments
+    /// @param topics: Option<ClusterMethod>
+    /// @return IndexOptions
+    pub fn with_topics(mut self, topics: Option<ClusterMethod>) -> Self {
+        self.topics = topics;
+        self
+    }
+
+    /// Write a one-sentence AI summary under each topic. Needs an AI driver.
+    ///
+    /// # Arguments
+    /// @param summaries: bool
+    /// @return IndexOptions
+    pub fn with_summaries(mut self, summaries: bool) -> Self {
+        self.summaries = summaries;
+        self
+    }
+}
+
+impl Vault {
+    /// Create or refresh the index note of the vault, and write it if it changed.
+    ///
+    /// The generated part is between `VAULT_INDEX_START` and `VAULT_INDEX_END`, and has a section for each of:
+    /// - Folders: the top-level folders, linking their folder notes when they have one, with their number of notes
+    /// - Hubs: the notes most other notes link to
+    /// - Recent: the notes modified last, by the modified key of the timestamp policy, else the file system
+    /// - Topics: the clusters of two or more notes, by their label and nearest members; notes need embeddings
+    ///
+    /// With `options.summaries`, each topic gets a one-sentence summary from the cheap model. A
+    /// summary is kept for as long as its topic lists the same notes, so only new or changed topics
+    /// are summarized again.
+    ///
+    /// Empty sections are left out, and the index note itself is never listed or counted. A new note
+    /// gets an `# Index` heading; in an existing note the generated part is replaced, or added to the
+    /// end of the body if it has none. Running again without changes to the vault changes nothing.
+    ///
+    /// # Arguments
+    /// @param path: &PathBuf - The index note, relative to the vault root
+    /// @param options: &IndexOptions
+    /// @return Result<bool> - Whether the index note changed. Errors with `EmbeddingDimensionMismatch` if embeddings have different lengths, and with `NoAIDriver` if summaries are asked for without an AI driver.
+    ///
+    /// # Example
+    /// ```
+    /// use std::path::PathBuf;
+    ///
+    /// use obsidian_driver::ai::api::AIDriver;
+    /// use obsidian_driver::file::vault::Vault;
+    /// use obsidian_driver::file::vault::index::IndexOptions;
+    ///
+    /// async fn update_index_note_example() {
+    /// 	let mut vault = Vault::from_path(PathBuf::from("vault")).unwrap();
+    /// 	vault.add_ai_driver(AIDriver::new_openai_from_config_path(PathBuf::from(".openai_config.json")).await.unwrap());
+    /// 	vault.update_embeddings().await.unwrap();
+    ///
+    /// 	let options = IndexOptions::default().with_summaries(true);
+    /// 	let changed = vault.update_index_note(&PathBuf::from("Home.md"), &options).await.unwrap();
+    /// }
+    /// ```
+    pub async fn update_index_note(&mut self, path: &PathBuf, options: &IndexOptions) -> Result<bool> {
+        let topics = self.index_topics(path, options)?;
+        let mut summaries = HashMap::new();
+        if options.summaries && !topics.is_empty() {
+            let driver = self.aidriver.clone().ok_or(Error::NoAIDriver)?;
+            if let Some(mdfile) = self.files.get(path).and_then(|file| file.get_mdfile()) {
+                summaries = existing_summaries(mdfile.get_body());
+            }
+            for (line, members) in &topics {
+                if summaries.contains_key(line) {
+                    continue;
+                }
+                let mut notes = String::new();
+                for member in members {
+                    if let Some(mdfile) = self.files.get(member).and_then(|file| file.get_mdfile()) {
+                        let title = member.file_stem().unwrap_or_default().to_string_lossy();
+                        notes.push_str(&f!("**{}**\n\n{}\n\n", title, mdfile.strip_comments().get_body().trim()));
+                    }
+                }
+                let mut context = Context::default();
+                context.insert("notes", split_text(&notes, TOPIC_TOKENS).first().map(String::as_str).unwrap_or_default());
+                let prompt = Prompt::new(TOPIC_SYSTEM_PROMPT, TOPIC_USER_PROMPT, ResponseBudget::Tokens(80)).substitute(&context)?;
+                let summary = driver.chat_cheap(prompt).await?;
+                let summary = summary.split_whitespace().collect::<Vec<&str>>().join(" ");
+                summaries.insert(line.clone(), summary);
+            }
+        }
+        let index = self.vault_index(path, options, &topics, &summaries);
+        let Some(mdfile) = self.files.get(path).and_then(|file| file.get_mdfile()) else {
+            self.put_note(path, &f!("# Index\n\n{}\n", index))?;
+            return Ok(true);
+        };
+        let body = mdfile.get_body();
+        let existing = body.find(VAULT_INDEX_START).and_then(|start| {
+            let end = body[start..].find(VAULT_INDEX_END)? + start + VAULT_INDEX_END.len();
+            Some(start..end)
+        });
+        let edit = match existing {
+            Some(range) if body[range.clone()] == index => return Ok(false),
+            Some(range) => (range, index),
+            None => {
+                let separator = match body.trim_end().is_empty() {
+                    true => "",
+                    false if body.ends_with("\n\n") => "",
+                    false if body.ends_with('\n') => "\n",
+                    false => "\n\n",
+                };
+                (body.len()..body.len(), f!("{}{}\n", separator, index))
+            }
+        };
+        self.edit_bodies(vec![(path.clone(), edit.0, edit.1)])?;
+        Ok(true)
+    }
+
+    /// The topics of the index note at `note`: the line listing each, with the notes it links.
+    ///
+    /// @private
+    fn index_topics(&self, note: &Path, options: &IndexOptions) -> Result<Vec<(String, Vec<PathBuf>)>> {
+        let mut topics = Vec::new();
+        let Some(method) = options.topics else {
+            return Ok(topics);
+        };
+        for cluster in self.cluster_notes(method)? {
+            let mut members: Vec<(f64, &PathBuf)> = cluster
+                .members
+                .iter()
+                .filter(|path| path.as_path() != note)
+                .filter_map(|path| {
+                    let embedding = self.files.get(path)?.get_embedding()?;
+                    let distance: f64 = embedding.iter().zip(&cluster.centroid).map(|(a, b)| (a - b) * (a - b)).sum();
+                    Some((distance, path))
+                })
+                .collect();
+            if members.len() < 2 {
+                continue;
+            }
+            members.sort_by(|a, b| a.0.total_cmp(&b.0).then_with(|| a.1.cmp(b.1)));
+            let links: Vec<String> = members
+                .iter()
+                .take(TOPIC_MEMBERS)
+                .map(|(_, path)| self.new_link(note, path, None))
+                .collect();
+            let more = match members.len().saturating_sub(TOPIC_MEMBERS) {
+                0 => String::new(),
+                more => f!(" and {} more", more),
+            };
+            let line = f!("- **{}** ({} notes): {}{}", cluster.label, members.len(), links.join(", "), more);
+            topics.push((line, members.into_iter().map(|(_, path)| path.clone()).collect()));
+        }
+        Ok(topics)
+    }
+
+    /// The generated part of the index note at `note`, with its start and end comments.
+    ///
+    /// @private
+    fn vault_index(&self, note: &Path, options: &IndexOptions, topics: &[(String, Vec<PathBuf>)], summaries: &HashMap<String, String>) -> String {
+        let mut sections = Vec::new();
+
+        let mut folders = Vec::new();
+        for folder in self.list_folder(Path::new("")).folders {
+            let name = folder.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+            let notes = self.subtree(&folder).notes.iter().filter(|other| other.as_path() != note).count();
+            let entry = match self.folder_note(&folder) {
+                Some(target) => self.new_link(note, &target, Some(&name)),
+                None => f!("{}/", name),
+            };
+            folders.push(f!("- {} ({})", entry, plural(notes, "note")));
+        }
+        sections.push(("Folders", folders));
+
+        let graph = self.link_graph();
+        let mut hubs: Vec<(usize, &PathBuf)> = self
+            .files
+            .iter()
+            .filter(|(path, file)| file.get_mdfile().is_some() && path.as_path() != note)
+            .map(|(path, _)| {
+                let count = graph
+                    .incoming(path)
+                    .iter()
+                    .filter(|edge| &edge.source != path && edge.source != note)
+                    .count();
+                (count, path)
+            })
+            .filter(|(count, _)| *count > 0)
+            .collect();
+        hubs.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(b.1)));
+        let hubs = hubs
+            .into_iter()
+            .take(options.hubs)
+            .map(|(count, path)| f!("- {} ({} in)", self.new_link(note, path, None), plural(count, "link")))
+            .collect();
+        sections.push(("Hubs", hubs));
+
+        let mut recent: Vec<(chrono::NaiveDateTime, &PathBuf)> = Vec::new();
+        if options.recent > 0 {
+            for (path, file) in self.files.iter().filter(|(path, file)| file.get_mdfile().is_some() && path.as_path() != note) {
+                let Ok(timestamps) = file.timestamps(&self.timestamp_policy) else {
+                    continue;
+                };
+                if let Some(modified) = timestamps.modified.or(timestamps.fs_modified) {
+                    recent.push((modified, path));
+                }
+            }
+        }
+        recent.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(b.1)));
+        let recent = recent
+            .into_iter()
+            .take(options.recent)
+            .map(|(modified, path)| f!("- {} ({})", self.new_link(note, path, None), modified.format("%Y-%m-%d")))
+            .collect();
+        sections.push(("Recent", recent));
+
+        let topics = topics
+            .iter()
+            .map(|(line, _)| match summaries.get(line) {
+                // indented, so the summary belongs to the list item of its topic
+                Some(summary) => f!("{}\n  {}", line, summary),
+                None => line.clone(),
+            })
+            .collect();
+        sections.push(("Topics", topics));
+
+        let mut index = vec![VAULT_INDEX_START.to_string()];
+        for (heading, lines) in sections.into_iter().filter(|(_, lines)| !lines.is_empty()) {
+            index.push(f!("## {}\n\n{}\n", heading, lines.join("\n")));
+        }
+        index.push(VAULT_INDEX_END.to_string());
+        index.join("\n")
+    }
+}
+
+/// The topic summaries in the generated part of an index note, by the line of their topic.
+///
+/// @private
+fn existing_summaries(body: &str) -> HashMap<String, String> {
+    let mut summaries = HashMap::new();
+    let Some(start) = body.find(VAULT_INDEX_START) else {
+        return summaries;
+    };
+    let end = body[start..].find(VAULT_INDEX_END).map_or(body.len(), |end| start + end);
+    let lines: Vec<&str> = body[start..end].lines().collect();
+    for pair in lines.windows(2) {
+        if pair[0].starts_with("- **") {
+            if let Some(summary) = pair[1].strip_prefix("  ") {
+                summaries.insert(pair[0].to_string(), summary.trim().to_string());
+            }
+        }
+    }
+    summaries
+}
+
+/// A count with a noun, e.g. `1 note` or `3 notes`.
+///
+/// @private
+fn plural(count: usize, noun: &str) -> String {
+    match count {
+        1 => f!("1 {}", noun),
+        count => f!("{} {}s", count, noun),
+    }
+}
+
+#[cfg(test)]
+mod index_tests {
+    use super::*;
+    use crate::ai::api::mock::MockDriver;
+    use crate::ai::api::AIDriver;
+
+    #[tokio::test]
+    async fn test_update_index_note() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("Biology/Lectures")).unwrap();
+        std::fs::create_dir_all(dir.path().join("Physics")).unwrap();
+        std::fs::write(dir.path().join("Biology/Biology.md"), "---\nmodified: 2024-03-01T10:00\n---\n[[Mitosis]] [[Meiosis]]").unwrap();
+        std::fs::write(dir.path().join("Biology/Lectures/Mitosis.md"), "---\nmodified: 2024-03-03T10:00\n---\n[[Meiosis]]").unwrap();
+        std::fs::write(dir.path().join("Biology/Lectures/Meiosis.md"), "---\nmodified: 2024-03-02T10:00\n---\n# Meiosis").unwrap();
+        std::fs::write(dir.path().join("Physics/Forces.md"), "---\nmodified: 2024-01-01T10:00\n---\n# Forces").unwrap();
+        std::fs::write(dir.path().join("Home.md"), "# Home\n\nMy notes.\n").unwrap();
+        let mut vault = Vault::from_path(dir.path().to_path_buf()).unwrap();
+        let embeddings = [("Biology/Lectures/Mitosis.md", vec![1.0, 0.0]), ("Biology/Lectures/Meiosis.md", vec![0.9, 0.0]), ("Physics/Forces.md", vec![0.0, 1.0])];
+        for (path, embedding) in embeddings {
+            let file = vault.get_file_mut(&PathBuf::from(path)).unwrap();
+            file.get_mdfile_mut().unwrap().set_embedding(embedding);
+        }
+        let options = IndexOptions::default().with_recent(2).with_topics(Some(ClusterMethod::Threshold(0.5)));
+        let home = PathBuf::from("Home.md");
+
+        assert!(vault.update_index_note(&home, &options).await.unwrap());
+        let expected = "# Home\n\nMy notes.\n\n%% begin vault index %%\n\
+            ## Folders\n\n- [[Biology]] (3 notes)\n- Physics/ (1 note)\n\n\
+            ## Hubs\n\n- [[Meiosis]] (2 links in)\n- [[Mitosis]] (1 link in)\n\n\
+            ## Recent\n\n- [[Mitosis]] (2024-03-03)\n- [[Meiosis]] (2024-03-02)\n\n\
+            ## Topics\n\n- **Meiosis** (2 notes): [[Meiosis]], [[Mitosis]]\n\n\
+            %% end vault index %%\n";
+        assert_eq!(std::fs::read_to_string(dir.path().join(&home)).unwrap(), expected);
+        assert!(!vault.update_index_note(&home, &options).await.unwrap());
+
+        // a summary is asked for once, and kept while the topic lists the same notes
+        let summaries = options.clone().with_summaries(true);
+        assert!(matches!(vault.update_index_note(&home, &summaries).await, Err(Error::NoAIDriver)));
+        let mock = MockDriver::new().with_default_response("Cell division,\nin two kinds.");
+        vault.add_ai_driver(AIDriver::new_mock(mock.clone()));
+        assert!(vault.update_index_note(&home, &summaries).await.unwrap());
+        let index = std::fs::read_to_string(dir.path().join(&home)).unwrap();
+        assert!(index.contains("## Topics\n\n- **Meiosis** (2 notes): [[Meiosis]], [[Mitosis]]\n  Cell division, in two kinds.\n\n%% end"));
+        assert!(mock.prompts()[0].user_prompt.contains("**Mitosis**\n\n[[Meiosis]]"));
+        assert!(!vault.update_index_note(&home, &summaries).await.unwrap());
+        assert_eq!(mock.prompts().len(), 1);
+        assert!(vault.update_index_note(&home, &options).await.unwrap());
+        assert_eq!(std::fs::read_to_string(dir.path().join(&home)).unwrap(), expected);
+
+        assert!(vault.update_index_note(&PathBuf::from("Index.md"), &options.with_hubs(0).with_recent(0).with_topics(None)).await.unwrap());
+        let index = std::fs::read_to_string(dir.path().join("Index.md")).unwrap();
+        assert!(index.starts_with("# Index\n\n%% begin vault index %%\n## Folders\n"));
+    }
+}
@@ -0,0 +1,214 @@
+//! obsidian-driver::file::vault::iter
+//!
+//! This module contains sorted iteration over the files of the Vault: a FileIter walks the files in path order, or another FileOrder, and can be narrowed to a folder or an extension, without cloning the map of files.
+//!
+//! @public FileIter
+//!
+//! @public FileOrder
+//!
+//! @public Vault::iter_files
+//!
+//! @public Vault::iter_md_files
+
+// std imports
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+
+// first-party imports
+use super::Vault;
+use crate::file::mdfile::MDFile;
+use crate::file::File;
+
+/// The order a FileIter yields files in.
+///
+/// @public
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FileOrder {
+    /// By path relative to the vault root.
+    #[default]
+    Path,
+    /// By file name, ignoring case, then by path.
+    Name,
+    /// By file system modified time, oldest first, then by path. Files that were never read from disk come first.
+    Modified,
+}
+
+/// FileIter struct
+///
+/// An iterator over files of the Vault and their paths relative to the vault root. Files are
+/// yielded in path order unless sorted with `sorted_by`; `rev` walks them backwards.
+///
+/// # Example
+/// ```should_panic
+/// use std::path::{Path, PathBuf};
+///
+/// use obsidian_driver::file::vault::iter::FileOrder;
+/// use obsidian_driver::file::vault::Vault;
+///
+/// let vault = Vault::from_path(PathBuf::from("vault")).unwrap();
+/// let recent: Vec<&PathBuf> = vault
+/// 	.iter_md_files()
+/// 	.in_folder(Path::new("Courses"))
+/// 	.sorted_by(FileOrder::Modified)
+/// 	.rev()
+/// 	.take(10)
+/// 	.map(|(path, _)| path)
+/// 	.collect();
+/// ```
+///
+/// @public
+pub struct FileIter<'a, T> {
+    entries: VecDeque<(&'a PathBuf, &'a File)>,
+    project: fn(&'a File) -> &'a T,
+}
+
+impl<'a, T> FileIter<'a, T> {
+    /// Keep only the files anywhere below a folder.
+    ///
+    /// # Arguments
+    /// @param folder: &Path - Relative to the vault root
+    /// @return FileIter
+    pub fn in_folder(mut self, folder: &Path) -> Self {
+        self.entries.retain(|(path, _)| path.starts_with(folder));
+        self
+    }
+
+    /// Keep only the files with an extension, e.g. `pdf`.
+    ///
+    /// # Arguments
+    /// @param extension: &str - Without the dot
+    /// @return FileIter
+    pub fn with_extension(mut self, extension: &str) -> Self {
+        self.entries.retain(|(path, _)| path.extension().is_some_and(|ext| ext == extension));
+        self
+    }
+
+    /// Keep only the files for which a predicate on the path and file holds.
+    ///
+    /// Unlike `Iterator::filter`, the result can still be sorted and reversed.
+    ///
+    /// # Arguments
+    /// @param predicate: impl Fn(&Path, &T) -> bool
+    /// @return FileIter
+    pub fn matching(mut self, predicate: impl Fn(&Path, &T) -> bool) -> Self {
+        let project = self.project;
+        self.entries.retain(|(path, file)| predicate(path, project(file)));
+        self
+    }
+
+    /// Sort the remaining files.
+    ///
+    /// # Arguments
+    /// @param order: FileOrder
+    /// @return FileIter
+    pub fn sorted_by(mut self, order: FileOrder) -> Self {
+        let entries = self.entries.make_contiguous();
+        match order {
+            FileOrder::Path => entries.sort_by(|a, b| a.0.cmp(b.0)),
+            FileOrder::Name => entries.sort_by_cached_key(|(path, _)| (file_name(path).to_lowercase(), *path)),
+            FileOrder::Modified => entries.sort_by_key(|(path, file)| (file.last_modified, *path)),
+        }
+        self
+    }
+}
+
+impl<'a, T> Iterator for FileIter<'a, T> {
+    type Item = (&'a PathBuf, &'a T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (path, file) = self.entries.pop_front()?;
+        Some((path, (self.project)(file)))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.entries.len(), Some(self.entries.len()))
+    }
+}
+
+impl<T> DoubleEndedIterator for FileIter<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let (path, file) = self.entries.pop_back()?;
+        Some((path, (self.project)(file)))
+    }
+}
+
+impl<T> ExactSizeIterator for FileIter<'_, T> {}
+
+/// The file name of a path, or an empty string.
+///
+/// @private
+fn file_name(path: &Path) -> String {
+    path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default()
+}
+
+impl Vault {
+    /// Iterate over every file of the Vault in path order.
+    ///
+    /// # Arguments
+    /// @return FileIter<File> - Yields paths relative to the vault root and files
+    ///
+    /// # Example
+    /// ```should_panic
+    /// use std::path::PathBuf;
+    ///
+    /// use obsidian_driver::file::vault::Vault;
+    ///
+    /// let vault = Vault::from_path(PathBuf::from("vault")).unwrap();
+    /// let pdfs = vault.iter_files().with_extension("pdf").count();
+    /// ```
+    pub fn iter_files(&self) -> FileIter<'_, File> {
+        FileIter { entries: self.files.iter().collect(), project: |file| file }.sorted_by(FileOrder::Path)
+    }
+
+    /// Iterate over the markdown notes of the Vault in path order.
+    ///
+    /// # Arguments
+    /// @return FileIter<MDFile> - Yields paths relative to the vault root and notes
+    ///
+    /// # Example
+    /// ```should_panic
+    /// use std::path::PathBuf;
+    ///
+    /// use obsidian_driver::file::vault::Vault;
+    ///
+    /// let vault = Vault::from_path(PathBuf::from("vault")).unwrap();
+    /// let words: usize = vault.iter_md_files().map(|(_, mdfile)| mdfile.word_count()).sum();
+    /// ```
+    pub fn iter_md_files(&self) -> FileIter<'_, MDFile> {
+        FileIter {
+            entries: self.files.iter().filter(|(_, file)| file.get_mdfile().is_some()).collect(),
+            project: |file| file.get_mdfile().expect("only notes are iterated"),
+        }
+        .sorted_by(FileOrder::Path)
+    }
+}
+
+#[cfg(test)]
+mod iter_tests {
+    use super::*;
+
+    #[test]
+    fn test_iter_files_sorted_and_filtered() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("b")).unwrap();
+        std::fs::write(dir.path().join("b/Apple.md"), "# Apple").unwrap();
+        std::fs::write(dir.path().join("a.md"), "# a #fruit").unwrap();
+        std::fs::write(dir.path().join("c.md"), "# c #fruit").unwrap();
+        let vault = Vault::from_path(dir.path().to_path_buf()).unwrap();
+
+        let paths: Vec<&PathBuf> = vault.iter_files().map(|(path, _)| path).collect();
+        assert_eq!(paths, ["a.md", "b/Apple.md", "c.md"].map(PathBuf::from).iter().collect::<Vec<_>>());
+        let notes = vault.iter_md_files();
+        assert_eq!(notes.len(), 3);
+        let by_name: Vec<String> = notes.sorted_by(FileOrder::Name).rev().map(|(path, _)| file_name(path)).collect();
+        assert_eq!(by_name, ["c.md", "Apple.md", "a.md"]);
+        let fruit: Vec<&PathBuf> = vault
+            .iter_md_files()
+            .matching(|_, mdfile| mdfile.get_tags().contains(&"fruit".to_string()))
+            .map(|(path, _)| path)
+            .collect();
+        assert_eq!(fruit, [&PathBuf::from("a.md"), &PathBuf::from("c.md")]);
+        assert_eq!(vault.iter_files().in_folder(Path::new("b")).with_extension("md").count(), 1);
+        assert_eq!(vault.iter_files().with_extension("pdf").count(), 0);
+    }
+}
@@ -0,0 +1,606 @@
+//! obsidian-driver::file::vault::links
+//!
+//! This module contains link resolution between the notes of a Vault.
+//!
+//! @public BrokenLink
+//!
+//! @public BrokenLinkPolicy
+//!
+//! @public LinkStyle
+//!
+//! @public LinkPath
+//!
+//! @public Vault::set_link_style
+//!
+//! @public Vault::get_link_style
+//!
+//! @public Vault::resolve_link
+//!
+//! @public Vault::backlinks
+//!
+//! @public Vault::resolve_section
+//!
+//! @public Vault::resolve_block
+//!
+//! @public Vault::broken_heading_links
+//!
+//! @public Vault::broken_links
+
+// std imports
+use std::collections::BTreeSet;
+use std::path::{Component, Path, PathBuf};
+
+// third-party imports
+use serde::{Deserialize, Serialize};
+
+// first-party imports
+use super::Vault;
+use crate::file::mdfile::block::Block;
+use crate::file::mdfile::link::{Link, LinkKind};
+use crate::file::mdfile::section::Section;
+use crate::prelude::*;
+
+/// BrokenLink struct
+///
+/// A link in `source` (relative to the vault root) that no longer resolves to a file in the vault.
+///
+/// @public
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BrokenLink {
+    pub source: PathBuf,
+    pub link: Link,
+}
+
+/// What to do with links that break because of a vault operation.
+///
+/// @public
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BrokenLinkPolicy {
+    /// Do not look for broken links.
+    #[default]
+    Ignore,
+    /// Return the broken links without changing the referring notes.
+    Report,
+    /// Return the broken links and replace them with their display text in the referring notes.
+    Strip,
+}
+
+/// How the path of a link target is written, the "New link format" option of Obsidian.
+///
+/// @public
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LinkPath {
+    /// The file name when it resolves to the target, and the path from the vault root otherwise.
+    #[default]
+    Shortest,
+    /// The path relative to the folder of the note containing the link.
+    Relative,
+    /// The path from the vault root.
+    Absolute,
+}
+
+/// LinkStyle struct
+///
+/// How the Vault writes links when it creates or rewrites them. Rewritten links keep their kind,
+/// new links are markdown links or wikilinks as configured. The default is Obsidian's default;
+/// a vault uses the style in its `.obsidian/app.json`, see `Vault::get_link_style`.
+///
+/// # Example
+/// ```
+/// use obsidian_driver::file::vault::links::{LinkPath, LinkStyle};
+///
+/// let style = LinkStyle::default()
+/// 	.with_markdown(true)
+/// 	.with_path(LinkPath::Relative);
+/// ```
+///
+/// @public
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LinkStyle {
+    // write new links as markdown links instead of wikilinks
+    pub markdown: bool,
+    pub path: LinkPath,
+    // keep the `.md` extension of notes in wikilinks; markdown links always have it
+    pub extension: bool,
+}
+
+impl LinkStyle {
+    /// Write new links as `[text](Note.md)` markdown links instead of `[[Note]]` wikilinks.
+    ///
+    /// # Arguments
+    /// @param markdown: bool
+    /// @return LinkStyle
+    pub fn with_markdown(mut self, markdown: bool) -> Self {
+        self.markdown = markdown;
+        self
+    }
+
+    /// Set how the path of a link target is written.
+    ///
+    /// # Arguments
+    /// @param path: LinkPath
+    /// @return LinkStyle
+    pub fn with_path(mut self, path: LinkPath) -> Self {
+        self.path = path;
+        self
+    }
+
+    /// Keep the `.md` extension of notes in wikilinks, e.g. `[[Note.md]]`.
+    ///
+    /// # Arguments
+    /// @param extension: bool
+    /// @return LinkStyle
+    pub fn with_extension(mut self, extension: bool) -> Self {
+        self.extension = extension;
+        self
+    }
+}
+
+impl Vault {
+    /// Set the style of the links the Vault creates and rewrites, instead of the one in the Obsidian settings.
+    ///
+    /// # Arguments
+    /// @param style: LinkStyle
+    pub fn set_link_style(&mut self, style: LinkStyle) {
+        self.link_style = Some(style);
+    }
+
+    /// Get the style of the links the Vault creates and rewrites, e.g. when a note is moved.
+    /// Defaults to the style in the Obsidian settings, see `Vault::settings`.
+    ///
+    /// # Arguments
+    /// @return LinkStyle
+    pub fn get_link_style(&self) -> LinkStyle {
+        self.link_style.unwrap_or(self.settings.link_style)
+    }
+
+    /// Resolve a link in the note at `source` to the path of the file it points to.
+    ///
+    /// Targets are tried relative to the folder of `source`, then relative to the vault root,
+    /// then as the shortest path in the vault ending with the target (how Obsidian resolves
+    /// `[[Note]]`). A missing extension is taken to mean `.md`.
+    ///
+    /// # Arguments
+    /// @param source: &Path - The path of the note containing the link, relative to the vault root
+    /// @param link: &Link - The link to resolve
+    /// @return Option<PathBuf> - The path of the target relative to the vault root
+    ///
+    /// # Example
+    /// ```should_panic
+    /// use std::path::PathBuf;
+    ///
+    /// use obsidian_driver::file::vault::Vault;
+    ///
+    /// let vault = Vault::from_path(PathBuf::from("vault")).unwrap();
+    /// let source = PathBuf::from("note.md");
+    /// let links = vault.get_file(&source).unwrap().get_mdfile().unwrap().get_links();
+    /// let target = vault.resolve_link(&source, &links[0]);
+    /// ```
+    pub fn resolve_link(&self, source: &Path, link: &Link) -> Option<PathBuf> {
+        resolve_link_in(source, link, |path| self.files.contains_key(path), self.files.keys())
+    }
+
+    /// Resolve a link to a file of the Vault or to one of its attachments, which are not loaded and so not seen by `resolve_link`.
+    ///
+    /// # Arguments
+    /// @param source: &Path - The path of the note containing the link, relative to the vault root
+    /// @param link: &Link - The link to resolve
+    /// @param attachments: &BTreeSet<PathBuf> - e.g. from `Vault::attachments`
+    /// @return Option<PathBuf> - The path of the target relative to the vault root
+    ///
+    /// @super
+    pub(super) fn resolve_link_among(&self, source: &Path, link: &Link, attachments: &BTreeSet<PathBuf>) -> Option<PathBuf> {
+        let contains = |path: &Path| self.files.contains_key(path) || attachments.contains(path);
+        resolve_link_in(source, link, contains, self.files.keys().chain(attachments))
+    }
+
+    /// Get the files on disk the Vault has no parser for, such as images, relative to the vault root.
+    ///
+    /// # Arguments
+    /// @return Result<BTreeSet<PathBuf>>
+    ///
+    /// @super
+    pub(super) fn attachments(&self) -> Result<BTreeSet<PathBuf>> {
+        let mut attachments = BTreeSet::new();
+        let excluded = super::excluded_folders(&self.vault_root, self.backup_folder.as_ref());
+        let walk = walkdir::WalkDir::new(&self.vault_root)
+            .into_iter()
+            .filter_entry(|entry| !excluded.iter().any(|folder| entry.path() == folder));
+        for entry in walk {
+            let entry = entry?;
+            let path = entry.path().strip_prefix(&self.vault_root)?.to_path_buf();
+            if entry.file_type().is_file() && !self.files.contains_key(&path) {
+                attachments.insert(path);
+            }
+        }
+        Ok(attachments)
+    }
+
+    /// Resolve a link with headings, e.g. `[[Note#Part#Summary]]`, to the section of the target note it points at.
+    ///
+    /// References written as text, like `Note > Summary`, can be resolved with `Link::parse_reference`.
+    ///
+    /// # Arguments
+    /// @param source: &Path - The path of the note containing the link, relative to the vault root
+    /// @param link: &Link - The link to resolve
+    /// @return Option<(PathBuf, Section)> - The path of the target and the section, with ranges relative to its body. None if the link has no headings or does not resolve.
+    ///
+    /// # Example
+    /// ```should_panic
+    /// use std::path::PathBuf;
+    ///
+    /// use obsidian_driver::file::mdfile::link::Link;
+    /// use obsidian_driver::file::vault::Vault;
+    ///
+    /// let vault = Vault::from_path(PathBuf::from("vault")).unwrap();
+    /// let link = Link::parse_reference("Cells > Structure");
+    /// let (path, section) = vault.resolve_section(&PathBuf::from("note.md"), &link).unwrap();
+    /// let body = vault.get_file(&path).unwrap().get_mdfile().unwrap().get_body();
+    /// let text = &body[section.content_range];
+    /// ```
+    pub fn resolve_section(&self, source: &Path, link: &Link) -> Option<(PathBuf, Section)> {
+        let path = self.resolve_link(source, link)?;
+        let sections = self.files.get(&path)?.get_mdfile()?.get_sections();
+        let section = Section::find_path(&sections, &link.heading_path())?.clone();
+        Some((path, section))
+    }
+
+    /// Resolve a block reference, e.g. `[[Note#^block-id]]`, to the block of the target note it points at.
+    ///
+    /// # Arguments
+    /// @param source: &Path - The path of the note containing the link, relative to the vault root
+    /// @param link: &Link - The link to resolve
+    /// @return Option<(PathBuf, Block)> - The path of the target and the block, with ranges relative to its body. None if the link is not a block reference or does not resolve.
+    ///
+    /// # Example
+    /// ```should_panic
+    /// use std::path::PathBuf;
+    ///
+    /// use obsidian_driver::file::mdfile::link::Link;
+    /// use obsidian_driver::file::vault::Vault;
+    ///
+    /// let vault = Vault::from_path(PathBuf::from("vault")).unwrap();
+    /// let link = Link::parse_reference("[[Cells#^division]]");
+    /// let (path, block) = vault.resolve_block(&PathBuf::from("note.md"), &link).unwrap();
+    /// let body = vault.get_file(&path).unwrap().get_mdfile().unwrap().get_body();
+    /// let text = &body[block.range];
+    /// ```
+    pub fn resolve_block(&self, source: &Path, link: &Link) -> Option<(PathBuf, Block)> {
+        let id = link.block_id()?;
+        let path = self.resolve_link(source, link)?;
+        let block = self.files.get(&path)?.get_mdfile()?.get_block(id)?;
+        Some((path, block))
+    }
+
+    /// Get the links whose target note exists but no longer has the heading they point at.
+    ///
+    /// Links to missing files and block references (`#^id`) are not reported.
+    ///
+    /// # Arguments
+    /// @return Vec<BrokenLink> - The notes and links, sorted by source path
+    ///
+    /// # Example
+    /// ```should_panic
+    /// use std::path::PathBuf;
+    ///
+    /// use obsidian_driver::file::vault::Vault;
+    ///
+    /// let vault = Vault::from_path(PathBuf::from("vault")).unwrap();
+    /// for broken in vault.broken_heading_links() {
+    /// 	println!("{}: {}", broken.source.display(), broken.link.display_text());
+    /// }
+    /// ```
+    pub fn broken_heading_links(&self) -> Vec<BrokenLink> {
+        let mut broken = Vec::new();
+        for (source, file) in self.files.iter() {
+            let Some(mdfile) = file.get_mdfile() else {
+                continue;
+            };
+            for link in mdfile.get_links() {
+                if link.heading_path().is_empty() {
+                    continue;
+                }
+                let target = self.resolve_link(source, &link);
+                let is_note = target
+                    .as_ref()
+                    .and_then(|target| self.files.get(target))
+                    .is_some_and(|file| file.get_mdfile().is_some());
+                if is_note && self.resolve_section(source, &link).is_none() {
+                    broken.push(BrokenLink {
+                        source: source.clone(),
+                        link,
+                    });
+                }
+            }
+        }
+        broken.sort_by(|a, b| (&a.source, a.link.range.start).cmp(&(&b.source, b.link.range.start)));
+        broken
+    }
+
+    /// Get the links whose target does not resolve to a file in the vault.
+    ///
+    /// Links to web pages and links to a heading of their own note are not checked. Links to attachments,
+    /// like image embeds, resolve against the files on disk the Vault does not load.
+    ///
+    /// # Arguments
+    /// @return Result<Vec<BrokenLink>> - The notes and links, sorted by source path
+    ///
+    /// # Example
+    /// ```should_panic
+    /// use std::path::PathBuf;
+    ///
+    /// use obsidian_driver::file::vault::Vault;
+    ///
+    /// let vault = Vault::from_path(PathBuf::from("vault")).unwrap();
+    /// for broken in vault.broken_links().unwrap() {
+    /// 	println!("{}: {}", broken.source.display(), broken.link.target);
+    /// }
+    /// ```
+    pub fn broken_links(&self) -> Result<Vec<BrokenLink>> {
+        let attachments = self.attachments()?;
+        let mut broken = Vec::new();
+        for (source, file) in self.files.iter() {
+            let Some(mdfile) = file.get_mdfile() else {
+                continue;
+            };
+            for link in mdfile.get_links() {
+                if !link.target.is_empty() && self.resolve_link_among(source, &link, &attachments).is_none() {
+                    broken.push(BrokenLink {
+                        source: source.clone(),
+                        link,
+                    });
+                }
+            }
+        }
+        broken.sort_by(|a, b| (&a.source, a.link.range.start).cmp(&(&b.source, b.link.range.start)));
+        Ok(broken)
+    }
+
+    /// Get all links in other notes that resolve to the given path.
+    ///
+    /// # Arguments
+    /// @param path: &Path - The path of the target relative to the vault root
+    /// @return Vec<BrokenLink> - The referring notes and links, sorted by source path
+    ///
+    /// # Example
+    /// ```should_panic
+    /// use std::path::PathBuf;
+    ///
+    /// use obsidian_driver::file::vault::Vault;
+    ///
+    /// let vault = Vault::from_path(PathBuf::from("vault")).unwrap();
+    /// let backlinks = vault.backlinks(&PathBuf::from("note.md"));
+    /// ```
+    pub fn backlinks(&self, path: &Path) -> Vec<BrokenLink> {
+        let mut backlinks = Vec::new();
+        for (source, file) in self.files.iter() {
+            if source == path {
+                continue;
+            }
+            let Some(mdfile) = file.get_mdfile() else {
+                continue;
+            };
+            for link in mdfile.get_links() {
+                if self.resolve_link(source, &link).as_deref() == Some(path) {
+                    backlinks.push(BrokenLink {
+                        source: source.clone(),
+                        link,
+                    });
+                }
+            }
+        }
+        backlinks.sort_by(|a, b| (&a.source, a.link.range.start).cmp(&(&b.source, b.link.range.start)));
+        backlinks
+    }
+
+    /// Replace the given links with their display text (embeds are removed) and write the notes.
+    ///
+    /// # Arguments
+    /// @param links: &[BrokenLink] - The links to strip
+    /// @return Result<()>
+    pub(super) fn strip_links(&mut self, links: &[BrokenLink]) -> Result<()> {
+        let edits = links
+            .iter()
+            .map(|broken| {
+                let replacement = if broken.link.embed {
+                    String::new()
+                } else {
+                    broken.link.display_text()
+                };
+                (broken.source.clone(), broken.link.range.clone(), replacement)
+            })
+            .collect();
+        self.edit_bodies(edits)
+    }
+
+    /// Get the text of a link from `source` that resolves to `target`, in the link style of the Vault.
+    ///
+    /// The link keeps its kind, embed marker, heading and alias.
+    ///
+    /// # Arguments
+    /// @param source: &Path - The note containing the link, relative to the vault root
+    /// @param target: &Path - The file the link should point to, relative to the vault root
+    /// @param link: &Link - The link to rewrite
+    /// @return String - The new link text
+    pub(super) fn link_to(&self, source: &Path, target: &Path, link: &Link) -> String {
+        let style = self.get_link_style();
+        let keep_extension = style.extension || link.kind == LinkKind::Markdown;
+        let written = match target.extension() == Some("md".as_ref()) && !keep_extension {
+            true => target.with_extension(""),
+            false => target.to_path_buf(),
+        };
+        let absolute = to_link_path(&written);
+        let path = match style.path {
+            LinkPath::Absolute => absolute,
+            LinkPath::Relative => {
+                let folder = source.parent().unwrap_or(Path::new(""));
+                to_link_path(&relative_path(folder, &written))
+            }
+            LinkPath::Shortest => {
+                let mut short = link.clone();
+                short.target = written
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_default();
+                match self.resolve_link(source, &short).as_deref() == Some(target) {
+                    true => short.target,
+                    false => absolute,
+                }
+            }
+        };
+        link.with_target(&path)
+    }
+
+    /// Get the text of a new link from `source` to `target`, in the link style of the Vault.
+    ///
+    /// # Arguments
+    /// @param source: &Path - The note containing the link, relative to the vault root
+    /// @param target: &Path - The file the link should point to, relative to the vault root
+    /// @param text: Option<&str> - The text of the link; wikilinks leave it out when it is the file name
+    /// @return String - The link text
+    pub(super) fn new_link(&self, source: &Path, target: &Path, text: Option<&str>) -> String {
+        let name = target.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default();
+        let markdown = self.get_link_style().markdown;
+        let link = Link {
+            kind: if markdown { LinkKind::Markdown } else { LinkKind::Wiki },
+            embed: false,
+            target: String::new(),
+            heading: None,
+            alias: match (markdown, text) {
+                (true, text) => Some(text.unwrap_or(&name).to_string()),
+                (false, text) => text.filter(|text| *text != name).map(str::to_string),
+            },
+            range: 0..0,
+        };
+        self.link_to(source, target, &link)
+    }
+}
+
+/// Resolve a link among the given paths, as `Vault::resolve_link` does among the files of the vault.
+///
+/// @super
+pub(super) fn resolve_link_in<'a>(
+    source: &Path,
+    link: &Link,
+    contains: impl Fn(&Path) -> bool,
+    paths: impl Iterator<Item = &'a PathBuf> + Clone,
+) -> Option<PathBuf> {
+    if link.target.is_empty() {
+        return contains(source).then(|| source.to_path_buf());
+    }
+    let target = PathBuf::from(&link.target);
+    let mut candidates = vec![target.clone()];
+    if target.extension() != Some("md".as_ref()) {
+        candidates.push(PathBuf::from(f!("{}.md", link.target)));
+    }
+
+    for candidate in &candidates {
+        let relative = source
+            .parent()
+            .map(|parent| normalize_path(&parent.join(candidate)));
+        if let Some(relative) = relative.filter(|path| contains(path)) {
+            return Some(relative);
+        }
+        let absolute = normalize_path(candidate);
+        if contains(&absolute) {
+            return Some(absolute);
+        }
+    }
+    for candidate in &candidates {
+        let shortest = paths
+            .clone()
+            .filter(|path| path.ends_with(candidate))
+            .min_by_key(|path| (path.components().count(), (*path).clone()));
+        if let Some(shortest) = shortest {
+            return Some(shortest.clone());
+        }
+    }
+    None
+}
+
+/// Get the path of `target` relative to the folder `from`, both relative to the vault root.
+///
+/// @super
+pub(super) fn relative_path(from: &Path, target: &Path) -> PathBuf {
+    let from: Vec<Component> = from.components().collect();
+    let target: Vec<Component> = target.components().collect();
+    let common = from
+        .iter()
+        .zip(target.iter())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let mut relative = PathBuf::new();
+    for _ in common..from.len() {
+        relative.push("..");
+    }
+    for component in &target[common..] {
+        relative.push(component);
+    }
+    relative
+}
+
+/// Render a relative path with `/` separators, as used in links on every platform.
+///
+/// @super
+pub(super) fn to_link_path(path: &Path) -> String {
+    path.components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Normalize `.` and `..` components of a relative path without touching the filesystem.
+///
+/// @super
+pub(super) fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+#[cfg(test)]
+mod links_tests {
+    use super::*;
+
+    #[test]
+    fn test_links_follow_link_style() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join(".obsidian")).unwrap();
+        std::fs::create_dir_all(dir.path().join("notes")).unwrap();
+        std::fs::write(dir.path().join(".obsidian/app.json"), r#"{"useMarkdownLinks": true, "newLinkFormat": "relative"}"#).unwrap();
+        std::fs::write(dir.path().join("notes/a.md"), "[[b]] and [b](../b.md), about Cells").unwrap();
+        std::fs::write(dir.path().join("b.md"), "# B").unwrap();
+        std::fs::write(dir.path().join("Cells.md"), "# Cells").unwrap();
+        let mut vault = Vault::from_path(dir.path().to_path_buf()).unwrap();
+        assert_eq!(vault.get_link_style(), LinkStyle::default().with_markdown(true).with_path(LinkPath::Relative));
+
+        let suggestions = vault.suggest_links(Path::new("notes/a.md")).unwrap();
+        vault.apply_link_suggestions(&suggestions).unwrap();
+        vault.set_link_style(LinkStyle::default().with_path(LinkPath::Absolute).with_extension(true));
+        vault.move_file(&PathBuf::from("b.md"), &PathBuf::from("archive/b.md")).unwrap();
+        // `[[b]]` still resolves, so it is left as it is
+        let a = std::fs::read_to_string(dir.path().join("notes/a.md")).unwrap();
+        assert_eq!(a, "[[b]] and [b](archive/b.md), about [Cells](../Cells.md)");
+    }
+
+    #[test]
+    fn test_broken_links_resolve_attachments() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("img")).unwrap();
+        std::fs::write(dir.path().join("img/cell.png"), [0u8]).unwrap();
+        std::fs::write(dir.path().join("a.md"), "![[cell.png]] ![cell](img/cell.png) ![[missing.png]] [[b]]").unwrap();
+        let vault = Vault::from_path(dir.path().to_path_buf()).unwrap();
+
+        let broken = vault.broken_links().unwrap();
+        let targets: Vec<&str> = broken.iter().map(|broken| broken.link.target.as_str()).collect();
+        assert_eq!(targets, vec!["missing.png", "b"]);
+    }
+}
@@ -0,0 +1,255 @@
+//! obsidian-driver::file::vault::merge
+//!
+//! This module contains the merge workflow: groups of notes with similar embeddings are merged by the AI model into one note, which can replace the originals.
+//!
+//! @public MergeOptions
+//!
+//! @public MergedGroup
+//!
+//! @public Vault::merge_similar
+
+// std imports
+use std::path::{Path, PathBuf};
+
+// third-party imports
+use serde::{Deserialize, Serialize};
+
+// first-party imports
+use super::cluster::ClusterMethod;
+use super::events::VaultEvent;
+use super::Vault;
+use crate::ai::merge_files;
+use crate::file::naming::{resolve_collision, CollisionStrategy};
+use crate::prelude::*;
+
+/// MergeOptions struct
+///
+/// Options for `Vault::merge_similar`. By default merged notes are written next to the originals,
+/// which are kept, and links to the originals are redirected to the merged notes.
+///
+/// # Example
+/// ```
+/// use std::path::PathBuf;
+///
+/// use obsidian_driver::file::vault::merge::MergeOptions;
+///
+/// let options = MergeOptions::default()
+/// 	.archive_to(PathBuf::from("Archive"))
+/// 	.with_dry_run(true);
+/// ```
+///
+/// @public
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MergeOptions {
+    // move the originals into this folder, relative to the vault root
+    pub archive_folder: Option<PathBuf>,
+    pub redirect_links: bool,
+    // only report the groups, without merging them
+    pub dry_run: bool,
+}
+
+impl Default for MergeOptions {
+    fn default() -> Self {
+        MergeOptions {
+            archive_folder: None,
+            redirect_links: true,
+            dry_run: false,
+        }
+    }
+}
+
+impl MergeOptions {
+    /// Move the merged notes into a folder, relative to the vault root.
+    ///
+    /// # Arguments
+    /// @param folder: PathBuf
+    /// @return MergeOptions
+    pub fn archive_to(mut self, folder: PathBuf) -> Self {
+        self.archive_folder = Some(folder);
+        self
+    }
+
+    /// Rewrite the links to the merged notes in other notes so they point at the new note.
+    ///
+    /// # Arguments
+    /// @param redirect_links: bool
+    /// @return MergeOptions
+    pub fn with_redirect_links(mut self, redirect_links: bool) -> Self {
+        self.redirect_links = redirect_links;
+        self
+    }
+
+    /// Only find the groups; nothing is sent to the AI model or written.
+    ///
+    /// # Arguments
+    /// @param dry_run: bool
+    /// @return MergeOptions
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+}
+
+/// MergedGroup struct
+///
+/// A group of similar notes and what `Vault::merge_similar` did with it. Paths are relative to the vault root.
+///
+/// @public
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MergedGroup {
+    // sorted by path
+    pub members: Vec<PathBuf>,
+    // None for a dry run
+    pub merged: Option<PathBuf>,
+    // the new paths of the members, if they were archived
+    pub archived: Vec<PathBuf>,
+    // the notes whose links were redirected to the merged note
+    pub redirected: Vec<PathBuf>,
+}
+
+impl Vault {
+    /// Merge every group of notes whose embeddings are at most `threshold` apart into one note.
+    ///
+    /// Groups are found with `ClusterMethod::Threshold`, and each group of two or more notes is
+    /// merged with `ai::merge_files`. The merged note is named after the member closest to the
+    /// group's centroid and written to its folder; if that path is taken (the originals are
+    /// kept), ` 1`, ` 2`, ... is added to the name. Notes need embeddings, see
+    /// `Vault::update_embeddings`.
+    ///
+    /// # Arguments
+    /// @param threshold: f64 - The embedding distance
+    /// @param options: &MergeOptions
+    /// @return Result<Vec<MergedGroup>> - The groups, largest first. Errors with `NoAIDriver` if no driver was added.
+    ///
+    /// # Example
+    /// ```
+    /// use std::path::PathBuf;
+    ///
+    /// use obsidian_driver::ai::api::AIDriver;
+    /// use obsidian_driver::file::vault::Vault;
+    /// use obsidian_driver::file::vault::merge::MergeOptions;
+    ///
+    /// async fn merge_similar_example() {
+    /// 	let mut vault = Vault::from_path(PathBuf::from("vault")).unwrap();
+    /// 	vault.add_ai_driver(AIDriver::new_openai_from_config_path(PathBuf::from(".openai_config.json")).await.unwrap());
+    /// 	vault.update_embeddings().await.unwrap();
+    ///
+    /// 	let options = MergeOptions::default().archive_to(PathBuf::from("Archive"));
+    /// 	for group in vault.merge_similar(0.3, &options).await.unwrap() {
+    /// 		println!("{:?} -> {:?}", group.members, group.merged);
+    /// 	}
+    /// }
+    /// ```
+    pub async fn merge_similar(&mut self, threshold: f64, options: &MergeOptions) -> Result<Vec<MergedGroup>> {
+        let driver = self.aidriver.clone().ok_or(Error::NoAIDriver)?;
+        let clusters = self.cluster_notes(ClusterMethod::Threshold(threshold))?;
+        let mut groups = Vec::new();
+        for cluster in clusters {
+            let members: Vec<PathBuf> = cluster
+                .members
+                .into_iter()
+                .filter(|path| self.files.get(path).is_some_and(|file| file.get_mdfile().is_some()))
+                .collect();
+            if members.len() < 2 {
+                continue;
+            }
+            let mut group = MergedGroup {
+                members,
+                merged: None,
+                archived: Vec::new(),
+                redirected: Vec::new(),
+            };
+            if options.dry_run {
+                groups.push(group);
+                continue;
+            }
+
+            let files = group.members.iter().map(|path| &self.files[path]).collect();
+            let merged = merge_files(&driver, files).await?;
+
+            // the originals are moved first, so the merged note can take the name of one
+            if let Some(archive_folder) = &options.archive_folder {
+                for member in group.members.iter() {
+                    let name = member.file_name().map(PathBuf::from).unwrap_or_default();
+                    let archived = self.available_path(&archive_folder.join(name))?;
+                    self.move_file(member, &archived)?;
+                    group.archived.push(archived);
+                }
+            }
+            let folder = group.members[0].parent().unwrap_or(Path::new("")).to_path_buf();
+            let merged_path = self.available_path(&folder.join(f!("{}.md", cluster.label)))?;
+            let file = crate::file::File::from_mdfile(self.vault_root.join(&merged_path), merged.mdfile);
+            self.files.insert(merged_path.clone(), file);
+            self.notify(VaultEvent::FileAdded(merged_path.clone()));
+            self.write_file(&merged_path)?;
+
+            if options.redirect_links {
+                let originals = if group.archived.is_empty() { &group.members } else { &group.archived };
+                let mut edits = Vec::new();
+                for original in originals {
+                    for backlink in self.backlinks(original) {
+                        if originals.contains(&backlink.source) || backlink.source == merged_path {
+                            continue;
+                        }
+                        let text = self.link_to(&backlink.source, &merged_path, &backlink.link);
+                        edits.push((backlink.source, backlink.link.range, text));
+                    }
+                }
+                group.redirected = edits.iter().map(|(source, ..)| source.clone()).collect();
+                group.redirected.sort();
+                group.redirected.dedup();
+                self.edit_bodies(edits)?;
+            }
+            group.merged = Some(merged_path);
+            groups.push(group);
+        }
+        Ok(groups)
+    }
+
+    /// The path itself if no file of the vault is there, otherwise the first free path with ` 1`, ` 2`, ... added to the stem.
+    ///
+    /// @private
+    fn available_path(&self, path: &Path) -> Result<PathBuf> {
+        let taken = |path: &Path| self.files.contains_key(path) || self.vault_root.join(path).exists();
+        resolve_collision(path, CollisionStrategy::NumericSuffix, taken)
+    }
+}
+
+#[cfg(test)]
+mod merge_tests {
+    use super::*;
+    use crate::ai::api::mock::MockDriver;
+    use crate::ai::api::AIDriver;
+
+    #[tokio::test]
+    async fn test_merge_similar_archives_and_redirects() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.md"), "Cells divide.").unwrap();
+        std::fs::write(dir.path().join("b.md"), "Cells split.").unwrap();
+        std::fs::write(dir.path().join("c.md"), "See [[a]] and [b](b.md).").unwrap();
+        let mut vault = Vault::from_path(dir.path().to_path_buf()).unwrap();
+        let embeddings = [("a.md", vec![1.0, 0.0]), ("b.md", vec![1.0, 0.0]), ("c.md", vec![0.0, 1.0])];
+        for (path, embedding) in embeddings {
+            let file = vault.get_file_mut(&PathBuf::from(path)).unwrap();
+            file.get_mdfile_mut().unwrap().set_embedding(embedding);
+        }
+        vault.add_ai_driver(AIDriver::new_mock(MockDriver::new().with_responses(["# Cells"])));
+
+        let dry_run = vault.merge_similar(0.5, &MergeOptions::default().with_dry_run(true)).await.unwrap();
+        assert_eq!(dry_run.len(), 1);
+        assert_eq!(dry_run[0].merged, None);
+
+        let options = MergeOptions::default().archive_to(PathBuf::from("Archive"));
+        let groups = vault.merge_similar(0.5, &options).await.unwrap();
+        let expected = MergedGroup {
+            members: vec![PathBuf::from("a.md"), PathBuf::from("b.md")],
+            merged: Some(PathBuf::from("a.md")),
+            archived: vec![PathBuf::from("Archive/a.md"), PathBuf::from("Archive/b.md")],
+            redirected: vec![PathBuf::from("c.md")],
+        };
+        assert_eq!(groups, vec![expected]);
+        assert_eq!(std::fs::read_to_string(dir.path().join("a.md")).unwrap(), "# Cells");
+        assert_eq!(std::fs::read_to_string(dir.path().join("c.md")).unwrap(), "See [[a]] and [b](a.md).");
+        assert!(dir.path().join("Archive/b.md").exists());
+    }
+}
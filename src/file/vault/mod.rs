@@ -1,19 +1,107 @@
 //! obsidian-driver::file::vault
 //!
 //! This module contains the Vault struct and its implementations. This struct is used to provide the main public interface for the library.
+//!
+//! @public Vault
+//!
+//! @public ImportedEmbedding
+//!
+//! @public ImportReport
+//!
+//! @public events
+//!
+//! @public folders
+//!
+//! @public format
+//!
+//! @public images
+//!
+//! @public iter
+//!
+//! @public links
+//!
+//! @public profile
+//!
+//! @public properties
+//!
+//! @public query
+//!
+//! @public repair
+//!
+//! @public review
+//!
+//! @public saved_queries
+//!
+//! @public schema
+//!
+//! @public settings
+//!
+//! @public tagging
+//!
+//! @public templates
+//!
+//! @public terminology
+//!
+//! @public timestamps
 
 // std imports
 use std::collections::HashMap;
-use std::path::{Path, PathBuf};
+use std::ops::Range;
+use std::path::PathBuf;
 
 // third-party imports
 use kdtree::distance::squared_euclidean;
 use kdtree::KdTree;
+use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
+use tracing::{debug, info, info_span, warn};
 
 // first-party imports
-use crate::file::mdfile::MDFile;
+use crate::batch::{BatchReport, BatchStatus, CancellationToken};
+use crate::file::mdfile::task::{Task, TaskFilter};
 use crate::prelude::*;
+use events::VaultEvent;
+use profile::{Operation, Phase};
+
+// submodules
+pub mod anki;
+pub mod ask;
+pub mod autolink;
+pub mod backup;
+pub mod cache;
+pub mod cluster;
+pub mod daily;
+mod embedding_store;
+pub mod embeds;
+pub mod enrich;
+pub mod events;
+pub mod export;
+pub mod folders;
+pub mod format;
+pub mod frontmatter;
+pub mod graph;
+pub mod images;
+pub mod index;
+pub mod iter;
+pub mod links;
+pub mod merge;
+pub mod persist;
+pub mod profile;
+pub mod prompts;
+pub mod properties;
+pub mod query;
+pub mod related;
+pub mod repair;
+pub mod review;
+pub mod saved_queries;
+pub mod schema;
+pub mod search;
+pub mod settings;
+pub mod tagging;
+pub mod templates;
+pub mod terminology;
+pub mod timestamps;
+pub mod trash;
 
 /// Vault struct
 ///
@@ -22,17 +110,96 @@ use crate::prelude::*;
 pub struct Vault {
     // the key is the local path to the file from the vault root
     files: HashMap<PathBuf, crate::file::File>,
+    // saved in the cache, so queries do not need to read every note's front matter
+    #[serde(default)]
+    properties: properties::PropertyIndex,
     // ignore the vault root for now
     #[serde(skip)]
     vault_root: PathBuf,
 
     #[serde(skip)]
     aidriver: Option<crate::ai::api::AIDriver>,
+
+    #[serde(skip)]
+    embedding_store: Option<embedding_store::EmbeddingStore>,
+
+    #[serde(skip)]
+    profiler: profile::Profiler,
+
+    #[serde(skip)]
+    settings: settings::VaultSettings,
+
+    #[serde(skip)]
+    link_style: Option<links::LinkStyle>,
+
+    #[serde(skip)]
+    naming_policy: crate::file::naming::NamingPolicy,
+
+    #[serde(skip)]
+    timestamp_policy: crate::file::timestamps::TimestampPolicy,
+
+    #[serde(skip)]
+    template_folder: Option<PathBuf>,
+
+    #[serde(skip)]
+    prompt_folder: Option<PathBuf>,
+
+    #[serde(skip)]
+    query_note: Option<PathBuf>,
+
+    #[serde(skip)]
+    backup_folder: Option<PathBuf>,
+
+    #[serde(skip)]
+    schemas: Vec<(query::QuerySource, schema::FrontmatterSchema)>,
+
+    #[serde(skip)]
+    events: events::EventBus,
+}
+
+/// ImportedEmbedding struct
+///
+/// A single record of an embedding file produced outside of the library (e.g. by a GPU batch job).
+/// Records are matched to notes by their path relative to the vault root, or failing that by the
+/// content hash of the note (see `File::content_hash`).
+///
+/// # Example
+/// ```
+/// use std::path::PathBuf;
+///
+/// use obsidian_driver::file::vault::ImportedEmbedding;
+///
+/// let record: ImportedEmbedding = serde_json::from_str(r#"{"path": "note.md", "embedding": [0.1, 0.2]}"#).unwrap();
+/// assert_eq!(record.path, Some(PathBuf::from("note.md")));
+/// ```
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ImportedEmbedding {
+    #[serde(default)]
+    pub path: Option<PathBuf>,
+    #[serde(default)]
+    pub hash: Option<String>,
+    pub embedding: Vec<f64>,
+}
+
+/// ImportReport struct
+///
+/// The outcome of `Vault::import_embeddings`: the notes that received an embedding, and the notes
+/// whose record was skipped because its content hash no longer matches the note.
+///
+/// @public
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImportReport {
+    pub imported: Vec<PathBuf>,
+    pub stale: Vec<PathBuf>,
 }
 
 impl Vault {
     /// Create a new Vault from a given path.
     ///
+    /// Files are read and parsed in parallel on the rayon thread pool. Obsidian's `.obsidian` and
+    /// `.trash` folders are skipped. Only notes and files of registered
+    /// types are loaded; other files, such as images and PDFs, are attachments and stay on disk only.
+    ///
     /// # Arguments
     /// @param vault_root: PathBuf
     /// @return Result<Self>
@@ -47,25 +214,75 @@ impl Vault {
     /// let vault = Vault::from_path(vault_root).unwrap();
     /// ```
     pub fn from_path(vault_root: PathBuf) -> Result<Self> {
+        Self::from_path_until(vault_root, &CancellationToken::new())
+    }
+
+    /// Create a new Vault from a given path, stopping early if `shutdown` is cancelled.
+    ///
+    /// The scan checks the token between directory entries and before reading each file, so a
+    /// large vault stops loading promptly. A partly loaded vault is not returned.
+    ///
+    /// # Arguments
+    /// @param vault_root: PathBuf
+    /// @param shutdown: &CancellationToken
+    /// @return Result<Self> - Errors with `Cancelled` if the token was cancelled before the scan finished
+    ///
+    /// # Example
+    /// ```
+    /// use std::path::PathBuf;
+    ///
+    /// use obsidian_driver::batch::CancellationToken;
+    /// use obsidian_driver::error::Error;
+    /// use obsidian_driver::file::vault::Vault;
+    ///
+    /// let shutdown = CancellationToken::new();
+    /// shutdown.cancel();
+    /// let vault = Vault::from_path_until(PathBuf::from("."), &shutdown);
+    /// assert!(matches!(vault, Err(Error::Cancelled)));
+    /// ```
+    pub fn from_path_until(vault_root: PathBuf, shutdown: &CancellationToken) -> Result<Self> {
+        let profiler = profile::Profiler::default();
         let mut files = HashMap::new();
         let vault_root = vault_root.canonicalize()?;
-        for entry in walkdir::WalkDir::new(&vault_root) {
-            let entry = entry?;
-            let path = entry.path().to_path_buf();
-            if path.is_file() {
-                let file = crate::file::File::read_file(path.clone())?;
-                let path = path.canonicalize()?;
-                let path = path.strip_prefix(&vault_root)?.to_path_buf();
-                files.insert(path.clone(), file);
-            }
+        let _span = info_span!("load_vault", root = %vault_root.display()).entered();
+        let excluded = excluded_folders(&vault_root, None);
+        let entries = profiler.time(Operation::Load, Phase::Io, || scan(&vault_root, &excluded, shutdown))?;
+        if shutdown.is_cancelled() {
+            return Err(Error::Cancelled);
         }
+        let paths: Vec<PathBuf> = entries
+            .into_iter()
+            .map(|entry| entry.path().to_path_buf())
+            .filter(|path| path.is_file() && crate::file::File::is_loadable(path))
+            .collect();
+        for (path, file) in paths.iter().zip(read_files(&profiler, &paths, shutdown)) {
+            let file = file?;
+            let path = path.canonicalize()?;
+            let path = path.strip_prefix(&vault_root)?.to_path_buf();
+            files.insert(path, file);
+        }
+        info!(files = files.len(), "vault loaded");
 
         let aidriver = None;
+        let settings = load_settings(&vault_root);
 
         Ok(Self {
+            properties: properties::PropertyIndex::build(&files),
             files,
             vault_root,
             aidriver,
+            embedding_store: None,
+            profiler,
+            settings,
+            link_style: None,
+            naming_policy: Default::default(),
+            timestamp_policy: Default::default(),
+            template_folder: None,
+            prompt_folder: None,
+            query_note: None,
+            backup_folder: None,
+            schemas: Vec::new(),
+            events: events::EventBus::default(),
         })
     }
 
@@ -91,50 +308,134 @@ impl Vault {
         if !cache_path.exists() {
             return Self::from_path(vault_root);
         }
-        let cache_str: String = std::fs::read_to_string(cache_path)?;
-        let mut vault: Self = serde_json::from_str(&cache_str)?;
+        let profiler = profile::Profiler::default();
+        let cache_str: String =
+            profiler.time(Operation::Load, Phase::Io, || std::fs::read_to_string(cache_path))?;
+        let mut vault: Self =
+            profiler.time(Operation::Load, Phase::Parsing, || serde_json::from_str(&cache_str))?;
+        vault.profiler = profiler;
+        vault.refresh_from_disk(vault_root)?;
+        Ok(vault)
+    }
 
-        let vault_root = vault_root.canonicalize()?;
-        vault.vault_root.clone_from(&vault_root);
+    /// Set the vault root, then insert files that are not in the vault yet and re-read files modified since they were loaded.
+    ///
+    /// # Arguments
+    /// @param vault_root: PathBuf
+    /// @return Result<()>
+    ///
+    /// @private
+    fn refresh_from_disk(&mut self, vault_root: PathBuf) -> Result<()> {
+        self.vault_root = vault_root.canonicalize()?;
+        self.settings = load_settings(&self.vault_root);
+        self.refresh_until(&CancellationToken::new())?;
+        if !self.properties.covers(&self.files) {
+            self.reindex_properties();
+        }
+        Ok(())
+    }
+
+    /// Insert files that are not in the vault yet and re-read files modified since they were loaded, stopping early if `shutdown` is cancelled.
+    ///
+    /// Files re-read before the token was cancelled are kept in the vault; the others keep their
+    /// old contents and are listed as remaining. Files removed from disk are not removed from the
+    /// vault.
+    ///
+    /// # Arguments
+    /// @param shutdown: &CancellationToken
+    /// @return Result<BatchReport> - The re-read files, relative to the vault root
+    ///
+    /// # Example
+    /// ```should_panic
+    /// use std::path::PathBuf;
+    ///
+    /// use obsidian_driver::batch::CancellationToken;
+    /// use obsidian_driver::file::vault::Vault;
+    ///
+    /// let cache_path = PathBuf::from("vault_cache.json");
+    /// let mut vault = Vault::from_cache(PathBuf::from("vault"), &cache_path).unwrap();
+    /// let report = vault.refresh_until(&CancellationToken::new()).unwrap();
+    /// println!("{} notes changed on disk", report.completed.len());
+    /// ```
+    pub fn refresh_until(&mut self, shutdown: &CancellationToken) -> Result<BatchReport> {
+        let profiler = self.profiler.clone();
+        let vault_root = self.vault_root.clone();
+        let mut report = BatchReport::default();
 
         // for all files in vault root, insert / update them if they are not in the cache / not up to date
-        for entry in walkdir::WalkDir::new(&vault_root) {
-            let entry = entry?;
+        let excluded = excluded_folders(&vault_root, self.backup_folder.as_ref());
+        let entries = profiler.time(Operation::Load, Phase::Io, || scan(&vault_root, &excluded, shutdown))?;
+        if shutdown.is_cancelled() {
+            report.status = BatchStatus::Cancelled;
+            return Ok(report);
+        }
+        let mut stale = Vec::new();
+        for entry in entries {
             let path = entry.path();
-            if !path.is_file() {
+            if !path.is_file() || !crate::file::File::is_loadable(path) {
                 continue;
             }
             let local_path = path.canonicalize()?;
             let local_path = local_path.strip_prefix(&vault_root)?.to_path_buf();
-            if let std::collections::hash_map::Entry::Vacant(e) =
-                vault.files.entry(local_path.clone())
-            {
-                let file = crate::file::File::read_file(path.to_path_buf())?;
-                e.insert(file);
-            } else {
-                let file = vault
-                    .files
-                    .get_mut(&local_path)
-                    .expect("File not found in vault");
-                let last_modified = std::fs::metadata(&path)?
+            if let Some(file) = self.files.get(&local_path) {
+                let last_modified = std::fs::metadata(path)?
                     .modified()?
                     .duration_since(std::time::SystemTime::UNIX_EPOCH)?
                     .as_millis();
-                if file.last_modified < Some(last_modified) {
-                    let contents = std::fs::read_to_string(&path)?;
-                    *file = crate::file::File::new_raw(
-                        path.to_path_buf(),
-                        path.extension()
-                            .expect("No extension found")
-                            .to_str()
-                            .expect("Invalid extension"),
-                        contents,
-                        Some(last_modified),
-                    )?;
+                if file.last_modified >= Some(last_modified) {
+                    continue;
                 }
             }
+            stale.push((local_path, path.to_path_buf()));
         }
-        Ok(vault)
+        let paths: Vec<PathBuf> = stale.iter().map(|(_, path)| path.clone()).collect();
+        for ((local_path, _), file) in stale.into_iter().zip(read_files(&profiler, &paths, shutdown)) {
+            match file {
+                Ok(file) => {
+                    let event = match self.files.insert(local_path.clone(), file) {
+                        Some(_) => VaultEvent::FileModified(local_path.clone()),
+                        None => VaultEvent::FileAdded(local_path.clone()),
+                    };
+                    self.notify(event);
+                    report.completed.push(local_path);
+                }
+                Err(Error::Cancelled) => {
+                    report.status = BatchStatus::Cancelled;
+                    report.remaining.push(local_path);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(report)
+    }
+
+    /// Remove the files that no longer exist on disk from the Vault, the counterpart of `refresh_until`.
+    ///
+    /// # Arguments
+    /// @return Vec<PathBuf> - The removed files, sorted
+    ///
+    /// # Example
+    /// ```should_panic
+    /// use std::path::PathBuf;
+    ///
+    /// use obsidian_driver::file::vault::Vault;
+    ///
+    /// let mut vault = Vault::from_cache(PathBuf::from("vault"), &PathBuf::from("vault_cache.json")).unwrap();
+    /// let deleted = vault.forget_missing_files();
+    /// ```
+    pub fn forget_missing_files(&mut self) -> Vec<PathBuf> {
+        let mut missing: Vec<PathBuf> = self
+            .files
+            .keys()
+            .filter(|path| !self.vault_root.join(path).is_file())
+            .cloned()
+            .collect();
+        missing.sort();
+        for path in &missing {
+            self.files.remove(path);
+            self.notify(VaultEvent::FileRemoved(path.clone()));
+        }
+        missing
     }
 
     /// Write the Vault to a cache file.
@@ -143,9 +444,23 @@ impl Vault {
     /// @param cache_path: &PathBuf
     /// @return Result<()>
     ///
+    /// If an embedding store is attached, embeddings are written to the store instead of the cache.
     pub fn to_cache(&self, cache_path: &PathBuf) -> Result<()> {
-        let cache_str = serde_json::to_string(self)?;
-        std::fs::write(cache_path, cache_str)?;
+        let cache_str = match &self.embedding_store {
+            Some(store) => {
+                let mut vault = self.clone();
+                let mut embeddings = Vec::new();
+                for (path, file) in vault.files.iter_mut() {
+                    if let Some(embedding) = file.take_embedding() {
+                        embeddings.push((path.clone(), file.content_hash(), embedding));
+                    }
+                }
+                store.save(embeddings.into_iter(), &vault.files.keys().collect())?;
+                serde_json::to_string(&vault)?
+            }
+            None => serde_json::to_string(self)?,
+        };
+        crate::file::write_atomic(cache_path, cache_str)?;
         Ok(())
     }
 
@@ -169,7 +484,8 @@ impl Vault {
         let path = path.canonicalize()?;
         let path = path.strip_prefix(&self.vault_root)?.to_path_buf();
 
-        self.files.insert(path, file);
+        self.files.insert(path.clone(), file);
+        self.notify(VaultEvent::FileAdded(path));
         Ok(())
     }
 
@@ -192,6 +508,198 @@ impl Vault {
         Ok(())
     }
 
+    /// Remove a file from the Vault and move it to the `.trash` folder of the vault, as Obsidian does. Uses the path relative to the vault root.
+    ///
+    /// Files in the trash can be restored by hand until `Vault::purge_trash` is called.
+    ///
+    /// Depending on `policy`, links in other notes that pointed at the removed file and no longer
+    /// resolve are reported, or reported and replaced with their display text.
+    ///
+    /// # Arguments
+    /// @param path: &PathBuf
+    /// @param policy: links::BrokenLinkPolicy
+    /// @return Result<Vec<links::BrokenLink>> - The links broken by the removal
+    ///
+    /// # Example
+    /// ```should_panic
+    /// use std::path::PathBuf;
+    ///
+    /// use obsidian_driver::file::vault::Vault;
+    /// use obsidian_driver::file::vault::links::BrokenLinkPolicy;
+    ///
+    /// let mut vault = Vault::from_path(PathBuf::from("vault")).unwrap();
+    /// let broken = vault.remove_file(&PathBuf::from("note.md"), BrokenLinkPolicy::Report).unwrap();
+    /// ```
+    pub fn remove_file(
+        &mut self,
+        path: &PathBuf,
+        policy: links::BrokenLinkPolicy,
+    ) -> Result<Vec<links::BrokenLink>> {
+        if !self.files.contains_key(path) {
+            return Err(Error::PathNotFound(path.clone()));
+        }
+        let referring = match policy {
+            links::BrokenLinkPolicy::Ignore => Vec::new(),
+            _ => self.backlinks(path),
+        };
+
+        self.backup_file(path)?;
+        self.trash_file(path)?;
+        self.files.remove(path);
+        self.notify(VaultEvent::FileRemoved(path.clone()));
+
+        let broken: Vec<links::BrokenLink> = referring
+            .into_iter()
+            .filter(|backlink| self.resolve_link(&backlink.source, &backlink.link).is_none())
+            .collect();
+        for link in broken.iter() {
+            self.notify(VaultEvent::LinkBroken(link.clone()));
+        }
+        if policy == links::BrokenLinkPolicy::Strip {
+            self.strip_links(&broken)?;
+        }
+        Ok(broken)
+    }
+
+    /// Move a file to a new path in the Vault, on disk and in memory. Uses paths relative to the vault root.
+    ///
+    /// Destination folders are created as needed and the file keeps its embedding. Links in
+    /// other notes that pointed at the file, and relative links in the moved note itself, are
+    /// rewritten so they keep resolving to the same files.
+    ///
+    /// # Arguments
+    /// @param from: &PathBuf
+    /// @param to: &PathBuf
+    /// @return Result<Vec<PathBuf>> - The notes whose links were rewritten. Errors with `PathNotInVaultRoot` if `to` is absolute or has `..` components.
+    ///
+    /// # Example
+    /// ```should_panic
+    /// use std::path::PathBuf;
+    ///
+    /// use obsidian_driver::file::vault::Vault;
+    ///
+    /// let mut vault = Vault::from_path(PathBuf::from("vault")).unwrap();
+    /// let rewritten = vault.move_file(&PathBuf::from("note.md"), &PathBuf::from("archive/note.md")).unwrap();
+    /// ```
+    pub fn move_file(&mut self, from: &PathBuf, to: &PathBuf) -> Result<Vec<PathBuf>> {
+        if !self.files.contains_key(from) {
+            return Err(Error::PathNotFound(from.clone()));
+        }
+        if !is_inside_vault(to) {
+            return Err(Error::PathNotInVaultRoot(to.clone(), self.vault_root.clone()));
+        }
+        if self.files.contains_key(to) || self.vault_root.join(to).exists() {
+            return Err(Error::VaultAlreadyContainsPath(to.clone()));
+        }
+
+        let backlinks = self.backlinks(from);
+        let attachments = self.attachments()?;
+        let own_links: Vec<(crate::file::mdfile::link::Link, Option<PathBuf>)> = self.files[from]
+            .get_mdfile()
+            .map(|mdfile| mdfile.get_links())
+            .unwrap_or_default()
+            .into_iter()
+            .map(|link| {
+                let resolved = self.resolve_link_among(from, &link, &attachments);
+                (link, resolved)
+            })
+            .collect();
+
+        self.relocate_file(from, to)?;
+
+        let mut edits = Vec::new();
+        for backlink in backlinks {
+            if self.resolve_link(&backlink.source, &backlink.link).as_ref() != Some(to) {
+                let text = self.link_to(&backlink.source, to, &backlink.link);
+                edits.push((backlink.source, backlink.link.range, text));
+            }
+        }
+        for (link, resolved) in own_links {
+            // links to the moved note itself now resolve to its new path
+            let Some(resolved) = resolved.map(|path| if &path == from { to.clone() } else { path }) else {
+                continue;
+            };
+            if self.resolve_link_among(to, &link, &attachments).as_ref() != Some(&resolved) {
+                let text = self.link_to(to, &resolved, &link);
+                edits.push((to.clone(), link.range, text));
+            }
+        }
+        let mut rewritten: Vec<PathBuf> = edits.iter().map(|(source, ..)| source.clone()).collect();
+        rewritten.sort();
+        rewritten.dedup();
+        self.edit_bodies(edits)?;
+        Ok(rewritten)
+    }
+
+    /// Move a file of the Vault on disk and in memory, without rewriting any links.
+    ///
+    /// # Arguments
+    /// @param from: &PathBuf
+    /// @param to: &PathBuf - Its parents are created as needed
+    /// @return Result<()>
+    ///
+    /// @super
+    pub(super) fn relocate_file(&mut self, from: &PathBuf, to: &PathBuf) -> Result<()> {
+        let abs_to = self.vault_root.join(to);
+        if let Some(parent) = abs_to.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        self.backup_file(from)?;
+        std::fs::rename(self.vault_root.join(from), &abs_to)?;
+        let mut file = self.files.remove(from).expect("File not found in vault");
+        file.path = abs_to;
+        self.files.insert(to.clone(), file);
+        self.notify(VaultEvent::FileMoved {
+            from: from.clone(),
+            to: to.clone(),
+        });
+        Ok(())
+    }
+
+    /// Set the file name conventions of notes the Vault creates or renames.
+    ///
+    /// # Arguments
+    /// @param policy: NamingPolicy
+    pub fn set_naming_policy(&mut self, policy: crate::file::naming::NamingPolicy) {
+        self.naming_policy = policy;
+    }
+
+    /// Get the file name conventions of notes the Vault creates or renames. Defaults to keeping titles as they are, made valid as file names.
+    ///
+    /// # Arguments
+    /// @return &NamingPolicy
+    pub fn get_naming_policy(&self) -> &crate::file::naming::NamingPolicy {
+        &self.naming_policy
+    }
+
+    /// Rename a note to a title, named by the naming policy, in the same folder. Links to it are rewritten as by `move_file`.
+    ///
+    /// # Arguments
+    /// @param path: &PathBuf - The note, relative to the vault root
+    /// @param title: &str - The new title, e.g. `Mitosis and the Cell Cycle`
+    /// @return Result<PathBuf> - The new path; the note is not moved if it already has it
+    ///
+    /// # Example
+    /// ```should_panic
+    /// use std::path::PathBuf;
+    ///
+    /// use obsidian_driver::file::naming::{NameCase, NamingPolicy};
+    /// use obsidian_driver::file::vault::Vault;
+    ///
+    /// let mut vault = Vault::from_path(PathBuf::from("vault")).unwrap();
+    /// vault.set_naming_policy(NamingPolicy::default().with_case(NameCase::Kebab));
+    /// let path = vault.rename_note(&PathBuf::from("Inbox/untitled.md"), "Mitosis: The Cell Cycle").unwrap();
+    /// assert_eq!(path, PathBuf::from("Inbox/mitosis-the-cell-cycle.md"));
+    /// ```
+    pub fn rename_note(&mut self, path: &PathBuf, title: &str) -> Result<PathBuf> {
+        let extension = path.extension().map(|extension| extension.to_string_lossy().into_owned()).unwrap_or("md".to_string());
+        let to = path.with_file_name(f!("{}.{}", self.naming_policy.name(title), extension));
+        if &to != path {
+            self.move_file(path, &to)?;
+        }
+        Ok(to)
+    }
+
     /// Get a file from the Vault. Uses the path relative to the vault root.
     ///
     /// # Arguments
@@ -218,6 +726,49 @@ impl Vault {
         &self.files
     }
 
+    /// Get the absolute path of the vault root.
+    ///
+    /// # Arguments
+    /// @return &PathBuf
+    pub fn get_vault_root(&self) -> &PathBuf {
+        &self.vault_root
+    }
+
+    /// Get the checklist items of every note in the Vault.
+    ///
+    /// # Arguments
+    /// @param filter: TaskFilter - Which tasks to return by completion state
+    /// @return Vec<(PathBuf, Task)> - The note path and task, ordered by path and then by position in the note
+    ///
+    /// # Example
+    /// ```should_panic
+    /// use std::path::PathBuf;
+    ///
+    /// use obsidian_driver::file::mdfile::task::TaskFilter;
+    /// use obsidian_driver::file::vault::Vault;
+    ///
+    /// let vault = Vault::from_path(PathBuf::from("vault")).unwrap();
+    /// for (path, task) in vault.get_tasks(TaskFilter::Open) {
+    /// 	println!("{}: {}", path.display(), task.text);
+    /// }
+    /// ```
+    pub fn get_tasks(&self, filter: TaskFilter) -> Vec<(PathBuf, Task)> {
+        let mut tasks: Vec<(PathBuf, Task)> = self
+            .files
+            .iter()
+            .filter_map(|(path, file)| Some((path, file.get_mdfile()?)))
+            .flat_map(|(path, mdfile)| {
+                mdfile
+                    .get_tasks()
+                    .into_iter()
+                    .filter(|task| filter.matches(task))
+                    .map(|task| (path.clone(), task))
+            })
+            .collect();
+        tasks.sort_by(|a, b| (&a.0, a.1.line).cmp(&(&b.0, b.1.line)));
+        tasks
+    }
+
     /// Adds an AIDriver to the Vault.
     ///
     /// # Arguments
@@ -226,60 +777,268 @@ impl Vault {
         self.aidriver = Some(aidriver);
     }
 
+    /// Adds an embedding store to the Vault.
+    ///
+    /// Once a store is attached, `to_cache` writes embeddings to the store file instead of the
+    /// JSON cache, and `load_embeddings` restores them for notes whose contents are unchanged.
+    /// The store file is not read until embeddings are first needed.
+    ///
+    /// # Arguments
+    /// @param store_path: PathBuf
+    ///
+    /// # Example
+    /// ```should_panic
+    /// use std::path::PathBuf;
+    ///
+    /// use obsidian_driver::file::vault::Vault;
+    ///
+    /// let mut vault = Vault::from_path(PathBuf::from("vault")).unwrap();
+    /// vault.add_embedding_store(PathBuf::from("vault_embeddings.bin"));
+    /// vault.load_embeddings().unwrap();
+    /// ```
+    pub fn add_embedding_store(&mut self, store_path: PathBuf) {
+        self.embedding_store = Some(embedding_store::EmbeddingStore::new(store_path));
+    }
+
+    /// Loads embeddings from the embedding store into notes that do not have one yet.
+    ///
+    /// # Arguments
+    /// @return Result<usize> - The number of notes that received an embedding
+    pub fn load_embeddings(&mut self) -> Result<usize> {
+        let Some(store) = self.embedding_store.as_ref() else {
+            return Ok(0);
+        };
+        let mut loaded = 0;
+        for (path, file) in self.files.iter_mut() {
+            if file.get_embedding().is_some() {
+                continue;
+            }
+            if let Some(embedding) = store.get(path, &file.content_hash())? {
+                file.set_embedding(embedding);
+                loaded += 1;
+            }
+        }
+        Ok(loaded)
+    }
+
     /// Updates the embeddings of all files in the Vault.
     ///
+    /// Dropping the future stops the requests; embeddings received before that are kept. Use
+    /// `update_embeddings_until` to stop at a cancellation token and checkpoint the progress.
+    ///
     /// # Arguments
     /// @return Result<()>
     pub async fn update_embeddings(&mut self) -> Result<()> {
         if self.aidriver.is_none() {
             return Err(Error::NoAIDriver);
         }
+        let profiler = self.profiler.clone();
+        profiler.time(Operation::EmbeddingRefresh, Phase::Io, || self.load_embeddings())?;
 
-        let mut mdfiles: Vec<(&mut MDFile, &Path)> = Vec::new();
+        let mut files: Vec<(PathBuf, &mut crate::file::File)> = Vec::new();
 
         for (path, file) in self.files.iter_mut() {
             let abs_file_path = self.vault_root.join(path);
-            println!("Updating embedding for file: {}", abs_file_path.display());
-            let last_modified = std::fs::metadata(&abs_file_path)?
+            let last_modified = profiler
+                .time(Operation::EmbeddingRefresh, Phase::Io, || std::fs::metadata(&abs_file_path))?
                 .modified()?
-                .elapsed()?
+                .duration_since(std::time::SystemTime::UNIX_EPOCH)?
                 .as_millis();
-            if file.get_mdfile().is_none() {
+            if Some(last_modified) <= file.last_modified && file.get_embedding().is_some() {
                 continue;
             }
-            if file.last_modified <= Some(last_modified)
-                && file
-                    .get_mdfile()
-                    .as_ref()
-                    .unwrap()
-                    .get_embedding()
-                    .is_some()
-            {
-                continue;
+            debug!(path = %path.display(), "embedding out of date");
+            files.push((path.clone(), file));
+        }
+
+        let mut paths = Vec::new();
+        let mut futures = Vec::new();
+        for (path, file) in files {
+            paths.push(path);
+            futures.push(file.update_embedding(self.aidriver.as_ref().expect("AIDriver not found")));
+        }
+
+        let results = profiler
+            .time_async(Operation::EmbeddingRefresh, Phase::Api, futures::future::join_all(futures))
+            .await;
+        for (path, result) in paths.into_iter().zip(results) {
+            match result {
+                Ok(()) => self.notify(VaultEvent::EmbeddingUpdated(path)),
+                Err(e) => warn!(error = %e, "embedding update failed"),
             }
-            if let Some(mdfile) = file.get_mdfile_mut() {
-                mdfiles.push((mdfile, path));
+        }
+
+        Ok(())
+    }
+
+    /// Updates the embeddings of notes that do not have one, stopping early if `shutdown` is cancelled.
+    ///
+    /// Notes are embedded `concurrency` at a time. After each group finishes, the vault is
+    /// written to `checkpoint_path` with `to_cache` (and to the embedding store, if attached), so
+    /// embeddings computed so far survive an interruption. Once `shutdown` is cancelled, requests
+    /// already in flight are finished and checkpointed, and no new ones are started. Resume with
+    /// `Vault::from_cache(vault_root, checkpoint_path)`; notes that already have an embedding are
+    /// skipped.
+    ///
+    /// # Arguments
+    /// @param shutdown: &CancellationToken - Cancel to stop after the in-flight requests
+    /// @param checkpoint_path: &PathBuf - The cache file to checkpoint to
+    /// @param concurrency: usize - The number of requests in flight at once
+    /// @return Result<BatchReport>
+    ///
+    /// # Example
+    /// ```should_panic
+    /// use std::path::PathBuf;
+    ///
+    /// use obsidian_driver::batch::CancellationToken;
+    /// use obsidian_driver::file::vault::Vault;
+    ///
+    /// async fn update_embeddings_until_example() {
+    /// 	let checkpoint_path = PathBuf::from("vault_cache.json");
+    /// 	let mut vault = Vault::from_cache(PathBuf::from("vault"), &checkpoint_path).unwrap();
+    /// 	// cancel a clone of the token from a Ctrl-C handler to stop cleanly
+    /// 	let shutdown = CancellationToken::new();
+    /// 	let report = vault.update_embeddings_until(&shutdown, &checkpoint_path, 16).await.unwrap();
+    /// }
+    /// let vault = Vault::from_path(PathBuf::from("vault")).unwrap();
+    /// ```
+    pub async fn update_embeddings_until(
+        &mut self,
+        shutdown: &CancellationToken,
+        checkpoint_path: &PathBuf,
+        concurrency: usize,
+    ) -> Result<BatchReport> {
+        let aidriver = self.aidriver.clone().ok_or(Error::NoAIDriver)?;
+        let profiler = self.profiler.clone();
+        profiler.time(Operation::EmbeddingRefresh, Phase::Io, || self.load_embeddings())?;
+
+        let mut pending: Vec<(PathBuf, String)> = self
+            .files
+            .iter()
+            .filter(|(_, file)| file.get_embedding().is_none())
+            .map(|(path, file)| (path.clone(), file.get_text()))
+            .collect();
+        pending.sort();
+
+        let mut report = BatchReport::default();
+        let mut started = 0;
+        for group in pending.chunks(concurrency.max(1)) {
+            if shutdown.is_cancelled() {
+                info!(remaining = pending.len() - started, "embedding update cancelled");
+                report.status = BatchStatus::Cancelled;
+                break;
             }
+            started += group.len();
+            let futures = group.iter().map(|(_, text)| aidriver.get_embedding(text));
+            let results = profiler
+                .time_async(Operation::EmbeddingRefresh, Phase::Api, futures::future::join_all(futures))
+                .await;
+            for ((path, _), result) in group.iter().zip(results) {
+                match result {
+                    Ok(embedding) => {
+                        if let Some(file) = self.files.get_mut(path) {
+                            file.set_embedding(embedding);
+                        }
+                        self.notify(VaultEvent::EmbeddingUpdated(path.clone()));
+                        report.completed.push(path.clone());
+                    }
+                    Err(e) => {
+                        warn!(path = %path.display(), error = %e, "embedding update failed");
+                        report.failed.push((path.clone(), e.to_string()))
+                    }
+                }
+            }
+            self.to_cache(checkpoint_path)?;
+            debug!(path = %checkpoint_path.display(), completed = report.completed.len(), "embeddings checkpointed");
         }
+        report.remaining = pending[started..].iter().map(|(path, _)| path.clone()).collect();
+        Ok(report)
+    }
 
-        let mut futures = Vec::new();
-        for (mdfile, path) in mdfiles {
-            let abs_file_path = self.vault_root.join(path);
-            futures.push(mdfile.update_embedding(
-                self.aidriver.as_ref().expect("AIDriver not found"),
-                abs_file_path,
+    /// Import embeddings computed outside of the library and attach them to matching notes.
+    ///
+    /// The import file is a JSON array of `ImportedEmbedding` records. Each record is matched by
+    /// its path relative to the vault root, falling back to the content hash of the note. A record
+    /// matched by path whose hash differs from the note's current content hash is stale, and is
+    /// skipped. `model_tag` must match the embedding model of the AIDriver and the vectors must
+    /// match its embedding dimensions. All vectors must also match the dimensions of the
+    /// embeddings already in the vault.
+    ///
+    /// # Arguments
+    /// @param path: &PathBuf - The path of the import file
+    /// @param model_tag: &str - The name of the model the embeddings were computed with
+    /// @return Result<ImportReport> - The notes that received an embedding and the notes with stale records, sorted by path.
+    /// Errors with `NoAIDriver` if no driver was added, since `model_tag` cannot be checked, and with `EmbeddingModelMismatch` if it differs.
+    ///
+    /// # Example
+    /// ```should_panic
+    /// use std::path::PathBuf;
+    ///
+    /// use obsidian_driver::file::vault::Vault;
+    ///
+    /// let mut vault = Vault::from_path(PathBuf::from("vault")).unwrap();
+    /// let imported = vault.import_embeddings(&PathBuf::from("embeddings.json"), "text-embedding-3-small").unwrap();
+    /// ```
+    pub fn import_embeddings(&mut self, path: &PathBuf, model_tag: &str) -> Result<ImportReport> {
+        let aidriver = self.aidriver.as_ref().ok_or(Error::NoAIDriver)?;
+        if aidriver.embedding_model() != model_tag {
+            return Err(Error::EmbeddingModelMismatch(
+                aidriver.embedding_model().to_string(),
+                model_tag.to_string(),
             ));
         }
+        let mut dimensions = aidriver.embedding_dimensions();
+        let import_str = std::fs::read_to_string(path)?;
+        let records: Vec<ImportedEmbedding> = serde_json::from_str(&import_str)?;
 
-        let results = futures::future::join_all(futures).await;
-        for result in results {
-            if let Err(e) = result {
-                eprintln!("{}", e);
-                // Optionally retry or handle the error
+        if dimensions.is_none() {
+            dimensions = self
+                .files
+                .values()
+                .find_map(|file| file.get_embedding())
+                .map(|embedding| embedding.len());
+        }
+        let dimensions = dimensions
+            .or_else(|| records.first().map(|record| record.embedding.len()))
+            .unwrap_or_default();
+        for record in &records {
+            if record.embedding.len() != dimensions {
+                return Err(Error::EmbeddingDimensionMismatch(
+                    dimensions,
+                    record.embedding.len(),
+                ));
             }
         }
 
-        Ok(())
+        let hashes: HashMap<String, PathBuf> = self
+            .files
+            .iter()
+            .map(|(path, file)| (file.content_hash(), path.clone()))
+            .collect();
+
+        let mut report = ImportReport::default();
+        for record in records {
+            let local_path = record
+                .path
+                .filter(|path| self.files.contains_key(path))
+                .or_else(|| hashes.get(record.hash.as_ref()?).cloned());
+            let Some(local_path) = local_path else {
+                continue;
+            };
+            if let Some(file) = self.files.get_mut(&local_path) {
+                if record.hash.as_ref().is_some_and(|hash| *hash != file.content_hash()) {
+                    warn!(path = %local_path.display(), "imported embedding is stale");
+                    report.stale.push(local_path);
+                    continue;
+                }
+                file.set_embedding(record.embedding);
+                self.notify(VaultEvent::EmbeddingUpdated(local_path.clone()));
+                report.imported.push(local_path);
+            }
+        }
+        report.imported.sort();
+        report.stale.sort();
+        Ok(report)
     }
 
     /// Get the closest files to a given file by embedding distance.
@@ -293,33 +1052,41 @@ impl Vault {
             .files
             .get(path)
             .ok_or(Error::Generic(f!("Path Not Found: {}", path.display())))?;
-        let mdfile = file
-            .get_mdfile()
-            .ok_or(Error::Generic(f!("Not MDFile: {}", path.display())))?;
-        let embedding = mdfile.get_embedding().ok_or(Error::Generic(f!(
+        let embedding = file.get_embedding().ok_or(Error::Generic(f!(
             "Noo embedding for file: {}",
             path.display()
         )))?;
 
+        self.get_closest_files_to_embedding(embedding, n)
+    }
+
+    /// Get the closest files to an embedding, such as that of a query text, by embedding distance.
+    ///
+    /// # Arguments
+    /// @param embedding: &[f64]
+    /// @param n: usize
+    /// @return Result<Vec<(PathBuf, f64)>> - Nearest first. Errors with `EmbeddingDimensionMismatch` if a file embedding has a different length.
+    pub fn get_closest_files_to_embedding(&self, embedding: &[f64], n: usize) -> Result<Vec<(PathBuf, f64)>> {
+        let index_start = std::time::Instant::now();
+        let mut paths = Vec::new();
         let mut embeddings = Vec::new();
 
         for (other_path, other_file) in self.files.iter() {
-            // if other_path == path {
-            // 	continue;
-            // }
-            let other_mdfile = other_file.get_mdfile();
-            if other_mdfile.is_none() {
-                continue;
-            }
-            let other_mdfile = other_mdfile.unwrap();
-            let other_embedding = other_mdfile.get_embedding();
+            let other_embedding = other_file.get_embedding();
             if other_embedding.is_none() {
                 continue;
             }
             let other_embedding = other_embedding.unwrap();
+            if other_embedding.len() != embedding.len() {
+                return Err(Error::EmbeddingDimensionMismatch(embedding.len(), other_embedding.len()));
+            }
 
+            paths.push(other_path);
             embeddings.push(other_embedding.clone());
         }
+        if n == 0 || paths.is_empty() {
+            return Ok(Vec::new());
+        }
 
         let dimensions = embedding.len();
         let mut kdtree = KdTree::new(dimensions);
@@ -327,12 +1094,14 @@ impl Vault {
         for (i, embedding) in embeddings.iter().enumerate() {
             kdtree.add(embedding, i).unwrap();
         }
+        self.profiler.record(Operation::Search, Phase::Indexing, index_start.elapsed());
 
-        let nearest: Vec<(f64, &usize)> = kdtree.nearest(embedding, n, &squared_euclidean).unwrap();
+        let nearest: Vec<(f64, &usize)> = self.profiler.time(Operation::Search, Phase::Lookup, || {
+            kdtree.nearest(embedding, n, &squared_euclidean).unwrap()
+        });
         let mut distances = Vec::new();
         for (distance, &index) in nearest {
-            let other_path = self.files.keys().nth(index).unwrap();
-            distances.push((other_path.clone(), distance.sqrt()));
+            distances.push((paths[index].clone(), distance.sqrt()));
         }
         Ok(distances)
     }
@@ -352,28 +1121,23 @@ impl Vault {
             .files
             .get(path)
             .ok_or(Error::Generic(f!("Path Not Found: {}", path.display())))?;
-        let mdfile = file
-            .get_mdfile()
-            .ok_or(Error::Generic(f!("Not MDFile: {}", path.display())))?;
-        let embedding = mdfile.get_embedding().ok_or(Error::Generic(f!(
+        let embedding = file.get_embedding().ok_or(Error::Generic(f!(
             "Noo embedding for file: {}",
             path.display()
         )))?;
 
+        let index_start = std::time::Instant::now();
+        let mut paths = Vec::new();
         let mut embeddings = Vec::new();
 
         for (other_path, other_file) in self.files.iter() {
-            let other_mdfile = other_file.get_mdfile();
-            if other_mdfile.is_none() {
-                continue;
-            }
-            let other_mdfile = other_mdfile.unwrap();
-            let other_embedding = other_mdfile.get_embedding();
+            let other_embedding = other_file.get_embedding();
             if other_embedding.is_none() {
                 continue;
             }
             let other_embedding = other_embedding.unwrap();
 
+            paths.push(other_path);
             embeddings.push(other_embedding.clone());
         }
 
@@ -383,17 +1147,459 @@ impl Vault {
         for (i, embedding) in embeddings.iter().enumerate() {
             kdtree.add(embedding, i).unwrap();
         }
+        self.profiler.record(Operation::Search, Phase::Indexing, index_start.elapsed());
         let threshold = threshold.powi(2);
-        let nearest: Vec<(f64, &usize)> = kdtree
-            .iter_nearest(embedding, &squared_euclidean)
-            .unwrap()
-            .filter(|(distance, _)| *distance <= threshold)
-            .collect();
+        let nearest: Vec<(f64, &usize)> = self.profiler.time(Operation::Search, Phase::Lookup, || {
+            kdtree
+                .iter_nearest(embedding, &squared_euclidean)
+                .unwrap()
+                .filter(|(distance, _)| *distance <= threshold)
+                .collect()
+        });
         let mut distances = Vec::new();
         for (distance, &index) in nearest {
-            let other_path = self.files.keys().nth(index).unwrap();
-            distances.push((other_path.clone(), distance.sqrt()));
+            distances.push((paths[index].clone(), distance.sqrt()));
         }
         Ok(distances)
     }
+
+    /// Replace byte ranges in the bodies of notes and write the notes.
+    ///
+    /// # Arguments
+    /// @param edits: Vec<(PathBuf, Range<usize>, String)> - (note path, range in body, replacement) triples
+    /// @return Result<()>
+    pub(crate) fn edit_bodies(&mut self, mut edits: Vec<(PathBuf, Range<usize>, String)>) -> Result<()> {
+        edits.sort_by(|a, b| (&a.0, a.1.start).cmp(&(&b.0, b.1.start)));
+        for group in edits.chunk_by(|a, b| a.0 == b.0) {
+            let source = &group[0].0;
+            if !self.files.contains_key(source) {
+                continue;
+            }
+            self.backup_file(source)?;
+            let file = self.files.get_mut(source).expect("File not found in vault");
+            let Some(mdfile) = file.get_mdfile_mut() else {
+                continue;
+            };
+            let mut body = mdfile.get_body().clone();
+            for (_, range, replacement) in group.iter().rev() {
+                body.replace_range(range.clone(), replacement);
+            }
+            mdfile.set_body(body);
+            self.stamp_modified(source);
+            let file = self.files.get_mut(source).expect("File not found in vault");
+            file.save()?;
+            self.notify(VaultEvent::FileModified(source.clone()));
+            self.notify(VaultEvent::FileWritten(source.clone()));
+        }
+        Ok(())
+    }
+}
+
+impl Vault {
+    /// Keep the property index up to date with a change, then send it to the subscribers.
+    ///
+    /// @crate
+    pub(crate) fn notify(&mut self, event: VaultEvent) {
+        match &event {
+            VaultEvent::FileAdded(path) | VaultEvent::FileModified(path) | VaultEvent::FileWritten(path) => {
+                if let Some(file) = self.files.get(path) {
+                    self.properties.insert(path, file);
+                }
+            }
+            VaultEvent::FileRemoved(path) => self.properties.remove(path),
+            VaultEvent::FileMoved { from, to } => {
+                self.properties.remove(from);
+                if let Some(file) = self.files.get(to) {
+                    self.properties.insert(to, file);
+                }
+            }
+            VaultEvent::EmbeddingUpdated(_) | VaultEvent::LinkBroken(_) => {}
+        }
+        self.events.emit(event);
+    }
+}
+
+/// The folders Obsidian keeps its own files in, relative to the vault root. They are not loaded.
+const OBSIDIAN_FOLDERS: [&str; 2] = [".obsidian", trash::TRASH_FOLDER];
+
+/// The absolute folders a walk of the vault skips: Obsidian's own folders and the backup folder, if one is set.
+///
+/// # Arguments
+/// @param vault_root: &Path
+/// @param backup_folder: Option<&PathBuf> - Relative to the vault root, or absolute
+/// @return Vec<PathBuf>
+///
+/// @private
+fn excluded_folders(vault_root: &std::path::Path, backup_folder: Option<&PathBuf>) -> Vec<PathBuf> {
+    let mut folders: Vec<PathBuf> = OBSIDIAN_FOLDERS.iter().map(|folder| vault_root.join(folder)).collect();
+    if let Some(folder) = backup_folder {
+        let folder = vault_root.join(folder);
+        folders.push(folder.canonicalize().unwrap_or(folder));
+    }
+    folders
+}
+
+/// Check that a path relative to the vault root stays inside it: it is not absolute and has no `..` components.
+///
+/// # Arguments
+/// @param path: &std::path::Path
+/// @return bool
+///
+/// @private
+fn is_inside_vault(path: &std::path::Path) -> bool {
+    path.components().all(|component| matches!(component, std::path::Component::Normal(_)))
+}
+
+/// Read the Obsidian settings of a vault. Invalid settings are logged and replaced by the defaults, so they do not stop the vault from loading.
+///
+/// @private
+fn load_settings(vault_root: &std::path::Path) -> settings::VaultSettings {
+    settings::VaultSettings::load(vault_root).unwrap_or_else(|e| {
+        warn!(error = %e, "invalid Obsidian settings, using the defaults");
+        settings::VaultSettings::default()
+    })
+}
+
+/// Walk the vault root, skipping the `excluded` folders. The walk stops early if `shutdown` is cancelled.
+///
+/// @private
+fn scan(vault_root: &PathBuf, excluded: &[PathBuf], shutdown: &CancellationToken) -> Result<Vec<walkdir::DirEntry>> {
+    let entries = walkdir::WalkDir::new(vault_root)
+        .into_iter()
+        .filter_entry(|entry| !excluded.iter().any(|folder| entry.path() == folder))
+        .take_while(|_| !shutdown.is_cancelled())
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    Ok(entries)
+}
+
+/// Read and parse files on the rayon thread pool. The results are in the order of `paths`.
+///
+/// # Arguments
+/// @param profiler: &profile::Profiler
+/// @param paths: &[PathBuf] - Absolute paths
+/// @param shutdown: &CancellationToken - Files not started when it is cancelled fail with `Cancelled`
+/// @return Vec<Result<crate::file::File>>
+///
+/// @private
+fn read_files(profiler: &profile::Profiler, paths: &[PathBuf], shutdown: &CancellationToken) -> Vec<Result<crate::file::File>> {
+    paths
+        .par_iter()
+        .map(|path| {
+            if shutdown.is_cancelled() {
+                return Err(Error::Cancelled);
+            }
+            let (contents, last_modified) =
+                profiler.time(Operation::Load, Phase::Io, || crate::file::File::read_raw(path))?;
+            let file = profiler.time(Operation::Load, Phase::Parsing, || {
+                crate::file::File::parse_raw(path.clone(), contents, Some(last_modified))
+            })?;
+            debug!(path = %path.display(), "file parsed");
+            Ok(file)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod vault_tests {
+    use super::*;
+    use crate::ai::api::mock::{MockDriver, MOCK_EMBEDDING_MODEL};
+    use crate::ai::api::AIDriver;
+    use crate::file::mdfile::MDFile;
+    use crate::file::mdfile::link::Link;
+
+    fn test_vault() -> (tempfile::TempDir, Vault) {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.md"), "# A\n\nFirst note.").unwrap();
+        std::fs::write(dir.path().join("b.md"), "# B\n\nSecond note.").unwrap();
+        let vault = Vault::from_path(dir.path().to_path_buf()).unwrap();
+        (dir, vault)
+    }
+
+    #[test]
+    fn test_from_path_parses_in_parallel() {
+        let dir = tempfile::tempdir().unwrap();
+        for i in 0..64 {
+            std::fs::create_dir_all(dir.path().join(f!("{}", i % 4))).unwrap();
+            std::fs::write(dir.path().join(f!("{}/{}.md", i % 4, i)), f!("# Note {}", i)).unwrap();
+        }
+        let vault = Vault::from_path(dir.path().to_path_buf()).unwrap();
+        assert_eq!(vault.get_files().len(), 64);
+        for i in 0..64 {
+            let file = vault.get_file(&PathBuf::from(f!("{}/{}.md", i % 4, i))).unwrap();
+            assert_eq!(file.get_mdfile().unwrap().get_body(), &f!("# Note {}", i));
+        }
+    }
+
+    #[test]
+    fn test_refresh_until_stops_when_cancelled() {
+        let (dir, mut vault) = test_vault();
+        std::fs::write(dir.path().join("c.md"), "# C").unwrap();
+
+        let shutdown = CancellationToken::new();
+        shutdown.cancel();
+        let report = vault.refresh_until(&shutdown).unwrap();
+        assert_eq!(report.status, BatchStatus::Cancelled);
+        assert!(vault.get_file(&PathBuf::from("c.md")).is_none());
+        assert!(matches!(Vault::from_path_until(dir.path().to_path_buf(), &shutdown), Err(Error::Cancelled)));
+
+        let report = vault.refresh_until(&CancellationToken::new()).unwrap();
+        assert_eq!(report.status, BatchStatus::Completed);
+        assert_eq!(report.completed, vec![PathBuf::from("c.md")]);
+        assert!(vault.get_file(&PathBuf::from("c.md")).is_some());
+    }
+
+    #[test]
+    fn test_get_tasks_filters_by_completion() {
+        let (dir, _) = test_vault();
+        std::fs::write(dir.path().join("c.md"), "# C\n\n- [x] Done\n- [ ] Todo").unwrap();
+        std::fs::write(dir.path().join("a.md"), "- [ ] First").unwrap();
+        let vault = Vault::from_path(dir.path().to_path_buf()).unwrap();
+
+        let open: Vec<(PathBuf, String)> = vault
+            .get_tasks(TaskFilter::Open)
+            .into_iter()
+            .map(|(path, task)| (path, task.text))
+            .collect();
+        let expected = vec![
+            (PathBuf::from("a.md"), "First".to_string()),
+            (PathBuf::from("c.md"), "Todo".to_string()),
+        ];
+        assert_eq!(open, expected);
+        assert_eq!(vault.get_tasks(TaskFilter::Completed).len(), 1);
+        assert_eq!(vault.get_tasks(TaskFilter::All).len(), 3);
+    }
+
+    #[test]
+    fn test_import_embeddings_by_path_and_hash() {
+        let (dir, mut vault) = test_vault();
+        vault.add_ai_driver(AIDriver::new_mock(MockDriver::new().with_embedding_dimensions(2)));
+        let hash = MDFile::from_string("# B\n\nSecond note.".to_string()).content_hash();
+        let import_path = dir.path().join("embeddings.json");
+        let records = serde_json::json!([
+            {"path": "a.md", "embedding": [1.0, 0.0]},
+            {"hash": hash, "embedding": [0.0, 1.0]},
+            {"path": "missing.md", "embedding": [0.5, 0.5]},
+        ]);
+        std::fs::write(&import_path, records.to_string()).unwrap();
+
+        let actual = vault.import_embeddings(&import_path, MOCK_EMBEDDING_MODEL).unwrap();
+        let expected = vec![PathBuf::from("a.md"), PathBuf::from("b.md")];
+        assert_eq!(actual.imported, expected);
+        assert!(actual.stale.is_empty());
+
+        let b = vault.get_file(&PathBuf::from("b.md")).unwrap();
+        assert_eq!(b.get_mdfile().unwrap().get_embedding(), Some(&vec![0.0, 1.0]));
+    }
+
+    #[test]
+    fn test_import_embeddings_dimension_mismatch() {
+        let (dir, mut vault) = test_vault();
+        vault
+            .get_file_mut(&PathBuf::from("a.md"))
+            .unwrap()
+            .get_mdfile_mut()
+            .unwrap()
+            .set_embedding(vec![1.0, 0.0, 0.0]);
+        let import_path = dir.path().join("embeddings.json");
+        let records = serde_json::json!([{"path": "b.md", "embedding": [0.0, 1.0]}]);
+        std::fs::write(&import_path, records.to_string()).unwrap();
+
+        let actual = vault.import_embeddings(&import_path, MOCK_EMBEDDING_MODEL);
+        assert!(matches!(actual, Err(Error::NoAIDriver)));
+        vault.add_ai_driver(AIDriver::new_mock(MockDriver::new().with_embedding_dimensions(3)));
+        let actual = vault.import_embeddings(&import_path, "external");
+        assert!(matches!(actual, Err(Error::EmbeddingModelMismatch(..))));
+        let actual = vault.import_embeddings(&import_path, MOCK_EMBEDDING_MODEL);
+        assert!(matches!(actual, Err(Error::EmbeddingDimensionMismatch(3, 2))));
+    }
+
+    #[test]
+    fn test_import_embeddings_skips_stale_records() {
+        let (dir, mut vault) = test_vault();
+        vault.add_ai_driver(AIDriver::new_mock(MockDriver::new().with_embedding_dimensions(2)));
+        let hash = MDFile::from_string("# A\n\nFirst note.".to_string()).content_hash();
+        let import_path = dir.path().join("embeddings.json");
+        let records = serde_json::json!([
+            {"path": "a.md", "hash": hash, "embedding": [1.0, 0.0]},
+            {"path": "b.md", "hash": hash, "embedding": [0.0, 1.0]},
+        ]);
+        std::fs::write(&import_path, records.to_string()).unwrap();
+
+        let actual = vault.import_embeddings(&import_path, MOCK_EMBEDDING_MODEL).unwrap();
+        assert_eq!(actual.imported, vec![PathBuf::from("a.md")]);
+        assert_eq!(actual.stale, vec![PathBuf::from("b.md")]);
+        assert!(vault.get_file(&PathBuf::from("b.md")).unwrap().get_embedding().is_none());
+    }
+
+    #[test]
+    fn test_embedding_store_survives_cache_rebuild() {
+        let (dir, mut vault) = test_vault();
+        let out = tempfile::tempdir().unwrap();
+        let store_path = out.path().join("embeddings.bin");
+        let cache_path = out.path().join("cache.json");
+        vault.add_embedding_store(store_path.clone());
+        vault
+            .get_file_mut(&PathBuf::from("a.md"))
+            .unwrap()
+            .get_mdfile_mut()
+            .unwrap()
+            .set_embedding(vec![1.0, 0.0]);
+        vault.to_cache(&cache_path).unwrap();
+
+        let cache_str = std::fs::read_to_string(&cache_path).unwrap();
+        assert!(!cache_str.contains("[1.0,0.0]"));
+
+        let mut rebuilt = Vault::from_path(dir.path().to_path_buf()).unwrap();
+        rebuilt.add_embedding_store(store_path);
+        assert_eq!(rebuilt.load_embeddings().unwrap(), 1);
+        let a = rebuilt.get_file(&PathBuf::from("a.md")).unwrap();
+        assert_eq!(a.get_mdfile().unwrap().get_embedding(), Some(&vec![1.0, 0.0]));
+    }
+
+    #[test]
+    fn test_embedding_store_sees_saved_embeddings() {
+        let (_dir, mut vault) = test_vault();
+        let out = tempfile::tempdir().unwrap();
+        vault.add_embedding_store(out.path().join("embeddings.bin"));
+        // loads the empty store
+        assert_eq!(vault.load_embeddings().unwrap(), 0);
+
+        let a = PathBuf::from("a.md");
+        vault.get_file_mut(&a).unwrap().get_mdfile_mut().unwrap().set_embedding(vec![1.0, 0.0]);
+        vault.to_cache(&out.path().join("cache.json")).unwrap();
+        vault.get_file_mut(&a).unwrap().get_mdfile_mut().unwrap().take_embedding();
+        assert_eq!(vault.load_embeddings().unwrap(), 1);
+        assert_eq!(vault.get_file(&a).unwrap().get_mdfile().unwrap().get_embedding(), Some(&vec![1.0, 0.0]));
+    }
+
+    #[test]
+    fn test_embedding_store_prunes_removed_notes() {
+        let (dir, mut vault) = test_vault();
+        let out = tempfile::tempdir().unwrap();
+        let store_path = out.path().join("embeddings.bin");
+        vault.add_embedding_store(store_path.clone());
+        for path in ["a.md", "b.md"] {
+            vault.get_file_mut(&PathBuf::from(path)).unwrap().get_mdfile_mut().unwrap().set_embedding(vec![1.0, 0.0]);
+        }
+        vault.to_cache(&out.path().join("cache.json")).unwrap();
+
+        vault.remove_file(&PathBuf::from("b.md"), links::BrokenLinkPolicy::Ignore).unwrap();
+        vault.get_file_mut(&PathBuf::from("a.md")).unwrap().get_mdfile_mut().unwrap().take_embedding();
+        vault.to_cache(&out.path().join("cache.json")).unwrap();
+
+        // a note with the same contents at the old path does not get the old embedding back
+        std::fs::write(dir.path().join("b.md"), "# B\n\nSecond note.").unwrap();
+        let mut rebuilt = Vault::from_path(dir.path().to_path_buf()).unwrap();
+        rebuilt.add_embedding_store(store_path);
+        assert_eq!(rebuilt.load_embeddings().unwrap(), 1);
+        assert!(rebuilt.get_file(&PathBuf::from("b.md")).unwrap().get_embedding().is_none());
+    }
+
+    #[test]
+    fn test_remove_file_strips_broken_links() {
+        let (dir, _) = test_vault();
+        std::fs::write(dir.path().join("c.md"), "See [[b]] and [[b|Bee]] and [[a]].").unwrap();
+        let mut vault = Vault::from_path(dir.path().to_path_buf()).unwrap();
+
+        let broken = vault
+            .remove_file(&PathBuf::from("b.md"), links::BrokenLinkPolicy::Strip)
+            .unwrap();
+        assert_eq!(broken.len(), 2);
+        assert!(!dir.path().join("b.md").exists());
+        assert!(vault.get_file(&PathBuf::from("b.md")).is_none());
+
+        let expected = "See b and Bee and [[a]].";
+        let c = vault.get_file(&PathBuf::from("c.md")).unwrap();
+        assert_eq!(c.get_mdfile().unwrap().get_body(), expected);
+        assert_eq!(std::fs::read_to_string(dir.path().join("c.md")).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_move_file_rewrites_links() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.md"), "[[b]] [b](b.md) [[b#Top|B]]").unwrap();
+        std::fs::write(dir.path().join("b.md"), "[a](a.md)").unwrap();
+        let mut vault = Vault::from_path(dir.path().to_path_buf()).unwrap();
+        vault
+            .get_file_mut(&PathBuf::from("b.md"))
+            .unwrap()
+            .get_mdfile_mut()
+            .unwrap()
+            .set_embedding(vec![1.0]);
+
+        let to = PathBuf::from("sub/c.md");
+        let rewritten = vault.move_file(&PathBuf::from("b.md"), &to).unwrap();
+        assert_eq!(rewritten, vec![PathBuf::from("a.md")]);
+
+        assert!(dir.path().join("sub/c.md").exists());
+        assert!(!dir.path().join("b.md").exists());
+        let a = std::fs::read_to_string(dir.path().join("a.md")).unwrap();
+        assert_eq!(a, "[[c]] [b](c.md) [[c#Top|B]]");
+        let c = vault.get_file(&to).unwrap().get_mdfile().unwrap();
+        assert_eq!(c.get_body(), "[a](a.md)");
+        assert_eq!(c.get_embedding(), Some(&vec![1.0]));
+    }
+
+    #[test]
+    fn test_move_file_keeps_attachment_links_and_stays_in_vault() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("Bio")).unwrap();
+        std::fs::create_dir_all(dir.path().join("Chem")).unwrap();
+        std::fs::write(dir.path().join("Bio/note.md"), "![pic](img.png)").unwrap();
+        std::fs::write(dir.path().join("Bio/img.png"), [0u8]).unwrap();
+        std::fs::write(dir.path().join("Chem/img.png"), [1u8]).unwrap();
+        let mut vault = Vault::from_path(dir.path().to_path_buf()).unwrap();
+
+        let from = PathBuf::from("Bio/note.md");
+        for to in ["../escaped.md", "/tmp/escaped.md"] {
+            let moved = vault.move_file(&from, &PathBuf::from(to));
+            assert!(matches!(moved, Err(Error::PathNotInVaultRoot(..))));
+        }
+        assert!(!dir.path().parent().unwrap().join("escaped.md").exists());
+
+        let to = PathBuf::from("Chem/note.md");
+        assert_eq!(vault.move_file(&from, &to).unwrap(), vec![to.clone()]);
+        assert_eq!(std::fs::read_to_string(dir.path().join(&to)).unwrap(), "![pic](Bio/img.png)");
+    }
+
+    #[test]
+    fn test_rename_note_follows_naming_policy() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("Inbox")).unwrap();
+        std::fs::write(dir.path().join("a.md"), "[[untitled]]").unwrap();
+        std::fs::write(dir.path().join("Inbox/untitled.md"), "Cells divide.").unwrap();
+        let mut vault = Vault::from_path(dir.path().to_path_buf()).unwrap();
+        vault.set_naming_policy(crate::file::naming::NamingPolicy::default().with_case(crate::file::naming::NameCase::Kebab));
+
+        let path = vault.rename_note(&PathBuf::from("Inbox/untitled.md"), "Mitosis: The Cell Cycle").unwrap();
+        assert_eq!(path, PathBuf::from("Inbox/mitosis-the-cell-cycle.md"));
+        assert!(dir.path().join(&path).exists());
+        assert_eq!(std::fs::read_to_string(dir.path().join("a.md")).unwrap(), "[[mitosis-the-cell-cycle]]");
+        assert_eq!(vault.rename_note(&path, "mitosis the cell cycle").unwrap(), path);
+    }
+
+    #[test]
+    fn test_heading_links_resolve_and_report_missing_headings() {
+        let (dir, _) = test_vault();
+        std::fs::write(dir.path().join("a.md"), "# A\n## Part\n### Summary\nText").unwrap();
+        std::fs::write(
+            dir.path().join("c.md"),
+            "[[a#Part#summary]] [[a#Gone]] [[missing#Gone]] [[a#^block]]",
+        )
+        .unwrap();
+        let vault = Vault::from_path(dir.path().to_path_buf()).unwrap();
+        let source = PathBuf::from("c.md");
+
+        let link = Link::parse_reference("a > Part > Summary");
+        let (path, section) = vault.resolve_section(&source, &link).unwrap();
+        assert_eq!(path, PathBuf::from("a.md"));
+        assert_eq!(section.heading, "Summary");
+
+        let broken: Vec<String> = vault
+            .broken_heading_links()
+            .into_iter()
+            .map(|broken| broken.link.display_text())
+            .collect();
+        assert_eq!(broken, vec!["a > Gone".to_string()]);
+    }
 }
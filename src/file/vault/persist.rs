@@ -0,0 +1,224 @@
+//! obsidian-driver::file::vault::persist
+//!
+//! This module contains the Vault methods that save in-memory edits back to disk.
+//!
+//! @public Vault::write_file
+//!
+//! @public Vault::write_all
+//!
+//! @public Vault::dirty_files
+//!
+//! @public Vault::put_note
+
+// std imports
+use std::path::{Component, PathBuf};
+
+// first-party imports
+use super::events::VaultEvent;
+use super::Vault;
+use crate::batch::BatchReport;
+use crate::file::mdfile::MDFile;
+use crate::file::write_atomic;
+use crate::prelude::*;
+
+impl Vault {
+    /// Write a file of the Vault to disk, if its contents differ from the file on disk. Uses the path relative to the vault root.
+    ///
+    /// The file is no longer dirty afterwards.
+    ///
+    /// # Arguments
+    /// @param path: &PathBuf
+    /// @return Result<bool> - Whether the file was written
+    ///
+    /// # Example
+    /// ```should_panic
+    /// use std::path::PathBuf;
+    ///
+    /// use obsidian_driver::file::vault::Vault;
+    ///
+    /// let mut vault = Vault::from_path(PathBuf::from("vault")).unwrap();
+    /// let path = PathBuf::from("note.md");
+    /// let mdfile = vault.get_file_mut(&path).unwrap().get_mdfile_mut().unwrap();
+    /// mdfile.set_body("# Rewritten".to_string());
+    /// assert!(vault.write_file(&path).unwrap());
+    /// ```
+    pub fn write_file(&mut self, path: &PathBuf) -> Result<bool> {
+        let Some(contents) = self.prepare_write(path)? else {
+            return Ok(false);
+        };
+        let abs_path = self.vault_root.join(path);
+        if let Some(parent) = abs_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        write_atomic(&abs_path, &contents)?;
+        self.finish_write(path, contents)?;
+        Ok(true)
+    }
+
+    /// Get the contents to write for a file, or None if the file on disk already has them, in which case the file is marked clean.
+    ///
+    /// The modified timestamp is stamped and the file on disk backed up first, as the policies say.
+    ///
+    /// # Arguments
+    /// @param path: &PathBuf
+    /// @return Result<Option<String>>
+    ///
+    /// @crate
+    pub(crate) fn prepare_write(&mut self, path: &PathBuf) -> Result<Option<String>> {
+        let abs_path = self.vault_root.join(path);
+        let file = self
+            .files
+            .get_mut(path)
+            .ok_or(Error::PathNotFound(path.clone()))?;
+        let mut contents = file.contents_to_write()?;
+        if std::fs::read_to_string(&abs_path).is_ok_and(|on_disk| on_disk == contents) {
+            file.mark_clean();
+            return Ok(None);
+        }
+        if self.timestamp_policy.stamp_on_write {
+            self.stamp_modified(path);
+            contents = self.files[path].contents_to_write()?;
+        }
+        self.backup_file(path)?;
+        Ok(Some(contents))
+    }
+
+    /// Record that the contents from `prepare_write` are on disk: the file takes them as its contents, in case the write hooks changed them, is marked clean, and `FileWritten` is sent.
+    ///
+    /// # Arguments
+    /// @param path: &PathBuf
+    /// @param written: String - The contents from `prepare_write`
+    /// @return Result<()>
+    ///
+    /// @crate
+    pub(crate) fn finish_write(&mut self, path: &PathBuf, written: String) -> Result<()> {
+        let abs_path = self.vault_root.join(path);
+        let file = self.files.get_mut(path).ok_or(Error::PathNotFound(path.clone()))?;
+        file.last_modified = Some(
+            std::fs::metadata(&abs_path)?
+                .modified()?
+                .duration_since(std::time::SystemTime::UNIX_EPOCH)?
+                .as_millis(),
+        );
+        file.keep_written(written)?;
+        file.mark_clean();
+        self.notify(VaultEvent::FileWritten(path.clone()));
+        Ok(())
+    }
+
+    /// Write every dirty file of the Vault, see `Vault::dirty_files`.
+    ///
+    /// A failure to write one file does not stop the others.
+    ///
+    /// # Arguments
+    /// @return BatchReport - `completed` lists the files written, `failed` the files that could not be, with the error
+    pub fn write_all(&mut self) -> BatchReport {
+        let mut report = BatchReport::default();
+        for path in self.dirty_files() {
+            match self.write_file(&path) {
+                Ok(true) => report.completed.push(path),
+                Ok(false) => {}
+                Err(e) => report.failed.push((path, e.to_string())),
+            }
+        }
+        report
+    }
+
+    /// Get the files whose contents changed since they were loaded or last written, sorted.
+    ///
+    /// # Arguments
+    /// @return Vec<PathBuf>
+    pub fn dirty_files(&self) -> Vec<PathBuf> {
+        let mut paths: Vec<PathBuf> = self
+            .files
+            .iter()
+            .filter(|(_, file)| file.is_dirty())
+            .map(|(path, _)| path.clone())
+            .collect();
+        paths.sort();
+        paths
+    }
+
+    /// Create a note or replace its contents, and write it to disk. Uses the path relative to the vault root.
+    ///
+    /// The contents are parsed before anything changes, so invalid front matter leaves the Vault as it was.
+    ///
+    /// # Arguments
+    /// @param path: &PathBuf - A `.md` path inside the vault
+    /// @param contents: &str - The whole note, front matter included
+    /// @return Result<bool> - Whether the note was created; errors with `PathNotInVaultRoot` for paths outside the vault and `InvalidFrontmatter` for unparsable YAML
+    ///
+    /// # Example
+    /// ```should_panic
+    /// use std::path::PathBuf;
+    ///
+    /// use obsidian_driver::file::vault::Vault;
+    ///
+    /// let mut vault = Vault::from_path(PathBuf::from("vault")).unwrap();
+    /// let created = vault.put_note(&PathBuf::from("Inbox/idea.md"), "---\ntags: [idea]\n---\n# Idea").unwrap();
+    /// ```
+    pub fn put_note(&mut self, path: &PathBuf, contents: &str) -> Result<bool> {
+        let inside = path.components().all(|component| matches!(component, Component::Normal(_)));
+        if !inside || path.extension().is_none_or(|ext| ext != "md") {
+            return Err(Error::PathNotInVaultRoot(path.clone(), self.vault_root.clone()));
+        }
+        let mdfile = MDFile::parse(contents)?;
+        let created = match self.files.get_mut(path).and_then(|file| file.get_mdfile_mut()) {
+            Some(existing) => {
+                if *existing != mdfile {
+                    *existing = mdfile;
+                }
+                false
+            }
+            None => {
+                let file = crate::file::File::from_mdfile(self.vault_root.join(path), mdfile);
+                self.files.insert(path.clone(), file);
+                self.notify(VaultEvent::FileAdded(path.clone()));
+                true
+            }
+        };
+        self.write_file(path)?;
+        Ok(created)
+    }
+}
+
+#[cfg(test)]
+mod persist_tests {
+    use super::*;
+
+    #[test]
+    fn test_write_all_writes_changed_files() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.md"), "# A").unwrap();
+        std::fs::write(dir.path().join("b.md"), "# B").unwrap();
+        let mut vault = Vault::from_path(dir.path().to_path_buf()).unwrap();
+        assert!(vault.write_all().completed.is_empty());
+
+        let mdfile = vault.get_file_mut(&"a.md".into()).unwrap().get_mdfile_mut().unwrap();
+        mdfile.set_body("# A\n\nEdited".to_string());
+        assert_eq!(vault.dirty_files(), vec![PathBuf::from("a.md")]);
+        let report = vault.write_all();
+        assert_eq!(report.completed, vec![PathBuf::from("a.md")]);
+        assert!(report.failed.is_empty());
+        assert_eq!(std::fs::read_to_string(dir.path().join("a.md")).unwrap(), "# A\n\nEdited");
+        assert!(vault.dirty_files().is_empty());
+    }
+
+    #[test]
+    fn test_put_note_creates_and_replaces() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.md"), "# A").unwrap();
+        let mut vault = Vault::from_path(dir.path().to_path_buf()).unwrap();
+
+        assert!(!vault.put_note(&"a.md".into(), "---\ntags: [x]\n---\n# A2").unwrap());
+        assert_eq!(std::fs::read_to_string(dir.path().join("a.md")).unwrap(), "---\ntags: [x]\n---\n# A2");
+        assert!(vault.put_note(&"sub/b.md".into(), "# B").unwrap());
+        assert_eq!(std::fs::read_to_string(dir.path().join("sub/b.md")).unwrap(), "# B");
+        assert!(vault.get_file(&"sub/b.md".into()).is_some());
+
+        assert!(vault.put_note(&"../c.md".into(), "# C").is_err());
+        assert!(vault.put_note(&"c.txt".into(), "C").is_err());
+        assert!(matches!(vault.put_note(&"a.md".into(), "---\nkey: [\n---\n"), Err(Error::InvalidFrontmatter(_))));
+        assert_eq!(vault.get_file(&"a.md".into()).unwrap().get_mdfile().unwrap().get_body(), "# A2");
+    }
+}
@@ -0,0 +1,238 @@
+//! obsidian-driver::file::vault::profile
+//!
+//! This module contains the built-in profiler of the Vault, which records the wall time spent in each phase of an operation.
+//!
+//! @public Operation
+//!
+//! @public Phase
+//!
+//! @public PhaseTiming
+//!
+//! @public ProfileReport
+//!
+//! @public Vault::profile_report
+//!
+//! @public Vault::reset_profile
+
+// std imports
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+// third-party imports
+use serde::{Deserialize, Serialize};
+
+// first-party imports
+use super::Vault;
+use crate::prelude::*;
+
+/// A profiled Vault operation.
+///
+/// @public
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Operation {
+    /// `Vault::from_path` and `Vault::from_cache`
+    Load,
+    /// `Vault::update_embeddings`
+    EmbeddingRefresh,
+    /// `Vault::get_closest_files` and `Vault::get_closest_files_by_threshold`
+    Search,
+    /// `Vault::query` and `Vault::run_query`
+    Query,
+}
+
+/// A phase of a profiled operation.
+///
+/// @public
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Phase {
+    /// Reading from or walking the file system, including the embedding store.
+    Io,
+    /// Parsing notes and caches.
+    Parsing,
+    /// Building search indexes.
+    Indexing,
+    /// Running a search against an index or the notes.
+    Lookup,
+    /// Waiting on the AI provider.
+    Api,
+}
+
+/// The accumulated wall time of one phase of an operation.
+///
+/// @public
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PhaseTiming {
+    pub calls: usize,
+    pub total: Duration,
+    pub max: Duration,
+}
+
+/// ProfileReport struct
+///
+/// The wall time recorded per operation and phase since the Vault was created or the profile was reset.
+///
+/// # Example
+/// ```should_panic
+/// use std::path::PathBuf;
+///
+/// use obsidian_driver::file::vault::Vault;
+/// use obsidian_driver::file::vault::profile::{Operation, Phase};
+///
+/// let vault = Vault::from_path(PathBuf::from("vault")).unwrap();
+/// let report = vault.profile_report();
+/// println!("{}", report);
+/// let parsing = report.get(Operation::Load, Phase::Parsing).unwrap();
+/// println!("parsed {} notes in {:?}", parsing.calls, parsing.total);
+/// ```
+/// @public
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProfileReport {
+    timings: BTreeMap<Operation, BTreeMap<Phase, PhaseTiming>>,
+}
+
+impl ProfileReport {
+    /// Get the timing of a phase of an operation.
+    ///
+    /// # Arguments
+    /// @param operation: Operation
+    /// @param phase: Phase
+    /// @return Option<&PhaseTiming> - None if the phase was never recorded
+    pub fn get(&self, operation: Operation, phase: Phase) -> Option<&PhaseTiming> {
+        self.timings.get(&operation)?.get(&phase)
+    }
+
+    /// Get the timings of all phases of an operation.
+    ///
+    /// # Arguments
+    /// @param operation: Operation
+    /// @return Option<&BTreeMap<Phase, PhaseTiming>>
+    pub fn phases(&self, operation: Operation) -> Option<&BTreeMap<Phase, PhaseTiming>> {
+        self.timings.get(&operation)
+    }
+
+    /// Get the total wall time recorded for an operation across its phases.
+    ///
+    /// # Arguments
+    /// @param operation: Operation
+    /// @return Duration
+    pub fn total(&self, operation: Operation) -> Duration {
+        self.phases(operation)
+            .map(|phases| phases.values().map(|timing| timing.total).sum())
+            .unwrap_or_default()
+    }
+
+    /// Check whether anything has been recorded.
+    ///
+    /// # Arguments
+    /// @return bool
+    pub fn is_empty(&self) -> bool {
+        self.timings.is_empty()
+    }
+}
+
+impl Display for ProfileReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{:<18} {:<10} {:>8} {:>14} {:>14}", "operation", "phase", "calls", "total", "max")?;
+        for (operation, phases) in &self.timings {
+            for (phase, timing) in phases {
+                writeln!(
+                    f,
+                    "{:<18} {:<10} {:>8} {:>14} {:>14}",
+                    f!("{:?}", operation),
+                    f!("{:?}", phase),
+                    timing.calls,
+                    f!("{:.3?}", timing.total),
+                    f!("{:.3?}", timing.max)
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Records phase timings for a Vault. Clones share the same recordings.
+///
+/// @crate
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Profiler {
+    report: Arc<Mutex<ProfileReport>>,
+}
+
+impl Profiler {
+    /// Record the wall time of one call of a phase.
+    pub(crate) fn record(&self, operation: Operation, phase: Phase, elapsed: Duration) {
+        let mut report = self.report.lock().expect("Profiler lock poisoned");
+        let timing = report
+            .timings
+            .entry(operation)
+            .or_default()
+            .entry(phase)
+            .or_default();
+        timing.calls += 1;
+        timing.total += elapsed;
+        timing.max = timing.max.max(elapsed);
+    }
+
+    /// Run a closure and record its wall time.
+    pub(crate) fn time<T>(&self, operation: Operation, phase: Phase, f: impl FnOnce() -> T) -> T {
+        let start = Instant::now();
+        let result = f();
+        self.record(operation, phase, start.elapsed());
+        result
+    }
+
+    /// Await a future and record its wall time.
+    pub(crate) async fn time_async<T>(
+        &self,
+        operation: Operation,
+        phase: Phase,
+        future: impl std::future::Future<Output = T>,
+    ) -> T {
+        let start = Instant::now();
+        let result = future.await;
+        self.record(operation, phase, start.elapsed());
+        result
+    }
+}
+
+impl Vault {
+    /// Get the wall time recorded per operation and phase.
+    ///
+    /// # Arguments
+    /// @return ProfileReport
+    pub fn profile_report(&self) -> ProfileReport {
+        self.profiler.report.lock().expect("Profiler lock poisoned").clone()
+    }
+
+    /// Clear the recorded timings, e.g. before profiling a single operation.
+    ///
+    /// # Arguments
+    pub fn reset_profile(&self) {
+        *self.profiler.report.lock().expect("Profiler lock poisoned") = ProfileReport::default();
+    }
+}
+
+#[cfg(test)]
+mod profile_tests {
+    use super::*;
+
+    #[test]
+    fn test_profile_records_load_and_query() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.md"), "# A").unwrap();
+        std::fs::write(dir.path().join("b.md"), "# B").unwrap();
+        let vault = Vault::from_path(dir.path().to_path_buf()).unwrap();
+
+        let report = vault.profile_report();
+        assert_eq!(report.get(Operation::Load, Phase::Parsing).unwrap().calls, 2);
+        assert!(report.get(Operation::Load, Phase::Io).is_some());
+
+        vault.reset_profile();
+        vault.query("WHERE file.name = 'a'").unwrap();
+        let report = vault.profile_report();
+        assert!(report.phases(Operation::Load).is_none());
+        assert_eq!(report.get(Operation::Query, Phase::Lookup).unwrap().calls, 1);
+    }
+}
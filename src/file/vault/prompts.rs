@@ -0,0 +1,178 @@
+//! obsidian-driver::file::vault::prompts
+//!
+//! This module contains the Vault methods that load prompts from the prompt library, a vault folder of prompt notes that can be edited in Obsidian.
+//!
+//! @public Vault::set_prompt_folder
+//!
+//! @public Vault::get_prompt_folder
+//!
+//! @public Vault::get_prompt_names
+//!
+//! @public Vault::get_prompt
+
+// std imports
+use std::path::{Path, PathBuf};
+
+// third-party imports
+
+// first-party imports
+use super::links::normalize_path;
+use super::Vault;
+use crate::ai::prompt::Prompt;
+use crate::file::mdfile::link::Link;
+use crate::prelude::*;
+
+/// The prompt folder used when none is configured.
+const DEFAULT_PROMPT_FOLDER: &str = "_prompts";
+
+impl Vault {
+    /// Set the folder prompts are loaded from, relative to the vault root.
+    ///
+    /// # Arguments
+    /// @param folder: PathBuf
+    pub fn set_prompt_folder(&mut self, folder: PathBuf) {
+        self.prompt_folder = Some(folder);
+    }
+
+    /// Get the folder prompts are loaded from, relative to the vault root. Defaults to `_prompts`.
+    ///
+    /// # Arguments
+    /// @return PathBuf
+    pub fn get_prompt_folder(&self) -> PathBuf {
+        self.prompt_folder
+            .clone()
+            .unwrap_or_else(|| PathBuf::from(DEFAULT_PROMPT_FOLDER))
+    }
+
+    /// Get the names of the prompts in the prompt folder, as paths relative to the folder without the extension, sorted.
+    ///
+    /// # Arguments
+    /// @return Vec<String>
+    pub fn get_prompt_names(&self) -> Vec<String> {
+        let folder = self.get_prompt_folder();
+        let mut names: Vec<String> = self
+            .files
+            .iter()
+            .filter(|(_, file)| file.get_mdfile().is_some())
+            .filter_map(|(path, _)| {
+                let relative = path.strip_prefix(&folder).ok()?.with_extension("");
+                Some(relative.to_string_lossy().replace('\\', "/"))
+            })
+            .collect();
+        names.sort();
+        names
+    }
+
+    /// Parse a note in the prompt folder into a Prompt.
+    ///
+    /// The note uses the prompt file format of `Prompt::from_markdown`. A line that is only an
+    /// embed (`![[Shared Style]]`) or an `!include <path>` directive is replaced by the body of
+    /// that note, so shared instructions can live in their own notes.
+    ///
+    /// # Arguments
+    /// @param name: &str - e.g. `lecture-notes` or `Courses/lecture-notes`
+    /// @return Result<Prompt> - Errors with `PathNotFound` for a missing prompt, `InvalidPrompt` for a malformed one
+    ///
+    /// # Example
+    /// ```should_panic
+    /// use std::path::PathBuf;
+    ///
+    /// use obsidian_driver::ai::prompt::Context;
+    /// use obsidian_driver::file::vault::Vault;
+    ///
+    /// let vault = Vault::from_path(PathBuf::from("vault")).unwrap();
+    /// let prompt = vault.get_prompt("lecture-notes").unwrap();
+    /// let mut context = Context::default();
+    /// context.insert("text", "transcript");
+    /// let prompt = prompt.substitute(&context).unwrap();
+    /// ```
+    pub fn get_prompt(&self, name: &str) -> Result<Prompt> {
+        let path = self.get_prompt_folder().join(f!("{}.md", name));
+        if !self.files.contains_key(&path) {
+            return Err(Error::PathNotFound(path));
+        }
+        let mut mdfile = self.files[&path]
+            .get_mdfile()
+            .ok_or(Error::PathNotFound(path.clone()))?
+            .clone();
+        mdfile.set_body(self.resolve_prompt_includes(&path, &mut Vec::new())?);
+        Prompt::from_markdown(&mdfile.to_string())
+    }
+
+    /// Get the body of a prompt note with its embed and include lines replaced by the bodies of the notes they point to.
+    ///
+    /// @private
+    fn resolve_prompt_includes(&self, path: &Path, stack: &mut Vec<PathBuf>) -> Result<String> {
+        if stack.iter().any(|other| other == path) {
+            return Err(Error::InvalidPrompt(f!("Include cycle at {}", path.display())));
+        }
+        let body = self
+            .files
+            .get(path)
+            .and_then(|file| file.get_mdfile())
+            .ok_or(Error::InvalidPrompt(f!("Included note not found: {}", path.display())))?
+            .get_body();
+        stack.push(path.to_path_buf());
+        let mut resolved = String::with_capacity(body.len());
+        for line in body.split_inclusive('\n') {
+            let trimmed = line.trim();
+            let target = match trimmed.strip_prefix("!include ") {
+                Some(include) => {
+                    let folder = path.parent().unwrap_or(Path::new(""));
+                    Some(normalize_path(&folder.join(include.trim())))
+                }
+                None => match Link::parse_all(trimmed).as_slice() {
+                    [link] if link.embed && link.range == (0..trimmed.len()) => Some(
+                        self.resolve_link(path, link)
+                            .ok_or(Error::InvalidPrompt(f!("Embedded note not found: {}", link.target)))?,
+                    ),
+                    _ => None,
+                },
+            };
+            match target {
+                Some(target) => {
+                    resolved.push_str(self.resolve_prompt_includes(&target, stack)?.trim_end());
+                    resolved.push('\n');
+                }
+                None => resolved.push_str(line),
+            }
+        }
+        stack.pop();
+        Ok(resolved)
+    }
+}
+
+#[cfg(test)]
+mod prompts_tests {
+    use super::*;
+
+    #[test]
+    fn test_get_prompt_with_embeds() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("_prompts/shared")).unwrap();
+        std::fs::write(
+            dir.path().join("_prompts/lecture-notes.md"),
+            "---\nvariables: [text]\nmax_characters: 100\n---\n# System\nYou take notes.\n![[Style]]\n\n# User\n[text]\n",
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("_prompts/shared/Style.md"), "---\ntags: [prompt]\n---\nUse markdown.\n").unwrap();
+        let vault = Vault::from_path(dir.path().to_path_buf()).unwrap();
+
+        assert_eq!(vault.get_prompt_names(), vec!["lecture-notes".to_string(), "shared/Style".to_string()]);
+        let prompt = vault.get_prompt("lecture-notes").unwrap();
+        assert_eq!(prompt.system_prompt, "You take notes.\nUse markdown.");
+        assert_eq!(prompt.user_prompt, "[text]");
+        assert_eq!(prompt.variables, vec!["text".to_string()]);
+        assert!(matches!(vault.get_prompt("missing"), Err(Error::PathNotFound(_))));
+    }
+
+    #[test]
+    fn test_get_prompt_include_cycle() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("_prompts")).unwrap();
+        std::fs::write(dir.path().join("_prompts/a.md"), "# User\n!include b.md\n").unwrap();
+        std::fs::write(dir.path().join("_prompts/b.md"), "![[a]]\n").unwrap();
+        let vault = Vault::from_path(dir.path().to_path_buf()).unwrap();
+        assert!(matches!(vault.get_prompt("a"), Err(Error::InvalidPrompt(_))));
+    }
+}
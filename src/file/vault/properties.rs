@@ -0,0 +1,254 @@
+//! obsidian-driver::file::vault::properties
+//!
+//! This module contains the front matter property index of the Vault: for every key, the values notes give it and the notes that give them, so notes can be looked up by property without reading the front matter of every note. The index is kept up to date by the Vault's methods and saved in its cache.
+//!
+//! @public PropertyIndex
+//!
+//! @public PropertyIndex::lookup
+//!
+//! @public PropertyIndex::values
+//!
+//! @public Vault::property_index
+//!
+//! @public Vault::notes_with_property
+//!
+//! @public Vault::reindex_properties
+
+// std imports
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+
+// third-party imports
+use serde::{Deserialize, Serialize};
+use serde_yaml::Value;
+
+// first-party imports
+use super::query::compare_values;
+use super::Vault;
+use crate::file::File;
+
+/// PropertyIndex struct
+///
+/// Note paths by front matter key and value. Scalar values are indexed by their text, and every
+/// scalar item of a list is indexed, so a note with `tags: [a, b]` is found under `a` and `b`.
+/// Numbers are also indexed as floats, so `3` and `3.0` find the same notes. Nested mappings are
+/// not indexed.
+///
+/// @public
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct PropertyIndex {
+    // key -> value text -> paths
+    entries: BTreeMap<String, BTreeMap<String, BTreeSet<PathBuf>>>,
+    // path -> the (key, value text) pairs it is indexed under, to remove it again
+    notes: BTreeMap<PathBuf, Vec<(String, String)>>,
+}
+
+impl PropertyIndex {
+    /// Build the index of a set of files.
+    ///
+    /// @crate
+    pub(crate) fn build(files: &HashMap<PathBuf, File>) -> Self {
+        let mut index = PropertyIndex::default();
+        for (path, file) in files {
+            index.insert(path, file);
+        }
+        index
+    }
+
+    /// Check whether every file has been indexed, e.g. after loading a cache written before the index existed.
+    ///
+    /// @crate
+    pub(crate) fn covers(&self, files: &HashMap<PathBuf, File>) -> bool {
+        self.notes.len() == files.len() && files.keys().all(|path| self.notes.contains_key(path))
+    }
+
+    /// Index a file, replacing what it was indexed under before.
+    ///
+    /// @crate
+    pub(crate) fn insert(&mut self, path: &Path, file: &File) {
+        self.remove(path);
+        let mut pairs = Vec::new();
+        if let Some(Value::Mapping(mapping)) = file.get_mdfile().and_then(|mdfile| mdfile.get_yaml()) {
+            for (key, value) in mapping {
+                let Some(key) = key.as_str() else {
+                    continue;
+                };
+                let items = match value {
+                    Value::Sequence(items) => items.iter().collect(),
+                    value => vec![value],
+                };
+                for text in items.into_iter().flat_map(value_texts) {
+                    pairs.push((key.to_string(), text));
+                }
+            }
+        }
+        pairs.sort();
+        pairs.dedup();
+        for (key, text) in pairs.iter() {
+            let paths = self.entries.entry(key.clone()).or_default().entry(text.clone()).or_default();
+            paths.insert(path.to_path_buf());
+        }
+        self.notes.insert(path.to_path_buf(), pairs);
+    }
+
+    /// Remove a file from the index.
+    ///
+    /// @crate
+    pub(crate) fn remove(&mut self, path: &Path) {
+        let Some(pairs) = self.notes.remove(path) else {
+            return;
+        };
+        for (key, text) in pairs {
+            let Some(values) = self.entries.get_mut(&key) else {
+                continue;
+            };
+            if let Some(paths) = values.get_mut(&text) {
+                paths.remove(path);
+                if paths.is_empty() {
+                    values.remove(&text);
+                }
+            }
+            if values.is_empty() {
+                self.entries.remove(&key);
+            }
+        }
+    }
+
+    /// Get the notes that may give a key a value, or a list containing it.
+    ///
+    /// The result can include notes whose value only has the same text, e.g. `true` for the
+    /// string `"true"`; `Vault::notes_with_property` checks the values.
+    ///
+    /// # Arguments
+    /// @param key: &str
+    /// @param value: &Value
+    /// @return BTreeSet<&PathBuf>
+    pub fn lookup(&self, key: &str, value: &Value) -> BTreeSet<&PathBuf> {
+        let Some(values) = self.entries.get(key) else {
+            return BTreeSet::new();
+        };
+        value_texts(value)
+            .iter()
+            .filter_map(|text| values.get(text))
+            .flatten()
+            .collect()
+    }
+
+    /// Get the values given to a key, with the number of notes giving each.
+    ///
+    /// # Arguments
+    /// @param key: &str
+    /// @return Vec<(String, usize)> - Sorted by value
+    pub fn values(&self, key: &str) -> Vec<(String, usize)> {
+        self.entries
+            .get(key)
+            .map(|values| values.iter().map(|(text, paths)| (text.clone(), paths.len())).collect())
+            .unwrap_or_default()
+    }
+}
+
+/// The texts a scalar value is indexed under.
+///
+/// @private
+fn value_texts(value: &Value) -> Vec<String> {
+    match value {
+        Value::String(string) => vec![string.clone()],
+        Value::Bool(boolean) => vec![boolean.to_string()],
+        Value::Null => vec!["null".to_string()],
+        Value::Number(number) => {
+            let mut texts = vec![number.to_string()];
+            if let Some(float) = number.as_f64() {
+                texts.push(float.to_string());
+            }
+            texts.dedup();
+            texts
+        }
+        _ => Vec::new(),
+    }
+}
+
+impl Vault {
+    /// Get the front matter property index of the Vault.
+    ///
+    /// # Arguments
+    /// @return &PropertyIndex
+    pub fn property_index(&self) -> &PropertyIndex {
+        &self.properties
+    }
+
+    /// Get the notes whose front matter gives a key a value, or a list containing it, using the property index.
+    ///
+    /// # Arguments
+    /// @param key: &str
+    /// @param value: &Value
+    /// @return Vec<PathBuf> - Sorted
+    ///
+    /// # Example
+    /// ```should_panic
+    /// use std::path::PathBuf;
+    ///
+    /// use obsidian_driver::file::vault::Vault;
+    ///
+    /// let vault = Vault::from_path(PathBuf::from("vault")).unwrap();
+    /// let notes = vault.notes_with_property("course", &"CPSC 351".into());
+    /// ```
+    pub fn notes_with_property(&self, key: &str, value: &Value) -> Vec<PathBuf> {
+        self.properties
+            .lookup(key, value)
+            .into_iter()
+            .filter(|path| {
+                let found = self.files.get(*path).and_then(|file| file.get_mdfile()?.get_yaml_key(key).cloned());
+                match found {
+                    Some(Value::Sequence(items)) => items
+                        .iter()
+                        .any(|item| compare_values(item, value) == Some(std::cmp::Ordering::Equal)),
+                    Some(found) => compare_values(&found, value) == Some(std::cmp::Ordering::Equal),
+                    None => false,
+                }
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Rebuild the property index from every note.
+    ///
+    /// The Vault's methods keep the index up to date; a note edited through `get_file_mut` is
+    /// re-indexed when it is written with `write_file`, or by calling this.
+    ///
+    /// # Arguments
+    pub fn reindex_properties(&mut self) {
+        self.properties = PropertyIndex::build(&self.files);
+    }
+}
+
+#[cfg(test)]
+mod properties_tests {
+    use super::*;
+    use crate::file::vault::links::BrokenLinkPolicy;
+
+    #[test]
+    fn test_property_index_follows_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.md"), "---\ncourse: CPSC 351\nweek: 3\ntags: [exam]\n---\n# A").unwrap();
+        std::fs::write(dir.path().join("b.md"), "---\ncourse: CPSC 351\nweek: 4\n---\n# B").unwrap();
+        std::fs::write(dir.path().join("c.md"), "# C").unwrap();
+        let mut vault = Vault::from_path(dir.path().to_path_buf()).unwrap();
+
+        let course: Value = "CPSC 351".into();
+        assert_eq!(vault.notes_with_property("course", &course), vec![PathBuf::from("a.md"), PathBuf::from("b.md")]);
+        assert_eq!(vault.notes_with_property("week", &Value::from(3.0)), vec![PathBuf::from("a.md")]);
+        assert_eq!(vault.notes_with_property("tags", &"exam".into()), vec![PathBuf::from("a.md")]);
+        assert_eq!(vault.property_index().values("course"), vec![("CPSC 351".to_string(), 2)]);
+        assert_eq!(vault.query("WHERE course = 'CPSC 351' AND week > 3").unwrap(), vec![PathBuf::from("b.md")]);
+
+        vault.move_file(&PathBuf::from("a.md"), &PathBuf::from("d.md")).unwrap();
+        vault.remove_file(&PathBuf::from("b.md"), BrokenLinkPolicy::Ignore).unwrap();
+        assert_eq!(vault.notes_with_property("course", &course), vec![PathBuf::from("d.md")]);
+
+        let cache_dir = tempfile::tempdir().unwrap();
+        let cache = cache_dir.path().join("cache.json");
+        vault.to_cache(&cache).unwrap();
+        let cached = Vault::from_cache(dir.path().to_path_buf(), &cache).unwrap();
+        assert_eq!(cached.property_index(), vault.property_index());
+    }
+}
@@ -0,0 +1,980 @@
+//! obsidian-driver::file::vault::query
+//!
+//! This module contains a Dataview-style query engine that filters and sorts the notes of a Vault by tags, folders, and front matter fields, and groups and projects the results into tables.
+//!
+//! @public Query
+//!
+//! @public Query::parse
+//!
+//! @public QueryTable
+//!
+//! @public QueryGroup
+//!
+//! @public QueryRow
+//!
+//! @public QuerySource
+//!
+//! @public QueryCondition
+//!
+//! @public QueryOp
+//!
+//! @public SortOrder
+//!
+//! @public Vault::query
+//!
+//! @public Vault::run_query
+//!
+//! @public Vault::table
+//!
+//! @public Vault::run_table
+
+// std imports
+use std::cmp::Ordering;
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+// third-party imports
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_yaml::{Mapping, Value};
+
+// first-party imports
+use super::profile::{Operation, Phase};
+use super::properties::PropertyIndex;
+use super::Vault;
+use crate::file::File;
+use crate::prelude::*;
+
+/// Where a query takes its notes from.
+///
+/// @public
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum QuerySource {
+    /// Notes with the tag, or a nested tag below it (`#lecture` matches `#lecture/cpsc`).
+    Tag(String),
+    /// Notes in the folder or any of its subfolders, relative to the vault root.
+    Folder(PathBuf),
+}
+
+impl QuerySource {
+    /// Check whether a note at a path with the given tags comes from this source.
+    ///
+    /// # Arguments
+    /// @param path: &Path - The note path relative to the vault root
+    /// @param tags: &[String] - The tags of the note, see `MDFile::get_tags`
+    /// @return bool
+    pub fn matches(&self, path: &Path, tags: &[String]) -> bool {
+        match self {
+            QuerySource::Tag(tag) => tags.iter().any(|other| {
+                let other = other.to_lowercase();
+                let tag = tag.to_lowercase();
+                other == tag || other.starts_with(&f!("{}/", tag))
+            }),
+            QuerySource::Folder(folder) => path.starts_with(folder),
+        }
+    }
+}
+
+/// A comparison in a query condition.
+///
+/// @public
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum QueryOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    /// List membership for lists, substring for strings.
+    Contains,
+}
+
+/// A condition on a field. Without a comparison, the field must be present and truthy.
+///
+/// Fields are front matter keys, or one of `file.name`, `file.title`, `file.path`, `file.folder`, `file.tags`, `file.mtime`.
+/// `file.title` is the `title` front matter key, else the first top-level heading, else the file name.
+///
+/// @public
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct QueryCondition {
+    pub field: String,
+    pub comparison: Option<(QueryOp, Value)>,
+}
+
+/// Sort direction.
+///
+/// @public
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SortOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
+/// Query struct
+///
+/// Sources and conditions are each an OR of AND groups. Notes without a matching source are
+/// excluded; a query without sources covers the whole vault. Results are sorted by path unless
+/// sort keys are given, and notes missing a sort field come last. Selected fields and a group
+/// field only matter to `Vault::run_table`.
+///
+/// # Example
+/// ```
+/// use obsidian_driver::file::vault::query::{Query, QueryOp, SortOrder};
+///
+/// let parsed = Query::parse("FROM #lecture WHERE status = 'todo' SORT date DESC").unwrap();
+/// let built = Query::new()
+/// 	.from_tag("lecture")
+/// 	.filter("status", QueryOp::Eq, "todo".into())
+/// 	.sort("date", SortOrder::Desc);
+/// assert_eq!(parsed, built);
+/// ```
+/// @public
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Query {
+    sources: Vec<Vec<QuerySource>>,
+    conditions: Vec<Vec<QueryCondition>>,
+    sort: Vec<(String, SortOrder)>,
+    limit: Option<usize>,
+    #[serde(default)]
+    fields: Vec<String>,
+    #[serde(default)]
+    group: Option<String>,
+}
+
+impl Query {
+    /// Create an empty query that matches every note.
+    ///
+    /// # Arguments
+    /// @return Query
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only include notes with the given tag (AND with previous sources).
+    ///
+    /// # Arguments
+    /// @param tag: &str - The tag, with or without the leading `#`
+    /// @return Query
+    pub fn from_tag(self, tag: &str) -> Self {
+        self.and_source(QuerySource::Tag(tag.trim_start_matches('#').to_string()))
+    }
+
+    /// Only include notes in the given folder (AND with previous sources).
+    ///
+    /// # Arguments
+    /// @param folder: impl Into<PathBuf> - The folder relative to the vault root
+    /// @return Query
+    pub fn from_folder(self, folder: impl Into<PathBuf>) -> Self {
+        self.and_source(QuerySource::Folder(folder.into()))
+    }
+
+    /// Only include notes where the field compares to the value (AND with previous conditions).
+    ///
+    /// # Arguments
+    /// @param field: &str
+    /// @param op: QueryOp
+    /// @param value: Value
+    /// @return Query
+    pub fn filter(self, field: &str, op: QueryOp, value: Value) -> Self {
+        self.and_condition(QueryCondition {
+            field: field.to_string(),
+            comparison: Some((op, value)),
+        })
+    }
+
+    /// Only include notes where the field is present and truthy (AND with previous conditions).
+    ///
+    /// # Arguments
+    /// @param field: &str
+    /// @return Query
+    pub fn filter_exists(self, field: &str) -> Self {
+        self.and_condition(QueryCondition {
+            field: field.to_string(),
+            comparison: None,
+        })
+    }
+
+    /// Add a sort key. Earlier keys take precedence.
+    ///
+    /// # Arguments
+    /// @param field: &str
+    /// @param order: SortOrder
+    /// @return Query
+    pub fn sort(mut self, field: &str, order: SortOrder) -> Self {
+        self.sort.push((field.to_string(), order));
+        self
+    }
+
+    /// Limit the number of results.
+    ///
+    /// # Arguments
+    /// @param limit: usize
+    /// @return Query
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Add a field to the columns of the table results.
+    ///
+    /// # Arguments
+    /// @param field: &str
+    /// @return Query
+    pub fn select(mut self, field: &str) -> Self {
+        self.fields.push(field.to_string());
+        self
+    }
+
+    /// Group the table results by a field. Notes with a list value are in the group of each item.
+    ///
+    /// # Arguments
+    /// @param field: &str - e.g. `file.tags`, `file.folder`, or a front matter key
+    /// @return Query
+    pub fn group_by(mut self, field: &str) -> Self {
+        self.group = Some(field.to_string());
+        self
+    }
+
+    fn and_source(mut self, source: QuerySource) -> Self {
+        match self.sources.last_mut() {
+            Some(group) => group.push(source),
+            None => self.sources.push(vec![source]),
+        }
+        self
+    }
+
+    fn and_condition(mut self, condition: QueryCondition) -> Self {
+        match self.conditions.last_mut() {
+            Some(group) => group.push(condition),
+            None => self.conditions.push(vec![condition]),
+        }
+        self
+    }
+
+    /// Parse a query string.
+    ///
+    /// The syntax is a subset of Dataview: `TABLE <field>, ... FROM <sources> WHERE <conditions> SORT <field> [ASC|DESC], ... GROUP BY <field> LIMIT <n>`,
+    /// with every clause optional. Sources are `#tag` or `"folder"`, conditions are `field`
+    /// or `field <op> value` with `=`, `!=`, `<`, `<=`, `>`, `>=`, or `contains`, and both
+    /// are combined with `AND` / `OR` (AND binds tighter). Keywords are case-insensitive.
+    ///
+    /// # Arguments
+    /// @param query: &str
+    /// @return Result<Query>
+    ///
+    /// # Example
+    /// ```
+    /// use obsidian_driver::file::vault::query::Query;
+    ///
+    /// let query = Query::parse(r#"FROM #lecture OR "Courses/CPSC 351" WHERE week >= 3 AND reviewed LIMIT 10"#).unwrap();
+    /// let table = Query::parse("TABLE week, status FROM #lecture SORT file.title GROUP BY course").unwrap();
+    /// assert!(Query::parse("WHERE status =").is_err());
+    /// ```
+    pub fn parse(query: &str) -> Result<Self> {
+        let tokens = tokenize(query)?;
+        let mut parser = Parser { tokens, position: 0 };
+        let mut parsed = Query::new();
+        while let Some(token) = parser.next() {
+            match token {
+                Token::Word(word) if word.eq_ignore_ascii_case("FROM") => {
+                    parsed.sources = parser.groups(Parser::source)?;
+                }
+                Token::Word(word) if word.eq_ignore_ascii_case("WHERE") => {
+                    parsed.conditions = parser.groups(Parser::condition)?;
+                }
+                Token::Word(word) if word.eq_ignore_ascii_case("SORT") => loop {
+                    let field = parser.word("a sort field")?;
+                    let order = match parser.peek_keyword() {
+                        Some(keyword) if keyword == "ASC" => SortOrder::Asc,
+                        Some(keyword) if keyword == "DESC" => SortOrder::Desc,
+                        _ => {
+                            parsed.sort.push((field, SortOrder::Asc));
+                            if parser.eat(&Token::Comma) {
+                                continue;
+                            }
+                            break;
+                        }
+                    };
+                    parser.next();
+                    parsed.sort.push((field, order));
+                    if !parser.eat(&Token::Comma) {
+                        break;
+                    }
+                },
+                Token::Word(word) if word.eq_ignore_ascii_case("TABLE") => loop {
+                    parsed.fields.push(parser.word("a field")?);
+                    if !parser.eat(&Token::Comma) {
+                        break;
+                    }
+                },
+                Token::Word(word) if word.eq_ignore_ascii_case("GROUP") => {
+                    if parser.peek_keyword().as_deref() != Some("BY") {
+                        return Err(Error::InvalidQuery("Expected BY after GROUP".to_string()));
+                    }
+                    parser.next();
+                    parsed.group = Some(parser.word("a group field")?);
+                }
+                Token::Word(word) if word.eq_ignore_ascii_case("LIMIT") => {
+                    let limit = parser.word("a limit")?;
+                    parsed.limit = Some(limit.parse().map_err(|_| {
+                        Error::InvalidQuery(f!("Invalid limit: {}", limit))
+                    })?);
+                }
+                other => {
+                    return Err(Error::InvalidQuery(f!("Unexpected {:?}", other)));
+                }
+            }
+        }
+        Ok(parsed)
+    }
+
+    /// Check whether a note matches the sources and conditions of the query.
+    ///
+    /// @crate
+    pub(crate) fn matches(&self, path: &Path, file: &File) -> bool {
+        let Some(mdfile) = file.get_mdfile() else {
+            return false;
+        };
+        let tags = mdfile.get_tags();
+        let source_matches = |source: &QuerySource| source.matches(path, &tags);
+        let sources_match = self.sources.is_empty()
+            || self
+                .sources
+                .iter()
+                .any(|group| group.iter().all(source_matches));
+        let conditions_match = self.conditions.is_empty()
+            || self.conditions.iter().any(|group| {
+                group
+                    .iter()
+                    .all(|condition| condition.matches(field_value(path, file, &condition.field).as_ref()))
+            });
+        sources_match && conditions_match
+    }
+
+    /// Get the notes that can match the conditions from the property index, if every condition group has an equality on a front matter key.
+    ///
+    /// The index is updated when files are written, so notes edited since, which are dirty, are
+    /// not in it by their new values; callers add the dirty notes to the candidates.
+    ///
+    /// @crate
+    pub(crate) fn indexed_candidates<'a>(&self, index: &'a PropertyIndex) -> Option<BTreeSet<&'a PathBuf>> {
+        if self.conditions.is_empty() {
+            return None;
+        }
+        let mut candidates = BTreeSet::new();
+        for group in &self.conditions {
+            let indexed = group.iter().find_map(|condition| match &condition.comparison {
+                Some((QueryOp::Eq, value)) if !condition.field.starts_with("file.") => {
+                    Some(index.lookup(&condition.field, value))
+                }
+                _ => None,
+            })?;
+            candidates.extend(indexed);
+        }
+        Some(candidates)
+    }
+
+    /// Sort and limit matching notes in place.
+    ///
+    /// @crate
+    pub(crate) fn order<'a>(&self, results: &mut Vec<(&'a PathBuf, &'a File)>) {
+        results.sort_by(|a, b| a.0.cmp(b.0));
+        results.sort_by(|a, b| {
+            for (field, order) in &self.sort {
+                let a_value = field_value(a.0, a.1, field);
+                let b_value = field_value(b.0, b.1, field);
+                let ordering = match (a_value, b_value) {
+                    (Some(a_value), Some(b_value)) => {
+                        let ordering = compare_values(&a_value, &b_value).unwrap_or(Ordering::Equal);
+                        match order {
+                            SortOrder::Asc => ordering,
+                            SortOrder::Desc => ordering.reverse(),
+                        }
+                    }
+                    (Some(_), None) => Ordering::Less,
+                    (None, Some(_)) => Ordering::Greater,
+                    (None, None) => Ordering::Equal,
+                };
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+            }
+            Ordering::Equal
+        });
+        if let Some(limit) = self.limit {
+            results.truncate(limit);
+        }
+    }
+}
+
+/// QueryTable struct
+///
+/// The results of a query as rows of the selected fields, in groups. Without a group field there
+/// is one group, with no key.
+///
+/// # Example
+/// ```
+/// use std::path::PathBuf;
+///
+/// use serde::Deserialize;
+///
+/// use obsidian_driver::file::vault::query::{QueryGroup, QueryRow, QueryTable};
+///
+/// #[derive(Deserialize)]
+/// struct Lecture {
+/// 	path: PathBuf,
+/// 	file_name: String,
+/// 	week: Option<u32>,
+/// }
+///
+/// let table = QueryTable {
+/// 	fields: vec!["file.name".to_string(), "week".to_string()],
+/// 	groups: vec![QueryGroup {
+/// 		key: None,
+/// 		rows: vec![QueryRow { path: PathBuf::from("Mitosis.md"), values: vec![Some("Mitosis".into()), None] }],
+/// 	}],
+/// };
+/// let lectures: Vec<Lecture> = table.deserialize_rows().unwrap();
+/// assert_eq!(lectures[0].file_name, "Mitosis");
+/// assert_eq!(lectures[0].week, None);
+/// ```
+/// @public
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct QueryTable {
+    pub fields: Vec<String>,
+    pub groups: Vec<QueryGroup>,
+}
+
+/// The rows of a table that share a value of the group field, in result order.
+///
+/// @public
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct QueryGroup {
+    /// None for notes without the group field, and for the only group of an ungrouped table.
+    pub key: Option<Value>,
+    pub rows: Vec<QueryRow>,
+}
+
+/// A note of a table, with the values of the selected fields in column order.
+///
+/// @public
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct QueryRow {
+    pub path: PathBuf,
+    pub values: Vec<Option<Value>>,
+}
+
+impl QueryTable {
+    /// Iterate over the rows of every group, in order.
+    ///
+    /// # Arguments
+    /// @return impl Iterator<Item = &QueryRow>
+    pub fn rows(&self) -> impl Iterator<Item = &QueryRow> {
+        self.groups.iter().flat_map(|group| group.rows.iter())
+    }
+
+    /// Get the value of a field in a row, by field name.
+    ///
+    /// # Arguments
+    /// @param row: &QueryRow
+    /// @param field: &str
+    /// @return Option<&Value> - None if the field is not selected or the note does not have it
+    pub fn value<'a>(&self, row: &'a QueryRow, field: &str) -> Option<&'a Value> {
+        let column = self.fields.iter().position(|other| other == field)?;
+        row.values.get(column)?.as_ref()
+    }
+
+    /// Deserialize every row into a struct.
+    ///
+    /// The struct gets the note path as `path`, and each selected field under its name with dots
+    /// replaced by underscores (`file.name` as `file_name`). Fields a note does not have are left
+    /// out, so they should be `Option`s or have defaults.
+    ///
+    /// # Arguments
+    /// @return Result<Vec<T>> - Errors with `InvalidQuery` naming the first row that does not fit
+    pub fn deserialize_rows<T: DeserializeOwned>(&self) -> Result<Vec<T>> {
+        self.rows()
+            .map(|row| {
+                let mut mapping = Mapping::new();
+                mapping.insert("path".into(), Value::String(row.path.to_string_lossy().into_owned()));
+                for (field, value) in self.fields.iter().zip(row.values.iter()) {
+                    if let Some(value) = value {
+                        mapping.insert(field.replace('.', "_").into(), value.clone());
+                    }
+                }
+                serde_yaml::from_value(Value::Mapping(mapping))
+                    .map_err(|e| Error::InvalidQuery(f!("Row {} does not fit: {}", row.path.display(), e)))
+            })
+            .collect()
+    }
+}
+
+impl QueryCondition {
+    /// Check the condition against a field value.
+    ///
+    /// @private
+    fn matches(&self, value: Option<&Value>) -> bool {
+        let Some((op, expected)) = &self.comparison else {
+            return match value {
+                None | Some(Value::Null) | Some(Value::Bool(false)) => false,
+                Some(Value::String(string)) => !string.is_empty(),
+                Some(_) => true,
+            };
+        };
+        let ordering = value.and_then(|value| compare_values(value, expected));
+        match op {
+            QueryOp::Eq => ordering == Some(Ordering::Equal),
+            QueryOp::Ne => ordering != Some(Ordering::Equal),
+            QueryOp::Lt => ordering == Some(Ordering::Less),
+            QueryOp::Le => matches!(ordering, Some(Ordering::Less | Ordering::Equal)),
+            QueryOp::Gt => ordering == Some(Ordering::Greater),
+            QueryOp::Ge => matches!(ordering, Some(Ordering::Greater | Ordering::Equal)),
+            QueryOp::Contains => match (value, expected) {
+                (Some(Value::Sequence(items)), _) => items
+                    .iter()
+                    .any(|item| compare_values(item, expected) == Some(Ordering::Equal)),
+                (Some(Value::String(string)), Value::String(expected)) => string.contains(expected.as_str()),
+                _ => false,
+            },
+        }
+    }
+}
+
+impl Vault {
+    /// Run a query string against the Vault.
+    ///
+    /// # Arguments
+    /// @param query: &str - See `Query::parse` for the syntax
+    /// @return Result<Vec<PathBuf>> - The matching note paths, in result order
+    ///
+    /// # Example
+    /// ```should_panic
+    /// use std::path::PathBuf;
+    ///
+    /// use obsidian_driver::file::vault::Vault;
+    ///
+    /// let vault = Vault::from_path(PathBuf::from("vault")).unwrap();
+    /// let todo = vault.query("FROM #lecture WHERE status = 'todo' SORT date DESC").unwrap();
+    /// ```
+    pub fn query(&self, query: &str) -> Result<Vec<PathBuf>> {
+        Ok(self.run_query(&Query::parse(query)?))
+    }
+
+    /// Run a parsed or built Query against the Vault.
+    ///
+    /// If every `OR` group of the conditions has an `=` on a front matter key, only the notes the
+    /// property index finds for it are checked.
+    ///
+    /// # Arguments
+    /// @param query: &Query
+    /// @return Vec<PathBuf> - The matching note paths, in result order
+    pub fn run_query(&self, query: &Query) -> Vec<PathBuf> {
+        self.matching(query).into_iter().map(|(path, _)| path.clone()).collect()
+    }
+
+    /// Run a query string against the Vault, and get its results as a table.
+    ///
+    /// # Arguments
+    /// @param query: &str - See `Query::parse` for the syntax
+    /// @return Result<QueryTable>
+    ///
+    /// # Example
+    /// ```should_panic
+    /// use std::path::PathBuf;
+    ///
+    /// use obsidian_driver::file::vault::Vault;
+    ///
+    /// let vault = Vault::from_path(PathBuf::from("vault")).unwrap();
+    /// let table = vault.table("TABLE week, status FROM #lecture SORT week GROUP BY course").unwrap();
+    /// for group in table.groups {
+    /// 	println!("{:?}: {} lectures", group.key, group.rows.len());
+    /// }
+    /// ```
+    pub fn table(&self, query: &str) -> Result<QueryTable> {
+        Ok(self.run_table(&Query::parse(query)?))
+    }
+
+    /// Run a parsed or built Query against the Vault, and get its results as a table.
+    ///
+    /// Rows hold the selected fields of each matching note. Groups are sorted by key, with notes
+    /// without the group field last, and keep the result order within each group; a limit
+    /// applies to notes before grouping.
+    ///
+    /// # Arguments
+    /// @param query: &Query
+    /// @return QueryTable
+    pub fn run_table(&self, query: &Query) -> QueryTable {
+        let results = self.matching(query);
+        let mut groups: Vec<QueryGroup> = Vec::new();
+        for (path, file) in results {
+            let row = QueryRow {
+                path: path.clone(),
+                values: query.fields.iter().map(|field| field_value(path, file, field)).collect(),
+            };
+            let keys = match query.group.as_ref().map(|field| field_value(path, file, field)) {
+                None => vec![None],
+                Some(Some(Value::Sequence(items))) if !items.is_empty() => items.into_iter().map(Some).collect(),
+                Some(Some(Value::Sequence(_))) | Some(None) => vec![None],
+                Some(key) => vec![key],
+            };
+            for key in keys {
+                match groups.iter_mut().find(|group| group.key == key) {
+                    Some(group) => group.rows.push(row.clone()),
+                    None => groups.push(QueryGroup { key, rows: vec![row.clone()] }),
+                }
+            }
+        }
+        groups.sort_by(|a, b| match (&a.key, &b.key) {
+            (Some(a), Some(b)) => compare_values(a, b).unwrap_or(Ordering::Equal),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        });
+        if groups.is_empty() && query.group.is_none() {
+            groups.push(QueryGroup::default());
+        }
+        QueryTable {
+            fields: query.fields.clone(),
+            groups,
+        }
+    }
+
+    /// Get the notes matching a query, in result order.
+    ///
+    /// @private
+    fn matching(&self, query: &Query) -> Vec<(&PathBuf, &File)> {
+        self.profiler.time(Operation::Query, Phase::Lookup, || {
+            let mut results: Vec<(&PathBuf, &File)> = match query.indexed_candidates(&self.properties) {
+                Some(candidates) => candidates
+                    .into_iter()
+                    .filter_map(|path| self.files.get_key_value(path))
+                    .filter(|(_, file)| !file.is_dirty())
+                    .chain(self.files.iter().filter(|(_, file)| file.is_dirty()))
+                    .filter(|(path, file)| query.matches(path, file))
+                    .collect(),
+                None => self
+                    .files
+                    .iter()
+                    .filter(|(path, file)| query.matches(path, file))
+                    .collect(),
+            };
+            query.order(&mut results);
+            results
+        })
+    }
+}
+
+/// Get the value of a query field for a note.
+///
+/// @crate
+pub(crate) fn field_value(path: &Path, file: &File, field: &str) -> Option<Value> {
+    let path_string = |path: &Path| Value::String(path.to_string_lossy().replace('\\', "/"));
+    match field {
+        "file.name" => path
+            .file_stem()
+            .map(|stem| Value::String(stem.to_string_lossy().into_owned())),
+        "file.title" => {
+            let mdfile = file.get_mdfile()?;
+            match mdfile.get_yaml_key("title") {
+                Some(Value::String(title)) if !title.is_empty() => Some(Value::String(title.clone())),
+                _ => mdfile
+                    .get_sections()
+                    .into_iter()
+                    .find(|section| section.level == 1)
+                    .map(|section| Value::String(section.heading))
+                    .or_else(|| field_value(path, file, "file.name")),
+            }
+        }
+        "file.path" => Some(path_string(path)),
+        "file.folder" => path.parent().map(path_string),
+        "file.tags" => file.get_mdfile().map(|mdfile| {
+            Value::Sequence(mdfile.get_tags().into_iter().map(Value::String).collect())
+        }),
+        "file.mtime" => file.last_modified.map(|millis| Value::Number((millis as u64).into())),
+        _ => file.get_mdfile()?.get_yaml_key(field).cloned(),
+    }
+}
+
+/// Compare two scalar values of the same kind.
+///
+/// @crate
+pub(crate) fn compare_values(a: &Value, b: &Value) -> Option<Ordering> {
+    match (a, b) {
+        (Value::Number(a), Value::Number(b)) => a.as_f64()?.partial_cmp(&b.as_f64()?),
+        (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+        (Value::Bool(a), Value::Bool(b)) => Some(a.cmp(b)),
+        (Value::Null, Value::Null) => Some(Ordering::Equal),
+        (Value::Number(a), Value::String(b)) => Some(a.to_string().cmp(b)),
+        (Value::String(a), Value::Number(b)) => Some(a.cmp(&b.to_string())),
+        _ => None,
+    }
+}
+
+/// A token of a query string.
+///
+/// @private
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Word(String),
+    Str(String),
+    Op(String),
+    Comma,
+}
+
+/// Split a query string into tokens.
+///
+/// @private
+fn tokenize(query: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = query.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '"' | '\'' => {
+                chars.next();
+                let mut string = String::new();
+                loop {
+                    match chars.next() {
+                        Some(end) if end == c => break,
+                        Some(other) => string.push(other),
+                        None => return Err(Error::InvalidQuery(f!("Unterminated string: {}", string))),
+                    }
+                }
+                tokens.push(Token::Str(string));
+            }
+            ',' => {
+                chars.next();
+                tokens.push(Token::Comma);
+            }
+            '=' | '!' | '<' | '>' => {
+                chars.next();
+                let mut op = c.to_string();
+                if chars.peek() == Some(&'=') {
+                    chars.next();
+                    op.push('=');
+                }
+                if op == "!" {
+                    return Err(Error::InvalidQuery("Expected != ".to_string()));
+                }
+                tokens.push(Token::Op(op));
+            }
+            _ => {
+                let mut word = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || ",=!<>\"'".contains(c) {
+                        break;
+                    }
+                    word.push(c);
+                    chars.next();
+                }
+                tokens.push(Token::Word(word));
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+/// A cursor over query tokens.
+///
+/// @private
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn peek_keyword(&self) -> Option<String> {
+        match self.tokens.get(self.position) {
+            Some(Token::Word(word)) => Some(word.to_uppercase()),
+            _ => None,
+        }
+    }
+
+    fn eat(&mut self, token: &Token) -> bool {
+        if self.tokens.get(self.position) == Some(token) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn word(&mut self, expected: &str) -> Result<String> {
+        match self.next() {
+            Some(Token::Word(word)) => Ok(word),
+            other => Err(Error::InvalidQuery(f!("Expected {}, found {:?}", expected, other))),
+        }
+    }
+
+    /// Parse items separated by AND / OR into an OR of AND groups.
+    fn groups<T>(&mut self, item: fn(&mut Parser) -> Result<T>) -> Result<Vec<Vec<T>>> {
+        let mut groups = vec![vec![item(self)?]];
+        loop {
+            match self.peek_keyword().as_deref() {
+                Some("AND") => {
+                    self.next();
+                    groups.last_mut().expect("No group").push(item(self)?);
+                }
+                Some("OR") => {
+                    self.next();
+                    groups.push(vec![item(self)?]);
+                }
+                _ => return Ok(groups),
+            }
+        }
+    }
+
+    fn source(&mut self) -> Result<QuerySource> {
+        match self.next() {
+            Some(Token::Word(word)) if word.starts_with('#') => match word.trim_start_matches('#') {
+                "" => Err(Error::InvalidQuery(f!("Expected a tag name, found {}", word))),
+                tag => Ok(QuerySource::Tag(tag.to_string())),
+            },
+            Some(Token::Str(folder)) | Some(Token::Word(folder)) => {
+                Ok(QuerySource::Folder(PathBuf::from(folder)))
+            }
+            other => Err(Error::InvalidQuery(f!("Expected a source, found {:?}", other))),
+        }
+    }
+
+    fn condition(&mut self) -> Result<QueryCondition> {
+        let field = self.word("a field")?;
+        let op = match self.tokens.get(self.position) {
+            Some(Token::Op(op)) => match op.as_str() {
+                "=" => QueryOp::Eq,
+                "!=" => QueryOp::Ne,
+                "<" => QueryOp::Lt,
+                "<=" => QueryOp::Le,
+                ">" => QueryOp::Gt,
+                ">=" => QueryOp::Ge,
+                other => return Err(Error::InvalidQuery(f!("Unknown operator: {}", other))),
+            },
+            Some(Token::Word(word)) if word.eq_ignore_ascii_case("contains") => QueryOp::Contains,
+            _ => {
+                return Ok(QueryCondition {
+                    field,
+                    comparison: None,
+                })
+            }
+        };
+        self.next();
+        let value = match self.next() {
+            Some(Token::Str(string)) => Value::String(string),
+            Some(Token::Word(word)) => serde_yaml::from_str(&word).unwrap_or(Value::String(word)),
+            other => return Err(Error::InvalidQuery(f!("Expected a value, found {:?}", other))),
+        };
+        Ok(QueryCondition {
+            field,
+            comparison: Some((op, value)),
+        })
+    }
+}
+
+#[cfg(test)]
+mod query_tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_groups() {
+        let actual = Query::parse("from #a and 'b' or #c where x or y > 2 and z contains w sort x, y desc limit 3").unwrap();
+        let expected = Query {
+            sources: vec![
+                vec![QuerySource::Tag("a".to_string()), QuerySource::Folder(PathBuf::from("b"))],
+                vec![QuerySource::Tag("c".to_string())],
+            ],
+            conditions: vec![
+                vec![QueryCondition { field: "x".to_string(), comparison: None }],
+                vec![
+                    QueryCondition { field: "y".to_string(), comparison: Some((QueryOp::Gt, Value::from(2))) },
+                    QueryCondition { field: "z".to_string(), comparison: Some((QueryOp::Contains, Value::from("w"))) },
+                ],
+            ],
+            sort: vec![("x".to_string(), SortOrder::Asc), ("y".to_string(), SortOrder::Desc)],
+            limit: Some(3),
+            fields: Vec::new(),
+            group: None,
+        };
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_parse_empty_tag() {
+        assert!(matches!(Query::parse("FROM #"), Err(Error::InvalidQuery(_))));
+        assert!(matches!(Query::parse("FROM #a OR ## WHERE x"), Err(Error::InvalidQuery(_))));
+    }
+
+    #[test]
+    fn test_run_query() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("Courses")).unwrap();
+        std::fs::write(dir.path().join("Courses/one.md"), "---\nstatus: todo\ndate: 2024-01-02\n---\n#lecture").unwrap();
+        std::fs::write(dir.path().join("Courses/two.md"), "---\nstatus: todo\ndate: 2024-01-03\ntags: [lecture/cpsc]\n---\n").unwrap();
+        std::fs::write(dir.path().join("three.md"), "---\nstatus: done\ndate: 2024-01-04\n---\n#lecture").unwrap();
+        std::fs::write(dir.path().join("four.md"), "---\nstatus: todo\n---\n#other").unwrap();
+        let vault = Vault::from_path(dir.path().to_path_buf()).unwrap();
+
+        let actual = vault.query("FROM #lecture WHERE status = 'todo' SORT date DESC").unwrap();
+        let expected = vec![PathBuf::from("Courses/two.md"), PathBuf::from("Courses/one.md")];
+        assert_eq!(actual, expected);
+
+        let actual = vault.run_query(&Query::new().from_folder("Courses").filter("date", QueryOp::Lt, "2024-01-03".into()));
+        assert_eq!(actual, vec![PathBuf::from("Courses/one.md")]);
+    }
+
+    #[test]
+    fn test_run_query_sees_unsaved_edits() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("one.md"), "---\nstatus: todo\n---\n").unwrap();
+        std::fs::write(dir.path().join("two.md"), "---\nstatus: done\n---\n").unwrap();
+        let mut vault = Vault::from_path(dir.path().to_path_buf()).unwrap();
+        let mdfile = vault.get_file_mut(&PathBuf::from("one.md")).unwrap().get_mdfile_mut().unwrap();
+        mdfile.add_yaml_key("status".to_string(), "done".into());
+
+        // the indexed equality and an unindexed comparison agree
+        let done = vec![PathBuf::from("one.md"), PathBuf::from("two.md")];
+        assert_eq!(vault.query("WHERE status = 'done'").unwrap(), done);
+        assert_eq!(vault.query("WHERE status != 'todo'").unwrap(), done);
+        assert!(vault.query("WHERE status = 'todo'").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_run_table_groups_and_projects() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("Courses")).unwrap();
+        std::fs::write(dir.path().join("Courses/one.md"), "---\nweek: 2\n---\n# Mitosis\n#lecture #exam").unwrap();
+        std::fs::write(dir.path().join("Courses/two.md"), "---\ntitle: Meiosis\nweek: 1\n---\n#lecture").unwrap();
+        std::fs::write(dir.path().join("three.md"), "#lecture").unwrap();
+        let vault = Vault::from_path(dir.path().to_path_buf()).unwrap();
+
+        let table = vault.table("TABLE file.title, week FROM #lecture SORT file.title GROUP BY file.folder").unwrap();
+        assert_eq!(table.fields, vec!["file.title".to_string(), "week".to_string()]);
+        let keys: Vec<Option<Value>> = table.groups.iter().map(|group| group.key.clone()).collect();
+        assert_eq!(keys, vec![Some("".into()), Some("Courses".into())]);
+        let titles: Vec<Option<&Value>> = table.rows().map(|row| table.value(row, "file.title")).collect();
+        assert_eq!(titles, vec![Some(&"three".into()), Some(&"Meiosis".into()), Some(&"Mitosis".into())]);
+
+        // a note is in the group of each of its tags
+        let table = vault.run_table(&Query::new().from_folder("Courses").select("week").group_by("file.tags"));
+        let sizes: Vec<(Option<Value>, usize)> = table.groups.iter().map(|group| (group.key.clone(), group.rows.len())).collect();
+        assert_eq!(sizes, vec![(Some("exam".into()), 1), (Some("lecture".into()), 2)]);
+
+        #[derive(Deserialize)]
+        struct Row {
+            path: PathBuf,
+            week: Option<u32>,
+        }
+        let rows: Vec<Row> = vault.table("TABLE week SORT week").unwrap().deserialize_rows().unwrap();
+        let rows: Vec<(PathBuf, Option<u32>)> = rows.into_iter().map(|row| (row.path, row.week)).collect();
+        assert_eq!(rows, vec![(PathBuf::from("Courses/two.md"), Some(1)), (PathBuf::from("Courses/one.md"), Some(2)), (PathBuf::from("three.md"), None)]);
+    }
+}
@@ -0,0 +1,200 @@
+//! obsidian-driver::file::vault::related
+//!
+//! This module contains generated `## Related` sections: links to the notes nearest to a note by embedding, marked with an HTML comment so that every run replaces the section instead of adding another.
+//!
+//! @public RELATED_MARKER
+//!
+//! @public Vault::update_related_sections
+
+// std imports
+use std::path::PathBuf;
+
+// first-party imports
+use super::query::Query;
+use super::Vault;
+use crate::file::mdfile::section::Section;
+use crate::prelude::*;
+
+/// The comment that marks a `## Related` section as generated. Sections starting with it are replaced on every run; edit the section under another heading to keep changes.
+///
+/// @public
+pub const RELATED_MARKER: &str = "<!-- related-notes -->";
+const RELATED_HEADING: &str = "## Related";
+
+impl Vault {
+    /// Insert or refresh a `## Related` section listing the notes nearest by embedding, in every note matching a query.
+    ///
+    /// The section starts with `RELATED_MARKER` and lists up to `count` links, nearest first, in
+    /// the link style of the vault. A marked section is replaced where it is; otherwise the section
+    /// is added at the end of the note. Notes without an embedding are left as they are, and a
+    /// marked section is removed if no other note has an embedding. Running again without changes
+    /// to the embeddings changes nothing, as refreshing a section keeps the embedding of its note.
+    /// The sources and conditions of the query select the notes, its sort and limit are ignored.
+    /// Only markdown notes are linked. With `dry_run`, nothing is written.
+    ///
+    /// # Arguments
+    /// @param filter: &Query - e.g. `Query::new().from_folder("Lectures")`
+    /// @param count: usize - How many notes to link
+    /// @param dry_run: bool
+    /// @return Result<Vec<PathBuf>> - The notes that changed, sorted. Errors with `EmbeddingDimensionMismatch` if embeddings have different lengths.
+    ///
+    /// # Example
+    /// ```
+    /// use std::path::PathBuf;
+    ///
+    /// use obsidian_driver::ai::api::AIDriver;
+    /// use obsidian_driver::file::vault::Vault;
+    /// use obsidian_driver::file::vault::query::Query;
+    ///
+    /// async fn update_related_sections_example() {
+    /// 	let mut vault = Vault::from_path(PathBuf::from("vault")).unwrap();
+    /// 	vault.add_ai_driver(AIDriver::new_openai_from_config_path(PathBuf::from(".openai_config.json")).await.unwrap());
+    /// 	vault.update_embeddings().await.unwrap();
+    ///
+    /// 	let changed = vault.update_related_sections(&Query::new().from_folder("Lectures"), 5, false).unwrap();
+    /// }
+    /// ```
+    pub fn update_related_sections(&mut self, filter: &Query, count: usize, dry_run: bool) -> Result<Vec<PathBuf>> {
+        let mut paths: Vec<PathBuf> = self
+            .files
+            .iter()
+            .filter(|(path, file)| file.get_embedding().is_some() && file.get_mdfile().is_some() && filter.matches(path, file))
+            .map(|(path, _)| path.clone())
+            .collect();
+        paths.sort();
+
+        let mut edits = Vec::new();
+        for path in paths {
+            // other files with embeddings are skipped, so every file is ranked before taking `count`
+            let links: Vec<String> = self
+                .get_closest_files(&path, self.files.len())?
+                .into_iter()
+                .filter(|(other, _)| other != &path && self.files.get(other).is_some_and(|file| file.get_mdfile().is_some()))
+                .take(count)
+                .map(|(other, _)| f!("- {}", self.new_link(&path, &other, None)))
+                .collect();
+            let body = self.files[&path].get_mdfile().expect("Filtered to markdown notes").get_body();
+            let existing = Section::parse_all(body)
+                .into_iter()
+                .find(|section| section.level == 2 && body[section.content_range.clone()].trim_start().starts_with(RELATED_MARKER));
+
+            let section = f!("{}\n\n{}\n{}\n", RELATED_HEADING, RELATED_MARKER, links.join("\n"));
+            let (range, text) = match (existing, links.is_empty()) {
+                (None, true) => continue,
+                (Some(existing), true) => (existing.range, String::new()),
+                // keep the blank line before the heading after the section
+                (Some(existing), false) if existing.range.end < body.len() => (existing.range, f!("{}\n", section)),
+                (Some(existing), false) => (existing.range, section),
+                (None, false) if body.trim().is_empty() => (0..body.len(), section),
+                (None, false) => (body.trim_end().len()..body.len(), f!("\n\n{}", section)),
+            };
+            if body[range.clone()] != text {
+                edits.push((path, range, text));
+            }
+        }
+
+        let changed: Vec<PathBuf> = edits.iter().map(|(path, ..)| path.clone()).collect();
+        if !dry_run {
+            // the sections are made from the embeddings, so editing them keeps the embeddings, and runs stay stable
+            let mut embeddings = Vec::new();
+            for path in changed.iter() {
+                let mdfile = self.files.get_mut(path).and_then(|file| file.get_mdfile_mut());
+                if let Some(embedding) = mdfile.and_then(|mdfile| mdfile.take_embedding()) {
+                    embeddings.push((path.clone(), embedding));
+                }
+            }
+            let edited = self.edit_bodies(edits);
+            for (path, embedding) in embeddings {
+                if let Some(mdfile) = self.files.get_mut(&path).and_then(|file| file.get_mdfile_mut()) {
+                    mdfile.set_embedding(embedding);
+                }
+            }
+            edited?;
+        }
+        Ok(changed)
+    }
+}
+
+#[cfg(test)]
+mod related_tests {
+    use super::*;
+
+    #[test]
+    fn test_update_related_sections_replaces_marked_section() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.md"), "# A\n\nCells.\n\n## Related\n\n<!-- related-notes -->\n- [[old]]\n\n## Notes\n\nKept.").unwrap();
+        std::fs::write(dir.path().join("b.md"), "# B\n").unwrap();
+        std::fs::write(dir.path().join("c.md"), "# C").unwrap();
+        std::fs::write(dir.path().join("d.md"), "# D").unwrap();
+        let mut vault = Vault::from_path(dir.path().to_path_buf()).unwrap();
+        let embeddings = [("a.md", vec![0.0, 0.0]), ("b.md", vec![1.0, 0.0]), ("c.md", vec![0.0, 2.0])];
+        for (path, embedding) in embeddings {
+            let file = vault.get_file_mut(&PathBuf::from(path)).unwrap();
+            file.get_mdfile_mut().unwrap().set_embedding(embedding);
+        }
+
+        let preview = vault.update_related_sections(&Query::new(), 2, true).unwrap();
+        assert_eq!(preview, vec![PathBuf::from("a.md"), PathBuf::from("b.md"), PathBuf::from("c.md")]);
+        assert!(vault.dirty_files().is_empty());
+
+        assert_eq!(vault.update_related_sections(&Query::new(), 2, false).unwrap(), preview);
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("a.md")).unwrap(),
+            "# A\n\nCells.\n\n## Related\n\n<!-- related-notes -->\n- [[b]]\n- [[c]]\n\n## Notes\n\nKept."
+        );
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("b.md")).unwrap(),
+            "# B\n\n## Related\n\n<!-- related-notes -->\n- [[a]]\n- [[c]]\n"
+        );
+        assert!(vault.update_related_sections(&Query::new(), 2, false).unwrap().is_empty());
+
+        assert_eq!(vault.update_related_sections(&Query::new(), 1, false).unwrap(), preview);
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("b.md")).unwrap(),
+            "# B\n\n## Related\n\n<!-- related-notes -->\n- [[a]]\n"
+        );
+    }
+
+    #[test]
+    fn test_update_related_sections_links_only_notes() {
+        struct Lines;
+
+        impl crate::file::registry::FileType for Lines {
+            fn extensions(&self) -> Vec<String> {
+                vec!["relatedlines".to_string()]
+            }
+
+            fn parse(&self, contents: &str) -> Result<serde_json::Value> {
+                Ok(serde_json::Value::from(contents.lines().collect::<Vec<&str>>()))
+            }
+
+            fn serialize(&self, data: &serde_json::Value) -> Result<String> {
+                let lines: Vec<String> = serde_json::from_value(data.clone())?;
+                Ok(lines.join("\n"))
+            }
+        }
+
+        crate::file::registry::register_file_type(std::sync::Arc::new(Lines));
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.md"), "# A").unwrap();
+        std::fs::write(dir.path().join("b.md"), "# B").unwrap();
+        std::fs::write(dir.path().join("c.md"), "# C").unwrap();
+        std::fs::write(dir.path().join("list.relatedlines"), "one").unwrap();
+        let mut vault = Vault::from_path(dir.path().to_path_buf()).unwrap();
+        let embeddings = [
+            ("a.md", vec![0.0, 0.0]),
+            ("list.relatedlines", vec![0.1, 0.0]),
+            ("b.md", vec![1.0, 0.0]),
+            ("c.md", vec![0.0, 2.0]),
+        ];
+        for (path, embedding) in embeddings {
+            vault.get_file_mut(&PathBuf::from(path)).unwrap().set_embedding(embedding);
+        }
+
+        vault.update_related_sections(&Query::new(), 2, false).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("a.md")).unwrap(),
+            "# A\n\n## Related\n\n<!-- related-notes -->\n- [[b]]\n- [[c]]\n"
+        );
+    }
+}
@@ -0,0 +1,315 @@
+//! obsidian-driver::file::vault::repair
+//!
+//! This module contains broken-link repair: the targets of broken links are fuzzy-matched against the names and aliases of existing notes, and the links are rewritten to the closest match.
+//!
+//! @public RepairOptions
+//!
+//! @public LinkRepair
+//!
+//! @public Vault::suggest_link_repairs
+//!
+//! @public Vault::repair_links
+
+// std imports
+use std::path::{Path, PathBuf};
+
+// third-party imports
+use serde::{Deserialize, Serialize};
+
+// first-party imports
+use super::Vault;
+use crate::file::mdfile::link::Link;
+use crate::prelude::*;
+
+/// RepairOptions struct
+///
+/// Options for `Vault::repair_links`. By default a broken link is repaired when a note name or
+/// alias is at least 80% similar to its target, and the most similar one is used.
+///
+/// # Example
+/// ```
+/// use obsidian_driver::file::vault::repair::RepairOptions;
+///
+/// let options = RepairOptions::default()
+/// 	.with_min_similarity(0.7)
+/// 	.with_embeddings(true)
+/// 	.with_dry_run(true);
+/// ```
+///
+/// @public
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RepairOptions {
+    // from 0 (anything) to 1 (the same name, ignoring case)
+    pub min_similarity: f64,
+    // choose between similar names by the embedding distance of the notes
+    pub use_embeddings: bool,
+    // only report the repairs, without rewriting the links
+    pub dry_run: bool,
+}
+
+impl Default for RepairOptions {
+    fn default() -> Self {
+        RepairOptions {
+            min_similarity: 0.8,
+            use_embeddings: false,
+            dry_run: false,
+        }
+    }
+}
+
+impl RepairOptions {
+    /// Set how similar a name must be to a broken target to repair the link to it.
+    ///
+    /// # Arguments
+    /// @param min_similarity: f64 - From 0 to 1, one minus the edit distance over the length of the longer name
+    /// @return RepairOptions
+    pub fn with_min_similarity(mut self, min_similarity: f64) -> Self {
+        self.min_similarity = min_similarity;
+        self
+    }
+
+    /// Choose between notes with similar enough names by embedding instead of by similarity.
+    ///
+    /// The note closest to the note containing the link wins, so `[[Mitossis]]` in a note about
+    /// cell division goes to `Mitosis` rather than `Meiosis`. Notes need embeddings, see
+    /// `Vault::update_embeddings`; notes without one come after those with one.
+    ///
+    /// # Arguments
+    /// @param use_embeddings: bool
+    /// @return RepairOptions
+    pub fn with_embeddings(mut self, use_embeddings: bool) -> Self {
+        self.use_embeddings = use_embeddings;
+        self
+    }
+
+    /// Only find the repairs; no note is changed.
+    ///
+    /// # Arguments
+    /// @param dry_run: bool
+    /// @return RepairOptions
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+}
+
+/// LinkRepair struct
+///
+/// A broken link and the file it is repaired to point at.
+///
+/// @public
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LinkRepair {
+    pub source: PathBuf,
+    pub link: Link,
+    pub target: PathBuf,
+    // the name or alias of the target that matched
+    pub matched: String,
+    pub similarity: f64,
+    // embedding distance between the source and target, when both have embeddings
+    pub distance: Option<f64>,
+    // the new link text
+    pub replacement: String,
+}
+
+impl Vault {
+    /// Find a repair for each broken link, see `Vault::broken_links`.
+    ///
+    /// Links to notes are matched against the file names and aliases of markdown notes; links
+    /// to other files, e.g. `![[Board.canvas]]`, against the file names of files with the same
+    /// extension, attachments on disk included. Names are compared ignoring case. Ties go to the
+    /// shortest path.
+    ///
+    /// # Arguments
+    /// @param options: &RepairOptions - `dry_run` is ignored, nothing is changed
+    /// @return Result<Vec<LinkRepair>> - Sorted by source path; links without a match are left out
+    pub fn suggest_link_repairs(&self, options: &RepairOptions) -> Result<Vec<LinkRepair>> {
+        // (name, file) pairs for notes, (file name, file) pairs for other files
+        let mut notes: Vec<(String, &PathBuf)> = Vec::new();
+        let mut others: Vec<(String, &PathBuf)> = Vec::new();
+        let attachments = self.attachments()?;
+        for path in &attachments {
+            if let Some(name) = path.file_name() {
+                others.push((name.to_string_lossy().into_owned(), path));
+            }
+        }
+        for (path, file) in self.files.iter() {
+            match file.get_mdfile() {
+                Some(mdfile) => {
+                    let title = path.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default();
+                    for name in std::iter::once(title).chain(mdfile.get_aliases()) {
+                        notes.push((name, path));
+                    }
+                }
+                None => {
+                    if let Some(name) = path.file_name() {
+                        others.push((name.to_string_lossy().into_owned(), path));
+                    }
+                }
+            }
+        }
+
+        let mut repairs = Vec::new();
+        for broken in self.broken_links()? {
+            let distance = |target: &Path| -> Option<f64> {
+                let source = self.files.get(&broken.source)?.get_embedding()?;
+                let target = self.files.get(target)?.get_embedding()?;
+                Some(source.iter().zip(target).map(|(a, b)| (a - b).powi(2)).sum::<f64>().sqrt())
+            };
+            let target = Path::new(&broken.link.target);
+            let extension = target.extension().map(|extension| extension.to_string_lossy().to_lowercase());
+            let (wanted, names) = match extension.as_deref() {
+                None | Some("md") => (target.file_stem(), &notes),
+                Some(_) => (target.file_name(), &others),
+            };
+            let same_kind = |path: &Path| {
+                extension.as_deref().is_none_or(|extension| extension == "md")
+                    || path.extension().map(|other| other.to_string_lossy().to_lowercase()) == extension
+            };
+            let Some(wanted) = wanted.map(|wanted| wanted.to_string_lossy().to_lowercase()) else {
+                continue;
+            };
+            let mut candidates: Vec<(f64, Option<f64>, &String, &PathBuf)> = names
+                .iter()
+                .filter(|(_, path)| same_kind(path))
+                .map(|(name, path)| (similarity(&wanted, &name.to_lowercase()), distance(path), name, *path))
+                .filter(|(similarity, ..)| *similarity >= options.min_similarity)
+                .collect();
+            candidates.sort_by(|a, b| {
+                let by_distance = match (a.1, b.1) {
+                    (Some(x), Some(y)) => x.total_cmp(&y),
+                    (x, y) => y.is_some().cmp(&x.is_some()),
+                };
+                let by_similarity = b.0.total_cmp(&a.0);
+                let ordering = match options.use_embeddings {
+                    true => by_distance.then(by_similarity),
+                    false => by_similarity,
+                };
+                ordering.then_with(|| (a.3.components().count(), a.3).cmp(&(b.3.components().count(), b.3)))
+            });
+            let Some((similarity, distance, matched, target)) = candidates.into_iter().next() else {
+                continue;
+            };
+            repairs.push(LinkRepair {
+                replacement: self.link_to(&broken.source, target, &broken.link),
+                source: broken.source,
+                link: broken.link,
+                target: target.clone(),
+                matched: matched.clone(),
+                similarity,
+                distance,
+            });
+        }
+        Ok(repairs)
+    }
+
+    /// Repair broken links by rewriting them to the closest existing file, and write the changed notes.
+    ///
+    /// Use `dry_run` to preview the repairs first, then apply them by running again without it.
+    ///
+    /// # Arguments
+    /// @param options: &RepairOptions
+    /// @return Result<Vec<LinkRepair>> - The repairs made, or proposed with `dry_run`
+    ///
+    /// # Example
+    /// ```should_panic
+    /// use std::path::PathBuf;
+    ///
+    /// use obsidian_driver::file::vault::repair::RepairOptions;
+    /// use obsidian_driver::file::vault::Vault;
+    ///
+    /// let mut vault = Vault::from_path(PathBuf::from("vault")).unwrap();
+    /// for repair in vault.repair_links(&RepairOptions::default().with_dry_run(true)).unwrap() {
+    /// 	println!("{}: {} -> {}", repair.source.display(), repair.link.target, repair.replacement);
+    /// }
+    /// vault.repair_links(&RepairOptions::default()).unwrap();
+    /// ```
+    pub fn repair_links(&mut self, options: &RepairOptions) -> Result<Vec<LinkRepair>> {
+        let repairs = self.suggest_link_repairs(options)?;
+        if !options.dry_run {
+            let edits = repairs
+                .iter()
+                .map(|repair| (repair.source.clone(), repair.link.range.clone(), repair.replacement.clone()))
+                .collect();
+            self.edit_bodies(edits)?;
+        }
+        Ok(repairs)
+    }
+}
+
+/// The similarity of two names: one minus their edit distance over the length of the longer one.
+///
+/// @private
+fn similarity(a: &str, b: &str) -> f64 {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let longest = a.len().max(b.len());
+    if longest == 0 {
+        return 1.0;
+    }
+    // Levenshtein distance, one row at a time
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.iter().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != b_char);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    1.0 - previous[b.len()] as f64 / longest as f64
+}
+
+#[cfg(test)]
+mod repair_tests {
+    use super::*;
+
+    #[test]
+    fn test_similarity() {
+        assert_eq!(similarity("mitosis", "mitosis"), 1.0);
+        assert_eq!(similarity("mitossis", "mitosis"), 1.0 - 1.0 / 8.0);
+        assert_eq!(similarity("", "abc"), 0.0);
+    }
+
+    #[test]
+    fn test_repair_links() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("Biology")).unwrap();
+        std::fs::write(dir.path().join("Biology/Mitosis.md"), "# Mitosis").unwrap();
+        std::fs::write(dir.path().join("Karnaugh Map.md"), "---\naliases: [K-map]\n---\n").unwrap();
+        std::fs::write(
+            dir.path().join("note.md"),
+            "[[Mitossis|division]] [[k-maps#Rules]] [[Nothing like it]] [[Biology/Mitosis]]",
+        )
+        .unwrap();
+        let mut vault = Vault::from_path(dir.path().to_path_buf()).unwrap();
+        assert_eq!(vault.broken_links().unwrap().len(), 3);
+
+        let preview = vault.repair_links(&RepairOptions::default().with_dry_run(true)).unwrap();
+        let replacements: Vec<&str> = preview.iter().map(|repair| repair.replacement.as_str()).collect();
+        assert_eq!(replacements, vec!["[[Mitosis|division]]", "[[Karnaugh Map#Rules]]"]);
+        assert_eq!(preview[1].matched, "K-map");
+        assert_eq!(vault.broken_links().unwrap().len(), 3);
+
+        vault.repair_links(&RepairOptions::default()).unwrap();
+        let body = std::fs::read_to_string(dir.path().join("note.md")).unwrap();
+        assert_eq!(body, "[[Mitosis|division]] [[Karnaugh Map#Rules]] [[Nothing like it]] [[Biology/Mitosis]]");
+        assert_eq!(vault.broken_links().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_repair_links_keeps_attachment_embeds() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("img")).unwrap();
+        std::fs::write(dir.path().join("img/diagram.png"), [0u8]).unwrap();
+        std::fs::write(dir.path().join("note.md"), "![[diagram.png]] ![[img/diagram.png]] ![[diagrm.png]]").unwrap();
+        let mut vault = Vault::from_path(dir.path().to_path_buf()).unwrap();
+        assert_eq!(vault.broken_links().unwrap().len(), 1);
+
+        let repairs = vault.repair_links(&RepairOptions::default()).unwrap();
+        assert_eq!(repairs.len(), 1);
+        assert_eq!(repairs[0].target, PathBuf::from("img/diagram.png"));
+        let body = std::fs::read_to_string(dir.path().join("note.md")).unwrap();
+        assert_eq!(body, "![[diagram.png]] ![[img/diagram.png]] ![[img/diagram.png]]");
+    }
+}
@@ -0,0 +1,256 @@
+//! obsidian-driver::file::vault::review
+//!
+//! This module contains spaced repetition over whole notes, using the front matter written by the Spaced Repetition community plugin (`sr-due`, `sr-interval`, `sr-ease`), so review tools can work on a vault without Obsidian running.
+//!
+//! @public ReviewState
+//!
+//! @public ReviewRating
+//!
+//! @public Vault::review_queue
+//!
+//! @public Vault::record_review
+
+// std imports
+use std::path::PathBuf;
+
+// third-party imports
+use chrono::{NaiveDate, TimeDelta};
+use serde::{Deserialize, Serialize};
+use serde_yaml::Value;
+
+// first-party imports
+use super::Vault;
+use crate::file::mdfile::MDFile;
+use crate::prelude::*;
+
+/// The front matter key holding the next review date.
+pub const SR_DUE_KEY: &str = "sr-due";
+
+/// The front matter key holding the days between the last review and the next.
+pub const SR_INTERVAL_KEY: &str = "sr-interval";
+
+/// The front matter key holding the ease, in percent.
+pub const SR_EASE_KEY: &str = "sr-ease";
+
+/// The ease of a note reviewed for the first time.
+const BASE_EASE: u32 = 250;
+
+/// The lowest ease a note can reach.
+const MIN_EASE: u32 = 130;
+
+/// The longest interval, in days.
+const MAX_INTERVAL: u32 = 36525;
+
+/// How a review went, as rated by the reviewer.
+///
+/// @public
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReviewRating {
+    Hard,
+    Good,
+    Easy,
+}
+
+/// ReviewState struct
+///
+/// The spaced repetition state of a note.
+///
+/// # Example
+/// ```
+/// use chrono::NaiveDate;
+///
+/// use obsidian_driver::file::mdfile::MDFile;
+/// use obsidian_driver::file::vault::review::{ReviewRating, ReviewState};
+///
+/// let mut mdfile = MDFile::from_string("---\nsr-due: 2024-09-01\nsr-interval: 4\nsr-ease: 250\n---\n# Cells".to_string());
+/// let state = ReviewState::from_mdfile(&mdfile).unwrap().unwrap();
+/// let today = NaiveDate::from_ymd_opt(2024, 9, 3).unwrap();
+/// assert!(state.is_due(today));
+///
+/// let next = state.next(ReviewRating::Good, today);
+/// assert_eq!(next.interval, 13);
+/// next.write_to(&mut mdfile);
+/// assert!(mdfile.to_string().starts_with("---\nsr-due: 2024-09-16\nsr-interval: 13\nsr-ease: 250\n---\n"));
+/// ```
+///
+/// @public
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReviewState {
+    pub due: NaiveDate,
+    // days
+    pub interval: u32,
+    // percent; a higher ease grows the interval faster
+    pub ease: u32,
+}
+
+impl ReviewState {
+    /// Read the state from the front matter of a note.
+    ///
+    /// A missing `sr-interval` or `sr-ease` gets the value of a new note.
+    ///
+    /// # Arguments
+    /// @param mdfile: &MDFile
+    /// @return Result<Option<ReviewState>> - None if the note has no `sr-due`; errors with `InvalidDate` if it is not a date
+    pub fn from_mdfile(mdfile: &MDFile) -> Result<Option<Self>> {
+        let Some(due) = mdfile.get_yaml_date(SR_DUE_KEY)? else {
+            return Ok(None);
+        };
+        let number = |key: &str| mdfile.get_yaml_key(key).and_then(Value::as_u64).map(|n| n as u32);
+        Ok(Some(ReviewState {
+            due,
+            interval: number(SR_INTERVAL_KEY).unwrap_or(1),
+            ease: number(SR_EASE_KEY).unwrap_or(BASE_EASE),
+        }))
+    }
+
+    /// Write the state to the front matter of a note.
+    ///
+    /// # Arguments
+    /// @param mdfile: &mut MDFile
+    pub fn write_to(&self, mdfile: &mut MDFile) {
+        mdfile.set_yaml_date(SR_DUE_KEY, self.due);
+        mdfile.add_yaml_key(SR_INTERVAL_KEY.to_string(), Value::from(self.interval));
+        mdfile.add_yaml_key(SR_EASE_KEY.to_string(), Value::from(self.ease));
+    }
+
+    /// Whether the note is due for review on a day.
+    ///
+    /// # Arguments
+    /// @param today: NaiveDate
+    /// @return bool
+    pub fn is_due(&self, today: NaiveDate) -> bool {
+        self.due <= today
+    }
+
+    /// Schedule the next review after a review today, the way the Spaced Repetition plugin does.
+    ///
+    /// Easy raises the ease by 20 and grows the interval by a further 30%, good keeps the ease,
+    /// and hard lowers the ease by 20 (to at least 130) and halves the interval. Days a review
+    /// was overdue count partly towards the new interval.
+    ///
+    /// # Arguments
+    /// @param rating: ReviewRating
+    /// @param today: NaiveDate
+    /// @return ReviewState
+    pub fn next(&self, rating: ReviewRating, today: NaiveDate) -> ReviewState {
+        let delay = (today - self.due).num_days().max(0) as f64;
+        let interval = self.interval as f64;
+        let (ease, interval) = match rating {
+            ReviewRating::Easy => {
+                let ease = self.ease + 20;
+                (ease, (interval + delay) * ease as f64 / 100.0 * 1.3)
+            }
+            ReviewRating::Good => (self.ease, (interval + delay / 2.0) * self.ease as f64 / 100.0),
+            ReviewRating::Hard => {
+                let ease = self.ease.saturating_sub(20).max(MIN_EASE);
+                (ease, (interval + delay / 4.0) * 0.5)
+            }
+        };
+        let interval = (interval.round() as u32).clamp(1, MAX_INTERVAL);
+        ReviewState {
+            due: today + TimeDelta::days(interval as i64),
+            interval,
+            ease,
+        }
+    }
+
+    /// The state of a note that was never reviewed, as if it were due today.
+    ///
+    /// @private
+    fn new(today: NaiveDate) -> Self {
+        ReviewState {
+            due: today,
+            interval: 1,
+            ease: BASE_EASE,
+        }
+    }
+}
+
+impl Vault {
+    /// Get the notes due for review on a day, i.e. whose `sr-due` is that day or earlier.
+    ///
+    /// Notes whose `sr-due` is not a date are skipped.
+    ///
+    /// # Arguments
+    /// @param today: NaiveDate
+    /// @return Vec<(PathBuf, ReviewState)> - Sorted by due date, then path
+    ///
+    /// # Example
+    /// ```should_panic
+    /// use std::path::PathBuf;
+    ///
+    /// use chrono::Local;
+    /// use obsidian_driver::file::vault::Vault;
+    /// use obsidian_driver::file::vault::review::ReviewRating;
+    ///
+    /// let mut vault = Vault::from_path(PathBuf::from("vault")).unwrap();
+    /// let today = Local::now().date_naive();
+    /// for (path, _) in vault.review_queue(today) {
+    /// 	vault.record_review(&path, ReviewRating::Good, today).unwrap();
+    /// }
+    /// ```
+    pub fn review_queue(&self, today: NaiveDate) -> Vec<(PathBuf, ReviewState)> {
+        let mut queue: Vec<(PathBuf, ReviewState)> = self
+            .files
+            .iter()
+            .filter_map(|(path, file)| {
+                let state = ReviewState::from_mdfile(file.get_mdfile()?).ok()??;
+                state.is_due(today).then(|| (path.clone(), state))
+            })
+            .collect();
+        queue.sort_by(|(a_path, a), (b_path, b)| a.due.cmp(&b.due).then_with(|| a_path.cmp(b_path)));
+        queue
+    }
+
+    /// Record a review of a note: schedule its next review and write it.
+    ///
+    /// A note without review state is treated as new and due today.
+    ///
+    /// # Arguments
+    /// @param path: &PathBuf
+    /// @param rating: ReviewRating
+    /// @param today: NaiveDate
+    /// @return Result<ReviewState> - The new state; errors with `PathNotFound` if the path is not a note
+    pub fn record_review(&mut self, path: &PathBuf, rating: ReviewRating, today: NaiveDate) -> Result<ReviewState> {
+        let mdfile = self
+            .files
+            .get_mut(path)
+            .and_then(|file| file.get_mdfile_mut())
+            .ok_or(Error::PathNotFound(path.clone()))?;
+        let state = ReviewState::from_mdfile(mdfile)?.unwrap_or(ReviewState::new(today));
+        let next = state.next(rating, today);
+        next.write_to(mdfile);
+        self.write_file(path)?;
+        Ok(next)
+    }
+}
+
+#[cfg(test)]
+mod review_tests {
+    use super::*;
+
+    #[test]
+    fn test_review_queue_and_record_review() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.md"), "---\nsr-due: 2024-09-03\nsr-interval: 2\nsr-ease: 150\n---\n# A").unwrap();
+        std::fs::write(dir.path().join("b.md"), "---\nsr-due: 2024-08-30\n---\n# B").unwrap();
+        std::fs::write(dir.path().join("c.md"), "---\nsr-due: 2024-09-10\n---\n# C").unwrap();
+        std::fs::write(dir.path().join("d.md"), "# D").unwrap();
+        let mut vault = Vault::from_path(dir.path().to_path_buf()).unwrap();
+        let today = NaiveDate::from_ymd_opt(2024, 9, 3).unwrap();
+
+        let queue: Vec<PathBuf> = vault.review_queue(today).into_iter().map(|(path, _)| path).collect();
+        assert_eq!(queue, vec![PathBuf::from("b.md"), PathBuf::from("a.md")]);
+
+        let hard = vault.record_review(&PathBuf::from("a.md"), ReviewRating::Hard, today).unwrap();
+        assert_eq!((hard.interval, hard.ease), (1, 130));
+        let easy = vault.record_review(&PathBuf::from("d.md"), ReviewRating::Easy, today).unwrap();
+        assert_eq!((easy.interval, easy.ease, easy.due), (4, 270, NaiveDate::from_ymd_opt(2024, 9, 7).unwrap()));
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("d.md")).unwrap(),
+            "---\nsr-due: 2024-09-07\nsr-interval: 4\nsr-ease: 270\n---\n# D"
+        );
+        assert_eq!(vault.review_queue(today).len(), 1);
+    }
+}
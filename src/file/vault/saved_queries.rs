@@ -0,0 +1,201 @@
+//! obsidian-driver::file::vault::saved_queries
+//!
+//! This module contains the Vault methods that run saved queries: named queries defined in a config note of the vault, so they are versioned next to the notes and can be edited in Obsidian.
+//!
+//! @public SavedQueries
+//!
+//! @public Vault::set_query_note
+//!
+//! @public Vault::get_query_note
+//!
+//! @public Vault::get_saved_queries
+//!
+//! @public Vault::get_saved_query
+//!
+//! @public Vault::run_saved_query
+//!
+//! @public Vault::run_saved_table
+
+// std imports
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+// first-party imports
+use super::query::{Query, QueryTable};
+use super::Vault;
+use crate::prelude::*;
+
+/// The config note used when none is configured.
+const DEFAULT_QUERY_NOTE: &str = "_queries.md";
+
+/// SavedQueries struct
+///
+/// The saved queries of the config note. A block that does not parse, e.g. a Dataview query
+/// using syntax `Query` does not support, is reported on its own and does not hide the others.
+///
+/// @public
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SavedQueries {
+    // by name
+    pub queries: BTreeMap<String, Query>,
+    // the name of each query that does not parse, with the error, in note order
+    pub invalid: Vec<(String, String)>,
+}
+
+impl Vault {
+    /// Set the config note saved queries are read from, relative to the vault root.
+    ///
+    /// # Arguments
+    /// @param path: PathBuf
+    pub fn set_query_note(&mut self, path: PathBuf) {
+        self.query_note = Some(path);
+    }
+
+    /// Get the config note saved queries are read from, relative to the vault root. Defaults to `_queries.md`.
+    ///
+    /// # Arguments
+    /// @return PathBuf
+    pub fn get_query_note(&self) -> PathBuf {
+        self.query_note
+            .clone()
+            .unwrap_or_else(|| PathBuf::from(DEFAULT_QUERY_NOTE))
+    }
+
+    /// Get the saved queries of the config note, by name.
+    ///
+    /// Each heading of the note names the query in the first `query` or `dataview` code block
+    /// below it, before the next heading. Headings without one are ignored, so the note can
+    /// have a heading that groups queries, and text explaining them.
+    ///
+    /// ````markdown
+    /// ## inbox
+    /// Notes still to process.
+    /// ```query
+    /// FROM "Inbox" WHERE status != 'done' SORT file.mtime DESC
+    /// ```
+    /// ````
+    ///
+    /// # Arguments
+    /// @return SavedQueries - Empty without a config note. If two headings have the same name, the first is used.
+    pub fn get_saved_queries(&self) -> SavedQueries {
+        let mut saved = SavedQueries::default();
+        let Some(mdfile) = self.files.get(&self.get_query_note()).and_then(|file| file.get_mdfile()) else {
+            return saved;
+        };
+        let body = mdfile.get_body();
+        let sections = mdfile.get_sections();
+        for (index, section) in sections.iter().enumerate() {
+            // nested sections overlap their parents, so a section ends at the next heading
+            let end = sections.get(index + 1).map_or(section.content_range.end, |next| next.range.start);
+            let Some(text) = query_block(&body[section.content_range.start..end]) else {
+                continue;
+            };
+            let name = &section.heading;
+            if saved.queries.contains_key(name) || saved.invalid.iter().any(|(other, _)| other == name) {
+                continue;
+            }
+            match Query::parse(&text) {
+                Ok(query) => {
+                    saved.queries.insert(name.clone(), query);
+                }
+                Err(e) => saved.invalid.push((name.clone(), e.to_string())),
+            }
+        }
+        saved
+    }
+
+    /// Get a saved query by name.
+    ///
+    /// # Arguments
+    /// @param name: &str - The heading of the query in the config note
+    /// @return Result<Query> - Errors with `InvalidQuery` if the query does not parse, and with `UnknownQuery` if there is no query with the name
+    pub fn get_saved_query(&self, name: &str) -> Result<Query> {
+        let mut saved = self.get_saved_queries();
+        if let Some((_, e)) = saved.invalid.iter().find(|(other, _)| other == name) {
+            return Err(Error::InvalidQuery(f!("Saved query {}: {}", name, e)));
+        }
+        saved.queries.remove(name).ok_or(Error::UnknownQuery(name.to_string()))
+    }
+
+    /// Run a saved query by name.
+    ///
+    /// # Arguments
+    /// @param name: &str - The heading of the query in the config note
+    /// @return Result<Vec<PathBuf>> - The matching note paths, in result order
+    ///
+    /// # Example
+    /// ```should_panic
+    /// use std::path::PathBuf;
+    ///
+    /// use obsidian_driver::file::vault::Vault;
+    ///
+    /// let vault = Vault::from_path(PathBuf::from("vault")).unwrap();
+    /// let inbox = vault.run_saved_query("inbox").unwrap();
+    /// ```
+    pub fn run_saved_query(&self, name: &str) -> Result<Vec<PathBuf>> {
+        Ok(self.run_query(&self.get_saved_query(name)?))
+    }
+
+    /// Run a saved query by name, and get its results as a table.
+    ///
+    /// # Arguments
+    /// @param name: &str - The heading of the query in the config note
+    /// @return Result<QueryTable>
+    pub fn run_saved_table(&self, name: &str) -> Result<QueryTable> {
+        Ok(self.run_table(&self.get_saved_query(name)?))
+    }
+}
+
+/// Get the contents of the first `query` or `dataview` code block in a text.
+///
+/// @private
+fn query_block(text: &str) -> Option<String> {
+    let mut lines = text.lines();
+    while let Some(line) = lines.next() {
+        let line = line.trim();
+        let fence: String = line.chars().take_while(|c| *c == '`' || *c == '~').collect();
+        if fence.len() < 3 {
+            continue;
+        }
+        let is_query = matches!(line[fence.len()..].trim(), "query" | "dataview");
+        let block: Vec<&str> = lines.by_ref().take_while(|line| !line.trim().starts_with(&fence)).collect();
+        if is_query {
+            return Some(block.join("\n"));
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod saved_queries_tests {
+    use super::*;
+
+    #[test]
+    fn test_run_saved_query() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("Inbox")).unwrap();
+        std::fs::write(dir.path().join("Inbox/a.md"), "---\nstatus: todo\n---\n").unwrap();
+        std::fs::write(dir.path().join("Inbox/b.md"), "---\nstatus: done\n---\n").unwrap();
+        std::fs::write(
+            dir.path().join("_queries.md"),
+            "# Queries\n\n## inbox\nNotes still to process.\n```query\nFROM \"Inbox\"\nWHERE status != 'done'\n```\n\n## by status\n```dataview\nTABLE status GROUP BY status\n```\n\n## calendar\n```dataview\nCALENDAR file.day\n```\n\n## notes\n```\nnot a query\n```\n",
+        )
+        .unwrap();
+        let mut vault = Vault::from_path(dir.path().to_path_buf()).unwrap();
+
+        // the calendar block is not supported, but does not hide the other queries
+        let saved = vault.get_saved_queries();
+        let names: Vec<String> = saved.queries.into_keys().collect();
+        assert_eq!(names, vec!["by status".to_string(), "inbox".to_string()]);
+        let invalid: Vec<&str> = saved.invalid.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(invalid, vec!["calendar"]);
+        assert!(matches!(vault.run_saved_query("calendar"), Err(Error::InvalidQuery(_))));
+        assert_eq!(vault.run_saved_query("inbox").unwrap(), vec![PathBuf::from("Inbox/a.md")]);
+        // the config note has no status, so it is in the last group
+        assert_eq!(vault.run_saved_table("by status").unwrap().groups.len(), 3);
+        assert!(matches!(vault.run_saved_query("notes"), Err(Error::UnknownQuery(_))));
+
+        vault.set_query_note(PathBuf::from("missing.md"));
+        assert_eq!(vault.get_saved_queries(), SavedQueries::default());
+    }
+}
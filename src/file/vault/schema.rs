@@ -0,0 +1,338 @@
+//! obsidian-driver::file::vault::schema
+//!
+//! This module contains front matter schemas, which describe the keys notes in a folder or with a tag must have, and the Vault methods that audit notes against them.
+//!
+//! @public FrontmatterSchema
+//!
+//! @public FieldType
+//!
+//! @public FieldRule
+//!
+//! @public SchemaViolation
+//!
+//! @public ViolationKind
+//!
+//! @public Vault::register_schema
+//!
+//! @public Vault::validate_frontmatter
+
+// std imports
+use std::path::PathBuf;
+
+// third-party imports
+use serde::{Deserialize, Serialize};
+use serde_yaml::Value;
+
+// first-party imports
+use super::query::QuerySource;
+use super::Vault;
+use crate::file::mdfile::MDFile;
+
+/// The expected type of a front matter value.
+///
+/// @public
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FieldType {
+    String,
+    Number,
+    Bool,
+    List,
+    /// A `YYYY-MM-DD` date, optionally followed by a time.
+    Date,
+}
+
+impl FieldType {
+    /// Check whether a value has this type.
+    ///
+    /// @private
+    fn matches(&self, value: &Value) -> bool {
+        match (self, value) {
+            (FieldType::String, Value::String(_)) => true,
+            (FieldType::Number, Value::Number(_)) => true,
+            (FieldType::Bool, Value::Bool(_)) => true,
+            (FieldType::List, Value::Sequence(_)) => true,
+            (FieldType::Date, Value::String(date)) => {
+                date.get(..10)
+                    .is_some_and(|day| chrono::NaiveDate::parse_from_str(day, "%Y-%m-%d").is_ok())
+                    && (date.len() == 10 || date[10..].starts_with(['T', ' ']))
+            }
+            _ => false,
+        }
+    }
+}
+
+/// The rule for one front matter key.
+///
+/// @public
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FieldRule {
+    pub key: String,
+    pub required: bool,
+    pub field_type: Option<FieldType>,
+    // the allowed values; for lists, every item must be allowed
+    pub allowed: Option<Vec<Value>>,
+}
+
+/// FrontmatterSchema struct
+///
+/// The rules the front matter of a group of notes must follow.
+///
+/// # Example
+/// ```
+/// use obsidian_driver::file::mdfile::MDFile;
+/// use obsidian_driver::file::vault::schema::{FieldType, FrontmatterSchema};
+///
+/// let schema = FrontmatterSchema::new()
+/// 	.require("course", FieldType::String)
+/// 	.optional("date", FieldType::Date)
+/// 	.allow("status", &["todo", "done"]);
+///
+/// let mdfile = MDFile::from_string("---\ndate: 2024-13-01\nstatus: doing\n---\n".to_string());
+/// assert_eq!(schema.check(&mdfile).len(), 3);
+/// ```
+/// @public
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct FrontmatterSchema {
+    rules: Vec<FieldRule>,
+}
+
+impl FrontmatterSchema {
+    /// Create an empty schema.
+    ///
+    /// # Arguments
+    /// @return FrontmatterSchema
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the rule for a key, adding an empty one if needed.
+    ///
+    /// @private
+    fn rule(&mut self, key: &str) -> &mut FieldRule {
+        let index = match self.rules.iter().position(|rule| rule.key == key) {
+            Some(index) => index,
+            None => {
+                self.rules.push(FieldRule {
+                    key: key.to_string(),
+                    required: false,
+                    field_type: None,
+                    allowed: None,
+                });
+                self.rules.len() - 1
+            }
+        };
+        &mut self.rules[index]
+    }
+
+    /// Require a key with a value of the given type.
+    ///
+    /// # Arguments
+    /// @param key: &str
+    /// @param field_type: FieldType
+    /// @return FrontmatterSchema
+    pub fn require(mut self, key: &str, field_type: FieldType) -> Self {
+        let rule = self.rule(key);
+        rule.required = true;
+        rule.field_type = Some(field_type);
+        self
+    }
+
+    /// Check the type of a key when it is present.
+    ///
+    /// # Arguments
+    /// @param key: &str
+    /// @param field_type: FieldType
+    /// @return FrontmatterSchema
+    pub fn optional(mut self, key: &str, field_type: FieldType) -> Self {
+        self.rule(key).field_type = Some(field_type);
+        self
+    }
+
+    /// Restrict a key to a set of values when it is present.
+    ///
+    /// # Arguments
+    /// @param key: &str
+    /// @param values: &[impl Into<Value> + Clone]
+    /// @return FrontmatterSchema
+    pub fn allow(mut self, key: &str, values: &[impl Into<Value> + Clone]) -> Self {
+        self.rule(key).allowed = Some(values.iter().cloned().map(Into::into).collect());
+        self
+    }
+
+    /// Get the rules of the schema.
+    ///
+    /// # Arguments
+    /// @return &[FieldRule]
+    pub fn rules(&self) -> &[FieldRule] {
+        &self.rules
+    }
+
+    /// Check a note against the schema.
+    ///
+    /// # Arguments
+    /// @param mdfile: &MDFile
+    /// @return Vec<(String, ViolationKind)> - The key and kind of each violation, in rule order
+    pub fn check(&self, mdfile: &MDFile) -> Vec<(String, ViolationKind)> {
+        let mut violations = Vec::new();
+        for rule in &self.rules {
+            let value = match mdfile.get_yaml_key(&rule.key) {
+                None | Some(Value::Null) => {
+                    if rule.required {
+                        violations.push((rule.key.clone(), ViolationKind::Missing));
+                    }
+                    continue;
+                }
+                Some(value) => value,
+            };
+            if let Some(field_type) = rule.field_type {
+                if !field_type.matches(value) {
+                    violations.push((
+                        rule.key.clone(),
+                        ViolationKind::WrongType {
+                            expected: field_type,
+                            found: value.clone(),
+                        },
+                    ));
+                    continue;
+                }
+            }
+            if let Some(allowed) = &rule.allowed {
+                let values = match value {
+                    Value::Sequence(items) => items.iter().collect(),
+                    value => vec![value],
+                };
+                for value in values {
+                    if !allowed.contains(value) {
+                        violations.push((rule.key.clone(), ViolationKind::NotAllowed(value.clone())));
+                    }
+                }
+            }
+        }
+        violations
+    }
+}
+
+/// What is wrong with a front matter key.
+///
+/// @public
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ViolationKind {
+    Missing,
+    WrongType { expected: FieldType, found: Value },
+    NotAllowed(Value),
+}
+
+/// A front matter key of a note that breaks a registered schema.
+///
+/// @public
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SchemaViolation {
+    pub path: PathBuf,
+    // the scope of the schema that was broken
+    pub scope: QuerySource,
+    pub key: String,
+    pub kind: ViolationKind,
+}
+
+impl Vault {
+    /// Register a schema for the notes in a folder or with a tag (including nested tags).
+    ///
+    /// A note in the scope of several schemas must follow all of them.
+    ///
+    /// # Arguments
+    /// @param scope: QuerySource - `QuerySource::Folder` or `QuerySource::Tag`
+    /// @param schema: FrontmatterSchema
+    pub fn register_schema(&mut self, scope: QuerySource, schema: FrontmatterSchema) {
+        self.schemas.push((scope, schema));
+    }
+
+    /// Check the front matter of every note against the registered schemas.
+    ///
+    /// # Arguments
+    /// @return Vec<SchemaViolation> - The violations, ordered by note path
+    ///
+    /// # Example
+    /// ```should_panic
+    /// use std::path::PathBuf;
+    ///
+    /// use obsidian_driver::file::vault::Vault;
+    /// use obsidian_driver::file::vault::query::QuerySource;
+    /// use obsidian_driver::file::vault::schema::{FieldType, FrontmatterSchema};
+    ///
+    /// let mut vault = Vault::from_path(PathBuf::from("vault")).unwrap();
+    /// vault.register_schema(
+    /// 	QuerySource::Tag("lecture".to_string()),
+    /// 	FrontmatterSchema::new().require("course", FieldType::String).require("date", FieldType::Date),
+    /// );
+    /// for violation in vault.validate_frontmatter() {
+    /// 	println!("{}: {} {:?}", violation.path.display(), violation.key, violation.kind);
+    /// }
+    /// ```
+    pub fn validate_frontmatter(&self) -> Vec<SchemaViolation> {
+        let mut paths: Vec<&PathBuf> = self.files.keys().collect();
+        paths.sort();
+        let mut violations = Vec::new();
+        for path in paths {
+            let Some(mdfile) = self.files[path].get_mdfile() else {
+                continue;
+            };
+            let tags = mdfile.get_tags();
+            for (scope, schema) in &self.schemas {
+                if !scope.matches(path, &tags) {
+                    continue;
+                }
+                for (key, kind) in schema.check(mdfile) {
+                    violations.push(SchemaViolation {
+                        path: path.clone(),
+                        scope: scope.clone(),
+                        key,
+                        kind,
+                    });
+                }
+            }
+        }
+        violations
+    }
+}
+
+#[cfg(test)]
+mod schema_tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_frontmatter_by_folder_and_tag() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("Lectures")).unwrap();
+        std::fs::write(dir.path().join("Lectures/one.md"), "---\ncourse: CPSC 351\ntags: [a, z]\n---\n").unwrap();
+        std::fs::write(dir.path().join("two.md"), "---\ncourse: 351\n---\n#lecture/cpsc").unwrap();
+        std::fs::write(dir.path().join("three.md"), "no front matter").unwrap();
+        let mut vault = Vault::from_path(dir.path().to_path_buf()).unwrap();
+        vault.register_schema(
+            QuerySource::Folder(PathBuf::from("Lectures")),
+            FrontmatterSchema::new().allow("tags", &["a", "b"]),
+        );
+        vault.register_schema(
+            QuerySource::Tag("lecture".to_string()),
+            FrontmatterSchema::new().require("course", FieldType::String).require("date", FieldType::Date),
+        );
+
+        let actual: Vec<(PathBuf, String, ViolationKind)> = vault
+            .validate_frontmatter()
+            .into_iter()
+            .map(|violation| (violation.path, violation.key, violation.kind))
+            .collect();
+        let expected = vec![
+            (PathBuf::from("Lectures/one.md"), "tags".to_string(), ViolationKind::NotAllowed(Value::from("z"))),
+            (
+                PathBuf::from("two.md"),
+                "course".to_string(),
+                ViolationKind::WrongType {
+                    expected: FieldType::String,
+                    found: Value::from(351),
+                },
+            ),
+            (PathBuf::from("two.md"), "date".to_string(), ViolationKind::Missing),
+        ];
+        assert_eq!(actual, expected);
+    }
+}
@@ -0,0 +1,173 @@
+//! obsidian-driver::file::vault::search
+//!
+//! This module contains semantic search: ranking notes against an arbitrary query text by embedding, with the passage of each note that best matches the query.
+//!
+//! @public SearchResult
+//!
+//! @public Vault::semantic_search
+
+// std imports
+use std::ops::Range;
+use std::path::PathBuf;
+
+// third-party imports
+use serde::{Deserialize, Serialize};
+
+// first-party imports
+use super::Vault;
+use crate::prelude::*;
+
+/// Paragraphs are merged into snippets of up to this many characters.
+const SNIPPET_CHARACTERS: usize = 600;
+
+/// SearchResult struct
+///
+/// A note matching a semantic search, with its best-matching passage.
+///
+/// @public
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SearchResult {
+    pub path: PathBuf,
+    // embedding distance between the query and the whole note
+    pub distance: f64,
+    pub snippet: String,
+    // byte range of the snippet in the body of the note
+    pub snippet_range: Range<usize>,
+    // embedding distance between the query and the snippet
+    pub snippet_distance: f64,
+}
+
+impl Vault {
+    /// Find the n notes nearest to a query text, nearest first, each with its best-matching snippet.
+    ///
+    /// The query is embedded with the vault's AI driver and the notes are ranked by their stored
+    /// embeddings (see `Vault::update_embeddings`). The body of each result is then split into
+    /// paragraph snippets, which are embedded to pick the one nearest to the query; a note that
+    /// fits in one snippet needs no extra request.
+    ///
+    /// # Arguments
+    /// @param text: &str - The query text
+    /// @param n: usize - The number of notes
+    /// @return Result<Vec<SearchResult>> - Errors with `NoAIDriver` if no driver was added
+    ///
+    /// # Example
+    /// ```
+    /// use std::path::PathBuf;
+    ///
+    /// use obsidian_driver::ai::api::AIDriver;
+    /// use obsidian_driver::file::vault::Vault;
+    ///
+    /// async fn semantic_search_example() {
+    /// 	let mut vault = Vault::from_path(PathBuf::from("vault")).unwrap();
+    /// 	vault.add_ai_driver(AIDriver::new_openai_from_config_path(PathBuf::from(".openai_config.json")).await.unwrap());
+    /// 	vault.update_embeddings().await.unwrap();
+    /// 	for result in vault.semantic_search("how cells make energy", 5).await.unwrap() {
+    /// 		println!("{:.3} {}\n{}\n", result.distance, result.path.display(), result.snippet);
+    /// 	}
+    /// }
+    /// ```
+    pub async fn semantic_search(&self, text: &str, n: usize) -> Result<Vec<SearchResult>> {
+        let driver = self.aidriver.as_ref().ok_or(Error::NoAIDriver)?;
+        let query = driver.get_embedding(text).await?;
+
+        let mut results = Vec::new();
+        for (path, note_distance) in self.get_closest_files_to_embedding(&query, n)? {
+            let Some(mdfile) = self.files.get(&path).and_then(|file| file.get_mdfile()) else {
+                continue;
+            };
+            let body = mdfile.get_body();
+            let snippets = snippet_ranges(body);
+            let (snippet_range, snippet_distance) = match snippets.as_slice() {
+                [] => (0..0, note_distance),
+                [range] => (range.clone(), note_distance),
+                _ => {
+                    let embeddings = futures::future::join_all(
+                        snippets.iter().map(|range| driver.get_embedding(&body[range.clone()])),
+                    )
+                    .await;
+                    let mut best = (snippets[0].clone(), f64::INFINITY);
+                    for (range, embedding) in snippets.iter().zip(embeddings) {
+                        let embedding = embedding?;
+                        let snippet_distance = query.iter().zip(&embedding).map(|(a, b)| (a - b).powi(2)).sum::<f64>().sqrt();
+                        if snippet_distance < best.1 {
+                            best = (range.clone(), snippet_distance);
+                        }
+                    }
+                    best
+                }
+            };
+            results.push(SearchResult {
+                path,
+                distance: note_distance,
+                snippet: body[snippet_range.clone()].to_string(),
+                snippet_range,
+                snippet_distance,
+            });
+        }
+        Ok(results)
+    }
+}
+
+/// Split a note body into snippets: paragraphs, merged while they fit in `SNIPPET_CHARACTERS`.
+///
+/// @private
+fn snippet_ranges(body: &str) -> Vec<Range<usize>> {
+    let mut paragraphs = Vec::new();
+    let mut current: Option<Range<usize>> = None;
+    let mut offset = 0;
+    for line in body.split_inclusive('\n') {
+        let content = line.trim_end();
+        if content.trim().is_empty() {
+            paragraphs.extend(current.take());
+        } else {
+            let end = offset + content.len();
+            current = Some(current.map_or(offset..end, |range| range.start..end));
+        }
+        offset += line.len();
+    }
+    paragraphs.extend(current);
+
+    let mut snippets: Vec<Range<usize>> = Vec::new();
+    for paragraph in paragraphs {
+        match snippets.last_mut() {
+            Some(last) if paragraph.end - last.start <= SNIPPET_CHARACTERS => last.end = paragraph.end,
+            _ => snippets.push(paragraph),
+        }
+    }
+    snippets
+}
+
+#[cfg(test)]
+mod search_tests {
+    use super::*;
+    use std::path::Path;
+
+    use crate::ai::api::mock::MockDriver;
+    use crate::ai::api::AIDriver;
+
+    #[test]
+    fn test_snippet_ranges() {
+        let long = "x".repeat(SNIPPET_CHARACTERS);
+        let body = f!("One.\nStill one.\n\nTwo.\n\n{}\n", long);
+        let ranges = snippet_ranges(&body);
+        assert_eq!(ranges.iter().map(|range| &body[range.clone()]).collect::<Vec<_>>(), vec!["One.\nStill one.\n\nTwo.", long.as_str()]);
+    }
+
+    #[tokio::test]
+    async fn test_semantic_search_picks_matching_snippet() {
+        let dir = tempfile::tempdir().unwrap();
+        let filler = "y".repeat(SNIPPET_CHARACTERS);
+        std::fs::write(dir.path().join("a.md"), f!("{}\n\nexact query\n", filler)).unwrap();
+        std::fs::write(dir.path().join("b.md"), "Other").unwrap();
+        let mut vault = Vault::from_path(dir.path().to_path_buf()).unwrap();
+        vault.add_ai_driver(AIDriver::new_mock(MockDriver::new()));
+        vault.update_embeddings().await.unwrap();
+
+        let results = vault.semantic_search("exact query", 2).await.unwrap();
+        assert_eq!(results.len(), 2);
+        let a = results.iter().find(|result| result.path == Path::new("a.md")).unwrap();
+        // the mock embeds identical text identically
+        assert_eq!(a.snippet, "exact query");
+        assert_eq!(a.snippet_distance, 0.0);
+    }
+}
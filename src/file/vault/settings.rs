@@ -0,0 +1,324 @@
+//! obsidian-driver::file::vault::settings
+//!
+//! This module contains the Obsidian configuration of a vault, read from the `.obsidian` folder, so the library puts attachments, daily notes and templates where the Obsidian app would, and writes links the way it does.
+//!
+//! @public VaultSettings
+//!
+//! @public VaultSettings::load
+//!
+//! @public VaultSettings::attachment_folder_for
+//!
+//! @public AttachmentFolder
+//!
+//! @public DailyNoteSettings
+//!
+//! @public DailyNoteSettings::path_for
+//!
+//! @public Vault::settings
+//!
+//! @public Vault::reload_settings
+
+// std imports
+use std::path::{Path, PathBuf};
+
+// third-party imports
+use chrono::NaiveDate;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+// first-party imports
+use super::links::{LinkPath, LinkStyle};
+use super::Vault;
+use crate::file::template::moment_to_strftime;
+use crate::prelude::*;
+
+/// The folder Obsidian keeps its configuration in, relative to the vault root.
+const CONFIG_FOLDER: &str = ".obsidian";
+
+/// Where new attachments are saved, the "Default location for new attachments" option.
+///
+/// @public
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AttachmentFolder {
+    /// The vault root, `/` in `app.json`.
+    #[default]
+    VaultRoot,
+    /// The folder of the note the attachment is added to, `./`.
+    SameFolder,
+    /// A folder below the folder of the note, `./<folder>`.
+    Subfolder(PathBuf),
+    /// A folder of the vault, relative to the vault root.
+    Folder(PathBuf),
+}
+
+/// DailyNoteSettings struct
+///
+/// The options of the Daily notes core plugin.
+///
+/// # Example
+/// ```
+/// use std::path::PathBuf;
+///
+/// use chrono::NaiveDate;
+/// use obsidian_driver::file::vault::settings::DailyNoteSettings;
+///
+/// let settings = DailyNoteSettings {
+/// 	folder: PathBuf::from("Journal"),
+/// 	format: "YYYY/MM/YYYY-MM-DD dddd".to_string(),
+/// 	template: None,
+/// };
+/// let date = NaiveDate::from_ymd_opt(2024, 9, 3).unwrap();
+/// assert_eq!(settings.path_for(date), PathBuf::from("Journal/2024/09/2024-09-03 Tuesday.md"));
+/// ```
+///
+/// @public
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DailyNoteSettings {
+    // relative to the vault root; empty for the vault root
+    pub folder: PathBuf,
+    // moment.js format of the note name, which can contain `/` to nest notes in folders
+    pub format: String,
+    // the template note, relative to the vault root
+    pub template: Option<PathBuf>,
+}
+
+impl Default for DailyNoteSettings {
+    fn default() -> Self {
+        DailyNoteSettings {
+            folder: PathBuf::new(),
+            format: "YYYY-MM-DD".to_string(),
+            template: None,
+        }
+    }
+}
+
+impl DailyNoteSettings {
+    /// Get the path of the daily note of a date.
+    ///
+    /// # Arguments
+    /// @param date: NaiveDate
+    /// @return PathBuf - Relative to the vault root
+    pub fn path_for(&self, date: NaiveDate) -> PathBuf {
+        let name = date.format(&moment_to_strftime(&self.format)).to_string();
+        self.folder.join(f!("{}.md", name))
+    }
+}
+
+/// VaultSettings struct
+///
+/// The parts of the Obsidian configuration the library uses. Options that are not set, or whose
+/// configuration file is missing, have Obsidian's defaults.
+///
+/// @public
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VaultSettings {
+    pub attachment_folder: AttachmentFolder,
+    pub link_style: LinkStyle,
+    pub daily_notes: DailyNoteSettings,
+    // the folder of the Templates core plugin, relative to the vault root
+    pub template_folder: Option<PathBuf>,
+    // moment.js formats of `{{date}}` and `{{time}}` in templates
+    pub template_date_format: Option<String>,
+    pub template_time_format: Option<String>,
+}
+
+/// The options read from `app.json`.
+///
+/// @private
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct AppJson {
+    attachment_folder_path: Option<String>,
+    use_markdown_links: Option<bool>,
+    new_link_format: Option<String>,
+}
+
+/// The options read from `daily-notes.json`.
+///
+/// @private
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct DailyNotesJson {
+    folder: Option<String>,
+    format: Option<String>,
+    template: Option<String>,
+}
+
+/// The options read from `templates.json`.
+///
+/// @private
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct TemplatesJson {
+    folder: Option<String>,
+    date_format: Option<String>,
+    time_format: Option<String>,
+}
+
+impl VaultSettings {
+    /// Read the settings of a vault from its `.obsidian` folder.
+    ///
+    /// `app.json` (attachments and links), `daily-notes.json` and `templates.json` are read; a missing file leaves its
+    /// options at their defaults.
+    ///
+    /// # Arguments
+    /// @param vault_root: &Path
+    /// @return Result<VaultSettings> - Errors with `SerdeJson` if a file is not valid JSON
+    ///
+    /// # Example
+    /// ```
+    /// use std::path::PathBuf;
+    ///
+    /// use obsidian_driver::file::vault::settings::{AttachmentFolder, VaultSettings};
+    ///
+    /// let dir = tempfile::tempdir().unwrap();
+    /// std::fs::create_dir(dir.path().join(".obsidian")).unwrap();
+    /// std::fs::write(dir.path().join(".obsidian/app.json"), r#"{"attachmentFolderPath": "./assets"}"#).unwrap();
+    /// let settings = VaultSettings::load(dir.path()).unwrap();
+    /// assert_eq!(settings.attachment_folder, AttachmentFolder::Subfolder(PathBuf::from("assets")));
+    /// ```
+    pub fn load(vault_root: &Path) -> Result<VaultSettings> {
+        let config = vault_root.join(CONFIG_FOLDER);
+        let app: AppJson = read_json(&config.join("app.json"))?;
+        let daily: DailyNotesJson = read_json(&config.join("daily-notes.json"))?;
+        let templates: TemplatesJson = read_json(&config.join("templates.json"))?;
+
+        let attachment_folder = match app.attachment_folder_path.as_deref().map(str::trim) {
+            None | Some("") | Some("/") => AttachmentFolder::VaultRoot,
+            Some("." | "./") => AttachmentFolder::SameFolder,
+            Some(path) => match path.strip_prefix("./") {
+                Some(subfolder) => AttachmentFolder::Subfolder(PathBuf::from(subfolder.trim_end_matches('/'))),
+                None => AttachmentFolder::Folder(PathBuf::from(path.trim_matches('/'))),
+            },
+        };
+        let link_style = LinkStyle {
+            markdown: app.use_markdown_links.unwrap_or(false),
+            path: match app.new_link_format.as_deref() {
+                Some("relative") => LinkPath::Relative,
+                Some("absolute") => LinkPath::Absolute,
+                _ => LinkPath::Shortest,
+            },
+            extension: false,
+        };
+        let defaults = DailyNoteSettings::default();
+        let daily_notes = DailyNoteSettings {
+            folder: daily.folder.map(|folder| vault_path(&folder)).unwrap_or(defaults.folder),
+            format: daily.format.filter(|format| !format.trim().is_empty()).unwrap_or(defaults.format),
+            template: daily.template.filter(|template| !template.trim().is_empty()).map(|template| note_path(&template)),
+        };
+        let non_empty = |value: Option<String>| value.filter(|value| !value.trim().is_empty());
+        Ok(VaultSettings {
+            attachment_folder,
+            link_style,
+            daily_notes,
+            template_folder: non_empty(templates.folder).map(|folder| vault_path(&folder)),
+            template_date_format: non_empty(templates.date_format),
+            template_time_format: non_empty(templates.time_format),
+        })
+    }
+
+    /// Get the folder a new attachment of a note is saved in.
+    ///
+    /// # Arguments
+    /// @param note: &Path - The note the attachment is added to, relative to the vault root
+    /// @return PathBuf - Relative to the vault root
+    pub fn attachment_folder_for(&self, note: &Path) -> PathBuf {
+        let note_folder = note.parent().map(Path::to_path_buf).unwrap_or_default();
+        match &self.attachment_folder {
+            AttachmentFolder::VaultRoot => PathBuf::new(),
+            AttachmentFolder::SameFolder => note_folder,
+            AttachmentFolder::Subfolder(folder) => note_folder.join(folder),
+            AttachmentFolder::Folder(folder) => folder.clone(),
+        }
+    }
+}
+
+/// Read a JSON configuration file, or the default if it does not exist.
+///
+/// @private
+fn read_json<T: DeserializeOwned + Default>(path: &Path) -> Result<T> {
+    if !path.exists() {
+        return Ok(T::default());
+    }
+    Ok(serde_json::from_str(&std::fs::read_to_string(path)?)?)
+}
+
+/// A folder path from the configuration, relative to the vault root.
+///
+/// @private
+fn vault_path(path: &str) -> PathBuf {
+    PathBuf::from(path.trim().trim_matches('/'))
+}
+
+/// A note path from the configuration, which leaves out the `.md` extension.
+///
+/// @private
+fn note_path(path: &str) -> PathBuf {
+    let path = vault_path(path);
+    match path.extension() {
+        Some(extension) if extension == "md" => path,
+        _ => PathBuf::from(f!("{}.md", path.display())),
+    }
+}
+
+impl Vault {
+    /// Get the Obsidian settings of the vault, read when it was loaded.
+    ///
+    /// # Arguments
+    /// @return &VaultSettings
+    ///
+    /// # Example
+    /// ```should_panic
+    /// use std::path::PathBuf;
+    ///
+    /// use chrono::Local;
+    /// use obsidian_driver::file::vault::Vault;
+    ///
+    /// let vault = Vault::from_path(PathBuf::from("vault")).unwrap();
+    /// let today = vault.settings().daily_notes.path_for(Local::now().date_naive());
+    /// ```
+    pub fn settings(&self) -> &VaultSettings {
+        &self.settings
+    }
+
+    /// Read the Obsidian settings of the vault again, e.g. after they were changed in Obsidian.
+    ///
+    /// # Arguments
+    /// @return Result<()> - Errors if a configuration file is not valid JSON; the settings are then unchanged
+    pub fn reload_settings(&mut self) -> Result<()> {
+        self.settings = VaultSettings::load(&self.vault_root)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod settings_tests {
+    use super::*;
+
+    #[test]
+    fn test_vault_uses_settings() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join(".obsidian")).unwrap();
+        std::fs::create_dir_all(dir.path().join("Meta/Templates")).unwrap();
+        std::fs::write(dir.path().join(".obsidian/app.json"), r#"{"attachmentFolderPath": "Files/"}"#).unwrap();
+        std::fs::write(
+            dir.path().join(".obsidian/daily-notes.json"),
+            r#"{"folder": "/Journal", "format": "", "template": "Meta/Templates/Daily"}"#,
+        )
+        .unwrap();
+        std::fs::write(dir.path().join(".obsidian/templates.json"), r#"{"folder": "Meta/Templates", "dateFormat": "DD.MM.YYYY"}"#).unwrap();
+        std::fs::write(dir.path().join("Meta/Templates/Daily.md"), "# {{date}}").unwrap();
+        let vault = Vault::from_path(dir.path().to_path_buf()).unwrap();
+
+        let settings = vault.settings();
+        assert_eq!(settings.attachment_folder_for(Path::new("Notes/a.md")), PathBuf::from("Files"));
+        assert_eq!(settings.daily_notes.template, Some(PathBuf::from("Meta/Templates/Daily.md")));
+        let date = NaiveDate::from_ymd_opt(2024, 9, 3).unwrap();
+        assert_eq!(settings.daily_notes.path_for(date), PathBuf::from("Journal/2024-09-03.md"));
+
+        assert_eq!(vault.get_template_folder(), PathBuf::from("Meta/Templates"));
+        let now = date.and_hms_opt(8, 0, 0).unwrap();
+        let note = vault.get_template("Daily").unwrap().instantiate_at("Today", &Default::default(), now).unwrap();
+        assert_eq!(note.get_body(), "# 03.09.2024");
+    }
+}
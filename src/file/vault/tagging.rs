@@ -0,0 +1,478 @@
+//! obsidian-driver::file::vault::tagging
+//!
+//! This module contains AI tagging: the cheap model proposes tags for a note from the tags already used in the vault and by the notes nearest to it by embedding, and confident proposals are added to the `tags` front matter key.
+//!
+//! @public TagOptions
+//!
+//! @public TagSuggestion
+//!
+//! @public TaggedNote
+//!
+//! @public TaggingReport
+//!
+//! @public Vault::tag_vocabulary
+//!
+//! @public Vault::suggest_tags
+//!
+//! @public Vault::auto_tag
+
+// std imports
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+// third-party imports
+use serde::{Deserialize, Serialize};
+use serde_yaml::Value;
+
+// first-party imports
+use super::query::Query;
+use super::Vault;
+use crate::ai::chunk::split_text;
+use crate::batch::BatchReport;
+use crate::ai::prompt::{Context, Prompt, ResponseBudget};
+use crate::ai::structured::{parse_json_response, JSON_SYSTEM_PROMPT};
+use crate::prelude::*;
+
+const TAG_SYSTEM_PROMPT: &str = "You tag the notes of a student's knowledge base so related notes can be found together.";
+const TAG_USER_PROMPT: &str = r#"Propose tags for the note below. Prefer the tags already used in the vault, listed with how many notes use them; [new_tags] Similar notes and their tags are listed as a hint. Do not repeat the tags the note already has. Give each tag a confidence from 0 to 1 that it fits the note.
+
+Reply with a JSON object of the form {"tags": [{"tag": "...", "confidence": 0.9}]}, without the # of the tags.
+
+**Vault tags**
+[vocabulary]
+
+**Similar notes**
+[neighbours]
+
+**Current tags**
+[current]
+
+**Note: [title]**
+[note]"#;
+const NEW_TAGS_ALLOWED: &str = "propose a new tag only if none of them fits.";
+const NEW_TAGS_FORBIDDEN: &str = "do not propose any other tag.";
+// the note is cut to this many estimated tokens to keep the cheap model call small
+const NOTE_TOKENS: u32 = 2000;
+
+/// TagOptions struct
+///
+/// Options for `Vault::suggest_tags` and `Vault::auto_tag`. By default up to 3 tags of the
+/// vault's vocabulary with a confidence of at least 0.7 are proposed, using the tags of the 5
+/// nearest notes as a hint.
+///
+/// # Example
+/// ```
+/// use obsidian_driver::file::vault::tagging::TagOptions;
+///
+/// let options = TagOptions::default()
+/// 	.with_min_confidence(0.8)
+/// 	.with_new_tags(true)
+/// 	.with_dry_run(true);
+/// ```
+///
+/// @public
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TagOptions {
+    // proposals below this confidence are dropped
+    pub min_confidence: f64,
+    pub max_tags: usize,
+    // how many notes nearest by embedding are shown with their tags
+    pub neighbours: usize,
+    // allow tags not yet used in the vault
+    pub new_tags: bool,
+    // only report the proposals, without writing them
+    pub dry_run: bool,
+}
+
+impl Default for TagOptions {
+    fn default() -> Self {
+        TagOptions {
+            min_confidence: 0.7,
+            max_tags: 3,
+            neighbours: 5,
+            new_tags: false,
+            dry_run: false,
+        }
+    }
+}
+
+impl TagOptions {
+    /// Drop proposals the model is less confident about.
+    ///
+    /// # Arguments
+    /// @param min_confidence: f64 - From 0 to 1
+    /// @return TagOptions
+    pub fn with_min_confidence(mut self, min_confidence: f64) -> Self {
+        self.min_confidence = min_confidence;
+        self
+    }
+
+    /// Keep at most this many proposals per note, the most confident first.
+    ///
+    /// # Arguments
+    /// @param max_tags: usize
+    /// @return TagOptions
+    pub fn with_max_tags(mut self, max_tags: usize) -> Self {
+        self.max_tags = max_tags;
+        self
+    }
+
+    /// Show the model the tags of this many notes nearest by embedding; 0 to leave them out.
+    ///
+    /// # Arguments
+    /// @param neighbours: usize
+    /// @return TagOptions
+    pub fn with_neighbours(mut self, neighbours: usize) -> Self {
+        self.neighbours = neighbours;
+        self
+    }
+
+    /// Allow tags that no note of the vault uses yet.
+    ///
+    /// # Arguments
+    /// @param new_tags: bool
+    /// @return TagOptions
+    pub fn with_new_tags(mut self, new_tags: bool) -> Self {
+        self.new_tags = new_tags;
+        self
+    }
+
+    /// Only propose the tags; nothing is written.
+    ///
+    /// # Arguments
+    /// @param dry_run: bool
+    /// @return TagOptions
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+}
+
+/// A tag proposed for a note, without the `#`.
+///
+/// @public
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TagSuggestion {
+    pub tag: String,
+    // from 0 to 1
+    pub confidence: f64,
+}
+
+/// TaggedNote struct
+///
+/// The tags `Vault::auto_tag` added to a note, or would add with `dry_run`.
+///
+/// @public
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TaggedNote {
+    pub path: PathBuf,
+    pub tags: Vec<TagSuggestion>,
+}
+
+/// TaggingReport struct
+///
+/// The outcome of `Vault::auto_tag`. A note whose tags could not be proposed or written is
+/// reported in `report.failed` and does not stop the others.
+///
+/// @public
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct TaggingReport {
+    // the notes that got tags, sorted by path
+    pub tagged: Vec<TaggedNote>,
+    // `completed` lists every note that was tagged or needed no tags
+    pub report: BatchReport,
+}
+
+/// The JSON reply to the tag prompt.
+///
+/// @private
+#[derive(Deserialize)]
+struct TagList {
+    tags: Vec<TagSuggestion>,
+}
+
+impl Vault {
+    /// The tags used in the vault, in front matter or the body of notes, with how many notes use them.
+    ///
+    /// # Arguments
+    /// @return Vec<(String, usize)> - Most used first, then by name
+    ///
+    /// # Example
+    /// ```should_panic
+    /// use std::path::PathBuf;
+    ///
+    /// use obsidian_driver::file::vault::Vault;
+    ///
+    /// let vault = Vault::from_path(PathBuf::from("vault")).unwrap();
+    /// for (tag, count) in vault.tag_vocabulary() {
+    /// 	println!("#{} ({})", tag, count);
+    /// }
+    /// ```
+    pub fn tag_vocabulary(&self) -> Vec<(String, usize)> {
+        let mut counts: HashMap<String, usize> = HashMap::new();
+        for mdfile in self.files.values().filter_map(|file| file.get_mdfile()) {
+            for tag in mdfile.get_tags() {
+                *counts.entry(tag).or_default() += 1;
+            }
+        }
+        let mut vocabulary: Vec<(String, usize)> = counts.into_iter().collect();
+        vocabulary.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        vocabulary
+    }
+
+    /// Ask the cheap model for tags for a note.
+    ///
+    /// The model sees the note, its current tags, the vault's tag vocabulary, and the tags of the
+    /// notes nearest to it if it has an embedding. Proposals the note already has, below
+    /// `min_confidence`, or outside the vocabulary (unless `new_tags`) are dropped. Tags are
+    /// compared without case, and vocabulary tags keep the case used in the vault.
+    ///
+    /// # Arguments
+    /// @param path: &PathBuf - The note, relative to the vault root
+    /// @param options: &TagOptions - `dry_run` is ignored, nothing is written
+    /// @return Result<Vec<TagSuggestion>> - The most confident first. Errors with `NoAIDriver` if no driver was added, and with `InvalidChatResponse` if the reply is not tags.
+    ///
+    /// # Example
+    /// ```
+    /// use std::path::PathBuf;
+    ///
+    /// use obsidian_driver::ai::api::AIDriver;
+    /// use obsidian_driver::file::vault::Vault;
+    /// use obsidian_driver::file::vault::tagging::TagOptions;
+    ///
+    /// async fn suggest_tags_example() {
+    /// 	let mut vault = Vault::from_path(PathBuf::from("vault")).unwrap();
+    /// 	vault.add_ai_driver(AIDriver::new_openai_from_config_path(PathBuf::from(".openai_config.json")).await.unwrap());
+    /// 	vault.update_embeddings().await.unwrap();
+    ///
+    /// 	for suggestion in vault.suggest_tags(&PathBuf::from("Lecture 1.md"), &TagOptions::default()).await.unwrap() {
+    /// 		println!("#{} ({:.2})", suggestion.tag, suggestion.confidence);
+    /// 	}
+    /// }
+    /// ```
+    pub async fn suggest_tags(&self, path: &PathBuf, options: &TagOptions) -> Result<Vec<TagSuggestion>> {
+        let driver = self.aidriver.as_ref().ok_or(Error::NoAIDriver)?;
+        let mdfile = self
+            .files
+            .get(path)
+            .and_then(|file| file.get_mdfile())
+            .ok_or(Error::PathNotFound(path.clone()))?;
+        let current: Vec<String> = mdfile.get_tags().iter().map(|tag| tag.to_lowercase()).collect();
+        let vocabulary = self.tag_vocabulary();
+        if vocabulary.is_empty() && !options.new_tags {
+            return Ok(Vec::new());
+        }
+
+        let mut neighbours = Vec::new();
+        if options.neighbours > 0 && mdfile.get_embedding().is_some() {
+            for (other, _) in self.get_closest_files(path, options.neighbours + 1)? {
+                let Some(other_mdfile) = self.files.get(&other).and_then(|file| file.get_mdfile()) else {
+                    continue;
+                };
+                if &other == path {
+                    continue;
+                }
+                let tags: Vec<String> = other_mdfile.get_tags().iter().map(|tag| f!("#{}", tag)).collect();
+                neighbours.push(f!("- {}: {}", title(&other), tags.join(" ")));
+            }
+        }
+        let list = |lines: Vec<String>| if lines.is_empty() { "None".to_string() } else { lines.join("\n") };
+        let body = mdfile.strip_comments().get_body().trim().to_string();
+        let mut context = Context::default();
+        context.insert("new_tags", if options.new_tags { NEW_TAGS_ALLOWED } else { NEW_TAGS_FORBIDDEN });
+        context.insert("vocabulary", &list(vocabulary.iter().map(|(tag, count)| f!("- #{} ({})", tag, count)).collect()));
+        context.insert("neighbours", &list(neighbours));
+        context.insert("current", &list(mdfile.get_tags().iter().map(|tag| f!("- #{}", tag)).collect()));
+        context.insert("title", &title(path));
+        context.insert("note", split_text(&body, NOTE_TOKENS).first().map(String::as_str).unwrap_or_default());
+        let prompt = Prompt::new(TAG_SYSTEM_PROMPT, TAG_USER_PROMPT, ResponseBudget::Tokens(300))
+            .stack_system_prompt(JSON_SYSTEM_PROMPT)
+            .substitute(&context)?;
+        let response = driver.chat_cheap(prompt).await?;
+
+        let mut suggestions = Vec::new();
+        for mut suggestion in parse_json_response::<TagList>(&response)?.tags {
+            let tag = suggestion.tag.trim().trim_start_matches('#').replace(' ', "-");
+            let known = vocabulary.iter().find(|(other, _)| other.to_lowercase() == tag.to_lowercase());
+            suggestion.tag = match known {
+                Some((other, _)) => other.clone(),
+                None if options.new_tags => tag,
+                None => continue,
+            };
+            let lowercase = suggestion.tag.to_lowercase();
+            let duplicate = suggestions.iter().any(|other: &TagSuggestion| other.tag.to_lowercase() == lowercase);
+            if suggestion.tag.is_empty() || suggestion.confidence < options.min_confidence || current.contains(&lowercase) || duplicate {
+                continue;
+            }
+            suggestions.push(suggestion);
+        }
+        suggestions.sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
+        suggestions.truncate(options.max_tags);
+        Ok(suggestions)
+    }
+
+    /// Propose tags for every note matching a query, and add them to the `tags` front matter key.
+    ///
+    /// Tags are proposed with `Vault::suggest_tags`, one cheap model call per note. They are
+    /// appended to the existing front matter tags, which become a list if they were a string.
+    /// The sources and conditions of the query select the notes, its sort and limit are ignored.
+    /// With `dry_run`, nothing is written and the report shows what would be added. A note that
+    /// fails, e.g. because the reply is not tags, is reported and the other notes are still tagged.
+    ///
+    /// # Arguments
+    /// @param filter: &Query - e.g. `Query::new().from_folder("Lectures")`
+    /// @param options: &TagOptions
+    /// @return Result<TaggingReport> - Errors with `NoAIDriver` if no driver was added
+    ///
+    /// # Example
+    /// ```
+    /// use std::path::PathBuf;
+    ///
+    /// use obsidian_driver::ai::api::AIDriver;
+    /// use obsidian_driver::file::vault::Vault;
+    /// use obsidian_driver::file::vault::query::Query;
+    /// use obsidian_driver::file::vault::tagging::TagOptions;
+    ///
+    /// async fn auto_tag_example() {
+    /// 	let mut vault = Vault::from_path(PathBuf::from("vault")).unwrap();
+    /// 	vault.add_ai_driver(AIDriver::new_openai_from_config_path(PathBuf::from(".openai_config.json")).await.unwrap());
+    /// 	vault.update_embeddings().await.unwrap();
+    ///
+    /// 	let filter = Query::new().from_folder("Lectures");
+    /// 	let preview = vault.auto_tag(&filter, &TagOptions::default().with_dry_run(true)).await.unwrap();
+    /// }
+    /// ```
+    pub async fn auto_tag(&mut self, filter: &Query, options: &TagOptions) -> Result<TaggingReport> {
+        if self.aidriver.is_none() {
+            return Err(Error::NoAIDriver);
+        }
+        let mut paths: Vec<PathBuf> = self
+            .files
+            .iter()
+            .filter(|(path, file)| file.get_mdfile().is_some() && filter.matches(path, file))
+            .map(|(path, _)| path.clone())
+            .collect();
+        paths.sort();
+
+        let mut tagging = TaggingReport::default();
+        for path in paths {
+            let tags = match self.suggest_tags(&path, options).await {
+                Ok(tags) => tags,
+                Err(e) => {
+                    tagging.report.failed.push((path, e.to_string()));
+                    continue;
+                }
+            };
+            if tags.is_empty() {
+                tagging.report.completed.push(path);
+                continue;
+            }
+            if !options.dry_run {
+                let mdfile = self
+                    .files
+                    .get_mut(&path)
+                    .and_then(|file| file.get_mdfile_mut())
+                    .expect("File not found in vault");
+                let key = if mdfile.get_yaml_key("tags").is_none() && mdfile.get_yaml_key("tag").is_some() { "tag" } else { "tags" };
+                let mut values = match mdfile.get_yaml_key(key) {
+                    Some(Value::Sequence(values)) => values.clone(),
+                    Some(Value::String(values)) => values
+                        .split([',', ' '])
+                        .filter(|tag| !tag.trim().is_empty())
+                        .map(|tag| Value::from(tag.trim()))
+                        .collect(),
+                    _ => Vec::new(),
+                };
+                values.extend(tags.iter().map(|suggestion| Value::from(suggestion.tag.as_str())));
+                mdfile.add_yaml_key(key.to_string(), Value::Sequence(values));
+                if let Err(e) = self.write_file(&path) {
+                    tagging.report.failed.push((path, e.to_string()));
+                    continue;
+                }
+            }
+            tagging.report.completed.push(path.clone());
+            tagging.tagged.push(TaggedNote { path, tags });
+        }
+        Ok(tagging)
+    }
+}
+
+/// The title of a note, its file name without the extension.
+///
+/// @private
+fn title(path: &std::path::Path) -> String {
+    path.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tagging_tests {
+    use super::*;
+    use crate::ai::api::mock::MockDriver;
+    use crate::ai::api::AIDriver;
+
+    #[tokio::test]
+    async fn test_suggest_and_auto_tag() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("Lectures")).unwrap();
+        std::fs::write(dir.path().join("Lectures/mitosis.md"), "---\ntags: biology\n---\n# Mitosis\n\nCells divide.").unwrap();
+        std::fs::write(dir.path().join("meiosis.md"), "---\ntags: [biology, Exam]\n---\n# Meiosis").unwrap();
+        std::fs::write(dir.path().join("physics.md"), "# Physics #physics").unwrap();
+        let mut vault = Vault::from_path(dir.path().to_path_buf()).unwrap();
+        let embeddings = [("Lectures/mitosis.md", vec![1.0, 0.0]), ("meiosis.md", vec![1.0, 0.1]), ("physics.md", vec![0.0, 1.0])];
+        for (path, embedding) in embeddings {
+            let file = vault.get_file_mut(&PathBuf::from(path)).unwrap();
+            file.get_mdfile_mut().unwrap().set_embedding(embedding);
+        }
+        assert_eq!(vault.tag_vocabulary()[0], ("biology".to_string(), 2));
+
+        let response = r##"{"tags": [
+            {"tag": "#exam", "confidence": 0.8},
+            {"tag": "physics", "confidence": 0.3},
+            {"tag": "biology", "confidence": 0.95},
+            {"tag": "cell division", "confidence": 0.9}
+        ]}"##;
+        let mock = MockDriver::new().with_default_response(response);
+        vault.add_ai_driver(AIDriver::new_mock(mock.clone()));
+        let path = PathBuf::from("Lectures/mitosis.md");
+
+        let suggestions = vault.suggest_tags(&path, &TagOptions::default().with_neighbours(1)).await.unwrap();
+        assert_eq!(suggestions, vec![TagSuggestion { tag: "Exam".to_string(), confidence: 0.8 }]);
+        let prompt = &mock.prompts()[0].user_prompt;
+        assert!(prompt.contains("**Similar notes**\n- meiosis: #biology #Exam\n\n"));
+        assert!(prompt.contains("**Vault tags**\n- #biology (2)\n- #Exam (1)\n- #physics (1)\n"));
+        assert!(prompt.contains("do not propose any other tag."));
+
+        let options = TagOptions::default().with_new_tags(true).with_dry_run(true);
+        let filter = Query::new().from_folder("Lectures");
+        let preview = vault.auto_tag(&filter, &options).await.unwrap().tagged;
+        assert_eq!(preview.len(), 1);
+        assert_eq!(preview[0].tags.iter().map(|suggestion| suggestion.tag.as_str()).collect::<Vec<_>>(), vec!["cell-division", "Exam"]);
+        assert!(vault.dirty_files().is_empty());
+
+        vault.auto_tag(&filter, &options.with_dry_run(false)).await.unwrap();
+        let written = std::fs::read_to_string(dir.path().join(&path)).unwrap();
+        assert!(written.starts_with("---\ntags:\n- biology\n- cell-division\n- Exam\n---\n"));
+    }
+
+    #[tokio::test]
+    async fn test_auto_tag_reports_failed_notes() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.md"), "# A
+
+Cells divide.").unwrap();
+        std::fs::write(dir.path().join("b.md"), "# B
+
+Atoms bond.").unwrap();
+        let mut vault = Vault::from_path(dir.path().to_path_buf()).unwrap();
+        let mock = MockDriver::new().with_responses(["not json", r#"{"tags": [{"tag": "chemistry", "confidence": 0.9}]}"#]);
+        vault.add_ai_driver(AIDriver::new_mock(mock));
+
+        let options = TagOptions::default().with_new_tags(true);
+        let tagging = vault.auto_tag(&Query::new(), &options).await.unwrap();
+        assert_eq!(tagging.report.failed.len(), 1);
+        assert_eq!(tagging.report.failed[0].0, PathBuf::from("a.md"));
+        assert_eq!(tagging.report.completed, vec![PathBuf::from("b.md")]);
+        assert_eq!(tagging.tagged[0].path, PathBuf::from("b.md"));
+        assert!(std::fs::read_to_string(dir.path().join("b.md")).unwrap().starts_with("---\ntags:\n- chemistry\n---\n"));
+    }
+}
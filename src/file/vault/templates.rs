@@ -0,0 +1,266 @@
+//! obsidian-driver::file::vault::templates
+//!
+//! This module contains the Vault methods that load note templates from the template folder, and create notes from them.
+//!
+//! @public Vault::set_template_folder
+//!
+//! @public Vault::get_template_folder
+//!
+//! @public Vault::get_templates
+//!
+//! @public Vault::get_template
+//!
+//! @public Vault::create_note_from_template
+
+// std imports
+use std::path::PathBuf;
+
+// third-party imports
+use futures::future;
+
+// first-party imports
+use super::events::VaultEvent;
+use super::Vault;
+use crate::ai::prompt::{Context, Prompt, ResponseBudget};
+use crate::file::mdfile::MDFile;
+use crate::file::template::Template;
+use crate::prelude::*;
+
+/// The template folder used when none is configured.
+const DEFAULT_TEMPLATE_FOLDER: &str = "Templates";
+
+/// The placeholders a template fills in itself.
+const BUILT_IN_PLACEHOLDERS: [&str; 3] = ["title", "date", "time"];
+
+const TEMPLATE_FILL_SYSTEM_PROMPT: &str = "You fill in one placeholder of a note template. Reply with only the text that replaces the placeholder, written to fit where it appears in the template, without explanations, quotes, or code fences.";
+const TEMPLATE_FILL_USER_PROMPT: &str = r#"**Template**
+
+[template]
+
+**Known values**
+
+[values]
+
+**Placeholder to fill**
+
+[placeholder]"#;
+
+impl Vault {
+    /// Set the folder templates are loaded from, relative to the vault root.
+    ///
+    /// # Arguments
+    /// @param folder: PathBuf
+    pub fn set_template_folder(&mut self, folder: PathBuf) {
+        self.template_folder = Some(folder);
+    }
+
+    /// Get the folder templates are loaded from, relative to the vault root. Defaults to the
+    /// folder of the Templates core plugin, see `Vault::settings`, or else `Templates`.
+    ///
+    /// # Arguments
+    /// @return PathBuf
+    pub fn get_template_folder(&self) -> PathBuf {
+        self.template_folder
+            .clone()
+            .or_else(|| self.settings.template_folder.clone())
+            .unwrap_or_else(|| PathBuf::from(DEFAULT_TEMPLATE_FOLDER))
+    }
+
+    /// Create a Template with the date and time formats of the Templates core plugin.
+    ///
+    /// @super
+    pub(super) fn template(&self, name: &str, mdfile: &MDFile) -> Template {
+        Template::new(name, mdfile.clone()).with_formats(
+            self.settings.template_date_format.as_deref(),
+            self.settings.template_time_format.as_deref(),
+        )
+    }
+
+    /// Get every note in the template folder as a Template, sorted by name.
+    ///
+    /// # Arguments
+    /// @return Vec<Template>
+    pub fn get_templates(&self) -> Vec<Template> {
+        let folder = self.get_template_folder();
+        let mut templates: Vec<Template> = self
+            .files
+            .iter()
+            .filter(|(path, _)| path.starts_with(&folder))
+            .filter_map(|(path, file)| {
+                let name = path.file_stem()?.to_string_lossy();
+                Some(self.template(&name, file.get_mdfile()?))
+            })
+            .collect();
+        templates.sort_by(|a, b| a.get_name().cmp(b.get_name()));
+        templates
+    }
+
+    /// Get a template by name, or by its path relative to the template folder without the extension.
+    ///
+    /// # Arguments
+    /// @param name: &str - e.g. `Lecture` or `Courses/Lecture`
+    /// @return Result<Template>
+    ///
+    /// # Example
+    /// ```should_panic
+    /// use std::path::PathBuf;
+    ///
+    /// use obsidian_driver::ai::prompt::Context;
+    /// use obsidian_driver::file::vault::Vault;
+    ///
+    /// let vault = Vault::from_path(PathBuf::from("vault")).unwrap();
+    /// let template = vault.get_template("Lecture").unwrap();
+    /// let note = template.instantiate("Lecture 1", &Context::default()).unwrap();
+    /// ```
+    pub fn get_template(&self, name: &str) -> Result<Template> {
+        let folder = self.get_template_folder();
+        let path = folder.join(f!("{}.md", name));
+        if let Some(mdfile) = self.files.get(&path).and_then(|file| file.get_mdfile()) {
+            return Ok(self.template(name, mdfile));
+        }
+        self.get_templates()
+            .into_iter()
+            .find(|template| template.get_name() == name)
+            .ok_or(Error::PathNotFound(path))
+    }
+
+    /// Create a note from a template, write it, and add it to the Vault.
+    ///
+    /// The file name of the note follows the naming policy of the Vault, see `Vault::get_naming_policy`.
+    /// Placeholders are filled from the Context, and `{{title}}` from the file name given in `path`.
+    /// With `ai_fill`, each placeholder the Context has no value for is filled by its own request
+    /// to the smart model, which sees the template and the known values; the requests run
+    /// concurrently. Without it, a missing value is an error.
+    ///
+    /// # Arguments
+    /// @param path: PathBuf - The new note, relative to the vault root; its file name is the title
+    /// @return Result<PathBuf> - The path of the note, named by the naming policy
+    /// @param template: &Template
+    /// @param context: &Context - Values for the placeholders
+    /// @param ai_fill: bool - Ask the model for missing values
+    /// Errors with `PathAlreadyExists` if the note exists, `InvalidContextKey` for a missing value without `ai_fill`, and `NoAIDriver` for one with it if no driver was added
+    ///
+    /// # Example
+    /// ```
+    /// use std::path::PathBuf;
+    ///
+    /// use obsidian_driver::ai::api::AIDriver;
+    /// use obsidian_driver::ai::prompt::Context;
+    /// use obsidian_driver::file::vault::Vault;
+    ///
+    /// async fn create_note_example() {
+    /// 	let mut vault = Vault::from_path(PathBuf::from("vault")).unwrap();
+    /// 	vault.add_ai_driver(AIDriver::new_openai_from_config_path(PathBuf::from(".openai_config.json")).await.unwrap());
+    /// 	let template = vault.get_template("Lecture").unwrap();
+    /// 	let mut context = Context::default();
+    /// 	context.insert("course", "CPSC 351");
+    /// 	vault.create_note_from_template(PathBuf::from("Courses/Lecture 1.md"), &template, &context, true).await.unwrap();
+    /// }
+    /// ```
+    pub async fn create_note_from_template(&mut self, path: PathBuf, template: &Template, context: &Context, ai_fill: bool) -> Result<PathBuf> {
+        let title = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let path = path.with_file_name(f!("{}.md", self.naming_policy.name(&title)));
+        if self.files.contains_key(&path) || self.vault_root.join(&path).exists() {
+            return Err(Error::PathAlreadyExists(path));
+        }
+        let placeholders = template.placeholders();
+        let missing: Vec<&String> = placeholders
+            .iter()
+            .filter(|key| context.get(key).is_none() && !BUILT_IN_PLACEHOLDERS.contains(&key.as_str()))
+            .collect();
+
+        let mut context = context.clone();
+        if ai_fill && !missing.is_empty() {
+            let driver = self.aidriver.as_ref().ok_or(Error::NoAIDriver)?;
+            let mut values = vec![f!("- title: {}", title)];
+            values.extend(
+                placeholders
+                    .iter()
+                    .filter_map(|key| context.get(key).map(|value| f!("- {}: {}", key, value))),
+            );
+            let mut fill_context = Context::default();
+            fill_context.insert("template", &template.get_mdfile().to_string());
+            fill_context.insert("values", &values.join("\n"));
+            let prompts = missing
+                .iter()
+                .map(|key| {
+                    fill_context.insert("placeholder", &f!("{{{{{}}}}}", key));
+                    let user_prompt = Prompt::new("", TEMPLATE_FILL_USER_PROMPT, ResponseBudget::Unlimited).substitute(&fill_context)?.user_prompt;
+                    Ok(Prompt::new(TEMPLATE_FILL_SYSTEM_PROMPT, &user_prompt, ResponseBudget::Unlimited))
+                })
+                .collect::<Result<Vec<Prompt>>>()?;
+            let filled = future::try_join_all(prompts.into_iter().map(|prompt| driver.chat_smart(prompt))).await?;
+            for (key, value) in missing.iter().zip(filled) {
+                context.insert(key, value.trim());
+            }
+        }
+
+        let mdfile = template.instantiate(&title, &context)?;
+        let file = crate::file::File::from_mdfile(self.vault_root.join(&path), mdfile);
+        self.files.insert(path.clone(), file);
+        self.notify(VaultEvent::FileAdded(path.clone()));
+        self.write_file(&path)?;
+        Ok(path)
+    }
+}
+
+#[cfg(test)]
+mod templates_tests {
+    use super::*;
+    use crate::ai::api::mock::MockDriver;
+    use crate::ai::api::AIDriver;
+    use crate::file::naming::{NameCase, NamingPolicy};
+
+    #[test]
+    fn test_get_template_from_folder() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("Meta/Templates")).unwrap();
+        std::fs::write(dir.path().join("Meta/Templates/Lecture.md"), "# {{title}}\n\n## Summary\n").unwrap();
+        std::fs::write(dir.path().join("note.md"), "# Note").unwrap();
+        let mut vault = Vault::from_path(dir.path().to_path_buf()).unwrap();
+        assert!(vault.get_template("Lecture").is_err());
+
+        vault.set_template_folder(PathBuf::from("Meta/Templates"));
+        let template = vault.get_template("Lecture").unwrap();
+        let note = template.instantiate("Week 1", &Context::default()).unwrap();
+        assert_eq!(note.get_body(), "# Week 1\n\n## Summary\n");
+        assert_eq!(vault.get_templates().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_create_note_from_template_with_ai_fill() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("Templates")).unwrap();
+        std::fs::write(dir.path().join("Templates/Lecture.md"), "---\ncourse: '{{course}}'\n---\n# {{title}}\n\n## Summary\n{{summary}}\n\n## Questions\n{{questions}}\n").unwrap();
+        let mock = MockDriver::new().with_responses(["Cells divide.\n", "- Why?"]);
+        let mut vault = Vault::from_path(dir.path().to_path_buf()).unwrap();
+        let template = vault.get_template("Lecture").unwrap();
+        let mut context = Context::default();
+        context.insert("course", "BIOL 241");
+        let path = PathBuf::from("Lecture 1.md");
+
+        let result = vault.create_note_from_template(path.clone(), &template, &context, false).await;
+        assert!(matches!(result, Err(Error::InvalidContextKey(_))));
+        assert!(matches!(vault.create_note_from_template(path.clone(), &template, &context, true).await, Err(Error::NoAIDriver)));
+
+        vault.add_ai_driver(AIDriver::new_mock(mock.clone()));
+        vault.create_note_from_template(path.clone(), &template, &context, true).await.unwrap();
+        let written = std::fs::read_to_string(dir.path().join(&path)).unwrap();
+        assert!(written.ends_with("# Lecture 1\n\n## Summary\nCells divide.\n\n## Questions\n- Why?\n"));
+        let prompts = mock.prompts();
+        assert_eq!(prompts.len(), 2);
+        assert!(prompts[0].user_prompt.contains("- title: Lecture 1\n- course: BIOL 241"));
+        assert!(prompts[0].user_prompt.ends_with("{{summary}}"));
+        assert!(matches!(vault.create_note_from_template(path, &template, &context, true).await, Err(Error::PathAlreadyExists(_))));
+
+        vault.set_naming_policy(NamingPolicy::default().with_case(NameCase::Kebab));
+        context.insert("summary", "");
+        context.insert("questions", "");
+        let path = vault.create_note_from_template(PathBuf::from("Lecture 2.md"), &template, &context, false).await.unwrap();
+        assert_eq!(path, PathBuf::from("lecture-2.md"));
+        assert!(std::fs::read_to_string(dir.path().join(&path)).unwrap().contains("# Lecture 2\n"));
+    }
+}
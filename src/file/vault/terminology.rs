@@ -0,0 +1,352 @@
+//! obsidian-driver::file::vault::terminology
+//!
+//! This module contains a checker for consistent spelling and casing of domain terms across the notes of a Vault.
+//!
+//! @public Lexicon
+//!
+//! @public Lexicon::add_term
+//!
+//! @public Lexicon::add_glossary
+//!
+//! @public TermIssue
+//!
+//! @public TermIssueKind
+//!
+//! @public Vault::build_lexicon
+//!
+//! @public Vault::check_terminology
+//!
+//! @public Vault::apply_term_fixes
+
+// std imports
+use std::collections::HashMap;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+// third-party imports
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+// first-party imports
+use super::Vault;
+use crate::file::mdfile::link::Link;
+use crate::file::mdfile::MDFile;
+use crate::prelude::*;
+
+/// Lexicon struct
+///
+/// The canonical spelling of each domain term, and the variant spellings that should be replaced by it.
+/// Terms are matched case-insensitively on word boundaries.
+///
+/// # Example
+/// ```
+/// use obsidian_driver::file::vault::terminology::Lexicon;
+///
+/// let mut lexicon = Lexicon::default();
+/// lexicon.add_term("Karnaugh map", &["K-map", "KV map"]);
+/// ```
+/// @public
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Lexicon {
+    // lowercased spelling -> (canonical spelling, whether the spelling is a variant, whether its casing is checked)
+    spellings: HashMap<String, (String, bool, bool)>,
+}
+
+/// What is inconsistent about a term occurrence.
+///
+/// @public
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TermIssueKind {
+    /// The term is spelled with different casing than the canonical term.
+    Casing,
+    /// A variant spelling is used instead of the canonical term.
+    Variant,
+}
+
+/// TermIssue struct
+///
+/// A single inconsistent occurrence of a term in the body of a note.
+///
+/// @public
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TermIssue {
+    pub path: PathBuf,
+    // byte range of the occurrence in the body of the note
+    pub range: Range<usize>,
+    pub found: String,
+    pub expected: String,
+    pub kind: TermIssueKind,
+}
+
+impl Lexicon {
+    /// Add a term and its variant spellings. Spellings already in the lexicon are kept.
+    ///
+    /// # Arguments
+    /// @param canonical: &str - The canonical spelling
+    /// @param variants: &[&str] - Spellings that should be replaced by the canonical spelling
+    pub fn add_term(&mut self, canonical: &str, variants: &[&str]) {
+        self.add_spellings(canonical, variants, true);
+    }
+
+    /// Add a term and its variant spellings, optionally without checking the casing of the term itself.
+    ///
+    /// # Arguments
+    /// @param canonical: &str - The canonical spelling
+    /// @param variants: &[&str] - Spellings that should be replaced by the canonical spelling
+    /// @param check_casing: bool - Whether other casings of the canonical spelling are issues
+    ///
+    /// @private
+    fn add_spellings(&mut self, canonical: &str, variants: &[&str], check_casing: bool) {
+        let canonical = canonical.trim();
+        if canonical.is_empty() {
+            return;
+        }
+        self.spellings
+            .entry(canonical.to_lowercase())
+            .or_insert_with(|| (canonical.to_string(), false, check_casing));
+        for variant in variants {
+            let variant = variant.trim();
+            if variant.is_empty() {
+                continue;
+            }
+            self.spellings
+                .entry(variant.to_lowercase())
+                .or_insert_with(|| (canonical.to_string(), true, true));
+        }
+    }
+
+    /// Add the terms defined in a glossary note, written as `- **Term**: definition` list items.
+    ///
+    /// # Arguments
+    /// @param glossary: &MDFile - The glossary note
+    ///
+    /// # Example
+    /// ```
+    /// use obsidian_driver::file::mdfile::MDFile;
+    /// use obsidian_driver::file::vault::terminology::Lexicon;
+    ///
+    /// let glossary = MDFile::from_string("- **Alphabet**: Finite non-empty set of symbols".to_string());
+    /// let mut lexicon = Lexicon::default();
+    /// lexicon.add_glossary(&glossary);
+    /// ```
+    pub fn add_glossary(&mut self, glossary: &MDFile) {
+        let pattern = Regex::new(r"(?m)^\s*[-*+]\s+\*\*([^*]+)\*\*").unwrap();
+        for captures in pattern.captures_iter(glossary.get_body()) {
+            self.add_term(&captures[1], &[]);
+        }
+    }
+
+    /// Build a single regex matching every spelling, longest first.
+    ///
+    /// @private
+    fn pattern(&self) -> Option<Regex> {
+        if self.spellings.is_empty() {
+            return None;
+        }
+        let mut spellings: Vec<&String> = self.spellings.keys().collect();
+        spellings.sort_by(|a, b| b.len().cmp(&a.len()).then(a.cmp(b)));
+        let alternatives: Vec<String> = spellings
+            .iter()
+            .map(|spelling| {
+                let escaped = regex::escape(spelling);
+                let start = if spelling.starts_with(|c: char| c.is_alphanumeric()) { r"\b" } else { "" };
+                let end = if spelling.ends_with(|c: char| c.is_alphanumeric()) { r"\b" } else { "" };
+                f!("{}{}{}", start, escaped, end)
+            })
+            .collect();
+        Regex::new(&f!("(?i){}", alternatives.join("|"))).ok()
+    }
+}
+
+impl Vault {
+    /// Build a lexicon from the titles and aliases of the notes in the Vault, and an optional glossary note.
+    ///
+    /// Each note title is a canonical term and its aliases are variants of it. Titles of several words,
+    /// like `Karnaugh Map`, are often title-cased only because they are file names, so their casing is
+    /// only checked if the glossary defines them too; see `Lexicon::add_glossary`.
+    ///
+    /// # Arguments
+    /// @param glossary: Option<&Path> - The path of the glossary note relative to the vault root
+    /// @return Result<Lexicon> - Errors with `PathNotFound` if the glossary is not a note of the vault
+    ///
+    /// # Example
+    /// ```should_panic
+    /// use std::path::{Path, PathBuf};
+    ///
+    /// use obsidian_driver::file::vault::Vault;
+    ///
+    /// let vault = Vault::from_path(PathBuf::from("vault")).unwrap();
+    /// let lexicon = vault.build_lexicon(Some(Path::new("Glossary.md"))).unwrap();
+    /// let issues = vault.check_terminology(&lexicon);
+    /// ```
+    pub fn build_lexicon(&self, glossary: Option<&Path>) -> Result<Lexicon> {
+        let mut lexicon = Lexicon::default();
+        // the glossary goes first, so its spellings win over the titles
+        if let Some(glossary) = glossary {
+            let mdfile = self
+                .files
+                .get(glossary)
+                .and_then(|file| file.get_mdfile())
+                .ok_or_else(|| Error::PathNotFound(glossary.to_path_buf()))?;
+            lexicon.add_glossary(mdfile);
+        }
+        let mut paths: Vec<&PathBuf> = self.files.keys().collect();
+        paths.sort();
+        for path in paths {
+            let Some(mdfile) = self.files[path].get_mdfile() else {
+                continue;
+            };
+            let Some(title) = path.file_stem().and_then(|stem| stem.to_str()) else {
+                continue;
+            };
+            let aliases = mdfile.get_aliases();
+            let aliases: Vec<&str> = aliases.iter().map(String::as_str).collect();
+            lexicon.add_spellings(title, &aliases, !title.trim().contains(char::is_whitespace));
+        }
+        Ok(lexicon)
+    }
+
+    /// Find occurrences of lexicon terms that do not use the canonical spelling.
+    ///
+    /// Code and links are skipped. Differences in the case of the first letter are allowed, so a
+    /// term can start a sentence.
+    ///
+    /// # Arguments
+    /// @param lexicon: &Lexicon
+    /// @return Vec<TermIssue> - The issues, sorted by path and position
+    pub fn check_terminology(&self, lexicon: &Lexicon) -> Vec<TermIssue> {
+        let Some(pattern) = lexicon.pattern() else {
+            return Vec::new();
+        };
+        let mut issues = Vec::new();
+        for (path, file) in self.files.iter() {
+            let Some(mdfile) = file.get_mdfile() else {
+                continue;
+            };
+            let body = mdfile.get_body();
+            let skipped = skipped_ranges(body);
+            for found in pattern.find_iter(body) {
+                if skipped.iter().any(|range| range.start < found.end() && found.start() < range.end) {
+                    continue;
+                }
+                let Some((canonical, variant, check_casing)) = lexicon.spellings.get(&found.as_str().to_lowercase()) else {
+                    continue;
+                };
+                let kind = if *variant {
+                    TermIssueKind::Variant
+                } else if *check_casing && !same_except_first_letter_case(found.as_str(), canonical) {
+                    TermIssueKind::Casing
+                } else {
+                    continue;
+                };
+                issues.push(TermIssue {
+                    path: path.clone(),
+                    range: found.range(),
+                    found: found.as_str().to_string(),
+                    expected: canonical.clone(),
+                    kind,
+                });
+            }
+        }
+        issues.sort_by(|a, b| (&a.path, a.range.start).cmp(&(&b.path, b.range.start)));
+        issues
+    }
+
+    /// Replace each issue's occurrence with the expected spelling and write the changed notes.
+    ///
+    /// Pass the result of `check_terminology`, filtered to the issues that should be fixed.
+    ///
+    /// # Arguments
+    /// @param issues: &[TermIssue]
+    /// @return Result<()>
+    pub fn apply_term_fixes(&mut self, issues: &[TermIssue]) -> Result<()> {
+        let edits = issues
+            .iter()
+            .map(|issue| (issue.path.clone(), issue.range.clone(), issue.expected.clone()))
+            .collect();
+        self.edit_bodies(edits)
+    }
+}
+
+/// Compare two spellings, ignoring the case of the first letter.
+///
+/// @private
+fn same_except_first_letter_case(a: &str, b: &str) -> bool {
+    let mut a_chars = a.chars();
+    let mut b_chars = b.chars();
+    match (a_chars.next(), b_chars.next()) {
+        (Some(a_first), Some(b_first)) => {
+            a_first.to_lowercase().eq(b_first.to_lowercase()) && a_chars.eq(b_chars)
+        }
+        (a_first, b_first) => a_first == b_first,
+    }
+}
+
+/// Byte ranges of code, comments, and links in a note body.
+///
+/// @private
+fn skipped_ranges(body: &str) -> Vec<Range<usize>> {
+    let mut ranges = crate::file::mdfile::obsidian::hidden_ranges(body);
+    ranges.extend(Link::parse_all(body).into_iter().map(|link| link.range));
+    ranges
+}
+
+#[cfg(test)]
+mod terminology_tests {
+    use super::*;
+
+    #[test]
+    fn test_check_and_fix_terminology() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("Karnaugh Map.md"), "---\naliases: [K-map]\n---\nA map.").unwrap();
+        std::fs::write(
+            dir.path().join("note.md"),
+            "Use a k-map or Karnaugh map. karnaugh Map, [[Karnaugh Map|karnaugh map]] `k-map`",
+        )
+        .unwrap();
+        let vault = Vault::from_path(dir.path().to_path_buf()).unwrap();
+
+        // the casing of a multi-word title is not checked without a glossary
+        let lexicon = vault.build_lexicon(None).unwrap();
+        let issues = vault.check_terminology(&lexicon);
+        let actual: Vec<(&str, TermIssueKind)> = issues
+            .iter()
+            .map(|issue| (issue.found.as_str(), issue.kind))
+            .collect();
+        assert_eq!(actual, vec![("k-map", TermIssueKind::Variant)]);
+
+        std::fs::write(dir.path().join("Glossary.md"), "- **Karnaugh Map**: A grid of a truth table.").unwrap();
+        let mut vault = Vault::from_path(dir.path().to_path_buf()).unwrap();
+        assert!(matches!(vault.build_lexicon(Some(Path::new("Missing.md"))), Err(Error::PathNotFound(_))));
+        let lexicon = vault.build_lexicon(Some(Path::new("Glossary.md"))).unwrap();
+        let issues = vault.check_terminology(&lexicon);
+        let actual: Vec<(&str, TermIssueKind)> = issues
+            .iter()
+            .map(|issue| (issue.found.as_str(), issue.kind))
+            .collect();
+        let expected = vec![
+            ("k-map", TermIssueKind::Variant),
+            ("Karnaugh map", TermIssueKind::Casing),
+        ];
+        assert_eq!(actual, expected);
+
+        vault.apply_term_fixes(&issues).unwrap();
+        let note = std::fs::read_to_string(dir.path().join("note.md")).unwrap();
+        assert_eq!(
+            note,
+            "Use a Karnaugh Map or Karnaugh Map. karnaugh Map, [[Karnaugh Map|karnaugh map]] `k-map`"
+        );
+    }
+
+    #[test]
+    fn test_single_word_titles_check_casing() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("DNA.md"), "A molecule.").unwrap();
+        std::fs::write(dir.path().join("note.md"), "Dna and DNA.").unwrap();
+        let vault = Vault::from_path(dir.path().to_path_buf()).unwrap();
+
+        let issues = vault.check_terminology(&vault.build_lexicon(None).unwrap());
+        let actual: Vec<(&str, &str)> = issues.iter().map(|issue| (issue.found.as_str(), issue.expected.as_str())).collect();
+        assert_eq!(actual, vec![("Dna", "DNA")]);
+    }
+}
@@ -0,0 +1,191 @@
+//! obsidian-driver::file::vault::timestamps
+//!
+//! This module contains the timestamp policy of the Vault: the front matter keys of the created and modified times of notes, stamping the modified key when notes are written, and keeping the front matter and file system times in sync.
+//!
+//! @public Vault::set_timestamp_policy
+//!
+//! @public Vault::get_timestamp_policy
+//!
+//! @public Vault::reconcile_timestamps
+//!
+//! @super Vault::stamp_modified
+
+// std imports
+use std::path::{Path, PathBuf};
+
+// third-party imports
+use chrono::{Local, Timelike};
+
+// first-party imports
+use super::events::VaultEvent;
+use super::Vault;
+use crate::file::timestamps::{to_system_time, TimestampPolicy, TimestampSource};
+use crate::prelude::*;
+
+impl Vault {
+    /// Set how the Vault reads and keeps the created and modified times of notes.
+    ///
+    /// # Arguments
+    /// @param policy: TimestampPolicy
+    ///
+    /// # Example
+    /// ```should_panic
+    /// use std::path::PathBuf;
+    ///
+    /// use obsidian_driver::file::timestamps::TimestampPolicy;
+    /// use obsidian_driver::file::vault::Vault;
+    ///
+    /// let mut vault = Vault::from_path(PathBuf::from("vault")).unwrap();
+    /// vault.set_timestamp_policy(TimestampPolicy::default().with_stamp_on_write(true));
+    /// ```
+    pub fn set_timestamp_policy(&mut self, policy: TimestampPolicy) {
+        self.timestamp_policy = policy;
+    }
+
+    /// Get how the Vault reads and keeps the created and modified times of notes. By default the keys are `created` and `modified`, and nothing is synced or stamped.
+    ///
+    /// # Arguments
+    /// @return &TimestampPolicy
+    pub fn get_timestamp_policy(&self) -> &TimestampPolicy {
+        &self.timestamp_policy
+    }
+
+    /// Copy the created and modified times of every note from the source of the timestamp policy to the other.
+    ///
+    /// From the file system, the front matter keys are set to the file system times, and the notes
+    /// that changed are written with their file system modified time kept. The created key is only
+    /// set if it is missing, since the file system created time changes when a note is written. From the front matter,
+    /// the file system modified time is set to the modified key. Nothing is done without a source.
+    ///
+    /// # Arguments
+    /// @return Result<Vec<PathBuf>> - The notes that changed, sorted. Errors with `InvalidDate` if a key is not a date
+    ///
+    /// # Example
+    /// ```should_panic
+    /// use std::path::PathBuf;
+    ///
+    /// use obsidian_driver::file::timestamps::{TimestampPolicy, TimestampSource};
+    /// use obsidian_driver::file::vault::Vault;
+    ///
+    /// let mut vault = Vault::from_path(PathBuf::from("vault")).unwrap();
+    /// vault.set_timestamp_policy(TimestampPolicy::default().with_sync_from(Some(TimestampSource::Filesystem)));
+    /// let stamped = vault.reconcile_timestamps().unwrap();
+    /// ```
+    pub fn reconcile_timestamps(&mut self) -> Result<Vec<PathBuf>> {
+        let Some(source) = self.timestamp_policy.sync_from else {
+            return Ok(Vec::new());
+        };
+        let policy = self.timestamp_policy.clone();
+        let mut paths: Vec<PathBuf> = self
+            .files
+            .iter()
+            .filter(|(_, file)| file.get_mdfile().is_some())
+            .map(|(path, _)| path.clone())
+            .collect();
+        paths.sort();
+
+        let mut changed = Vec::new();
+        for path in paths {
+            let abs_path = self.vault_root.join(&path);
+            let timestamps = self.files[&path].timestamps(&policy)?;
+            match source {
+                TimestampSource::Filesystem => {
+                    // writing replaces the file, which resets its created time, so a created key is never overwritten
+                    let created = timestamps.fs_created.filter(|_| timestamps.created.is_none());
+                    let modified = timestamps.fs_modified.filter(|fs| timestamps.modified != Some(*fs));
+                    let updates = [(&policy.created_key, created), (&policy.modified_key, modified)];
+                    if updates.iter().all(|(_, fs)| fs.is_none()) {
+                        continue;
+                    }
+                    let fs_modified = std::fs::metadata(&abs_path)?.modified()?;
+                    self.backup_file(&path)?;
+                    let file = self.files.get_mut(&path).expect("File not found in vault");
+                    let mdfile = file.get_mdfile_mut().expect("notes are markdown files");
+                    for (key, fs) in updates {
+                        if let Some(fs) = fs {
+                            mdfile.set_yaml_datetime(key, fs);
+                        }
+                    }
+                    file.save()?;
+                    std::fs::File::options().write(true).open(&abs_path)?.set_modified(fs_modified)?;
+                    self.notify(VaultEvent::FileModified(path.clone()));
+                    self.notify(VaultEvent::FileWritten(path.clone()));
+                }
+                TimestampSource::Frontmatter => {
+                    let Some(modified) = timestamps.modified.filter(|modified| Some(*modified) != timestamps.fs_modified) else {
+                        continue;
+                    };
+                    let Some(time) = to_system_time(modified) else {
+                        continue;
+                    };
+                    std::fs::File::options().write(true).open(&abs_path)?.set_modified(time)?;
+                }
+            }
+            let file = self.files.get_mut(&path).expect("File not found in vault");
+            file.last_modified = Some(std::fs::metadata(&abs_path)?.modified()?.duration_since(std::time::SystemTime::UNIX_EPOCH)?.as_millis());
+            changed.push(path);
+        }
+        Ok(changed)
+    }
+
+    /// Set the modified key of a note to the current time, to the minute, if the timestamp policy stamps notes on write.
+    ///
+    /// @super
+    pub(super) fn stamp_modified(&mut self, path: &Path) {
+        if !self.timestamp_policy.stamp_on_write {
+            return;
+        }
+        let now = Local::now().naive_local();
+        let now = now.with_second(0).and_then(|now| now.with_nanosecond(0)).unwrap_or(now);
+        let key = self.timestamp_policy.modified_key.clone();
+        if let Some(mdfile) = self.files.get_mut(path).and_then(|file| file.get_mdfile_mut()) {
+            mdfile.set_yaml_datetime(&key, now);
+        }
+    }
+}
+
+#[cfg(test)]
+mod timestamps_tests {
+    use super::*;
+    use crate::file::timestamps::to_local;
+
+    #[test]
+    fn test_reconcile_timestamps() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.md"), "# A").unwrap();
+        std::fs::write(dir.path().join("b.md"), "---\ncreated: 2024-01-01T09:00\nmodified: 2024-01-02T15:04\n---\n# B").unwrap();
+        let mut vault = Vault::from_path(dir.path().to_path_buf()).unwrap();
+        assert!(vault.reconcile_timestamps().unwrap().is_empty());
+
+        vault.set_timestamp_policy(TimestampPolicy::default().with_sync_from(Some(TimestampSource::Frontmatter)));
+        assert_eq!(vault.reconcile_timestamps().unwrap(), vec![PathBuf::from("b.md")]);
+        let fs_modified = to_local(std::fs::metadata(dir.path().join("b.md")).unwrap().modified().unwrap());
+        assert_eq!(fs_modified.to_string(), "2024-01-02 15:04:00");
+        assert!(vault.reconcile_timestamps().unwrap().is_empty());
+
+        std::fs::File::options().write(true).open(dir.path().join("a.md")).unwrap().set_modified(to_system_time(fs_modified).unwrap()).unwrap();
+        vault.set_timestamp_policy(TimestampPolicy::default().with_sync_from(Some(TimestampSource::Filesystem)));
+        let changed = vault.reconcile_timestamps().unwrap();
+        assert_eq!(changed, vec![PathBuf::from("a.md")]);
+        let a = crate::file::mdfile::MDFile::from_string(std::fs::read_to_string(dir.path().join("a.md")).unwrap());
+        assert_eq!(a.get_yaml_datetime("modified").unwrap(), Some(fs_modified));
+        assert_eq!(to_local(std::fs::metadata(dir.path().join("a.md")).unwrap().modified().unwrap()), fs_modified);
+        assert!(vault.reconcile_timestamps().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_stamp_modified_on_write() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.md"), "# A").unwrap();
+        let mut vault = Vault::from_path(dir.path().to_path_buf()).unwrap();
+        vault.set_timestamp_policy(TimestampPolicy::default().with_stamp_on_write(true));
+        let path = PathBuf::from("a.md");
+        assert!(!vault.write_file(&path).unwrap());
+
+        vault.get_file_mut(&path).unwrap().get_mdfile_mut().unwrap().set_body("# A\n\nEdited".to_string());
+        assert!(vault.write_file(&path).unwrap());
+        let a = crate::file::mdfile::MDFile::from_string(std::fs::read_to_string(dir.path().join("a.md")).unwrap());
+        assert!(a.get_yaml_datetime("modified").unwrap().is_some());
+        assert_eq!(a.get_body(), "# A\n\nEdited");
+    }
+}
@@ -0,0 +1,104 @@
+//! obsidian-driver::file::vault::trash
+//!
+//! This module contains soft deletion: like Obsidian, removed files are moved into the `.trash` folder of the vault, from which they can be restored until the trash is purged.
+//!
+//! @public Vault::purge_trash
+//!
+//! @crate Vault::trash_file
+//!
+//! @super TRASH_FOLDER
+
+// std imports
+use std::path::{Path, PathBuf};
+
+// first-party imports
+use super::Vault;
+use crate::prelude::*;
+
+/// The trash folder of the vault, as used by Obsidian.
+///
+/// @super
+pub(super) const TRASH_FOLDER: &str = ".trash";
+
+impl Vault {
+    /// Permanently delete everything in the `.trash` folder of the vault.
+    ///
+    /// # Arguments
+    /// @return Result<usize> - The number of files deleted
+    ///
+    /// # Example
+    /// ```should_panic
+    /// use std::path::PathBuf;
+    ///
+    /// use obsidian_driver::file::vault::Vault;
+    /// use obsidian_driver::file::vault::links::BrokenLinkPolicy;
+    ///
+    /// let mut vault = Vault::from_path(PathBuf::from("vault")).unwrap();
+    /// vault.remove_file(&PathBuf::from("note.md"), BrokenLinkPolicy::Ignore).unwrap();
+    /// let purged = vault.purge_trash().unwrap();
+    /// ```
+    pub fn purge_trash(&self) -> Result<usize> {
+        let trash = self.vault_root.join(TRASH_FOLDER);
+        if !trash.is_dir() {
+            return Ok(0);
+        }
+        let mut purged = 0;
+        for entry in walkdir::WalkDir::new(&trash).min_depth(1) {
+            if entry?.file_type().is_file() {
+                purged += 1;
+            }
+        }
+        std::fs::remove_dir_all(&trash)?;
+        Ok(purged)
+    }
+
+    /// Move a file into the `.trash` folder. Uses the path relative to the vault root.
+    ///
+    /// Like Obsidian, the trash is flat: a file is stored under its name, with ` 1`, ` 2`, ...
+    /// added to the stem if the trash already holds a file of that name.
+    ///
+    /// # Arguments
+    /// @param path: &Path
+    /// @return Result<PathBuf> - The absolute path of the file in the trash
+    ///
+    /// @crate
+    pub(crate) fn trash_file(&self, path: &Path) -> Result<PathBuf> {
+        let trash = self.vault_root.join(TRASH_FOLDER);
+        std::fs::create_dir_all(&trash)?;
+        let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+        let extension = path.extension().map(|extension| f!(".{}", extension.to_string_lossy())).unwrap_or_default();
+        let mut target = trash.join(f!("{}{}", stem, extension));
+        let mut n = 1;
+        while target.exists() {
+            target = trash.join(f!("{} {}{}", stem, n, extension));
+            n += 1;
+        }
+        std::fs::rename(self.vault_root.join(path), &target)?;
+        Ok(target)
+    }
+}
+
+#[cfg(test)]
+mod trash_tests {
+    use super::*;
+    use crate::file::vault::links::BrokenLinkPolicy;
+
+    #[test]
+    fn test_remove_file_moves_to_trash() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("sub")).unwrap();
+        std::fs::write(dir.path().join("a.md"), "# Top").unwrap();
+        std::fs::write(dir.path().join("sub/a.md"), "# Sub").unwrap();
+        let mut vault = Vault::from_path(dir.path().to_path_buf()).unwrap();
+
+        vault.remove_file(&"a.md".into(), BrokenLinkPolicy::Ignore).unwrap();
+        vault.remove_file(&"sub/a.md".into(), BrokenLinkPolicy::Ignore).unwrap();
+        assert!(!dir.path().join("a.md").exists());
+        assert_eq!(std::fs::read_to_string(dir.path().join(".trash/a.md")).unwrap(), "# Top");
+        assert_eq!(std::fs::read_to_string(dir.path().join(".trash/a 1.md")).unwrap(), "# Sub");
+        assert!(Vault::from_path(dir.path().to_path_buf()).unwrap().get_files().is_empty());
+
+        assert_eq!(vault.purge_trash().unwrap(), 2);
+        assert!(!dir.path().join(".trash").exists());
+    }
+}
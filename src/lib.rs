@@ -1,11 +1,24 @@
 //! obsidian-driver
 //! 
 //! A library for interacting with Obsidian vaults.
+//!
+//! The library logs through `tracing` (vault loads, parsed files, API requests and token usage,
+//! cache hits, batch failures) and prints nothing itself; install a subscriber to collect the events.
+
+// doc examples are indented with tabs like the rest of the crate
+#![allow(clippy::tabs_in_doc_comments)]
 
 // public submodules
 pub mod ai;
+pub mod app;
+pub mod batch;
+pub mod daemon;
+pub mod export;
 pub mod file;
 pub mod error;
+pub mod sync;
+#[cfg(feature = "server")]
+pub mod server;
 
 // private submodules
 mod prelude;
\ No newline at end of file
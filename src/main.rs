@@ -0,0 +1,265 @@
+//! obsidian-driver
+//!
+//! The command line interface to the obsidian-driver library.
+//!
+//! ```text
+//! obsidian-driver --vault <VAULT> [--cache <CACHE>] [--config <CONFIG>] [--profile <PROFILE>] [--response-cache <FILE>] [-v...] <COMMAND>
+//!
+//! Commands:
+//!   embed     Compute embeddings for new and changed notes
+//!   search    Run a query (`FROM #tag WHERE ... SORT ... LIMIT ...`) against the vault
+//!   similar   List the notes closest to a note by embedding
+//!   merge     Merge notes into one with the AI model
+//!   generate  Generate a note from a prompt
+//!   cache     Build or refresh the vault cache
+//!   serve     Serve the vault over HTTP (with the `server` feature)
+//! ```
+
+// std imports
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+// third-party imports
+use clap::{Parser, Subcommand};
+
+// first-party imports
+use obsidian_driver::ai;
+use obsidian_driver::ai::api::cache::ResponseCache;
+use obsidian_driver::ai::api::openai::OpenAIConfig;
+use obsidian_driver::ai::api::AIDriver;
+use obsidian_driver::ai::prompt::{Context, Prompt};
+use obsidian_driver::error::Error;
+use obsidian_driver::file::vault::Vault;
+use obsidian_driver::file::write_atomic;
+
+/// Drive an Obsidian vault from the command line.
+#[derive(Debug, Parser)]
+#[command(name = "obsidian-driver", version, about)]
+struct Cli {
+    /// The root folder of the vault
+    #[arg(long, global = true, default_value = ".")]
+    vault: PathBuf,
+    /// The vault cache file. It is read if it exists and written after commands that change it (`cache`, `embed` and `similar` default to vault_cache.json)
+    #[arg(long, global = true)]
+    cache: Option<PathBuf>,
+    /// The OpenAI config file, for commands that use the AI model; `OPENAI_API_KEY` and the other `OPENAI_*` variables override it
+    #[arg(long, global = true, default_value = ".openai_config.json")]
+    config: PathBuf,
+    /// A named profile of the OpenAI config to use instead of its top-level settings
+    #[arg(long, global = true)]
+    profile: Option<String>,
+    /// Keep AI responses in this file and reuse them for identical requests, e.g. when re-running after a failure
+    #[arg(long, global = true)]
+    response_cache: Option<PathBuf>,
+    /// Log more to stderr: `-v` for requests and token usage, `-vv` for every file and cache lookup
+    #[arg(short, long, global = true, action = clap::ArgAction::Count)]
+    verbose: u8,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Compute embeddings for new and changed notes
+    Embed,
+    /// Run a query (`FROM #tag WHERE ... SORT ... LIMIT ...`) against the vault
+    Search {
+        query: String,
+    },
+    /// List the notes closest to a note by embedding
+    Similar {
+        /// The note, relative to the vault root
+        path: PathBuf,
+        /// The number of notes to list
+        #[arg(short, default_value_t = 10)]
+        n: usize,
+    },
+    /// Merge notes into one with the AI model
+    Merge {
+        /// The notes, relative to the vault root
+        #[arg(required = true, num_args = 2..)]
+        paths: Vec<PathBuf>,
+        /// Where to write the merged note
+        #[arg(long, short)]
+        output: PathBuf,
+    },
+    /// Generate a note from a prompt
+    Generate {
+        /// A prompt file (see `Prompt::from_file`), with `[key]` placeholders
+        #[arg(long)]
+        prompt: PathBuf,
+        /// Values for the placeholders, as `key=value` or `key=@file`
+        #[arg(long = "context", short = 'c', value_parser = parse_context_entry)]
+        context: Vec<(String, String)>,
+        /// The title of the note, including the extension
+        title: String,
+        /// The folder to write the note to
+        #[arg(long, short, default_value = ".")]
+        output: PathBuf,
+    },
+    /// Build or refresh the vault cache
+    Cache,
+    /// Serve the vault over HTTP until Ctrl-C, see `obsidian_driver::server`
+    #[cfg(feature = "server")]
+    Serve {
+        /// The address to listen on
+        #[arg(long, default_value = "127.0.0.1:27125")]
+        addr: std::net::SocketAddr,
+        /// Require `Authorization: Bearer <TOKEN>` on every request
+        #[arg(long)]
+        token: Option<String>,
+        /// Reject requests that change the vault
+        #[arg(long)]
+        read_only: bool,
+    },
+}
+
+/// Parse a `key=value` or `key=@file` context entry.
+fn parse_context_entry(entry: &str) -> std::result::Result<(String, String), String> {
+    let (key, value) = entry
+        .split_once('=')
+        .ok_or_else(|| format!("expected key=value, found `{}`", entry))?;
+    let value = match value.strip_prefix('@') {
+        Some(path) => std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?,
+        None => value.to_string(),
+    };
+    Ok((key.to_string(), value))
+}
+
+/// The cache file used when `--cache` is not given, by the commands that always need one.
+const DEFAULT_CACHE: &str = "vault_cache.json";
+
+/// The cache given with `--cache`, or the default one.
+fn cache_or_default(cli: &Cli) -> PathBuf {
+    cli.cache.clone().unwrap_or_else(|| PathBuf::from(DEFAULT_CACHE))
+}
+
+/// Load the vault, from the cache when one is given.
+fn load_vault(cli: &Cli) -> Result<Vault, Error> {
+    match &cli.cache {
+        Some(cache) => Vault::from_cache(cli.vault.clone(), cache),
+        None => Vault::from_path(cli.vault.clone()),
+    }
+}
+
+/// Write the vault cache when one is given.
+#[cfg(feature = "server")]
+fn save_vault(cli: &Cli, vault: &Vault) -> Result<(), Error> {
+    match &cli.cache {
+        Some(cache) => vault.to_cache(cache),
+        None => Ok(()),
+    }
+}
+
+/// Create the AI driver from the config file, with the chosen profile.
+async fn load_driver(cli: &Cli) -> Result<AIDriver, Error> {
+    let mut config = OpenAIConfig::from_file(cli.config.clone())?;
+    if let Some(profile) = &cli.profile {
+        config = config.profile(profile)?;
+    }
+    let driver = AIDriver::new_openai(config).await?;
+    match &cli.response_cache {
+        Some(path) => Ok(driver.with_cache(ResponseCache::open(path)?)),
+        None => Ok(driver),
+    }
+}
+
+async fn run(cli: Cli) -> Result<(), Error> {
+    match &cli.command {
+        Command::Embed => {
+            // the embeddings only outlive the command in the cache
+            let cache = cache_or_default(&cli);
+            let mut vault = Vault::from_cache(cli.vault.clone(), &cache)?;
+            vault.add_ai_driver(load_driver(&cli).await?);
+            vault.update_embeddings().await?;
+            vault.to_cache(&cache)?;
+        }
+        Command::Search { query } => {
+            let vault = load_vault(&cli)?;
+            for path in vault.query(query)? {
+                println!("{}", path.display());
+            }
+        }
+        Command::Similar { path, n } => {
+            let vault = Vault::from_cache(cli.vault.clone(), &cache_or_default(&cli))?;
+            let closest = vault.get_closest_files(path, *n + 1)?;
+            for (other, distance) in closest.into_iter().filter(|(other, _)| other != path).take(*n) {
+                println!("{:.4}\t{}", distance, other.display());
+            }
+        }
+        Command::Merge { paths, output } => {
+            let vault = load_vault(&cli)?;
+            let driver = load_driver(&cli).await?;
+            let mut files = Vec::new();
+            for path in paths {
+                files.push(vault.get_file(path).ok_or(Error::PathNotFound(path.clone()))?);
+            }
+            let merged = ai::merge_files(&driver, files).await?;
+            for path in merged.skipped.iter() {
+                eprintln!("skipped (not a markdown note): {}", path.display());
+            }
+            write_atomic(output, merged.mdfile.to_string())?;
+        }
+        Command::Generate {
+            prompt,
+            context,
+            title,
+            output,
+        } => {
+            let driver = load_driver(&cli).await?;
+            let prompt = Prompt::from_file(prompt)?;
+            let mut values = Context::default();
+            for (key, value) in context {
+                values.insert(key, value);
+            }
+            let file = ai::generate_file(&driver, prompt, values, title.clone(), output.clone()).await?;
+            std::fs::create_dir_all(output)?;
+            file.write()?;
+        }
+        Command::Cache => {
+            let vault = load_vault(&cli)?;
+            vault.to_cache(&cache_or_default(&cli))?;
+        }
+        #[cfg(feature = "server")]
+        Command::Serve { addr, token, read_only } => {
+            use obsidian_driver::server::{serve, ServerOptions};
+            let mut vault = load_vault(&cli)?;
+            // the AI endpoints answer 503 without a config
+            if cli.config.exists() {
+                vault.add_ai_driver(load_driver(&cli).await?);
+            }
+            let options = ServerOptions {
+                token: token.clone(),
+                read_only: *read_only,
+            };
+            let shutdown = tokio_util::sync::CancellationToken::new();
+            let ctrl_c = shutdown.clone();
+            tokio::spawn(async move {
+                let _ = tokio::signal::ctrl_c().await;
+                ctrl_c.cancel();
+            });
+            let vault = std::sync::Arc::new(tokio::sync::RwLock::new(vault));
+            serve(vault.clone(), *addr, options, &shutdown).await?;
+            save_vault(&cli, &*vault.read().await)?;
+        }
+    }
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let level = match cli.verbose {
+        0 => tracing::Level::WARN,
+        1 => tracing::Level::INFO,
+        _ => tracing::Level::DEBUG,
+    };
+    tracing_subscriber::fmt().with_max_level(level).with_writer(std::io::stderr).init();
+    match run(cli).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -5,6 +5,7 @@ pub use crate::error::Error;
 pub type Result<T> = core::result::Result<T, Error>;
 
 // Generic Wrapper tuple struct for newtype pattern
+#[allow(dead_code)]
 pub struct W<T>(pub T);
 
 // preference items
@@ -0,0 +1,407 @@
+//! obsidian-driver::server
+//!
+//! This module contains an HTTP API over a Vault, behind the `server` feature, for Obsidian plugins, web UIs and scripts that should not embed the library. Requests and responses are JSON, except note contents, which are sent and returned as markdown text.
+//!
+//! ```text
+//! GET  /notes?folder=<folder>         the paths of the notes, sorted
+//! GET  /notes/<path>                  a note: path, frontmatter, body, tags, links
+//! PUT  /notes/<path>                  create or replace a note from the markdown request body
+//! GET  /search?q=<query>              run a query (`FROM #tag WHERE ... SORT ... LIMIT ...`)
+//! GET  /search/semantic?q=<text>&n=5  search by embedding, see `Vault::semantic_search`
+//! GET  /similar/<path>?n=10           the notes nearest to a note by embedding
+//! POST /ask  {"question": "..."}      answer from the vault, see `Vault::ask`
+//! ```
+//!
+//! Errors are returned as `{"error": "..."}` with a 4xx or 5xx status.
+//!
+//! @public SharedVault
+//!
+//! @public ServerOptions
+//!
+//! @public router
+//!
+//! @public serve
+
+// std imports
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+// third-party imports
+use axum::extract::{Path, Query, Request, State};
+use axum::http::{header, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
+use tokio_util::sync::CancellationToken;
+use tracing::info;
+
+// first-party imports
+use crate::file::vault::ask::Answer;
+use crate::file::vault::search::SearchResult;
+use crate::file::vault::Vault;
+use crate::prelude::*;
+
+/// A Vault shared between the server and the rest of the program.
+///
+/// @public
+pub type SharedVault = Arc<RwLock<Vault>>;
+
+/// ServerOptions struct
+///
+/// # Example
+/// ```
+/// use obsidian_driver::server::ServerOptions;
+///
+/// let options = ServerOptions::default().with_token("secret").with_read_only(true);
+/// ```
+///
+/// @public
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ServerOptions {
+    // if set, requests must send `Authorization: Bearer <token>`
+    pub token: Option<String>,
+    // reject requests that change the vault
+    pub read_only: bool,
+}
+
+impl ServerOptions {
+    /// Require a bearer token.
+    ///
+    /// # Arguments
+    /// @param token: &str
+    /// @return ServerOptions
+    pub fn with_token(mut self, token: &str) -> Self {
+        self.token = Some(token.to_string());
+        self
+    }
+
+    /// Reject requests that change the vault.
+    ///
+    /// # Arguments
+    /// @param read_only: bool
+    /// @return ServerOptions
+    pub fn with_read_only(mut self, read_only: bool) -> Self {
+        self.read_only = read_only;
+        self
+    }
+}
+
+/// The state shared by the handlers.
+///
+/// @private
+#[derive(Clone)]
+struct AppState {
+    vault: SharedVault,
+    options: Arc<ServerOptions>,
+}
+
+/// A note as returned by `GET /notes/<path>`.
+///
+/// @private
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+struct NoteResponse {
+    path: PathBuf,
+    frontmatter: Option<serde_json::Value>,
+    body: String,
+    tags: Vec<String>,
+    // link targets as written
+    links: Vec<String>,
+}
+
+/// A note near another, as returned by `GET /similar/<path>`.
+///
+/// @private
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+struct SimilarNote {
+    path: PathBuf,
+    distance: f64,
+}
+
+/// The query of `GET /notes`.
+///
+/// @private
+#[derive(Debug, Deserialize)]
+struct ListParams {
+    folder: Option<PathBuf>,
+}
+
+/// The query of the search endpoints.
+///
+/// @private
+#[derive(Debug, Deserialize)]
+struct SearchParams {
+    q: String,
+    n: Option<usize>,
+}
+
+/// The query of `GET /similar/<path>`.
+///
+/// @private
+#[derive(Debug, Deserialize)]
+struct SimilarParams {
+    n: Option<usize>,
+}
+
+/// The body of `POST /ask`.
+///
+/// @private
+#[derive(Debug, Deserialize)]
+struct AskRequest {
+    question: String,
+}
+
+/// An Error as an HTTP response.
+///
+/// @private
+struct ApiError(StatusCode, String);
+
+impl From<Error> for ApiError {
+    fn from(e: Error) -> Self {
+        let status = match &e {
+            Error::PathNotFound(_) => StatusCode::NOT_FOUND,
+            Error::PathAlreadyExists(_) => StatusCode::CONFLICT,
+            Error::PathNotInVaultRoot(..) | Error::InvalidFrontmatter(_) | Error::InvalidQuery(_) | Error::UnknownQuery(_) => StatusCode::BAD_REQUEST,
+            Error::NoAIDriver => StatusCode::SERVICE_UNAVAILABLE,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        ApiError(status, e.to_string())
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.0, Json(serde_json::json!({ "error": self.1 }))).into_response()
+    }
+}
+
+/// Build the routes of the API, to serve or to nest in a larger application.
+///
+/// # Arguments
+/// @param vault: SharedVault
+/// @param options: ServerOptions
+/// @return Router
+///
+/// # Example
+/// ```
+/// use std::sync::Arc;
+///
+/// use tokio::sync::RwLock;
+///
+/// use obsidian_driver::file::vault::Vault;
+/// use obsidian_driver::server::{router, ServerOptions};
+///
+/// let app = router(Arc::new(RwLock::new(Vault::default())), ServerOptions::default());
+/// ```
+///
+/// @public
+pub fn router(vault: SharedVault, options: ServerOptions) -> Router {
+    let state = AppState {
+        vault,
+        options: Arc::new(options),
+    };
+    Router::new()
+        .route("/notes", get(list_notes))
+        .route("/notes/{*path}", get(get_note).put(put_note))
+        .route("/search", get(search))
+        .route("/search/semantic", get(semantic_search))
+        .route("/similar/{*path}", get(similar))
+        .route("/ask", post(ask))
+        .layer(middleware::from_fn_with_state(state.clone(), authorize))
+        .with_state(state)
+}
+
+/// Serve the API until the token is cancelled.
+///
+/// # Arguments
+/// @param vault: SharedVault
+/// @param addr: SocketAddr - e.g. `127.0.0.1:27125`; bind to localhost unless a token is set
+/// @param options: ServerOptions
+/// @param shutdown: &CancellationToken - Stops accepting connections and waits for running requests
+/// @return Result<()>
+///
+/// # Example
+/// ```should_panic
+/// use std::path::PathBuf;
+/// use std::sync::Arc;
+///
+/// use tokio::sync::RwLock;
+/// use tokio_util::sync::CancellationToken;
+///
+/// use obsidian_driver::file::vault::Vault;
+/// use obsidian_driver::server::{serve, ServerOptions};
+///
+/// async fn serve_example() {
+/// 	let vault = Arc::new(RwLock::new(Vault::from_path(PathBuf::from("vault")).unwrap()));
+/// 	let shutdown = CancellationToken::new();
+/// 	serve(vault, "127.0.0.1:27125".parse().unwrap(), ServerOptions::default(), &shutdown).await.unwrap();
+/// }
+/// # panic!("needs a vault");
+/// ```
+///
+/// @public
+pub async fn serve(vault: SharedVault, addr: SocketAddr, options: ServerOptions, shutdown: &CancellationToken) -> Result<()> {
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    info!(addr = %listener.local_addr()?, "serving vault");
+    axum::serve(listener, router(vault, options))
+        .with_graceful_shutdown(shutdown.clone().cancelled_owned())
+        .await?;
+    Ok(())
+}
+
+/// Check the bearer token and the read-only option.
+///
+/// @private
+async fn authorize(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    if let Some(token) = &state.options.token {
+        let sent = request
+            .headers()
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "));
+        if sent != Some(token.as_str()) {
+            return ApiError(StatusCode::UNAUTHORIZED, "missing or wrong bearer token".to_string()).into_response();
+        }
+    }
+    if state.options.read_only && request.method() == axum::http::Method::PUT {
+        return ApiError(StatusCode::FORBIDDEN, "the server is read-only".to_string()).into_response();
+    }
+    next.run(request).await
+}
+
+/// `GET /notes`
+///
+/// @private
+async fn list_notes(State(state): State<AppState>, Query(params): Query<ListParams>) -> Json<Vec<PathBuf>> {
+    let vault = state.vault.read().await;
+    let notes = vault.iter_md_files();
+    let notes = match &params.folder {
+        Some(folder) => notes.in_folder(folder),
+        None => notes,
+    };
+    Json(notes.map(|(path, _)| path.clone()).collect())
+}
+
+/// `GET /notes/<path>`
+///
+/// @private
+async fn get_note(State(state): State<AppState>, Path(path): Path<PathBuf>) -> std::result::Result<Json<NoteResponse>, ApiError> {
+    let vault = state.vault.read().await;
+    let mdfile = vault
+        .get_file(&path)
+        .and_then(|file| file.get_mdfile())
+        .ok_or(Error::PathNotFound(path.clone()))?;
+    let frontmatter = mdfile.get_yaml().map(serde_json::to_value).transpose().map_err(Error::from)?;
+    Ok(Json(NoteResponse {
+        path,
+        frontmatter,
+        body: mdfile.get_body().clone(),
+        tags: mdfile.get_tags(),
+        links: mdfile.get_links().into_iter().map(|link| link.target).collect(),
+    }))
+}
+
+/// `PUT /notes/<path>`
+///
+/// @private
+async fn put_note(State(state): State<AppState>, Path(path): Path<PathBuf>, contents: String) -> std::result::Result<StatusCode, ApiError> {
+    let created = state.vault.write().await.put_note(&path, &contents)?;
+    match created {
+        true => Ok(StatusCode::CREATED),
+        false => Ok(StatusCode::NO_CONTENT),
+    }
+}
+
+/// `GET /search`
+///
+/// @private
+async fn search(State(state): State<AppState>, Query(params): Query<SearchParams>) -> std::result::Result<Json<Vec<PathBuf>>, ApiError> {
+    Ok(Json(state.vault.read().await.query(&params.q)?))
+}
+
+/// `GET /search/semantic`
+///
+/// @private
+async fn semantic_search(State(state): State<AppState>, Query(params): Query<SearchParams>) -> std::result::Result<Json<Vec<SearchResult>>, ApiError> {
+    let vault = state.vault.read().await;
+    Ok(Json(vault.semantic_search(&params.q, params.n.unwrap_or(5)).await?))
+}
+
+/// `GET /similar/<path>`
+///
+/// @private
+async fn similar(
+    State(state): State<AppState>,
+    Path(path): Path<PathBuf>,
+    Query(params): Query<SimilarParams>,
+) -> std::result::Result<Json<Vec<SimilarNote>>, ApiError> {
+    let vault = state.vault.read().await;
+    if vault.get_file(&path).is_none() {
+        return Err(Error::PathNotFound(path).into());
+    }
+    let n = params.n.unwrap_or(10);
+    let similar = vault
+        .get_closest_files(&path, n + 1)?
+        .into_iter()
+        .filter(|(other, _)| other != &path)
+        .take(n)
+        .map(|(path, distance)| SimilarNote { path, distance })
+        .collect();
+    Ok(Json(similar))
+}
+
+/// `POST /ask`
+///
+/// @private
+async fn ask(State(state): State<AppState>, Json(request): Json<AskRequest>) -> std::result::Result<Json<Answer>, ApiError> {
+    Ok(Json(state.vault.read().await.ask(&request.question).await?))
+}
+
+#[cfg(test)]
+mod server_tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_server_lists_gets_and_puts_notes() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("Biology")).unwrap();
+        std::fs::write(dir.path().join("Biology/Cell.md"), "---\naliases: [cell]\n---\n# Cell #bio\n\nSee [[Mitosis]].").unwrap();
+        std::fs::write(dir.path().join("Mitosis.md"), "# Mitosis #bio").unwrap();
+        let vault = Arc::new(RwLock::new(Vault::from_path(dir.path().to_path_buf()).unwrap()));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = f!("http://{}", listener.local_addr().unwrap());
+        let app = router(vault.clone(), ServerOptions::default().with_token("secret"));
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        let client = reqwest::Client::new();
+        let get = |url: String| client.get(url).bearer_auth("secret").send();
+
+        let response = client.get(f!("{}/notes", base)).send().await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let notes: Vec<PathBuf> = get(f!("{}/notes", base)).await.unwrap().json().await.unwrap();
+        assert_eq!(notes, vec![PathBuf::from("Biology/Cell.md"), PathBuf::from("Mitosis.md")]);
+        let notes: Vec<PathBuf> = get(f!("{}/notes?folder=Biology", base)).await.unwrap().json().await.unwrap();
+        assert_eq!(notes, vec![PathBuf::from("Biology/Cell.md")]);
+
+        let note: NoteResponse = get(f!("{}/notes/Biology/Cell.md", base)).await.unwrap().json().await.unwrap();
+        assert_eq!(note.frontmatter, Some(serde_json::json!({ "aliases": ["cell"] })));
+        assert_eq!(note.tags, vec!["bio".to_string()]);
+        assert_eq!(note.links, vec!["Mitosis".to_string()]);
+        let response = get(f!("{}/notes/Missing.md", base)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let put = |path: &str, body: &str| client.put(f!("{}/notes/{}", base, path)).bearer_auth("secret").body(body.to_string()).send();
+        assert_eq!(put("Inbox/Idea.md", "# Idea #bio").await.unwrap().status(), StatusCode::CREATED);
+        assert_eq!(put("Mitosis.md", "# Mitosis #bio\n\nEdited").await.unwrap().status(), StatusCode::NO_CONTENT);
+        assert_eq!(put("Bad.md", "---\nkey: [\n---\n").await.unwrap().status(), StatusCode::BAD_REQUEST);
+        assert_eq!(std::fs::read_to_string(dir.path().join("Inbox/Idea.md")).unwrap(), "# Idea #bio");
+        assert_eq!(std::fs::read_to_string(dir.path().join("Mitosis.md")).unwrap(), "# Mitosis #bio\n\nEdited");
+
+        let found: Vec<PathBuf> = get(f!("{}/search?q=FROM%20%23bio%20SORT%20file.name", base)).await.unwrap().json().await.unwrap();
+        assert_eq!(found.len(), 3);
+        let response = get(f!("{}/search/semantic?q=cells", base)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    }
+}
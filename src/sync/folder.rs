@@ -0,0 +1,85 @@
+//! obsidian-driver::sync::folder
+//!
+//! This module contains the folder remote: a vault copy in another folder, e.g. a network share or a mounted drive.
+//!
+//! @public FolderRemote
+
+// std imports
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+// first-party imports
+use super::{hash, local_files, write_local, RemoteStore, SyncFuture};
+use crate::prelude::*;
+
+/// A remote that is a folder of the local file system. File versions are their content hashes.
+///
+/// # Example
+/// ```should_panic
+/// use std::path::PathBuf;
+///
+/// use obsidian_driver::file::vault::Vault;
+/// use obsidian_driver::sync::folder::FolderRemote;
+/// use obsidian_driver::sync::SyncOptions;
+///
+/// async fn folder_example() {
+/// 	let mut vault = Vault::from_path(PathBuf::from("vault")).unwrap();
+/// 	let remote = FolderRemote::new(PathBuf::from("/mnt/share/vault"));
+/// 	vault.sync(&remote, &SyncOptions::default()).await.unwrap();
+/// }
+/// # panic!("needs a shared folder");
+/// ```
+///
+/// @public
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FolderRemote {
+    root: PathBuf,
+}
+
+impl FolderRemote {
+    /// Create a remote for a folder.
+    ///
+    /// # Arguments
+    /// @param root: PathBuf - The folder holding the remote copy; it is created on the first upload
+    /// @return FolderRemote
+    pub fn new(root: PathBuf) -> Self {
+        FolderRemote { root }
+    }
+}
+
+impl RemoteStore for FolderRemote {
+    fn id(&self) -> String {
+        f!("folder:{}", self.root.display())
+    }
+
+    fn list(&self) -> SyncFuture<'_, HashMap<PathBuf, String>> {
+        Box::pin(async move {
+            let mut files = HashMap::new();
+            if !self.root.is_dir() {
+                return Ok(files);
+            }
+            for path in local_files(&self.root)? {
+                let version = hash(&std::fs::read(self.root.join(&path))?);
+                files.insert(path, version);
+            }
+            Ok(files)
+        })
+    }
+
+    fn get<'a>(&'a self, path: &'a Path) -> SyncFuture<'a, Vec<u8>> {
+        Box::pin(async move { Ok(std::fs::read(self.root.join(path))?) })
+    }
+
+    fn put<'a>(&'a self, path: &'a Path, contents: Vec<u8>) -> SyncFuture<'a, ()> {
+        Box::pin(async move { write_local(&self.root.join(path), &contents) })
+    }
+
+    fn delete<'a>(&'a self, path: &'a Path) -> SyncFuture<'a, ()> {
+        Box::pin(async move {
+            match std::fs::remove_file(self.root.join(path)) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+                _ => Ok(()),
+            }
+        })
+    }
+}
@@ -0,0 +1,651 @@
+//! obsidian-driver::sync
+//!
+//! This module contains remote sync: pushing the files of a vault to a remote store and pulling the remote's changes, so a headless machine can work on a vault that lives on another one. Changes are found against the state of the last sync, kept in the vault, and a file changed on both sides since then is a conflict, handled by a ConflictPolicy.
+//!
+//! @public RemoteStore
+//!
+//! @public SyncFuture
+//!
+//! @public SyncDirection
+//!
+//! @public ConflictPolicy
+//!
+//! @public SyncOptions
+//!
+//! @public SyncReport
+//!
+//! @public Vault::sync
+//!
+//! @public folder
+//!
+//! @public s3
+//!
+//! @public webdav
+
+// std imports
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+
+// third-party imports
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tracing::{info, warn};
+
+// first-party imports
+use crate::batch::CancellationToken;
+use crate::file::naming::{resolve_collision, CollisionStrategy};
+use crate::file::vault::links::BrokenLinkPolicy;
+use crate::file::vault::Vault;
+use crate::prelude::*;
+
+// submodules
+pub mod folder;
+pub mod s3;
+pub mod webdav;
+
+/// The folder of the vault the sync states are kept in, relative to the vault root. It is hidden, so it is not synced.
+const STATE_FOLDER: &str = ".obsidian-driver/sync";
+
+/// The boxed future returned by RemoteStore methods.
+///
+/// @public
+pub type SyncFuture<'a, T> = Pin<Box<dyn Future<Output = Result<T>> + Send + 'a>>;
+
+/// A remote copy of a vault. Paths are relative to the vault root.
+///
+/// # Example
+/// ```
+/// use std::collections::HashMap;
+/// use std::path::{Path, PathBuf};
+///
+/// use obsidian_driver::sync::{RemoteStore, SyncFuture};
+///
+/// /// A remote that is always empty and accepts nothing.
+/// struct Empty;
+///
+/// impl RemoteStore for Empty {
+/// 	fn id(&self) -> String {
+/// 		"empty".to_string()
+/// 	}
+///
+/// 	fn list(&self) -> SyncFuture<'_, HashMap<PathBuf, String>> {
+/// 		Box::pin(async { Ok(HashMap::new()) })
+/// 	}
+///
+/// 	fn get<'a>(&'a self, path: &'a Path) -> SyncFuture<'a, Vec<u8>> {
+/// 		Box::pin(async move { Err(obsidian_driver::error::Error::PathNotFound(path.to_path_buf())) })
+/// 	}
+///
+/// 	fn put<'a>(&'a self, _path: &'a Path, _contents: Vec<u8>) -> SyncFuture<'a, ()> {
+/// 		Box::pin(async { Ok(()) })
+/// 	}
+///
+/// 	fn delete<'a>(&'a self, _path: &'a Path) -> SyncFuture<'a, ()> {
+/// 		Box::pin(async { Ok(()) })
+/// 	}
+/// }
+/// ```
+///
+/// @public
+pub trait RemoteStore: Send + Sync {
+    /// A name of the remote that stays the same between runs, e.g. its URL, to keep its sync state apart from other remotes.
+    fn id(&self) -> String;
+
+    /// List every file of the remote with its version, a value that changes whenever the file does, e.g. an ETag.
+    fn list(&self) -> SyncFuture<'_, HashMap<PathBuf, String>>;
+
+    /// Download a file.
+    fn get<'a>(&'a self, path: &'a Path) -> SyncFuture<'a, Vec<u8>>;
+
+    /// Upload a file, creating or replacing it.
+    fn put<'a>(&'a self, path: &'a Path, contents: Vec<u8>) -> SyncFuture<'a, ()>;
+
+    /// Delete a file. Deleting a missing file is not an error.
+    fn delete<'a>(&'a self, path: &'a Path) -> SyncFuture<'a, ()>;
+}
+
+/// Which way changes are copied.
+///
+/// @public
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SyncDirection {
+    /// Local changes are uploaded and remote changes downloaded.
+    #[default]
+    Both,
+    /// Only local changes are uploaded; remote changes wait for a later sync.
+    Push,
+    /// Only remote changes are downloaded; local changes wait for a later sync.
+    Pull,
+}
+
+/// What happens to a file changed on both sides since the last sync, or changed on one and deleted on the other.
+///
+/// @public
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ConflictPolicy {
+    /// Leave both sides as they are and report the conflict; it is found again by the next sync.
+    #[default]
+    Report,
+    /// Upload the local side.
+    KeepLocal,
+    /// Download the remote side.
+    KeepRemote,
+    /// Keep the local file and download the remote one next to it as `<name> (remote)`, which the next sync uploads.
+    KeepBoth,
+}
+
+/// SyncOptions struct
+///
+/// The options of `Vault::sync`.
+///
+/// # Example
+/// ```
+/// use obsidian_driver::sync::{ConflictPolicy, SyncDirection, SyncOptions};
+///
+/// let options = SyncOptions::default()
+/// 	.with_direction(SyncDirection::Pull)
+/// 	.with_conflicts(ConflictPolicy::KeepBoth);
+/// ```
+///
+/// @public
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SyncOptions {
+    pub direction: SyncDirection,
+    pub conflicts: ConflictPolicy,
+    // relative to the vault root; by default a file per remote in `.obsidian-driver/sync`
+    pub state_path: Option<PathBuf>,
+    // report what would be copied without changing either side
+    pub dry_run: bool,
+}
+
+impl SyncOptions {
+    /// Set which way changes are copied.
+    ///
+    /// # Arguments
+    /// @param direction: SyncDirection
+    /// @return SyncOptions
+    pub fn with_direction(mut self, direction: SyncDirection) -> Self {
+        self.direction = direction;
+        self
+    }
+
+    /// Set what happens to conflicting files.
+    ///
+    /// # Arguments
+    /// @param conflicts: ConflictPolicy
+    /// @return SyncOptions
+    pub fn with_conflicts(mut self, conflicts: ConflictPolicy) -> Self {
+        self.conflicts = conflicts;
+        self
+    }
+
+    /// Set the file the sync state is kept in.
+    ///
+    /// # Arguments
+    /// @param state_path: PathBuf - Relative to the vault root
+    /// @return SyncOptions
+    pub fn with_state_path(mut self, state_path: PathBuf) -> Self {
+        self.state_path = Some(state_path);
+        self
+    }
+
+    /// Set whether the sync only reports what it would copy.
+    ///
+    /// # Arguments
+    /// @param dry_run: bool
+    /// @return SyncOptions
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+}
+
+/// SyncReport struct
+///
+/// What a sync copied, with paths relative to the vault root, each sorted.
+///
+/// @public
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SyncReport {
+    // uploaded to the remote
+    pub pushed: Vec<PathBuf>,
+    // downloaded from the remote
+    pub pulled: Vec<PathBuf>,
+    // deleted from the remote, since they were deleted locally
+    pub deleted_remote: Vec<PathBuf>,
+    // moved to the trash of the vault, since they were deleted on the remote
+    pub deleted_local: Vec<PathBuf>,
+    // changed on both sides; with `ConflictPolicy::KeepBoth`, the remote side was saved next to them
+    pub conflicts: Vec<PathBuf>,
+}
+
+/// The state of a file at the last sync.
+///
+/// @private
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct SyncedFile {
+    // SHA-256 of the local contents
+    hash: String,
+    // the version of the remote file
+    version: String,
+}
+
+/// What to do with one file.
+///
+/// @private
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Action {
+    Push,
+    Pull,
+    DeleteRemote,
+    DeleteLocal,
+    // the two sides are the same, only the state is updated
+    Record,
+    Conflict,
+}
+
+impl Vault {
+    /// Sync the vault with a remote store.
+    ///
+    /// Unsaved edits are written first. A file is changed on a side if it differs from the last
+    /// sync; changes on one side are copied to the other, and files changed on both are handled by
+    /// `options.conflicts`. On the first sync, files on both sides with the same contents are not
+    /// conflicts. Attachments, such as images, are synced like notes; hidden files, like the
+    /// `.obsidian` folder, are not. Files deleted on the remote are moved to the trash of the vault,
+    /// and downloaded notes are loaded into the Vault.
+    ///
+    /// # Arguments
+    /// @param remote: &dyn RemoteStore
+    /// @param options: &SyncOptions
+    /// @return Result<SyncReport> - Errors if the remote cannot be reached; the state is then kept as it was, so the next sync retries
+    ///
+    /// # Example
+    /// ```should_panic
+    /// use std::path::PathBuf;
+    ///
+    /// use obsidian_driver::file::vault::Vault;
+    /// use obsidian_driver::sync::webdav::WebDavRemote;
+    /// use obsidian_driver::sync::SyncOptions;
+    ///
+    /// async fn sync_example() {
+    /// 	let mut vault = Vault::from_path(PathBuf::from("vault")).unwrap();
+    /// 	let remote = WebDavRemote::new("https://dav.example.com/vault").with_credentials("me", "secret");
+    /// 	let report = vault.sync(&remote, &SyncOptions::default()).await.unwrap();
+    /// 	println!("{} pushed, {} conflicts", report.pushed.len(), report.conflicts.len());
+    /// }
+    /// # panic!("needs a WebDAV server");
+    /// ```
+    pub async fn sync(&mut self, remote: &dyn RemoteStore, options: &SyncOptions) -> Result<SyncReport> {
+        if !options.dry_run {
+            if let Some((path, e)) = self.write_all().failed.into_iter().next() {
+                return Err(Error::SyncFailed(f!("could not write {}: {}", path.display(), e)));
+            }
+        }
+        let vault_root = self.get_vault_root().clone();
+        let state_path = vault_root.join(options.state_path.clone().unwrap_or_else(|| default_state_path(remote)));
+        let mut state: BTreeMap<PathBuf, SyncedFile> = match state_path.exists() {
+            true => serde_json::from_str(&std::fs::read_to_string(&state_path)?)?,
+            false => BTreeMap::new(),
+        };
+
+        // the Vault only holds notes, so the local side is read from disk, like the remote listings
+        let mut local = HashMap::new();
+        for path in local_files(&vault_root)? {
+            local.insert(path.clone(), hash(&std::fs::read(vault_root.join(&path))?));
+        }
+        for (path, file) in self.iter_files() {
+            // on a dry run, unsaved edits are compared as they would be written
+            if file.is_dirty() && is_synced(path) {
+                local.insert(path.clone(), hash(file.contents_to_write()?.as_bytes()));
+            }
+        }
+        let remote_files = remote.list().await?;
+        let paths: BTreeSet<&PathBuf> = local.keys().chain(remote_files.keys()).chain(state.keys()).collect();
+
+        let mut report = SyncReport::default();
+        let mut pushed = Vec::new();
+        let mut pulled = Vec::new();
+        for path in paths.into_iter().cloned().collect::<Vec<PathBuf>>() {
+            let (local_hash, version, synced) = (local.get(&path), remote_files.get(&path), state.get(&path));
+            let local_changed = local_hash != synced.map(|synced| &synced.hash);
+            let remote_changed = version != synced.map(|synced| &synced.version);
+            let mut action = match (local_changed, remote_changed) {
+                (false, false) => continue,
+                (true, false) if local_hash.is_some() => Action::Push,
+                (true, false) => Action::DeleteRemote,
+                (false, true) if version.is_some() => Action::Pull,
+                (false, true) => Action::DeleteLocal,
+                (true, true) => match (local_hash, version) {
+                    (None, None) => {
+                        state.remove(&path);
+                        continue;
+                    }
+                    (Some(local_hash), Some(_)) if &hash(&remote.get(&path).await?) == local_hash => Action::Record,
+                    _ => Action::Conflict,
+                },
+            };
+            if action == Action::Conflict {
+                report.conflicts.push(path.clone());
+                action = match (options.conflicts, local_hash, version) {
+                    (ConflictPolicy::Report, ..) => continue,
+                    (ConflictPolicy::KeepLocal, Some(_), _) => Action::Push,
+                    (ConflictPolicy::KeepLocal, None, _) => Action::DeleteRemote,
+                    (ConflictPolicy::KeepRemote, _, Some(_)) => Action::Pull,
+                    (ConflictPolicy::KeepRemote, _, None) => Action::DeleteLocal,
+                    (ConflictPolicy::KeepBoth, Some(_), Some(_)) => {
+                        if !options.dry_run {
+                            let copy = conflict_copy(&vault_root, &path)?;
+                            write_local(&vault_root.join(&copy), &remote.get(&path).await?)?;
+                            report.pulled.push(copy);
+                        }
+                        continue;
+                    }
+                    // a file deleted on one side and changed on the other is kept
+                    (ConflictPolicy::KeepBoth, Some(_), None) => Action::Push,
+                    (ConflictPolicy::KeepBoth, None, _) => Action::Pull,
+                };
+            }
+            let skipped = match action {
+                Action::Push | Action::DeleteRemote => options.direction == SyncDirection::Pull,
+                Action::Pull | Action::DeleteLocal => options.direction == SyncDirection::Push,
+                Action::Record | Action::Conflict => false,
+            };
+            if skipped {
+                continue;
+            }
+            match action {
+                Action::Push => report.pushed.push(path.clone()),
+                Action::Pull => report.pulled.push(path.clone()),
+                Action::DeleteRemote => report.deleted_remote.push(path.clone()),
+                Action::DeleteLocal => report.deleted_local.push(path.clone()),
+                Action::Record | Action::Conflict => {}
+            }
+            if options.dry_run {
+                continue;
+            }
+            match action {
+                Action::Push => {
+                    remote.put(&path, std::fs::read(vault_root.join(&path))?).await?;
+                    pushed.push(path);
+                }
+                Action::Pull => {
+                    let contents = remote.get(&path).await?;
+                    write_local(&vault_root.join(&path), &contents)?;
+                    let version = version.expect("pulled files are on the remote").clone();
+                    state.insert(path.clone(), SyncedFile { hash: hash(&contents), version });
+                    pulled.push(path);
+                }
+                Action::DeleteRemote => {
+                    remote.delete(&path).await?;
+                    state.remove(&path);
+                }
+                Action::DeleteLocal => {
+                    match self.get_file(&path).is_some() {
+                        true => {
+                            self.remove_file(&path, BrokenLinkPolicy::Ignore)?;
+                        }
+                        false => {
+                            self.trash_file(&path)?;
+                        }
+                    }
+                    state.remove(&path);
+                }
+                Action::Record => {
+                    let synced = SyncedFile {
+                        hash: local_hash.expect("recorded files are local").clone(),
+                        version: version.expect("recorded files are on the remote").clone(),
+                    };
+                    state.insert(path, synced);
+                }
+                Action::Conflict => {}
+            }
+        }
+        if options.dry_run {
+            return Ok(report);
+        }
+
+        // uploads change the versions of the remote files, which only a new listing has
+        if !pushed.is_empty() {
+            let versions = remote.list().await?;
+            for path in pushed {
+                match versions.get(&path) {
+                    Some(version) => {
+                        state.insert(path.clone(), SyncedFile { hash: local[&path].clone(), version: version.clone() });
+                    }
+                    None => warn!(path = %path.display(), "pushed file is missing from the remote listing"),
+                }
+            }
+        }
+        write_local(&state_path, serde_json::to_string_pretty(&state)?.as_bytes())?;
+        if !pulled.is_empty() || !report.pulled.is_empty() {
+            self.refresh_until(&CancellationToken::new())?;
+        }
+        info!(
+            pushed = report.pushed.len(),
+            pulled = report.pulled.len(),
+            conflicts = report.conflicts.len(),
+            "vault synced"
+        );
+        Ok(report)
+    }
+}
+
+/// The default state file of a remote, relative to the vault root.
+///
+/// @private
+fn default_state_path(remote: &dyn RemoteStore) -> PathBuf {
+    let id = hash(remote.id().as_bytes());
+    PathBuf::from(STATE_FOLDER).join(f!("{}.json", &id[..16]))
+}
+
+/// Check whether a path is synced: none of its parts is hidden, i.e. starts with `.`.
+///
+/// @private
+fn is_synced(path: &Path) -> bool {
+    !path.components().any(|component| component.as_os_str().to_string_lossy().starts_with('.'))
+}
+
+/// The synced files in a folder on disk, notes and attachments alike, relative to the folder.
+///
+/// @private
+fn local_files(vault_root: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let walker = walkdir::WalkDir::new(vault_root)
+        .into_iter()
+        .filter_entry(|entry| entry.depth() == 0 || !entry.file_name().to_string_lossy().starts_with('.'));
+    for entry in walker {
+        let entry = entry?;
+        if entry.file_type().is_file() {
+            files.push(entry.path().strip_prefix(vault_root)?.to_path_buf());
+        }
+    }
+    Ok(files)
+}
+
+/// The SHA-256 hash of contents, hex encoded.
+///
+/// @private
+fn hash(contents: &[u8]) -> String {
+    Sha256::digest(contents).iter().map(|byte| f!("{:02x}", byte)).collect()
+}
+
+/// Write a file, creating its folder.
+///
+/// @private
+fn write_local(path: &Path, contents: &[u8]) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    crate::file::write_atomic(path, contents)
+}
+
+/// A free path next to a conflicting file for its remote side, `<name> (remote).<extension>`.
+///
+/// @private
+fn conflict_copy(vault_root: &Path, path: &Path) -> Result<PathBuf> {
+    let stem = path.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default();
+    let name = match path.extension() {
+        Some(extension) => f!("{} (remote).{}", stem, extension.to_string_lossy()),
+        None => f!("{} (remote)", stem),
+    };
+    resolve_collision(&path.with_file_name(name), CollisionStrategy::NumericSuffix, |candidate| vault_root.join(candidate).exists())
+}
+
+/// The key of a path on a remote: its components joined with `/`.
+///
+/// @crate
+pub(crate) fn remote_key(path: &Path) -> String {
+    path.components().map(|component| component.as_os_str().to_string_lossy()).collect::<Vec<_>>().join("/")
+}
+
+/// Percent-encode text for a URL, keeping the unreserved characters, and `/` if `keep_slash`.
+///
+/// @crate
+pub(crate) fn uri_encode(text: &str, keep_slash: bool) -> String {
+    let mut encoded = String::with_capacity(text.len());
+    for byte in text.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => encoded.push(byte as char),
+            b'/' if keep_slash => encoded.push('/'),
+            _ => encoded.push_str(&f!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+/// Decode percent-encoded text. Invalid escapes are kept as they are.
+///
+/// @private
+fn uri_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escape = match bytes[i] == b'%' && i + 2 < bytes.len() {
+            true => std::str::from_utf8(&bytes[i + 1..i + 3]).ok().and_then(|hex| u8::from_str_radix(hex, 16).ok()),
+            false => None,
+        };
+        match escape {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Replace the predefined XML entities in text.
+///
+/// @private
+fn xml_unescape(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+/// Fail with `SyncFailed` if a response is not a success.
+///
+/// @private
+async fn check_status(response: reqwest::Response, what: &str) -> Result<reqwest::Response> {
+    if response.status().is_success() {
+        return Ok(response);
+    }
+    let status = response.status();
+    let body = response.text().await.unwrap_or_default();
+    Err(Error::SyncFailed(f!("{} failed with {}: {}", what, status, body.chars().take(200).collect::<String>())))
+}
+
+#[cfg(test)]
+mod sync_tests {
+    use super::folder::FolderRemote;
+    use super::*;
+
+    #[tokio::test]
+    async fn test_sync_copies_changes_and_detects_conflicts() {
+        let dir = tempfile::tempdir().unwrap();
+        let remote_dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.md"), "# A").unwrap();
+        std::fs::write(dir.path().join("b.md"), "# B").unwrap();
+        std::fs::create_dir_all(remote_dir.path().join("sub")).unwrap();
+        std::fs::write(remote_dir.path().join("sub/c.md"), "# C").unwrap();
+        std::fs::write(remote_dir.path().join("b.md"), "# B").unwrap();
+        let remote = FolderRemote::new(remote_dir.path().to_path_buf());
+        let mut vault = Vault::from_path(dir.path().to_path_buf()).unwrap();
+
+        let report = vault.sync(&remote, &SyncOptions::default()).await.unwrap();
+        assert_eq!(report.pushed, vec![PathBuf::from("a.md")]);
+        assert_eq!(report.pulled, vec![PathBuf::from("sub/c.md")]);
+        assert!(report.conflicts.is_empty());
+        assert!(vault.get_file(&PathBuf::from("sub/c.md")).is_some());
+        assert_eq!(std::fs::read_to_string(remote_dir.path().join("a.md")).unwrap(), "# A");
+        assert_eq!(vault.sync(&remote, &SyncOptions::default()).await.unwrap(), SyncReport::default());
+
+        // a edited on both sides, b deleted on the remote, c edited locally
+        std::fs::write(remote_dir.path().join("a.md"), "# A remote").unwrap();
+        vault.get_file_mut(&PathBuf::from("a.md")).unwrap().get_mdfile_mut().unwrap().set_body("# A local".to_string());
+        std::fs::remove_file(remote_dir.path().join("b.md")).unwrap();
+        vault.get_file_mut(&PathBuf::from("sub/c.md")).unwrap().get_mdfile_mut().unwrap().set_body("# C local".to_string());
+
+        let dry_run = SyncOptions::default().with_dry_run(true);
+        let report = vault.sync(&remote, &dry_run).await.unwrap();
+        assert_eq!(report.conflicts, vec![PathBuf::from("a.md")]);
+        assert_eq!(report.deleted_local, vec![PathBuf::from("b.md")]);
+        assert!(dir.path().join("b.md").exists());
+
+        let keep_both = SyncOptions::default().with_conflicts(ConflictPolicy::KeepBoth);
+        let report = vault.sync(&remote, &keep_both).await.unwrap();
+        assert_eq!(report.conflicts, vec![PathBuf::from("a.md")]);
+        assert_eq!(report.pulled, vec![PathBuf::from("a (remote).md")]);
+        assert_eq!(report.pushed, vec![PathBuf::from("sub/c.md")]);
+        assert_eq!(report.deleted_local, vec![PathBuf::from("b.md")]);
+        assert!(!dir.path().join("b.md").exists());
+        assert_eq!(std::fs::read_to_string(dir.path().join("a (remote).md")).unwrap(), "# A remote");
+        assert_eq!(std::fs::read_to_string(remote_dir.path().join("sub/c.md")).unwrap(), "# C local");
+
+        let keep_local = SyncOptions::default().with_conflicts(ConflictPolicy::KeepLocal);
+        let report = vault.sync(&remote, &keep_local).await.unwrap();
+        assert_eq!(report.pushed, vec![PathBuf::from("a (remote).md"), PathBuf::from("a.md")]);
+        assert_eq!(std::fs::read_to_string(remote_dir.path().join("a.md")).unwrap(), "# A local");
+        assert_eq!(vault.sync(&remote, &SyncOptions::default()).await.unwrap(), SyncReport::default());
+    }
+
+    #[tokio::test]
+    async fn test_sync_copies_attachments() {
+        let dir = tempfile::tempdir().unwrap();
+        let remote_dir = tempfile::tempdir().unwrap();
+        let (local_image, remote_image) = (vec![0x89, b'P', b'N', b'G', 0x00, 0xff], vec![0x89, b'P', b'N', b'G', 0x01, 0xfe]);
+        std::fs::write(dir.path().join("a.md"), "# A\n\n![[img.png]]").unwrap();
+        std::fs::write(dir.path().join("img.png"), &local_image).unwrap();
+        std::fs::create_dir_all(remote_dir.path().join("assets")).unwrap();
+        std::fs::write(remote_dir.path().join("assets/photo.png"), &remote_image).unwrap();
+        let remote = FolderRemote::new(remote_dir.path().to_path_buf());
+        let mut vault = Vault::from_path(dir.path().to_path_buf()).unwrap();
+
+        let report = vault.sync(&remote, &SyncOptions::default()).await.unwrap();
+        assert_eq!(report.pushed, vec![PathBuf::from("a.md"), PathBuf::from("img.png")]);
+        assert_eq!(report.pulled, vec![PathBuf::from("assets/photo.png")]);
+        assert_eq!(std::fs::read(remote_dir.path().join("img.png")).unwrap(), local_image);
+        assert_eq!(std::fs::read(dir.path().join("assets/photo.png")).unwrap(), remote_image);
+        assert!(vault.get_file(&PathBuf::from("assets/photo.png")).is_none());
+
+        // nothing changed, so nothing is copied or deleted
+        assert_eq!(vault.sync(&remote, &SyncOptions::default()).await.unwrap(), SyncReport::default());
+        assert!(remote_dir.path().join("img.png").exists());
+        assert!(dir.path().join("assets/photo.png").exists());
+
+        std::fs::remove_file(remote_dir.path().join("img.png")).unwrap();
+        let report = vault.sync(&remote, &SyncOptions::default()).await.unwrap();
+        assert_eq!(report.deleted_local, vec![PathBuf::from("img.png")]);
+        assert!(!dir.path().join("img.png").exists());
+        assert_eq!(std::fs::read(dir.path().join(".trash/img.png")).unwrap(), local_image);
+    }
+}
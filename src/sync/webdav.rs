@@ -0,0 +1,319 @@
+//! obsidian-driver::sync::webdav
+//!
+//! This module contains the WebDAV remote, for vault copies on Nextcloud, ownCloud, Apache `mod_dav`, rclone `serve webdav`, and other WebDAV servers.
+//!
+//! @public WebDavRemote
+
+// std imports
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+// third-party imports
+use regex::Regex;
+use reqwest::{Client, Method, RequestBuilder, StatusCode};
+use sha2::{Digest, Sha256};
+
+// first-party imports
+use super::{check_status, remote_key, uri_decode, uri_encode, xml_unescape, RemoteStore, SyncFuture};
+use crate::prelude::*;
+
+/// The properties asked for when listing a folder.
+const PROPFIND_BODY: &str = r#"<?xml version="1.0" encoding="utf-8"?><d:propfind xmlns:d="DAV:"><d:prop><d:resourcetype/><d:getetag/><d:getlastmodified/></d:prop></d:propfind>"#;
+
+/// A remote on a WebDAV server. File versions are ETags, or modification dates if the server has none,
+/// or hashes of the contents if it has neither.
+///
+/// # Example
+/// ```
+/// use obsidian_driver::sync::webdav::WebDavRemote;
+///
+/// let remote = WebDavRemote::new("https://cloud.example.com/remote.php/dav/files/me/Vault")
+/// 	.with_credentials("me", "app-password");
+/// ```
+///
+/// @public
+#[derive(Clone, Debug)]
+pub struct WebDavRemote {
+    // the URL of the folder holding the vault, without a trailing `/`
+    base_url: String,
+    credentials: Option<(String, String)>,
+    client: Client,
+}
+
+/// An entry of a PROPFIND response.
+///
+/// @private
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct DavEntry {
+    // decoded path of the entry on the server
+    href: String,
+    version: Option<String>,
+    collection: bool,
+}
+
+impl WebDavRemote {
+    /// Create a remote for a folder on a WebDAV server.
+    ///
+    /// # Arguments
+    /// @param base_url: &str - The URL of the folder holding the vault
+    /// @return WebDavRemote
+    pub fn new(base_url: &str) -> Self {
+        WebDavRemote {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            credentials: None,
+            client: Client::new(),
+        }
+    }
+
+    /// Log in with HTTP basic authentication.
+    ///
+    /// # Arguments
+    /// @param username: &str
+    /// @param password: &str - For Nextcloud, an app password
+    /// @return WebDavRemote
+    pub fn with_credentials(mut self, username: &str, password: &str) -> Self {
+        self.credentials = Some((username.to_string(), password.to_string()));
+        self
+    }
+
+    /// The URL of a path relative to the vault folder.
+    ///
+    /// @private
+    fn url(&self, key: &str) -> String {
+        match key.is_empty() {
+            true => f!("{}/", self.base_url),
+            false => f!("{}/{}", self.base_url, uri_encode(key, true)),
+        }
+    }
+
+    /// Start a request, with the credentials if there are any.
+    ///
+    /// @private
+    fn request(&self, method: Method, url: &str) -> RequestBuilder {
+        let request = self.client.request(method, url);
+        match &self.credentials {
+            Some((username, password)) => request.basic_auth(username, Some(password)),
+            None => request,
+        }
+    }
+
+    /// List the entries directly in a folder, relative to the vault folder; the folder itself is left out.
+    ///
+    /// @private
+    async fn list_folder(&self, key: &str) -> Result<Vec<(String, DavEntry)>> {
+        let propfind = Method::from_bytes(b"PROPFIND").expect("PROPFIND is a valid method");
+        let response = self
+            .request(propfind, &self.url(key))
+            .header("Depth", "1")
+            .header("Content-Type", "application/xml")
+            .body(PROPFIND_BODY)
+            .send()
+            .await?;
+        if response.status() == StatusCode::NOT_FOUND && key.is_empty() {
+            return Ok(Vec::new());
+        }
+        let xml = check_status(response, "PROPFIND").await?.text().await?;
+        let base_path = uri_decode(reqwest::Url::parse(&self.base_url).map(|url| url.path().to_string()).unwrap_or_default().trim_end_matches('/'));
+        let entries = parse_multistatus(&xml)
+            .into_iter()
+            .filter_map(|entry| {
+                // servers answer with absolute URLs or paths
+                let path = match entry.href.find("://") {
+                    Some(scheme) => entry.href[scheme + 3..].find('/').map(|start| entry.href[scheme + 3 + start..].to_string())?,
+                    None => entry.href.clone(),
+                };
+                let relative = path.strip_prefix(&base_path)?.trim_matches('/').to_string();
+                (relative != key).then_some((relative, entry))
+            })
+            .collect();
+        Ok(entries)
+    }
+}
+
+impl RemoteStore for WebDavRemote {
+    fn id(&self) -> String {
+        f!("webdav:{}", self.base_url)
+    }
+
+    fn list(&self) -> SyncFuture<'_, HashMap<PathBuf, String>> {
+        Box::pin(async move {
+            let mut files = HashMap::new();
+            let mut folders = vec![String::new()];
+            while let Some(folder) = folders.pop() {
+                for (key, entry) in self.list_folder(&folder).await? {
+                    if key.split('/').any(|part| part.starts_with('.')) {
+                        continue;
+                    }
+                    match entry.collection {
+                        true => folders.push(key),
+                        false => {
+                            let path = PathBuf::from(&key);
+                            let version = match entry.version {
+                                Some(version) => version,
+                                None => content_version(&self.get(&path).await?),
+                            };
+                            files.insert(path, version);
+                        }
+                    }
+                }
+            }
+            Ok(files)
+        })
+    }
+
+    fn get<'a>(&'a self, path: &'a Path) -> SyncFuture<'a, Vec<u8>> {
+        Box::pin(async move {
+            let response = self.request(Method::GET, &self.url(&remote_key(path))).send().await?;
+            Ok(check_status(response, "GET").await?.bytes().await?.to_vec())
+        })
+    }
+
+    fn put<'a>(&'a self, path: &'a Path, contents: Vec<u8>) -> SyncFuture<'a, ()> {
+        Box::pin(async move {
+            let key = remote_key(path);
+            let response = self.request(Method::PUT, &self.url(&key)).body(contents.clone()).send().await?;
+            if response.status() != StatusCode::CONFLICT {
+                check_status(response, "PUT").await?;
+                return Ok(());
+            }
+            // the folder is missing: create it and its parents, which may exist already
+            let mkcol = Method::from_bytes(b"MKCOL").expect("MKCOL is a valid method");
+            let parts: Vec<&str> = key.split('/').collect();
+            for end in 1..parts.len() {
+                let response = self.request(mkcol.clone(), &f!("{}/", self.url(&parts[..end].join("/")))).send().await?;
+                if response.status() != StatusCode::METHOD_NOT_ALLOWED {
+                    check_status(response, "MKCOL").await?;
+                }
+            }
+            let response = self.request(Method::PUT, &self.url(&key)).body(contents).send().await?;
+            check_status(response, "PUT").await?;
+            Ok(())
+        })
+    }
+
+    fn delete<'a>(&'a self, path: &'a Path) -> SyncFuture<'a, ()> {
+        Box::pin(async move {
+            let response = self.request(Method::DELETE, &self.url(&remote_key(path))).send().await?;
+            if response.status() != StatusCode::NOT_FOUND {
+                check_status(response, "DELETE").await?;
+            }
+            Ok(())
+        })
+    }
+}
+
+/// The version of a file the server gives neither an ETag nor a modification date for: a hash of its contents.
+///
+/// # Arguments
+/// @param contents: &[u8]
+/// @return String
+///
+/// @private
+fn content_version(contents: &[u8]) -> String {
+    let digest: String = Sha256::digest(contents).iter().map(|byte| f!("{:02x}", byte)).collect();
+    f!("sha256:{}", digest)
+}
+
+/// Parse the entries of a PROPFIND multistatus response, whatever namespace prefix the server uses.
+///
+/// @private
+fn parse_multistatus(xml: &str) -> Vec<DavEntry> {
+    let response_pattern = Regex::new(r"(?s)<(?:[\w-]+:)?response[\s>].*?</(?:[\w-]+:)?response>").unwrap();
+    let property = |name: &str| Regex::new(&f!(r"(?s)<(?:[\w-]+:)?{}(?:\s[^>]*)?>(.*?)</(?:[\w-]+:)?{}>", name, name)).unwrap();
+    let href_pattern = property("href");
+    let etag_pattern = property("getetag");
+    let modified_pattern = property("getlastmodified");
+    let collection_pattern = Regex::new(r"<(?:[\w-]+:)?collection\s*/?>").unwrap();
+    response_pattern
+        .find_iter(xml)
+        .filter_map(|response| {
+            let response = response.as_str();
+            let href = href_pattern.captures(response)?[1].trim().to_string();
+            let version = etag_pattern
+                .captures(response)
+                .or_else(|| modified_pattern.captures(response))
+                .map(|captures| xml_unescape(captures[1].trim()));
+            Some(DavEntry {
+                href: uri_decode(&xml_unescape(&href)),
+                version,
+                collection: collection_pattern.is_match(response),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod webdav_tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_multistatus() {
+        let xml = r#"<?xml version="1.0"?>
+<d:multistatus xmlns:d="DAV:">
+  <d:response>
+    <d:href>/dav/Vault/</d:href>
+    <d:propstat><d:prop><d:resourcetype><d:collection/></d:resourcetype></d:prop></d:propstat>
+  </d:response>
+  <d:response>
+    <d:href>/dav/Vault/Cells%20%26%20Tissues.md</d:href>
+    <d:propstat><d:prop><d:resourcetype/><d:getetag>&quot;5f2e&quot;</d:getetag></d:prop></d:propstat>
+  </d:response>
+  <D:response xmlns:D="DAV:">
+    <D:href>https://dav.example.com/dav/Vault/Labs/</D:href>
+    <D:propstat><D:prop><D:resourcetype><D:collection /></D:resourcetype><D:getlastmodified>Tue, 03 Sep 2024 10:00:00 GMT</D:getlastmodified></D:prop></D:propstat>
+  </D:response>
+</d:multistatus>"#;
+        let entries = parse_multistatus(xml);
+        assert_eq!(entries.len(), 3);
+        assert!(entries[0].collection);
+        assert_eq!(entries[1].href, "/dav/Vault/Cells & Tissues.md");
+        assert_eq!(entries[1].version.as_deref(), Some("\"5f2e\""));
+        assert!(!entries[1].collection);
+        assert_eq!(entries[2].href, "https://dav.example.com/dav/Vault/Labs/");
+        assert_eq!(entries[2].version.as_deref(), Some("Tue, 03 Sep 2024 10:00:00 GMT"));
+        assert!(entries[2].collection);
+
+        let remote = WebDavRemote::new("https://dav.example.com/dav/Vault/");
+        assert_eq!(remote.url("Labs/Lab 1.md"), "https://dav.example.com/dav/Vault/Labs/Lab%201.md");
+    }
+
+    #[tokio::test]
+    async fn test_list_hashes_files_without_version() {
+        use std::io::{Read, Write};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = f!("http://{}/dav/Vault", listener.local_addr().unwrap());
+        let multistatus = r#"<d:multistatus xmlns:d="DAV:">
+  <d:response><d:href>/dav/Vault/</d:href><d:propstat><d:prop><d:resourcetype><d:collection/></d:resourcetype></d:prop></d:propstat></d:response>
+  <d:response><d:href>/dav/Vault/a.md</d:href><d:propstat><d:prop><d:resourcetype/></d:prop></d:propstat></d:response>
+</d:multistatus>"#;
+        std::thread::spawn(move || {
+            for (status, body) in [(207, multistatus), (200, "# A")] {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut request = Vec::new();
+                let mut buffer = [0u8; 4096];
+                loop {
+                    let read = stream.read(&mut buffer).unwrap();
+                    request.extend_from_slice(&buffer[..read]);
+                    let text = String::from_utf8_lossy(&request).to_string();
+                    if let Some(end) = text.find("\r\n\r\n") {
+                        let length = text
+                            .lines()
+                            .find_map(|line| line.to_lowercase().strip_prefix("content-length:").map(|n| n.trim().parse::<usize>().unwrap()))
+                            .unwrap_or(0);
+                        if request.len() >= end + 4 + length || read == 0 {
+                            break;
+                        }
+                    }
+                }
+                let response = f!("HTTP/1.1 {} X\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}", status, body.len(), body);
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+        });
+
+        let files = WebDavRemote::new(&base_url).list().await.unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[Path::new("a.md")], content_version(b"# A"));
+        assert!(files[Path::new("a.md")].starts_with("sha256:"));
+    }
+}